@@ -1,5 +1,6 @@
 use std::time::Duration;
 
+use hyper_diff::algorithms::pipeline::DiffConfig;
 use hyperast::utils::memusage_linux;
 use hyperast_benchmark_diffs::{other_tools, postprocess::CompressedBfPostProcess};
 use hyperast_vcs_git::multi_preprocessed::PreProcessedRepositories;
@@ -24,8 +25,12 @@ fn main() {
     let repo_name = repo.next().unwrap();
     let before = args.get(2).map_or("", |x| x);
     let after = args.get(3).map_or("", |x| x);
+    // e.g. {"bottom_up":"hybrid","size_threshold":50}
+    let diff_config = args
+        .get(4)
+        .map(|x| serde_json::from_str(x).expect("a diff config as json"));
     let config = hyperast_vcs_git::processing::RepoConfig::JavaMaven;
-    single(repo_user, repo_name, config, before, after);
+    single(repo_user, repo_name, config, before, after, diff_config);
 }
 
 #[test]
@@ -36,6 +41,7 @@ fn aaa() {
         hyperast_vcs_git::processing::RepoConfig::JavaMaven,
         "a02834611bad3442ad073b10f1dee2322916f1f3",
         "c3cf29438e3d65d6ee5c5726f8611af99d9a649a",
+        None,
     )
 }
 
@@ -47,6 +53,7 @@ fn bbb() {
         hyperast_vcs_git::processing::RepoConfig::JavaMaven,
         "14449e426aee2763d6435b63ef632b7c0b9ed767",
         "6fba7aa3c4d31d088df3ef682f7307b7c9a2f17c",
+        None,
     )
 }
 
@@ -58,6 +65,7 @@ fn test_histogram_bug() {
         hyperast_vcs_git::processing::RepoConfig::JavaMaven,
         "810e3560590bb807ed7113ccfff716aac21a3f33",
         "00ae39775708147e115512be5d4f92bee02e9b89",
+        None,
     )
 }
 
@@ -67,6 +75,7 @@ fn single(
     config: hyperast_vcs_git::processing::RepoConfig,
     before: &str,
     after: &str,
+    diff_config: Option<DiffConfig>,
 ) {
     let mut repositories = PreProcessedRepositories::default();
 
@@ -109,10 +118,14 @@ fn single(
     let hyperast = hyperast_vcs_git::no_space::as_nospaces(stores);
 
     let mu = memusage_linux();
-    let lazy =
+    let lazy = if let Some(diff_config) = &diff_config {
+        hyper_diff::algorithms::pipeline::diff(&hyperast, &src_tr, &dst_tr, diff_config)
+            .expect("a valid diff config")
+    } else {
         hyper_diff::algorithms::gumtree_hybrid_lazy::diff_with_hyperparameters::<_, 1, 50, 1, 2>(
             &hyperast, &src_tr, &dst_tr,
-        );
+        )
+    };
     let summarized_lazy = &lazy.summarize();
     let total_lazy_t: Duration = summarized_lazy.exec_data.sum().unwrap();
     dbg!(summarized_lazy);
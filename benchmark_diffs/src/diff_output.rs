@@ -1,14 +1,9 @@
-use std::ops::Range;
-
-use hyper_diff::tree::tree_path::CompressedTreePath;
-use hyperast::{
-    position::{Position, compute_position},
-    types::{self, LabelStore, Labeled, NodeStore, WithSerialization},
-};
+pub use hyper_diff::output::Tree;
 use serde::Deserialize;
 
 #[derive(Deserialize)]
 pub struct F<T> {
+    #[serde(default)]
     pub times: Vec<usize>,
     pub matches: Vec<Match<T>>,
     pub actions: Option<Vec<Act<T>>>,
@@ -29,15 +24,6 @@ pub struct Act<T> {
     pub label: Option<String>,
 }
 
-#[derive(Deserialize, PartialEq, Eq, Hash, Debug, Clone)]
-pub struct Tree {
-    pub r#type: String,
-    pub label: Option<String>,
-    pub file: String,
-    pub start: usize,
-    pub end: usize,
-}
-
 #[derive(Deserialize, PartialEq, Eq, Hash, Debug, Clone)]
 pub struct Path(pub Vec<u32>);
 
@@ -52,60 +38,3 @@ pub enum Kind {
     #[serde(rename = "delete-node")]
     Del,
 }
-
-impl Tree {
-    pub fn from_pos<HAST>(stores: HAST, (pos, x): (Position, HAST::IdN)) -> Self
-    where
-        HAST: types::HyperAST,
-        for<'t> <HAST as hyperast::types::AstLending<'t>>::RT: types::Tree,
-    {
-        let Range { start, end } = pos.range();
-        let file = pos.file().to_string_lossy().to_string();
-        let r = stores.node_store().resolve(&x);
-        let t = stores.resolve_type(&x);
-        Tree {
-            r#type: t.to_string(),
-            label: r
-                .try_get_label()
-                .map(|x| stores.label_store().resolve(x).to_string())
-                .filter(|x| !x.is_empty()),
-            file,
-            start,
-            end,
-        }
-    }
-}
-
-impl<HAST> From<(HAST, HAST::IdN, &CompressedTreePath<HAST::Idx>)> for Tree
-where
-    HAST: types::HyperAST + Copy,
-    HAST::IdN: types::NodeId<IdN = HAST::IdN>,
-    for<'t> <HAST as hyperast::types::AstLending<'t>>::RT: types::Tree + WithSerialization,
-{
-    fn from((stores, ori, p): (HAST, HAST::IdN, &CompressedTreePath<HAST::Idx>)) -> Self {
-        (stores, compute_position(ori, &mut p.iter(), stores)).into()
-    }
-}
-
-impl<HAST> From<(HAST, (Position, HAST::IdN))> for Tree
-where
-    HAST: types::HyperAST,
-    for<'t> <HAST as hyperast::types::AstLending<'t>>::RT: types::Tree,
-{
-    fn from((stores, (pos, x)): (HAST, (Position, HAST::IdN))) -> Self {
-        let Range { start, end } = pos.range();
-        let file = pos.file().to_string_lossy().to_string();
-        let r = stores.node_store().resolve(&x);
-        let t = stores.resolve_type(&x);
-        Tree {
-            r#type: t.to_string(),
-            label: r
-                .try_get_label()
-                .map(|x| stores.label_store().resolve(x).to_string())
-                .filter(|x| !x.is_empty()),
-            file,
-            start,
-            end,
-        }
-    }
-}
@@ -11,7 +11,7 @@ use tower::ServiceBuilder;
 use tower_http::trace::TraceLayer;
 
 use crate::{
-    SharedState, commit, fetch, file, pull_requests, querying,
+    SharedState, change_types, commit, diff, fetch, file, pull_requests, querying, refactorings,
     scriptingv1::{self, ScriptContent, ScriptContentDepth, ScriptingError, ScriptingParam},
     smells, track, view,
 };
@@ -44,6 +44,7 @@ impl IntoResponse for crate::tsg::QueryingError {
 mod tsg {
     #[derive(serde::Deserialize)]
     pub struct Param {
+        forge: String,
         user: String,
         name: String,
         commit: String,
@@ -86,11 +87,11 @@ pub fn scripting_app(_st: SharedState) -> Router<SharedState> {
         .layer(TraceLayer::new_for_http());
     Router::new()
         .route(
-            "/script/github/:user/:name/:commit",
+            "/script/:forge/:user/:name/:commit",
             post(scripting).layer(scripting_service_config.clone()),
         )
         .route(
-            "/script-depth/github/:user/:name/:commit",
+            "/script-depth/:forge/:user/:name/:commit",
             post(scripting_depth).layer(scripting_service_config.clone()),
         )
         .route("/sharing-scripts/shared-db", get(crate::ws::connect_db))
@@ -152,11 +153,15 @@ async fn querying(
 
 #[axum_macros::debug_handler]
 async fn querying_streamed(
+    headers: http::HeaderMap,
     axum::extract::Path(path): axum::extract::Path<querying::Param>,
     axum::extract::State(state): axum::extract::State<SharedState>,
     axum::extract::Json(script): axum::extract::Json<querying::Content>,
 ) -> axum::response::Response {
-    querying::streamed(state, path, script)
+    let accept = headers
+        .get(http::header::ACCEPT)
+        .map_or("", |x| x.to_str().unwrap_or_default());
+    querying::streamed(state, path, script, accept.contains("ndjson"))
 }
 
 async fn querying_differential(
@@ -168,6 +173,15 @@ async fn querying_differential(
     Ok(r.into())
 }
 
+async fn querying_precomputed(
+    axum::extract::Path(path): axum::extract::Path<querying::ParamPrecomputed>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+    axum::extract::Json(content): axum::extract::Json<querying::ContentPrecomputed>,
+) -> axum::response::Result<Json<querying::PrecomputedReport>> {
+    let r = querying::precomputed(content, state, path)?;
+    Ok(r.into())
+}
+
 pub fn querying_app(_st: SharedState) -> Router<SharedState> {
     let querying_service_config = ServiceBuilder::new()
         .layer(HandleErrorLayer::new(|e: BoxError| async move {
@@ -182,17 +196,21 @@ pub fn querying_app(_st: SharedState) -> Router<SharedState> {
         .layer(TraceLayer::new_for_http());
     Router::new()
         .route(
-            "/query/github/:user/:name/*commit",
+            "/query/:forge/:user/:name/*commit",
             post(querying).layer(querying_service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
-            "/query-st/github/:user/:name/*commit",
+            "/query-st/:forge/:user/:name/*commit",
             post(querying_streamed).layer(querying_service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
-            "/query-differential/github/:user/:name/:commit/:baseline",
+            "/query-differential/:forge/:user/:name/:commit/:baseline",
             post(querying_differential).layer(querying_service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
+        .route(
+            "/query-precomputed/:forge/:user/:name",
+            post(querying_precomputed).layer(querying_service_config.clone()),
+        )
         .route(
             "/sharing-queries/shared-db",
             get(crate::ws::connect_db), // .with_state(Arc::clone(&shared_state)),
@@ -232,7 +250,7 @@ pub fn tsg_app(_st: SharedState) -> Router<SharedState> {
         .layer(TraceLayer::new_for_http());
     Router::new()
         .route(
-            "/tsg/github/:user/:name/:commit",
+            "/tsg/:forge/:user/:name/:commit",
             post(tsg).layer(tsg_service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
@@ -276,11 +294,11 @@ pub fn smells_app(_st: SharedState) -> Router<SharedState> {
         .layer(TraceLayer::new_for_http());
     Router::new()
         .route(
-            "/smells/github/:user/:name/:commit/:len",
+            "/smells/:forge/:user/:name/:commit/:len",
             post(smells).layer(smells_service_config.clone()),
         )
         .route(
-            "/smells_ex_from_diffs/github/:user/:name/:commit/:len",
+            "/smells_ex_from_diffs/:forge/:user/:name/:commit/:len",
             post(smells_ex_from_diffs).layer(smells_service_config.clone()),
         )
 }
@@ -298,7 +316,7 @@ pub fn fetch_git_file(_st: SharedState) -> Router<SharedState> {
         .timeout(Duration::from_secs(10))
         .layer(TraceLayer::new_for_http());
     Router::new().route(
-        "/file/github/:user/:name/:commit/*file",
+        "/file/:forge/:user/:name/:commit/*file",
         get(file).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
     )
 }
@@ -312,6 +330,59 @@ async fn file(
     file::from_hyperast(state, path).map_err(|err| err.into())
 }
 
+pub fn diff_route(_st: SharedState) -> Router<SharedState> {
+    let service_config = ServiceBuilder::new()
+        .layer(HandleErrorLayer::new(|e: BoxError| async move {
+            dbg!(e);
+        }))
+        .load_shed()
+        .concurrency_limit(8)
+        .buffer(20)
+        .rate_limit(2, Duration::from_secs(2))
+        // .request_body_limit(1024 * 5_000 /* ~5mb */)
+        .timeout(Duration::from_secs(30))
+        .layer(TraceLayer::new_for_http());
+    Router::new()
+        .route(
+            "/diff/:forge/:user/:name/:src/:dst",
+            get(unified_diff).layer(service_config.clone()),
+        )
+        .route(
+            "/refactorings/:forge/:user/:name/:src/:dst",
+            get(moves_as_refactorings).layer(service_config.clone()),
+        )
+        .route(
+            "/change_types/:forge/:user/:name/:src/:dst",
+            get(distilled_changes).layer(service_config.clone()),
+        )
+}
+
+async fn unified_diff(
+    axum::extract::Path(path): axum::extract::Path<diff::Param>,
+    axum::extract::Query(query): axum::extract::Query<diff::Query>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> axum::response::Result<impl IntoResponse> {
+    dbg!(&path);
+    let patch = diff::unified_diff(state, path, query)?;
+    Ok(([(http::header::CONTENT_TYPE, "text/x-diff")], patch))
+}
+
+async fn moves_as_refactorings(
+    axum::extract::Path(path): axum::extract::Path<diff::Param>,
+    axum::extract::Query(query): axum::extract::Query<diff::ConfigQuery>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> axum::response::Result<Json<Vec<refactorings::Refactoring>>> {
+    refactorings::refactorings(state, path, query).map_err(|err| err.into())
+}
+
+async fn distilled_changes(
+    axum::extract::Path(path): axum::extract::Path<diff::Param>,
+    axum::extract::Query(query): axum::extract::Query<diff::ConfigQuery>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> axum::response::Result<Json<Vec<change_types::ChangeEvent>>> {
+    change_types::change_types(state, path, query).map_err(|err| err.into())
+}
+
 pub fn track_code_route(_st: SharedState) -> Router<SharedState> {
     let service_config = ServiceBuilder::new()
         .layer(HandleErrorLayer::new(|e: BoxError| async move {
@@ -326,19 +397,19 @@ pub fn track_code_route(_st: SharedState) -> Router<SharedState> {
         .layer(TraceLayer::new_for_http());
     Router::new()
         .route(
-            "/track/github/:user/:name/:commit/*file",
+            "/track/:forge/:user/:name/:commit/*file",
             get(track_code).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
-            "/track_at_path/github/:user/:name/:commit/*path",
+            "/track_at_path/:forge/:user/:name/:commit/*path",
             get(track_code_at_path).layer(service_config.clone()),
         )
         .route(
-            "/track_at_path_with_changes/github/:user/:name/:commit/",
+            "/track_at_path_with_changes/:forge/:user/:name/:commit/",
             get(track_code_at_path_with_changes).layer(service_config.clone()),
         )
         .route(
-            "/track_at_path_with_changes/github/:user/:name/:commit/*path",
+            "/track_at_path_with_changes/:forge/:user/:name/:commit/*path",
             get(track_code_at_path_with_changes).layer(service_config.clone()),
         )
 }
@@ -386,17 +457,21 @@ pub fn view_code_route(_st: SharedState) -> Router<SharedState> {
         .layer(TraceLayer::new_for_http());
     Router::new()
         .route(
-            "/view/github/:user/:name/:commit/*path",
+            "/view/:forge/:user/:name/:commit/*path",
             get(view_code).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
-            "/view/github/:user/:name/:commit/",
+            "/view/:forge/:user/:name/:commit/",
             get(view_code).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
             "/view/:id",
             get(view_code_with_node_id).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
+        .route(
+            "/modules/:forge/:user/:name/:commit",
+            get(view_modules).layer(service_config.clone()),
+        )
 }
 
 // #[axum_macros::debug_handler]
@@ -407,6 +482,12 @@ async fn view_code(
     dbg!(&path);
     view::view(state, path).map_err(|err| err.into())
 }
+async fn view_modules(
+    axum::extract::Path(path): axum::extract::Path<view::ModulesParameters>,
+    axum::extract::State(state): axum::extract::State<SharedState>,
+) -> axum::response::Result<Json<hyperast_vcs_git::maven::MavenModuleGraph>> {
+    view::modules(state, path).map_err(|err| err.into())
+}
 async fn view_code_with_node_id(
     axum::extract::Path(id): axum::extract::Path<u64>,
     axum::extract::State(state): axum::extract::State<SharedState>,
@@ -428,11 +509,11 @@ pub fn fetch_code_route(_st: SharedState) -> Router<SharedState> {
         .layer(TraceLayer::new_for_http());
     Router::new()
         .route(
-            "/fetch/github/:user/:name/:commit/*path",
+            "/fetch/:forge/:user/:name/:commit/*path",
             get(fetch_code).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
-            "/fetch/github/:user/:name/:commit/",
+            "/fetch/:forge/:user/:name/:commit/",
             get(fetch_code).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
@@ -497,7 +578,7 @@ pub fn commit_metadata_route(_st: SharedState) -> Router<SharedState> {
         .layer(TraceLayer::new_for_http());
     Router::new()
         .route(
-            "/commit/github/:user/:name/:version",
+            "/commit/:forge/:user/:name/:version",
             get(commit_metadata).layer(service_config.clone()), // .with_state(Arc::clone(&shared_state)),
         )
         .route(
@@ -0,0 +1,667 @@
+//! Fine-grained change types computed from edit scripts, following the taxonomy of ChangeDistiller
+//! (Fluri et al., Change Distilling: Tree Differencing for Fine-Grained Source Code Change Extraction).
+//!
+//! Each action is classified from the point of view of the closest enclosing entity,
+//! e.g. a statement, a parameter or a declaration.
+//! Actions inside inserted or deleted subtrees are covered by the action on the root of the subtree.
+//! Only Java code is considered, other changed nodes are ignored.
+
+use std::collections::HashSet;
+
+use axum::Json;
+use serde::Serialize;
+use tokio::time::Instant;
+
+use hyper_diff::actions::action_vec::ActionsVec;
+use hyper_diff::actions::script_generator2::Act;
+use hyper_diff::decompressed_tree_store::ShallowDecompressedTreeStore;
+use hyper_diff::matchers::Mapper;
+use hyper_diff::matchers::mapping_store::{MonoMappingStore, VecStore};
+use hyperast::store::defaults::LabelIdentifier;
+use hyperast::types::{HyperAST, HyperType, Labeled, TypeTrait, WithChildren};
+use hyperast_gen_ts_java::types::Type;
+use hyperast_vcs_git::SimpleStores;
+use hyperast_vcs_git::git::{Oid, Repo};
+
+use crate::SharedState;
+use crate::diff::{A, ConfigQuery, Param, pre_process_pair};
+use crate::refactorings::{Located, is_class, java_type};
+use crate::utils::{IdN, LocalPieceOfCode, PieceOfCode};
+
+type Local = LocalPieceOfCode<IdN, usize>;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ChangeType {
+    // body parts
+    StatementInsert,
+    StatementDelete,
+    StatementUpdate,
+    StatementOrderingChange,
+    StatementParentChange,
+    ConditionExpressionChange,
+    AlternativePartInsert,
+    AlternativePartDelete,
+    CommentInsert,
+    CommentDelete,
+    CommentUpdate,
+    CommentMove,
+    /// renaming of a local variable along with its uses, not part of ChangeDistiller's taxonomy
+    LocalVariableRenaming,
+    // declaration parts
+    AdditionalClass,
+    RemovedClass,
+    AdditionalFunctionality,
+    RemovedFunctionality,
+    AdditionalObjectState,
+    RemovedObjectState,
+    ClassRenaming,
+    MethodRenaming,
+    AttributeRenaming,
+    AttributeTypeChange,
+    ReturnTypeChange,
+    ParameterInsert,
+    ParameterDelete,
+    ParameterOrderingChange,
+    ParameterRenaming,
+    ParameterTypeChange,
+    ParentClassInsert,
+    ParentClassDelete,
+    ParentClassChange,
+    ParentInterfaceInsert,
+    ParentInterfaceDelete,
+    ParentInterfaceChange,
+}
+
+/// How much a change can impact other parts of the code, as defined by ChangeDistiller
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Significance {
+    None,
+    Low,
+    Medium,
+    High,
+    Crucial,
+}
+
+impl ChangeType {
+    pub fn significance(&self) -> Significance {
+        use ChangeType::*;
+        match self {
+            CommentInsert | CommentDelete | CommentUpdate | CommentMove => Significance::None,
+            LocalVariableRenaming => Significance::None,
+            StatementUpdate | StatementOrderingChange => Significance::Low,
+            AdditionalClass | AdditionalFunctionality | AdditionalObjectState => Significance::Low,
+            StatementInsert | StatementDelete | StatementParentChange => Significance::Medium,
+            ConditionExpressionChange => Significance::Medium,
+            AlternativePartInsert | AlternativePartDelete => Significance::Medium,
+            ParameterRenaming => Significance::Medium,
+            ClassRenaming | MethodRenaming | AttributeRenaming => Significance::High,
+            AttributeTypeChange => Significance::High,
+            RemovedClass | RemovedFunctionality | RemovedObjectState => Significance::Crucial,
+            ReturnTypeChange => Significance::Crucial,
+            ParameterInsert | ParameterDelete | ParameterOrderingChange => Significance::Crucial,
+            ParameterTypeChange => Significance::Crucial,
+            ParentClassInsert | ParentClassDelete | ParentClassChange => Significance::Crucial,
+            ParentInterfaceInsert | ParentInterfaceDelete | ParentInterfaceChange => {
+                Significance::Crucial
+            }
+        }
+    }
+
+    /// Changes that can alter the behavior of the program,
+    /// i.e. all but comment changes and renamings of local variables
+    pub fn is_behavioral(&self) -> bool {
+        self.significance() > Significance::None
+    }
+}
+
+/// A change of an entity, e.g. a statement or a declaration
+#[derive(Serialize, Debug, Clone)]
+pub struct ChangeEvent<P = PieceOfCode> {
+    pub change_type: ChangeType,
+    pub significance: Significance,
+    /// the entity in the original version, if it exists
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<P>,
+    /// the entity in the modified version, if it exists
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<P>,
+}
+
+impl ChangeEvent<Local> {
+    fn globalize(self, spec: &Repo, src: Oid, dst: Oid) -> ChangeEvent {
+        ChangeEvent {
+            change_type: self.change_type,
+            significance: self.significance,
+            from: self.from.map(|x| x.globalize(spec, src)),
+            to: self.to.map(|x| x.globalize(spec, dst)),
+        }
+    }
+}
+
+pub fn change_types(
+    state: SharedState,
+    path: Param,
+    query: ConfigQuery,
+) -> Result<Json<Vec<ChangeEvent>>, String> {
+    let now = Instant::now();
+    let config = crate::diff::parse_config(query.config.as_deref())?;
+    let (repo, src_oid, dst_oid) = pre_process_pair(&state, &path)?;
+
+    let repositories = state.repositories.read().unwrap();
+    let commit_src = repositories.get_commit(&repo.config, &src_oid).unwrap();
+    let src_tr = commit_src.ast_root;
+    let commit_dst = repositories.get_commit(&repo.config, &dst_oid).unwrap();
+    let dst_tr = commit_dst.ast_root;
+    if src_tr == dst_tr {
+        return Ok(Json(vec![]));
+    }
+
+    let with_spaces_stores = &repositories.processor.main_stores;
+    let stores = &hyperast_vcs_git::no_space::as_nospaces(with_spaces_stores);
+    let diff = match &config {
+        Some(config) => hyper_diff::algorithms::pipeline::diff(stores, &src_tr, &dst_tr, config)?,
+        None => hyper_diff::algorithms::gumtree_stable_hybrid_lazy::diff(stores, &src_tr, &dst_tr),
+    };
+    let actions = diff.actions.unwrap_or_default();
+    let res = classify_actions(with_spaces_stores, src_tr, dst_tr, &diff.mapper, &actions);
+    log::info!("{} changes classified in {:?}", res.len(), now.elapsed());
+    let res = res.into_iter();
+    let res = res.map(|r| r.globalize(&repo.spec, src_oid, dst_oid));
+    Ok(Json(res.collect()))
+}
+
+/// Whether the changes between the subtrees `src` and `dst` can alter the behavior of the program,
+/// see [`ChangeType::is_behavioral`].
+/// `mappings` are between the post-order ids of `src` and `dst`, e.g. taken from a diff of enclosing trees.
+/// Changes that are not classified, e.g. outside of Java code, are not considered behavioral.
+pub(crate) fn behavioral_change(
+    stores: &SimpleStores,
+    src: IdN,
+    dst: IdN,
+    mappings: VecStore<u32>,
+) -> bool {
+    if src == dst {
+        return false;
+    }
+    let no_spaces = &hyperast_vcs_git::no_space::as_nospaces(stores);
+    let diff =
+        hyper_diff::algorithms::pipeline::diff_with_mappings(no_spaces, &src, &dst, mappings);
+    let actions = diff.actions.unwrap_or_default();
+    let res = classify_actions(stores, src, dst, &diff.mapper, &actions);
+    res.iter().any(|x| x.change_type.is_behavioral())
+}
+
+/// Classifies `actions`, in order, each entity being reported once per change type.
+///
+/// `stores` must contain spaces, contrary to the ones of `mapper`,
+/// and the paths of actions are expected to be the ones made by
+/// [`hyper_diff::actions::script_generator2::ScriptGenerator`].
+pub(crate) fn classify_actions<HAST, SD, DD, M>(
+    stores: &SimpleStores,
+    src_tr: IdN,
+    dst_tr: IdN,
+    mapper: &Mapper<HAST, SD, DD, M>,
+    actions: &ActionsVec<A>,
+) -> Vec<ChangeEvent<Local>>
+where
+    HAST: HyperAST,
+    M: MonoMappingStore,
+    SD: ShallowDecompressedTreeStore<HAST, M::Src>,
+    DD: ShallowDecompressedTreeStore<HAST, M::Dst>,
+{
+    let mapping = &mapper.mapping;
+    let src_at = |l: &Located, k: usize| {
+        let root = mapping.src_arena.root();
+        mapping.src_arena.child(&root, &l.no_spaces[..k])
+    };
+    let dst_at = |l: &Located, k: usize| {
+        let root = mapping.dst_arena.root();
+        mapping.dst_arena.child(&root, &l.no_spaces[..k])
+    };
+    let renamings = local_renamings(stores, src_tr, actions);
+    let mut res = vec![];
+    let mut reported = HashSet::new();
+    let mut report =
+        |ty: ChangeType, from: Option<(&Located, usize)>, to: Option<(&Located, usize)>| {
+            let key = |x: Option<(&Located, usize)>| x.map(|(l, k)| l.no_spaces[..k].to_vec());
+            if !reported.insert((ty, key(from), key(to))) {
+                return;
+            }
+            res.push(ChangeEvent {
+                change_type: ty,
+                significance: ty.significance(),
+                from: from.map(|(l, k)| l.piece(stores, k)),
+                to: to.map(|(l, k)| l.piece(stores, k)),
+            });
+        };
+    for a in actions.iter() {
+        match &a.action {
+            Act::Delete {} => {
+                let from = Located::new(stores, src_tr, &a.path.ori);
+                let d = from.depth();
+                // only the root of deleted subtrees
+                if d == 0 || !mapping.mappings.is_src(&src_at(&from, d - 1)) {
+                    continue;
+                }
+                if let Some((ty, k)) = inserted_or_deleted(stores, &from, false) {
+                    report(ty, Some((&from, k)), None);
+                }
+            }
+            Act::Insert { .. } => {
+                let to = Located::new(stores, dst_tr, &a.path.ori);
+                let d = to.depth();
+                // only the root of inserted subtrees
+                if d == 0 || !mapping.mappings.is_dst(&dst_at(&to, d - 1)) {
+                    continue;
+                }
+                if let Some((ty, k)) = inserted_or_deleted(stores, &to, true) {
+                    report(ty, None, Some((&to, k)));
+                }
+            }
+            Act::Update { before, new } => {
+                let from = Located::new(stores, src_tr, &before.ori);
+                let to = Located::new(stores, dst_tr, &a.path.ori);
+                if let Some((ty, k)) = updated(stores, &from) {
+                    let ty = match ty {
+                        ChangeType::StatementUpdate | ChangeType::ConditionExpressionChange
+                            if is_renaming(stores, &renamings, &from, new) =>
+                        {
+                            ChangeType::LocalVariableRenaming
+                        }
+                        ty => ty,
+                    };
+                    report(
+                        ty,
+                        Some((&from, k)),
+                        Some((&to, corresponding(&from, k, &to))),
+                    );
+                }
+            }
+            Act::Move { from } | Act::MovUpd { from, .. } => {
+                let from = Located::new(stores, src_tr, &from.ori);
+                let to = Located::new(stores, dst_tr, &a.path.ori);
+                let (d, d_) = (from.depth(), to.depth());
+                let same_parent = d > 0
+                    && d_ > 0
+                    && (mapping.mappings).has(&src_at(&from, d - 1), &dst_at(&to, d_ - 1));
+                if let Some(ty) = moved(stores, &from, same_parent) {
+                    report(ty, Some((&from, d)), Some((&to, d_)));
+                }
+                if !matches!(a.action, Act::MovUpd { .. }) {
+                    continue;
+                }
+                if let Some((ty, k)) = updated(stores, &from) {
+                    report(
+                        ty,
+                        Some((&from, k)),
+                        Some((&to, corresponding(&from, k, &to))),
+                    );
+                }
+            }
+        }
+    }
+    res
+}
+
+/// Local variables renamed by label updates, as their scope, i.e. the path without spaces of the enclosing method,
+/// their label before and their label after.
+type Renamings = HashSet<(Vec<u16>, LabelIdentifier, LabelIdentifier)>;
+
+/// Finds the local variables whose declaration is renamed by `actions`.
+fn local_renamings(stores: &SimpleStores, src_tr: IdN, actions: &ActionsVec<A>) -> Renamings {
+    let mut res = HashSet::new();
+    for a in actions.iter() {
+        let Act::Update { before, new } = &a.action else {
+            continue;
+        };
+        let from = Located::new(stores, src_tr, &before.ori);
+        let d = from.depth();
+        let is_declared = d >= 2
+            && from.java_type(stores, d) == Some(Type::Identifier)
+            && from.java_type(stores, d - 1) == Some(Type::VariableDeclarator)
+            && from.java_type(stores, d - 2) == Some(Type::LocalVariableDeclaration);
+        if !is_declared {
+            continue;
+        }
+        if let Some((scope, label)) = scope_and_label(stores, &from) {
+            res.insert((scope, label, *new));
+        }
+    }
+    res
+}
+
+/// The identifier at the end of `l` is updated to `new` as part of one of the `renamings`,
+/// either on the declaration or on a use.
+fn is_renaming(
+    stores: &SimpleStores,
+    renamings: &Renamings,
+    l: &Located,
+    new: &LabelIdentifier,
+) -> bool {
+    if l.java_type(stores, l.depth()) != Some(Type::Identifier) {
+        return false;
+    }
+    scope_and_label(stores, l)
+        .is_some_and(|(scope, label)| renamings.contains(&(scope, label, *new)))
+}
+
+/// the path without spaces of the method enclosing the node at the end of `l`, and the label of the node
+fn scope_and_label(stores: &SimpleStores, l: &Located) -> Option<(Vec<u16>, LabelIdentifier)> {
+    let is_scope = |stores: &SimpleStores, n| {
+        java_type(stores, n)
+            .is_some_and(|t| t.is_executable_member() || t == Type::StaticInitializer)
+    };
+    let k = l.enclosing(stores, is_scope)?;
+    let label = *stores.node_store.resolve(l.node()).try_get_label()?;
+    Some((l.no_spaces[..k].to_vec(), label))
+}
+
+/// depth in `to` of the ancestor at depth `k` in `from`, assuming both nodes are mapped
+fn corresponding(from: &Located, k: usize, to: &Located) -> usize {
+    (to.depth() + k).saturating_sub(from.depth())
+}
+
+/// Classifies the insertion or deletion of the subtree at the end of `l`,
+/// also returning the depth of the changed entity.
+fn inserted_or_deleted(
+    stores: &SimpleStores,
+    l: &Located,
+    ins: bool,
+) -> Option<(ChangeType, usize)> {
+    use ChangeType::*;
+    let d = l.depth();
+    let t = l.java_type(stores, d)?;
+    let parent = l.java_type(stores, d - 1);
+    let pick = |i, d| if ins { i } else { d };
+    let ty = if t == Type::Else {
+        // goes along with the alternative part
+        return None;
+    } else if t.is_comment() {
+        pick(CommentInsert, CommentDelete)
+    } else if t.is_executable_member() {
+        pick(AdditionalFunctionality, RemovedFunctionality)
+    } else if t.is_value_member() {
+        pick(AdditionalObjectState, RemovedObjectState)
+    } else if is_class(&t) {
+        pick(AdditionalClass, RemovedClass)
+    } else if t.is_parameter() && parent == Some(Type::FormalParameters) {
+        pick(ParameterInsert, ParameterDelete)
+    } else if t == Type::Superclass {
+        pick(ParentClassInsert, ParentClassDelete)
+    } else if t == Type::SuperInterfaces {
+        pick(ParentInterfaceInsert, ParentInterfaceDelete)
+    } else if t.is_statement() && parent == Some(Type::IfStatement) && is_alternative(stores, l) {
+        pick(AlternativePartInsert, AlternativePartDelete)
+    } else if t.is_statement() {
+        pick(StatementInsert, StatementDelete)
+    } else {
+        return within(stores, l);
+    };
+    Some((ty, d))
+}
+
+/// Classifies the label update of the node at the end of `l`,
+/// also returning the depth of the changed entity.
+fn updated(stores: &SimpleStores, l: &Located) -> Option<(ChangeType, usize)> {
+    let d = l.depth();
+    if l.java_type(stores, d)?.is_comment() {
+        return Some((ChangeType::CommentUpdate, d));
+    }
+    within(stores, l)
+}
+
+fn moved(stores: &SimpleStores, l: &Located, same_parent: bool) -> Option<ChangeType> {
+    use ChangeType::*;
+    let t = l.java_type(stores, l.depth())?;
+    if t.is_comment() {
+        Some(CommentMove)
+    } else if t.is_statement() && same_parent {
+        Some(StatementOrderingChange)
+    } else if t.is_statement() {
+        Some(StatementParentChange)
+    } else if t.is_parameter() && same_parent {
+        Some(ParameterOrderingChange)
+    } else {
+        None
+    }
+}
+
+/// Classifies a change strictly inside an entity,
+/// by looking for the closest enclosing entity of the node at the end of `l`.
+fn within(stores: &SimpleStores, l: &Located) -> Option<(ChangeType, usize)> {
+    use ChangeType::*;
+    for k in (0..l.depth()).rev() {
+        let Some(a) = l.java_type(stores, k) else {
+            continue;
+        };
+        // the child of the entity leading to the changed node
+        let c = l.java_type(stores, k + 1);
+        let is_name = c == Some(Type::Identifier);
+        let ty = match a {
+            Type::FormalParameter | Type::SpreadParameter if is_name => ParameterRenaming,
+            Type::FormalParameter | Type::SpreadParameter => ParameterTypeChange,
+            Type::VariableDeclarator => {
+                let field = k > 0 && l.java_type(stores, k - 1) == Some(Type::FieldDeclaration);
+                if field && is_name {
+                    return Some((AttributeRenaming, k - 1));
+                }
+                continue;
+            }
+            Type::FieldDeclaration => match c {
+                Some(Type::Modifiers) => return None,
+                Some(Type::VariableDeclarator) => StatementUpdate,
+                _ => AttributeTypeChange,
+            },
+            Type::MethodDeclaration | Type::ConstructorDeclaration => match c {
+                Some(Type::Identifier) => MethodRenaming,
+                Some(Type::Modifiers | Type::TypeParameters | Type::FormalParameters) => {
+                    return None;
+                }
+                Some(Type::Block | Type::ConstructorBody | Type::Throws) => return None,
+                _ => ReturnTypeChange,
+            },
+            Type::Superclass => ParentClassChange,
+            Type::SuperInterfaces => ParentInterfaceChange,
+            a if is_class(&a) && is_name => ClassRenaming,
+            a if is_class(&a) => return None,
+            a if a.is_statement() && is_condition(a, c) => ConditionExpressionChange,
+            a if a.is_statement() => StatementUpdate,
+            _ => continue,
+        };
+        return Some((ty, k));
+    }
+    None
+}
+
+/// the child of type `c` of the statement of type `a` is its condition
+fn is_condition(a: Type, c: Option<Type>) -> bool {
+    let Some(c) = c else {
+        return false;
+    };
+    match a {
+        Type::IfStatement | Type::WhileStatement | Type::DoStatement | Type::SwitchExpression => {
+            c == Type::ParenthesizedExpression
+        }
+        Type::ForStatement => c.is_expression(),
+        _ => false,
+    }
+}
+
+/// the node at the end of `l` comes after the `else` of its parent
+fn is_alternative(stores: &SimpleStores, l: &Located) -> bool {
+    let d = l.depth();
+    let parent = stores.node_store.resolve(l.nodes[d - 1]);
+    let o = l.path[d - 1];
+    (0..o)
+        .rev()
+        .filter_map(|i| parent.child(&i))
+        .find(|x| !stores.resolve_type(x).is_spaces())
+        .is_some_and(|x| java_type(stores, x) == Some(Type::Else))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{diff_param, java_repo};
+
+    fn class_a(members: &str) -> String {
+        format!("package a;\n\nclass A {{\n{members}}}\n")
+    }
+
+    fn method(body: &str) -> String {
+        class_a(&format!(
+            "    int run(int x) {{\n{body}        return x;\n    }}\n"
+        ))
+    }
+
+    fn changes_between(name: &str, before: &str, after: &str) -> Vec<ChangeEvent> {
+        let (state, commits) = java_repo(name, &[&[("a/A.java", before)], &[("a/A.java", after)]]);
+        let path = diff_param(name, &commits[0], &commits[1]);
+        change_types(state, path, Default::default()).unwrap().0
+    }
+
+    #[test]
+    fn test_significance() {
+        assert!(!ChangeType::CommentUpdate.is_behavioral());
+        assert!(ChangeType::StatementUpdate.is_behavioral());
+        assert!(!ChangeType::LocalVariableRenaming.is_behavioral());
+        assert_eq!(
+            ChangeType::ParameterTypeChange.significance(),
+            Significance::Crucial
+        );
+        assert!(ChangeType::MethodRenaming.significance() > Significance::Medium);
+    }
+
+    #[test]
+    fn test_change_types() {
+        use ChangeType::*;
+        let stmts = "        a(1);\n        b(2);\n";
+        let cases = [
+            (
+                "stmt_insert",
+                method(stmts),
+                method("        a(1);\n        b(2);\n        c(3);\n"),
+                StatementInsert,
+            ),
+            (
+                "stmt_delete",
+                method(stmts),
+                method("        a(1);\n"),
+                StatementDelete,
+            ),
+            (
+                "stmt_update",
+                method(stmts),
+                method("        a(1);\n        b(3);\n"),
+                StatementUpdate,
+            ),
+            (
+                "stmt_order",
+                method(stmts),
+                method("        b(2);\n        a(1);\n"),
+                StatementOrderingChange,
+            ),
+            (
+                "condition",
+                method("        if (x > 0) {\n            a(1);\n        }\n"),
+                method("        if (x > 1) {\n            a(1);\n        }\n"),
+                ConditionExpressionChange,
+            ),
+            (
+                "alternative",
+                method("        if (x > 0) {\n            a(1);\n        }\n"),
+                method(
+                    "        if (x > 0) {\n            a(1);\n        } else {\n            b(2);\n        }\n",
+                ),
+                AlternativePartInsert,
+            ),
+            (
+                "comment",
+                method(stmts),
+                method("        a(1);\n        // then b\n        b(2);\n"),
+                CommentInsert,
+            ),
+            (
+                "local_rename",
+                method("        int y = x;\n        a(y);\n"),
+                method("        int z = x;\n        a(z);\n"),
+                LocalVariableRenaming,
+            ),
+            (
+                "method_rename",
+                method(stmts),
+                method(stmts).replace("run", "walk"),
+                MethodRenaming,
+            ),
+            (
+                "return_type",
+                method(stmts),
+                method(stmts).replace("int run", "long run"),
+                ReturnTypeChange,
+            ),
+            (
+                "param_insert",
+                method(stmts),
+                method(stmts).replace("int x)", "int x, int y)"),
+                ParameterInsert,
+            ),
+            (
+                "param_type",
+                method(stmts),
+                method(stmts).replace("int x)", "long x)"),
+                ParameterTypeChange,
+            ),
+            (
+                "param_rename",
+                method("        a(1);\n"),
+                method("        a(1);\n").replace("int x)", "int y)"),
+                ParameterRenaming,
+            ),
+            (
+                "field_insert",
+                class_a("    int f = 0;\n"),
+                class_a("    int f = 0;\n    int g = 1;\n"),
+                AdditionalObjectState,
+            ),
+            (
+                "field_rename",
+                class_a("    int f = 0;\n"),
+                class_a("    int g = 0;\n"),
+                AttributeRenaming,
+            ),
+            (
+                "method_insert",
+                class_a("    int f = 0;\n"),
+                class_a("    int f = 0;\n\n    void m() {\n        a(1);\n    }\n"),
+                AdditionalFunctionality,
+            ),
+            (
+                "class_rename",
+                class_a("    int f = 0;\n"),
+                class_a("    int f = 0;\n").replace("class A", "class B"),
+                ClassRenaming,
+            ),
+            (
+                "parent_class",
+                class_a("    int f = 0;\n"),
+                class_a("    int f = 0;\n").replace("class A", "class A extends B"),
+                ParentClassInsert,
+            ),
+        ];
+        for (name, before, after, expected) in cases {
+            let res = changes_between(name, &before, &after);
+            assert!(
+                res.iter().any(|x| x.change_type == expected),
+                "{name}: expected {expected:?} in {res:?}"
+            );
+            if !expected.is_behavioral() {
+                assert!(
+                    res.iter().all(|x| !x.change_type.is_behavioral()),
+                    "{name}: {res:?}"
+                );
+            }
+        }
+    }
+}
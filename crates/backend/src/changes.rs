@@ -93,6 +93,7 @@ pub(crate) fn added_deleted(
         use hyper_diff::matchers::mapping_store::DefaultMultiMappingStore as MM;
         let mapped = crate::changes::continue_compute_mappings_full::<_, _, MM<_>>(
             &state.mappings_alone,
+            &state.subtree_mappings,
             &mut mapper,
             None,
         );
@@ -309,6 +310,7 @@ type Mpr<'tree, HAST: HyperAST, M: MonoMappingStore> = hyper_diff::matchers::Map
 
 pub fn continue_compute_mappings_full<'alone, 'tree, HAST: HyperAST + Copy, M, MM>(
     mappings_alone: &'alone crate::MappingAloneCache<HAST::IdN, M>,
+    subtree_mappings: &crate::SubtreeMappingCache<HAST::IdN>,
     mapper: &mut Mpr<'tree, HAST, M>,
     partial: Option<MM>,
 ) -> MappingAloneCacheRef<'alone, HAST::IdN, M>
@@ -327,6 +329,8 @@ where
 {
     use dashmap::mapref::entry::Entry;
     use hyper_diff::matchers::heuristic::gt as matching;
+    // files are the subtrees most likely to be matched again by later diffs
+    let is_file = |hyperast: HAST, id: &HAST::IdN| hyperast.resolve_type(id).is_file();
     match mappings_alone.entry((
         mapper.src_arena.original(&mapper.src_arena.root()),
         mapper.dst_arena.original(&mapper.dst_arena.root()),
@@ -361,11 +365,16 @@ where
             use gt::lazy_greedy_subtree_matcher::LazyGreedySubtreeMatcher;
             LazyGreedySubtreeMatcher::<_>::filter_mappings(mapper, &mm);
 
+            let reused = subtree_mappings.read().unwrap().reuse(mapper, is_file);
+            log::info!("reused {} mappings of files", reused);
+
             use gt::lazy_hybrid_bottom_up_matcher::LazyHybridBottomUpMatcher;
             LazyHybridBottomUpMatcher::<_, M, 200>::execute(mapper);
             let bottom_up_t = now.elapsed().as_secs_f64();
             log::info!("bottom_up_t: {}", bottom_up_t);
 
+            subtree_mappings.write().unwrap().record(mapper, is_file);
+
             let value = (
                 crate::MappingStage::Bottomup,
                 mapper.mapping.mappings.clone(),
@@ -398,11 +407,16 @@ where
             use gt::lazy_greedy_subtree_matcher::LazyGreedySubtreeMatcher;
             LazyGreedySubtreeMatcher::<_>::filter_mappings(mapper, &mm);
 
+            let reused = subtree_mappings.read().unwrap().reuse(mapper, is_file);
+            log::info!("reused {} mappings of files", reused);
+
             use gt::lazy_hybrid_bottom_up_matcher::LazyHybridBottomUpMatcher;
             LazyHybridBottomUpMatcher::<_, M, 200>::execute(mapper);
             let bottom_up_t = now.elapsed().as_secs_f64();
             log::info!("bottom_up_t: {}", bottom_up_t);
 
+            subtree_mappings.write().unwrap().record(mapper, is_file);
+
             let value = (
                 crate::MappingStage::Bottomup,
                 mapper.mapping.mappings.clone(),
@@ -434,6 +448,7 @@ fn compute_and_cache_full_diff(
     use hyper_diff::matchers::mapping_store::DefaultMultiMappingStore as MM;
     crate::changes::continue_compute_mappings_full::<_, _, MM<_>>(
         &state.mappings_alone,
+        &state.subtree_mappings,
         &mut mapper,
         None,
     );
@@ -22,8 +22,30 @@ pub struct Options {
     ///
     /// use the following syntax: <forge>/<user>/<name>:<config>
     /// example: github.com/INRIA/spoon:Java
+    ///
+    /// <forge> can also be the url of a git host or a directory on disk,
+    /// example: file:///srv/git/INRIA/spoon:Java
+    /// example: /home/me/repos/INRIA/spoon:Java
     #[clap(short, long)]
     pub repository: Vec<RepoConfig>,
+
+    /// directory where remote repositories are cloned
+    #[clap(long)]
+    pub clone_root: Option<std::path::PathBuf>,
+
+    /// snapshot of the processed repositories,
+    /// loaded at startup if it exists and written back on shutdown
+    #[clap(long)]
+    pub snapshot: Option<std::path::PathBuf>,
+
+    /// memory budget in MiB,
+    /// when exceeded unreachable nodes are collected and caches are dropped
+    #[clap(long)]
+    pub memory_budget: Option<usize>,
+
+    /// delay in seconds between checks of the memory budget
+    #[clap(long, default_value_t = 60)]
+    pub gc_interval: u64,
 }
 
 pub struct RepoConfig {
@@ -35,7 +57,7 @@ impl std::str::FromStr for RepoConfig {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (repo, config) = s.split_once(":").ok_or("")?;
+        let (repo, config) = s.rsplit_once(":").ok_or("")?;
         let repo = repo.parse()?;
         let config = config.parse()?;
 
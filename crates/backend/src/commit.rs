@@ -6,6 +6,9 @@ use crate::SharedState;
 
 #[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
 pub struct Param {
+    /// only github for pull requests
+    #[serde(default = "github")]
+    pub forge: String,
     pub user: String,
     pub name: String,
     /// either a commit id or a tag
@@ -32,13 +35,14 @@ pub struct Metadata {
 }
 
 // TODO prefetch a list of parent ids in power of 2 [2,4,8,16,32]
-pub fn commit_metadata(_state: SharedState, path: Param) -> Result<Json<Metadata>, String> {
+pub fn commit_metadata(state: SharedState, path: Param) -> Result<Json<Metadata>, String> {
     let Param {
+        forge,
         user,
         name,
         version,
     } = path.clone();
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = state.repo_spec(&forge, &user, &name)?;
     let repo = repo_spec.fetch();
     log::debug!("done cloning {repo_spec}");
     let commit = retrieve_commit(&repo, &version);
@@ -111,6 +115,10 @@ impl From<BuffOut> for String {
     }
 }
 
+fn github() -> String {
+    "github".to_string()
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
 pub struct ParamRemote {
     pub user: String,
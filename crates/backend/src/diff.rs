@@ -0,0 +1,776 @@
+//! Unified diffs derived from structural edit scripts.
+//!
+//! The changed lines are the ones touched by the actions of [`ScriptGenerator`],
+//! the remaining text of both versions is aligned token by token,
+//! so no line diffing is involved.
+//! Moves are annotated as moves and updates as label changes in the hunk headers,
+//! keeping the output applicable as a patch.
+//!
+//! [`ScriptGenerator`]: hyper_diff::actions::script_generator2::ScriptGenerator
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::ops::Range;
+
+use serde::Deserialize;
+use tokio::time::Instant;
+
+use hyper_diff::actions::action_vec::ActionsVec;
+use hyper_diff::actions::script_generator2::{Act, SimpleAction};
+use hyper_diff::algorithms::pipeline::DiffConfig;
+use hyper_diff::tree::tree_path::CompressedTreePath;
+use hyperast::nodes::TextSerializer;
+use hyperast::position::{compute_position, path_with_spaces};
+use hyperast::store::defaults::{LabelIdentifier, NodeIdentifier};
+use hyperast::types::{Childrn, HyperAST, HyperType, LabelStore, Labeled, WithChildren};
+use hyperast_vcs_git::SimpleStores;
+use hyperast_vcs_git::git::Oid;
+use hyperast_vcs_git::preprocessed::child_at_path;
+use hyperast_vcs_git::processing::ConfiguredRepo2;
+
+use crate::{SharedState, utils};
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Param {
+    pub(crate) forge: String,
+    pub(crate) user: String,
+    pub(crate) name: String,
+    /// the commit considered as the original version
+    pub(crate) src: String,
+    /// the commit considered as the modified version
+    pub(crate) dst: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Query {
+    /// number of unchanged lines around changes
+    #[serde(default = "default_context")]
+    context: usize,
+    /// matcher pipeline as JSON, see [`DiffConfig`],
+    /// by default the one of [`hyper_diff::algorithms::gumtree_stable_hybrid_lazy`]
+    config: Option<String>,
+}
+
+fn default_context() -> usize {
+    3
+}
+
+/// The query of the endpoints only configured by a matcher pipeline
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct ConfigQuery {
+    /// matcher pipeline as JSON, see [`DiffConfig`],
+    /// by default the one of [`hyper_diff::algorithms::gumtree_stable_hybrid_lazy`]
+    pub(crate) config: Option<String>,
+}
+
+/// Parses and validates a matcher pipeline given as JSON
+pub(crate) fn parse_config(config: Option<&str>) -> Result<Option<DiffConfig>, String> {
+    let config: Option<DiffConfig> = (config.map(serde_json::from_str).transpose())
+        .map_err(|e| format!("invalid diff config: {}", e))?;
+    if let Some(config) = &config {
+        (config.validate()).map_err(|e| format!("invalid diff config: {}", e))?;
+    }
+    Ok(config)
+}
+
+pub(crate) type A = SimpleAction<LabelIdentifier, CompressedTreePath<u16>, NodeIdentifier>;
+
+pub fn unified_diff(state: SharedState, path: Param, query: Query) -> Result<String, String> {
+    let now = Instant::now();
+    let config = parse_config(query.config.as_deref())?;
+    let (repo, src_oid, dst_oid) = pre_process_pair(&state, &path)?;
+
+    let repositories = state.repositories.read().unwrap();
+    let commit_src = repositories.get_commit(&repo.config, &src_oid).unwrap();
+    let src_tr = commit_src.ast_root;
+    let commit_dst = repositories.get_commit(&repo.config, &dst_oid).unwrap();
+    let dst_tr = commit_dst.ast_root;
+    if src_tr == dst_tr {
+        return Ok(String::new());
+    }
+
+    let with_spaces_stores = &repositories.processor.main_stores;
+    let stores = &hyperast_vcs_git::no_space::as_nospaces(with_spaces_stores);
+    let diff = match &config {
+        Some(config) => hyper_diff::algorithms::pipeline::diff(stores, &src_tr, &dst_tr, config)?,
+        None => hyper_diff::algorithms::gumtree_stable_hybrid_lazy::diff(stores, &src_tr, &dst_tr),
+    };
+    let actions = diff.actions.unwrap_or_default();
+    log::debug!("{} actions in {:?}", actions.len(), now.elapsed());
+
+    let files = collect_changes(with_spaces_stores, src_tr, dst_tr, &actions);
+    let mut out = String::new();
+    for (src_file, f) in files {
+        let dst_file = f.renamed.as_deref().unwrap_or(&src_file);
+        let text = |root, file: &str| {
+            let id = child_at_path(with_spaces_stores, root, file.split("/"))?;
+            Some(TextSerializer::new(with_spaces_stores, id).to_string())
+        };
+        let src_text = text(src_tr, &src_file);
+        let dst_text = text(dst_tr, dst_file);
+        if src_text.is_none() && dst_text.is_none() {
+            log::warn!("{} is missing in both versions", src_file);
+            continue;
+        }
+        let src = src_text.as_deref().map(|t| (src_file.as_str(), t));
+        let dst = dst_text.as_deref().map(|t| (dst_file, t));
+        write_file_diff(&mut out, src, dst, &f.removed, &f.added, query.context)
+            .map_err(|e| e.to_string())?;
+    }
+    log::info!(
+        "unified diff of {src_oid} and {dst_oid} done in {:?}",
+        now.elapsed()
+    );
+    Ok(out)
+}
+
+/// Ensures both commits of `path` are preprocessed, returning them along with their repository
+pub(crate) fn pre_process_pair(
+    state: &SharedState,
+    path: &Param,
+) -> Result<(ConfiguredRepo2, Oid, Oid), String> {
+    let repo_spec = state.repo_spec(&path.forge, &path.user, &path.name)?;
+    let repo = state
+        .repositories
+        .write()
+        .unwrap()
+        .get_config(repo_spec)
+        .ok_or_else(|| "missing config for repository".to_string())?;
+    let mut repo = repo.fetch();
+    log::debug!("done cloning {}", repo.spec);
+    let mut prepro = |commit: &str| {
+        let commits = utils::handle_pre_processing(state, &mut repo, "", commit, 1)
+            .map_err(|e| e.to_string())?;
+        commits
+            .first()
+            .copied()
+            .ok_or_else(|| format!("commit {} not found", commit))
+    };
+    let src_oid = prepro(&path.src)?;
+    let dst_oid = prepro(&path.dst)?;
+    log::debug!(
+        "done construction of {src_oid} and {dst_oid} in {}",
+        repo.spec
+    );
+    Ok((repo, src_oid, dst_oid))
+}
+
+/// A changed range of bytes in a file, along with a description for the hunk headers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Change {
+    pub(crate) range: Range<usize>,
+    pub(crate) note: Option<String>,
+}
+
+/// The changes of a file, keyed by its path in the original version
+#[derive(Debug, Default)]
+struct FileChanges {
+    /// the path in the modified version, if it differs
+    renamed: Option<String>,
+    /// ranges in the original version
+    removed: Vec<Change>,
+    /// ranges in the modified version
+    added: Vec<Change>,
+}
+
+/// What an action designates in a version of the code base
+enum Target {
+    /// a subtree in a file
+    Range {
+        file: String,
+        range: Range<usize>,
+        node: NodeIdentifier,
+    },
+    /// whole files, relatively to the designated file or directory
+    Files {
+        path: String,
+        node: NodeIdentifier,
+        files: Vec<String>,
+    },
+}
+
+fn collect_changes(
+    stores: &SimpleStores,
+    src_tr: NodeIdentifier,
+    dst_tr: NodeIdentifier,
+    actions: &ActionsVec<A>,
+) -> BTreeMap<String, FileChanges> {
+    // paths of actions do not consider spaces
+    let at = |root, path: &CompressedTreePath<u16>| {
+        let (path, _) = path_with_spaces(root, &mut path.iter(), stores);
+        locate(stores, root, &path)
+    };
+    let mut removed = vec![];
+    let mut added = vec![];
+    let mut renames = BTreeMap::<String, String>::new();
+    for a in actions.iter() {
+        match &a.action {
+            Act::Delete {} => removed.push((at(src_tr, &a.path.ori), None)),
+            Act::Insert { .. } => added.push((at(dst_tr, &a.path.ori), None)),
+            Act::Update { before, new } => {
+                let from = at(src_tr, &before.ori);
+                let to = at(dst_tr, &a.path.ori);
+                let note = update_note(stores, &from, new);
+                moved_or_updated(
+                    from,
+                    to,
+                    false,
+                    note,
+                    &mut removed,
+                    &mut added,
+                    &mut renames,
+                );
+            }
+            Act::Move { from } => {
+                let from = at(src_tr, &from.ori);
+                let to = at(dst_tr, &a.path.ori);
+                moved_or_updated(from, to, true, None, &mut removed, &mut added, &mut renames);
+            }
+            Act::MovUpd { from, new } => {
+                let from = at(src_tr, &from.ori);
+                let to = at(dst_tr, &a.path.ori);
+                let note = update_note(stores, &from, new);
+                moved_or_updated(from, to, true, note, &mut removed, &mut added, &mut renames);
+            }
+        }
+    }
+
+    let renamed_from: BTreeMap<_, _> = (renames.iter())
+        .map(|(s, d)| (d.clone(), s.clone()))
+        .collect();
+    let mut res = BTreeMap::<String, FileChanges>::new();
+    for s in renames.keys() {
+        entry(&mut res, &renames, s.clone());
+    }
+    for (target, note) in removed {
+        match target {
+            Target::Range { file: f, range, .. } => entry(&mut res, &renames, f)
+                .removed
+                .push(Change { range, note }),
+            Target::Files { path, files, .. } => {
+                for f in files {
+                    entry(&mut res, &renames, join(&path, &f));
+                }
+            }
+        }
+    }
+    // changes in the modified version are keyed by the original path of their file
+    let key = |f: String| renamed_from.get(&f).cloned().unwrap_or(f);
+    for (target, note) in added {
+        match target {
+            Target::Range { file: f, range, .. } => entry(&mut res, &renames, key(f))
+                .added
+                .push(Change { range, note }),
+            Target::Files { path, files, .. } => {
+                for f in files {
+                    entry(&mut res, &renames, key(join(&path, &f)));
+                }
+            }
+        }
+    }
+    res
+}
+
+fn entry<'a>(
+    res: &'a mut BTreeMap<String, FileChanges>,
+    renames: &BTreeMap<String, String>,
+    path: String,
+) -> &'a mut FileChanges {
+    let renamed = renames.get(&path).cloned();
+    res.entry(path).or_insert_with(|| FileChanges {
+        renamed,
+        ..Default::default()
+    })
+}
+
+/// Records the changes of a moved or updated subtree.
+/// Moved or renamed files and directories become renames.
+fn moved_or_updated(
+    from: Target,
+    to: Target,
+    moved: bool,
+    updated: Option<String>,
+    removed: &mut Vec<(Target, Option<String>)>,
+    added: &mut Vec<(Target, Option<String>)>,
+    renames: &mut BTreeMap<String, String>,
+) {
+    match (from, to) {
+        (Target::Files { path: s, files, .. }, Target::Files { path: d, .. }) => {
+            for f in files {
+                renames.insert(join(&s, &f), join(&d, &f));
+            }
+        }
+        (from, to) => {
+            let describe = |t: &Target| match t {
+                Target::Range { file, .. } => file.clone(),
+                Target::Files { path, .. } => path.clone(),
+            };
+            let notes = |dir: &str, other: &Target| {
+                let moved = moved.then(|| format!("moved {} {}", dir, describe(other)));
+                let n: Vec<_> = moved.iter().chain(updated.iter()).cloned().collect();
+                (!n.is_empty()).then(|| n.join(", "))
+            };
+            let from_note = notes("to", &to);
+            let to_note = notes("from", &from);
+            removed.push((from, from_note));
+            added.push((to, to_note));
+        }
+    }
+}
+
+fn update_note(stores: &SimpleStores, from: &Target, new: &LabelIdentifier) -> Option<String> {
+    let node = match from {
+        Target::Range { node, .. } => node,
+        Target::Files { node, .. } => node,
+    };
+    let t = stores.resolve_type(node);
+    let n = stores.node_store.resolve(*node);
+    let before = n
+        .try_get_label()
+        .map_or("", |l| stores.label_store.resolve(l));
+    let after = stores.label_store.resolve(new);
+    Some(format!("{}: {} -> {}", t.as_static_str(), before, after))
+}
+
+fn join(dir: &str, file: &str) -> String {
+    match (dir.is_empty(), file.is_empty()) {
+        (true, _) => file.to_string(),
+        (_, true) => dir.to_string(),
+        _ => format!("{}/{}", dir, file),
+    }
+}
+
+/// Follows `path` (considering spaces) from `root`,
+/// the reached subtree is then located relatively to its file.
+fn locate(stores: &SimpleStores, root: NodeIdentifier, path: &[u16]) -> Target {
+    let mut x = root;
+    let mut names: Vec<&str> = vec![];
+    let mut rest = path;
+    loop {
+        if stores.resolve_type(&x).is_file() {
+            let (pos, node) = compute_position(x, &mut rest.iter().copied(), stores);
+            return Target::Range {
+                file: names.join("/"),
+                range: pos.range(),
+                node,
+            };
+        }
+        let n = stores.node_store.resolve(x);
+        let child = rest.split_first().and_then(|(o, r)| Some((n.child(o)?, r)));
+        let Some((child, r)) = child else {
+            let mut files = vec![];
+            files_under(stores, x, "", &mut files);
+            return Target::Files {
+                path: names.join("/"),
+                node: x,
+                files,
+            };
+        };
+        let t = stores.resolve_type(&child);
+        if t.is_directory() || t.is_file() {
+            let n = stores.node_store.resolve(child);
+            names.push(stores.label_store.resolve(n.get_label_unchecked()));
+        }
+        x = child;
+        rest = r;
+    }
+}
+
+fn files_under(stores: &SimpleStores, x: NodeIdentifier, prefix: &str, out: &mut Vec<String>) {
+    let t = stores.resolve_type(&x);
+    if t.is_file() {
+        out.push(prefix.to_string());
+        return;
+    } else if !t.is_directory() {
+        return;
+    }
+    let n = stores.node_store.resolve(x);
+    let Some(cs) = n.children() else {
+        return;
+    };
+    for c in cs.iter_children() {
+        let t = stores.resolve_type(&c);
+        if !t.is_directory() && !t.is_file() {
+            continue;
+        }
+        let name = stores
+            .label_store
+            .resolve(stores.node_store.resolve(c).get_label_unchecked());
+        files_under(stores, c, &join(prefix, name), out);
+    }
+}
+
+/// Lines of a text, with their starting offsets
+struct Lines<'a> {
+    text: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> Lines<'a> {
+    fn new(text: &'a str) -> Self {
+        let mut starts = vec![];
+        if !text.is_empty() {
+            starts.push(0);
+        }
+        let newlines = text.match_indices('\n').map(|(i, _)| i + 1);
+        starts.extend(newlines.filter(|i| *i < text.len()));
+        Self { text, starts }
+    }
+
+    fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// the line, including its line ending
+    fn get(&self, i: usize) -> &'a str {
+        let end = self.starts.get(i + 1).copied().unwrap_or(self.text.len());
+        &self.text[self.starts[i]..end]
+    }
+
+    fn line_of(&self, offset: usize) -> usize {
+        self.starts
+            .partition_point(|s| *s <= offset)
+            .saturating_sub(1)
+    }
+
+    /// the lines touched by `range`
+    fn lines_of(&self, range: &Range<usize>) -> Range<usize> {
+        let end = range.end.max(range.start + 1) - 1;
+        self.line_of(range.start)..self.line_of(end) + 1
+    }
+}
+
+/// Offsets of the non-whitespace bytes of `text` that are outside of `changes`
+fn kept(text: &str, changes: &[Change]) -> Vec<usize> {
+    let mut ranges: Vec<_> = changes.iter().map(|c| c.range.clone()).collect();
+    ranges.sort_by_key(|r| r.start);
+    let mut ranges = ranges.into_iter().peekable();
+    let mut skip_until = 0;
+    let mut res = vec![];
+    for (i, b) in text.bytes().enumerate() {
+        while let Some(r) = ranges.next_if(|r| r.start <= i) {
+            skip_until = skip_until.max(r.end);
+        }
+        if i >= skip_until && !b.is_ascii_whitespace() {
+            res.push(i);
+        }
+    }
+    res
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Keep(usize, usize),
+    Del(usize),
+    Ins(usize),
+}
+
+/// Aligns the lines of two versions of a file.
+///
+/// Lines touched by changes are removed or added,
+/// other lines are paired through their tokens, which should be the same in both versions.
+/// Lines without tokens (e.g. blank lines) are paired when they surround paired lines.
+fn align(src: &Lines, dst: &Lines, removed: &[Change], added: &[Change]) -> Vec<Op> {
+    let mark = |lines: &Lines, changes: &[Change]| {
+        let mut changed = vec![false; lines.len()];
+        for c in changes.iter().filter(|_| lines.len() > 0) {
+            changed[lines.lines_of(&c.range)].fill(true);
+        }
+        changed
+    };
+    let changed_src = mark(src, removed);
+    let changed_dst = mark(dst, added);
+
+    // pair lines through their remaining tokens
+    const CONFLICT: usize = usize::MAX;
+    let mut partner_src = vec![None; src.len()];
+    let mut partner_dst = vec![None; dst.len()];
+    let kept_src = kept(src.text, removed);
+    let kept_dst = kept(dst.text, added);
+    if kept_src.len() != kept_dst.len() {
+        log::warn!("the edit script does not explain all the textual changes");
+    }
+    let pair = |partner: &mut Option<usize>, other| match partner {
+        None => *partner = Some(other),
+        Some(p) if *p != other => *partner = Some(CONFLICT),
+        _ => (),
+    };
+    let (mut l, mut m) = (0, 0);
+    for (i, j) in kept_src.into_iter().zip(kept_dst) {
+        if src.text.as_bytes()[i] != dst.text.as_bytes()[j] {
+            log::warn!("the edit script does not explain all the textual changes");
+            break;
+        }
+        while l + 1 < src.len() && src.starts[l + 1] <= i {
+            l += 1;
+        }
+        while m + 1 < dst.len() && dst.starts[m + 1] <= j {
+            m += 1;
+        }
+        pair(&mut partner_src[l], m);
+        pair(&mut partner_dst[m], l);
+    }
+    let anchors = (0..src.len()).filter_map(|a| {
+        let b = partner_src[a].filter(|b| *b != CONFLICT)?;
+        let unchanged = !changed_src[a] && !changed_dst[b];
+        (unchanged && partner_dst[b] == Some(a) && src.get(a) == dst.get(b)).then_some((a, b))
+    });
+
+    let mut ops = vec![];
+    let (mut i, mut j) = (0, 0);
+    for (a, b) in anchors.chain([(src.len(), dst.len())]) {
+        // what remains between anchors,
+        // only the lines that are unchanged and identical at both ends are kept
+        let (mut a0, mut b0) = (i, j);
+        let keep =
+            |a: usize, b: usize| !changed_src[a] && !changed_dst[b] && src.get(a) == dst.get(b);
+        while a0 < a && b0 < b && keep(a0, b0) {
+            ops.push(Op::Keep(a0, b0));
+            (a0, b0) = (a0 + 1, b0 + 1);
+        }
+        let (mut a1, mut b1) = (a, b);
+        while a1 > a0 && b1 > b0 && keep(a1 - 1, b1 - 1) {
+            (a1, b1) = (a1 - 1, b1 - 1);
+        }
+        ops.extend((a0..a1).map(Op::Del));
+        ops.extend((b0..b1).map(Op::Ins));
+        ops.extend((a1..a).zip(b1..b).map(|(a, b)| Op::Keep(a, b)));
+        if a < src.len() {
+            ops.push(Op::Keep(a, b));
+        }
+        (i, j) = (a + 1, b + 1);
+    }
+    ops
+}
+
+/// Writes the unified diff of a file, `None` standing for a missing file.
+/// Files are given as their path along with their content.
+pub(crate) fn write_file_diff(
+    out: &mut impl Write,
+    src: Option<(&str, &str)>,
+    dst: Option<(&str, &str)>,
+    removed: &[Change],
+    added: &[Change],
+    context: usize,
+) -> std::fmt::Result {
+    let src_lines = Lines::new(src.map_or("", |x| x.1));
+    let dst_lines = Lines::new(dst.map_or("", |x| x.1));
+    let ops = match (src, dst) {
+        (Some(_), Some(_)) => align(&src_lines, &dst_lines, removed, added),
+        // added or deleted files
+        _ => (0..src_lines.len())
+            .map(Op::Del)
+            .chain((0..dst_lines.len()).map(Op::Ins))
+            .collect(),
+    };
+    let changes: Vec<usize> = (ops.iter().enumerate())
+        .filter(|(_, op)| !matches!(op, Op::Keep(..)))
+        .map(|(i, _)| i)
+        .collect();
+    let renamed = matches!((src, dst), (Some(s), Some(d)) if s.0 != d.0);
+    if changes.is_empty() && !renamed && src.is_some() && dst.is_some() {
+        return Ok(());
+    }
+
+    let (src_path, dst_path) = match (src, dst) {
+        (Some(s), Some(d)) => (s.0, d.0),
+        (Some(s), None) => (s.0, s.0),
+        (None, Some(d)) => (d.0, d.0),
+        (None, None) => return Ok(()),
+    };
+    writeln!(out, "diff --git a/{} b/{}", src_path, dst_path)?;
+    if src.is_none() {
+        writeln!(out, "new file mode 100644")?;
+    } else if dst.is_none() {
+        writeln!(out, "deleted file mode 100644")?;
+    } else if renamed {
+        writeln!(out, "rename from {}", src_path)?;
+        writeln!(out, "rename to {}", dst_path)?;
+    }
+    if changes.is_empty() {
+        return Ok(());
+    }
+    match src {
+        Some(_) => writeln!(out, "--- a/{}", src_path)?,
+        None => writeln!(out, "--- /dev/null")?,
+    }
+    match dst {
+        Some(_) => writeln!(out, "+++ b/{}", dst_path)?,
+        None => writeln!(out, "+++ /dev/null")?,
+    }
+
+    // lines consumed in each version before each op
+    let mut consumed = Vec::with_capacity(ops.len() + 1);
+    let (mut s, mut d) = (0, 0);
+    for op in &ops {
+        consumed.push((s, d));
+        match op {
+            Op::Keep(..) => (s, d) = (s + 1, d + 1),
+            Op::Del(_) => s += 1,
+            Op::Ins(_) => d += 1,
+        }
+    }
+    consumed.push((s, d));
+
+    let mut k = 0;
+    while k < changes.len() {
+        // group changes separated by less than twice the context
+        let first = changes[k];
+        while k + 1 < changes.len() && changes[k + 1] - changes[k] <= 2 * context + 1 {
+            k += 1;
+        }
+        let last = changes[k];
+        k += 1;
+        let start = first.saturating_sub(context);
+        let end = (last + context + 1).min(ops.len());
+        let hunk = &ops[start..end];
+
+        let (s0, d0) = consumed[start];
+        let (s1, d1) = consumed[end];
+        let range = |start: usize, len: usize| match len {
+            0 => format!("{},0", start),
+            1 => format!("{}", start + 1),
+            len => format!("{},{}", start + 1, len),
+        };
+        write!(out, "@@ -{} +{} @@", range(s0, s1 - s0), range(d0, d1 - d0))?;
+        let mut notes: Vec<&str> = vec![];
+        for op in hunk {
+            let (changes, lines, l) = match *op {
+                Op::Keep(..) => continue,
+                Op::Del(l) => (removed, &src_lines, l),
+                Op::Ins(l) => (added, &dst_lines, l),
+            };
+            for c in changes {
+                let Some(note) = &c.note else { continue };
+                if lines.lines_of(&c.range).contains(&l) && !notes.contains(&note.as_str()) {
+                    notes.push(note);
+                }
+            }
+        }
+        if !notes.is_empty() {
+            write!(out, " {}", notes.join("; "))?;
+        }
+        writeln!(out)?;
+        for op in hunk {
+            let (prefix, line) = match *op {
+                Op::Keep(a, _) => (' ', src_lines.get(a)),
+                Op::Del(a) => ('-', src_lines.get(a)),
+                Op::Ins(b) => ('+', dst_lines.get(b)),
+            };
+            write!(out, "{}{}", prefix, line)?;
+            if !line.ends_with('\n') {
+                writeln!(out)?;
+                writeln!(out, "\\ No newline at end of file")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(text: &str, pat: &str, note: Option<&str>) -> Change {
+        let start = text.find(pat).unwrap();
+        Change {
+            range: start..start + pat.len(),
+            note: note.map(|x| x.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_update_and_insert() {
+        let src = "class A {\n    int f() {\n        return a;\n    }\n\n    void g() {}\n}\n";
+        let dst = "class A {\n    int f() {\n        return b;\n    }\n\n    void g() {}\n    void h() {}\n}\n";
+        let removed = [change(src, "a;", Some("identifier: a -> b"))];
+        let added = [
+            change(dst, "b;", Some("identifier: a -> b")),
+            change(dst, "void h() {}", None),
+        ];
+        let mut out = String::new();
+        write_file_diff(
+            &mut out,
+            Some(("A.java", src)),
+            Some(("A.java", dst)),
+            &removed,
+            &added,
+            1,
+        )
+        .unwrap();
+        let expected = "\
+diff --git a/A.java b/A.java
+--- a/A.java
++++ b/A.java
+@@ -2,3 +2,3 @@ identifier: a -> b
+     int f() {
+-        return a;
++        return b;
+     }
+@@ -6,2 +6,3 @@
+     void g() {}
++    void h() {}
+ }
+";
+        assert_eq!(expected, out);
+    }
+
+    #[test]
+    fn test_move_in_same_file() {
+        let src = "class A {\n    void f() {}\n    void g() {}\n}";
+        let dst = "class A {\n    void g() {}\n    void f() {}\n}";
+        let note = |n: &str| Some(format!("moved {} A.java", n));
+        let removed = [change(src, "void f() {}", note("to").as_deref())];
+        let added = [change(dst, "void f() {}", note("from").as_deref())];
+        let mut out = String::new();
+        write_file_diff(
+            &mut out,
+            Some(("A.java", src)),
+            Some(("A.java", dst)),
+            &removed,
+            &added,
+            3,
+        )
+        .unwrap();
+        let expected = "\
+diff --git a/A.java b/A.java
+--- a/A.java
++++ b/A.java
+@@ -1,4 +1,4 @@ moved to A.java; moved from A.java
+ class A {
+-    void f() {}
+     void g() {}
++    void f() {}
+ }
+\\ No newline at end of file
+";
+        assert_eq!(expected, out);
+    }
+
+    #[test]
+    fn test_parse_config() {
+        assert_eq!(None, parse_config(None).unwrap());
+        let config = parse_config(Some(r#"{"bottom_up":"hybrid","size_threshold":50}"#));
+        let config = config.unwrap().unwrap();
+        assert_eq!(50, config.size_threshold);
+        assert!(parse_config(Some(r#"{"sim_threshold_den":0}"#)).is_err());
+        assert!(parse_config(Some(r#"{"bottom_up":"unknown"}"#)).is_err());
+    }
+
+    #[test]
+    fn test_new_file() {
+        let dst = "a\nb\n";
+        let mut out = String::new();
+        write_file_diff(&mut out, None, Some(("B.java", dst)), &[], &[], 3).unwrap();
+        let expected = "\
+diff --git a/B.java b/B.java
+new file mode 100644
+--- /dev/null
++++ b/B.java
+@@ -0,0 +1,2 @@
++a
++b
+";
+        assert_eq!(expected, out);
+    }
+}
@@ -17,6 +17,7 @@ use crate::{SharedState, app::Timed};
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct Parameters {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -53,13 +54,14 @@ pub struct FetchedNodes {
 pub fn fetch(mut state: SharedState, path: Parameters) -> Result<FetchedNodes, String> {
     let now = Instant::now();
     let Parameters {
+        forge,
         user,
         name,
         commit,
         path,
     } = path;
     dbg!(&path);
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = state.repo_spec(&forge, &user, &name)?;
     let repo = (state.repositories.read().unwrap())
         .get_config(repo_spec)
         .ok_or_else(|| "missing config for repository".to_string())?;
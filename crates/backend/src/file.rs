@@ -6,6 +6,7 @@ use crate::{SharedState, utils};
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct FetchFileParam {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -15,12 +16,13 @@ pub struct FetchFileParam {
 pub fn from_hyperast(state: SharedState, path: FetchFileParam) -> Result<String, String> {
     let now = Instant::now();
     let FetchFileParam {
+        forge,
         user,
         name,
         commit,
         file,
     } = path.clone();
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = state.repo_spec(&forge, &user, &name)?;
     let repo = state
         .repositories
         .write()
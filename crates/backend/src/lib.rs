@@ -4,28 +4,36 @@
 #![allow(unused)]
 use std::{
     net::SocketAddr,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
 };
 
 use dashmap::DashMap;
 use hyper_diff::matchers::mapping_store::VecStore;
+use hyper_diff::matchers::persist::CompressedMappings;
+use hyperast_vcs_git::git::{Forge, Repo};
 use hyperast_vcs_git::multi_preprocessed::PreProcessedRepositories;
 
 use axum::body::Bytes;
 use hyperast::store::nodes::legion::NodeIdentifier;
+use hyperast::store::nodes::legion::gc::{GcStats, Reachable};
 
 pub mod app;
+mod change_types;
 mod changes;
 pub mod cli;
 mod commit;
+mod diff;
 pub mod examples;
 mod fetch;
 mod file;
 mod matching;
 mod pull_requests;
 mod querying;
+mod refactorings;
 mod scriptingv1;
 pub mod smells;
+#[cfg(test)]
+mod test_utils;
 pub mod track;
 #[cfg(feature = "tsg")]
 mod tsg;
@@ -50,7 +58,10 @@ pub struct AppState {
     // configs: RwLock<RepoConfigs>,
     mappings: MappingCache,
     mappings_alone: MappingAloneCache,
+    subtree_mappings: SubtreeMappingCache,
     partial_decomps: PartialDecompCache,
+    precomputed: PrecomputedCache,
+    match_cache: Mutex<MatchCache>,
     // Single shared doc
     doc: Arc<DocState>,
     // Multiple shared docs
@@ -65,7 +76,10 @@ impl Default for AppState {
             repositories: Default::default(),
             mappings: Default::default(),
             mappings_alone: Default::default(),
+            subtree_mappings: Default::default(),
             partial_decomps: Default::default(),
+            precomputed: Default::default(),
+            match_cache: Default::default(),
             doc: Arc::new((
                 RwLock::new(automerge::AutoCommit::new()),
                 tokio::sync::broadcast::channel(50),
@@ -77,6 +91,119 @@ impl Default for AppState {
     }
 }
 
+impl AppState {
+    /// Resolves the repository designated by the `forge`, `user` and `name` segments of a route.
+    /// Registered repositories come first, e.g. the ones on disk or on other hosts,
+    /// then the known forges, e.g. `github`.
+    pub(crate) fn repo_spec(&self, forge: &str, user: &str, name: &str) -> Result<Repo, String> {
+        let repos = self.repositories.read().unwrap();
+        if let Some(repo) = repos.find_repo(forge, user, name) {
+            return Ok(repo.clone());
+        }
+        let forge: Forge = forge.parse()?;
+        forge.try_repo(user, name)
+    }
+
+    /// Frees the nodes that are not reachable from processed commits anymore,
+    /// along with the cached mappings and decompressions referring to them.
+    pub fn collect_garbage(&self) -> GcStats {
+        let gc = self.repositories.write().unwrap().collect_garbage(|_| true);
+        self.retain_reachable(gc)
+    }
+
+    /// Like [`AppState::collect_garbage`],
+    /// but only keeps the `count` most recently processed or retrieved commits.
+    pub fn collect_garbage_keeping_recent(&self, count: usize) -> GcStats {
+        let gc = (self.repositories.write().unwrap()).collect_garbage_keeping_recent(count);
+        self.retain_reachable(gc)
+    }
+
+    fn retain_reachable(&self, (reachable, stats): (Reachable, GcStats)) -> GcStats {
+        self.mappings
+            .retain(|(src, dst), _| reachable.contains(src) && reachable.contains(dst));
+        self.mappings_alone
+            .retain(|(src, dst), _| reachable.contains(src) && reachable.contains(dst));
+        self.subtree_mappings
+            .write()
+            .unwrap()
+            .retain(|src, dst| reachable.contains(src) && reachable.contains(dst));
+        self.partial_decomps.retain(|id, _| reachable.contains(id));
+        // identifiers of freed nodes can be reused
+        *self.match_cache.lock().unwrap() = Default::default();
+        self.precomputed.iter().for_each(|x| x.clear());
+        log::info!("collected garbage: {:?}", stats);
+        stats
+    }
+
+    /// Called periodically when a memory budget (in bytes) is given.
+    /// First collects garbage, then drops the mapping and decompression caches if it was not enough,
+    /// and finally halves the processed commits until the budget is met, evicting the least recently used ones.
+    pub fn enforce_memory_budget(&self, budget: usize) {
+        let used = || isize::from(hyperast::utils::memusage()).max(0) as usize;
+        if used() <= budget {
+            return;
+        }
+        self.collect_garbage();
+        if used() <= budget {
+            return;
+        }
+        self.mappings.clear();
+        self.mappings_alone.clear();
+        self.subtree_mappings.write().unwrap().clear();
+        self.partial_decomps.clear();
+        let mut count = self.repositories.read().unwrap().total_commit_count();
+        while used() > budget && count > 0 {
+            count /= 2;
+            self.collect_garbage_keeping_recent(count);
+        }
+        let used = used();
+        if used > budget {
+            log::warn!(
+                "memory budget exceeded: {} used for a budget of {}",
+                hyperast::utils::Bytes::from(used as isize),
+                hyperast::utils::Bytes::from(budget as isize)
+            );
+        }
+    }
+}
+
+impl AppState {
+    /// Saves the completed mappings, to be loaded along with the snapshot of the repositories,
+    /// as node identifiers are kept by snapshots.
+    pub fn save_mappings(&self, w: &mut impl std::io::Write) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::{write_id, write_u64};
+        let completed: Vec<_> = (self.mappings_alone.iter())
+            .filter(|x| x.value().0 == MappingStage::Bottomup)
+            .collect();
+        write_u64(w, completed.len() as u64)?;
+        for x in completed {
+            let (src, dst) = *x.key();
+            let mappings = &x.value().1;
+            // topit reserves one more slot than the number of nodes
+            let src_len = mappings.src_to_dst.len().saturating_sub(1);
+            let dst_len = mappings.dst_to_src.len().saturating_sub(1);
+            write_id(w, src)?;
+            write_id(w, dst)?;
+            CompressedMappings::compress(mappings, src_len, dst_len).write(w)?;
+        }
+        self.subtree_mappings.read().unwrap().write(w)
+    }
+
+    pub fn load_mappings(&self, r: &mut impl std::io::Read) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::{read_id, read_u64_from};
+        for _ in 0..read_u64_from(r)? {
+            let src = read_id(r)?;
+            let dst = read_id(r)?;
+            let mappings = CompressedMappings::read(r)?.decompress();
+            self.mappings_alone
+                .insert((src, dst), (MappingStage::Bottomup, mappings));
+        }
+        *self.subtree_mappings.write().unwrap() =
+            hyper_diff::matchers::subtree_cache::SubtreeMappingCache::read(r)?;
+        Ok(())
+    }
+}
+
 // pub(crate) type PartialDecompCache = DashMap<NodeIdentifier, DS<NodeIdentifier>>;
 pub(crate) type PartialDecompCache = clashmap::ClashMap<NodeIdentifier, DS<NodeIdentifier>>;
 pub(crate) type MappingAloneCache<IdN = NodeIdentifier, M = VecStore<u32>> =
@@ -84,6 +211,10 @@ pub(crate) type MappingAloneCache<IdN = NodeIdentifier, M = VecStore<u32>> =
 pub(crate) type MappingAloneCacheRef<'a, IdN = NodeIdentifier, M = VecStore<u32>> =
     dashmap::mapref::one::Ref<'a, (IdN, IdN), (MappingStage, M)>;
 
+/// Mappings of subtree pairs, e.g. files, reused across the diffs of different commit pairs
+pub(crate) type SubtreeMappingCache<IdN = NodeIdentifier> =
+    RwLock<hyper_diff::matchers::subtree_cache::SubtreeMappingCache<IdN>>;
+
 #[derive(PartialEq, Eq)]
 pub(crate) enum MappingStage {
     Subtree,
@@ -91,6 +222,18 @@ pub(crate) enum MappingStage {
     Decls,
 }
 
+/// Precomputed patterns registered on already processed repositories
+pub(crate) type PrecomputedCache = DashMap<Repo, Arc<LazyPrecomputed>>;
+pub(crate) type LazyPrecomputed =
+    hyperast_tsquery::lazy_precomputed::LazyPrecomputed<NodeIdentifier, u16>;
+
+/// Matches of the memoized queries, shared between requests,
+/// see [`MATCH_CACHE_LEN`] for its bound
+pub(crate) type MatchCache = hyperast_tsquery::memoized::MatchCache<NodeIdentifier, u16>;
+/// Maximum number of subtrees in the [`MatchCache`],
+/// the least recently used subtrees of the other queries are evicted beyond it
+pub(crate) const MATCH_CACHE_LEN: usize = 1 << 22;
+
 type DS<T> = hyper_diff::decompressed_tree_store::lazy_post_order::LazyPostOrder<T, u32>;
 pub type PersistableMappings<I> = hyper_diff::matchers::Mapping<DS<I>, DS<I>, VecStore<u32>>;
 pub(crate) type MappingCache =
@@ -317,3 +460,30 @@ fn run_tsg(
     );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{diff_param, java_repo};
+
+    #[test]
+    fn test_collect_garbage_keeping_recent() {
+        let a = "class A {\n    void f() {}\n}\n";
+        let a2 = "class A {\n    void g() {}\n}\n";
+        let (state, commits) = java_repo("gc", &[&[("A.java", a)], &[("A.java", a2)]]);
+        let param = diff_param("gc", &commits[0], &commits[1]);
+        let (repo, src, dst) = crate::diff::pre_process_pair(&state, &param).unwrap();
+        let processed = |oid| {
+            let repos = state.repositories.read().unwrap();
+            repos.get_commit(&repo.config, &oid).map(|c| c.ast_root)
+        };
+        let dst_root = processed(dst).unwrap();
+        // retrieving the first commit makes it the most recently used one
+        processed(src).unwrap();
+        state.collect_garbage_keeping_recent(1);
+        assert!(processed(src).is_some());
+        assert!(processed(dst).is_none());
+        let repos = state.repositories.read().unwrap();
+        let node_store = &repos.processor.main_stores.node_store;
+        assert!(node_store.try_resolve(dst_root).is_none());
+    }
+}
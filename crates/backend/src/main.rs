@@ -7,8 +7,8 @@ use backend::*;
 
 use axum::Router;
 use backend::app::{
-    commit_metadata_route, fetch_code_route, fetch_git_file, querying_app, scripting_app,
-    smells_app, track_code_route, tsg_app, view_code_route,
+    commit_metadata_route, diff_route, fetch_code_route, fetch_git_file, querying_app,
+    scripting_app, smells_app, track_code_route, tsg_app, view_code_route,
 };
 use backend::examples::{example_app, kv_store_app};
 use hyper_diff::matchers::mapping_store::VecStore;
@@ -35,6 +35,9 @@ async fn main() {
             log::error!("error logging languages: {}", e)
         };
     }
+    if let Some(path) = &opts.clone_root {
+        hyperast_vcs_git::git::set_clone_root(path).unwrap();
+    }
     let shared_state = SharedState::default();
     {
         use hyperast_vcs_git::processing::RepoConfig;
@@ -60,7 +63,40 @@ async fn main() {
         );
         opts.repository.iter().for_each(|x| {
             repos.register_config(x.repo.clone(), x.config);
-        })
+        });
+        if let Some(path) = opts.snapshot.as_ref().filter(|p| p.exists()) {
+            let now = std::time::Instant::now();
+            let file = std::fs::File::open(path).unwrap();
+            match repos.load(&mut std::io::BufReader::new(file)) {
+                Ok(()) => log::info!("loaded snapshot {:?} in {:?}", path, now.elapsed()),
+                Err(e) => log::error!("failed to load snapshot {:?}: {}", path, e),
+            }
+        }
+    }
+    // mappings refer to the nodes of the snapshot
+    if let Some(path) = opts.snapshot.as_ref().map(|p| p.with_extension("mappings")) {
+        if path.exists() {
+            let now = std::time::Instant::now();
+            let file = std::fs::File::open(&path).unwrap();
+            match shared_state.load_mappings(&mut std::io::BufReader::new(file)) {
+                Ok(()) => log::info!("loaded mappings {:?} in {:?}", path, now.elapsed()),
+                Err(e) => log::error!("failed to load mappings {:?}: {}", path, e),
+            }
+        }
+    }
+    if let Some(budget) = opts.memory_budget {
+        let state = Arc::clone(&shared_state);
+        let period = std::time::Duration::from_secs(opts.gc_interval);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let state = Arc::clone(&state);
+                tokio::task::spawn_blocking(move || state.enforce_memory_budget(budget << 20))
+                    .await
+                    .unwrap();
+            }
+        });
     }
     let app = Router::new()
         .fallback(fallback)
@@ -72,6 +108,7 @@ async fn main() {
         .merge(smells_app(Arc::clone(&shared_state)))
         .merge(fetch_git_file(Arc::clone(&shared_state)))
         .merge(track_code_route(Arc::clone(&shared_state)))
+        .merge(diff_route(Arc::clone(&shared_state)))
         .merge(view_code_route(Arc::clone(&shared_state)))
         .merge(fetch_code_route(Arc::clone(&shared_state)))
         .merge(commit_metadata_route(Arc::clone(&shared_state)))
@@ -93,6 +130,24 @@ async fn main() {
     .with_graceful_shutdown(shutdown_signal())
     .await
     .unwrap();
+    if let Some(path) = &opts.snapshot {
+        let now = std::time::Instant::now();
+        let repos = shared_state.repositories.read().unwrap();
+        let file = std::fs::File::create(path).unwrap();
+        let mut w = std::io::BufWriter::new(file);
+        match repos.save(&mut w) {
+            Ok(()) => log::info!("saved snapshot {:?} in {:?}", path, now.elapsed()),
+            Err(e) => log::error!("failed to save snapshot {:?}: {}", path, e),
+        }
+        let path = path.with_extension("mappings");
+        let now = std::time::Instant::now();
+        let file = std::fs::File::create(&path).unwrap();
+        let mut w = std::io::BufWriter::new(file);
+        match shared_state.save_mappings(&mut w) {
+            Ok(()) => log::info!("saved mappings {:?} in {:?}", path, now.elapsed()),
+            Err(e) => log::error!("failed to save mappings {:?}: {}", path, e),
+        }
+    }
 }
 /// axum handler for any request that fails to match the router routes.
 /// This implementation returns HTTP status code Not Found (404).
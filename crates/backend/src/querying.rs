@@ -28,6 +28,7 @@ use crate::utils::{Arena, IdD, IdN, LocalPieceOfCode, NoS, PieceOfCode, Position
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Param {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -48,6 +49,31 @@ pub struct Content {
     /// checked each match (in milli seconds)
     #[serde(default = "default_timeout")]
     pub timeout: u64,
+    /// reuse the matches of subtrees shared between the queried commits,
+    /// and between requests, see [`hyperast_tsquery::memoized`]
+    ///
+    /// Ignored with `captures`, as memoized matches are not positioned,
+    /// and with structural predicates, as they can depend on the context of subtrees.
+    #[serde(default)]
+    pub memoize: bool,
+    /// also return the captures of matches, a page of them per commit
+    #[serde(default)]
+    pub captures: Option<CapturesPage>,
+    /// also return a profile of the search in each commit, see [`Explanation`]
+    ///
+    /// Ignored with `captures` or `memoize`.
+    #[serde(default)]
+    pub explain: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CapturesPage {
+    /// number of matches skipped in each commit
+    #[serde(default)]
+    pub offset: usize,
+    /// maximum number of matches with their captures in each commit
+    #[serde(default = "default_page_size")]
+    pub limit: usize,
 }
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ContentDifferential {
@@ -88,6 +114,10 @@ fn default_timeout() -> u64 {
     1000
 }
 
+fn default_page_size() -> usize {
+    100
+}
+
 #[derive(Serialize, Clone, Debug)]
 pub enum QueryingError {
     ProcessingError(String),
@@ -132,6 +162,17 @@ pub struct ComputeResults {
     pub prepare_time: f64,
     pub matching_error_count: usize,
     pub results: Vec<Result<ComputeResultIdentified, MatchingError<ComputeResultIdentified>>>,
+    /// patterns that would be searched faster with a precomputed pattern
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hints: Vec<PrecomputedHint>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PrecomputedHint {
+    /// index among the enabled patterns
+    pub pattern: u16,
+    /// the pattern to precompute, e.g. through the `/query-precomputed` route
+    pub precomputed: String,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -145,6 +186,81 @@ pub struct ComputeResultIdentified {
 pub struct ComputeResult {
     pub compute_time: f64,
     pub result: Vec<u64>,
+    /// the requested page of matches, see [`Content::captures`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matches: Option<Vec<MatchResult>>,
+    /// see [`Content::explain`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explain: Option<Explanation>,
+}
+
+/// Where the search of a query spent its time, see [`hyperast_tsquery::explain`]
+#[derive(Debug, Serialize, Clone)]
+pub struct Explanation {
+    /// visible nodes entered
+    pub visited: u64,
+    /// subtrees skipped as they do not contain the precomputed patterns used by the query
+    pub skipped_subtrees: u64,
+    /// per enabled pattern
+    pub patterns: Vec<PatternExplanation>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PatternExplanation {
+    /// searches started on a node matching the root of the pattern
+    pub started: u64,
+    /// searches not started thanks to precomputed patterns
+    pub skipped: u64,
+    /// per step of the pattern, nodes compared with the step
+    pub steps: Vec<u64>,
+    /// matches before evaluating predicates
+    pub matches: u64,
+    /// matches rejected by predicates
+    pub rejected: u64,
+    /// time spent evaluating predicates, in seconds
+    pub predicates_time: f64,
+}
+
+impl Explanation {
+    fn new(
+        query: &hyperast_tsquery::Query,
+        profile: &hyperast_tsquery::explain::QueryProfile,
+    ) -> Self {
+        let patterns = profile
+            .enabled_patterns(query)
+            .into_iter()
+            .map(|p| PatternExplanation {
+                started: p.started,
+                skipped: p.skipped,
+                steps: p.steps.clone(),
+                matches: p.matches,
+                rejected: p.rejected,
+                predicates_time: p.predicates.as_secs_f64(),
+            });
+        Self {
+            visited: profile.visited,
+            skipped_subtrees: profile.skipped_subtrees,
+            patterns: patterns.collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MatchResult {
+    /// index among the enabled patterns
+    pub pattern: u16,
+    pub captures: Vec<CaptureResult>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CaptureResult {
+    pub name: String,
+    #[serde(flatten)]
+    pub code: PieceOfCode<IdN, usize>,
+    /// zero-based line and column (in bytes) of `start`
+    pub start_point: (usize, usize),
+    /// zero-based line and column (in bytes) of `end`
+    pub end_point: (usize, usize),
 }
 impl ComputeResult {
     fn with(self, commit_oid: &Oid) -> ComputeResultIdentified {
@@ -163,7 +279,12 @@ pub fn simple(
     path: Param,
 ) -> Result<ComputeResults, QueryingError> {
     let now = Instant::now();
-    let Param { user, name, commit } = path.clone();
+    let Param {
+        forge,
+        user,
+        name,
+        commit,
+    } = path.clone();
     let mut additional = commit.split("/");
     let commit = additional.next().unwrap();
     let Content {
@@ -173,20 +294,30 @@ pub fn simple(
         commits,
         max_matches,
         timeout,
+        memoize,
+        captures,
+        explain,
     } = query;
+    let query_hash = hash_query(&query, precomp.as_deref());
     let timeout = std::time::Duration::from_millis(timeout);
     let mut proc_commit_limit = commits;
     let config = if language == "Java" {
         hyperast_vcs_git::processing::RepoConfig::JavaMaven
     } else if language == "Cpp" {
         hyperast_vcs_git::processing::RepoConfig::CppMake
+    } else if language == "Python" {
+        hyperast_vcs_git::processing::RepoConfig::PythonPip
+    } else if language == "Rust" {
+        hyperast_vcs_git::processing::RepoConfig::RustCargo
     } else {
         hyperast_vcs_git::processing::RepoConfig::Any
     };
     let lang = &language;
     let language = hyperast_vcs_git::resolve_language(&language)
         .ok_or_else(|| QueryingError::MissingLanguage(language.to_string()))?;
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = state
+        .repo_spec(&forge, &user, &name)
+        .map_err(QueryingError::ProcessingError)?;
     let repo = state
         .repositories
         .read()
@@ -224,7 +355,11 @@ pub fn simple(
         })
         .flatten();
 
-    let query = if let Some(precomputeds) = precomputeds {
+    let lazy = lazy_precomputed(&state, &repo.spec, memoize);
+    let query = if let Some(lazy) = &lazy {
+        let precomputeds = lazy.precomputeds();
+        hyperast_tsquery::Query::with_precomputed(&query, language, &precomputeds[..]).map(|x| x.1)
+    } else if let Some(precomputeds) = precomputeds {
         hyperast_tsquery::Query::with_precomputed(&query, language, precomputeds).map(|x| x.1)
     } else {
         hyperast_tsquery::Query::new(&query, language)
@@ -232,6 +367,7 @@ pub fn simple(
     .map_err(|e| QueryingError::ParsingError(e.to_string()))?;
 
     log::info!("done query construction");
+    let hints = precomputed_hints(&query);
     let prepare_time = now.elapsed().as_secs_f64();
     let mut results = vec![];
     let mut matching_error_count = 0;
@@ -241,6 +377,7 @@ pub fn simple(
                 prepare_time,
                 matching_error_count,
                 results,
+                hints,
             });
         }
         let mut oid = commit_oid.to_string();
@@ -250,7 +387,30 @@ pub fn simple(
         let commit = repositories.get_commit(&repo.config, commit_oid).unwrap();
         let code = commit.ast_root;
         let stores = &repositories.processor.main_stores;
-        let result = simple_aux(stores, code, &query, timeout, max_matches);
+        let result = if let Some(page) = &captures {
+            let globalize =
+                |pos: LocalPieceOfCode<IdN, usize>| pos.globalize(&repo.spec, *commit_oid);
+            let predicates = CursorNode::structural_predicates();
+            let qcursor =
+                matches_with_positions(stores, code, &query, lazy.as_deref(), &predicates);
+            captures_aux(
+                stores,
+                &query,
+                qcursor,
+                page,
+                globalize,
+                timeout,
+                max_matches,
+            )
+        } else if memoize && query.general_predicates.is_empty() {
+            let query = hyperast_tsquery::memoized::MemoizedQuery::new(&query, query_hash);
+            let cache = &state.match_cache;
+            memoized_aux(stores, code, &query, cache, timeout, max_matches)
+        } else if explain {
+            explain_aux(stores, code, &query, lazy.as_deref(), timeout, max_matches)
+        } else {
+            simple_aux(stores, code, &query, lazy.as_deref(), timeout, max_matches)
+        };
         let result = match result {
             Ok(inner) => Ok(inner.with(commit_oid)),
             Err(err) if results.is_empty() => {
@@ -272,10 +432,18 @@ pub fn simple(
         prepare_time,
         matching_error_count,
         results,
+        hints,
     })
 }
 
-pub fn streamed(mut state: SharedState, path: Param, content: Content) -> axum::response::Response {
+/// Streams the result of each commit as soon as it is computed,
+/// as concatenated JSON objects or, if `ndjson`, as newline delimited JSON.
+pub fn streamed(
+    mut state: SharedState,
+    path: Param,
+    content: Content,
+    ndjson: bool,
+) -> axum::response::Response {
     let now = Instant::now();
 
     let mut headers = HeaderMap::new();
@@ -305,13 +473,18 @@ pub fn streamed(mut state: SharedState, path: Param, content: Content) -> axum::
 
     headers.insert("commits", commits.len().into());
 
-    let pre_query = pre_query(&mut state, &path, &content, repo.config);
+    let lazy = lazy_precomputed(&state, &repo.spec, content.memoize);
+    let pre_query = pre_query(&mut state, &path, &content, repo.config, lazy.as_deref());
     let Content {
         commits: mut proc_commit_limit,
         max_matches,
         timeout,
+        memoize,
+        captures,
+        explain,
         ..
     } = content.clone();
+    let query_hash = hash_query(&content.query, content.precomp.as_deref());
     let timeout = std::time::Duration::from_millis(timeout);
     log::info!("done query construction");
     let prepare_time = now.elapsed().as_secs_f64();
@@ -356,7 +529,30 @@ pub fn streamed(mut state: SharedState, path: Param, content: Content) -> axum::
             let commit = repositories.get_commit(&repo.config, &commit_oid).unwrap();
             let code = commit.ast_root;
             let stores = &repositories.processor.main_stores;
-            let result = simple_aux(stores, code, &query, timeout, max_matches);
+            let result = if let Some(page) = &captures {
+                let globalize =
+                    |pos: LocalPieceOfCode<IdN, usize>| pos.globalize(&repo.spec, commit_oid);
+                let predicates = CursorNode::structural_predicates();
+                let qcursor =
+                    matches_with_positions(stores, code, &query, lazy.as_deref(), &predicates);
+                captures_aux(
+                    stores,
+                    &query,
+                    qcursor,
+                    page,
+                    globalize,
+                    timeout,
+                    max_matches,
+                )
+            } else if memoize && query.general_predicates.is_empty() {
+                let query = hyperast_tsquery::memoized::MemoizedQuery::new(&query, query_hash);
+                let cache = &state.match_cache;
+                memoized_aux(stores, code, &query, cache, timeout, max_matches)
+            } else if explain {
+                explain_aux(stores, code, &query, lazy.as_deref(), timeout, max_matches)
+            } else {
+                simple_aux(stores, code, &query, lazy.as_deref(), timeout, max_matches)
+            };
             let result = match result {
                 Ok(inner) => Ok(inner.with(&commit_oid)),
                 Err(err) => {
@@ -380,15 +576,22 @@ pub fn streamed(mut state: SharedState, path: Param, content: Content) -> axum::
             Some(result)
         });
 
-    let st_vals = futures::stream::iter(it.map(|x| {
+    let st_vals = futures::stream::iter(it.map(move |x| {
         match x {
             Ok(x) => serde_json::to_string(&x).map_err(|e| e.to_string()),
             Err(x) => serde_json::to_string(&x).map_err(|e| e.to_string()),
         }
+        .map(|x| if ndjson { x + "\n" } else { x })
         // x.map(|x| serde_json::to_string(&x).unwrap())
         //     .map_err(|x| serde_json::to_string(&x).unwrap())
     }));
 
+    if ndjson {
+        headers.insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static("application/x-ndjson"),
+        );
+    }
     (
         StatusCode::OK,
         headers,
@@ -397,12 +600,124 @@ pub fn streamed(mut state: SharedState, path: Param, content: Content) -> axum::
         .into_response()
 }
 
+#[derive(Deserialize, Clone)]
+pub struct ParamPrecomputed {
+    forge: String,
+    user: String,
+    name: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ContentPrecomputed {
+    pub language: String,
+    /// patterns to precompute in addition to the already registered ones, one pattern per element
+    pub patterns: Vec<String>,
+    /// queries on which to report the uses of the precomputed patterns
+    #[serde(default)]
+    pub queries: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PrecomputedReport {
+    /// all the precomputed patterns of the repository, in the order of their bits,
+    /// starting with the ones precomputed while processing it
+    pub precomputeds: Vec<String>,
+    /// for each query then each of its patterns,
+    /// the indexes of the precomputed patterns speeding up its search
+    pub uses: Vec<Vec<Vec<usize>>>,
+}
+
+/// Adds precomputed patterns to an already processed repository,
+/// their presence in each subtree is computed lazily while querying,
+/// see [`hyperast_tsquery::lazy_precomputed`].
+pub fn precomputed(
+    content: ContentPrecomputed,
+    state: SharedState,
+    path: ParamPrecomputed,
+) -> Result<PrecomputedReport, QueryingError> {
+    use hyperast_tsquery::ArrayStr;
+    let ParamPrecomputed { forge, user, name } = path;
+    let ContentPrecomputed {
+        language,
+        patterns,
+        queries,
+    } = content;
+    let lang = &language;
+    let language = hyperast_vcs_git::resolve_language(&language)
+        .ok_or_else(|| QueryingError::MissingLanguage(language.to_string()))?;
+    let repo_spec = state
+        .repo_spec(&forge, &user, &name)
+        .map_err(QueryingError::ProcessingError)?;
+    let stored = {
+        let repositories = state.repositories.read().unwrap();
+        let repo = repositories
+            .get_config(repo_spec.clone())
+            .ok_or_else(|| QueryingError::ProcessingError(format!("{repo_spec} not processed")))?;
+        repositories.get_precomp_query(repo.config, lang)
+    };
+    let stored: Vec<&str> = stored.iter().flat_map(|x| x.iter()).collect();
+    let previous = state.precomputed.get(&repo_spec).map(|x| x.clone());
+    let mut registered: Vec<&str> = previous
+        .iter()
+        .flat_map(|x| x.registered())
+        .map(|x| x.as_str())
+        .collect();
+    for p in &patterns {
+        if !stored.contains(&p.as_str()) && !registered.contains(&p.as_str()) {
+            registered.push(p);
+        }
+    }
+    let lazy = crate::LazyPrecomputed::new(language.clone(), &stored, &registered)
+        .map_err(|e| QueryingError::ParsingError(e.to_string()))?;
+    let precomputeds = lazy.precomputeds();
+    let uses = queries
+        .iter()
+        .map(|query| {
+            let (_, query) = hyperast_tsquery::Query::with_precomputed(
+                query,
+                language.clone(),
+                &precomputeds[..],
+            )
+            .map_err(|e| QueryingError::ParsingError(e.to_string()))?;
+            let uses = query.precomputed_uses().into_iter().map(|bits| {
+                (0..precomputeds.len())
+                    .filter(|i| bits >> i & 1 == 1)
+                    .collect()
+            });
+            Ok(uses.collect())
+        })
+        .collect::<Result<_, QueryingError>>()?;
+    let precomputeds = precomputeds.iter().map(|x| x.to_string()).collect();
+    state
+        .precomputed
+        .insert(repo_spec, std::sync::Arc::new(lazy));
+    Ok(PrecomputedReport { precomputeds, uses })
+}
+
+/// The precomputed patterns registered on `repo` with [`precomputed`],
+/// not used when memoizing as matches are then only computed once per subtree anyway
+fn lazy_precomputed(
+    state: &SharedState,
+    repo: &hyperast_vcs_git::git::Repo,
+    memoize: bool,
+) -> Option<std::sync::Arc<crate::LazyPrecomputed>> {
+    if memoize {
+        return None;
+    }
+    state.precomputed.get(repo).map(|x| x.clone())
+}
+
 fn pre_repo(
     state: &mut SharedState,
     path: &Param,
     content: &Content,
 ) -> Result<(hyperast_vcs_git::processing::ConfiguredRepo2, Vec<Oid>), Box<dyn std::error::Error>> {
-    let Param { user, name, commit } = path.clone();
+    let Param {
+        forge,
+        user,
+        name,
+        commit,
+    } = path.clone();
     let mut additional = commit.split("/");
     let commit = additional.next().unwrap();
     let Content {
@@ -412,15 +727,22 @@ fn pre_repo(
         commits,
         max_matches: _,
         timeout: _,
+        memoize: _,
+        captures: _,
+        explain: _,
     } = content.clone();
     let config = if language == "Java" {
         hyperast_vcs_git::processing::RepoConfig::JavaMaven
     } else if language == "Cpp" {
         hyperast_vcs_git::processing::RepoConfig::CppMake
+    } else if language == "Python" {
+        hyperast_vcs_git::processing::RepoConfig::PythonPip
+    } else if language == "Rust" {
+        hyperast_vcs_git::processing::RepoConfig::RustCargo
     } else {
         hyperast_vcs_git::processing::RepoConfig::Any
     };
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = state.repo_spec(&forge, &user, &name)?;
     let repo = state
         .repositories
         .read()
@@ -462,8 +784,14 @@ fn pre_query(
     path: &Param,
     content: &Content,
     repo_config: hyperast_vcs_git::processing::ParametrizedCommitProcessorHandle,
+    lazy: Option<&crate::LazyPrecomputed>,
 ) -> Result<hyperast_tsquery::Query, QueryingError> {
-    let Param { user, name, commit } = path.clone();
+    let Param {
+        forge,
+        user,
+        name,
+        commit,
+    } = path.clone();
     let mut additional = commit.split("/");
     let commit = additional.next().unwrap();
     let Content {
@@ -473,11 +801,18 @@ fn pre_query(
         commits: _,
         max_matches: _,
         timeout: _,
+        memoize: _,
+        captures: _,
+        explain: _,
     } = &content;
     let config = if language == "Java" {
         hyperast_vcs_git::processing::RepoConfig::JavaMaven
     } else if language == "Cpp" {
         hyperast_vcs_git::processing::RepoConfig::CppMake
+    } else if language == "Python" {
+        hyperast_vcs_git::processing::RepoConfig::PythonPip
+    } else if language == "Rust" {
+        hyperast_vcs_git::processing::RepoConfig::RustCargo
     } else {
         hyperast_vcs_git::processing::RepoConfig::Any
     };
@@ -486,6 +821,12 @@ fn pre_query(
         .ok_or_else(|| QueryingError::MissingLanguage(language.to_string()))?;
     let language: tree_sitter::Language = language.clone();
 
+    if let Some(lazy) = lazy {
+        let precomputeds = lazy.precomputeds();
+        return hyperast_tsquery::Query::with_precomputed(query, language, &precomputeds[..])
+            .map(|x| x.1)
+            .map_err(|e| QueryingError::ParsingError(e.to_string()));
+    }
     let precomputeds = INCREMENTAL_QUERIES.then(|| {
         state
             .repositories
@@ -505,6 +846,7 @@ fn simple_aux(
     stores: &SimpleStores<TStore>,
     code: NodeIdentifier,
     query: &hyperast_tsquery::Query,
+    lazy: Option<&crate::LazyPrecomputed>,
     timeout: std::time::Duration,
     max_matches: u64,
 ) -> Result<ComputeResult, MatchingError<ComputeResult>> {
@@ -524,7 +866,12 @@ fn simple_aux(
         None
     };
     log::info!("Starting query on tree with height {}", height);
-    let r = if height < 128 {
+    let r = if let Some(lazy) = lazy {
+        aux_lazy(stores, code, query, lazy, ex)
+    } else if !query.general_predicates.is_empty() {
+        // the optimized cursors do not evaluate structural predicates
+        aux_default(stores, code, query, ex)
+    } else if height < 128 {
         aux_opt128(stores, code, query, ex)
     } else if height < 512 {
         aux_opt(stores, code, query, ex)
@@ -537,12 +884,16 @@ fn simple_aux(
             return Err(MatchingError::TimeOut(ComputeResult {
                 result,
                 compute_time: compute_time.as_secs_f64(),
+                matches: None,
+                explain: None,
             }));
         }
         Some(MatchingError::MaxMatches(compute_time)) => {
             return Err(MatchingError::MaxMatches(ComputeResult {
                 result,
                 compute_time: compute_time.as_secs_f64(),
+                matches: None,
+                explain: None,
             }));
         }
         _ => (),
@@ -552,9 +903,248 @@ fn simple_aux(
     Ok(ComputeResult {
         result,
         compute_time,
+        matches: None,
+        explain: None,
     })
 }
 
+/// Counts matches like [`simple_aux`], while profiling the search, see [`Content::explain`]
+fn explain_aux(
+    stores: &SimpleStores<TStore>,
+    code: NodeIdentifier,
+    query: &hyperast_tsquery::Query,
+    lazy: Option<&crate::LazyPrecomputed>,
+    timeout: std::time::Duration,
+    max_matches: u64,
+) -> Result<ComputeResult, MatchingError<ComputeResult>> {
+    let mut result = vec![0; query.enabled_pattern_count()];
+    let now = Instant::now();
+    let mut ex = |m: hyperast_tsquery::QueryMatch<CursorNode<'_>>| {
+        let i = query.enabled_pattern_index(m.pattern_index).unwrap() as usize;
+        result[i] += 1;
+        let compute_time = now.elapsed();
+        if compute_time >= timeout {
+            return Some(MatchingError::TimeOut(()));
+        } else if result[i] > max_matches {
+            return Some(MatchingError::MaxMatches(()));
+        }
+        None
+    };
+    let pos = StructuralPosition::new(code);
+    let predicates = CursorNode::structural_predicates();
+    // the profile is also kept when interrupted, to explain the interruption
+    let (error, profile) = if let Some(lazy) = lazy {
+        let cursor = hyperast_tsquery::lazy_precomputed::TreeCursor::new(stores, pos, lazy);
+        let mut qcursor = query.matches(cursor).with_predicates(&predicates).explain();
+        (qcursor.by_ref().find_map(&mut ex), qcursor.profile)
+    } else {
+        let cursor = hyperast_tsquery::hyperast_cursor::TreeCursor::new(stores, pos);
+        let mut qcursor = query.matches(cursor).with_predicates(&predicates).explain();
+        (qcursor.by_ref().find_map(&mut ex), qcursor.profile)
+    };
+    let result = ComputeResult {
+        result,
+        compute_time: now.elapsed().as_secs_f64(),
+        matches: None,
+        explain: profile.map(|profile| Explanation::new(query, &profile)),
+    };
+    match error {
+        Some(err) => Err(err.map(|()| result.clone())),
+        None => Ok(result),
+    }
+}
+
+/// See [`hyperast_tsquery::Query::precomputed_hints`]
+fn precomputed_hints(query: &hyperast_tsquery::Query) -> Vec<PrecomputedHint> {
+    let hints = query.precomputed_hints().into_iter().enumerate();
+    hints
+        .filter_map(|(i, hint)| {
+            hint.map(|precomputed| PrecomputedHint {
+                pattern: i as u16,
+                precomputed,
+            })
+        })
+        .collect()
+}
+
+/// Counts matches like [`simple_aux`], and positions the captures of the matches in `page`
+fn captures_aux<'a>(
+    stores: &'a SimpleStores<TStore>,
+    query: &hyperast_tsquery::Query,
+    qcursor: impl Iterator<Item = hyperast_tsquery::QueryMatch<CursorNode<'a>>>,
+    page: &CapturesPage,
+    globalize: impl Fn(LocalPieceOfCode<IdN, usize>) -> PieceOfCode<IdN, usize>,
+    timeout: std::time::Duration,
+    max_matches: u64,
+) -> Result<ComputeResult, MatchingError<ComputeResult>> {
+    let now = Instant::now();
+    let mut result = vec![0; query.enabled_pattern_count()];
+    let mut matches = vec![];
+    // the text of files with captures, to compute lines and columns
+    let mut texts = std::collections::HashMap::<NodeIdentifier, String>::new();
+    // the search stops as soon as a pattern exceeds max_matches
+    let count = (max_matches as usize)
+        .saturating_add(1)
+        .saturating_mul(query.enabled_pattern_count());
+    let page = page.offset.min(count)..page.offset.saturating_add(page.limit).min(count);
+    let mut error = None;
+    for (n, m) in qcursor.enumerate() {
+        let i = query.enabled_pattern_index(m.pattern_index).unwrap();
+        result[i as usize] += 1;
+        if page.contains(&n) {
+            let captures = (&m.captures).into_iter().map(|c| {
+                let pos = &c.node.pos;
+                let offsets = pos.iter_offsets().map(|x| x as usize).collect();
+                let position = pos.make_position(stores);
+                let range = position.range();
+                let file = pos.iter_nodes().chain([pos.node()]);
+                let file = file.filter(|x| stores.resolve_type(x).is_file()).last();
+                let text = file.map_or("", |file| {
+                    texts
+                        .entry(file)
+                        .or_insert_with(|| TextSerializer::new(stores, file).to_string())
+                        .as_str()
+                });
+                let start_point = text_point(text, range.start);
+                let end_point = text_point(text, range.end);
+                let code = LocalPieceOfCode::from_position(&position, offsets, vec![]);
+                CaptureResult {
+                    name: query.capture_name(c.index).to_string(),
+                    code: globalize(code),
+                    start_point,
+                    end_point,
+                }
+            });
+            let captures = captures.collect();
+            matches.push(MatchResult {
+                pattern: i,
+                captures,
+            });
+        }
+        let compute_time = now.elapsed();
+        if compute_time >= timeout {
+            error = Some(MatchingError::TimeOut(()));
+            break;
+        } else if result[i as usize] > max_matches {
+            error = Some(MatchingError::MaxMatches(()));
+            break;
+        }
+    }
+    let result = ComputeResult {
+        result,
+        compute_time: now.elapsed().as_secs_f64(),
+        matches: Some(matches),
+        explain: None,
+    };
+    match error {
+        Some(err) => Err(err.map(|()| result.clone())),
+        None => Ok(result),
+    }
+}
+
+type CursorNode<'a> = hyperast_tsquery::hyperast_cursor::Node<'a, SimpleStores<TStore>>;
+
+/// Matches of `query` in `code` satisfying the structural `predicates`,
+/// skipping subtrees without the `lazy` precomputed patterns
+fn matches_with_positions<'a>(
+    stores: &'a SimpleStores<TStore>,
+    code: NodeIdentifier,
+    query: &'a hyperast_tsquery::Query,
+    lazy: Option<&'a crate::LazyPrecomputed>,
+    predicates: &'a hyperast_tsquery::predicate::CustomPredicates<CursorNode<'a>>,
+) -> Box<dyn Iterator<Item = hyperast_tsquery::QueryMatch<CursorNode<'a>>> + 'a> {
+    use hyperast_tsquery::{hyperast_cursor, lazy_precomputed};
+    let pos = StructuralPosition::new(code);
+    match lazy {
+        Some(lazy) => {
+            let cursor = lazy_precomputed::TreeCursor::new(stores, pos, lazy);
+            Box::new(query.matches(cursor).with_predicates(predicates))
+        }
+        None => {
+            let cursor = hyperast_cursor::TreeCursor::new(stores, pos);
+            Box::new(query.matches(cursor).with_predicates(predicates))
+        }
+    }
+}
+
+/// Zero-based line and column of `offset` in `text`
+fn text_point(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text.as_bytes()[..offset.min(text.len())];
+    let line = before.iter().filter(|x| **x == b'\n').count();
+    let column = before.iter().rev().take_while(|x| **x != b'\n').count();
+    (line, column)
+}
+
+/// Identifies a query and its precomputed patterns in a [`hyperast_tsquery::memoized::MatchCache`]
+fn hash_query(query: &str, precomp: Option<&str>) -> hyperast_tsquery::memoized::QueryHash {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    query.hash(&mut hasher);
+    precomp.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Number of subtrees computed by [`memoized_aux`] before releasing the shared cache,
+/// so other requests are not blocked during a whole evaluation
+const MEMOIZED_BATCH: usize = 1 << 12;
+
+/// Counts matches like [`simple_aux`], reusing the matches of the subtrees in the shared `cache`
+///
+/// On timeout, the subtrees computed so far stay in `cache`, thus a retry resumes the search.
+/// The cache is locked per batch of [`MEMOIZED_BATCH`] subtrees,
+/// each batch resuming from the subtrees computed by the previous ones.
+fn memoized_aux(
+    stores: &SimpleStores<TStore>,
+    code: NodeIdentifier,
+    query: &hyperast_tsquery::memoized::MemoizedQuery,
+    cache: &std::sync::Mutex<crate::MatchCache>,
+    timeout: std::time::Duration,
+    max_matches: u64,
+) -> Result<ComputeResult, MatchingError<ComputeResult>> {
+    let now = Instant::now();
+    let mut result = vec![];
+    let mut err = None;
+    let matches = loop {
+        let mut cache = cache.lock().unwrap();
+        let mut computed = 0;
+        let matches = query.eval_until(stores, &mut cache, code, |counts| {
+            if now.elapsed() >= timeout {
+                err = Some(MatchingError::TimeOut(()));
+            } else if counts.iter().any(|x| *x > max_matches) {
+                err = Some(MatchingError::MaxMatches(()));
+            } else {
+                computed += 1;
+                return computed >= MEMOIZED_BATCH;
+            }
+            // counts only increase towards the root
+            result = counts.to_vec();
+            true
+        });
+        if matches.is_some() || err.is_some() {
+            break matches;
+        }
+    };
+    let mut cache = cache.lock().unwrap();
+    cache.evict(crate::MATCH_CACHE_LEN, Some(query.hash()));
+    log::info!("{} subtrees with memoized matches", cache.len());
+    drop(cache);
+    let result = ComputeResult {
+        result: matches.map_or(result, |x| x.counts.to_vec()),
+        compute_time: now.elapsed().as_secs_f64(),
+        matches: None,
+        explain: None,
+    };
+    match err {
+        Some(MatchingError::TimeOut(())) => Err(MatchingError::TimeOut(result)),
+        Some(MatchingError::MaxMatches(())) => Err(MatchingError::MaxMatches(result)),
+        // the root was already in cache
+        None if result.result.iter().any(|x| *x > max_matches) => {
+            Err(MatchingError::MaxMatches(result))
+        }
+        None => Ok(result),
+    }
+}
+
 fn aux_opt<T>(
     stores: &SimpleStores<TStore>,
     code: NodeIdentifier,
@@ -603,7 +1193,29 @@ fn aux_default<T>(
 ) -> Option<T> {
     let pos = StructuralPosition::new(code);
     let cursor = hyperast_tsquery::hyperast_cursor::TreeCursor::new(stores, pos);
-    let qcursor = query.matches(cursor);
+    let predicates = CursorNode::structural_predicates();
+    let qcursor = query.matches(cursor).with_predicates(&predicates);
+    for m in qcursor {
+        let i = m.pattern_index;
+        let i = query.enabled_pattern_index(i).unwrap();
+        if let Some(value) = ex(i as usize) {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn aux_lazy<T>(
+    stores: &SimpleStores<TStore>,
+    code: NodeIdentifier,
+    query: &hyperast_tsquery::Query,
+    lazy: &crate::LazyPrecomputed,
+    mut ex: impl FnMut(usize) -> Option<T>,
+) -> Option<T> {
+    let pos = StructuralPosition::new(code);
+    let cursor = hyperast_tsquery::lazy_precomputed::TreeCursor::new(stores, pos, lazy);
+    let predicates = CursorNode::structural_predicates();
+    let qcursor = query.matches(cursor).with_predicates(&predicates);
     for m in qcursor {
         let i = m.pattern_index;
         let i = query.enabled_pattern_index(i).unwrap();
@@ -621,6 +1233,7 @@ pub struct ComputeResultsDifferential {
 }
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ParamDifferential {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -634,6 +1247,7 @@ pub fn differential(
 ) -> Result<ComputeResultsDifferential, QueryingError> {
     let now = Instant::now();
     let ParamDifferential {
+        forge,
         user,
         name,
         commit,
@@ -659,13 +1273,19 @@ pub fn differential(
         hyperast_vcs_git::processing::RepoConfig::JavaMaven
     } else if language == "Cpp" {
         hyperast_vcs_git::processing::RepoConfig::CppMake
+    } else if language == "Python" {
+        hyperast_vcs_git::processing::RepoConfig::PythonPip
+    } else if language == "Rust" {
+        hyperast_vcs_git::processing::RepoConfig::RustCargo
     } else {
         hyperast_vcs_git::processing::RepoConfig::Any
     };
     let lang = &language;
     let language: tree_sitter::Language = hyperast_vcs_git::resolve_language(&language)
         .ok_or_else(|| QueryingError::MissingLanguage(language.to_string()))?;
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = state
+        .repo_spec(&forge, &user, &name)
+        .map_err(QueryingError::ProcessingError)?;
     let repo = state
         .repositories
         .read()
@@ -1192,7 +1812,8 @@ fn differential_aux(
 ) -> (Vec<Position>, Vec<Names>, DifferentialErrorFlags) {
     let pos = StructuralPosition::new(code);
     let cursor = hyperast_tsquery::hyperast_cursor::TreeCursor::new(stores, pos);
-    let qcursor = query.matches(cursor);
+    let predicates = CursorNode::structural_predicates();
+    let qcursor = query.matches(cursor).with_predicates(&predicates);
     let now = Instant::now();
     let mut results = vec![];
     let mut result_names = vec![];
@@ -1235,3 +1856,116 @@ fn differential_aux(
     let compute_time = now.elapsed().as_secs_f64();
     (results, result_names, err_flags)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::java_repo;
+
+    const A: &str =
+        "package a;\n\nclass A {\n    void f() {}\n\n    void g() {}\n\n    void h() {}\n}\n";
+
+    const A2: &str = "package a;\n\nclass A {\n    void f() {}\n}\n";
+
+    fn content(commits: usize, captures: Option<CapturesPage>) -> Content {
+        Content {
+            language: "Java".to_string(),
+            query: "(method_declaration) @m".to_string(),
+            precomp: None,
+            commits,
+            max_matches: default_max_matches(),
+            timeout: default_timeout(),
+            memoize: false,
+            captures,
+            explain: false,
+        }
+    }
+
+    #[test]
+    fn test_captures_paging() {
+        let (state, commits) = java_repo("captures", &[&[("a/A.java", A)]]);
+        let page = |offset, limit| {
+            let path = Param {
+                forge: "local".to_string(),
+                user: "test".to_string(),
+                name: "captures".to_string(),
+                commit: commits[0].clone(),
+            };
+            let content = content(1, Some(CapturesPage { offset, limit }));
+            let res = simple(content, state.clone(), path).unwrap();
+            let res = res.results.into_iter().next().unwrap().unwrap().inner;
+            assert_eq!(vec![3], res.result);
+            let matches = res.matches.unwrap();
+            let lines = matches.iter().map(|m| m.captures[0].start_point.0);
+            lines.collect::<Vec<_>>()
+        };
+        assert_eq!(vec![3, 5], page(0, 2));
+        assert_eq!(vec![7], page(2, 2));
+        assert_eq!(Vec::<usize>::new(), page(3, 2));
+        assert_eq!(vec![5, 7], page(1, usize::MAX));
+        assert_eq!(Vec::<usize>::new(), page(usize::MAX, usize::MAX));
+    }
+
+    #[test]
+    fn test_structural_predicates() {
+        let (state, commits) = java_repo("predicates", &[&[("a/A.java", A)]]);
+        let methods = |predicate: &str| {
+            let path = Param {
+                forge: "local".to_string(),
+                user: "test".to_string(),
+                name: "predicates".to_string(),
+                commit: commits[0].clone(),
+            };
+            let content = Content {
+                query: format!("((method_declaration) @m {})", predicate),
+                ..content(1, None)
+            };
+            let res = simple(content, state.clone(), path).unwrap();
+            let res = res.results.into_iter().next().unwrap();
+            res.unwrap().inner.result
+        };
+        assert_eq!(vec![3], methods(r#"(#mcc-lt? @m "2")"#));
+        assert_eq!(vec![0], methods(r#"(#mcc-gt? @m "1")"#));
+        assert_eq!(vec![0], methods(r#"(#size-lt? @m "1")"#));
+    }
+
+    async fn query_streamed(name: &str, accept: &str) -> (Option<http::HeaderValue>, String) {
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+        let (state, commits) = java_repo(name, &[&[("a/A.java", A)], &[("a/A.java", A2)]]);
+        let app = crate::app::querying_app(state.clone()).with_state(state);
+        let req = http::Request::post(format!("/query-st/local/test/{}/{}", name, commits[1]))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .header(http::header::ACCEPT, accept)
+            .body(axum::body::Body::from(
+                serde_json::to_string(&content(2, None)).unwrap(),
+            ))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, resp.status());
+        let content_type = resp.headers().get(http::header::CONTENT_TYPE).cloned();
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        (content_type, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_streamed_ndjson() {
+        let (content_type, body) = query_streamed("ndjson", "application/x-ndjson").await;
+        assert_eq!(
+            Some("application/x-ndjson"),
+            content_type.as_ref().map(|x| x.to_str().unwrap())
+        );
+        let results = body
+            .lines()
+            .map(|x| serde_json::from_str::<serde_json::Value>(x).unwrap());
+        let counts: Vec<_> = results.map(|x| x["result"][0].as_u64()).collect();
+        assert_eq!(vec![Some(1), Some(3)], counts);
+
+        // without the ndjson Accept header, results are concatenated
+        let (content_type, body) = query_streamed("concatenated", "application/json").await;
+        assert_eq!(None, content_type);
+        assert!(!body.contains('\n'));
+        let results = serde_json::Deserializer::from_str(&body).into_iter::<serde_json::Value>();
+        assert_eq!(2, results.map(|x| x.unwrap()).count());
+    }
+}
@@ -0,0 +1,469 @@
+//! Classifies the moves of repository-level edit scripts as refactorings.
+//!
+//! Moved members and type declarations are compared to their enclosing declarations,
+//! which tells apart moves to another declaration from plain reorderings.
+//! Statements moved from an existing method to a new one are grouped as method extractions.
+//! Only Java code is considered, other moved nodes are ignored.
+
+use std::path::Path;
+
+use axum::Json;
+use serde::Serialize;
+use tokio::time::Instant;
+
+use hyper_diff::actions::action_vec::ActionsVec;
+use hyper_diff::actions::script_generator2::Act;
+use hyper_diff::decompressed_tree_store::ShallowDecompressedTreeStore;
+use hyper_diff::matchers::Mapper;
+use hyper_diff::matchers::mapping_store::MonoMappingStore;
+use hyper_diff::tree::tree_path::CompressedTreePath;
+use hyperast::position::path_with_spaces;
+use hyperast::store::defaults::NodeIdentifier;
+use hyperast::types::{HyperAST, HyperType, TypeTrait, WithChildren};
+use hyperast_gen_ts_java::types::Type;
+use hyperast_vcs_git::SimpleStores;
+use hyperast_vcs_git::git::{Oid, Repo};
+
+use crate::SharedState;
+use crate::diff::{A, ConfigQuery, Param, pre_process_pair};
+use crate::utils::{IdN, LocalPieceOfCode, PieceOfCode};
+
+type Local = LocalPieceOfCode<IdN, usize>;
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "kind")]
+pub enum Refactoring<P = PieceOfCode> {
+    /// A method or a constructor moved to another type declaration
+    MoveMethod { from: P, to: P },
+    /// A field moved to another type declaration
+    MoveField { from: P, to: P },
+    /// A type declaration moved to another file or type declaration
+    MoveClass {
+        from: P,
+        to: P,
+        /// moved to another directory
+        package_changed: bool,
+    },
+    /// Statements of a method moved to a new method
+    ExtractMethod {
+        /// the original method
+        from: P,
+        /// the new method
+        to: P,
+        /// number of moved statements
+        statements: usize,
+    },
+}
+
+impl Refactoring<Local> {
+    fn globalize(self, spec: &Repo, src: Oid, dst: Oid) -> Refactoring {
+        let g = |from: Local, to: Local| (from.globalize(spec, src), to.globalize(spec, dst));
+        match self {
+            Refactoring::MoveMethod { from, to } => {
+                let (from, to) = g(from, to);
+                Refactoring::MoveMethod { from, to }
+            }
+            Refactoring::MoveField { from, to } => {
+                let (from, to) = g(from, to);
+                Refactoring::MoveField { from, to }
+            }
+            Refactoring::MoveClass {
+                from,
+                to,
+                package_changed,
+            } => {
+                let (from, to) = g(from, to);
+                Refactoring::MoveClass {
+                    from,
+                    to,
+                    package_changed,
+                }
+            }
+            Refactoring::ExtractMethod {
+                from,
+                to,
+                statements,
+            } => {
+                let (from, to) = g(from, to);
+                Refactoring::ExtractMethod {
+                    from,
+                    to,
+                    statements,
+                }
+            }
+        }
+    }
+}
+
+pub fn refactorings(
+    state: SharedState,
+    path: Param,
+    query: ConfigQuery,
+) -> Result<Json<Vec<Refactoring>>, String> {
+    let now = Instant::now();
+    let config = crate::diff::parse_config(query.config.as_deref())?;
+    let (repo, src_oid, dst_oid) = pre_process_pair(&state, &path)?;
+
+    let repositories = state.repositories.read().unwrap();
+    let commit_src = repositories.get_commit(&repo.config, &src_oid).unwrap();
+    let src_tr = commit_src.ast_root;
+    let commit_dst = repositories.get_commit(&repo.config, &dst_oid).unwrap();
+    let dst_tr = commit_dst.ast_root;
+    if src_tr == dst_tr {
+        return Ok(Json(vec![]));
+    }
+
+    let with_spaces_stores = &repositories.processor.main_stores;
+    let stores = &hyperast_vcs_git::no_space::as_nospaces(with_spaces_stores);
+    let diff = match &config {
+        Some(config) => hyper_diff::algorithms::pipeline::diff(stores, &src_tr, &dst_tr, config)?,
+        None => hyper_diff::algorithms::gumtree_stable_hybrid_lazy::diff(stores, &src_tr, &dst_tr),
+    };
+    let actions = diff.actions.unwrap_or_default();
+    let res = classify_moves(with_spaces_stores, src_tr, dst_tr, &diff.mapper, &actions);
+    log::info!("{} refactorings found in {:?}", res.len(), now.elapsed());
+    let res = res.into_iter();
+    let res = res.map(|r| r.globalize(&repo.spec, src_oid, dst_oid));
+    Ok(Json(res.collect()))
+}
+
+/// A node reached by following the path of an action
+pub(crate) struct Located {
+    /// path without spaces, as in actions and decompressed trees
+    pub(crate) no_spaces: Vec<u16>,
+    /// path with spaces
+    pub(crate) path: Vec<u16>,
+    /// nodes along the path, starting with the root
+    pub(crate) nodes: Vec<NodeIdentifier>,
+}
+
+impl Located {
+    pub(crate) fn new(
+        stores: &SimpleStores,
+        root: NodeIdentifier,
+        path: &CompressedTreePath<u16>,
+    ) -> Self {
+        let no_spaces: Vec<u16> = path.iter().collect();
+        let (path, _) = path_with_spaces(root, &mut no_spaces.iter().copied(), stores);
+        let mut nodes = vec![root];
+        for o in &path {
+            let n = stores.node_store.resolve(*nodes.last().unwrap());
+            let Some(c) = n.child(o) else { break };
+            nodes.push(c);
+        }
+        Self {
+            no_spaces,
+            path,
+            nodes,
+        }
+    }
+
+    pub(crate) fn node(&self) -> NodeIdentifier {
+        *self.nodes.last().unwrap()
+    }
+
+    pub(crate) fn depth(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    /// the Java type of the node at `depth` along the path
+    pub(crate) fn java_type(&self, stores: &SimpleStores, depth: usize) -> Option<Type> {
+        java_type(stores, *self.nodes.get(depth)?)
+    }
+
+    /// depth of the closest strict ancestor satisfying `pred`
+    pub(crate) fn enclosing(
+        &self,
+        stores: &SimpleStores,
+        pred: impl Fn(&SimpleStores, NodeIdentifier) -> bool,
+    ) -> Option<usize> {
+        (0..self.depth())
+            .rev()
+            .find(|k| pred(stores, self.nodes[*k]))
+    }
+
+    pub(crate) fn piece(&self, stores: &SimpleStores, depth: usize) -> Local {
+        let root = self.nodes[0];
+        LocalPieceOfCode::from_root_and_offsets(stores, root, self.path[..depth].to_vec())
+    }
+}
+
+pub(crate) fn java_type(stores: &SimpleStores, id: NodeIdentifier) -> Option<Type> {
+    let t = stores.resolve_type(&id);
+    t.as_any().downcast_ref::<Type>().copied()
+}
+
+pub(crate) fn is_class(t: &Type) -> bool {
+    t.is_type_declaration() && t != &Type::EnumConstant
+}
+
+/// type declarations and files contain members
+fn is_container(stores: &SimpleStores, id: NodeIdentifier) -> bool {
+    stores.resolve_type(&id).is_file() || java_type(stores, id).is_some_and(|t| is_class(&t))
+}
+
+fn is_method(stores: &SimpleStores, id: NodeIdentifier) -> bool {
+    java_type(stores, id).is_some_and(|t| t.is_executable_member())
+}
+
+/// Groups the moves of `actions` into refactorings.
+///
+/// `stores` must contain spaces, contrary to the ones of `mapper`,
+/// and the paths of actions are expected to be the ones made by
+/// [`hyper_diff::actions::script_generator2::ScriptGenerator`].
+pub(crate) fn classify_moves<HAST, SD, DD, M>(
+    stores: &SimpleStores,
+    src_tr: NodeIdentifier,
+    dst_tr: NodeIdentifier,
+    mapper: &Mapper<HAST, SD, DD, M>,
+    actions: &ActionsVec<A>,
+) -> Vec<Refactoring<Local>>
+where
+    HAST: HyperAST,
+    M: MonoMappingStore,
+    SD: ShallowDecompressedTreeStore<HAST, M::Src>,
+    DD: ShallowDecompressedTreeStore<HAST, M::Dst>,
+{
+    let mapping = &mapper.mapping;
+    let src_at = |l: &Located, k: usize| {
+        let root = mapping.src_arena.root();
+        mapping.src_arena.child(&root, &l.no_spaces[..k])
+    };
+    let dst_at = |l: &Located, k: usize| {
+        let root = mapping.dst_arena.root();
+        mapping.dst_arena.child(&root, &l.no_spaces[..k])
+    };
+    let mut res = vec![];
+    // paths of the original and new methods of extractions, along with their index in res
+    let mut extractions: Vec<(Vec<u16>, Vec<u16>, usize)> = vec![];
+    for a in actions.iter() {
+        let from = match &a.action {
+            Act::Move { from } | Act::MovUpd { from, .. } => from,
+            _ => continue,
+        };
+        let from = Located::new(stores, src_tr, &from.ori);
+        let to = Located::new(stores, dst_tr, &a.path.ori);
+        let Some(t) = java_type(stores, from.node()) else {
+            continue;
+        };
+        if t.is_executable_member() || t.is_value_member() || is_class(&t) {
+            let (Some(cf), Some(ct)) = (
+                from.enclosing(stores, is_container),
+                to.enclosing(stores, is_container),
+            ) else {
+                continue;
+            };
+            if mapping.mappings.has(&src_at(&from, cf), &dst_at(&to, ct)) {
+                // still in the same declaration, it is just reordered
+                continue;
+            }
+            let (f, t_) = (
+                from.piece(stores, from.path.len()),
+                to.piece(stores, to.path.len()),
+            );
+            res.push(if t.is_executable_member() {
+                Refactoring::MoveMethod { from: f, to: t_ }
+            } else if t.is_value_member() {
+                Refactoring::MoveField { from: f, to: t_ }
+            } else {
+                let dir = |p: &Local| Path::new(&p.file).parent().map(|x| x.to_path_buf());
+                let package_changed = dir(&f) != dir(&t_);
+                Refactoring::MoveClass {
+                    from: f,
+                    to: t_,
+                    package_changed,
+                }
+            });
+        } else if t.is_statement() {
+            let (Some(mf), Some(mt)) = (
+                from.enclosing(stores, is_method),
+                to.enclosing(stores, is_method),
+            ) else {
+                continue;
+            };
+            // the original method remains while the other one is new
+            let still_there = mapping.mappings.is_src(&src_at(&from, mf));
+            let is_new = !mapping.mappings.is_dst(&dst_at(&to, mt));
+            if !still_there || !is_new {
+                continue;
+            }
+            let (f, t) = (&from.no_spaces[..mf], &to.no_spaces[..mt]);
+            let i = extractions.iter().find(|(f_, t_, _)| f_ == f && t_ == t);
+            if let Some((_, _, i)) = i {
+                if let Refactoring::ExtractMethod { statements, .. } = &mut res[*i] {
+                    *statements += 1;
+                }
+                continue;
+            }
+            extractions.push((f.to_vec(), t.to_vec(), res.len()));
+            res.push(Refactoring::ExtractMethod {
+                from: from.piece(stores, mf),
+                to: to.piece(stores, mt),
+                statements: 1,
+            });
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{diff_param, java_repo};
+
+    const A: &str = r#"package a;
+
+class A {
+    int counter = 0;
+
+    void run(int x) {
+        System.out.println("start " + x);
+        int y = x * 2;
+        System.out.println("double " + y);
+        System.out.println("end " + y);
+    }
+
+    void transfer(String account, long amount) {
+        if (amount < 0) {
+            throw new IllegalArgumentException("negative amount");
+        }
+        System.out.println(account + " " + amount);
+    }
+
+    static class Helper {
+        int help(int v) {
+            return v + 42;
+        }
+    }
+}
+"#;
+
+    const A2: &str = r#"package a;
+
+class A {
+    void run(int x) {
+        System.out.println("start " + x);
+        report(x);
+    }
+
+    void report(int x) {
+        int y = x * 2;
+        System.out.println("double " + y);
+        System.out.println("end " + y);
+    }
+}
+"#;
+
+    const B: &str = r#"package b;
+
+class B {
+    void other() {
+        System.out.println("other");
+    }
+}
+"#;
+
+    const B2: &str = r#"package b;
+
+class B {
+    int counter = 0;
+
+    void other() {
+        System.out.println("other");
+    }
+
+    void transfer(String account, long amount) {
+        if (amount < 0) {
+            throw new IllegalArgumentException("negative amount");
+        }
+        System.out.println(account + " " + amount);
+    }
+}
+"#;
+
+    const HELPER: &str = r#"package c;
+
+class Holder {
+    static class Helper {
+        int help(int v) {
+            return v + 42;
+        }
+    }
+}
+"#;
+
+    const R: &str = r#"package r;
+
+class R {
+    void first() {
+        System.out.println("first of R");
+    }
+
+    void second() {
+        System.out.println("second of R");
+    }
+}
+"#;
+
+    const R2: &str = r#"package r;
+
+class R {
+    void second() {
+        System.out.println("second of R");
+    }
+
+    void first() {
+        System.out.println("first of R");
+    }
+}
+"#;
+
+    fn refactorings_between(
+        name: &str,
+        v1: &[(&str, &str)],
+        v2: &[(&str, &str)],
+    ) -> Vec<Refactoring> {
+        let (state, commits) = java_repo(name, &[v1, v2]);
+        let path = diff_param(name, &commits[0], &commits[1]);
+        refactorings(state, path, Default::default()).unwrap().0
+    }
+
+    #[test]
+    fn test_classify_moves() {
+        let res = refactorings_between(
+            "moves",
+            &[
+                ("a/A.java", A),
+                ("b/B.java", B),
+                ("c/Holder.java", "package c;\n\nclass Holder {\n}\n"),
+            ],
+            &[
+                ("a/A.java", A2),
+                ("b/B.java", B2),
+                ("c/Holder.java", HELPER),
+            ],
+        );
+        let files = |from: &PieceOfCode, to: &PieceOfCode| (from.file.clone(), to.file.clone());
+        let expected = |from: &str, to: &str| (from.to_string(), to.to_string());
+        assert!(res.iter().any(|r| matches!(r,
+            Refactoring::MoveMethod { from, to } if files(from, to) == expected("a/A.java", "b/B.java")
+        )));
+        assert!(res.iter().any(|r| matches!(r,
+            Refactoring::MoveField { from, to } if files(from, to) == expected("a/A.java", "b/B.java")
+        )));
+        assert!(res.iter().any(|r| matches!(r,
+            Refactoring::MoveClass { from, to, package_changed: true }
+                if files(from, to) == expected("a/A.java", "c/Holder.java")
+        )));
+        assert!(res.iter().any(|r| matches!(r,
+            Refactoring::ExtractMethod { from, to, statements: 3 }
+                if files(from, to) == expected("a/A.java", "a/A.java")
+        )));
+    }
+
+    #[test]
+    fn test_reorder_is_not_a_refactoring() {
+        let res = refactorings_between("reorder", &[("r/R.java", R)], &[("r/R.java", R2)]);
+        assert!(res.is_empty(), "{:?}", res);
+    }
+}
@@ -28,6 +28,7 @@ use std::fmt::Display;
 
 #[derive(Deserialize, Clone)]
 pub struct ScriptingParam {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -125,7 +126,12 @@ pub fn simple_depth(
         commits,
     } = script;
     let now = Instant::now();
-    let ScriptingParam { user, name, commit } = path.clone();
+    let ScriptingParam {
+        forge,
+        user,
+        name,
+        commit,
+    } = path.clone();
     let mut engine = Engine::new();
     engine.disable_symbol("/");
     add_utils(&mut engine);
@@ -138,7 +144,9 @@ pub fn simple_depth(
     let accumulate_script = engine.compile(script.accumulate.clone()).map_err(|x| {
         ScriptingError::AtCompilation(format!("Acc: {}, {}", x, script.accumulate.clone()))
     })?;
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = state
+        .repo_spec(&forge, &user, &name)
+        .map_err(ScriptingError::Other)?;
     let repo = state
         .repositories
         .write()
@@ -210,7 +218,12 @@ fn simple_prepare(
     ),
     ScriptingError,
 > {
-    let ScriptingParam { user, name, commit } = path.clone();
+    let ScriptingParam {
+        forge,
+        user,
+        name,
+        commit,
+    } = path.clone();
     let mut engine = Engine::new();
     engine.disable_symbol("/");
     add_utils(&mut engine);
@@ -223,7 +236,9 @@ fn simple_prepare(
     let accumulate_script = engine.compile(script.accumulate.clone()).map_err(|x| {
         ScriptingError::AtCompilation(format!("Acc: {}, {}", x, script.accumulate.clone()))
     })?;
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = state
+        .repo_spec(&forge, &user, &name)
+        .map_err(ScriptingError::Other)?;
     let repo = state
         .repositories
         .write()
@@ -400,6 +415,14 @@ fn simple_aux(
                     .is_ok_and(|x| x.contains(SemFlag::HoldMavenSubModule))
             });
             let s = state.clone();
+            filter_engine.register_fn("maven_module", move || {
+                let repositories = s.repositories.read().unwrap();
+                repositories
+                    .processor
+                    .maven_module(&current)
+                    .map_or(Ok(Dynamic::UNIT), rhai::serde::to_dynamic)
+            });
+            let s = state.clone();
             filter_engine.register_fn("hold_java_folder", move || {
                 let stores = &stores!(s);
                 let node_store = &stores.node_store;
@@ -522,6 +545,14 @@ fn simple_aux(
                 .is_ok_and(|x| x.contains(SemFlag::HoldMavenSubModule))
         });
         let s = state.clone();
+        acc_engine.register_fn("maven_module", move || {
+            let repositories = s.repositories.read().unwrap();
+            repositories
+                .processor
+                .maven_module(&current)
+                .map_or(Ok(Dynamic::UNIT), rhai::serde::to_dynamic)
+        });
+        let s = state.clone();
         acc_engine.register_fn("hold_java_folder", move || {
             let stores = &stores!(s);
             let node_store = &stores.node_store;
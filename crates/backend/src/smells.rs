@@ -23,6 +23,7 @@ type Idx = u16;
 
 #[derive(Deserialize, Clone)]
 pub struct Path {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -30,8 +31,8 @@ pub struct Path {
 }
 
 impl Path {
-    pub fn repo(&self) -> hyperast_vcs_git::git::Repo {
-        hyperast_vcs_git::git::Forge::Github.repo(&self.user, &self.name)
+    pub fn repo(&self, state: &crate::AppState) -> Result<hyperast_vcs_git::git::Repo, String> {
+        state.repo_spec(&self.forge, &self.user, &self.name)
     }
 }
 
@@ -54,6 +55,7 @@ fn default_u64<const V: u64>() -> u64 {
 
 #[derive(Deserialize, Clone)]
 pub struct Diffs {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -61,8 +63,8 @@ pub struct Diffs {
 }
 
 impl Diffs {
-    pub fn repo(&self) -> hyperast_vcs_git::git::Repo {
-        hyperast_vcs_git::git::Forge::Github.repo(&self.user, &self.name)
+    pub fn repo(&self, state: &crate::AppState) -> Result<hyperast_vcs_git::git::Repo, String> {
+        state.repo_spec(&self.forge, &self.user, &self.name)
     }
 }
 
@@ -154,7 +156,7 @@ pub(crate) fn smells(
     more: More,
 ) -> Result<SearchResults, String> {
     let now = Instant::now();
-    let repo_spec = path.repo();
+    let repo_spec = path.repo(&state)?;
     let Path { commit, len, .. } = path;
     log::warn!("use len value={len}");
     let Examples {
@@ -413,7 +415,7 @@ pub(crate) fn smells_ex_from_diffs(
     path: Path,
 ) -> Result<ExamplesResults, String> {
     let now = Instant::now();
-    let repo_spec = path.repo();
+    let repo_spec = path.repo(&state)?;
     let Path { commit, len, .. } = path;
     log::warn!("use len value={len}");
     let repo_handle = (state.repositories.write().unwrap())
@@ -553,14 +555,14 @@ mod tests {
         let commit = "3d241ca0a6435cbf1fa1cdaed2af8480b99fecde";
         let language = "Java";
         let param = Path {
+            forge: "github".to_string(),
             user: user.to_string(),
             name: name.to_string(),
             commit: commit.to_string(),
             len: 1,
         };
-        let repo_spec = param.repo();
-
         let state = crate::AppState::default();
+        let repo_spec = param.repo(&state)?;
         (state.repositories.write().unwrap()).register_config(repo_spec.clone(), config);
         let state = std::sync::Arc::new(state);
 
@@ -144,7 +144,12 @@ pub(crate) fn diff(
         extract_moves2(with_spaces_stores, stores, src_tr, dst_tr, &actions).collect()
     } else if let Choice::Mov2Del = choice {
         let foc = extract_focuses(with_spaces_stores, stores, src_tr, dst_tr, &actions);
-        focuses = foc.collect();
+        // focuses only holding non-behavioral changes, e.g. comment changes, do not fix smells
+        let behavioral = behavioral_paths(with_spaces_stores, src_tr, dst_tr, &mapper, &actions);
+        let overlaps = |x: &[Idx], y: &[Idx]| x.starts_with(y) || y.starts_with(x);
+        focuses = foc
+            .filter(|(_, x)| behavioral.iter().any(|p| overlaps(p, &x.1)))
+            .collect();
         let dels = extract_deletes(with_spaces_stores, stores, src_tr, dst_tr, &actions);
         deletes = dels.map(|x| x.0).collect();
         let ins = extract_inserts(with_spaces_stores, stores, src_tr, dst_tr, &actions);
@@ -165,6 +170,31 @@ pub(crate) fn diff(
 
 pub(crate) type A = SimpleAction<LabelIdentifier, CompressedTreePath<u16>, NodeIdentifier>;
 
+/// Paths, with spaces, of the original entities changed in a way that can alter the behavior,
+/// see [`crate::change_types::ChangeType::is_behavioral`].
+fn behavioral_paths<HAST, SD, DD, M>(
+    with_spaces_stores: &hyperast::store::SimpleStores<hyperast_vcs_git::TStore>,
+    src_tr: NodeIdentifier,
+    dst_tr: NodeIdentifier,
+    mapper: &hyper_diff::matchers::Mapper<HAST, SD, DD, M>,
+    actions: &ActionsVec<A>,
+) -> Vec<Vec<Idx>>
+where
+    HAST: HyperAST,
+    M: hyper_diff::matchers::mapping_store::MonoMappingStore,
+    SD: hyper_diff::decompressed_tree_store::ShallowDecompressedTreeStore<HAST, M::Src>,
+    DD: hyper_diff::decompressed_tree_store::ShallowDecompressedTreeStore<HAST, M::Dst>,
+{
+    let changes =
+        crate::change_types::classify_actions(with_spaces_stores, src_tr, dst_tr, mapper, actions);
+    changes
+        .into_iter()
+        .filter(|x| x.change_type.is_behavioral())
+        .filter_map(|x| x.from)
+        .map(|x| x.path.into_iter().map(|o| o as Idx).collect())
+        .collect()
+}
+
 pub(crate) fn extract_moves<'a>(
     with_spaces_stores: &'a hyperast::store::SimpleStores<hyperast_vcs_git::TStore>,
     stores: &'a Stores,
@@ -0,0 +1,61 @@
+//! Fixtures for the tests of the endpoints, as repositories made on disk.
+
+use std::sync::Arc;
+
+use hyperast_vcs_git::git::Forge;
+use hyperast_vcs_git::processing::RepoConfig;
+
+use crate::{AppState, SharedState};
+
+/// Commits each version of the files, given as `(path, content)`, in a new repository,
+/// then registers it as a Java repository on the `local` forge, as `test/<name>`.
+///
+/// Returns the state along with the commits, in the order of `versions`.
+pub(crate) fn java_repo(name: &str, versions: &[&[(&str, &str)]]) -> (SharedState, Vec<String>) {
+    let dir = std::env::temp_dir().join(format!("hyperast_backend_{}", std::process::id()));
+    let path = dir.join("test").join(name);
+    let _ = std::fs::remove_dir_all(&path);
+    let repo = git2::Repository::init(&path).unwrap();
+    let sig = git2::Signature::now("test", "test@example.com").unwrap();
+    let mut commits: Vec<git2::Oid> = vec![];
+    for files in versions {
+        let mut index = repo.index().unwrap();
+        index.clear().unwrap();
+        for (file, content) in *files {
+            let file = path.join(file);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(&file, content).unwrap();
+        }
+        index
+            .add_all(
+                files.iter().map(|(file, _)| file),
+                git2::IndexAddOption::DEFAULT,
+                None,
+            )
+            .unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parents = commits.last().map(|x| repo.find_commit(*x).unwrap());
+        let parents: Vec<_> = parents.iter().collect();
+        let oid = repo
+            .commit(Some("HEAD"), &sig, &sig, "version", &tree, &parents)
+            .unwrap();
+        commits.push(oid);
+    }
+
+    let state = AppState::default();
+    let spec = Forge::Local(dir).repo("test", name);
+    (state.repositories.write().unwrap()).register_config(spec, RepoConfig::Java);
+    let commits = commits.iter().map(|x| x.to_string()).collect();
+    (Arc::new(state), commits)
+}
+
+/// The route parameters designating a pair of commits of a repository made by [`java_repo`].
+pub(crate) fn diff_param(name: &str, src: &str, dst: &str) -> crate::diff::Param {
+    crate::diff::Param {
+        forge: "local".to_string(),
+        user: "test".to_string(),
+        name: name.to_string(),
+        src: src.to_string(),
+        dst: dst.to_string(),
+    }
+}
@@ -27,6 +27,7 @@ use more::{TargetCodeElement, repo_config_error, shift_piece};
 mod my_dash;
 #[derive(serde::Deserialize, Clone, Debug)]
 pub struct TrackingParam {
+    pub forge: String,
     pub user: String,
     pub name: String,
     #[serde(deserialize_with = "string_to_oid")]
@@ -36,13 +37,14 @@ pub struct TrackingParam {
 }
 
 impl TrackingParam {
-    pub fn repo(&self) -> Repo {
-        hyperast_vcs_git::git::Forge::Github.repo(&self.user, &self.name)
+    pub fn repo(&self, state: &crate::AppState) -> Result<Repo, String> {
+        state.repo_spec(&self.forge, &self.user, &self.name)
     }
 }
 
 #[derive(serde::Deserialize, Clone, Debug)]
 pub struct TrackingAtPathParam {
+    forge: String,
     user: String,
     name: String,
     #[serde(deserialize_with = "string_to_oid")]
@@ -52,8 +54,8 @@ pub struct TrackingAtPathParam {
 }
 
 impl TrackingAtPathParam {
-    pub fn repo(&self) -> Repo {
-        hyperast_vcs_git::git::Forge::Github.repo(&self.user, &self.name)
+    pub fn repo(&self, state: &crate::AppState) -> Result<Repo, String> {
+        state.repo_spec(&self.forge, &self.user, &self.name)
     }
     pub fn path<T: std::str::FromStr>(&self) -> Vec<T> {
         (self.path.as_deref().unwrap_or_default())
@@ -124,7 +126,13 @@ decl_flags!(
     /// stops when any ancestor changes significantly,
     ///
     sim_parent => SimParent,
-    // TODO add beavior_child/parent which allow to ignore non-behavioral changes like local renames
+    /// stops when any descendant changes in a way that can alter the behavior,
+    /// ignoring e.g. comment changes or renamings of local variables,
+    /// see [`crate::change_types::ChangeType::is_behavioral`]
+    behavior_child => BehaviorChild,
+    /// stops when the parent changes in a way that can alter the behavior,
+    /// see [`crate::change_types::ChangeType::is_behavioral`]
+    behavior_parent => BehaviorParent,
     meth => Meth,
     typ => Typ,
     top => Top,
@@ -229,8 +237,11 @@ pub fn track_code(
     query: TrackingQuery,
 ) -> Result<TrackingResult<IdN, Idx>, TrackingError> {
     let now = Instant::now();
+    let repo_spec = path
+        .repo(&state)
+        .map_err(|_| repo_config_error(now))?;
     let repo_handle = (state.repositories.write().unwrap())
-        .get_config(path.repo())
+        .get_config(repo_spec)
         .ok_or_else(|| repo_config_error(now))?;
     let mut repository = repo_handle.fetch();
     log::debug!("done cloning {}", repository.spec);
@@ -270,8 +281,11 @@ pub(crate) fn track_code_at_path(
     query: TrackingQuery,
 ) -> Result<TrackingResult<IdN, Idx>, TrackingError> {
     let now = Instant::now();
+    let repo_spec = path
+        .repo(&state)
+        .map_err(|_| repo_config_error(now))?;
     let repository = (state.repositories.write().unwrap())
-        .get_config(path.repo())
+        .get_config(repo_spec)
         .ok_or_else(|| repo_config_error(now))?;
     let mut repository = repository.fetch();
     log::debug!("done cloning {}", repository.spec);
@@ -311,8 +325,11 @@ pub(crate) fn track_code_at_path_with_changes(
     query: TrackingQuery,
 ) -> Result<TrackingResultWithChanges<IdN, Idx>, TrackingError> {
     let now = Instant::now();
+    let repo_spec = path
+        .repo(&state)
+        .map_err(|_| repo_config_error(now))?;
     let repo_handle = (state.repositories.write().unwrap())
-        .get_config(path.repo())
+        .get_config(repo_spec)
         .ok_or_else(|| repo_config_error(now))?;
     let mut repository = repo_handle.fetch();
     log::debug!("done cloning {}", repository.spec);
@@ -569,6 +586,7 @@ fn track_aux(
         &repositories,
         &tracking.state.partial_decomps,
         &tracking.state.mappings_alone,
+        &tracking.state.subtree_mappings,
         &tracking.query.flags,
         &target,
         dst_tr,
@@ -662,6 +680,7 @@ fn track_at_path_aux(
         &repositories,
         &state.partial_decomps,
         &state.mappings_alone,
+        &state.subtree_mappings,
         flags,
         &target,
         dst_tr,
@@ -6,6 +6,7 @@ use hyperast::store::defaults::NodeIdentifier as IdN;
 use hyperast::types::{HyperAST, NodeStore as _, WithHashs, WithStats};
 use hyperast::{PrimInt, types};
 
+use hyper_diff::decompressed_tree_store::DecompressedTreeStore as _;
 use hyper_diff::decompressed_tree_store::DecompressedWithParent as _;
 use hyper_diff::decompressed_tree_store::LazyDecompressedTreeStore as _;
 use hyper_diff::decompressed_tree_store::{Shallow, lazy_post_order::LazyPostOrder};
@@ -19,8 +20,10 @@ use hyperast_vcs_git::processing::ConfiguredRepoTrait;
 use hyperast_vcs_git::{TStore, multi_preprocessed};
 
 use crate::MappingAloneCacheRef;
+use crate::change_types::behavioral_change;
 use crate::changes::{DstChanges, NoSpaceStore, SrcChanges};
-use crate::{MappingAloneCache, PartialDecompCache, SharedState, matching, no_space};
+use crate::{MappingAloneCache, PartialDecompCache, SharedState, SubtreeMappingCache};
+use crate::{matching, no_space};
 
 use super::more::WithPreOrderOffsetsNoSpaces;
 use super::{Flags, FlagsE, Idx, LocalPieceOfCode, MappingResult};
@@ -54,6 +57,7 @@ pub(super) fn do_tracking<P, C: Debug>(
     repositories: &multi_preprocessed::PreProcessedRepositories,
     partial_decomps: &PartialDecompCache,
     mappings_alone: &MappingAloneCache,
+    subtree_mappings_cache: &SubtreeMappingCache,
     flags: &Flags,
     target: &P,
     other_tr: IdN,
@@ -127,6 +131,7 @@ where
                 use mapping_store::DefaultMultiMappingStore as MM;
                 continue_compute_mappings_full::<_, _, MM<_>>(
                     mappings_alone,
+                    subtree_mappings_cache,
                     &mut mapper,
                     Some(subtree_mappings),
                 );
@@ -139,13 +144,19 @@ where
         use mapping_store::DefaultMultiMappingStore as MM;
         continue_compute_mappings_full::<_, _, MM<_>>(
             mappings_alone,
+            subtree_mappings_cache,
             &mut mapper,
             Some(subtree_mappings),
         )
     } else {
         use crate::changes::continue_compute_mappings_full;
         use mapping_store::DefaultMultiMappingStore as MM;
-        continue_compute_mappings_full::<_, _, MM<_>>(mappings_alone, &mut mapper, None)
+        continue_compute_mappings_full::<_, _, MM<_>>(
+            mappings_alone,
+            subtree_mappings_cache,
+            &mut mapper,
+            None,
+        )
     };
     let fuller_mappings = &fuller_mappings.1;
 
@@ -159,8 +170,15 @@ where
     if let Some(mapped) = fuller_mappings.get_dst(&mapping_target) {
         // TODO consider multimappings
         dbg!();
-        let (should_continue, mapped) =
-            track_with_mappings(&mut mapper, flags, target, mapping_target, mapped);
+        let (should_continue, mapped) = track_with_mappings(
+            with_spaces_stores,
+            &mut mapper,
+            fuller_mappings,
+            flags,
+            target,
+            mapping_target,
+            mapped,
+        );
         let mapped = postprocess_matching(reconstruct_mapped(
             with_spaces_stores,
             &mut mapper.mapping.dst_arena,
@@ -267,8 +285,10 @@ where
     postprocess_matching(fallback)
 }
 
-fn track_with_mappings<P, M: MappingStore>(
+fn track_with_mappings<P, M: MonoMappingStore>(
+    with_spaces_stores: &SimpleStores<TStore>,
     mapper: &mut MapperNos<'_, '_, M, M::Src, M::Dst>,
+    mappings: &M,
     flags: &Flags,
     target: &P,
     mapping_target: M::Src,
@@ -307,10 +327,63 @@ where
         dbg!();
         triggered |= trig_parent(mapper, flags, mapping_target, mapped);
     }
+    if flags.behavior_child {
+        flagged = true;
+        triggered |=
+            behavioral_subtree_change(with_spaces_stores, mapper, mappings, mapping_target, mapped);
+    }
+    if flags.behavior_parent {
+        flagged = true;
+        let target_parent = mapper.src_arena.parent(&mapping_target);
+        let mapped_parent = mapper.dst_arena.parent(&mapped);
+        triggered |= match (target_parent, mapped_parent) {
+            (Some(t), Some(m)) => {
+                behavioral_subtree_change(with_spaces_stores, mapper, mappings, t, m)
+            }
+            (t, m) => t.is_some() != m.is_some(),
+        };
+    }
     // TODO add flags for artefacts (tests, prod code, build, lang, misc)
     // TODO add flags for similarity comps
     (flagged && !triggered, mapped)
 }
+/// Whether the changes between the subtrees at `src` and `dst` can alter the behavior,
+/// reusing the `mappings` between the whole trees instead of diffing the subtrees again.
+fn behavioral_subtree_change<M: MonoMappingStore>(
+    with_spaces_stores: &SimpleStores<TStore>,
+    mapper: &mut MapperNos<'_, '_, M, M::Src, M::Dst>,
+    mappings: &M,
+    src: M::Src,
+    dst: M::Dst,
+) -> bool
+where
+    M::Src: PrimInt,
+    M::Dst: PrimInt + Shallow<M::Dst>,
+{
+    let dst = mapper.dst_arena.decompress_to(&dst);
+    let src_node = mapper.src_arena.original(&src);
+    let dst_node = mapper.dst_arena.original(&dst);
+    if src_node == dst_node {
+        return false;
+    }
+    // descendants are contiguous in post-order, ending with the root of the subtree
+    let src_first: usize = mapper.src_arena.first_descendant(&src).cast();
+    let dst_first: usize = mapper.dst_arena.first_descendant(&dst).cast();
+    let (src, dst): (usize, usize) = (src.cast(), dst.cast());
+    let mut sub_mappings = mapping_store::VecStore::<u32>::default();
+    sub_mappings.topit(src - src_first + 1, dst - dst_first + 1);
+    for s in src_first..=src {
+        let Some(d) = mappings.get_dst(&s.cast()) else {
+            continue;
+        };
+        let d: usize = d.cast();
+        if dst_first <= d && d <= dst {
+            sub_mappings.link((s - src_first).cast(), (d - dst_first).cast());
+        }
+    }
+    behavioral_change(with_spaces_stores, src_node, dst_node, sub_mappings)
+}
+
 fn trig_upd(
     flags: &Flags,
     target_node: IdN,
@@ -494,9 +567,9 @@ where
         + position_accessors::WithPreOrderOffsets<Idx = super::Idx>
         + WithPreOrderOffsetsNoSpaces,
 {
-    let curr_flags = FlagsE::Upd | FlagsE::Child | FlagsE::SimChild;
+    let curr_flags = FlagsE::Upd | FlagsE::Child | FlagsE::SimChild | FlagsE::BehaviorChild;
     //  | FlagsE::ExactChild
-    let parent_flags = curr_flags | FlagsE::Parent | FlagsE::SimParent;
+    let parent_flags = curr_flags | FlagsE::Parent | FlagsE::SimParent | FlagsE::BehaviorParent;
     //  | FlagsE::ExactParent
     if mappeds.is_empty() {
         // no mappeds
@@ -16,14 +16,15 @@ use crate::SharedState;
 
 #[derive(Deserialize, Clone)]
 pub struct Param {
+    forge: String,
     user: String,
     name: String,
     commit: String,
 }
 
 impl Param {
-    pub fn repo(&self) -> hyperast_vcs_git::git::Repo {
-        hyperast_vcs_git::git::Forge::Github.repo(&self.user, &self.name)
+    pub fn repo(&self, state: &crate::AppState) -> Result<hyperast_vcs_git::git::Repo, String> {
+        state.repo_spec(&self.forge, &self.user, &self.name)
     }
 }
 
@@ -74,7 +75,7 @@ pub fn simple(
     path: Param,
 ) -> Result<Json<ComputeResults>, QueryingError> {
     let now = Instant::now();
-    let repo_spec = path.repo();
+    let repo_spec = path.repo(&state).map_err(QueryingError::Preprocessing)?;
     let Param { commit, .. } = path;
     let language = query.language()?;
     let Content {
@@ -14,6 +14,7 @@ use crate::SharedState;
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct Parameters {
+    forge: String,
     user: String,
     name: String,
     commit: String,
@@ -77,13 +78,14 @@ pub struct ViewBoth {
 pub fn view(state: SharedState, path: Parameters) -> Result<Json<ViewRes>, String> {
     let now = Instant::now();
     let Parameters {
+        forge,
         user,
         name,
         commit,
         path,
     } = path;
     dbg!(&path);
-    let repo_spec = hyperast_vcs_git::git::Forge::Github.repo(user, name);
+    let repo_spec = state.repo_spec(&forge, &user, &name)?;
     let repo = state
         .repositories
         .write()
@@ -118,6 +120,49 @@ pub fn view(state: SharedState, path: Parameters) -> Result<Json<ViewRes>, Strin
     // Ok(view_res.into())
 }
 
+#[derive(Deserialize, Clone, Debug)]
+pub struct ModulesParameters {
+    forge: String,
+    user: String,
+    name: String,
+    commit: String,
+}
+
+/// The maven modules of a commit, with their submodule and dependency relations.
+pub fn modules(
+    state: SharedState,
+    path: ModulesParameters,
+) -> Result<Json<hyperast_vcs_git::maven::MavenModuleGraph>, String> {
+    let ModulesParameters {
+        forge,
+        user,
+        name,
+        commit,
+    } = path;
+    let repo_spec = state.repo_spec(&forge, &user, &name)?;
+    let repo = state
+        .repositories
+        .write()
+        .unwrap()
+        .get_config(repo_spec)
+        .ok_or_else(|| "missing config for repository".to_string())?;
+    let mut repo = repo.fetch();
+    let commits = state
+        .repositories
+        .write()
+        .unwrap()
+        .pre_process_with_limit(&mut repo, "", &commit, 1)
+        .map_err(|e| e.to_string())?;
+    let repositories = state.repositories.read().unwrap();
+    let commit_src = repositories
+        .get_commit(&repo.config, &commits[0])
+        .ok_or_else(|| "commit was not processed".to_string())?;
+    let graph = repositories
+        .processor
+        .maven_module_graph(commit_src.ast_root);
+    Ok(graph.into())
+}
+
 pub fn view_with_node_id(state: SharedState, id: u64) -> Result<Json<ViewRes>, String> {
     let now = Instant::now();
     if id == 0 {
@@ -17,6 +17,7 @@ fn test_track_at_file_pos() -> Result<(), Box<dyn std::error::Error>> {
         .unwrap()
         .register_config(Forge::Github.repo("INRIA", "spoon"), RepoConfig::JavaMaven);
     let path = TrackingParam {
+        forge: "github".to_string(),
         user: "INRIA".to_string(),
         name: "spoon".to_string(),
         commit: "5f250ead2df52d7fe26a3ed2bdd7a38355f764b1".parse().unwrap(),
@@ -45,6 +45,15 @@ impl Mcc {
         acc.value += self.value
     }
 
+    /// The accumulated value, as stored in nodes, used to persist caches
+    pub fn raw(&self) -> u32 {
+        self.value
+    }
+
+    pub fn from_raw(value: u32) -> Self {
+        Self { value }
+    }
+
     pub fn persist<K: TypeTrait>(kind: &K) -> bool {
         is_cyclomatic_persisted(kind)
     }
@@ -89,3 +89,42 @@ impl LabelStore {
         r
     }
 }
+
+impl LabelStore {
+    /// Writes labels in insertion order, so that reloading gives back the same identifiers.
+    pub fn save(&self, w: &mut impl std::io::Write) -> std::io::Result<()> {
+        w.write_all(&(self.count as u64).to_le_bytes())?;
+        w.write_all(&(self.internal.len() as u64).to_le_bytes())?;
+        for (_, s) in &self.internal {
+            w.write_all(&(s.len() as u32).to_le_bytes())?;
+            w.write_all(s.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn load(r: &mut impl std::io::Read) -> std::io::Result<Self> {
+        let mut b = [0; 8];
+        r.read_exact(&mut b)?;
+        let count = u64::from_le_bytes(b) as usize;
+        r.read_exact(&mut b)?;
+        let len = u64::from_le_bytes(b) as usize;
+        let mut internal = StringInterner::<string_interner::DefaultBackend>::with_capacity(len);
+        let mut buf = vec![];
+        for i in 0..len {
+            let mut b = [0; 4];
+            r.read_exact(&mut b)?;
+            buf.resize(u32::from_le_bytes(b) as usize, 0);
+            r.read_exact(&mut buf)?;
+            let s = std::str::from_utf8(&buf)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            let sym = internal.get_or_intern(s);
+            if sym.to_usize() != i {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "duplicated label",
+                ));
+            }
+        }
+        Ok(Self { count, internal })
+    }
+}
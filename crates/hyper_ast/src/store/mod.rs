@@ -29,6 +29,30 @@ impl<TS, NS, LS> SimpleStores<TS, NS, LS> {
     }
 }
 
+#[cfg(feature = "legion")]
+impl<TS> SimpleStores<TS, nodes::legion::NodeStore, labels::LabelStore> {
+    /// Snapshots both the labels and the nodes, see [`nodes::legion::persist`]
+    pub fn save(
+        &self,
+        registry: &nodes::legion::persist::ComponentRegistry,
+        w: &mut impl std::io::Write,
+    ) -> Result<(), nodes::legion::persist::Error> {
+        self.label_store.save(w)?;
+        self.node_store.save(registry, w)
+    }
+
+    pub fn load(
+        registry: &nodes::legion::persist::ComponentRegistry,
+        r: &mut impl std::io::Read,
+    ) -> Result<Self, nodes::legion::persist::Error> {
+        Ok(Self {
+            label_store: labels::LabelStore::load(r)?,
+            node_store: nodes::legion::NodeStore::load(registry, r)?,
+            type_store: PhantomData,
+        })
+    }
+}
+
 /// Declare we can convert from Self to T,
 /// e.g. from the git::types::TStore to the Java one, but not the contrary
 pub trait TyDown<T> {}
@@ -112,6 +112,8 @@ use super::*;
 /// A builder of entities for a archetypal store, here legion.
 pub struct BuiltEntity {
     inner: Common<fn() -> Box<dyn UnknownComponentStorage>>,
+    /// pushed instead of a newly allocated entity, used when reloading a store
+    id: Option<Entity>,
 }
 
 impl Debug for BuiltEntity {
@@ -144,7 +146,18 @@ impl EntityBuilder {
     }
 
     pub fn build(self) -> BuiltEntity {
-        BuiltEntity { inner: self.inner }
+        BuiltEntity {
+            inner: self.inner,
+            id: None,
+        }
+    }
+    /// Build an entity that will be inserted with the given `id`,
+    /// the caller is responsible for the uniqueness of `id`.
+    pub(super) fn build_with_id(self, id: Entity) -> BuiltEntity {
+        BuiltEntity {
+            inner: self.inner,
+            id: Some(id),
+        }
     }
     /// Add `component` to the entity.
     ///
@@ -237,7 +250,7 @@ impl ComponentSource for BuiltEntity {
         mut entities: impl Iterator<Item = Entity>,
     ) {
         let entity = entities.next().unwrap();
-        writer.push(entity);
+        writer.push(self.id.unwrap_or(entity));
 
         // let v = unsafe { Vec::from_raw_parts(self.inner.storage.as_ptr(), self.inner.cursor, 4) };
         // dbg!(&v);
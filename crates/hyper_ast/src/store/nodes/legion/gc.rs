@@ -0,0 +1,157 @@
+//! Mark and sweep collection of the nodes that are not reachable anymore.
+//!
+//! Subtrees are shared between commits and files,
+//! thus a node can only be freed once it is not reachable from any of the roots that are still in use,
+//! e.g. the [`NodeIdentifier`]s of the roots of commits still registered.
+//!
+//! Structures keeping [`NodeIdentifier`]s outside of the store
+//! (deduplication maps, metadata caches, cached mappings, ...)
+//! must be purged using the returned [`Reachable`] set,
+//! otherwise they would refer to freed nodes, or worse to new nodes reusing the same identifier.
+//!
+//! NOTE labels are not collected, the interner cannot release symbols.
+
+use legion::IntoQuery;
+
+use super::{DedupMap, NodeIdentifier, NodeStore, NodeStoreInner};
+use crate::store::nodes::compo;
+
+/// Nodes that survived a collection.
+#[derive(Default)]
+pub struct Reachable(std::collections::HashSet<NodeIdentifier>);
+
+impl Reachable {
+    pub fn contains(&self, id: &NodeIdentifier) -> bool {
+        self.0.contains(id)
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GcStats {
+    /// number of nodes kept
+    pub marked: usize,
+    /// number of nodes freed
+    pub swept: usize,
+}
+
+impl NodeStoreInner {
+    /// Marks the nodes reachable from `roots`, following children.
+    /// Roots that are not in the store are ignored.
+    pub fn mark(&self, roots: impl IntoIterator<Item = NodeIdentifier>) -> Reachable {
+        let mut reachable = Reachable::default();
+        let mut stack: Vec<NodeIdentifier> = roots.into_iter().collect();
+        while let Some(id) = stack.pop() {
+            if reachable.0.contains(&id) {
+                continue;
+            }
+            let Ok(e) = self.internal.entry_ref(id) else {
+                continue;
+            };
+            reachable.0.insert(id);
+            if let Ok(cs) = e.get_component::<compo::CS<NodeIdentifier>>() {
+                stack.extend(cs.0.iter().copied());
+            } else if let Ok(cs) = e.get_component::<compo::CS0<NodeIdentifier, 1>>() {
+                stack.extend(cs.0);
+            } else if let Ok(cs) = e.get_component::<compo::CS0<NodeIdentifier, 2>>() {
+                stack.extend(cs.0);
+            }
+            // NOTE no space children are a subset of children
+        }
+        reachable
+    }
+
+    /// Frees every node that is not in `reachable`.
+    pub fn sweep(&mut self, reachable: &Reachable) -> usize {
+        let mut query = <legion::Entity>::query();
+        let dead: Vec<NodeIdentifier> = query
+            .iter(&self.internal)
+            .filter(|id| !reachable.contains(id))
+            .copied()
+            .collect();
+        for id in &dead {
+            self.internal.remove(*id);
+        }
+        dead.len()
+    }
+
+    /// Only keeps the nodes reachable from `roots`.
+    ///
+    /// Deduplication maps are not handled here, see [`NodeStore::collect_garbage`] and [`DedupMap::retain_reachable`].
+    pub fn collect_garbage(
+        &mut self,
+        roots: impl IntoIterator<Item = NodeIdentifier>,
+    ) -> (Reachable, GcStats) {
+        let reachable = self.mark(roots);
+        let swept = self.sweep(&reachable);
+        let stats = GcStats {
+            marked: reachable.len(),
+            swept,
+        };
+        log::debug!("node store gc: {:?}", stats);
+        (reachable, stats)
+    }
+}
+
+impl NodeStore {
+    /// Only keeps the nodes reachable from `roots`, also purging the deduplication map.
+    pub fn collect_garbage(
+        &mut self,
+        roots: impl IntoIterator<Item = NodeIdentifier>,
+    ) -> (Reachable, GcStats) {
+        let (reachable, stats) = self.inner.collect_garbage(roots);
+        self.dedup.retain(|id, _| reachable.contains(id));
+        (reachable, stats)
+    }
+}
+
+impl DedupMap {
+    pub fn retain_reachable(&mut self, reachable: &Reachable) {
+        self.0.retain(|id, _| reachable.contains(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::nodes::EntityBuilder as _;
+    use crate::store::nodes::legion::dyn_builder::EntityBuilder;
+
+    fn insert(store: &mut NodeStoreInner, cs: &[NodeIdentifier]) -> NodeIdentifier {
+        let mut builder = EntityBuilder::new();
+        match cs {
+            [] => (),
+            [a] => {
+                builder.add(compo::CS0([*a]));
+            }
+            [a, b] => {
+                builder.add(compo::CS0([*a, *b]));
+            }
+            cs => {
+                builder.add(compo::CS(cs.into()));
+            }
+        }
+        store.internal.extend(builder.build())[0]
+    }
+
+    #[test]
+    fn keeps_shared_subtrees() {
+        let mut store = NodeStoreInner::default();
+        let leaf = insert(&mut store, &[]);
+        let other = insert(&mut store, &[]);
+        let a = insert(&mut store, &[leaf]);
+        let b = insert(&mut store, &[leaf, other]);
+        let c = insert(&mut store, &[a, b, other]);
+        let (reachable, stats) = store.collect_garbage([a]);
+        assert_eq!(stats, GcStats { marked: 2, swept: 3 });
+        assert!(reachable.contains(&leaf));
+        assert!(!reachable.contains(&c));
+        assert_eq!(store.len(), 2);
+        assert!(store.internal.entry_ref(b).is_err());
+    }
+}
@@ -9,7 +9,9 @@ use legion::{
 use std::{fmt::Debug, hash::Hash, ops::Deref};
 
 pub mod dyn_builder;
+pub mod gc;
 mod elem;
+pub mod persist;
 pub use elem::{EntryRef, HashedNode, HashedNodeRef, NodeIdentifier};
 
 pub struct NodeStore<I = NodeStoreInner, D = hashbrown::HashMap<NodeIdentifier, (), ()>> {
@@ -0,0 +1,742 @@
+//! Snapshotting of the legion backed node store.
+//!
+//! Nodes are written with their [`NodeIdentifier`] and reloaded under the same identifier,
+//! so that ids kept outside of the store (cached mappings, ids handed to clients, commit roots, ...)
+//! stay valid across restarts.
+//!
+//! Components are erased in the world, thus each persisted component type must be registered in a [`ComponentRegistry`].
+//! The registry comes with the components defined in this crate,
+//! language crates register their own components, e.g. their type component and language marker.
+//!
+//! ```ignore
+//! let mut registry = ComponentRegistry::new();
+//! registry.register_lang::<hyperast_gen_ts_java::types::Lang>();
+//! registry.register_type::<hyperast_gen_ts_java::types::Lang>();
+//! stores.save(&registry, &mut file)?;
+//! // later on
+//! let stores = SimpleStores::load(&registry, &mut file)?;
+//! ```
+//!
+//! NOTE the format is not portable, it targets reloading on the same platform with the same build.
+
+use std::{
+    io::{Read, Write},
+    mem::size_of,
+};
+
+use legion::{
+    IntoQuery,
+    storage::{Component, ComponentTypeId},
+};
+
+use super::{
+    DedupMap, EntryRef, HashedNodeRef, NodeIdentifier, NodeStore, NodeStoreInner,
+    dyn_builder::EntityBuilder,
+};
+use crate::{
+    compat::HashMap,
+    store::{defaults::LabelIdentifier, nodes::compo},
+    types::Role,
+    utils::make_hash,
+};
+use string_interner::Symbol as _;
+
+const MAGIC: &[u8; 8] = b"HYPERAST";
+/// Bumped each time the layout of snapshots changes.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    BadHeader,
+    UnsupportedVersion(u32),
+    /// A node holds a component that was not registered, it cannot be saved.
+    UnregisteredComponent(String),
+    /// The snapshot contains a component that is not registered, it cannot be loaded.
+    UnknownComponent(String),
+    Corrupted(&'static str),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "io error: {}", e),
+            Error::BadHeader => write!(f, "not a hyperast snapshot"),
+            Error::UnsupportedVersion(v) => {
+                write!(f, "unsupported snapshot version {} (expected {})", v, FORMAT_VERSION)
+            }
+            Error::UnregisteredComponent(n) => write!(f, "component {} is not registered", n),
+            Error::UnknownComponent(n) => write!(f, "snapshot component {} is not registered", n),
+            Error::Corrupted(w) => write!(f, "corrupted snapshot: {}", w),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A component that can be written to a snapshot.
+pub trait PersistedCompo: Component + Sized {
+    fn write(&self, out: &mut Vec<u8>);
+    fn read(bytes: &[u8]) -> Option<Self>;
+}
+
+type Writer = fn(&EntryRef<'_>, &mut Vec<u8>);
+type Reader = fn(&[u8], &mut EntityBuilder) -> Result<(), Error>;
+
+struct Registered {
+    name: &'static str,
+    write: Writer,
+    read: Reader,
+}
+
+/// Maps component types to their (de)serialization functions.
+pub struct ComponentRegistry {
+    by_type: HashMap<ComponentTypeId, usize>,
+    by_name: HashMap<&'static str, usize>,
+    compos: Vec<Registered>,
+}
+
+impl Default for ComponentRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ComponentRegistry {
+    /// Creates a registry containing the components defined in this crate.
+    pub fn new() -> Self {
+        let mut r = Self::empty();
+        r.register::<compo::Size>()
+            .register::<compo::SizeNoSpaces>()
+            .register::<compo::Height>()
+            .register::<compo::BytesLen>()
+            .register::<compo::LineCount>()
+            .register::<compo::VizCsCount>()
+            .register::<compo::HStruct>()
+            .register::<compo::HLabel>()
+            .register::<compo::StmtCount>()
+            .register::<compo::MemberImportCount>()
+            .register::<compo::LeafCount>()
+            .register::<compo::PrecompFlag>()
+            .register::<compo::Precomp<crate::tree_gen::PrecompQueries>>()
+            .register::<compo::RoleOffsets>()
+            .register::<compo::Roles<Role>>()
+            .register::<compo::CS<NodeIdentifier>>()
+            .register::<compo::CS<LabelIdentifier>>()
+            .register::<compo::NoSpacesCS<NodeIdentifier>>()
+            .register::<compo::CS0<NodeIdentifier, 1>>()
+            .register::<compo::CS0<NodeIdentifier, 2>>()
+            .register::<compo::NoSpacesCS0<NodeIdentifier, 1>>()
+            .register::<compo::NoSpacesCS0<NodeIdentifier, 2>>()
+            .register::<crate::hashed::SyntaxNodeHashs<u32>>()
+            .register::<LabelIdentifier>();
+        // SAFETY: plain data, without pointers
+        unsafe {
+            use crate::filter::{Bloom, BloomSize};
+            r.register_raw::<BloomSize>()
+                .register_raw::<crate::cyclomatic::Mcc>()
+                .register_raw::<Bloom<&'static [u8], u16>>()
+                .register_raw::<Bloom<&'static [u8], u32>>()
+                .register_raw::<Bloom<&'static [u8], u64>>()
+                .register_raw::<Bloom<&'static [u8], [u64; 2]>>()
+                .register_raw::<Bloom<&'static [u8], [u64; 4]>>()
+                .register_raw::<Bloom<&'static [u8], [u64; 8]>>()
+                .register_raw::<Bloom<&'static [u8], [u64; 16]>>()
+                .register_raw::<Bloom<&'static [u8], [u64; 32]>>()
+                .register_raw::<Bloom<&'static [u8], [u64; 64]>>();
+        }
+        r
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            by_type: Default::default(),
+            by_name: Default::default(),
+            compos: vec![],
+        }
+    }
+
+    pub fn register<T: PersistedCompo>(&mut self) -> &mut Self {
+        self.insert::<T>(
+            |e, out| e.get_component::<T>().unwrap().write(out),
+            |bytes, builder| {
+                let c = T::read(bytes).ok_or(Error::Corrupted(std::any::type_name::<T>()))?;
+                builder._add(c);
+                Ok(())
+            },
+        )
+    }
+
+    /// Registers a component persisted as its raw bytes.
+    ///
+    /// # Safety
+    /// `T` must be plain data, i.e. it must not contain pointers or references.
+    pub unsafe fn register_raw<T: Component>(&mut self) -> &mut Self {
+        self.insert::<T>(
+            |e, out| {
+                let c: &T = e.get_component::<T>().unwrap();
+                let ptr = c as *const T as *const u8;
+                out.extend_from_slice(unsafe { std::slice::from_raw_parts(ptr, size_of::<T>()) })
+            },
+            |bytes, builder| {
+                if bytes.len() != size_of::<T>() {
+                    return Err(Error::Corrupted(std::any::type_name::<T>()));
+                }
+                let c = unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const T) };
+                builder._add(c);
+                Ok(())
+            },
+        )
+    }
+
+    /// Registers a language marker, see [`EntityBuilder::with_lang`].
+    pub fn register_lang<L: Component>(&mut self) -> &mut Self {
+        assert_eq!(size_of::<L>(), 0);
+        // SAFETY: zero sized
+        unsafe { self.register_raw::<L>() }
+    }
+
+    /// Registers the type component of a language.
+    pub fn register_type<L>(&mut self) -> &mut Self
+    where
+        L: crate::types::LLang<crate::types::TypeU16<L>, I = u16> + 'static,
+    {
+        // SAFETY: a u16 and a marker
+        unsafe { self.register_raw::<crate::types::TypeU16<L>>() }
+    }
+
+    fn insert<T: Component>(&mut self, write: Writer, read: Reader) -> &mut Self {
+        let name = std::any::type_name::<T>();
+        let i = self.compos.len();
+        if self.by_type.insert(ComponentTypeId::of::<T>(), i).is_some() {
+            log::warn!("{} registered multiple times", name);
+        }
+        self.by_name.insert(name, i);
+        self.compos.push(Registered { name, write, read });
+        self
+    }
+}
+
+impl NodeStoreInner {
+    /// Writes all the nodes of the store.
+    pub fn save(&self, registry: &ComponentRegistry, w: &mut impl Write) -> Result<(), Error> {
+        write_header(w)?;
+        write_u64(w, self.count as u64)?;
+        write_u64(w, self.errors as u64)?;
+        write_u32(w, registry.compos.len() as u32)?;
+        for c in &registry.compos {
+            write_bytes(w, c.name.as_bytes())?;
+        }
+        let mut query = <legion::Entity>::query();
+        let entities: Vec<_> = query.iter(&self.internal).copied().collect();
+        write_u64(w, entities.len() as u64)?;
+        let mut buf = vec![];
+        for id in entities {
+            let e = self.internal.entry_ref(id).unwrap();
+            let types = e.archetype().layout().component_types();
+            write_u64(w, id_to_u64(id))?;
+            write_u32(w, types.len() as u32)?;
+            for ty in types {
+                let Some(&i) = registry.by_type.get(ty) else {
+                    return Err(Error::UnregisteredComponent(format!("{:?}", ty)));
+                };
+                buf.clear();
+                (registry.compos[i].write)(&e, &mut buf);
+                write_u32(w, i as u32)?;
+                write_bytes(w, &buf)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads nodes written with [`NodeStoreInner::save`],
+    /// each node gets back the identifier it had when saved.
+    pub fn load(registry: &ComponentRegistry, r: &mut impl Read) -> Result<Self, Error> {
+        read_header(r)?;
+        let mut store = Self::default();
+        store.count = read_u64_from(r)? as usize;
+        store.errors = read_u64_from(r)? as usize;
+        let table_len = read_u32_from(r)?;
+        let mut table = Vec::with_capacity(table_len as usize);
+        for _ in 0..table_len {
+            let name = String::from_utf8(read_bytes(r)?)
+                .map_err(|_| Error::Corrupted("component name"))?;
+            table.push(registry.by_name.get(name.as_str()).copied().ok_or(name));
+        }
+        let len = read_u64_from(r)?;
+        let mut max_id = 0;
+        let mut buf = vec![];
+        for _ in 0..len {
+            let id = read_u64_from(r)?;
+            max_id = max_id.max(id);
+            let n = read_u32_from(r)?;
+            let mut builder = EntityBuilder::new();
+            for _ in 0..n {
+                let i = read_u32_from(r)? as usize;
+                read_bytes_into(r, &mut buf)?;
+                let i = match table.get(i) {
+                    Some(Ok(i)) => *i,
+                    Some(Err(name)) => return Err(Error::UnknownComponent(name.clone())),
+                    None => return Err(Error::Corrupted("component index")),
+                };
+                (registry.compos[i].read)(&buf, &mut builder)?;
+            }
+            let id = id_from_u64(id).ok_or(Error::Corrupted("node identifier"))?;
+            store.internal.extend(builder.build_with_id(id));
+        }
+        reserve_ids_up_to(max_id);
+        Ok(store)
+    }
+
+    /// Writes the identifiers of the nodes deduplicated with `dedup`.
+    pub fn save_dedup(
+        &self,
+        dedup: &hashbrown::HashMap<NodeIdentifier, (), ()>,
+        w: &mut impl Write,
+    ) -> Result<(), Error> {
+        write_u64(w, dedup.len() as u64)?;
+        for id in dedup.keys() {
+            write_u64(w, id_to_u64(*id))?;
+        }
+        Ok(())
+    }
+
+    /// Rebuilds a deduplication map written with [`NodeStoreInner::save_dedup`],
+    /// hashes are recomputed from the nodes already loaded in the store.
+    pub fn load_dedup(
+        &self,
+        r: &mut impl Read,
+    ) -> Result<hashbrown::HashMap<NodeIdentifier, (), ()>, Error> {
+        let len = read_u64_from(r)? as usize;
+        let mut dedup = hashbrown::HashMap::<_, (), ()>::with_capacity_and_hasher(len, ());
+        for _ in 0..len {
+            let id = id_from_u64(read_u64_from(r)?).ok_or(Error::Corrupted("node identifier"))?;
+            let hash = self.hash_of(id)?;
+            let entry = dedup.raw_entry_mut().from_hash(hash, |x| *x == id);
+            if let hashbrown::hash_map::RawEntryMut::Vacant(vacant) = entry {
+                vacant.insert_with_hasher(hash, id, (), |id| self.hash_of(*id).unwrap());
+            }
+        }
+        Ok(dedup)
+    }
+
+    fn hash_of(&self, id: NodeIdentifier) -> Result<u64, Error> {
+        let node: HashedNodeRef<'_, NodeIdentifier> = self
+            .internal
+            .entry_ref(id)
+            .map(HashedNodeRef::new)
+            .map_err(|_| Error::Corrupted("dangling identifier in dedup"))?;
+        Ok(make_hash(&self.hasher, &node))
+    }
+}
+
+impl DedupMap {
+    pub fn save(&self, store: &NodeStoreInner, w: &mut impl Write) -> Result<(), Error> {
+        store.save_dedup(&self.0, w)
+    }
+    pub fn load(store: &NodeStoreInner, r: &mut impl Read) -> Result<Self, Error> {
+        store.load_dedup(r).map(DedupMap)
+    }
+}
+
+impl NodeStore {
+    pub fn save(&self, registry: &ComponentRegistry, w: &mut impl Write) -> Result<(), Error> {
+        self.inner.save(registry, w)?;
+        self.inner.save_dedup(&self.dedup, w)
+    }
+    pub fn load(registry: &ComponentRegistry, r: &mut impl Read) -> Result<Self, Error> {
+        let inner = NodeStoreInner::load(registry, r)?;
+        let dedup = inner.load_dedup(r)?;
+        Ok(Self { dedup, inner })
+    }
+}
+
+/// Makes sure that nodes inserted after a load do not reuse loaded identifiers.
+fn reserve_ids_up_to(max: u64) {
+    let mut alloc = legion::world::Allocate::new();
+    while let Some(e) = alloc.next() {
+        if id_to_u64(e) >= max {
+            break;
+        }
+    }
+}
+
+pub fn id_to_u64(id: NodeIdentifier) -> u64 {
+    unsafe { std::mem::transmute(id) }
+}
+
+pub fn id_from_u64(id: u64) -> Option<NodeIdentifier> {
+    // NodeIdentifier is a non zero u64
+    (id != 0).then(|| unsafe { std::mem::transmute(id) })
+}
+
+fn write_header(w: &mut impl Write) -> std::io::Result<()> {
+    w.write_all(MAGIC)?;
+    write_u32(w, FORMAT_VERSION)?;
+    w.write_all(&[cfg!(target_endian = "little") as u8, size_of::<usize>() as u8])
+}
+
+fn read_header(r: &mut impl Read) -> Result<(), Error> {
+    let mut magic = [0; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(Error::BadHeader);
+    }
+    let version = read_u32_from(r)?;
+    if version != FORMAT_VERSION {
+        return Err(Error::UnsupportedVersion(version));
+    }
+    let mut platform = [0; 2];
+    r.read_exact(&mut platform)?;
+    if platform != [cfg!(target_endian = "little") as u8, size_of::<usize>() as u8] {
+        return Err(Error::BadHeader);
+    }
+    Ok(())
+}
+
+pub fn write_u32(w: &mut impl Write, x: u32) -> std::io::Result<()> {
+    w.write_all(&x.to_le_bytes())
+}
+
+pub fn write_u64(w: &mut impl Write, x: u64) -> std::io::Result<()> {
+    w.write_all(&x.to_le_bytes())
+}
+
+pub fn write_bytes(w: &mut impl Write, x: &[u8]) -> std::io::Result<()> {
+    write_u32(w, x.len() as u32)?;
+    w.write_all(x)
+}
+
+pub fn read_u32_from(r: &mut impl Read) -> std::io::Result<u32> {
+    let mut b = [0; 4];
+    r.read_exact(&mut b)?;
+    Ok(u32::from_le_bytes(b))
+}
+
+pub fn read_u64_from(r: &mut impl Read) -> std::io::Result<u64> {
+    let mut b = [0; 8];
+    r.read_exact(&mut b)?;
+    Ok(u64::from_le_bytes(b))
+}
+
+pub fn read_bytes(r: &mut impl Read) -> std::io::Result<Vec<u8>> {
+    let mut v = vec![];
+    read_bytes_into(r, &mut v)?;
+    Ok(v)
+}
+
+/// Writes the metrics that generators keep in their caches.
+pub fn write_metrics(
+    w: &mut impl Write,
+    m: &crate::tree_gen::SubTreeMetrics<crate::hashed::SyntaxNodeHashs<u32>>,
+) -> std::io::Result<()> {
+    write_u32(w, m.hashs.structt)?;
+    write_u32(w, m.hashs.label)?;
+    write_u32(w, m.hashs.syntax)?;
+    write_u32(w, m.size)?;
+    write_u32(w, m.height)?;
+    write_u32(w, m.size_no_spaces)?;
+    write_u32(w, m.line_count)
+}
+
+pub fn read_metrics(
+    r: &mut impl Read,
+) -> std::io::Result<crate::tree_gen::SubTreeMetrics<crate::hashed::SyntaxNodeHashs<u32>>> {
+    Ok(crate::tree_gen::SubTreeMetrics {
+        hashs: crate::hashed::SyntaxNodeHashs {
+            structt: read_u32_from(r)?,
+            label: read_u32_from(r)?,
+            syntax: read_u32_from(r)?,
+        },
+        size: read_u32_from(r)?,
+        height: read_u32_from(r)?,
+        size_no_spaces: read_u32_from(r)?,
+        line_count: read_u32_from(r)?,
+    })
+}
+
+pub fn write_id(w: &mut impl Write, id: NodeIdentifier) -> std::io::Result<()> {
+    write_u64(w, id_to_u64(id))
+}
+
+pub fn read_id(r: &mut impl Read) -> std::io::Result<NodeIdentifier> {
+    id_from_u64(read_u64_from(r)?).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, "null node identifier")
+    })
+}
+
+pub fn role_from_u8(x: u8) -> Option<Role> {
+    // SAFETY: Role is repr(u8) and x is a valid discriminant
+    ((x as usize) < Role::len()).then(|| unsafe { std::mem::transmute::<u8, Role>(x) })
+}
+
+fn read_bytes_into(r: &mut impl Read, buf: &mut Vec<u8>) -> std::io::Result<()> {
+    let len = read_u32_from(r)? as usize;
+    buf.clear();
+    buf.resize(len, 0);
+    r.read_exact(buf)
+}
+
+fn read_u16(bytes: &[u8]) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8]) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_ids(bytes: &[u8]) -> Option<Box<[NodeIdentifier]>> {
+    if bytes.len() % 8 != 0 {
+        return None;
+    }
+    bytes
+        .chunks_exact(8)
+        .map(|x| id_from_u64(u64::from_le_bytes(x.try_into().unwrap())))
+        .collect()
+}
+
+fn write_ids(ids: &[NodeIdentifier], out: &mut Vec<u8>) {
+    for id in ids {
+        out.extend_from_slice(&id_to_u64(*id).to_le_bytes())
+    }
+}
+
+macro_rules! persisted_u32 {
+    ($($Ty:ty),* $(,)?) => {$(
+        impl PersistedCompo for $Ty {
+            fn write(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.0.to_le_bytes())
+            }
+            fn read(bytes: &[u8]) -> Option<Self> {
+                read_u32(bytes).map(Self)
+            }
+        }
+    )*};
+}
+
+persisted_u32!(
+    compo::Size,
+    compo::SizeNoSpaces,
+    compo::Height,
+    compo::BytesLen,
+    compo::LineCount,
+    compo::VizCsCount,
+    compo::HStruct,
+    compo::HLabel,
+);
+
+macro_rules! persisted_u8 {
+    ($($Ty:ty),* $(,)?) => {$(
+        impl PersistedCompo for $Ty {
+            fn write(&self, out: &mut Vec<u8>) {
+                out.push(self.0)
+            }
+            fn read(bytes: &[u8]) -> Option<Self> {
+                match bytes {
+                    [x] => Some(Self(*x)),
+                    _ => None,
+                }
+            }
+        }
+    )*};
+}
+
+persisted_u8!(
+    compo::StmtCount,
+    compo::MemberImportCount,
+    compo::LeafCount
+);
+
+impl PersistedCompo for compo::PrecompFlag {
+    fn write(&self, _out: &mut Vec<u8>) {}
+    fn read(bytes: &[u8]) -> Option<Self> {
+        bytes.is_empty().then_some(Self)
+    }
+}
+
+impl PersistedCompo for compo::Precomp<u16> {
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.0.to_le_bytes())
+    }
+    fn read(bytes: &[u8]) -> Option<Self> {
+        read_u16(bytes).map(Self)
+    }
+}
+
+impl PersistedCompo for compo::RoleOffsets {
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.0)
+    }
+    fn read(bytes: &[u8]) -> Option<Self> {
+        Some(Self(bytes.into()))
+    }
+}
+
+impl PersistedCompo for compo::Roles<Role> {
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend(self.0.iter().map(|r| *r as u8))
+    }
+    fn read(bytes: &[u8]) -> Option<Self> {
+        bytes
+            .iter()
+            .map(|x| role_from_u8(*x))
+            .collect::<Option<_>>()
+            .map(Self)
+    }
+}
+
+impl PersistedCompo for compo::CS<NodeIdentifier> {
+    fn write(&self, out: &mut Vec<u8>) {
+        write_ids(&self.0, out)
+    }
+    fn read(bytes: &[u8]) -> Option<Self> {
+        read_ids(bytes).map(Self)
+    }
+}
+
+impl PersistedCompo for compo::NoSpacesCS<NodeIdentifier> {
+    fn write(&self, out: &mut Vec<u8>) {
+        write_ids(&self.0, out)
+    }
+    fn read(bytes: &[u8]) -> Option<Self> {
+        read_ids(bytes).map(Self)
+    }
+}
+
+impl<const N: usize> PersistedCompo for compo::CS0<NodeIdentifier, N> {
+    fn write(&self, out: &mut Vec<u8>) {
+        write_ids(&self.0, out)
+    }
+    fn read(bytes: &[u8]) -> Option<Self> {
+        let ids = read_ids(bytes)?;
+        Some(Self(<[_; N]>::try_from(&ids[..]).ok()?))
+    }
+}
+
+impl<const N: usize> PersistedCompo for compo::NoSpacesCS0<NodeIdentifier, N> {
+    fn write(&self, out: &mut Vec<u8>) {
+        write_ids(&self.0, out)
+    }
+    fn read(bytes: &[u8]) -> Option<Self> {
+        let ids = read_ids(bytes)?;
+        Some(Self(<[_; N]>::try_from(&ids[..]).ok()?))
+    }
+}
+
+impl PersistedCompo for compo::CS<LabelIdentifier> {
+    fn write(&self, out: &mut Vec<u8>) {
+        for l in self.0.iter() {
+            out.extend_from_slice(&(l.0.to_usize() as u32).to_le_bytes())
+        }
+    }
+    fn read(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() % 4 != 0 {
+            return None;
+        }
+        bytes
+            .chunks_exact(4)
+            .map(|x| crate::store::labels::label_id_from_usize(read_u32(x)? as usize))
+            .collect::<Option<_>>()
+            .map(Self)
+    }
+}
+
+impl PersistedCompo for LabelIdentifier {
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&(self.0.to_usize() as u32).to_le_bytes())
+    }
+    fn read(bytes: &[u8]) -> Option<Self> {
+        crate::store::labels::label_id_from_usize(read_u32(bytes)? as usize)
+    }
+}
+
+impl PersistedCompo for crate::hashed::SyntaxNodeHashs<u32> {
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.structt.to_le_bytes());
+        out.extend_from_slice(&self.label.to_le_bytes());
+        out.extend_from_slice(&self.syntax.to_le_bytes());
+    }
+    fn read(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 12 {
+            return None;
+        }
+        Some(Self {
+            structt: read_u32(&bytes[0..4])?,
+            label: read_u32(&bytes[4..8])?,
+            syntax: read_u32(&bytes[8..12])?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::nodes::EntityBuilder as _;
+
+    #[test]
+    fn roundtrip_keeps_identifiers() {
+        let mut store = NodeStore::new();
+        let ids: Vec<_> = (0..3u32)
+            .map(|i| {
+                let mut builder = EntityBuilder::new();
+                builder.add(compo::Size(i + 1));
+                builder.add(crate::hashed::SyntaxNodeHashs {
+                    structt: i,
+                    label: i,
+                    syntax: i,
+                });
+                store.inner.internal.extend(builder.build())[0]
+            })
+            .collect();
+        let mut registry = ComponentRegistry::empty();
+        registry
+            .register::<compo::Size>()
+            .register::<crate::hashed::SyntaxNodeHashs<u32>>();
+
+        let mut buf = vec![];
+        store.save(&registry, &mut buf).unwrap();
+        let loaded = NodeStore::load(&registry, &mut buf.as_slice()).unwrap();
+
+        assert_eq!(loaded.len(), ids.len());
+        for (i, id) in ids.into_iter().enumerate() {
+            let e = loaded.inner.internal.entry_ref(id).unwrap();
+            assert_eq!(e.get_component::<compo::Size>().unwrap().0, i as u32 + 1);
+        }
+        let mut other = EntityBuilder::new();
+        other.add(compo::Size(0));
+        let mut loaded = loaded;
+        let new = loaded.inner.internal.extend(other.build())[0];
+        assert_eq!(loaded.len(), 4, "{:?} should not override a loaded node", new);
+    }
+
+    #[test]
+    fn unregistered_component() {
+        let mut store = NodeStore::new();
+        let mut builder = EntityBuilder::new();
+        builder.add(compo::Height(1));
+        store.inner.internal.extend(builder.build());
+        let registry = ComponentRegistry::empty();
+        let r = store.save(&registry, &mut vec![]);
+        assert!(matches!(r, Err(Error::UnregisteredComponent(_))));
+    }
+
+    #[test]
+    fn labels_roundtrip() {
+        use crate::types::LabelStore as _;
+        let mut labels = crate::store::labels::LabelStore::new();
+        let a = labels.get_or_insert("a");
+        let b = labels.get_or_insert("b");
+        let mut buf = vec![];
+        labels.save(&mut buf).unwrap();
+        let loaded = crate::store::labels::LabelStore::load(&mut buf.as_slice()).unwrap();
+        assert_eq!(loaded.resolve(&a), "a");
+        assert_eq!(loaded.resolve(&b), "b");
+        assert_eq!(loaded.get("b"), Some(b));
+    }
+}
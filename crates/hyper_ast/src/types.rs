@@ -214,6 +214,32 @@ role_impl!(
     TemplateParameters => "template_parameters",
     // Operator => "operator",
     // Condition => "condition",
+
+    // // Python
+    Alias => "alias",
+    Attribute => "attribute",
+    Cause => "cause",
+    Code => "code",
+    Definition => "definition",
+    Expression => "expression",
+    FormatSpecifier => "format_specifier",
+    Guard => "guard",
+    ModuleName => "module_name",
+    Operators => "operators",
+    ReturnType => "return_type",
+    Subject => "subject",
+    Subscript => "subscript",
+    Superclasses => "superclasses",
+    TypeConversion => "type_conversion",
+
+    // // Rust
+    Bounds => "bounds",
+    Doc => "doc",
+    Inner => "inner",
+    List => "list",
+    Macro => "macro",
+    Outer => "outer",
+    Trait => "trait",
     End => "end",
 );
 
@@ -200,6 +200,12 @@ impl fmt::Display for Bytes {
     }
 }
 
+impl From<isize> for Bytes {
+    fn from(val: isize) -> Self {
+        Bytes(val)
+    }
+}
+
 impl From<&Bytes> for isize {
     fn from(val: &Bytes) -> Self {
         val.0
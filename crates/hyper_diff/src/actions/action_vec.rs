@@ -1,9 +1,11 @@
 use std::fmt::Debug;
+use std::marker::PhantomData;
 
+use hyperast::PrimInt;
 use hyperast::types::HyperAST;
 use hyperast::types::NodeId;
 use hyperast::types::NodeStoreExt;
-use hyperast::types::{HyperType, Labeled, LendT};
+use hyperast::types::{HyperType, Labeled, LendN, LendT};
 use hyperast::types::{LabelStore, NodeStore};
 use hyperast::types::{WithSerialization, WithStats};
 
@@ -326,13 +328,13 @@ pub fn apply_actions<T, S, P>(
     T::Label: Debug + Copy,
     T::TreeId: Debug + Copy + NodeId<IdN = T::TreeId>,
     T::ChildIdx: Debug + Copy,
-    S: NodeStoreExt<T> + NodeStore<T::TreeId>, //NodeStoreExt<'a, T, R>,
-                                               // for<'d> S::R<'d>: hyperast::types::TypedTree<
-                                               //     TreeId = T::TreeId,
-                                               //     Type = T::Type,
-                                               //     Label = T::Label,
-                                               //     ChildIdx = T::ChildIdx,
-                                               // >,
+    S: NodeStoreExt<T> + NodeStore<T::TreeId>,
+    for<'t> LendN<'t, S, T::TreeId>: hyperast::types::TypedTree<
+            TreeId = T::TreeId,
+            Type = T::Type,
+            Label = T::Label,
+            ChildIdx = T::ChildIdx,
+        >,
 {
     for a in actions.iter() {
         // *log::debug!(
@@ -346,7 +348,7 @@ pub fn apply_actions<T, S, P>(
 pub fn apply_action<T, S, P>(
     a: &SimpleAction<T::Label, P, T::TreeId>,
     root: &'_ mut Vec<T::TreeId>,
-    _s: &'_ mut S,
+    s: &'_ mut S,
 ) where
     S: NodeStoreExt<T> + NodeStore<T::TreeId>,
     T: hyperast::types::TypedTree,
@@ -355,246 +357,212 @@ pub fn apply_action<T, S, P>(
     T::TreeId: Debug + Copy + NodeId<IdN = T::TreeId>,
     T::ChildIdx: Debug + Copy,
     P: TreePath<Item = T::ChildIdx> + Debug,
+    for<'t> LendN<'t, S, T::TreeId>: hyperast::types::TypedTree<
+            TreeId = T::TreeId,
+            Type = T::Type,
+            Label = T::Label,
+            ChildIdx = T::ChildIdx,
+        >,
 {
-    todo!("{:?}, {:?}", a, root)
-    // use hyperast::store::{
-    //     defaults::{LabelIdentifier, NodeIdentifier},
-    //     nodes::HashedNodeRef,
-    // };
-    // use hyperast::types::{AnyType, Children, TypeStore, Typed};
-    // let fun_name = |s: &mut S, x: &T::TreeId| -> (T::Type, Option<T::Label>) {
-    //     let node = s.resolve(x);
-    //     let t = node.get_type().to_owned();
-    //     let l = node.try_get_label().cloned();
-    //     (t, l)
-    // };
-    // let roots: &mut Vec<_> = root;
-    // log::trace!("{:?}", a);
-    // let SimpleAction { path, action } = a;
+    apply_with(a, root, &mut ExtStore::<T, S>(s, PhantomData))
+}
 
-    // let from = match action {
-    //     Act::Move { from } => Some(from),
-    //     Act::MovUpd { from, .. } => Some(from),
-    //     _ => None,
-    // };
+/// What a store must provide to replay [`SimpleAction`]s.
+///
+/// Nodes are never mutated, an edit rebuilds the edited node then each of its ancestors,
+/// so it is up to the store to dedup the rebuilt nodes.
+pub trait ApplyStore<IdN, L> {
+    fn children(&self, id: &IdN) -> Vec<IdN>;
+    /// Builds a node with the type of `like`, with `label` or else the label of `like`.
+    fn rebuild(&mut self, like: &IdN, label: Option<&L>, children: Vec<IdN>) -> IdN;
+}
 
-    // let sub = if let Some(from) = from {
-    //     // apply remove
-    //     log::trace!("sub path {:?}", from.mid.iter().collect::<Vec<_>>());
-    //     let mut path = from.mid.iter();
-    //     let fp = path.next().unwrap().to_usize().unwrap();
-    //     // dbg!(&fp);
-    //     let r = &mut roots[fp];
-    //     let mut x = *r;
-    //     let mut parents: Vec<(T::TreeId, T::ChildIdx, Vec<T::TreeId>)> = vec![];
-    //     while let Some(p) = path.next() {
-    //         // dbg!(&p);
-    //         let node = s.resolve(&x);
-    //         let cs = node.children().unwrap();
-    //         parents.push((x, p, cs.iter_children().cloned().collect()));
-    //         let i = p;
-    //         // dbg!(cs.len());
-    //         if i < cs.child_count() {
-    //             x = cs[i].clone();
-    //         } else {
-    //             assert!(path.next().is_none());
-    //             break;
-    //         }
-    //     }
-    //     log::trace!("parents {:?}", parents);
-    //     let (node, sub) = if let Some((x, i, cs)) = parents.pop() {
-    //         let mut children = Vec::with_capacity(cs.len() - 1);
-    //         children.extend_from_slice(&cs[..i.to_usize().unwrap()]);
-    //         children.extend_from_slice(&cs[i.to_usize().unwrap() + 1..]);
-    //         let (t, l) = fun_name(s, &x);
-    //         let node = s.build_then_insert(x, t, l, children);
-    //         (node, cs[i.to_usize().unwrap()].clone())
-    //     } else {
-    //         // let mut children = Vec::with_capacity(cs.len() - 1);
-    //         // children.extend_from_slice(&cs[..i.to_usize().unwrap()]);
-    //         // children.extend_from_slice(&cs[i.to_usize().unwrap() + 1..]);
-    //         // let node = s.resolve(&x);
-    //         // let node = Self::build(node.get_type(), node.get_label().clone(), children);
-    //         // s.get_or_insert(node)
-    //         (r.clone(), r.clone())
-    //     };
-    //     let mut node: T::TreeId = node;
-    //     for (x, i, cs) in parents.into_iter().rev() {
-    //         let mut children = Vec::with_capacity(cs.len() - 1);
-    //         children.extend_from_slice(&cs[..i.to_usize().unwrap()]);
-    //         children.push(node.clone());
-    //         children.extend_from_slice(&cs[i.to_usize().unwrap() + 1..]);
-    //         let (t, l) = fun_name(s, &x);
-    //         node = s.build_then_insert(x, t, l, children);
-    //         // let n: T = BuildableTree::build(n.get_type().to_owned(), n.get_label().clone(), children);
-    //         // node = s.get_or_insert(n);
-    //     }
-    //     *r = node;
-    //     Some(sub)
-    // } else {
-    //     None
-    // };
+/// Applies `a` on the roots of the middle tree.
+///
+/// The first element of a path indexes `roots`,
+/// an action targeting the root after the last one pushes a copy of the last one.
+/// The current version of the tree is `*roots.last()`.
+pub fn apply_with<S, L, P, IdN>(a: &SimpleAction<L, P, IdN>, roots: &mut Vec<IdN>, s: &mut S)
+where
+    S: ApplyStore<IdN, L>,
+    P: TreePath + Debug,
+    P::Item: PrimInt,
+    L: Debug,
+    IdN: Clone + Debug,
+{
+    log::trace!("{:?}", a);
+    let SimpleAction { path, action } = a;
 
-    // let mut parents: Vec<(T::TreeId, T::ChildIdx, Vec<T::TreeId>)> = vec![];
-    // log::trace!("{:?}", path.mid.iter().collect::<Vec<_>>());
-    // // dbg!(path.mid.iter().collect::<Vec<_>>());
-    // let mut path = path.mid.iter();
-    // let fp = path.next().unwrap().to_usize().unwrap();
-    // // dbg!(&fp);
-    // let r = if roots.len() > fp {
-    //     &mut roots[fp]
-    // } else if roots.len() == fp {
-    //     roots.push(roots[fp - 1].clone());
-    //     &mut roots[fp]
-    // } else {
-    //     panic!()
-    // };
-    // let mut x: T::TreeId = *r;
-    // while let Some(p) = path.next() {
-    //     // dbg!(&p);
-    //     let node = s.resolve(&x);
-    //     let cs = node.children();
-    //     let i = p;
-    //     // TODO use pattern match
-    //     if cs.is_some() && i < cs.unwrap().child_count() {
-    //         let tmp = cs.unwrap()[i].clone();
-    //         parents.push((x, p, cs.unwrap().iter_children().cloned().collect()));
-    //         x = tmp;
-    //     } else {
-    //         if cs.is_some() && !cs.unwrap().is_empty() {
-    //             parents.push((x, p, cs.unwrap().iter_children().cloned().collect()));
-    //             log::error!("{:?} > {:?}", i, cs.unwrap().child_count());
-    //         } else {
-    //             parents.push((x, p, Default::default()));
-    //             log::error!("{:?} > {:?}", i, 0);
-    //         }
-    //         assert_eq!(path.next(), None);
-    //         break;
-    //     }
-    // }
+    let sub = match action {
+        Act::Move { from } | Act::MovUpd { from, .. } => Some(detach(s, roots, &from.mid)),
+        _ => None,
+    };
 
-    // let node = match action {
-    //     Act::Delete {} => {
-    //         let (x, i, cs) = parents.pop().unwrap();
-    //         let mut children = Vec::with_capacity(cs.len() - 1);
-    //         children.extend_from_slice(&cs[..i.to_usize().unwrap()]);
-    //         children.extend_from_slice(&cs[i.to_usize().unwrap() + 1..]);
-    //         let (t, l) = fun_name(s, &x);
-    //         s.build_then_insert(x, t, l, children)
-    //     }
-    //     Act::Insert { sub } => {
-    //         if let Some((x, i, cs)) = parents.pop() {
-    //             let mut children = Vec::with_capacity(cs.len());
-    //             children.extend_from_slice(&cs[..i.to_usize().unwrap()]);
-    //             let sub = {
-    //                 let (t, l) = fun_name(s, sub);
-    //                 s.build_then_insert(*sub, t, l, vec![])
-    //             };
-    //             children.push(sub);
-    //             if i.to_usize().unwrap() < cs.len() {
-    //                 children.extend_from_slice(&cs[i.to_usize().unwrap()..]);
-    //             }
-    //             let (t, l) = fun_name(s, &x);
-    //             s.build_then_insert(x, t, l, children)
-    //         } else {
-    //             let sub = {
-    //                 let (t, l) = fun_name(s, sub);
-    //                 s.build_then_insert(*sub, t, l, vec![])
-    //             };
-    //             // *r = sub.clone();
-    //             sub
-    //         }
-    //     }
-    //     Act::Update { new } => {
-    //         if let Some((x, i, cs)) = parents.pop() {
-    //             let mut children = Vec::with_capacity(cs.len());
-    //             children.extend_from_slice(&cs[..i.to_usize().unwrap()]);
-    //             let sub = {
-    //                 let x = cs[i.to_usize().unwrap()].clone();
-    //                 // let node = s.resolve(&x);
-    //                 // s.build_then_insert(
-    //                 //     node.get_type(),
-    //                 //     new.clone(),
-    //                 //     node.get_children().to_vec(),
-    //                 // )
+    // updates are located in the middle tree as it is before the update
+    let at = match action {
+        Act::Update { before, .. } => &before.mid,
+        _ => &path.mid,
+    };
+    let (fp, mut parents) = descend(s, roots, at);
+    let parent = parents.pop();
 
-    //                 let (t, cs) = {
-    //                     let node = s.resolve(&x);
-    //                     let t = node.get_type().to_owned();
-    //                     let cs = node.children();
-    //                     let cs = cs.map(|cs| cs.iter_children().cloned().collect());
-    //                     (t, cs)
-    //                 };
-    //                 s.build_then_insert(x, t, Some(new.clone()), cs.unwrap_or_default())
-    //             };
-    //             children.push(sub);
-    //             children.extend_from_slice(&cs[i.to_usize().unwrap() + 1..]);
-    //             let (t, l) = fun_name(s, &x);
-    //             s.build_then_insert(x, t, l, children)
-    //         } else {
-    //             let (t, cs) = {
-    //                 let node = s.resolve(&x);
-    //                 let t = node.get_type().to_owned();
-    //                 let cs = node.children();
-    //                 let cs = cs.map(|cs| cs.iter_children().cloned().collect());
-    //                 (t, cs)
-    //             };
-    //             s.build_then_insert(x, t, Some(new.clone()), cs.unwrap_or_default())
-    //         }
-    //     }
+    let node = match action {
+        Act::Delete {} => {
+            let Some((x, i, mut cs)) = parent else {
+                // deleting a root does not materialize anything
+                return;
+            };
+            cs.remove(i);
+            s.rebuild(&x, None, cs)
+        }
+        Act::Update { new, .. } => {
+            if let Some((x, i, mut cs)) = parent {
+                let ccs = s.children(&cs[i]);
+                cs[i] = s.rebuild(&cs[i], Some(new), ccs);
+                s.rebuild(&x, None, cs)
+            } else {
+                let r = roots[fp].clone();
+                let cs = s.children(&r);
+                s.rebuild(&r, Some(new), cs)
+            }
+        }
+        Act::Insert { sub } => {
+            // children are inserted by the following actions
+            let sub = s.rebuild(sub, None, vec![]);
+            insert(s, parent, sub)
+        }
+        Act::Move { .. } => insert(s, parent, sub.unwrap()),
+        Act::MovUpd { new, .. } => {
+            let sub = sub.unwrap();
+            let cs = s.children(&sub);
+            let sub = s.rebuild(&sub, Some(new), cs);
+            insert(s, parent, sub)
+        }
+    };
+    ascend(s, roots, fp, parents, node);
+}
 
-    //     Act::Move { .. } => {
-    //         // apply insert
-    //         let (x, i, cs) = parents.pop().unwrap();
-    //         let mut children = Vec::with_capacity(cs.len());
-    //         children.extend_from_slice(&cs[..i.to_usize().unwrap()]);
-    //         let sub = {
-    //             // let node = s.resolve(&sub.unwrap());
-    //             // let node = BuildableTree::build(node.get_type(), node.get_label().clone(), vec![]);
-    //             // s.get_or_insert(node)
-    //             sub.unwrap()
-    //         };
-    //         children.push(sub);
-    //         if i.to_usize().unwrap() < cs.len() {
-    //             children.extend_from_slice(&cs[i.to_usize().unwrap()..]);
-    //         }
-    //         // dbg!(children.len());
-    //         let (t, l) = fun_name(s, &x);
-    //         s.build_then_insert(x, t, l, children)
-    //     }
-    //     Act::MovUpd { new, .. } => {
-    //         // apply insert
-    //         let (x, i, cs) = parents.pop().unwrap();
-    //         let mut children = Vec::with_capacity(cs.len());
-    //         children.extend_from_slice(&cs[..i.to_usize().unwrap()]);
-    //         let sub = {
-    //             // let node = s.resolve(&sub.unwrap());
-    //             // let node = BuildableTree::build(node.get_type(), node.get_label().clone(), vec![]);
-    //             // s.get_or_insert(node)
-    //             sub.unwrap()
-    //         };
-    //         children.push(sub);
-    //         if i.to_usize().unwrap() < cs.len() {
-    //             children.extend_from_slice(&cs[i.to_usize().unwrap()..]);
-    //         }
-    //         let t = {
-    //             let node = s.resolve(&x);
-    //             let t = node.get_type().to_owned();
-    //             t
-    //         };
-    //         s.build_then_insert(x, t, Some(new.clone()), children)
-    //     }
-    // };
-    // let mut node = node;
-    // for (x, i, cs) in parents.into_iter().rev() {
-    //     let mut children = Vec::with_capacity(cs.len() - 1);
-    //     children.extend_from_slice(&cs[..i.to_usize().unwrap()]);
-    //     children.push(node.clone());
-    //     children.extend_from_slice(&cs[i.to_usize().unwrap() + 1..]);
-    //     let (t, l) = fun_name(s, &x);
-    //     node = s.build_then_insert(x, t, l, children);
-    // }
-    // *r = node;
+type Parents<IdN> = Vec<(IdN, usize, Vec<IdN>)>;
+
+/// Walks `path` from its root,
+/// collecting each traversed node with the offset taken and its children.
+/// The last offset can be one past the children, where to insert.
+fn descend<S, L, P, IdN>(s: &S, roots: &mut Vec<IdN>, path: &P) -> (usize, Parents<IdN>)
+where
+    S: ApplyStore<IdN, L>,
+    P: TreePath + Debug,
+    P::Item: PrimInt,
+    IdN: Clone + Debug,
+{
+    log::trace!("{:?}", path);
+    let mut it = path.iter();
+    let fp = it.next().expect("a path should at least select a root");
+    let fp = fp.to_usize().unwrap();
+    if roots.len() == fp {
+        let r = roots[fp - 1].clone();
+        roots.push(r);
+    }
+    assert!(fp < roots.len(), "{:?} does not select a root", path);
+    let mut x = roots[fp].clone();
+    let mut parents = vec![];
+    while let Some(i) = it.next() {
+        let i = i.to_usize().unwrap();
+        let cs = s.children(&x);
+        let c = cs.get(i).cloned();
+        parents.push((x, i, cs));
+        let Some(c) = c else {
+            assert!(it.next().is_none(), "{:?} goes out of the tree", path);
+            break;
+        };
+        x = c;
+    }
+    (fp, parents)
+}
+
+/// Rebuilds the ancestors of `node`, from the bottom, up to the root.
+fn ascend<S, L, IdN>(s: &mut S, roots: &mut [IdN], fp: usize, parents: Parents<IdN>, node: IdN)
+where
+    S: ApplyStore<IdN, L>,
+{
+    let mut node = node;
+    for (x, i, mut cs) in parents.into_iter().rev() {
+        cs[i] = node;
+        node = s.rebuild(&x, None, cs);
+    }
+    roots[fp] = node;
+}
+
+fn insert<S, L, IdN>(s: &mut S, parent: Option<(IdN, usize, Vec<IdN>)>, sub: IdN) -> IdN
+where
+    S: ApplyStore<IdN, L>,
+{
+    let Some((x, i, mut cs)) = parent else {
+        // a new root
+        return sub;
+    };
+    assert!(
+        i <= cs.len(),
+        "inserting at {} among {} children",
+        i,
+        cs.len()
+    );
+    cs.insert(i, sub);
+    s.rebuild(&x, None, cs)
+}
+
+/// Removes the node at `from`, returning it.
+fn detach<S, L, P, IdN>(s: &mut S, roots: &mut Vec<IdN>, from: &P) -> IdN
+where
+    S: ApplyStore<IdN, L>,
+    P: TreePath + Debug,
+    P::Item: PrimInt,
+    IdN: Clone + Debug,
+{
+    let (fp, mut parents) = descend(s, roots, from);
+    let Some((x, i, mut cs)) = parents.pop() else {
+        // moving a root leaves it in place
+        return roots[fp].clone();
+    };
+    let sub = cs.remove(i);
+    let node = s.rebuild(&x, None, cs);
+    ascend(s, roots, fp, parents, node);
+    sub
+}
+
+/// Replays actions using [`NodeStoreExt::build_then_insert`]
+struct ExtStore<'a, T, S>(&'a mut S, PhantomData<T>);
+
+impl<T, S> ApplyStore<T::TreeId, T::Label> for ExtStore<'_, T, S>
+where
+    S: NodeStoreExt<T> + NodeStore<T::TreeId>,
+    T: hyperast::types::TypedTree,
+    T::Label: Copy,
+    T::TreeId: Clone + NodeId<IdN = T::TreeId>,
+    for<'t> LendN<'t, S, T::TreeId>: hyperast::types::TypedTree<
+            TreeId = T::TreeId,
+            Type = T::Type,
+            Label = T::Label,
+            ChildIdx = T::ChildIdx,
+        >,
+{
+    fn children(&self, id: &T::TreeId) -> Vec<T::TreeId> {
+        let node = self.0.resolve(id);
+        node.children().map_or_else(Vec::new, |cs| cs.collect())
+    }
+
+    fn rebuild(
+        &mut self,
+        like: &T::TreeId,
+        label: Option<&T::Label>,
+        cs: Vec<T::TreeId>,
+    ) -> T::TreeId {
+        let (t, l) = {
+            let node = self.0.resolve(like);
+            (node.get_type(), node.try_get_label().copied())
+        };
+        let l = label.copied().or(l);
+        self.0.build_then_insert(like.clone(), t, l, cs)
+    }
 }
 
 // pub trait ActionApplier<T>
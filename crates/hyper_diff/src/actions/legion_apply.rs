@@ -0,0 +1,244 @@
+//! Replays edit scripts on a legion node store.
+//!
+//! Rebuilt nodes are hashed the same way the generators hash them,
+//! thus they get deduplicated with the nodes already in the store.
+//! In particular, applying the actions computed from `src` to `dst` on `src`
+//! should give back `dst` itself, which [`check_actions`] verifies.
+
+use hyperast::filter::BloomSize;
+use hyperast::hashed::{HashesBuilder, IndexingHashBuilder, MetaDataHashsBuilder, SyntaxNodeHashs};
+use hyperast::store::SimpleStores;
+use hyperast::store::defaults::LabelIdentifier;
+use hyperast::store::nodes::EntityBuilder;
+use hyperast::store::nodes::compo;
+use hyperast::store::nodes::legion::{NodeIdentifier, NodeStore, eq_node, subtree_builder};
+use hyperast::tree_gen::{self, BasicAccumulator, SubTreeMetrics};
+use hyperast::types::{ETypeStore, HyperType, LabelStore as _, Labeled};
+use hyperast::types::{WithChildren, WithStats};
+use num_traits::ToPrimitive;
+
+use super::action_vec::{ActionsVec, ApplyStore, apply_with};
+use super::script_generator2::SimpleAction;
+use crate::tree::tree_path::TreePath;
+
+type Metrics = SubTreeMetrics<SyntaxNodeHashs<u32>>;
+
+/// Applies `actions` on `src`, returning the resulting root.
+pub fn apply_actions<TS: ETypeStore, P>(
+    stores: &mut SimpleStores<TS>,
+    src: NodeIdentifier,
+    actions: &ActionsVec<SimpleAction<LabelIdentifier, P, NodeIdentifier>>,
+) -> NodeIdentifier
+where
+    P: TreePath<Item = u16> + std::fmt::Debug,
+{
+    let mut roots = vec![src];
+    for a in actions.iter() {
+        apply_action(stores, a, &mut roots);
+    }
+    *roots.last().unwrap()
+}
+
+/// Applies a single action, see [`apply_with`] about `roots`.
+pub fn apply_action<TS: ETypeStore, P>(
+    stores: &mut SimpleStores<TS>,
+    action: &SimpleAction<LabelIdentifier, P, NodeIdentifier>,
+    roots: &mut Vec<NodeIdentifier>,
+) where
+    P: TreePath<Item = u16> + std::fmt::Debug,
+{
+    apply_with(action, roots, stores)
+}
+
+/// The tree obtained by applying an edit script is not the expected one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// root of the tree obtained by applying the actions
+    pub applied: NodeIdentifier,
+    /// root of the expected tree
+    pub expected: NodeIdentifier,
+    /// offsets leading, in both trees, to the deepest pair of nodes
+    /// that differ while having the same type, label and number of children
+    pub path: Vec<usize>,
+}
+
+/// Applies `actions` on `src` then checks that the result is hash-identical to `dst`.
+///
+/// Can serve as an oracle for the correctness of matchers and script generators.
+pub fn check_actions<TS: ETypeStore, P>(
+    stores: &mut SimpleStores<TS>,
+    src: NodeIdentifier,
+    dst: NodeIdentifier,
+    actions: &ActionsVec<SimpleAction<LabelIdentifier, P, NodeIdentifier>>,
+) -> Result<NodeIdentifier, Mismatch>
+where
+    P: TreePath<Item = u16> + std::fmt::Debug,
+{
+    let applied = apply_actions(stores, src, actions);
+    let hashs = |id: &NodeIdentifier| {
+        let node = stores.node_store.resolve(*id);
+        node.get_component::<SyntaxNodeHashs<u32>>().ok().copied()
+    };
+    if hashs(&applied) == hashs(&dst) {
+        return Ok(applied);
+    }
+    let mut path = vec![];
+    let (mut x, mut y) = (applied, dst);
+    loop {
+        let same = {
+            let x = stores.node_store.resolve(x);
+            let y = stores.node_store.resolve(y);
+            x.get_component::<TS::Ty>().ok() == y.get_component::<TS::Ty>().ok()
+                && x.try_get_label() == y.try_get_label()
+        };
+        let (x_cs, y_cs) = (stores.children(&x), stores.children(&y));
+        if !same || x_cs.len() != y_cs.len() {
+            break;
+        }
+        let Some(i) = (0..x_cs.len()).find(|&i| hashs(&x_cs[i]) != hashs(&y_cs[i])) else {
+            break;
+        };
+        path.push(i);
+        (x, y) = (x_cs[i], y_cs[i]);
+    }
+    Err(Mismatch {
+        applied,
+        expected: dst,
+        path,
+    })
+}
+
+impl<TS: ETypeStore> ApplyStore<NodeIdentifier, LabelIdentifier> for SimpleStores<TS> {
+    fn children(&self, id: &NodeIdentifier) -> Vec<NodeIdentifier> {
+        let node = self.node_store.resolve(*id);
+        node.children().map_or_else(Vec::new, |cs| cs.collect())
+    }
+
+    fn rebuild(
+        &mut self,
+        like: &NodeIdentifier,
+        label: Option<&LabelIdentifier>,
+        children: Vec<NodeIdentifier>,
+    ) -> NodeIdentifier {
+        let (kind, like_label, like_cs, like_bytes_len) = {
+            let node = self.node_store.resolve(*like);
+            let kind = *node.get_component::<TS::Ty>().unwrap_or_else(|_| {
+                panic!("{:?} is not a node of the language of the type store", like)
+            });
+            let cs: Vec<_> = node.children().map_or_else(Vec::new, |cs| cs.collect());
+            let bytes_len = node.get_component::<compo::BytesLen>().map_or(0, |x| x.0);
+            (kind, node.try_get_label().copied(), cs, bytes_len)
+        };
+        let label_id = label.copied().or(like_label);
+        if label_id == like_label && children == like_cs {
+            return *like;
+        }
+        let unspaced = kind.is_spaces() || kind.is_error();
+        if let (Some(label_id), true) = (label_id, unspaced && children.is_empty()) {
+            return rebuild_unspaced::<TS>(self, kind, label_id);
+        }
+
+        let mut acc = Metrics::default();
+        let mut no_space = vec![];
+        let mut bytes_len = 0;
+        for c in &children {
+            let (metrics, c_bytes_len) = child_metrics::<TS>(self, c);
+            if metrics.size_no_spaces > 0 {
+                no_space.push(*c);
+            }
+            acc.acc(metrics);
+            bytes_len += c_bytes_len;
+        }
+        let label = label_id.map(|l| self.label_store.resolve(&l).to_string());
+        if children.is_empty() {
+            bytes_len = label.as_ref().map_or(like_bytes_len, |l| l.len() as u32);
+        }
+        let own_line_count = label.as_ref().map_or(0, |l| {
+            l.matches("\n").count().to_u32().expect("too many newlines")
+        });
+        let metrics = acc.finalize(&kind, &label, own_line_count);
+
+        let hashable = &metrics.hashs.most_discriminating();
+        let eq = eq_node(&kind, label_id.as_ref(), &children);
+        let insertion = self.node_store.prepare_insertion(hashable, eq);
+        if let Some(id) = insertion.occupied_id() {
+            return id;
+        }
+
+        let metrics = metrics.map_hashs(|h| h.build());
+        let children_is_empty = children.is_empty();
+        let mut dyn_builder = subtree_builder::<TS>(kind);
+        dyn_builder.add(compo::BytesLen(bytes_len));
+        let hashs = metrics.add_md_metrics(&mut dyn_builder, children_is_empty);
+        hashs.persist(&mut dyn_builder);
+        if children.len() != no_space.len() {
+            tree_gen::add_cs_no_spaces(&mut dyn_builder, no_space);
+        }
+        BasicAccumulator { kind, children }.add_primary(&mut dyn_builder, kind, label_id);
+        NodeStore::insert_built_after_prepare(insertion.vacant(), dyn_builder.build())
+    }
+}
+
+/// Retrieves the metrics of a stored node as its parent accumulates them,
+/// along with its length in bytes.
+fn child_metrics<TS: ETypeStore>(stores: &SimpleStores<TS>, id: &NodeIdentifier) -> (Metrics, u32) {
+    let node = stores.node_store.resolve(*id);
+    let hashs = *node
+        .get_component::<SyntaxNodeHashs<u32>>()
+        .expect("nodes should have their hashes");
+    let bytes_len = node.get_component::<compo::BytesLen>().map_or(0, |x| x.0);
+    let unspaced = node.children().is_none()
+        && node
+            .get_component::<TS::Ty>()
+            .is_ok_and(|t| t.is_spaces() || t.is_error());
+    let metrics = if let (true, Some(l)) = (unspaced, node.try_get_label()) {
+        // like spaces and error leaves made by the generators
+        let line_count = stores.label_store.resolve(l).matches("\n").count();
+        Metrics {
+            hashs,
+            size: 1,
+            height: 0,
+            size_no_spaces: 0,
+            line_count: line_count.to_u32().expect("too many newlines"),
+        }
+    } else {
+        Metrics {
+            hashs,
+            size: node.size() as u32,
+            height: node.height() as u32,
+            size_no_spaces: node.size_no_spaces() as u32,
+            line_count: node.line_count() as u32,
+        }
+    };
+    (metrics, bytes_len)
+}
+
+/// Spaces and error leaves are hashed on their text,
+/// as the generators do it when making them.
+fn rebuild_unspaced<TS: ETypeStore>(
+    stores: &mut SimpleStores<TS>,
+    kind: TS::Ty,
+    label_id: LabelIdentifier,
+) -> NodeIdentifier {
+    let text = stores.label_store.resolve(&label_id).to_string();
+    let hbuilder: HashesBuilder<SyntaxNodeHashs<u32>> =
+        HashesBuilder::new(Default::default(), &kind, &text, 1);
+    let hashable = &hbuilder.most_discriminating();
+    let eq = eq_node::<_, _, NodeIdentifier>(&kind, Some(&label_id), &[]);
+    let insertion = stores.node_store.prepare_insertion(hashable, eq);
+    if let Some(id) = insertion.occupied_id() {
+        return id;
+    }
+    let mut hashs = hbuilder.build();
+    if kind.is_spaces() {
+        hashs.structt = 0;
+        hashs.label = 0;
+    }
+    let mut dyn_builder = subtree_builder::<TS>(kind);
+    dyn_builder.add(compo::BytesLen(text.len() as u32));
+    dyn_builder.add(BloomSize::None);
+    hashs.persist(&mut dyn_builder);
+    let acc = BasicAccumulator::<_, NodeIdentifier>::new(kind);
+    acc.add_primary(&mut dyn_builder, kind, Some(label_id));
+    NodeStore::insert_built_after_prepare(insertion.vacant(), dyn_builder.build())
+}
@@ -0,0 +1,335 @@
+//! Three-way structured merge on a legion node store.
+//!
+//! Both descendants are first matched with their common ancestor,
+//! e.g. with [`crate::algorithms::gumtree_lazy::diff`].
+//! Trees are then merged top-down, in the manner of diff3 on the children of matched nodes:
+//! a change made on a single side is taken from that side,
+//! the same change made on both sides is taken once,
+//! and different changes made to the same node or at the same place among siblings are conflicts.
+//!
+//! Conflicting nodes are kept in their left version,
+//! thus the merged tree is always complete and can be serialized as is.
+//! A node moved to another parent is only merged where it lands.
+
+use hyperast::position::{StructuralPosition, TreePathMut};
+use hyperast::store::SimpleStores;
+use hyperast::store::defaults::LabelIdentifier;
+use hyperast::store::nodes::legion::NodeIdentifier;
+use hyperast::types::{ETypeStore, HyperAST, Labeled, WithChildren};
+
+use super::action_vec::ApplyStore;
+use crate::decompressed_tree_store::ShallowDecompressedTreeStore;
+use crate::matchers::Mapper;
+use crate::matchers::mapping_store::MonoMappingStore;
+
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    pub root: NodeIdentifier,
+    pub conflicts: Vec<Conflict>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// both sides changed the type or the label of a node
+    Label,
+    /// a side deleted children that the other side modified
+    DeleteModify,
+    /// both sides inserted, deleted or reordered children at the same place
+    Children,
+}
+
+/// Positions are the ones of the conflicting node,
+/// or of the parent of the conflicting children.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    pub base: StructuralPosition,
+    pub left: StructuralPosition,
+    pub right: StructuralPosition,
+    /// where the left version was kept
+    pub merged: StructuralPosition,
+}
+
+/// A decompressed tree, detached from its hyperAST
+#[derive(Debug, PartialEq, Eq)]
+struct Tree {
+    root: u32,
+    ids: Vec<NodeIdentifier>,
+    children: Vec<Vec<u32>>,
+}
+
+impl Tree {
+    fn new<HAST, D>(arena: &D) -> Self
+    where
+        HAST: HyperAST<IdN = NodeIdentifier> + Copy,
+        D: ShallowDecompressedTreeStore<HAST, u32>,
+    {
+        let root = arena.root();
+        let mut ids = vec![arena.original(&root); arena.len()];
+        let mut children = vec![vec![]; arena.len()];
+        let mut stack = vec![root];
+        while let Some(x) = stack.pop() {
+            ids[x as usize] = arena.original(&x);
+            let cs = arena.children(&x);
+            stack.extend(cs.iter().copied());
+            children[x as usize] = cs;
+        }
+        Self {
+            root,
+            ids,
+            children,
+        }
+    }
+
+    fn len(&self) -> u32 {
+        self.ids.len() as u32
+    }
+}
+
+/// The mappings between a base version and two of its descendants.
+///
+/// They do not borrow the hyperAST, so that the merged tree can then be built in the same stores.
+pub struct ThreeWayMappings {
+    base: Tree,
+    left: Tree,
+    right: Tree,
+    left_base: Vec<Option<u32>>,
+    right_base: Vec<Option<u32>>,
+}
+
+impl ThreeWayMappings {
+    /// `left` and `right` must both have the base version as source,
+    /// decompressed the same way, e.g. in post-order.
+    pub fn new<HAST, SD, DD, M>(
+        left: &Mapper<HAST, SD, DD, M>,
+        right: &Mapper<HAST, SD, DD, M>,
+    ) -> Self
+    where
+        HAST: HyperAST<IdN = NodeIdentifier> + Copy,
+        M: MonoMappingStore<Src = u32, Dst = u32>,
+        SD: ShallowDecompressedTreeStore<HAST, u32>,
+        DD: ShallowDecompressedTreeStore<HAST, u32>,
+    {
+        let base = Tree::new::<HAST, _>(&left.mapping.src_arena);
+        assert_eq!(
+            base,
+            Tree::new::<HAST, _>(&right.mapping.src_arena),
+            "both sides should be matched with the same base version"
+        );
+        let side = |m: &Mapper<HAST, SD, DD, M>| {
+            let tree = Tree::new::<HAST, _>(&m.mapping.dst_arena);
+            let to_base = (0..tree.len())
+                .map(|x| m.mapping.mappings.get_src(&x))
+                .collect();
+            (tree, to_base)
+        };
+        let (left, left_base) = side(left);
+        let (right, right_base) = side(right);
+        Self {
+            base,
+            left,
+            right,
+            left_base,
+            right_base,
+        }
+    }
+}
+
+/// Merges the descendants of the base version matched in `mappings`,
+/// building the merged nodes in `stores`.
+pub fn merge<TS: ETypeStore>(
+    stores: &mut SimpleStores<TS>,
+    mappings: &ThreeWayMappings,
+) -> MergeResult {
+    let m = mappings;
+    let at = |t: &Tree| StructuralPosition::new(t.ids[t.root as usize]);
+    let mut merger = Merger {
+        stores,
+        m,
+        conflicts: vec![],
+        pos: [at(&m.base), at(&m.left), at(&m.right)],
+        merged: vec![],
+    };
+    let root = merger.node(m.base.root, m.left.root, m.right.root);
+    let Merger {
+        stores, conflicts, ..
+    } = merger;
+    let conflicts = conflicts
+        .into_iter()
+        .map(|(kind, [base, left, right], offsets)| {
+            let mut merged = StructuralPosition::new(root);
+            let mut x = root;
+            for o in offsets {
+                x = stores.node_store.resolve(x).child(&o).unwrap();
+                merged.goto(x, o);
+            }
+            Conflict {
+                kind,
+                base,
+                left,
+                right,
+                merged,
+            }
+        })
+        .collect();
+    MergeResult { root, conflicts }
+}
+
+struct Merger<'a, TS> {
+    stores: &'a mut SimpleStores<TS>,
+    m: &'a ThreeWayMappings,
+    conflicts: Vec<(ConflictKind, [StructuralPosition; 3], Vec<u16>)>,
+    /// current positions in the base, left and right versions
+    pos: [StructuralPosition; 3],
+    /// current offsets in the merged tree
+    merged: Vec<u16>,
+}
+
+impl<TS: ETypeStore> Merger<'_, TS> {
+    fn node(&mut self, b: u32, l: u32, r: u32) -> NodeIdentifier {
+        let m = self.m;
+        let (bn, ln, rn) = (
+            m.base.ids[b as usize],
+            m.left.ids[l as usize],
+            m.right.ids[r as usize],
+        );
+        if ln == rn || bn == rn {
+            return ln;
+        } else if bn == ln {
+            return rn;
+        }
+        let (bk, lk, rk) = (self.kind(bn), self.kind(ln), self.kind(rn));
+        let (bl, ll, rl) = (self.label(bn), self.label(ln), self.label(rn));
+        if (lk != rk && lk != bk && rk != bk) || (ll != rl && ll != bl && rl != bl) {
+            self.conflict(ConflictKind::Label);
+        }
+        // the left type is kept unless only the right side changed it
+        let like = if lk == bk && rk != bk { rn } else { ln };
+        let label = if ll == bl { rl } else { ll };
+        let children = self.children(b, l, r);
+        self.stores.rebuild(&like, label.as_ref(), children)
+    }
+
+    fn children(&mut self, b: u32, l: u32, r: u32) -> Vec<NodeIdentifier> {
+        let m = self.m;
+        let bs = &m.base.children[b as usize];
+        let ls = &m.left.children[l as usize];
+        let rs = &m.right.children[r as usize];
+        // matched children of b, other ones being treated as new
+        let in_b = |x: Option<u32>| x.filter(|x| bs.contains(x));
+        let bt: Vec<_> = bs.iter().map(|x| Some(*x)).collect();
+        let lt: Vec<_> = ls.iter().map(|x| in_b(m.left_base[*x as usize])).collect();
+        let rt: Vec<_> = rs.iter().map(|x| in_b(m.right_base[*x as usize])).collect();
+        let ml = lcs(&bt, &lt);
+        let mr = lcs(&bt, &rt);
+        let mut res = vec![];
+        let (mut i, mut j, mut k) = (0, 0, 0);
+        loop {
+            // stable, the children are matched together on both sides
+            while i < bs.len() && ml[i] == Some(j) && mr[i] == Some(k) {
+                self.goto([(bs[i], i), (ls[j], j), (rs[k], k)], res.len());
+                let c = self.node(bs[i], ls[j], rs[k]);
+                self.pop();
+                res.push(c);
+                (i, j, k) = (i + 1, j + 1, k + 1);
+            }
+            if i == bs.len() && j == ls.len() && k == rs.len() {
+                break;
+            }
+            // unstable, up to the next child of b matched on both sides
+            let next = (i..bs.len()).find_map(|x| Some((x, ml[x]?, mr[x]?)));
+            let (i_, j_, k_) = next.unwrap_or((bs.len(), ls.len(), rs.len()));
+            let ids = |t: &Tree, xs: &[u32]| -> Vec<_> {
+                xs.iter().map(|x| t.ids[*x as usize]).collect()
+            };
+            let bc = ids(&m.base, &bs[i..i_]);
+            let lc = ids(&m.left, &ls[j..j_]);
+            let rc = ids(&m.right, &rs[k..k_]);
+            if lc == bc || lc == rc {
+                res.extend(rc);
+            } else if rc == bc {
+                res.extend(lc);
+            } else {
+                // only keeping children identical to their base version
+                let deleting = |t: &[Option<u32>], c: &[NodeIdentifier]| {
+                    let base = |x: &Option<u32>| x.map(|x| m.base.ids[x as usize]);
+                    t.iter().zip(c).all(|(x, c)| base(x) == Some(*c))
+                };
+                let modifying = |t: &[Option<u32>]| t.iter().all(Option::is_some);
+                let (lt, rt) = (&lt[j..j_], &rt[k..k_]);
+                let kind =
+                    if deleting(lt, &lc) && modifying(rt) || deleting(rt, &rc) && modifying(lt) {
+                        ConflictKind::DeleteModify
+                    } else {
+                        ConflictKind::Children
+                    };
+                self.conflict(kind);
+                res.extend(lc);
+            }
+            (i, j, k) = (i_, j_, k_);
+        }
+        res
+    }
+
+    fn kind(&self, id: NodeIdentifier) -> Option<TS::Ty> {
+        let n = self.stores.node_store.resolve(id);
+        n.get_component::<TS::Ty>().ok().copied()
+    }
+
+    fn label(&self, id: NodeIdentifier) -> Option<LabelIdentifier> {
+        let n = self.stores.node_store.resolve(id);
+        n.try_get_label().copied()
+    }
+
+    fn goto(&mut self, children: [(u32, usize); 3], o: usize) {
+        let trees = [&self.m.base, &self.m.left, &self.m.right];
+        for ((pos, t), (x, i)) in self.pos.iter_mut().zip(trees).zip(children) {
+            pos.goto(t.ids[x as usize], i as u16);
+        }
+        self.merged.push(o as u16);
+    }
+
+    fn pop(&mut self) {
+        self.pos.iter_mut().for_each(|pos| {
+            pos.pop();
+        });
+        self.merged.pop();
+    }
+
+    fn conflict(&mut self, kind: ConflictKind) {
+        let pos = self.pos.clone();
+        self.conflicts.push((kind, pos, self.merged.clone()));
+    }
+}
+
+/// Longest common subsequence of `a` and `b`, where `None` never matches.
+///
+/// Gives for each element of `a` the index of the element of `b` it is matched with.
+fn lcs<T: PartialEq>(a: &[Option<T>], b: &[Option<T>]) -> Vec<Option<usize>> {
+    let eq = |i: usize, j: usize| a[i].is_some() && a[i] == b[j];
+    let (n, m) = (a.len(), b.len());
+    // lengths of the lcs of the suffixes
+    let mut len = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            len[i][j] = if eq(i, j) {
+                len[i + 1][j + 1] + 1
+            } else {
+                len[i + 1][j].max(len[i][j + 1])
+            };
+        }
+    }
+    let mut res = vec![None; n];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if eq(i, j) {
+            res[i] = Some(j);
+            (i, j) = (i + 1, j + 1);
+        } else if len[i + 1][j] >= len[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    res
+}
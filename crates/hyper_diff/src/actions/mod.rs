@@ -1,6 +1,8 @@
 #[allow(unused)] // still very experimental
 pub mod action_tree;
 pub mod action_vec;
+pub mod legion_apply;
+pub mod legion_merge;
 pub mod script_generator;
 pub mod script_generator2;
 pub mod script_generator3;
@@ -860,8 +860,8 @@ where
                 if self.ori_mappings.unwrap().has(a, b) && !lcs.contains(&(*a, *b)) {
                     let k = self.find_pos(b, x);
                     let path = ApplicablePath {
-                        ori: self.orig_src(*w).extend(&[k]),
-                        mid: self.path(*w),
+                        ori: self.path_dst(&self.dst_arena.root(), b),
+                        mid: self.path(*w).extend(&[k]),
                     };
                     let action = SimpleAction {
                         path,
@@ -21,6 +21,21 @@ pub fn diff<HAST: HyperAST + Copy>(
     src: &HAST::IdN,
     dst: &HAST::IdN,
 ) -> DiffRes<HAST>
+where
+    HAST::IdN: Clone + Debug + Eq,
+    HAST::IdN: NodeId<IdN = HAST::IdN>,
+    HAST::Idx: hyperast::PrimInt,
+    HAST::Label: Debug + Clone + Copy + Eq,
+    for<'t> <HAST as hyperast::types::AstLending<'t>>::RT: types::WithHashs + types::WithStats,
+{
+    diff_with_bottom_up(hyperast, src, dst, GreedyBottomUpMatcher::<_>::match_it)
+}
+
+pub fn diff_100<HAST: HyperAST + Copy>(
+    hyperast: HAST,
+    src: &HAST::IdN,
+    dst: &HAST::IdN,
+) -> DiffRes<HAST>
 where
     HAST::IdN: Clone + Debug + Eq,
     HAST::IdN: NodeId<IdN = HAST::IdN>,
@@ -36,15 +51,13 @@ where
     }
     let measure = measure.start();
 
-    let mapper = GreedySubtreeMatcher::<_>::match_it::<MM>(mapper);
+    let mapper = GreedySubtreeMatcher::<_>::match_it::<DefaultMultiMappingStore<_>>(mapper);
     let subtree_mappings_s = mapper.mappings().len();
     tr!(subtree_mappings_s);
 
     let measure = measure.stop_then_skip_prepare();
 
-    let mapper = GreedyBottomUpMatcher::<_>::match_it(mapper);
-    let bottomup_mappings_s = mapper.mappings().len();
-    tr!(bottomup_mappings_s);
+    let mapper = GreedyBottomUpMatcher::<_, 100>::match_it(mapper);
 
     let measure = measure.stop_then_prepare();
 
@@ -67,11 +80,36 @@ where
     }
 }
 
-pub fn diff_100<HAST: HyperAST + Copy>(
+/// Like [`diff`] but recovering mappings in the bottom-up phase with APTED instead of ZS
+pub fn diff_apted<HAST: HyperAST + Copy>(
     hyperast: HAST,
     src: &HAST::IdN,
     dst: &HAST::IdN,
 ) -> DiffRes<HAST>
+where
+    HAST::IdN: Clone + Debug + Eq,
+    HAST::IdN: NodeId<IdN = HAST::IdN>,
+    HAST::Idx: hyperast::PrimInt,
+    HAST::Label: Debug + Clone + Copy + Eq,
+    for<'t> <HAST as hyperast::types::AstLending<'t>>::RT: types::WithHashs + types::WithStats,
+{
+    diff_with_bottom_up(
+        hyperast,
+        src,
+        dst,
+        GreedyBottomUpMatcher::<_>::match_it_apted,
+    )
+}
+
+/// The pipeline of [`diff`], parameterized on the bottom-up phase, e.g. to change its last-chance recovery
+fn diff_with_bottom_up<HAST: HyperAST + Copy>(
+    hyperast: HAST,
+    src: &HAST::IdN,
+    dst: &HAST::IdN,
+    bottom_up: impl FnOnce(
+        Mapper<HAST, CDS<HAST>, CDS<HAST>, VecStore<u32>>,
+    ) -> Mapper<HAST, CDS<HAST>, CDS<HAST>, VecStore<u32>>,
+) -> DiffRes<HAST>
 where
     HAST::IdN: Clone + Debug + Eq,
     HAST::IdN: NodeId<IdN = HAST::IdN>,
@@ -87,13 +125,15 @@ where
     }
     let measure = measure.start();
 
-    let mapper = GreedySubtreeMatcher::<_>::match_it::<DefaultMultiMappingStore<_>>(mapper);
+    let mapper = GreedySubtreeMatcher::<_>::match_it::<MM>(mapper);
     let subtree_mappings_s = mapper.mappings().len();
     tr!(subtree_mappings_s);
 
     let measure = measure.stop_then_skip_prepare();
 
-    let mapper = GreedyBottomUpMatcher::<_, 100>::match_it(mapper);
+    let mapper = bottom_up(mapper);
+    let bottomup_mappings_s = mapper.mappings().len();
+    tr!(bottomup_mappings_s);
 
     let measure = measure.stop_then_prepare();
 
@@ -17,6 +17,8 @@ pub mod gumtree_stable;
 pub mod gumtree_stable_hybrid_lazy;
 pub mod gumtree_stable_lazy;
 pub mod gumtree_stable_simple_lazy;
+pub mod pipeline;
+pub mod quality;
 pub mod xy;
 
 type DefaultMetric = <LatMem as RuntimeMeasurement>::M;
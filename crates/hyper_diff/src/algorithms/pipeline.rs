@@ -0,0 +1,323 @@
+//! Diff with a matcher pipeline composed at runtime.
+//!
+//! The other modules of [`super`] each hard-wire their matchers and thresholds,
+//! here they are given by a [`DiffConfig`], e.g. deserialized from a request.
+//! Matching is done lazily, except for the [`BottomUp::Xy`] matcher that needs complete trees.
+
+use super::DiffResult;
+use super::tr;
+use std::fmt::Debug;
+
+use super::CDS;
+use super::DS;
+use super::DiffRes;
+use crate::actions::script_generator2::ScriptGenerator;
+use crate::decompressed_tree_store::DecompressedTreeStore as _;
+use crate::decompressed_tree_store::bfs_wrapper::SimpleBfsMapper;
+use crate::decompressed_tree_store::lazy_post_order::LazyPostOrder;
+use crate::matchers::mapping_store::{DefaultMultiMappingStore, MappingStore, VecStore};
+use crate::matchers::similarity_metrics::SimilarityMeasure;
+use crate::matchers::{Decompressible, Mapper};
+use hyperast::types::{self, HyperAST, NodeId};
+
+use crate::matchers::heuristic::gt::lazy_greedy_subtree_matcher::LazyGreedySubtreeMatcher;
+use crate::matchers::heuristic::xy_bottom_up_matcher::XYBottomUpMatcher;
+
+type IdD = u32;
+type M = VecStore<IdD>;
+type MM = DefaultMultiMappingStore<IdD>;
+
+#[allow(type_alias_bounds)]
+type LazyMapper<'a, HAST: HyperAST> = Mapper<
+    HAST,
+    Decompressible<HAST, &'a mut LazyPostOrder<HAST::IdN, IdD>>,
+    Decompressible<HAST, &'a mut LazyPostOrder<HAST::IdN, IdD>>,
+    M,
+>;
+
+/// The phases of a diff and their thresholds.
+///
+/// The default is the pipeline of [`super::gumtree_lazy`].
+/// Missing fields take their default value when deserializing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serialize", serde(default))]
+pub struct DiffConfig {
+    pub top_down: TopDown,
+    pub bottom_up: BottomUp,
+    /// minimum height of the subtrees matched top-down
+    pub min_height: usize,
+    /// maximum size of the subtrees where optimal recovery (ZS) is used
+    pub size_threshold: usize,
+    /// similarity needed to match containers bottom-up,
+    /// unused by the hybrid matchers that have an adaptive threshold
+    pub sim_threshold_num: u64,
+    pub sim_threshold_den: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serialize", serde(rename_all = "snake_case"))]
+pub enum TopDown {
+    /// isomorphic subtrees, from the highest ones, see [`LazyGreedySubtreeMatcher`]
+    #[default]
+    Greedy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serialize", serde(rename_all = "snake_case"))]
+pub enum BottomUp {
+    /// dice similarity with optimal recovery of small subtrees
+    #[default]
+    Greedy,
+    /// chawathe similarity with histogram recovery
+    Simple,
+    /// adaptive threshold with optimal recovery of small subtrees, histogram recovery otherwise
+    Hybrid,
+    /// stable variant of [`BottomUp::Greedy`]
+    Marriage,
+    /// stable variant of [`BottomUp::Simple`]
+    SimpleMarriage,
+    /// stable variant of [`BottomUp::Hybrid`]
+    HybridMarriage,
+    /// jaccard similarity with recovery of children of unique types, see [`XYBottomUpMatcher`]
+    Xy,
+}
+
+impl Default for DiffConfig {
+    fn default() -> Self {
+        Self {
+            top_down: TopDown::Greedy,
+            bottom_up: BottomUp::Greedy,
+            min_height: 1,
+            size_threshold: 1000,
+            sim_threshold_num: 1,
+            sim_threshold_den: 2,
+        }
+    }
+}
+
+impl DiffConfig {
+    /// the pipeline of [`super::gumtree_stable_hybrid_lazy`]
+    pub fn stable_hybrid() -> Self {
+        Self {
+            bottom_up: BottomUp::HybridMarriage,
+            min_height: 3,
+            size_threshold: 300,
+            sim_threshold_den: 8,
+            ..Default::default()
+        }
+    }
+
+    pub fn sim_threshold(&self) -> f64 {
+        self.sim_threshold_num as f64 / self.sim_threshold_den as f64
+    }
+
+    /// Fails if the similarity threshold is not a number, i.e. with a zero denominator
+    pub fn validate(&self) -> Result<(), String> {
+        if self.sim_threshold_den == 0 {
+            return Err("sim_threshold_den must not be zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Fails on an invalid `config`, see [`DiffConfig::validate`]
+pub fn diff<HAST: HyperAST + Copy>(
+    hyperast: HAST,
+    src: &HAST::IdN,
+    dst: &HAST::IdN,
+    config: &DiffConfig,
+) -> Result<DiffRes<HAST>, String>
+where
+    HAST::IdN: Clone + Debug + Eq,
+    HAST::IdN: NodeId<IdN = HAST::IdN>,
+    HAST::Label: Clone + Copy + Eq + Debug,
+    HAST::Idx: hyperast::PrimInt,
+    for<'t> types::LendT<'t, HAST>: types::WithHashs + types::WithStats,
+{
+    config.validate()?;
+    let measure = super::DefaultMetricSetup::prepare();
+    let mut mapper_owned: (DS<HAST>, DS<HAST>) = hyperast.decompress_pair(src, dst).1;
+    let mut mapper = Mapper::with_mut_decompressible(&mut mapper_owned, M::default());
+    let measure = measure.start();
+
+    match config.top_down {
+        TopDown::Greedy => {
+            let mapping = &mut mapper.mapping;
+            (mapping.mappings).topit(mapping.src_arena.len(), mapping.dst_arena.len());
+            let mut mm = MM::default();
+            mm.topit(mapping.src_arena.len(), mapping.dst_arena.len());
+            Mapper::<_, _, _, M>::compute_multimapping_with_min_height(
+                hyperast,
+                &mut mapping.src_arena,
+                &mut mapping.dst_arena,
+                &mut mm,
+                config.min_height,
+            );
+            LazyGreedySubtreeMatcher::<_>::filter_mappings(&mut mapper, &mm);
+        }
+    }
+    let subtree_mappings_s = mapper.mappings().len();
+    tr!(subtree_mappings_s);
+
+    let (measure, mapper) = if config.bottom_up == BottomUp::Xy {
+        let measure = measure.stop_then_prepare();
+        // Must fully decompress the subtrees to compute the non-lazy bottomup
+        let mapper = Mapper::new(hyperast, mapper.mapping.mappings, mapper_owned);
+        let mut mapper = mapper.map(
+            |src_arena| CDS::<_>::from(src_arena.map(|x| x.complete(hyperast))),
+            |dst_arena| CDS::<_>::from(dst_arena.map(|x| x.complete(hyperast))),
+        );
+        let measure = measure.start();
+        XYBottomUpMatcher::<_>::execute_with_threshold(&mut mapper, config.sim_threshold());
+        (measure.stop_then_prepare(), mapper)
+    } else {
+        let measure = measure.stop_then_skip_prepare();
+        lazy_bottom_up(&mut mapper, config);
+        let measure = measure.stop_then_prepare();
+        // Must fully decompress the subtrees to compute default chawathe
+        let mapper = Mapper::new(hyperast, mapper.mapping.mappings, mapper_owned);
+        let mapper = mapper.map(
+            |src_arena| CDS::<_>::from(src_arena.map(|x| x.complete(hyperast))),
+            |dst_arena| CDS::<_>::from(dst_arena.map(|x| x.complete(hyperast))),
+        );
+        (measure, mapper)
+    };
+    let bottomup_mappings_s = mapper.mappings().len();
+    tr!(bottomup_mappings_s);
+
+    let mapper = mapper.map(
+        |x| x,
+        // the dst side has to be traversed in bfs for chawathe
+        |dst_arena| SimpleBfsMapper::with_store(hyperast, dst_arena),
+    );
+    let measure = measure.start();
+
+    let actions = ScriptGenerator::compute_actions(mapper.hyperast, &mapper.mapping).ok();
+
+    // drop the bfs wrapper
+    let mapper = mapper.map(|x| x, |dst_arena| dst_arena.back);
+
+    let exec_data = measure.stop();
+
+    Ok(DiffResult {
+        mapper,
+        actions,
+        exec_data,
+    })
+}
+
+/// Edit script between `src` and `dst` given their `mappings`, e.g. extracted from the mappings of enclosing trees,
+/// so that no matcher is run.
+///
+/// `mappings` are between the post-order ids of `src` and `dst`.
+pub fn diff_with_mappings<HAST: HyperAST + Copy>(
+    hyperast: HAST,
+    src: &HAST::IdN,
+    dst: &HAST::IdN,
+    mut mappings: M,
+) -> DiffRes<HAST>
+where
+    HAST::IdN: Clone + Debug + Eq,
+    HAST::IdN: NodeId<IdN = HAST::IdN>,
+    HAST::Label: Clone + Copy + Eq + Debug,
+    HAST::Idx: hyperast::PrimInt,
+    for<'t> types::LendT<'t, HAST>: types::WithHashs + types::WithStats,
+{
+    let measure = super::DefaultMetricSetup::prepare();
+    let mapper_owned: (DS<HAST>, DS<HAST>) = hyperast.decompress_pair(src, dst).1;
+    mappings.topit(mapper_owned.0.len(), mapper_owned.1.len());
+    let measure = measure.start();
+    let measure = measure.stop_then_skip_prepare();
+    let measure = measure.stop_then_prepare();
+
+    let mapper = Mapper::new(hyperast, mappings, mapper_owned);
+    let mapper = mapper.map(
+        |src_arena| CDS::<_>::from(src_arena.map(|x| x.complete(hyperast))),
+        // the dst side has to be traversed in bfs for chawathe
+        |dst_arena| {
+            let complete = CDS::<_>::from(dst_arena.map(|x| x.complete(hyperast)));
+            SimpleBfsMapper::with_store(hyperast, complete)
+        },
+    );
+    let measure = measure.start();
+
+    let actions = ScriptGenerator::compute_actions(mapper.hyperast, &mapper.mapping).ok();
+
+    // drop the bfs wrapper
+    let mapper = mapper.map(|x| x, |dst_arena| dst_arena.back);
+
+    let exec_data = measure.stop();
+
+    DiffResult {
+        mapper,
+        actions,
+        exec_data,
+    }
+}
+
+/// The bodies of the lazy bottom-up matchers, with the thresholds of `config`
+fn lazy_bottom_up<HAST: HyperAST + Copy>(mapper: &mut LazyMapper<'_, HAST>, config: &DiffConfig)
+where
+    HAST::IdN: Clone + Debug + Eq,
+    HAST::IdN: NodeId<IdN = HAST::IdN>,
+    HAST::Label: Clone + Copy + Eq + Debug,
+    for<'t> types::LendT<'t, HAST>: types::WithHashs + types::WithStats,
+{
+    (mapper.mapping.mappings).topit(mapper.src_arena.len(), mapper.dst_arena.len());
+    let threshold = config.sim_threshold();
+    let size_threshold = config.size_threshold;
+    let fixed = |_: &LazyMapper<'_, HAST>, _, _| threshold;
+    // optimal recovery of small subtrees
+    let zs = |mapper: &mut LazyMapper<'_, HAST>, src: IdD, dst: IdD| {
+        let src_s = mapper.src_arena.descendants_count(&src);
+        let dst_s = mapper.dst_arena.descendants_count(&dst);
+        if src_s < size_threshold || dst_s < size_threshold {
+            mapper.last_chance_match_zs_lazy_slice::<M>(src, dst);
+        }
+    };
+    let hybrid = |mapper: &mut LazyMapper<'_, HAST>, src: IdD, dst: IdD| {
+        let src_s = mapper.src_arena.descendants_count(&src);
+        let dst_s = mapper.dst_arena.descendants_count(&dst);
+        if src_s < size_threshold && dst_s < size_threshold {
+            mapper.last_chance_match_zs_lazy_slice::<M>(src, dst);
+        } else {
+            mapper.last_chance_match_histogram_lazy(src, dst);
+        }
+    };
+    match config.bottom_up {
+        BottomUp::Greedy => mapper.bottom_up_with_similarity_threshold_and_recovery(
+            fixed,
+            SimilarityMeasure::dice,
+            zs,
+        ),
+        BottomUp::Simple => mapper.bottom_up_with_similarity_threshold_and_recovery(
+            fixed,
+            SimilarityMeasure::chawathe,
+            Mapper::last_chance_match_histogram_lazy,
+        ),
+        BottomUp::Hybrid => mapper.bottom_up_with_similarity_threshold_and_recovery(
+            Mapper::adaptive_threshold,
+            SimilarityMeasure::chawathe,
+            hybrid,
+        ),
+        BottomUp::Marriage => mapper.bottom_up_stable_with_similarity_threshold_and_recovery(
+            fixed,
+            SimilarityMeasure::dice,
+            zs,
+        ),
+        BottomUp::SimpleMarriage => mapper.bottom_up_stable_with_similarity_threshold_and_recovery(
+            fixed,
+            SimilarityMeasure::chawathe,
+            Mapper::last_chance_match_histogram_lazy,
+        ),
+        BottomUp::HybridMarriage => mapper.bottom_up_stable_with_similarity_threshold_and_recovery(
+            Mapper::adaptive_threshold,
+            SimilarityMeasure::chawathe,
+            hybrid,
+        ),
+        BottomUp::Xy => unreachable!("the xy matcher is not lazy"),
+    }
+}
@@ -0,0 +1,151 @@
+//! Quality metrics of a diff, relative to a reference diff of the same pair of trees.
+//!
+//! Helps ranking matchers, e.g. the variants of [`super::pipeline`],
+//! against a reference like [`super::gumtree`] or the output of GumTree itself.
+//! Mappings are compared on post-order ids, so both diffs must decompress trees the same way,
+//! which is the case of all the modules of [`super`] once their arenas are completed.
+
+use crate::actions::action_vec::ActionsVec;
+use crate::actions::script_generator2::{Act, SimpleAction};
+use crate::matchers::Mapper;
+use crate::matchers::mapping_store::{MappingStore, MonoMappingStore};
+
+use super::DiffResult;
+
+/// Length and composition of an edit script
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScriptStats {
+    pub len: usize,
+    pub deletes: usize,
+    pub updates: usize,
+    pub moves: usize,
+    pub mov_upds: usize,
+    pub inserts: usize,
+}
+
+impl ScriptStats {
+    pub fn new<L, P, I>(actions: &ActionsVec<SimpleAction<L, P, I>>) -> Self {
+        let mut stats = Self::default();
+        for a in actions.iter() {
+            stats.len += 1;
+            match a.action {
+                Act::Delete {} => stats.deletes += 1,
+                Act::Update { .. } => stats.updates += 1,
+                Act::Move { .. } => stats.moves += 1,
+                Act::MovUpd { .. } => stats.mov_upds += 1,
+                Act::Insert { .. } => stats.inserts += 1,
+            }
+        }
+        stats
+    }
+}
+
+/// Mappings of a candidate diff compared to the ones of a reference diff
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct MappingComparison {
+    pub candidate: usize,
+    pub reference: usize,
+    /// mappings found in both
+    pub common: usize,
+    /// ratio of candidate mappings also in the reference, 1 without candidate mappings
+    pub precision: f64,
+    /// ratio of reference mappings also in the candidate, 1 without reference mappings
+    pub recall: f64,
+    /// src nodes mapped on both sides but to different dst nodes,
+    /// as `(src, candidate dst, reference dst)`
+    pub mapped_differently: Vec<(u32, u32, u32)>,
+    /// candidate mappings of src nodes unmapped in the reference
+    pub additional: Vec<(u32, u32)>,
+    /// reference mappings of src nodes unmapped in the candidate
+    pub missing: Vec<(u32, u32)>,
+}
+
+impl MappingComparison {
+    pub fn new<M>(candidate: &M, reference: &M) -> Self
+    where
+        M: MonoMappingStore<Src = u32, Dst = u32>,
+    {
+        let mut res = Self {
+            candidate: candidate.len(),
+            reference: reference.len(),
+            ..Default::default()
+        };
+        for (src, dst) in candidate.iter() {
+            if reference.has(&src, &dst) {
+                res.common += 1;
+            } else if let Some(r_dst) = reference.get_dst(&src) {
+                res.mapped_differently.push((src, dst, r_dst));
+            } else {
+                res.additional.push((src, dst));
+            }
+        }
+        for (src, dst) in reference.iter() {
+            if !candidate.is_src(&src) {
+                res.missing.push((src, dst));
+            }
+        }
+        let ratio = |n: usize, d: usize| if d == 0 { 1. } else { n as f64 / d as f64 };
+        res.precision = ratio(res.common, res.candidate);
+        res.recall = ratio(res.common, res.reference);
+        res
+    }
+}
+
+/// Mappings that are not found again when diffing in the other direction,
+/// i.e. the pairs of `forward` (src to dst) missing from `backward` (dst to src),
+/// then the ones of `backward` missing from `forward`,
+/// all given as `(src, dst)`.
+///
+/// A stable matcher gives no such mappings, see `matchers::tests::instability`.
+pub fn unstable_mappings<M>(forward: &M, backward: &M) -> Vec<(u32, u32)>
+where
+    M: MonoMappingStore<Src = u32, Dst = u32>,
+{
+    let forward_only = forward.iter().filter(|(s, d)| !backward.has(d, s));
+    let backward_only = backward
+        .iter()
+        .filter(|(d, s)| !forward.has(s, d))
+        .map(|(d, s)| (s, d));
+    forward_only.chain(backward_only).collect()
+}
+
+/// Quality of a candidate diff relative to a reference diff.
+///
+/// Serializes to JSON with the `serialize` feature.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct QualityReport {
+    /// `None` if no edit script was generated
+    pub candidate: Option<ScriptStats>,
+    pub reference: Option<ScriptStats>,
+    pub mappings: MappingComparison,
+    /// `None` if the candidate was not also computed in the other direction
+    pub unstable: Option<Vec<(u32, u32)>>,
+}
+
+impl QualityReport {
+    /// `reversed` is the candidate diff computed from dst to src, to find unstable mappings.
+    pub fn new<L, P, I, HAST, DS, DD, M, MD>(
+        candidate: &DiffResult<SimpleAction<L, P, I>, Mapper<HAST, DS, DD, M>, MD>,
+        reference: &DiffResult<SimpleAction<L, P, I>, Mapper<HAST, DS, DD, M>, MD>,
+        reversed: Option<&DiffResult<SimpleAction<L, P, I>, Mapper<HAST, DD, DS, M>, MD>>,
+    ) -> Self
+    where
+        M: MonoMappingStore<Src = u32, Dst = u32>,
+    {
+        let mappings = &candidate.mapper.mapping.mappings;
+        Self {
+            candidate: candidate.actions.as_ref().map(ScriptStats::new),
+            reference: reference.actions.as_ref().map(ScriptStats::new),
+            mappings: MappingComparison::new(mappings, &reference.mapper.mapping.mappings),
+            unstable: reversed.map(|r| unstable_mappings(mappings, &r.mapper.mapping.mappings)),
+        }
+    }
+
+    #[cfg(feature = "serialize")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("a report should always be serializable")
+    }
+}
@@ -3,6 +3,7 @@ pub mod decompressed_tree_store;
 #[cfg(feature = "experimental")]
 pub mod mapping;
 pub mod matchers;
+pub mod output;
 pub mod tree;
 pub mod utils;
 // TODO rename to helpers
@@ -1,6 +1,7 @@
 use crate::decompressed_tree_store::POBorrowSlice;
 use crate::decompressed_tree_store::SimpleZsTree as ZsTree;
 use crate::matchers::mapping_store::MonoMappingStore;
+use crate::matchers::optimal::apted::{AptedMatcher, CostModel, ZsCostModel};
 use crate::matchers::{Decompressible, Mapper};
 use crate::matchers::{optimal::zs::ZsMatcher, similarity_metrics};
 use hyperast::PrimInt;
@@ -69,6 +70,31 @@ where
         Self::with_recovery(mapper, recovery);
     }
 
+    /// Like [`Self::match_it`] but recovers mappings with [`AptedMatcher`], see [`Self::execute_apted`]
+    pub fn match_it_apted(
+        mut mapper: crate::matchers::Mapper<HAST, Dsrc, Ddst, M>,
+    ) -> crate::matchers::Mapper<HAST, Dsrc, Ddst, M> {
+        mapper.mapping.mappings.topit(
+            mapper.mapping.src_arena.len(),
+            mapper.mapping.dst_arena.len(),
+        );
+        Self::execute_apted(&mut mapper);
+        mapper
+    }
+
+    /// Like [`Self::execute`] but the last chance matching of subtrees below `SIZE_THRESHOLD`
+    /// uses [`AptedMatcher`] instead of [`ZsMatcher`], with the same costs.
+    pub fn execute_apted(mapper: &mut Mapper<HAST, Dsrc, Ddst, M>) {
+        let recovery = |mapper: &mut Mapper<HAST, Dsrc, Ddst, M>, src, dst| {
+            let src_s = mapper.src_arena.descendants_count(&src);
+            let dst_s = mapper.dst_arena.descendants_count(&dst);
+            if src_s < SIZE_THRESHOLD || dst_s < SIZE_THRESHOLD {
+                mapper.last_chance_match_apted::<M, _>(&ZsCostModel, src, dst);
+            }
+        };
+        Self::with_recovery(mapper, recovery);
+    }
+
     pub fn with_recovery(
         mapper: &mut Mapper<HAST, Dsrc, Ddst, M>,
         recovery: impl Fn(&mut Mapper<HAST, Dsrc, Ddst, M>, M::Src, M::Dst),
@@ -187,6 +213,39 @@ where
         self.apply_mappings(src_offset, dst_offset, mappings);
     }
 
+    /// Maps the descendants of `src` and `dst` with [`AptedMatcher`],
+    /// can be used as a recovery in [`GreedyBottomUpMatcher::with_recovery`] with custom `costs`.
+    pub fn last_chance_match_apted<
+        MZs: MonoMappingStore<Src = M::Src, Dst = M::Dst> + Default,
+        C: CostModel<HAST>,
+    >(
+        &mut self,
+        costs: &C,
+        src: M::Src,
+        dst: M::Dst,
+    ) {
+        let stores = self.hyperast;
+        use crate::decompressed_tree_store::ShallowDecompressedTreeStore;
+        let o_src = self.mapping.src_arena.original(&src);
+        let o_dst = self.mapping.dst_arena.original(&dst);
+        let src_arena = ZsTree::<HAST::IdN, M::Src>::decompress(stores, &o_src);
+        let src_arena = Decompressible {
+            hyperast: stores,
+            decomp: src_arena,
+        };
+        let src_offset = src - src_arena.root();
+        let dst_arena = ZsTree::<HAST::IdN, M::Dst>::decompress(stores, &o_dst);
+        let dst_arena = Decompressible {
+            hyperast: stores,
+            decomp: dst_arena,
+        };
+        let mappings: MZs =
+            AptedMatcher::match_with_costs(self.hyperast, costs, src_arena, dst_arena);
+        let dst_offset = self.dst_arena.first_descendant(&dst);
+        assert_eq!(self.src_arena.first_descendant(&src), src_offset);
+        self.apply_mappings(src_offset, dst_offset, mappings);
+    }
+
     pub(crate) fn last_chance_match_zs_slice<
         MZs: MonoMappingStore<Src = M::Src, Dst = M::Dst> + Default,
     >(
@@ -332,15 +332,36 @@ where
         dst_arena: &mut Ddst,
         multi_mappings: &mut MM,
     ) {
-        let mut src_trees = PriorityTreeList::<'_, Dsrc, M::Src, Dsrc::IdD, HAST, MIN_HEIGHT>::new(
+        Self::compute_multimapping_with_min_height(
+            hyperast,
+            src_arena,
+            dst_arena,
+            multi_mappings,
+            MIN_HEIGHT,
+        )
+    }
+
+    /// same as [`Self::compute_multimapping`] with a minimum height given at runtime
+    pub fn compute_multimapping_with_min_height<
+        MM: MultiMappingStore<Src = Dsrc::IdD, Dst = Ddst::IdD>,
+    >(
+        hyperast: HAST,
+        src_arena: &mut Dsrc,
+        dst_arena: &mut Ddst,
+        multi_mappings: &mut MM,
+        min_height: usize,
+    ) {
+        let mut src_trees = PriorityTreeList::<'_, Dsrc, M::Src, Dsrc::IdD, HAST>::new(
             hyperast,
             src_arena.starter(),
             src_arena,
+            min_height,
         );
-        let mut dst_trees = PriorityTreeList::<'_, Ddst, M::Dst, Ddst::IdD, HAST, MIN_HEIGHT>::new(
+        let mut dst_trees = PriorityTreeList::<'_, Ddst, M::Dst, Ddst::IdD, HAST>::new(
             hyperast,
             dst_arena.starter(),
             dst_arena,
+            min_height,
         );
         while src_trees.peek_height() != -1 && dst_trees.peek_height() != -1 {
             // println!("multi_mappings={}", multi_mappings.len());
@@ -395,7 +416,7 @@ where
     }
 }
 
-pub(super) struct PriorityTreeList<'b, D, IdS, IdD, S, const MIN_HEIGHT: usize> {
+pub(super) struct PriorityTreeList<'b, D, IdS, IdD, S> {
     pub trees: Vec<Option<Vec<IdD>>>,
 
     pub store: S,
@@ -403,24 +424,25 @@ pub(super) struct PriorityTreeList<'b, D, IdS, IdD, S, const MIN_HEIGHT: usize>
 
     pub max_height: usize,
 
+    pub min_height: usize,
+
     pub current_idx: isize,
 
     pub phantom: std::marker::PhantomData<IdS>,
 }
 
-impl<'b, D, IdD, HAST, const MIN_HEIGHT: usize>
-    PriorityTreeList<'b, D, IdD, D::IdD, HAST, MIN_HEIGHT>
+impl<'b, D, IdD, HAST> PriorityTreeList<'b, D, IdD, D::IdD, HAST>
 where
     D::IdD: Clone,
     D: LazyDecompressedTreeStore<HAST, IdD>,
     HAST: HyperAST + Copy,
     for<'t> LendT<'t, HAST>: WithStats,
 {
-    pub(super) fn new(store: HAST, tree: D::IdD, arena: &'b mut D) -> Self {
+    pub(super) fn new(store: HAST, tree: D::IdD, arena: &'b mut D, min_height: usize) -> Self {
         let id = arena.original(&tree);
         let h = store.resolve(&id).height() - 1;
-        let list_size = if h >= MIN_HEIGHT {
-            h + 1 - MIN_HEIGHT
+        let list_size = if h >= min_height {
+            h + 1 - min_height
         } else {
             0
         };
@@ -429,6 +451,7 @@ where
             store,
             arena,
             max_height: h,
+            min_height,
             current_idx: if list_size == 0 { -1 } else { 0 },
             phantom: std::marker::PhantomData,
         };
@@ -451,7 +474,7 @@ where
     }
 
     pub(super) fn add_tree_aux(&mut self, tree: D::IdD, h: usize) {
-        if h >= MIN_HEIGHT {
+        if h >= self.min_height {
             let idx = self.idx(h);
             if self.trees[idx].is_none() {
                 self.trees[idx] = Some(vec![]);
@@ -44,6 +44,11 @@ where
     }
 
     pub fn execute(mapper: &mut Mapper<HAST, Dsrc, Ddst, M>) {
+        Self::execute_with_threshold(mapper, SIM_THRESHOLD_NUM as f64 / SIM_THRESHOLD_DEN as f64)
+    }
+
+    /// same as [`Self::execute`] with a similarity threshold given at runtime
+    pub fn execute_with_threshold(mapper: &mut Mapper<HAST, Dsrc, Ddst, M>, threshold: f64) {
         for a in mapper.src_arena.iter_df_post::<false>() {
             if mapper.mappings.is_src(&a) || !Self::src_has_children(mapper, a) {
                 continue;
@@ -58,7 +63,7 @@ where
                     &mapper.mappings,
                 )
                 .jaccard();
-                if sim > max && sim >= SIM_THRESHOLD_NUM as f64 / SIM_THRESHOLD_DEN as f64 {
+                if sim > max && sim >= threshold {
                     max = sim;
                     best = Some(cand);
                 }
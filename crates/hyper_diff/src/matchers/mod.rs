@@ -12,7 +12,9 @@
 pub mod heuristic;
 pub mod mapping_store;
 pub mod optimal;
+pub mod persist;
 pub mod similarity_metrics;
+pub mod subtree_cache;
 
 #[cfg(test)]
 mod tests;
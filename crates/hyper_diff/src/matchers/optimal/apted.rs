@@ -0,0 +1,692 @@
+//! All Path Tree Edit Distance (APTED) of Pawlik and Augsten, 2016
+//!
+//! Computes the same optimal tree edit distance as [`super::zs`],
+//! but for each pair of subtrees it decomposes along the path,
+//! among the left and right paths of both subtrees,
+//! that minimizes the number of subproblems (the RTED strategy).
+//! Zhang and Shasha always decompose along the left path of the source tree,
+//! which is quadratic in the worst case for each tree instead of being bounded
+//! by the shape of the most favorable tree.
+//!
+//! Heavy paths are not considered, it only restricts the strategy space,
+//! the computed distance and mappings stay optimal.
+//!
+//! The costs of edit operations are given by a [`CostModel`],
+//! [`ZsCostModel`] being the one of [`super::zs::ZsMatcher`].
+
+use crate::decompressed_tree_store::PostOrder;
+use crate::matchers::mapping_store::MonoMappingStore;
+use hyperast::PrimInt;
+use hyperast::types::{DecompressedFrom, HyperAST, Labeled, NodeStore};
+use num_traits::{ToPrimitive, cast};
+
+/// Costs of deleting, inserting and renaming nodes.
+pub trait CostModel<HAST: HyperAST> {
+    /// cost of deleting `n` from the source tree
+    fn del(&self, stores: HAST, n: &HAST::IdN) -> f64;
+    /// cost of inserting `n` from the destination tree
+    fn ins(&self, stores: HAST, n: &HAST::IdN) -> f64;
+    /// cost of renaming `n1` from the source tree into `n2` from the destination tree,
+    /// [`f64::MAX`] forbids mapping them (e.g. nodes with different types)
+    fn ren(&self, stores: HAST, n1: &HAST::IdN, n2: &HAST::IdN) -> f64;
+}
+
+/// Unit costs, renaming nodes of the same type and label is free.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnitCostModel;
+
+impl<HAST: HyperAST + Copy> CostModel<HAST> for UnitCostModel
+where
+    HAST::Label: Eq,
+{
+    fn del(&self, _stores: HAST, _n: &HAST::IdN) -> f64 {
+        1.0
+    }
+
+    fn ins(&self, _stores: HAST, _n: &HAST::IdN) -> f64 {
+        1.0
+    }
+
+    fn ren(&self, stores: HAST, n1: &HAST::IdN, n2: &HAST::IdN) -> f64 {
+        if stores.resolve_type(n1) != stores.resolve_type(n2) {
+            return f64::MAX;
+        }
+        let r1 = stores.node_store().resolve(n1);
+        let r2 = stores.node_store().resolve(n2);
+        if r1.try_get_label() == r2.try_get_label() {
+            0.
+        } else {
+            1.
+        }
+    }
+}
+
+/// The costs used by [`super::zs::ZsMatcher`],
+/// renaming costs the q-gram distance between labels.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ZsCostModel;
+
+impl<HAST: HyperAST + Copy> CostModel<HAST> for ZsCostModel
+where
+    HAST::Label: Eq,
+{
+    fn del(&self, _stores: HAST, _n: &HAST::IdN) -> f64 {
+        1.0
+    }
+
+    fn ins(&self, _stores: HAST, _n: &HAST::IdN) -> f64 {
+        1.0
+    }
+
+    fn ren(&self, stores: HAST, n1: &HAST::IdN, n2: &HAST::IdN) -> f64 {
+        super::zs::update_cost(stores, n1, n2)
+    }
+}
+
+pub struct AptedMatcher<M, SD, DD = SD> {
+    pub mappings: M,
+    pub src_arena: SD,
+    pub dst_arena: DD,
+}
+
+impl<SD, DD, M: MonoMappingStore + Default> AptedMatcher<M, SD, DD>
+where
+    M::Src: PrimInt,
+    M::Dst: PrimInt,
+{
+    pub fn matchh<HAST>(stores: HAST, src: HAST::IdN, dst: HAST::IdN) -> Self
+    where
+        SD: PostOrder<HAST, M::Src> + DecompressedFrom<HAST, Out = SD>,
+        DD: PostOrder<HAST, M::Dst> + DecompressedFrom<HAST, Out = DD>,
+        HAST: HyperAST + Copy,
+        HAST::Label: Eq,
+    {
+        let src_arena = SD::decompress(stores, &src);
+        let dst_arena = DD::decompress(stores, &dst);
+        let mappings = Self::mappings_with(stores, &ZsCostModel, &src_arena, &dst_arena);
+        Self {
+            src_arena,
+            dst_arena,
+            mappings,
+        }
+    }
+
+    pub fn match_with<HAST>(stores: HAST, src_arena: SD, dst_arena: DD) -> M
+    where
+        SD: PostOrder<HAST, M::Src>,
+        DD: PostOrder<HAST, M::Dst>,
+        HAST: HyperAST + Copy,
+        HAST::Label: Eq,
+    {
+        Self::mappings_with(stores, &ZsCostModel, &src_arena, &dst_arena)
+    }
+
+    pub fn match_with_costs<HAST, C: CostModel<HAST>>(
+        stores: HAST,
+        costs: &C,
+        src_arena: SD,
+        dst_arena: DD,
+    ) -> M
+    where
+        SD: PostOrder<HAST, M::Src>,
+        DD: PostOrder<HAST, M::Dst>,
+        HAST: HyperAST + Copy,
+    {
+        Self::mappings_with(stores, costs, &src_arena, &dst_arena)
+    }
+
+    fn mappings_with<HAST, C: CostModel<HAST>>(
+        stores: HAST,
+        costs: &C,
+        src_arena: &SD,
+        dst_arena: &DD,
+    ) -> M
+    where
+        SD: PostOrder<HAST, M::Src>,
+        DD: PostOrder<HAST, M::Dst>,
+        HAST: HyperAST + Copy,
+    {
+        let mut mappings = M::default();
+        mappings.topit(src_arena.len() + 1, dst_arena.len() + 1);
+        let base = Apted::new(stores, costs, src_arena, dst_arena);
+        let (_, delta) = base.compute_dist();
+        base.compute_mappings(&delta, &mut mappings);
+        mappings
+    }
+}
+
+/// Optimal edit distance between two decompressed trees, given the costs of edit operations.
+pub fn distance<HAST, IdS, IdD, SD, DD, C>(
+    stores: HAST,
+    costs: &C,
+    src_arena: &SD,
+    dst_arena: &DD,
+) -> f64
+where
+    HAST: HyperAST + Copy,
+    IdS: PrimInt,
+    IdD: PrimInt,
+    SD: PostOrder<HAST, IdS>,
+    DD: PostOrder<HAST, IdD>,
+    C: CostModel<HAST>,
+{
+    Apted::new(stores, costs, src_arena, dst_arena)
+        .compute_dist()
+        .0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dir {
+    Left,
+    Right,
+}
+
+/// Path along which a pair of subtrees is decomposed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Path {
+    Src(Dir),
+    Dst(Dir),
+}
+
+/// Layout of a decompressed tree, nodes being identified by their postorder offsets.
+///
+/// The right postorder visits children from right to left,
+/// decomposing along right paths is decomposing along left paths in this order.
+struct Indexer<IdN> {
+    nodes: Vec<IdN>,
+    lld: Vec<usize>,
+    parent: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    /// right postorder offset to postorder offset
+    post_r: Vec<usize>,
+    /// postorder offset to right postorder offset
+    to_post_r: Vec<usize>,
+    /// cost of removing each node, i.e. deleting it or inserting it depending on the side
+    cost: Vec<f64>,
+    /// sum of `cost` over each subtree
+    sum_cost: Vec<f64>,
+    /// sum of the sizes of the subtrees of the left keyroots of each subtree
+    kr_sum_l: Vec<f64>,
+    /// sum of the sizes of the subtrees of the right keyroots of each subtree
+    kr_sum_r: Vec<f64>,
+}
+
+impl<IdN> Indexer<IdN> {
+    fn new<HAST, IdD, D>(arena: &D, cost: impl Fn(&IdN) -> f64) -> Self
+    where
+        HAST: HyperAST<IdN = IdN> + Copy,
+        IdD: PrimInt,
+        D: PostOrder<HAST, IdD>,
+    {
+        let len = arena.len();
+        let mut nodes = Vec::with_capacity(len);
+        let mut lld = Vec::with_capacity(len);
+        for x in 0..len {
+            let x: IdD = cast(x).unwrap();
+            nodes.push(arena.tree(&x));
+            lld.push(arena.lld(&x).to_usize().unwrap());
+        }
+        let mut parent = vec![None; len];
+        let mut children = vec![vec![]; len];
+        for x in 0..len {
+            // children are the roots of the consecutive subtrees ending right before x
+            let mut c = x;
+            while c > lld[x] {
+                c -= 1;
+                children[x].push(c);
+                parent[c] = Some(x);
+                c = lld[c];
+            }
+            children[x].reverse();
+        }
+        // the right postorder is the reversed preorder
+        let mut post_r = Vec::with_capacity(len);
+        let mut stack: Vec<usize> = (0..len).filter(|x| parent[*x].is_none()).collect();
+        while let Some(x) = stack.pop() {
+            post_r.push(x);
+            stack.extend(children[x].iter().rev());
+        }
+        post_r.reverse();
+        let mut to_post_r = vec![0; len];
+        for (r, x) in post_r.iter().enumerate() {
+            to_post_r[*x] = r;
+        }
+        let cost: Vec<f64> = nodes.iter().map(cost).collect();
+        let mut sum_cost = cost.clone();
+        let mut kr_sum_l = vec![0.; len];
+        let mut kr_sum_r = vec![0.; len];
+        for x in 0..len {
+            let size = (x + 1 - lld[x]) as f64;
+            kr_sum_l[x] += size;
+            kr_sum_r[x] += size;
+            if let Some(p) = parent[x] {
+                sum_cost[p] += sum_cost[x];
+                // the first child of a node is on the same keyroot path
+                if children[p].first() != Some(&x) {
+                    kr_sum_l[p] += kr_sum_l[x];
+                } else {
+                    kr_sum_l[p] += kr_sum_l[x] - size;
+                }
+                if children[p].last() != Some(&x) {
+                    kr_sum_r[p] += kr_sum_r[x];
+                } else {
+                    kr_sum_r[p] += kr_sum_r[x] - size;
+                }
+            }
+        }
+        Self {
+            nodes,
+            lld,
+            parent,
+            children,
+            post_r,
+            to_post_r,
+            cost,
+            sum_cost,
+            kr_sum_l,
+            kr_sum_r,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn size(&self, x: usize) -> usize {
+        x + 1 - self.lld[x]
+    }
+
+    fn kr_sum(&self, dir: Dir, x: usize) -> f64 {
+        match dir {
+            Dir::Left => self.kr_sum_l[x],
+            Dir::Right => self.kr_sum_r[x],
+        }
+    }
+
+    fn first_child(&self, dir: Dir, x: usize) -> Option<usize> {
+        match dir {
+            Dir::Left => self.children[x].first().copied(),
+            Dir::Right => self.children[x].last().copied(),
+        }
+    }
+
+    /// offset of `x` in the postorder following `dir`
+    fn pos(&self, dir: Dir, x: usize) -> usize {
+        match dir {
+            Dir::Left => x,
+            Dir::Right => self.to_post_r[x],
+        }
+    }
+
+    /// node at offset `p` in the postorder following `dir`
+    fn node(&self, dir: Dir, p: usize) -> usize {
+        match dir {
+            Dir::Left => p,
+            Dir::Right => self.post_r[p],
+        }
+    }
+
+    /// offset of the first leaf of the subtree at offset `p`, in the postorder following `dir`
+    fn leaf(&self, dir: Dir, p: usize) -> usize {
+        p + 1 - self.size(self.node(dir, p))
+    }
+
+    /// keyroots of the subtree of `x` in the postorder following `dir`,
+    /// i.e. the nodes that are not the first child of their parent, and `x` itself
+    fn keyroots(&self, dir: Dir, x: usize) -> Vec<usize> {
+        let p = self.pos(dir, x);
+        (self.leaf(dir, p)..=p)
+            .map(|p| self.node(dir, p))
+            .filter(|y| *y == x || self.first_child(dir, self.parent[*y].unwrap()) != Some(*y))
+            .collect()
+    }
+
+    /// subtrees hanging off the path going from `x` through first children following `dir`
+    fn relevant_subtrees(&self, dir: Dir, mut x: usize) -> Vec<usize> {
+        let mut r = vec![];
+        while let Some(c) = self.first_child(dir, x) {
+            r.extend(self.children[x].iter().filter(|y| **y != c));
+            x = c;
+        }
+        r
+    }
+}
+
+/// Distances between the forests of children of each pair of nodes,
+/// indexed by source and destination postorder offsets.
+pub(crate) struct Delta {
+    dist: Vec<f64>,
+    dst_len: usize,
+}
+
+impl Delta {
+    fn get(&self, v: usize, w: usize) -> f64 {
+        self.dist[v * self.dst_len + w]
+    }
+
+    fn set(&mut self, v: usize, w: usize, d: f64) {
+        self.dist[v * self.dst_len + w] = d;
+    }
+}
+
+pub(crate) struct Apted<'a, HAST: HyperAST, C> {
+    stores: HAST,
+    costs: &'a C,
+    src: Indexer<HAST::IdN>,
+    dst: Indexer<HAST::IdN>,
+}
+
+impl<'a, HAST: HyperAST + Copy, C: CostModel<HAST>> Apted<'a, HAST, C> {
+    pub(crate) fn new<IdS: PrimInt, IdD: PrimInt>(
+        stores: HAST,
+        costs: &'a C,
+        src_arena: &impl PostOrder<HAST, IdS>,
+        dst_arena: &impl PostOrder<HAST, IdD>,
+    ) -> Self {
+        let src = Indexer::new::<HAST, IdS, _>(src_arena, |n| costs.del(stores, n));
+        let dst = Indexer::new::<HAST, IdD, _>(dst_arena, |n| costs.ins(stores, n));
+        Self {
+            stores,
+            costs,
+            src,
+            dst,
+        }
+    }
+
+    fn ren(&self, v: usize, w: usize) -> f64 {
+        self.costs
+            .ren(self.stores, &self.src.nodes[v], &self.dst.nodes[w])
+    }
+
+    /// Computes the edit distance between the two trees,
+    /// along with the distances between the forests of children of all pairs of nodes.
+    pub(crate) fn compute_dist(&self) -> (f64, Delta) {
+        let strategy = self.compute_strategy();
+        let mut delta = self.init_delta();
+        let d = self.gted(
+            &mut delta,
+            &strategy,
+            self.src.len() - 1,
+            self.dst.len() - 1,
+        );
+        (d, delta)
+    }
+
+    /// For each pair of subtrees, picks the path minimizing the number of subproblems,
+    /// accounting for the subproblems of the subtrees hanging off the path.
+    fn compute_strategy(&self) -> Vec<Path> {
+        let (n1, n2) = (self.src.len(), self.dst.len());
+        let mut strategy = vec![Path::Src(Dir::Left); n1 * n2];
+        // costs of the subtrees hanging off the left and right paths of pending source nodes,
+        // only kept until their parent gets processed
+        let mut rows_l: Vec<Option<Vec<f64>>> = vec![None; n1];
+        let mut rows_r: Vec<Option<Vec<f64>>> = vec![None; n1];
+        for v in 0..n1 {
+            let l_src = rows_l[v].take().unwrap_or_else(|| vec![0.; n2]);
+            let r_src = rows_r[v].take().unwrap_or_else(|| vec![0.; n2]);
+            let mut cost = vec![0.; n2];
+            let mut l_dst = vec![0.; n2];
+            let mut r_dst = vec![0.; n2];
+            let sv = self.src.size(v) as f64;
+            for w in 0..n2 {
+                let sw = self.dst.size(w) as f64;
+                cost[w] = if sv == 1. || sw == 1. {
+                    sv * sw
+                } else {
+                    let candidates = [
+                        (
+                            Path::Src(Dir::Left),
+                            sv * self.dst.kr_sum(Dir::Left, w) + l_src[w],
+                        ),
+                        (
+                            Path::Src(Dir::Right),
+                            sv * self.dst.kr_sum(Dir::Right, w) + r_src[w],
+                        ),
+                        (
+                            Path::Dst(Dir::Left),
+                            sw * self.src.kr_sum(Dir::Left, v) + l_dst[w],
+                        ),
+                        (
+                            Path::Dst(Dir::Right),
+                            sw * self.src.kr_sum(Dir::Right, v) + r_dst[w],
+                        ),
+                    ];
+                    let (path, c) = candidates
+                        .into_iter()
+                        .min_by(|a, b| a.1.total_cmp(&b.1))
+                        .unwrap();
+                    strategy[v * n2 + w] = path;
+                    c
+                };
+                if let Some(q) = self.dst.parent[w] {
+                    let l = if self.dst.first_child(Dir::Left, q) == Some(w) {
+                        l_dst[w]
+                    } else {
+                        cost[w]
+                    };
+                    l_dst[q] += l;
+                    let r = if self.dst.first_child(Dir::Right, q) == Some(w) {
+                        r_dst[w]
+                    } else {
+                        cost[w]
+                    };
+                    r_dst[q] += r;
+                }
+            }
+            if let Some(p) = self.src.parent[v] {
+                let first_l = self.src.first_child(Dir::Left, p) == Some(v);
+                let l = if first_l { &l_src } else { &cost };
+                let row = rows_l[p].get_or_insert_with(|| vec![0.; n2]);
+                row.iter_mut().zip(l).for_each(|(r, c)| *r += c);
+                let first_r = self.src.first_child(Dir::Right, p) == Some(v);
+                let r = if first_r { &r_src } else { &cost };
+                let row = rows_r[p].get_or_insert_with(|| vec![0.; n2]);
+                row.iter_mut().zip(r).for_each(|(r, c)| *r += c);
+            }
+        }
+        strategy
+    }
+
+    /// The forests of children of a leaf and a node are edited by insertions or deletions only.
+    fn init_delta(&self) -> Delta {
+        let (n1, n2) = (self.src.len(), self.dst.len());
+        let mut delta = Delta {
+            dist: vec![0.; n1 * n2],
+            dst_len: n2,
+        };
+        for v in 0..n1 {
+            for w in 0..n2 {
+                let d = match (self.src.size(v) == 1, self.dst.size(w) == 1) {
+                    (true, true) => 0.,
+                    (true, false) => self.dst.sum_cost[w] - self.dst.cost[w],
+                    (false, true) => self.src.sum_cost[v] - self.src.cost[v],
+                    (false, false) => continue,
+                };
+                delta.set(v, w, d);
+            }
+        }
+        delta
+    }
+
+    /// General tree edit distance algorithm,
+    /// fills `delta` for all pairs of nodes in the subtrees of `v` and `w`.
+    fn gted(&self, delta: &mut Delta, strategy: &[Path], v: usize, w: usize) -> f64 {
+        if self.src.size(v) == 1 || self.dst.size(w) == 1 {
+            return self.spf1(v, w);
+        }
+        match strategy[v * self.dst.len() + w] {
+            Path::Src(dir) => {
+                for c in self.src.relevant_subtrees(dir, v) {
+                    self.gted(delta, strategy, c, w);
+                }
+                self.spf(delta, dir, false, v, w)
+            }
+            Path::Dst(dir) => {
+                for c in self.dst.relevant_subtrees(dir, w) {
+                    self.gted(delta, strategy, v, c);
+                }
+                self.spf(delta, dir, true, w, v)
+            }
+        }
+    }
+
+    /// Distance when one of the subtrees is a single node,
+    /// it is either removed or renamed into one of the nodes of the other subtree.
+    fn spf1(&self, v: usize, w: usize) -> f64 {
+        if self.src.size(v) == 1 {
+            let mut min = self.src.cost[v];
+            for y in self.dst.lld[w]..=w {
+                min = min.min(self.ren(v, y) - self.dst.cost[y]);
+            }
+            self.dst.sum_cost[w] + min
+        } else {
+            let mut min = self.dst.cost[w];
+            for x in self.src.lld[v]..=v {
+                min = min.min(self.ren(x, w) - self.src.cost[x]);
+            }
+            self.src.sum_cost[v] + min
+        }
+    }
+
+    /// Single path function, decomposing the subtree of `i` along its path following `dir`,
+    /// the subtree of `i` being in the destination tree if `swapped`.
+    ///
+    /// Needs `delta` for the subtrees hanging off the path.
+    fn spf(&self, delta: &mut Delta, dir: Dir, swapped: bool, i: usize, j: usize) -> f64 {
+        let (f, g) = if swapped {
+            (&self.dst, &self.src)
+        } else {
+            (&self.src, &self.dst)
+        };
+        let mut forest = vec![vec![0.; g.size(j) + 1]; f.size(i) + 1];
+        let mut d = 0.;
+        // keyroots come in postorder, the last one being j
+        for kr in g.keyroots(dir, j) {
+            d = self.tree_edit_dist(delta, &mut forest, dir, swapped, i, kr);
+        }
+        d
+    }
+
+    /// Forest distances between prefixes of the subtrees of `i` and `j`,
+    /// following `dir`, as Zhang and Shasha do it for a single pair of keyroots.
+    fn tree_edit_dist(
+        &self,
+        delta: &mut Delta,
+        forest: &mut [Vec<f64>],
+        dir: Dir,
+        swapped: bool,
+        i: usize,
+        j: usize,
+    ) -> f64 {
+        let (f, g) = if swapped {
+            (&self.dst, &self.src)
+        } else {
+            (&self.src, &self.dst)
+        };
+        // delta and renaming are indexed by source then destination nodes
+        let pair = |x, y| if swapped { (y, x) } else { (x, y) };
+        let (ip, jp) = (f.pos(dir, i), g.pos(dir, j));
+        let (i0, j0) = (f.leaf(dir, ip), g.leaf(dir, jp));
+        let (ni, nj) = (ip + 1 - i0, jp + 1 - j0);
+        forest[0][0] = 0.;
+        for a in 1..=ni {
+            forest[a][0] = forest[a - 1][0] + f.cost[f.node(dir, i0 + a - 1)];
+        }
+        for b in 1..=nj {
+            forest[0][b] = forest[0][b - 1] + g.cost[g.node(dir, j0 + b - 1)];
+        }
+        for a in 1..=ni {
+            let x = f.node(dir, i0 + a - 1);
+            let x0 = f.leaf(dir, i0 + a - 1);
+            for b in 1..=nj {
+                let y = g.node(dir, j0 + b - 1);
+                let y0 = g.leaf(dir, j0 + b - 1);
+                let (v, w) = pair(x, y);
+                let rem = forest[a - 1][b] + f.cost[x];
+                let add = forest[a][b - 1] + g.cost[y];
+                let ren = if x0 == i0 && y0 == j0 {
+                    // both prefixes are trees
+                    delta.set(v, w, forest[a - 1][b - 1]);
+                    forest[a - 1][b - 1]
+                } else {
+                    forest[x0 - i0][y0 - j0] + delta.get(v, w)
+                } + self.ren(v, w);
+                forest[a][b] = rem.min(add).min(ren);
+            }
+        }
+        forest[ni][nj]
+    }
+
+    /// Forest distances between prefixes of the subtrees of `i` and `j`,
+    /// only following left paths, using the precomputed `delta`.
+    fn forest_dist(&self, delta: &Delta, forest: &mut [Vec<f64>], i: usize, j: usize) {
+        let (i0, j0) = (self.src.lld[i], self.dst.lld[j]);
+        forest[i0][j0] = 0.;
+        for x in i0..=i {
+            forest[x + 1][j0] = forest[x][j0] + self.src.cost[x];
+        }
+        for y in j0..=j {
+            forest[i0][y + 1] = forest[i0][y] + self.dst.cost[y];
+        }
+        for x in i0..=i {
+            let x0 = self.src.lld[x];
+            for y in j0..=j {
+                let y0 = self.dst.lld[y];
+                let del = forest[x][y + 1] + self.src.cost[x];
+                let ins = forest[x + 1][y] + self.dst.cost[y];
+                let ren = if x0 == i0 && y0 == j0 {
+                    forest[x][y]
+                } else {
+                    forest[x0][y0] + delta.get(x, y)
+                } + self.ren(x, y);
+                forest[x + 1][y + 1] = del.min(ins).min(ren);
+            }
+        }
+    }
+
+    /// Backtracks through forest distances to retrieve the renamed nodes,
+    /// only mapping nodes of the same type.
+    pub(crate) fn compute_mappings<M: MonoMappingStore>(&self, delta: &Delta, mappings: &mut M)
+    where
+        M::Src: PrimInt,
+        M::Dst: PrimInt,
+    {
+        let (n1, n2) = (self.src.len(), self.dst.len());
+        let mut forest = vec![vec![0.; n2 + 1]; n1 + 1];
+        let mut tree_pairs = vec![(n1, n2)];
+        while let Some((last_row, last_col)) = tree_pairs.pop() {
+            self.forest_dist(delta, &mut forest, last_row - 1, last_col - 1);
+            let first_row = self.src.lld[last_row - 1];
+            let first_col = self.dst.lld[last_col - 1];
+            let (mut row, mut col) = (last_row, last_col);
+            while row > first_row || col > first_col {
+                if row > first_row
+                    && forest[row - 1][col] + self.src.cost[row - 1] == forest[row][col]
+                {
+                    // deleted
+                    row -= 1;
+                } else if col > first_col
+                    && forest[row][col - 1] + self.dst.cost[col - 1] == forest[row][col]
+                {
+                    // inserted
+                    col -= 1;
+                } else if self.src.lld[row - 1] == first_row && self.dst.lld[col - 1] == first_col {
+                    // both subforests are trees, their roots are renamed
+                    let t_src = self.stores.resolve_type(&self.src.nodes[row - 1]);
+                    let t_dst = self.stores.resolve_type(&self.dst.nodes[col - 1]);
+                    if t_src == t_dst {
+                        mappings.link(cast(row - 1).unwrap(), cast(col - 1).unwrap());
+                    }
+                    row -= 1;
+                    col -= 1;
+                } else {
+                    // the pair of subtrees is handled later,
+                    // continue with the forests on their left
+                    tree_pairs.push((row, col));
+                    row = self.src.lld[row - 1];
+                    col = self.dst.lld[col - 1];
+                }
+            }
+        }
+    }
+}
@@ -118,59 +118,71 @@ where
         r1: &HAST::IdN,
         r2: &HAST::IdN,
     ) -> f64 {
-        // if r1 == r2 { // Cannot be used because we return 1 if there is no label in either node
-        //     return 0.;
-        // }
-        let n1 = self.stores.node_store().resolve(r1);
-        let t1 = self.stores.resolve_type(r1);
-        let l1 = n1.try_get_label();
-        let n2 = self.stores.node_store().resolve(r2);
-        let t2 = self.stores.resolve_type(r2);
-        if t1 != t2 {
-            return f64::MAX;
-        }
-        let Some(l1) = l1 else { return 1.0 };
-        let Some(l2) = n2.try_get_label() else {
-            return 1.0;
+        update_cost(self.stores, r1, r2)
+    }
+}
+
+/// Cost of renaming `r1` into `r2`, based on the q-gram distance between their labels
+pub(crate) fn update_cost<HAST: HyperAST + Copy>(
+    stores: HAST,
+    r1: &HAST::IdN,
+    r2: &HAST::IdN,
+) -> f64
+where
+    HAST::Label: Eq,
+{
+    // if r1 == r2 { // Cannot be used because we return 1 if there is no label in either node
+    //     return 0.;
+    // }
+    let n1 = stores.node_store().resolve(r1);
+    let t1 = stores.resolve_type(r1);
+    let l1 = n1.try_get_label();
+    let n2 = stores.node_store().resolve(r2);
+    let t2 = stores.resolve_type(r2);
+    if t1 != t2 {
+        return f64::MAX;
+    }
+    let Some(l1) = l1 else { return 1.0 };
+    let Some(l2) = n2.try_get_label() else {
+        return 1.0;
+    };
+    if l1 == l2 {
+        return 0.;
+    }
+    let s1 = stores.label_store().resolve(l1);
+    let s2 = stores.label_store().resolve(l2);
+    // debug_assert_ne!(s1.len(), 0);
+    // debug_assert_ne!(s2.len(), 0);
+    if s1.is_empty() || s2.is_empty() {
+        return 1.;
+    }
+    const S_LEN: usize = 3;
+    let s1 = s1.as_bytes();
+    let s2 = s2.as_bytes();
+    if s1.len() > 30 || s2.len() > 30 {
+        debug_assert_eq!(S_LEN, 3);
+        qgrams::qgram_distance_hash_opti(s1, s2)
+    } else {
+        const S: &[u8] = b"##";
+        debug_assert_eq!(S_LEN, 3);
+        // TODO find a way to repeat at compile time
+        //format!("{empty:#>width$}", empty = "", width = 3-1);
+        //"#".repeat(3 - 1)
+
+        let s1 = {
+            let mut tmp = S.to_vec();
+            tmp.extend_from_slice(s1);
+            tmp.extend_from_slice(S);
+            tmp
+        };
+        let s2 = {
+            let mut tmp = S.to_vec();
+            tmp.extend_from_slice(s2);
+            tmp.extend_from_slice(S);
+            tmp
         };
-        if l1 == l2 {
-            return 0.;
-        }
-        let s1 = self.stores.label_store().resolve(l1);
-        let s2 = self.stores.label_store().resolve(l2);
-        // debug_assert_ne!(s1.len(), 0);
-        // debug_assert_ne!(s2.len(), 0);
-        if s1.is_empty() || s2.is_empty() {
-            return 1.;
-        }
-        const S_LEN: usize = 3;
-        let s1 = s1.as_bytes();
-        let s2 = s2.as_bytes();
-        if s1.len() > 30 || s2.len() > 30 {
-            debug_assert_eq!(S_LEN, 3);
-            qgrams::qgram_distance_hash_opti(s1, s2)
-        } else {
-            const S: &[u8] = b"##";
-            debug_assert_eq!(S_LEN, 3);
-            // TODO find a way to repeat at compile time
-            //format!("{empty:#>width$}", empty = "", width = 3-1);
-            //"#".repeat(3 - 1)
-
-            let s1 = {
-                let mut tmp = S.to_vec();
-                tmp.extend_from_slice(s1);
-                tmp.extend_from_slice(S);
-                tmp
-            };
-            let s2 = {
-                let mut tmp = S.to_vec();
-                tmp.extend_from_slice(s2);
-                tmp.extend_from_slice(S);
-                tmp
-            };
 
-            str_distance_patched::QGram::new(S_LEN).normalized(s1, s2)
-        }
+        str_distance_patched::QGram::new(S_LEN).normalized(s1, s2)
     }
 }
 
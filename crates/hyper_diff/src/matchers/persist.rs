@@ -0,0 +1,142 @@
+//! Compact and persistable mono mappings between post-order decompressed trees.
+//!
+//! Subtrees mapped as a whole are contiguous in post-order on both sides,
+//! so mappings are stored as runs of consecutive pairs.
+//! It is the format used to keep mappings across restarts, see [`super::subtree_cache`].
+
+use std::io::{Read, Write};
+
+use hyperast::store::nodes::legion::persist::{read_u32_from, write_u32};
+
+use crate::matchers::mapping_store::{MappingStore, MonoMappingStore};
+
+/// Mappings between two post-order decompressed trees of `src_len` and `dst_len` nodes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompressedMappings {
+    pub src_len: u32,
+    pub dst_len: u32,
+    /// `(src, dst, len)` maps `src + i` to `dst + i` for each `i` in `0..len`,
+    /// sorted on `src`
+    runs: Vec<(u32, u32, u32)>,
+}
+
+impl CompressedMappings {
+    pub fn compress<M>(mappings: &M, src_len: usize, dst_len: usize) -> Self
+    where
+        M: MonoMappingStore<Src = u32, Dst = u32>,
+    {
+        let mut pairs: Vec<_> = mappings.iter().collect();
+        pairs.sort_unstable();
+        Self::from_sorted_pairs(src_len as u32, dst_len as u32, pairs)
+    }
+
+    /// `pairs` must be sorted on their source
+    pub fn from_sorted_pairs(
+        src_len: u32,
+        dst_len: u32,
+        pairs: impl IntoIterator<Item = (u32, u32)>,
+    ) -> Self {
+        let mut runs: Vec<(u32, u32, u32)> = vec![];
+        for (s, d) in pairs {
+            match runs.last_mut() {
+                Some((s0, d0, len)) if *s0 + *len == s && *d0 + *len == d => *len += 1,
+                _ => runs.push((s, d, 1)),
+            }
+        }
+        Self {
+            src_len,
+            dst_len,
+            runs,
+        }
+    }
+
+    pub fn decompress<M>(&self) -> M
+    where
+        M: MappingStore<Src = u32, Dst = u32> + Default,
+    {
+        let mut mappings = M::default();
+        mappings.topit(self.src_len as usize, self.dst_len as usize);
+        for (s, d) in self.iter() {
+            mappings.link(s, d);
+        }
+        mappings
+    }
+
+    /// Pairs of mapped nodes, sorted on their source
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        (self.runs.iter()).flat_map(|&(s, d, len)| (0..len).map(move |i| (s + i, d + i)))
+    }
+
+    /// Number of mapped pairs
+    pub fn len(&self) -> usize {
+        self.runs.iter().map(|r| r.2 as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> std::io::Result<()> {
+        write_u32(w, self.src_len)?;
+        write_u32(w, self.dst_len)?;
+        write_u32(w, self.runs.len() as u32)?;
+        for &(s, d, len) in &self.runs {
+            write_u32(w, s)?;
+            write_u32(w, d)?;
+            write_u32(w, len)?;
+        }
+        Ok(())
+    }
+
+    pub fn read(r: &mut impl Read) -> std::io::Result<Self> {
+        let src_len = read_u32_from(r)?;
+        let dst_len = read_u32_from(r)?;
+        let runs = (0..read_u32_from(r)?)
+            .map(|_| Ok((read_u32_from(r)?, read_u32_from(r)?, read_u32_from(r)?)))
+            .collect::<std::io::Result<_>>()?;
+        let res = Self {
+            src_len,
+            dst_len,
+            runs,
+        };
+        let in_bounds = |&(s, d, len): &(u32, u32, u32)| {
+            s as u64 + len as u64 <= src_len as u64 && d as u64 + len as u64 <= dst_len as u64
+        };
+        if !res.runs.iter().all(in_bounds) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "mapped node out of bounds",
+            ));
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matchers::mapping_store::VecStore;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut mappings = VecStore::<u32>::default();
+        mappings.topit(10, 12);
+        for (s, d) in [(0, 2), (1, 3), (2, 4), (5, 0), (7, 8), (8, 9), (9, 11)] {
+            mappings.link(s, d);
+        }
+        let compressed = CompressedMappings::compress(&mappings, 10, 12);
+        assert_eq!(4, compressed.runs.len());
+        assert_eq!(7, compressed.len());
+
+        let mut buf = vec![];
+        compressed.write(&mut buf).unwrap();
+        let read = CompressedMappings::read(&mut buf.as_slice()).unwrap();
+        assert_eq!(compressed, read);
+
+        let decompressed: VecStore<u32> = read.decompress();
+        let mut pairs: Vec<_> = decompressed.iter().collect();
+        pairs.sort_unstable();
+        assert_eq!(read.iter().collect::<Vec<_>>(), pairs);
+    }
+}
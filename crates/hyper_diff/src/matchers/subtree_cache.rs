@@ -0,0 +1,206 @@
+//! Mappings of subtree pairs, kept to be reused by later diffs involving the same pairs.
+//!
+//! When tracking code through a history, the same pairs of subtree versions keep being matched,
+//! e.g. a file changed in N-1→N is matched again when diffing N-1→N+1 if it did not change afterward.
+//! Mappings are recorded relative to the subtrees of the pair once their matching is complete,
+//! then a later matching reuses them on pairs of nodes found top-down with the same types and labels,
+//! right after matching identical subtrees.
+
+use std::hash::Hash;
+use std::io::{Read, Write};
+
+use hyperast::PrimInt;
+use hyperast::compat::HashMap;
+use hyperast::store::nodes::legion::NodeIdentifier;
+use hyperast::store::nodes::legion::persist::{read_id, read_u64_from, write_id, write_u64};
+use hyperast::types::{HyperAST, Labeled, NodeStore};
+use num_traits::{ToPrimitive, cast};
+
+use super::persist::CompressedMappings;
+use crate::decompressed_tree_store::{DecompressedTreeStore, LazyDecompressedTreeStore};
+use crate::matchers::Mapper;
+use crate::matchers::mapping_store::MonoMappingStore;
+
+pub struct SubtreeMappingCache<IdN> {
+    map: HashMap<(IdN, IdN), CompressedMappings>,
+}
+
+impl<IdN> Default for SubtreeMappingCache<IdN> {
+    fn default() -> Self {
+        Self {
+            map: Default::default(),
+        }
+    }
+}
+
+impl<IdN: Hash + Eq + Clone> SubtreeMappingCache<IdN> {
+    pub fn get(&self, src: &IdN, dst: &IdN) -> Option<&CompressedMappings> {
+        self.map.get(&(src.clone(), dst.clone()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&IdN, &IdN) -> bool) {
+        self.map.retain(|(src, dst), _| f(src, dst))
+    }
+
+    /// Records the mappings of the pairs of mapped nodes satisfying `unit`, e.g. files,
+    /// the matching of `mapper` being complete.
+    ///
+    /// Identical subtrees are not recorded, they are trivially matched.
+    pub fn record<HAST, SD, DD, M>(
+        &mut self,
+        mapper: &Mapper<HAST, SD, DD, M>,
+        unit: impl Fn(HAST, &HAST::IdN) -> bool,
+    ) -> usize
+    where
+        HAST: HyperAST<IdN = IdN> + Copy,
+        M: MonoMappingStore,
+        M::Src: PrimInt,
+        M::Dst: PrimInt,
+        SD: DecompressedTreeStore<HAST, M::Src>,
+        DD: DecompressedTreeStore<HAST, M::Dst>,
+    {
+        let (src_arena, dst_arena) = (&mapper.mapping.src_arena, &mapper.mapping.dst_arena);
+        let mappings = &mapper.mapping.mappings;
+        let mut count = 0;
+        for (s, d) in mappings.iter() {
+            let (os, od) = (src_arena.original(&s), dst_arena.original(&d));
+            if os == od
+                || !unit(mapper.hyperast, &os)
+                || self.map.contains_key(&(os.clone(), od.clone()))
+            {
+                continue;
+            }
+            let (fs, fd) = (
+                src_arena.first_descendant(&s),
+                dst_arena.first_descendant(&d),
+            );
+            let src_len = (s - fs).to_u32().unwrap() + 1;
+            let dst_len = (d - fd).to_u32().unwrap() + 1;
+            let pairs = (0..src_len).filter_map(|i| {
+                let y = mappings.get_dst(&(fs + cast(i).unwrap()))?;
+                (fd <= y && y <= d).then(|| (i, (y - fd).to_u32().unwrap()))
+            });
+            let compressed = CompressedMappings::from_sorted_pairs(src_len, dst_len, pairs);
+            self.map.insert((os, od), compressed);
+            count += 1;
+        }
+        count
+    }
+
+    /// Reuses the recorded mappings on the pairs of nodes satisfying `unit`,
+    /// found top-down from the roots by pairing children with the same type and label,
+    /// when there is only one such child on each side.
+    ///
+    /// To be called after matching identical subtrees, only unmapped nodes are linked.
+    /// Returns the number of new mappings.
+    pub fn reuse<HAST, SD, DD, M>(
+        &self,
+        mapper: &mut Mapper<HAST, SD, DD, M>,
+        unit: impl Fn(HAST, &HAST::IdN) -> bool,
+    ) -> usize
+    where
+        HAST: HyperAST<IdN = IdN> + Copy,
+        HAST::Label: Eq + Clone,
+        M: MonoMappingStore,
+        M::Src: PrimInt,
+        M::Dst: PrimInt,
+        SD: LazyDecompressedTreeStore<HAST, M::Src, IdD = M::Src>,
+        DD: LazyDecompressedTreeStore<HAST, M::Dst, IdD = M::Dst>,
+    {
+        if self.map.is_empty() {
+            return 0;
+        }
+        let hyperast = mapper.hyperast;
+        let key = |x: &IdN| {
+            let n = hyperast.node_store().resolve(x);
+            (hyperast.resolve_type(x), n.try_get_label().cloned())
+        };
+        let mapping = &mut mapper.mapping;
+        let mut count = 0;
+        let mut stack = vec![(mapping.src_arena.starter(), mapping.dst_arena.starter())];
+        while let Some((s, d)) = stack.pop() {
+            let (os, od) = (
+                mapping.src_arena.original(&s),
+                mapping.dst_arena.original(&d),
+            );
+            if os == od {
+                continue;
+            }
+            if let Some(compressed) = self.get(&os, &od) {
+                mapping.src_arena.decompress_descendants(&s);
+                mapping.dst_arena.decompress_descendants(&d);
+                let fs = mapping.src_arena.first_descendant(&s);
+                let fd = mapping.dst_arena.first_descendant(&d);
+                for (x, y) in compressed.iter() {
+                    let (x, y) = (fs + cast(x).unwrap(), fd + cast(y).unwrap());
+                    if mapping.mappings.link_if_both_unmapped(x, y) {
+                        count += 1;
+                    }
+                }
+                continue;
+            }
+            if unit(hyperast, &os) {
+                // nothing recorded deeper
+                continue;
+            }
+            let cs = mapping.src_arena.decompress_children(&s);
+            let cd = mapping.dst_arena.decompress_children(&d);
+            let ks: Vec<_> = cs
+                .iter()
+                .map(|x| key(&mapping.src_arena.original(x)))
+                .collect();
+            let kd: Vec<_> = cd
+                .iter()
+                .map(|x| key(&mapping.dst_arena.original(x)))
+                .collect();
+            for (i, k) in ks.iter().enumerate() {
+                if ks.iter().filter(|x| *x == k).count() != 1 {
+                    continue;
+                }
+                let mut same = kd.iter().enumerate().filter(|(_, x)| *x == k);
+                if let (Some((j, _)), None) = (same.next(), same.next()) {
+                    stack.push((cs[i], cd[j]));
+                }
+            }
+        }
+        count
+    }
+}
+
+impl SubtreeMappingCache<NodeIdentifier> {
+    pub fn write(&self, w: &mut impl Write) -> std::io::Result<()> {
+        write_u64(w, self.map.len() as u64)?;
+        for ((src, dst), compressed) in &self.map {
+            write_id(w, *src)?;
+            write_id(w, *dst)?;
+            compressed.write(w)?;
+        }
+        Ok(())
+    }
+
+    /// Node identifiers must be the same as when the cache was written,
+    /// e.g. by loading the stores from a snapshot.
+    pub fn read(r: &mut impl Read) -> std::io::Result<Self> {
+        let len = read_u64_from(r)? as usize;
+        let mut map = HashMap::default();
+        map.reserve(len);
+        for _ in 0..len {
+            let src = read_id(r)?;
+            let dst = read_id(r)?;
+            map.insert((src, dst), CompressedMappings::read(r)?);
+        }
+        Ok(Self { map })
+    }
+}
@@ -0,0 +1,131 @@
+use crate::decompressed_tree_store::{
+    CompletePostOrder, ShallowDecompressedTreeStore, SimpleZsTree,
+};
+use crate::matchers::heuristic::gt::greedy_bottom_up_matcher::GreedyBottomUpMatcher;
+use crate::matchers::mapping_store::{DefaultMappingStore, MappingStore, VecStore};
+use crate::matchers::optimal::apted::{self, AptedMatcher, UnitCostModel};
+use crate::matchers::{Decompressible, Mapper, Mapping, optimal::zs::ZsMatcher};
+use crate::tests::examples::{example_gt_java_code, example_gt_slides, example_zs_paper};
+
+use hyperast::test_utils::simple_tree::vpair_to_stores;
+use hyperast::types::DecompressedFrom as _;
+
+#[test]
+fn test_zs_paper_distance() {
+    let (stores, src, dst) = vpair_to_stores(example_zs_paper());
+    let src_arena = Decompressible::<_, SimpleZsTree<_, u16>>::decompress(&stores, &src);
+    let dst_arena = Decompressible::<_, SimpleZsTree<_, u16>>::decompress(&stores, &dst);
+    // d->d, q->a, delete c, insert c, and the b nodes have different types
+    let d = apted::distance(&stores, &UnitCostModel, &src_arena, &dst_arena);
+    assert_eq!(5.0, d);
+    let d = apted::distance(&stores, &UnitCostModel, &src_arena, &src_arena);
+    assert_eq!(0.0, d);
+    let d = apted::distance(&stores, &UnitCostModel, &dst_arena, &src_arena);
+    assert_eq!(5.0, d);
+}
+
+#[test]
+fn test_with_custom_example() {
+    let (stores, src, dst) = vpair_to_stores(example_gt_java_code());
+    let mapper =
+        AptedMatcher::<DefaultMappingStore<u16>, Decompressible<_, SimpleZsTree<_, u16>>>::matchh(
+            &stores, src, dst,
+        );
+    let AptedMatcher {
+        src_arena,
+        dst_arena,
+        mappings,
+    } = mapper;
+    let src = &src_arena.root();
+    let dst = &dst_arena.root();
+    assert_eq!(6, mappings.src_to_dst.iter().filter(|x| **x != 0).count());
+    assert!(mappings.has(&src_arena.child(src, &[0]), &dst_arena.child(dst, &[0, 0])));
+    assert!(mappings.has(&src_arena.child(src, &[1]), &dst_arena.child(dst, &[0, 1])));
+    assert!(mappings.has(
+        &src_arena.child(src, &[1, 0]),
+        &dst_arena.child(dst, &[0, 1, 0])
+    ));
+    assert!(mappings.has(
+        &src_arena.child(src, &[1, 2]),
+        &dst_arena.child(dst, &[0, 1, 2])
+    ));
+    assert!(mappings.has(
+        &src_arena.child(src, &[1, 3]),
+        &dst_arena.child(dst, &[0, 1, 3])
+    ));
+}
+
+#[test]
+fn test_with_slide_example() {
+    let (stores, src, dst) = vpair_to_stores(example_gt_slides());
+    let mapper =
+        AptedMatcher::<DefaultMappingStore<u16>, Decompressible<_, SimpleZsTree<_, u16>>>::matchh(
+            &stores, src, dst,
+        );
+    let AptedMatcher {
+        src_arena,
+        dst_arena,
+        mappings,
+    } = mapper;
+    let src = &src_arena.root();
+    let dst = &dst_arena.root();
+    assert_eq!(5, mappings.src_to_dst.iter().filter(|x| **x != 0).count());
+    assert!(mappings.has(src, dst));
+    assert!(mappings.has(&src_arena.child(src, &[0, 0]), &dst_arena.child(dst, &[0])));
+    assert!(mappings.has(
+        &src_arena.child(src, &[0, 0, 0]),
+        &dst_arena.child(dst, &[0, 0])
+    ));
+    assert!(mappings.has(
+        &src_arena.child(src, &[0, 1]),
+        &dst_arena.child(dst, &[1, 0])
+    ));
+    assert!(mappings.has(&src_arena.child(src, &[0, 2]), &dst_arena.child(dst, &[2])));
+}
+
+#[test]
+fn test_same_mappings_as_zs() {
+    for (src, dst) in [example_gt_java_code(), example_gt_slides()] {
+        let (stores, src, dst) = vpair_to_stores((src, dst));
+        let zs =
+            ZsMatcher::<DefaultMappingStore<u16>, Decompressible<_, SimpleZsTree<_, u16>>>::matchh(
+                &stores, src, dst,
+            );
+        let apted =
+            AptedMatcher::<DefaultMappingStore<u16>, Decompressible<_, SimpleZsTree<_, u16>>>::matchh(
+                &stores, src, dst,
+            );
+        assert_eq!(zs.mappings.src_to_dst, apted.mappings.src_to_dst);
+        assert_eq!(zs.mappings.dst_to_src, apted.mappings.dst_to_src);
+    }
+}
+
+#[test]
+fn test_bottom_up_recovery() {
+    let (stores, src, dst) = vpair_to_stores(example_gt_java_code());
+    let hyperast = &stores;
+    let mapper = || {
+        let src_arena = Decompressible::<_, CompletePostOrder<_, u32>>::decompress(hyperast, &src);
+        let dst_arena = Decompressible::<_, CompletePostOrder<_, u32>>::decompress(hyperast, &dst);
+        let mut mapper = Mapper {
+            hyperast,
+            mapping: Mapping {
+                src_arena,
+                dst_arena,
+                mappings: VecStore::<u32>::default(),
+            },
+        };
+        mapper.mapping.mappings.topit(
+            mapper.mapping.src_arena.len(),
+            mapper.mapping.dst_arena.len(),
+        );
+        mapper
+    };
+    let mut with_zs = mapper();
+    GreedyBottomUpMatcher::<_>::execute(&mut with_zs);
+    let mut with_apted = mapper();
+    GreedyBottomUpMatcher::<_>::execute_apted(&mut with_apted);
+    assert_eq!(6, with_apted.mappings.len());
+    assert_eq!(with_zs.mappings.src_to_dst, with_apted.mappings.src_to_dst);
+    assert_eq!(with_zs.mappings.dst_to_src, with_apted.mappings.dst_to_src);
+}
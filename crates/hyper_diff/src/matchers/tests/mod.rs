@@ -2,6 +2,8 @@
 // #[cfg(test)]
 // pub mod gumtree_tests;
 #[cfg(test)]
+pub mod apted_tests;
+#[cfg(test)]
 pub mod stability_tests;
 #[cfg(test)]
 pub mod zs_tests;
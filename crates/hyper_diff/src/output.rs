@@ -0,0 +1,326 @@
+//! Exports diffs in the formats of GumTree, i.e. its JSON and textual outputs.
+//!
+//! Nodes are described by their type, label, file and byte range,
+//! as computed by [`hyperast::position::compute_position`].
+//! With the `serialize` feature, the JSON output can be consumed by GumTree's tooling,
+//! and the nodes of GumTree's JSON output can be read back, e.g. by `benchmark_diffs::diff_output`.
+
+use std::fmt::{Display, Write};
+use std::ops::Range;
+
+use hyperast::PrimInt;
+use hyperast::position::{Position, compute_position};
+use hyperast::types::{self, HyperAST, LabelStore, Labeled, NodeStore, WithSerialization};
+use num_traits::ToPrimitive;
+
+use crate::actions::action_vec::ActionsVec;
+use crate::actions::script_generator2::{Act, SimpleAction};
+use crate::algorithms::DiffResult;
+use crate::decompressed_tree_store::{DecompressedWithParent, ShallowDecompressedTreeStore};
+use crate::matchers::mapping_store::MonoMappingStore;
+use crate::matchers::{Mapper, Mapping};
+use crate::tree::tree_path::TreePath;
+
+/// A node, as GumTree describes it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tree {
+    pub r#type: String,
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub label: Option<String>,
+    pub file: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct Match {
+    pub src: Tree,
+    pub dest: Tree,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub enum Kind {
+    #[cfg_attr(feature = "serialize", serde(rename = "update-node"))]
+    Upd,
+    #[cfg_attr(feature = "serialize", serde(rename = "move-tree"))]
+    Move,
+    #[cfg_attr(feature = "serialize", serde(rename = "insert-node"))]
+    Ins,
+    #[cfg_attr(feature = "serialize", serde(rename = "delete-node"))]
+    Del,
+}
+
+/// An edit action, as GumTree describes it.
+///
+/// Deleted, updated and moved trees are located in the source,
+/// inserted trees and the parents of inserted or moved trees are located in the destination.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct Action {
+    pub action: Kind,
+    pub tree: Tree,
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub parent: Option<Tree>,
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub at: Option<usize>,
+    /// the new label of updated nodes
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub label: Option<String>,
+}
+
+/// The mappings and actions of a diff.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct Diff {
+    pub matches: Vec<Match>,
+    /// none if the edit script was not computed
+    pub actions: Option<Vec<Action>>,
+}
+
+impl Tree {
+    pub fn from_pos<HAST>(stores: HAST, (pos, x): (Position, HAST::IdN)) -> Self
+    where
+        HAST: HyperAST,
+    {
+        let Range { start, end } = pos.range();
+        let file = pos.file().to_string_lossy().to_string();
+        let r = stores.node_store().resolve(&x);
+        let t = stores.resolve_type(&x);
+        Tree {
+            r#type: t.to_string(),
+            label: r
+                .try_get_label()
+                .map(|x| stores.label_store().resolve(x).to_string())
+                .filter(|x| !x.is_empty()),
+            file,
+            start,
+            end,
+        }
+    }
+
+    /// Describes the node reached by following `path` from `root`.
+    pub fn at_path<HAST>(
+        stores: HAST,
+        root: HAST::IdN,
+        path: impl Iterator<Item = HAST::Idx>,
+    ) -> Self
+    where
+        HAST: HyperAST + Copy,
+        HAST::IdN: types::NodeId<IdN = HAST::IdN>,
+        for<'t> types::LendT<'t, HAST>: WithSerialization,
+    {
+        let mut path = path;
+        Self::from_pos(stores, compute_position(root, &mut path, stores))
+    }
+}
+
+/// Like the node descriptions of GumTree, e.g. `SimpleName: foo [12,15]`
+impl Display for Tree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.r#type)?;
+        if let Some(label) = &self.label {
+            write!(f, ": {}", label)?;
+        }
+        write!(f, " [{},{}]", self.start, self.end)
+    }
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Kind::Upd => "update-node",
+            Kind::Move => "move-tree",
+            Kind::Ins => "insert-node",
+            Kind::Del => "delete-node",
+        })
+    }
+}
+
+impl Diff {
+    /// Describes the mappings and actions of a diff between `src` and `dst`.
+    ///
+    /// Paths of actions are expected to be the ones made by
+    /// [`crate::actions::script_generator2::ScriptGenerator`].
+    pub fn new<HAST, SD, DD, M, P>(
+        stores: HAST,
+        mapping: &Mapping<SD, DD, M>,
+        actions: Option<&ActionsVec<SimpleAction<HAST::Label, P, HAST::IdN>>>,
+    ) -> Self
+    where
+        HAST: HyperAST + Copy,
+        HAST::IdN: types::NodeId<IdN = HAST::IdN>,
+        HAST::Idx: PrimInt,
+        for<'t> types::LendT<'t, HAST>: WithSerialization,
+        M: MonoMappingStore,
+        SD: ShallowDecompressedTreeStore<HAST, M::Src> + DecompressedWithParent<HAST, M::Src>,
+        DD: ShallowDecompressedTreeStore<HAST, M::Dst> + DecompressedWithParent<HAST, M::Dst>,
+        P: TreePath<Item = HAST::Idx>,
+    {
+        let src = mapping.src_arena.original(&mapping.src_arena.root());
+        let dst = mapping.dst_arena.original(&mapping.dst_arena.root());
+        let matches = mapping
+            .mappings
+            .iter()
+            .map(|(a, b)| {
+                let a = mapping.src_arena.path_rooted::<HAST::Idx>(&a);
+                let b = mapping.dst_arena.path_rooted::<HAST::Idx>(&b);
+                Match {
+                    src: Tree::at_path(stores, src, a.into_iter()),
+                    dest: Tree::at_path(stores, dst, b.into_iter()),
+                }
+            })
+            .collect();
+        let actions = actions.map(|actions| {
+            let mut r = vec![];
+            for a in actions.iter() {
+                describe_action(stores, src, dst, a, &mut r);
+            }
+            r
+        });
+        Self { matches, actions }
+    }
+
+    /// Describes the result of one of the [`crate::algorithms`].
+    pub fn from_result<HAST, SD, DD, M, P, MD>(
+        stores: HAST,
+        result: &DiffResult<SimpleAction<HAST::Label, P, HAST::IdN>, Mapper<HAST, SD, DD, M>, MD>,
+    ) -> Self
+    where
+        HAST: HyperAST + Copy,
+        HAST::IdN: types::NodeId<IdN = HAST::IdN>,
+        HAST::Idx: PrimInt,
+        for<'t> types::LendT<'t, HAST>: WithSerialization,
+        M: MonoMappingStore,
+        SD: ShallowDecompressedTreeStore<HAST, M::Src> + DecompressedWithParent<HAST, M::Src>,
+        DD: ShallowDecompressedTreeStore<HAST, M::Dst> + DecompressedWithParent<HAST, M::Dst>,
+        P: TreePath<Item = HAST::Idx>,
+    {
+        Self::new(stores, &result.mapper.mapping, result.actions.as_ref())
+    }
+
+    /// GumTree's JSON output
+    #[cfg(feature = "serialize")]
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// GumTree's textual output
+    pub fn write_text(&self, w: &mut impl Write) -> std::fmt::Result {
+        for m in &self.matches {
+            writeln!(w, "===")?;
+            writeln!(w, "match")?;
+            writeln!(w, "---")?;
+            writeln!(w, "{}", m.src)?;
+            writeln!(w, "{}", m.dest)?;
+        }
+        for a in self.actions.iter().flatten() {
+            writeln!(w, "===")?;
+            writeln!(w, "{}", a.action)?;
+            writeln!(w, "---")?;
+            writeln!(w, "{}", a.tree)?;
+            match a.action {
+                Kind::Upd => {
+                    let before = a.tree.label.as_deref().unwrap_or("");
+                    let after = a.label.as_deref().unwrap_or("");
+                    writeln!(w, "replace {} by {}", before, after)?;
+                }
+                Kind::Move | Kind::Ins => {
+                    if let Some(parent) = &a.parent {
+                        writeln!(w, "to")?;
+                        writeln!(w, "{}", parent)?;
+                    }
+                    writeln!(w, "at {}", a.at.unwrap_or(0))?;
+                }
+                Kind::Del => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for Diff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_text(f)
+    }
+}
+
+fn describe_action<HAST, P>(
+    stores: HAST,
+    src: HAST::IdN,
+    dst: HAST::IdN,
+    a: &SimpleAction<HAST::Label, P, HAST::IdN>,
+    out: &mut Vec<Action>,
+) where
+    HAST: HyperAST + Copy,
+    HAST::IdN: types::NodeId<IdN = HAST::IdN>,
+    HAST::Idx: PrimInt,
+    for<'t> types::LendT<'t, HAST>: WithSerialization,
+    P: TreePath<Item = HAST::Idx>,
+{
+    let label = |l: &HAST::Label| stores.label_store().resolve(l).to_string();
+    // where the tree ends up in the destination
+    let parent_at = || {
+        let mut path: Vec<_> = a.path.ori.iter().collect();
+        let at = path.pop().map(|k| k.to_usize().unwrap());
+        let parent = at.map(|_| Tree::at_path(stores, dst, path.into_iter()));
+        (parent, at)
+    };
+    match &a.action {
+        Act::Delete {} => out.push(Action {
+            action: Kind::Del,
+            tree: Tree::at_path(stores, src, a.path.ori.iter()),
+            parent: None,
+            at: None,
+            label: None,
+        }),
+        Act::Update { before, new } => out.push(Action {
+            action: Kind::Upd,
+            tree: Tree::at_path(stores, src, before.ori.iter()),
+            parent: None,
+            at: None,
+            label: Some(label(new)),
+        }),
+        Act::Insert { .. } => {
+            let (parent, at) = parent_at();
+            out.push(Action {
+                action: Kind::Ins,
+                tree: Tree::at_path(stores, dst, a.path.ori.iter()),
+                parent,
+                at,
+                label: None,
+            })
+        }
+        Act::Move { from } => {
+            let (parent, at) = parent_at();
+            out.push(Action {
+                action: Kind::Move,
+                tree: Tree::at_path(stores, src, from.ori.iter()),
+                parent,
+                at,
+                label: None,
+            })
+        }
+        Act::MovUpd { from, new } => {
+            // GumTree does not combine them
+            let (parent, at) = parent_at();
+            let tree = Tree::at_path(stores, src, from.ori.iter());
+            out.push(Action {
+                action: Kind::Move,
+                tree: tree.clone(),
+                parent,
+                at,
+                label: None,
+            });
+            out.push(Action {
+                action: Kind::Upd,
+                tree,
+                parent: None,
+                at: None,
+                label: Some(label(new)),
+            })
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use hyperast::store::SimpleStores;
+use hyperast::store::defaults::NodeIdentifier;
+use hyperast_gen_ts_java::legion_with_refs::{self, JavaTreeGen};
+use hyperast_gen_ts_java::types::TStore;
+
+use crate::actions::legion_apply::check_actions;
+use crate::actions::script_generator2::Act;
+use crate::algorithms;
+use crate::tree::tree_path::CompressedTreePath;
+
+pub(crate) const SRC: &str = r#"class A {
+    int f(int a) {
+        return a + 1;
+    }
+    void g() {}
+}
+"#;
+
+pub(crate) const DST: &str = r#"class A {
+    void g() {
+        System.out.println("g");
+    }
+    int f(int b) {
+        return b * 2;
+    }
+}
+"#;
+
+pub(crate) fn parse(stores: &mut SimpleStores<TStore>, text: &[u8]) -> NodeIdentifier {
+    let mut md_cache = Default::default();
+    let mut java_tree_gen = JavaTreeGen::new(stores, &mut md_cache);
+    let tree = match legion_with_refs::tree_sitter_parse(text) {
+        Ok(t) => t,
+        Err(t) => t,
+    };
+    let full_node = java_tree_gen.generate_file(b"", text, tree.walk());
+    full_node.local.compressed_node
+}
+
+macro_rules! check_algo {
+    ( $stores:expr, $src:expr, $dst:expr, $algo:ident ) => {{
+        let actions = {
+            let diff = algorithms::$algo::diff(&$stores, &$src, &$dst);
+            diff.actions
+                .expect("ASTs are not identical, but no actions were found")
+        };
+        let r = check_actions(&mut $stores, $src, $dst, &actions);
+        assert_eq!(Ok($dst), r, "{}: {:?}", stringify!($algo), actions);
+    }};
+}
+
+#[test]
+fn test_apply_gumtree_actions() {
+    let mut stores = SimpleStores::<TStore>::default();
+    let src = parse(&mut stores, SRC.as_bytes());
+    let dst = parse(&mut stores, DST.as_bytes());
+    check_algo!(stores, src, dst, gumtree);
+    check_algo!(stores, src, dst, gumtree_lazy);
+    check_algo!(stores, src, dst, gumtree_simple);
+}
+
+#[test]
+fn test_apply_change_distiller_actions() {
+    let mut stores = SimpleStores::<TStore>::default();
+    let src = parse(&mut stores, SRC.as_bytes());
+    let dst = parse(&mut stores, DST.as_bytes());
+    check_algo!(stores, src, dst, change_distiller);
+}
+
+#[test]
+fn test_apply_identity() {
+    let mut stores = SimpleStores::<TStore>::default();
+    let src = parse(&mut stores, SRC.as_bytes());
+    let applied = check_actions::<_, crate::tree::tree_path::CompressedTreePath<u16>>(
+        &mut stores,
+        src,
+        src,
+        &Default::default(),
+    );
+    assert_eq!(Ok(src), applied);
+}
+
+fn resolve(
+    stores: &SimpleStores<TStore>,
+    root: NodeIdentifier,
+    path: &CompressedTreePath<u16>,
+) -> Option<NodeIdentifier> {
+    use hyperast::types::WithChildren;
+    let mut n = root;
+    for o in path.iter() {
+        n = stores.node_store.resolve(n).child(&o)?;
+    }
+    Some(n)
+}
+
+/// Reordered children are only moved by `align_children`,
+/// its moves must be positioned among the children of the parent,
+/// and point to the moved node in the destination
+#[test]
+fn test_apply_align_children_moves() {
+    let reordered = r#"class A {
+    void g() {}
+    int f(int a) {
+        return a + 1;
+    }
+}
+"#;
+    let mut stores = SimpleStores::<TStore>::default();
+    let src = parse(&mut stores, SRC.as_bytes());
+    let dst = parse(&mut stores, reordered.as_bytes());
+    let actions = algorithms::gumtree::diff(&stores, &src, &dst)
+        .actions
+        .expect("ASTs are not identical, but no actions were found");
+    let mut moves = 0;
+    for a in actions.iter() {
+        let Act::Move { from } = &a.action else {
+            continue;
+        };
+        moves += 1;
+        let moved = resolve(&stores, src, &from.ori);
+        assert!(moved.is_some());
+        assert_eq!(moved, resolve(&stores, dst, &a.path.ori), "{:?}", actions);
+    }
+    assert_ne!(0, moves, "{:?}", actions);
+    let r = check_actions(&mut stores, src, dst, &actions);
+    assert_eq!(Ok(dst), r, "{:?}", actions);
+}
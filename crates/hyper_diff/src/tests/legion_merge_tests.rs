@@ -0,0 +1,96 @@
+use hyperast::store::SimpleStores;
+use hyperast::store::defaults::NodeIdentifier;
+use hyperast_gen_ts_java::types::TStore;
+
+use super::legion_apply_tests::parse;
+use crate::actions::legion_merge::{MergeResult, ThreeWayMappings, merge};
+use crate::algorithms;
+
+const BASE: &str = r#"class A {
+    int f(int a) {
+        return a + 1;
+    }
+    void g() {}
+}
+"#;
+
+const BODY_CHANGED: &str = r#"class A {
+    int f(int a) {
+        return a * 2;
+    }
+    void g() {}
+}
+"#;
+
+const METHOD_ADDED: &str = r#"class A {
+    int f(int a) {
+        return a + 1;
+    }
+    void g() {}
+    void h() {}
+}
+"#;
+
+const BOTH: &str = r#"class A {
+    int f(int a) {
+        return a * 2;
+    }
+    void g() {}
+    void h() {}
+}
+"#;
+
+const OTHER_BODY: &str = r#"class A {
+    int f(int a) {
+        return a - 3;
+    }
+    void g() {}
+}
+"#;
+
+fn merge_texts(
+    stores: &mut SimpleStores<TStore>,
+    base: &str,
+    left: &str,
+    right: &str,
+) -> (NodeIdentifier, MergeResult) {
+    let base = parse(stores, base.as_bytes());
+    let left = parse(stores, left.as_bytes());
+    let right = parse(stores, right.as_bytes());
+    let mappings = {
+        let l = algorithms::gumtree_lazy::diff(&*stores, &base, &left);
+        let r = algorithms::gumtree_lazy::diff(&*stores, &base, &right);
+        ThreeWayMappings::new(&l.mapper, &r.mapper)
+    };
+    (left, merge(stores, &mappings))
+}
+
+#[test]
+fn test_merge_independent_changes() {
+    let mut stores = SimpleStores::<TStore>::default();
+    let (_, merged) = merge_texts(&mut stores, BASE, BODY_CHANGED, METHOD_ADDED);
+    let expected = parse(&mut stores, BOTH.as_bytes());
+    assert!(merged.conflicts.is_empty(), "{:?}", merged.conflicts);
+    assert_eq!(expected, merged.root);
+    // symmetric
+    let (_, merged) = merge_texts(&mut stores, BASE, METHOD_ADDED, BODY_CHANGED);
+    assert!(merged.conflicts.is_empty(), "{:?}", merged.conflicts);
+    assert_eq!(expected, merged.root);
+}
+
+#[test]
+fn test_merge_same_change() {
+    let mut stores = SimpleStores::<TStore>::default();
+    let (left, merged) = merge_texts(&mut stores, BASE, BODY_CHANGED, BODY_CHANGED);
+    assert!(merged.conflicts.is_empty(), "{:?}", merged.conflicts);
+    assert_eq!(left, merged.root);
+}
+
+#[test]
+fn test_merge_conflicting_changes() {
+    let mut stores = SimpleStores::<TStore>::default();
+    let (left, merged) = merge_texts(&mut stores, BASE, BODY_CHANGED, OTHER_BODY);
+    assert!(!merged.conflicts.is_empty());
+    // the left version is kept
+    assert_eq!(left, merged.root);
+}
@@ -22,7 +22,12 @@ pub mod examples;
 pub mod hungarian_tests;
 #[cfg(test)]
 pub mod lazy_decompression_tests;
+pub mod legion_apply_tests;
+pub mod legion_merge_tests;
+pub mod output_tests;
 pub mod pair_tests;
+pub mod pipeline_tests;
+pub mod quality_tests;
 pub mod simple_examples;
 pub mod simple_matcher_examples;
 pub mod simple_matcher_test;
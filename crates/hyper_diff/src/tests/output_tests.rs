@@ -0,0 +1,91 @@
+use hyperast::store::SimpleStores;
+use hyperast_gen_ts_java::types::TStore;
+
+use super::legion_apply_tests::{DST, SRC, parse};
+use crate::algorithms;
+use crate::matchers::mapping_store::MappingStore;
+use crate::output::{Diff, Kind};
+
+fn gumtree_diff() -> Diff {
+    let mut stores = SimpleStores::<TStore>::default();
+    let src = parse(&mut stores, SRC.as_bytes());
+    let dst = parse(&mut stores, DST.as_bytes());
+    let diff = algorithms::gumtree::diff(&stores, &src, &dst);
+    let out = Diff::from_result(&stores, &diff);
+    assert_eq!(diff.mapper.mappings.len(), out.matches.len());
+    let actions = diff.actions.as_ref().unwrap();
+    assert!(out.actions.as_ref().unwrap().len() >= actions.len());
+    out
+}
+
+#[test]
+fn test_ranges() {
+    let out = gumtree_diff();
+    let root = out
+        .matches
+        .iter()
+        .find(|m| m.src.start == 0 && m.src.end == SRC.len());
+    let root = root.expect("roots should be mapped");
+    assert_eq!((0, DST.len()), (root.dest.start, root.dest.end));
+    for m in &out.matches {
+        assert_eq!(m.src.r#type, m.dest.r#type);
+        if let Some(l) = &m.src.label {
+            assert_eq!(l, &SRC[m.src.start..m.src.end]);
+        }
+        if let Some(l) = &m.dest.label {
+            assert_eq!(l, &DST[m.dest.start..m.dest.end]);
+        }
+    }
+    for a in out.actions.as_ref().unwrap() {
+        let text = match a.action {
+            Kind::Ins => DST,
+            Kind::Upd | Kind::Move | Kind::Del => SRC,
+        };
+        if let Some(l) = &a.tree.label {
+            assert_eq!(l, &text[a.tree.start..a.tree.end]);
+        }
+        match a.action {
+            Kind::Upd => assert!(a.label.is_some() && a.parent.is_none()),
+            Kind::Ins | Kind::Move => assert!(a.at.is_some() && a.parent.is_some()),
+            Kind::Del => assert!(a.at.is_none() && a.parent.is_none()),
+        }
+    }
+}
+
+#[test]
+fn test_text_output() {
+    let out = gumtree_diff();
+    let text = out.to_string();
+    let blocks: Vec<_> = text.split("===\n").skip(1).collect();
+    let actions = out.actions.as_ref().unwrap();
+    assert_eq!(out.matches.len() + actions.len(), blocks.len());
+    for (b, m) in blocks.iter().zip(&out.matches) {
+        assert_eq!(&format!("match\n---\n{}\n{}\n", m.src, m.dest), b);
+    }
+    for (b, a) in blocks[out.matches.len()..].iter().zip(actions) {
+        assert!(b.starts_with(&format!("{}\n---\n{}\n", a.action, a.tree)));
+        if a.action == Kind::Upd {
+            assert!(b.contains(" by "));
+        }
+    }
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_json_output() {
+    let out = gumtree_diff();
+    let json = out.to_json().unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let matches = value["matches"].as_array().unwrap();
+    assert_eq!(out.matches.len(), matches.len());
+    assert_eq!(
+        serde_json::json!(out.matches[0].src.start),
+        matches[0]["src"]["start"]
+    );
+    for a in value["actions"].as_array().unwrap() {
+        let kind = a["action"].as_str().unwrap();
+        assert!(["update-node", "move-tree", "insert-node", "delete-node"].contains(&kind));
+        assert!(a["tree"]["type"].is_string());
+        assert!(a["tree"]["file"].is_string());
+    }
+}
@@ -0,0 +1,54 @@
+use hyperast::store::SimpleStores;
+use hyperast_gen_ts_java::types::TStore;
+
+use super::legion_apply_tests::{DST, SRC, parse};
+use crate::actions::legion_apply::check_actions;
+use crate::algorithms;
+use crate::algorithms::pipeline::{BottomUp, DiffConfig, diff};
+
+#[test]
+fn test_apply_pipeline_actions() {
+    let mut stores = SimpleStores::<TStore>::default();
+    let src = parse(&mut stores, SRC.as_bytes());
+    let dst = parse(&mut stores, DST.as_bytes());
+    let bottom_ups = [
+        BottomUp::Greedy,
+        BottomUp::Simple,
+        BottomUp::Hybrid,
+        BottomUp::Marriage,
+        BottomUp::SimpleMarriage,
+        BottomUp::HybridMarriage,
+        BottomUp::Xy,
+    ];
+    for bottom_up in bottom_ups {
+        let config = DiffConfig {
+            bottom_up,
+            min_height: 2,
+            ..Default::default()
+        };
+        let actions = diff(&stores, &src, &dst, &config)
+            .unwrap()
+            .actions
+            .expect("ASTs are not identical, but no actions were found");
+        let r = check_actions(&mut stores, src, dst, &actions);
+        assert_eq!(Ok(dst), r, "{:?}: {:?}", bottom_up, actions);
+    }
+    // same pipeline as the dedicated module
+    let lazy = algorithms::gumtree_lazy::diff(&stores, &src, &dst);
+    let configured = diff(&stores, &src, &dst, &DiffConfig::default()).unwrap();
+    assert_eq!(lazy.summarize().mappings, configured.summarize().mappings);
+}
+
+#[test]
+fn test_invalid_sim_threshold() {
+    let mut stores = SimpleStores::<TStore>::default();
+    let src = parse(&mut stores, SRC.as_bytes());
+    let dst = parse(&mut stores, DST.as_bytes());
+    let config = DiffConfig {
+        sim_threshold_den: 0,
+        ..Default::default()
+    };
+    assert!(config.validate().is_err());
+    assert!(diff(&stores, &src, &dst, &config).is_err());
+    assert_eq!(Ok(()), DiffConfig::stable_hybrid().validate());
+}
@@ -0,0 +1,58 @@
+use hyperast::store::SimpleStores;
+use hyperast_gen_ts_java::types::TStore;
+
+use super::legion_apply_tests::{DST, SRC, parse};
+use crate::actions::Actions;
+use crate::algorithms;
+use crate::algorithms::quality::{MappingComparison, QualityReport, unstable_mappings};
+
+#[test]
+fn test_quality_same_diff() {
+    let mut stores = SimpleStores::<TStore>::default();
+    let src = parse(&mut stores, SRC.as_bytes());
+    let dst = parse(&mut stores, DST.as_bytes());
+    let diff = algorithms::gumtree::diff(&stores, &src, &dst);
+    let report = QualityReport::new(&diff, &diff, None);
+    let stats = report.candidate.as_ref().unwrap();
+    assert_eq!(stats.len, diff.actions.as_ref().unwrap().len());
+    assert_eq!(
+        stats.len,
+        stats.deletes + stats.updates + stats.moves + stats.mov_upds + stats.inserts
+    );
+    assert_eq!(report.candidate, report.reference);
+    let m = &report.mappings;
+    assert_eq!(m.common, m.candidate);
+    assert_eq!((m.precision, m.recall), (1., 1.));
+    assert!(m.mapped_differently.is_empty());
+    assert!(m.additional.is_empty() && m.missing.is_empty());
+}
+
+#[test]
+fn test_quality_other_diff() {
+    let mut stores = SimpleStores::<TStore>::default();
+    let src = parse(&mut stores, SRC.as_bytes());
+    let dst = parse(&mut stores, DST.as_bytes());
+    let candidate = algorithms::gumtree_simple::diff(&stores, &src, &dst);
+    let reference = algorithms::gumtree::diff(&stores, &src, &dst);
+    let reversed = algorithms::gumtree_simple::diff(&stores, &dst, &src);
+    let report = QualityReport::new(&candidate, &reference, Some(&reversed));
+    let m = &report.mappings;
+    let candidate_only = m.candidate - m.common;
+    assert_eq!(
+        candidate_only,
+        m.mapped_differently.len() + m.additional.len()
+    );
+    assert!(m.missing.len() <= m.reference - m.common);
+    assert!((0. ..=1.).contains(&m.precision) && (0. ..=1.).contains(&m.recall));
+    // reversing the comparison swaps precision and recall
+    let swapped = MappingComparison::new(
+        &reference.mapper.mapping.mappings,
+        &candidate.mapper.mapping.mappings,
+    );
+    assert_eq!((swapped.precision, swapped.recall), (m.recall, m.precision));
+    // a diff is stable with itself
+    let mappings = &candidate.mapper.mapping.mappings;
+    let mirrored = mappings.clone().mirror();
+    assert!(unstable_mappings(mappings, &mirrored).is_empty());
+    assert!(report.unstable.is_some());
+}
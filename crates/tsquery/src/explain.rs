@@ -0,0 +1,72 @@
+//! Profiling of a query search, to explain where its time goes.
+//!
+//! Enabled with [`crate::QueryCursor::explain`],
+//! the counts are then read from [`crate::QueryCursor::profile`],
+//! even if the search was interrupted, e.g. after a timeout.
+//! See also [`crate::Query::precomputed_hints`] to speed up the patterns that are rarely matched.
+
+use std::time::Duration;
+
+use crate::indexed::{PatternId, StepId};
+
+#[derive(Clone, Debug, Default)]
+pub struct QueryProfile {
+    /// visible nodes entered by the cursor
+    pub visited: u64,
+    /// subtrees not descended into, as they do not contain the precomputed patterns used by the query
+    pub skipped_subtrees: u64,
+    /// indexed by [`PatternId`], see [`QueryProfile::enabled_patterns`]
+    pub patterns: Vec<PatternProfile>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PatternProfile {
+    /// states started on a node matching the root of the pattern
+    pub started: u64,
+    /// states not started, as the subtree does not contain the precomputed patterns needed by the pattern
+    pub skipped: u64,
+    /// per step of the pattern, nodes compared with the step
+    pub steps: Vec<u64>,
+    /// matches found, before evaluating their predicates
+    pub matches: u64,
+    /// matches rejected by their predicates
+    pub rejected: u64,
+    /// time spent evaluating the predicates, including the immediate ones checked on each step
+    pub predicates: Duration,
+}
+
+impl QueryProfile {
+    pub(crate) fn new(query: &crate::Query) -> Self {
+        let patterns = (0..query.patterns.len()).map(|i| PatternProfile {
+            steps: vec![0; query.patterns[PatternId::new(i)].step_count()],
+            ..Default::default()
+        });
+        Self {
+            patterns: patterns.collect(),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn pattern(&mut self, pid: PatternId) -> &mut PatternProfile {
+        &mut self.patterns[pid.to_usize()]
+    }
+
+    pub(crate) fn step(&mut self, query: &crate::Query, pid: PatternId, step: StepId) {
+        let offset = query.patterns[pid].step_offset();
+        let i = (step.0 - offset.0) as usize;
+        if let Some(count) = self.pattern(pid).steps.get_mut(i) {
+            *count += 1;
+        }
+    }
+
+    /// The profiles of the patterns enabled in `query`, in the order of [`crate::Query::enabled_pattern_index`]
+    pub fn enabled_patterns<'a>(&'a self, query: &crate::Query) -> Vec<&'a PatternProfile> {
+        let mut enabled = vec![None; query.enabled_pattern_count()];
+        for (i, p) in self.patterns.iter().enumerate() {
+            if let Some(j) = query.enabled_pattern_index(PatternId::new(i)) {
+                enabled[j as usize] = Some(p);
+            }
+        }
+        enabled.into_iter().flatten().collect()
+    }
+}
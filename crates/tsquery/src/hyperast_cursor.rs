@@ -522,3 +522,120 @@ where
         }
     }
 }
+
+impl<'hast, HAST: HyperAST> Node<'hast, HAST>
+where
+    HAST::IdN: std::fmt::Debug + Copy,
+    HAST::IdN: hyperast::types::NodeId<IdN = HAST::IdN>,
+    for<'t> <HAST as hyperast::types::AstLending<'t>>::RT: hyperast::types::WithStats,
+    for<'t> <HAST as hyperast::types::AstLending<'t>>::RT:
+        hyperast::types::WithMetaData<hyperast::cyclomatic::Mcc>,
+{
+    /// Predicates on the structure of the HyperAST, to give to [`crate::QueryCursor::with_predicates`],
+    /// possibly extended with other ones e.g. on derived metrics.
+    ///
+    /// - `(#has-descendant? @a "kind")` a descendant of `@a` has type `kind`
+    /// - `(#count-gt? @a "n")` and `(#count-lt? @a "n")` compare the number of children of `@a`,
+    ///   hidden and anonymous ones included (`<` and `>` cannot appear in predicate names)
+    /// - `(#size-gt? @a "n")` and `(#size-lt? @a "n")` compare the number of nodes in `@a`
+    /// - `(#mcc-gt? @a "n")` and `(#mcc-lt? @a "n")` compare the cyclomatic complexity of `@a`,
+    ///   only stored on files, type declarations and executable members, see [`hyperast::cyclomatic::Mcc`]
+    /// - `(#same-type? @a @b)` `@a` and `@b` have the same type
+    /// - `(#label-in-file? @a "label")` the file containing `@a` has a node labeled `label`
+    ///
+    /// Predicates hold when they hold for one of the nodes of each capture.
+    pub fn structural_predicates() -> crate::predicate::CustomPredicates<Self> {
+        use crate::predicate::{CustomPredicates, PredicateArg};
+        use hyperast::types::{WithChildren, WithMetaData, WithStats};
+        use num::ToPrimitive;
+        fn nodes<'a, N>(args: &'a [PredicateArg<'a, N>], i: usize) -> &'a [&'a N] {
+            args.get(i).and_then(|a| a.nodes()).unwrap_or_default()
+        }
+        fn string<'a, N>(args: &[PredicateArg<'a, N>], i: usize) -> &'a str {
+            args.get(i).and_then(|a| a.str()).unwrap_or_default()
+        }
+        /// registers `metric-gt?` and `metric-lt?`, nodes without the metric do not satisfy them
+        fn compare<N>(
+            preds: &mut CustomPredicates<N>,
+            metric: &str,
+            f: impl Fn(&N) -> Option<usize> + Copy + Send + Sync + 'static,
+        ) {
+            preds.register(&format!("{metric}-gt?"), move |args| {
+                let Ok(c) = string(args, 1).parse::<usize>() else {
+                    return false;
+                };
+                nodes(args, 0).iter().any(|n| f(*n).is_some_and(|x| x > c))
+            });
+            preds.register(&format!("{metric}-lt?"), move |args| {
+                let Ok(c) = string(args, 1).parse::<usize>() else {
+                    return false;
+                };
+                nodes(args, 0).iter().any(|n| f(*n).is_some_and(|x| x < c))
+            });
+        }
+        let mut preds = CustomPredicates::default();
+        preds.register("has-descendant?", |args| {
+            let kind = string(args, 1);
+            nodes(args, 0).iter().any(|n| {
+                let id = *n.pos.node().unwrap();
+                n.any_descendant(id, |d| n.stores.resolve_type(&d).as_static_str() == kind)
+            })
+        });
+        compare(&mut preds, "count", |n: &Self| {
+            let n = n.stores.node_store().resolve(n.pos.node().unwrap());
+            n.child_count().to_usize()
+        });
+        compare(&mut preds, "size", |n: &Self| {
+            let n = n.stores.node_store().resolve(n.pos.node().unwrap());
+            Some(n.size())
+        });
+        compare(&mut preds, "mcc", |n: &Self| {
+            let n = n.stores.node_store().resolve(n.pos.node().unwrap());
+            let mcc: Option<&hyperast::cyclomatic::Mcc> = n.get_metadata();
+            mcc.map(|x| x.raw() as usize + 1)
+        });
+        preds.register("same-type?", |args| {
+            let others = nodes(args, 1);
+            nodes(args, 0)
+                .iter()
+                .any(|n| others.iter().any(|o| n.kind() == o.kind()))
+        });
+        preds.register("label-in-file?", |args| {
+            let label = string(args, 1);
+            nodes(args, 0).iter().any(|n| {
+                let mut file = (*n).clone();
+                while !file.kind().is_file() {
+                    if file.pos.pop().is_none() || file.pos.node().is_none() {
+                        return false;
+                    }
+                }
+                let id = *file.pos.node().unwrap();
+                n.any_descendant(id, |d| {
+                    let d = n.stores.node_store().resolve(&d);
+                    d.try_get_label()
+                        .is_some_and(|l| n.stores.label_store().resolve(l) == label)
+                })
+            })
+        });
+        preds
+    }
+
+    /// Whether `pred` holds for a strict descendant of `id`
+    fn any_descendant(&self, id: HAST::IdN, mut pred: impl FnMut(HAST::IdN) -> bool) -> bool {
+        use hyperast::types::WithChildren;
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let n = self.stores.node_store().resolve(&id);
+            let Some(cs) = n.children() else {
+                continue;
+            };
+            for c in cs {
+                if pred(c) {
+                    return true;
+                }
+                stack.push(c);
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,248 @@
+//! Precomputed patterns added to an already built HyperAST.
+//!
+//! The patterns given to the tree generators are matched while building,
+//! and stored with each node as the bits read by [`crate::Cursor::wont_match`].
+//! Patterns registered later cannot be stored with the nodes,
+//! instead their bits are computed lazily, per subtree, with a [`MemoizedQuery`],
+//! and then shared by the following queries and commits.
+//!
+//! Registered patterns take the bits after the ones stored with the nodes,
+//! so queries must be prepared with all the patterns,
+//! i.e. `Query::with_precomputed(query, language, lazy.precomputeds())`,
+//! then searched through a [`TreeCursor`].
+
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use hyperast::types::{HyperAST, LendT, NodeId, RoleStore, WithPrecompQueries, WithRoles};
+
+use crate::memoized::{MatchCache, MemoizedQuery};
+use crate::{CNLending, Cursor, Precomps, StatusLending, TreeCursorStep, WithField};
+
+/// Only one query per cache
+const HASH: crate::memoized::QueryHash = 0;
+/// Maximum number of subtrees with computed bits,
+/// beyond it the least recently used half is evicted
+const CACHE_LEN: usize = 1 << 20;
+
+pub struct LazyPrecomputed<IdN, Idx> {
+    stored: Vec<String>,
+    registered: Vec<String>,
+    query: crate::Query,
+    cache: Mutex<MatchCache<IdN, Idx>>,
+}
+
+impl<IdN, Idx> LazyPrecomputed<IdN, Idx> {
+    /// `stored` are the patterns precomputed while building the HyperAST,
+    /// `registered` the ones to add, one pattern per element.
+    ///
+    /// Fails with more than 16 patterns, as precomputed patterns are stored as `u16` bits.
+    pub fn new(
+        language: crate::Language,
+        stored: &[&str],
+        registered: &[&str],
+    ) -> Result<Self, crate::QueryError> {
+        if stored.len() + registered.len() > Precomps::BITS as usize {
+            let message = format!("at most {} precomputed patterns", Precomps::BITS);
+            return Err(structure_error(0, message));
+        }
+        for (i, pattern) in registered.iter().enumerate() {
+            let count = if pattern.trim().is_empty() {
+                0
+            } else {
+                crate::Query::new(pattern, language.clone())?.enabled_pattern_count()
+            };
+            if count != 1 {
+                let message = format!("precomputed entry {i} holds {count} patterns instead of 1");
+                return Err(structure_error(i, message));
+            }
+        }
+        let query = crate::Query::new(&registered.join("\n"), language)?;
+        Ok(Self {
+            stored: stored.iter().map(|x| x.to_string()).collect(),
+            registered: registered.iter().map(|x| x.to_string()).collect(),
+            query,
+            cache: Default::default(),
+        })
+    }
+
+    /// All the precomputed patterns, in the order of their bits
+    pub fn precomputeds(&self) -> Vec<&str> {
+        let stored = self.stored.iter().map(|x| x.as_str());
+        stored
+            .chain(self.registered.iter().map(|x| x.as_str()))
+            .collect()
+    }
+
+    pub fn registered(&self) -> &[String] {
+        &self.registered
+    }
+
+    /// Bits of the patterns stored with the nodes
+    fn stored_mask(&self) -> Precomps {
+        ((1u32 << self.stored.len()) - 1) as Precomps
+    }
+}
+
+impl<IdN: Hash + Eq + Clone, Idx> LazyPrecomputed<IdN, Idx> {
+    /// Number of subtrees with computed bits
+    pub fn computed(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+
+    /// Forgets the computed bits, e.g. after a garbage collection, as the identifiers of freed subtrees can be reused
+    pub fn clear(&self) {
+        *self.cache.lock().unwrap() = Default::default();
+    }
+
+    /// Bits of the registered patterns (shifted after the stored ones) matching in `id`,
+    /// computing them for the subtrees of `id` not already visited.
+    pub fn bits<HAST>(&self, stores: &HAST, id: IdN) -> Precomps
+    where
+        HAST: HyperAST<IdN = IdN, Idx = Idx>,
+        HAST::IdN: Debug + Copy,
+        HAST::IdN: NodeId<IdN = HAST::IdN>,
+        HAST::TS: RoleStore,
+        <HAST::TS as RoleStore>::IdF: Into<u16> + From<u16>,
+        for<'t> LendT<'t, HAST>: WithRoles + WithPrecompQueries,
+    {
+        let mut cache = self.cache.lock().unwrap();
+        let matches = MemoizedQuery::new(&self.query, HASH).eval(stores, &mut cache, id);
+        if cache.len() > CACHE_LEN {
+            cache.evict(CACHE_LEN / 2, None);
+        }
+        let mut bits = 0;
+        for (i, count) in matches.counts.iter().enumerate() {
+            if *count > 0 {
+                bits |= 1 << (self.stored.len() + i);
+            }
+        }
+        bits
+    }
+}
+
+fn structure_error(row: usize, message: String) -> crate::QueryError {
+    crate::QueryError {
+        kind: crate::QueryErrorKind::Structure,
+        row,
+        column: 0,
+        offset: 0,
+        message,
+    }
+}
+
+/// A [`crate::hyperast_cursor::TreeCursor`] also skipping subtrees
+/// without the patterns of a [`LazyPrecomputed`]
+pub struct TreeCursor<'hast, HAST: HyperAST> {
+    pub inner: crate::hyperast_cursor::TreeCursor<'hast, HAST>,
+    pub precomputed: &'hast LazyPrecomputed<HAST::IdN, HAST::Idx>,
+}
+
+impl<'hast, HAST: HyperAST> TreeCursor<'hast, HAST> {
+    pub fn new(
+        stores: &'hast HAST,
+        pos: hyperast::position::StructuralPosition<HAST::IdN, HAST::Idx>,
+        precomputed: &'hast LazyPrecomputed<HAST::IdN, HAST::Idx>,
+    ) -> Self {
+        let inner = crate::hyperast_cursor::TreeCursor::new(stores, pos);
+        Self { inner, precomputed }
+    }
+}
+
+impl<HAST: HyperAST> WithField for TreeCursor<'_, HAST>
+where
+    HAST::TS: RoleStore,
+{
+    type IdF = <HAST::TS as RoleStore>::IdF;
+}
+
+impl<'hast, HAST: HyperAST> CNLending<'_> for TreeCursor<'hast, HAST>
+where
+    HAST::IdN: Debug + Copy,
+    HAST::TS: RoleStore,
+    for<'t> LendT<'t, HAST>: WithRoles + WithPrecompQueries,
+    HAST::IdN: NodeId<IdN = HAST::IdN>,
+{
+    type NR = crate::hyperast_cursor::Node<'hast, HAST>;
+}
+
+impl<'hast, HAST: HyperAST> StatusLending<'_> for TreeCursor<'hast, HAST>
+where
+    HAST::IdN: Debug + Copy,
+    HAST::TS: RoleStore,
+    for<'t> LendT<'t, HAST>: WithRoles + WithPrecompQueries,
+    HAST::IdN: NodeId<IdN = HAST::IdN>,
+{
+    type Status = crate::hyperast_cursor::CursorStatus<<HAST::TS as RoleStore>::IdF>;
+}
+
+impl<'hast, HAST: HyperAST> Cursor for TreeCursor<'hast, HAST>
+where
+    HAST::IdN: Debug + Copy + Hash + Eq,
+    HAST::TS: RoleStore,
+    <HAST::TS as RoleStore>::IdF: Into<u16> + From<u16>,
+    for<'t> LendT<'t, HAST>: WithRoles + WithPrecompQueries,
+    HAST::IdN: NodeId<IdN = HAST::IdN>,
+{
+    type Node = crate::hyperast_cursor::Node<'hast, HAST>;
+
+    fn goto_next_sibling_internal(&mut self) -> TreeCursorStep {
+        self.inner.goto_next_sibling_internal()
+    }
+
+    fn goto_first_child_internal(&mut self) -> TreeCursorStep {
+        self.inner.goto_first_child_internal()
+    }
+
+    fn goto_parent(&mut self) -> bool {
+        self.inner.goto_parent()
+    }
+
+    fn current_node(&self) -> <Self as CNLending<'_>>::NR {
+        self.inner.current_node()
+    }
+
+    fn parent_is_error(&self) -> bool {
+        self.inner.parent_is_error()
+    }
+
+    fn current_status(&self) -> <Self as StatusLending<'_>>::Status {
+        self.inner.current_status()
+    }
+
+    fn text_provider(&self) -> <Self::Node as crate::TextLending<'_>>::TP {
+        self.inner.text_provider()
+    }
+
+    fn wont_match(&self, needed: Precomps) -> bool {
+        let stored = self.precomputed.stored_mask();
+        if self.inner.wont_match(needed & stored) {
+            return true;
+        }
+        let needed = needed & !stored;
+        if needed == 0 {
+            return false;
+        }
+        use hyperast::position::TreePath;
+        let id = *self.inner.pos.node().unwrap();
+        let bits = self.precomputed.bits(self.inner.stores, id);
+        bits & needed != needed
+    }
+
+    fn is_visible_at_root(&self) -> bool {
+        self.inner.is_visible_at_root()
+    }
+
+    fn has_parent(&self) -> bool {
+        self.inner.has_parent()
+    }
+
+    fn persist(&self) -> Self::Node {
+        self.inner.persist()
+    }
+
+    fn persist_parent(&self) -> Option<Self::Node> {
+        self.inner.persist_parent()
+    }
+}
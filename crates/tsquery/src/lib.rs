@@ -20,10 +20,16 @@ mod ffi_extra;
 mod ts_private_bypass;
 
 pub mod default_impls;
+#[cfg(feature = "cursor_counts")]
+pub mod explain;
 #[cfg(feature = "hyperast")]
 pub mod hyperast_cursor;
 #[cfg(feature = "hyperast")]
 pub mod hyperast_opt;
+#[cfg(feature = "hyperast")]
+pub mod lazy_precomputed;
+#[cfg(feature = "hyperast")]
+pub mod memoized;
 pub mod stepped_query;
 pub mod stepped_query_imm;
 pub mod tsg;
@@ -120,6 +126,7 @@ impl Query {
                 next_state_id: indexed::StateId::ZERO,
             },
             max_start_depth: u32::MAX,
+            custom_predicates: None,
             depth: 0,
             on_visible_node: cursor.is_visible_at_root(),
             halted: false,
@@ -129,6 +136,8 @@ impl Query {
             status_count: 0,
             #[cfg(feature = "cursor_counts")]
             goto_count: 0,
+            #[cfg(feature = "cursor_counts")]
+            profile: None,
         }
     }
 
@@ -174,6 +183,8 @@ pub struct QueryCursor<'query, Cursor, Node> {
     pub depth: Depth,
     pub max_start_depth: Depth,
     pub exec_state: QueryExecState<Node>,
+    /// evaluated on the general predicates of matches, see [`QueryCursor::with_predicates`]
+    pub custom_predicates: Option<&'query predicate::CustomPredicates<Node>>,
 
     #[cfg(feature = "cursor_counts")]
     #[doc(hidden)]
@@ -184,6 +195,10 @@ pub struct QueryCursor<'query, Cursor, Node> {
     #[doc(hidden)]
     /// once per successful `hyperast_tsquery::Cursor::goto_*()`
     pub goto_count: usize,
+
+    #[cfg(feature = "cursor_counts")]
+    /// filled while searching, see [`QueryCursor::explain`]
+    pub profile: Option<Box<explain::QueryProfile>>,
 }
 
 pub struct QueryExecState<Node> {
@@ -196,10 +211,28 @@ pub struct QueryExecState<Node> {
     pub did_exceed_match_limit: bool,
 }
 
-impl<Cursor, N> QueryCursor<'_, Cursor, N> {
+impl<'query, Cursor, N> QueryCursor<'query, Cursor, N> {
     pub fn cursor(&self) -> &Cursor {
         &self.cursor
     }
+
+    /// Only yields matches that also satisfy the `predicates` used in their pattern
+    pub fn with_predicates(mut self, predicates: &'query predicate::CustomPredicates<N>) -> Self {
+        self.custom_predicates = Some(predicates);
+        self
+    }
+
+    /// Profiles the search per pattern and per step, see [`crate::explain`]
+    #[cfg(feature = "cursor_counts")]
+    pub fn explain(mut self) -> Self {
+        self.profile = Some(Box::new(explain::QueryProfile::new(self.query)));
+        self
+    }
+
+    #[cfg(feature = "cursor_counts")]
+    pub fn profile(&self) -> Option<&explain::QueryProfile> {
+        self.profile.as_deref()
+    }
 }
 
 #[derive(Clone)]
@@ -421,13 +454,28 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             let result = self.next_match()?;
-            if result.satisfies_text_predicates(
+            #[cfg(feature = "cursor_counts")]
+            let now = self.profile.is_some().then(std::time::Instant::now);
+            let satisfied = result.satisfies_text_predicates(
                 self.cursor.text_provider(),
                 self.query
                     .text_predicates_for_pattern_id(result.pattern_index),
-            ) {
-                return Some(result);
+            ) && self.custom_predicates.is_none_or(|custom| {
+                let preds =
+                    (self.query.general_predicates).preds_for_patern_id(result.pattern_index);
+                custom.satisfies(preds, |i| result.nodes_for_capture_index(i).collect())
+            });
+            #[cfg(feature = "cursor_counts")]
+            if let (Some(profile), Some(now)) = (&mut self.profile, now) {
+                let pattern = profile.pattern(result.pattern_index);
+                pattern.predicates += now.elapsed();
+                pattern.matches += 1;
+                pattern.rejected += !satisfied as u64;
+            }
+            if !satisfied {
+                continue;
             }
+            return Some(result);
         }
     }
 }
@@ -0,0 +1,220 @@
+//! Query evaluation memoised per subtree.
+//!
+//! HyperAST subtrees are shared across versions,
+//! so the matches in a subtree only have to be computed once per query,
+//! evaluating a new version then only visits its new subtrees.
+//!
+//! Matches are computed for each visible node with [`crate::Query::matches_immediate`],
+//! a node being queried without its context.
+//! Thus patterns that start on a supertype are not matched,
+//! [`crate::Query::matches`] should be used for such queries.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use hyperast::position::StructuralPosition;
+use hyperast::position::position_accessors::WithPreOrderOffsets;
+use hyperast::types::{
+    HyperAST, HyperType, LendT, NodeId, NodeStore, RoleStore, WithChildren, WithPrecompQueries,
+    WithRoles,
+};
+
+use crate::CaptureId;
+
+/// Identifies a query in a [`MatchCache`] shared by several queries,
+/// e.g. a hash of its source and of its precomputed patterns
+pub type QueryHash = u64;
+
+/// A match starting at the root of a subtree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoizedMatch<Idx> {
+    /// index among the enabled patterns
+    pub pattern: u16,
+    /// captured nodes, as offsets from the root of the subtree
+    pub captures: Box<[(CaptureId, Box<[Idx]>)]>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubtreeMatches<Idx> {
+    /// number of matches per enabled pattern, in the whole subtree
+    pub counts: Box<[u64]>,
+    pub immediate: Box<[MemoizedMatch<Idx>]>,
+}
+
+/// Matches per subtree, keyed by `(query hash, subtree)`
+pub struct MatchCache<IdN, Idx> {
+    /// along with the last evaluation that used each entry
+    map: HashMap<(QueryHash, IdN), (Arc<SubtreeMatches<Idx>>, u64)>,
+    /// incremented by each evaluation
+    clock: u64,
+}
+
+impl<IdN, Idx> Default for MatchCache<IdN, Idx> {
+    fn default() -> Self {
+        Self {
+            map: Default::default(),
+            clock: 0,
+        }
+    }
+}
+
+impl<IdN: Hash + Eq, Idx> MatchCache<IdN, Idx> {
+    pub fn get(&self, query: QueryHash, id: IdN) -> Option<&Arc<SubtreeMatches<Idx>>> {
+        self.map.get(&(query, id)).map(|(x, _)| x)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Forgets the matches of `query`, e.g. once it is not queried anymore
+    pub fn remove_query(&mut self, query: QueryHash) {
+        self.map.retain(|(q, _), _| *q != query);
+    }
+
+    /// Forgets the least recently used subtrees, until at most `max_len` subtrees remain,
+    /// the ones of `current` are kept, so a query larger than the cache still reuses its subtrees.
+    pub fn evict(&mut self, max_len: usize, current: Option<QueryHash>)
+    where
+        IdN: Clone,
+    {
+        let Some(excess) = self.map.len().checked_sub(max_len).filter(|x| *x > 0) else {
+            return;
+        };
+        let mut others: Vec<_> = (self.map.iter())
+            .filter(|((q, _), _)| Some(*q) != current)
+            .map(|(k, (_, t))| (*t, k.clone()))
+            .collect();
+        let excess = excess.min(others.len());
+        if excess == 0 {
+            return;
+        }
+        others.select_nth_unstable_by_key(excess - 1, |(t, _)| *t);
+        for (_, k) in &others[..excess] {
+            self.map.remove(k);
+        }
+    }
+}
+
+pub struct MemoizedQuery<'q> {
+    query: &'q crate::Query,
+    hash: QueryHash,
+}
+
+impl<'q> MemoizedQuery<'q> {
+    pub fn new(query: &'q crate::Query, hash: QueryHash) -> Self {
+        Self { query, hash }
+    }
+
+    pub fn hash(&self) -> QueryHash {
+        self.hash
+    }
+
+    /// Computes the matches in `root`, reusing the ones of subtrees already in `cache`
+    pub fn eval<HAST: HyperAST>(
+        &self,
+        stores: &HAST,
+        cache: &mut MatchCache<HAST::IdN, HAST::Idx>,
+        root: HAST::IdN,
+    ) -> Arc<SubtreeMatches<HAST::Idx>>
+    where
+        HAST::IdN: Debug + Copy + Hash + Eq,
+        HAST::IdN: NodeId<IdN = HAST::IdN>,
+        HAST::TS: RoleStore,
+        <HAST::TS as RoleStore>::IdF: Into<u16> + From<u16>,
+        for<'t> LendT<'t, HAST>: WithRoles + WithPrecompQueries,
+    {
+        self.eval_until(stores, cache, root, |_| false)
+            .expect("never stopped")
+    }
+
+    /// Same as [`MemoizedQuery::eval`], but stops once `stop` returns true,
+    /// `stop` being given the counts of each newly computed subtree, e.g. to check a timeout.
+    ///
+    /// The subtrees computed before stopping stay in `cache`,
+    /// and as counts only increase towards the root, a limit exceeded in a subtree is exceeded in `root`.
+    pub fn eval_until<HAST: HyperAST>(
+        &self,
+        stores: &HAST,
+        cache: &mut MatchCache<HAST::IdN, HAST::Idx>,
+        root: HAST::IdN,
+        mut stop: impl FnMut(&[u64]) -> bool,
+    ) -> Option<Arc<SubtreeMatches<HAST::Idx>>>
+    where
+        HAST::IdN: Debug + Copy + Hash + Eq,
+        HAST::IdN: NodeId<IdN = HAST::IdN>,
+        HAST::TS: RoleStore,
+        <HAST::TS as RoleStore>::IdF: Into<u16> + From<u16>,
+        for<'t> LendT<'t, HAST>: WithRoles + WithPrecompQueries,
+    {
+        let hash = self.hash;
+        cache.clock += 1;
+        let now = cache.clock;
+        // post-order traversal of the subtrees not in cache
+        let mut stack = vec![(root, false)];
+        while let Some((id, expanded)) = stack.pop() {
+            if let Some((_, used)) = cache.map.get_mut(&(hash, id)) {
+                *used = now;
+                continue;
+            }
+            let n = stores.node_store().resolve(&id);
+            let cs: Vec<_> = n.children().map_or(vec![], |cs| cs.collect());
+            if !expanded {
+                stack.push((id, true));
+                stack.extend(cs.into_iter().map(|c| (c, false)));
+                continue;
+            }
+            let mut counts = vec![0; self.query.enabled_pattern_count()];
+            for c in cs {
+                let (c, _) = &cache.map[&(hash, c)];
+                counts.iter_mut().zip(&c.counts).for_each(|(x, c)| *x += c);
+            }
+            let kind = stores.resolve_type(&id);
+            let immediate: Box<[_]> = if kind.is_hidden() || kind.is_spaces() {
+                Box::default()
+            } else {
+                self.immediate(stores, id).collect()
+            };
+            for m in immediate.iter() {
+                counts[m.pattern as usize] += 1;
+            }
+            let counts = counts.into();
+            let matches = Arc::new(SubtreeMatches { counts, immediate });
+            let stopped = stop(&matches.counts);
+            cache.map.insert((hash, id), (matches, now));
+            if stopped {
+                return None;
+            }
+        }
+        Some(cache.map[&(hash, root)].0.clone())
+    }
+
+    fn immediate<'a, HAST: HyperAST>(
+        &'a self,
+        stores: &'a HAST,
+        id: HAST::IdN,
+    ) -> impl Iterator<Item = MemoizedMatch<HAST::Idx>> + 'a
+    where
+        HAST::IdN: Debug + Copy,
+        HAST::IdN: NodeId<IdN = HAST::IdN>,
+        HAST::TS: RoleStore,
+        <HAST::TS as RoleStore>::IdF: Into<u16> + From<u16>,
+        for<'t> LendT<'t, HAST>: WithRoles + WithPrecompQueries,
+    {
+        let cursor = crate::hyperast_cursor::TreeCursor::new(stores, StructuralPosition::new(id));
+        let query = self.query;
+        query.matches_immediate(cursor).map(move |m| MemoizedMatch {
+            pattern: query.enabled_pattern_index(m.pattern_index).unwrap(),
+            captures: (&m.captures)
+                .into_iter()
+                .map(|c| (c.index, c.node.pos.iter_offsets().collect()))
+                .collect(),
+        })
+    }
+}
@@ -55,7 +55,106 @@ pub struct QueryPredicate {
     pub args: Box<[QueryPredicateArg]>,
 }
 
+/// Argument given to a [`CustomPredicate`], captures being resolved to the captured nodes
+pub enum PredicateArg<'a, N> {
+    Capture(Vec<&'a N>),
+    String(&'a str),
+}
+
+impl<'a, N> PredicateArg<'a, N> {
+    pub fn nodes(&self) -> Option<&[&'a N]> {
+        match self {
+            PredicateArg::Capture(nodes) => Some(nodes),
+            PredicateArg::String(_) => None,
+        }
+    }
+
+    pub fn str(&self) -> Option<&'a str> {
+        match self {
+            PredicateArg::Capture(_) => None,
+            PredicateArg::String(s) => Some(s),
+        }
+    }
+}
+
+/// A predicate function, given the arguments of its call in a query
+pub type CustomPredicate<N> = dyn Fn(&[PredicateArg<'_, N>]) -> bool + Send + Sync;
+
+/// Predicate functions by operator name, e.g. `same-type?` for `(#same-type? @a @b)`.
+///
+/// They are evaluated on the [`GeneralPredicates`] of each match by [`crate::QueryCursor::with_predicates`],
+/// general predicates without registered function being ignored as before.
+pub struct CustomPredicates<N> {
+    fcts: std::collections::HashMap<Box<str>, std::sync::Arc<CustomPredicate<N>>>,
+}
+
+impl<N> Default for CustomPredicates<N> {
+    fn default() -> Self {
+        Self {
+            fcts: Default::default(),
+        }
+    }
+}
+
+impl<N> Clone for CustomPredicates<N> {
+    fn clone(&self) -> Self {
+        Self {
+            fcts: self.fcts.clone(),
+        }
+    }
+}
+
+impl<N> Debug for CustomPredicates<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.fcts.keys()).finish()
+    }
+}
+
+impl<N> CustomPredicates<N> {
+    /// Replaces any predicate already registered as `operator`
+    pub fn register(
+        &mut self,
+        operator: &str,
+        f: impl Fn(&[PredicateArg<'_, N>]) -> bool + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.fcts.insert(operator.into(), std::sync::Arc::new(f));
+        self
+    }
+
+    pub fn get(&self, operator: &str) -> Option<&CustomPredicate<N>> {
+        self.fcts.get(operator).map(|f| f.as_ref())
+    }
+
+    pub fn extend(&mut self, other: &Self) {
+        (self.fcts).extend(other.fcts.iter().map(|(k, f)| (k.clone(), f.clone())));
+    }
+
+    pub(crate) fn satisfies<'p, 'n>(
+        &self,
+        mut predicates: impl Iterator<Item = &'p QueryPredicate>,
+        captures: impl Fn(super::CaptureId) -> Vec<&'n N>,
+    ) -> bool {
+        predicates.all(|p| {
+            let Some(f) = self.get(&p.operator) else {
+                return true;
+            };
+            let args: Vec<_> = (p.args.iter())
+                .map(|a| match a {
+                    QueryPredicateArg::Capture(i) => PredicateArg::Capture(captures((*i).into())),
+                    QueryPredicateArg::String(s) => PredicateArg::String(s),
+                })
+                .collect();
+            f(&args)
+        })
+    }
+}
+
 impl<P: Debug> PerPattern<P> {
+    /// Whether none of the patterns has predicates
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|x| x.is_empty())
+    }
+
     pub fn preds_for_patern_id(&self, id: crate::indexed::PatternId) -> impl Iterator<Item = &P> {
         self.0[id.to_usize()].iter()
     }
@@ -55,6 +55,14 @@ impl QueryPattern {
     fn is_empty(&self) -> bool {
         self.steps.length == StepId::new(1)
     }
+
+    pub(crate) fn step_offset(&self) -> StepId {
+        self.steps.offset
+    }
+
+    pub(crate) fn step_count(&self) -> usize {
+        self.steps.length.0 as usize
+    }
 }
 
 impl From<&crate::ffi_extra::QueryPattern> for QueryPattern {
@@ -1524,6 +1532,47 @@ impl Query {
         let i = self.enabled_pattern_map[pid.to_usize()];
         (i != u16::MAX).then_some(i)
     }
+    /// For each enabled pattern, the precomputed patterns (as bits, see [`Query::with_precomputed`])
+    /// needed in a subtree for the pattern to match in it,
+    /// i.e. the ones speeding up its search.
+    pub fn precomputed_uses(&self) -> Vec<Precomps> {
+        let mut uses = vec![0; self.enabled_pattern_count()];
+        for p in &self.pattern_map {
+            if let Some(i) = self.enabled_pattern_index(p.pattern_index) {
+                uses[i as usize] |= p.precomputed;
+            }
+        }
+        uses
+    }
+    /// For each enabled pattern not using any precomputed pattern (see [`Query::precomputed_uses`]),
+    /// its root as a pattern to precompute, e.g. `(method_declaration)`.
+    ///
+    /// Once precomputed, subtrees without such a root are skipped when searching the pattern.
+    /// Patterns starting with a wildcard or an anonymous node get no hint.
+    pub fn precomputed_hints(&self) -> Vec<Option<String>> {
+        let uses = self.precomputed_uses();
+        let mut hints = vec![None; self.enabled_pattern_count()];
+        for i in 0..self.patterns.len() {
+            let pid = PatternId::new(i);
+            let Some(j) = self.enabled_pattern_index(pid) else {
+                continue;
+            };
+            if uses[j as usize] != 0 {
+                continue;
+            }
+            let root = &self.steps[self.patterns[pid].steps.offset];
+            if root.done() || root.is_wildcard() || !root.is_named() || root.depth != 0 {
+                continue;
+            }
+            let ptr = unsafe { ffi::ts_language_symbol_name(self.language, root.symbol) };
+            if ptr.is_null() {
+                continue;
+            }
+            let name = unsafe { std::ffi::CStr::from_ptr(ptr) }.to_str().unwrap();
+            hints[j as usize] = Some(format!("({})", name));
+        }
+        hints
+    }
     #[allow(clippy::result_large_err)]
     pub fn with_one_pattern_enabled(mut self, i: u16) -> Result<Self, Self> {
         if i == u16::MAX
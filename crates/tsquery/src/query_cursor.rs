@@ -292,6 +292,9 @@ where
             #[cfg(feature = "cursor_counts")]
             {
                 self.status_count += 1;
+                if let Some(profile) = &mut self.profile {
+                    profile.visited += 1;
+                }
             }
             log::trace!(
                 "enter node. depth:{}, type:{}, field:{}, row:{} state_count:{}, finished_state_count:{}",
@@ -333,6 +336,10 @@ where
                             || self.max_start_depth == 0)
                         && (start_depth <= self.max_start_depth);
                     if should_add {
+                        #[cfg(feature = "cursor_counts")]
+                        if let Some(profile) = &mut self.profile {
+                            profile.pattern(pattern.pattern_index).started += 1;
+                        }
                         self.exec_state.add_state(query, self.depth, pattern);
                     }
                 }
@@ -358,7 +365,17 @@ where
                     } && step.constrained(status.field_id().into())
                         && (start_depth <= self.max_start_depth);
                     if can_start {
-                        if Self::wont_match(pattern, &self.cursor, pat) {
+                        let wont_match = Self::wont_match(pattern, &self.cursor, pat);
+                        #[cfg(feature = "cursor_counts")]
+                        if let Some(profile) = &mut self.profile {
+                            let profile = profile.pattern(pattern.pattern_index);
+                            if wont_match {
+                                profile.skipped += 1;
+                            } else {
+                                profile.started += 1;
+                            }
+                        }
+                        if wont_match {
                             log::trace!(
                                 "don't start. type:{}",
                                 self.cursor.current_node().str_symbol()
@@ -419,6 +436,11 @@ where
                     continue;
                 }
 
+                #[cfg(feature = "cursor_counts")]
+                if let Some(profile) = &mut self.profile {
+                    profile.step(query, state!().pattern_index, state!().step_index);
+                }
+
                 // Get the properties of the current node.
                 let node = self.cursor.current_node();
 
@@ -476,6 +498,9 @@ where
                 //     // dbg!(symbol);
                 // }
 
+                #[cfg(feature = "cursor_counts")]
+                let now = (self.profile.is_some() && state!(@step).has_immediate_pred())
+                    .then(std::time::Instant::now);
                 if node_does_match {
                     if let Some(pred_id) = state!(@step).immediate_pred() {
                         // TODO cache verdict in bitset outside of state loop !
@@ -515,6 +540,10 @@ where
                         }
                     }
                 }
+                #[cfg(feature = "cursor_counts")]
+                if let (Some(profile), Some(now)) = (&mut self.profile, now) {
+                    profile.pattern(state!().pattern_index).predicates += now.elapsed();
+                }
                 drop(node);
 
                 // Remove states immediately if it is ever clear that they cannot match.
@@ -848,7 +877,7 @@ impl<Cursor, Node> QueryCursor<'_, Cursor, Node> {
 }
 
 impl<Cursor: super::Cursor> QueryCursor<'_, Cursor, Cursor::Node> {
-    fn should_descend(&self, node_intersects_range: bool) -> bool {
+    fn should_descend(&mut self, node_intersects_range: bool) -> bool {
         if node_intersects_range && self.depth < self.max_start_depth {
             if self.cursor.wont_match(self.query.used_precomputed) {
                 for i in 0..self.exec_state.states.len() {
@@ -867,6 +896,10 @@ impl<Cursor: super::Cursor> QueryCursor<'_, Cursor, Cursor::Node> {
                     "skip subtree. type:{}",
                     self.cursor.current_node().str_symbol()
                 );
+                #[cfg(feature = "cursor_counts")]
+                if let Some(profile) = &mut self.profile {
+                    profile.skipped_subtrees += 1;
+                }
                 return false;
             }
             return true;
@@ -885,6 +918,10 @@ impl<Cursor: super::Cursor> QueryCursor<'_, Cursor, Cursor::Node> {
             return false;
         }
         if self.cursor.wont_match(self.query.used_precomputed) {
+            #[cfg(feature = "cursor_counts")]
+            if let Some(profile) = &mut self.profile {
+                profile.skipped_subtrees += 1;
+            }
             return false;
         }
 
@@ -1210,3 +1210,85 @@ where
         post.compressed_node
     }
 }
+
+/// Registers the components specific to this language, to snapshot stores containing its subtrees.
+pub fn register_persisted_components(
+    registry: &mut hyperast::store::nodes::legion::persist::ComponentRegistry,
+) {
+    registry
+        .register_lang::<crate::types::Lang>()
+        .register_type::<crate::types::Lang>();
+}
+
+impl MD {
+    /// Persists the metadata, except when it holds a reference analysis, then nothing is written.
+    pub fn save(&self, w: &mut impl std::io::Write) -> std::io::Result<bool> {
+        use hyperast::store::nodes::legion::persist::*;
+        if self.ana.is_some() {
+            return Ok(false);
+        }
+        write_metrics(w, &self.metrics)?;
+        write_u32(w, self.mcc.raw())?;
+        w.write_all(&self.precomp_queries.to_le_bytes())?;
+        Ok(true)
+    }
+
+    pub fn load(r: &mut impl std::io::Read) -> std::io::Result<Self> {
+        use hyperast::store::nodes::legion::persist::*;
+        let metrics = read_metrics(r)?;
+        let mcc = Mcc::from_raw(read_u32_from(r)?);
+        let mut b = [0; 2];
+        r.read_exact(&mut b)?;
+        Ok(Self {
+            metrics,
+            ana: None,
+            mcc,
+            precomp_queries: PrecompQueries::from_le_bytes(b),
+        })
+    }
+}
+
+impl Local {
+    /// Persists the local, except when it holds a reference analysis, then nothing is written.
+    pub fn save(&self, w: &mut impl std::io::Write) -> std::io::Result<bool> {
+        use hyperast::store::nodes::legion::persist::*;
+        if self.ana.is_some() {
+            return Ok(false);
+        }
+        write_id(w, self.compressed_node)?;
+        write_metrics(w, &self.metrics)?;
+        write_u32(w, self.mcc.raw())?;
+        let role = self.role.map_or(u8::MAX, |r| r as u8);
+        w.write_all(&[role, self.stmt_count, self.member_import_count])?;
+        w.write_all(&self.precomp_queries.to_le_bytes())?;
+        Ok(true)
+    }
+
+    pub fn load(r: &mut impl std::io::Read) -> std::io::Result<Self> {
+        use hyperast::store::nodes::legion::persist::*;
+        let compressed_node = read_id(r)?;
+        let metrics = read_metrics(r)?;
+        let mcc = Mcc::from_raw(read_u32_from(r)?);
+        let mut b = [0; 3];
+        r.read_exact(&mut b)?;
+        let [role, stmt_count, member_import_count] = b;
+        let role = match role {
+            u8::MAX => None,
+            x => Some(role_from_u8(x).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "bad role")
+            })?),
+        };
+        let mut b = [0; 2];
+        r.read_exact(&mut b)?;
+        Ok(Self {
+            compressed_node,
+            metrics,
+            ana: None,
+            mcc,
+            role,
+            precomp_queries: PrecompQueries::from_le_bytes(b),
+            stmt_count,
+            member_import_count,
+        })
+    }
+}
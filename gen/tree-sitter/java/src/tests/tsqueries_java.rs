@@ -317,6 +317,229 @@ public class TestDisableRouterQuota {
     // insta::assert_snapshot!(run_prepro(query, &prepro, text), @"1");
 }
 
+#[test]
+fn test_structural_predicates() {
+    let text = r#"
+class A {
+    int f(int a, int b) {
+        return a + b;
+    }
+    int g() {
+        return 1 + a;
+    }
+}
+    "#;
+    let text = text.as_bytes();
+    let query = r#"(binary_expression left: (_) @l right: (_) @r (#same-type? @l @r))"#;
+    assert_eq!(1, run_with_predicates(query, text));
+    let query =
+        r#"(method_declaration body: (block) @b (#has-descendant? @b "decimal_integer_literal"))"#;
+    assert_eq!(1, run_with_predicates(query, text));
+    let query = r#"((block) @b (#count-gt? @b "0"))"#;
+    assert_eq!(2, run_with_predicates(query, text));
+    let query = r#"((block) @b (#count-lt? @b "0"))"#;
+    assert_eq!(0, run_with_predicates(query, text));
+    let query = r#"(class_declaration name: (identifier) @n (#label-in-file? @n "b"))"#;
+    assert_eq!(1, run_with_predicates(query, text));
+    let query = r#"(class_declaration name: (identifier) @n (#label-in-file? @n "c"))"#;
+    assert_eq!(0, run_with_predicates(query, text));
+    // unregistered predicates are ignored
+    let query = r#"((block) @b (#unknown? @b))"#;
+    assert_eq!(2, run_with_predicates(query, text));
+}
+
+#[test]
+fn test_metric_predicates() {
+    let text = r#"
+class A {
+    int f(int a) {
+        if (a > 0) {
+            return a;
+        }
+        return 0;
+    }
+    int g() {
+        return 1;
+    }
+}
+    "#;
+    let text = text.as_bytes();
+    let query = r#"((method_declaration) @m (#mcc-gt? @m "1"))"#;
+    assert_eq!(1, run_with_predicates(query, text));
+    let query = r#"((method_declaration) @m (#mcc-lt? @m "2"))"#;
+    assert_eq!(1, run_with_predicates(query, text));
+    // the complexity is only stored on files, type declarations and executable members
+    let query = r#"((block) @b (#mcc-gt? @b "0"))"#;
+    assert_eq!(0, run_with_predicates(query, text));
+    let query = r#"((method_declaration) @m (#size-gt? @m "30"))"#;
+    assert_eq!(1, run_with_predicates(query, text));
+    let query = r#"((method_declaration) @m (#size-lt? @m "1"))"#;
+    assert_eq!(0, run_with_predicates(query, text));
+}
+
+#[cfg(test)]
+fn run_with_predicates(query: &str, text: &[u8]) -> usize {
+    let (query, stores, code) = prep_stepped(query, text);
+    let pos = hyperast::position::StructuralPosition::new(code);
+    let cursor = hyperast_tsquery::hyperast_cursor::TreeCursor::new(&stores, pos);
+    let predicates = hyperast_tsquery::hyperast_cursor::Node::structural_predicates();
+    query.matches(cursor).with_predicates(&predicates).count()
+}
+
+#[test]
+fn test_memoized_query() {
+    use hyperast_tsquery::memoized::{MatchCache, MemoizedQuery};
+    let text = r#"
+class A {
+    int f(int a) {
+        return a + 1;
+    }
+    void g() {}
+}
+    "#;
+    let text2 = r#"
+class A {
+    int f(int a) {
+        return a + 1;
+    }
+    void g() {
+        h();
+    }
+    void h() {}
+}
+    "#;
+    let query = r#"(method_declaration name: (identifier) @name)"#;
+    let (query, mut stores, code) = prep_stepped(query, text.as_bytes());
+    let code2 = {
+        use crate::legion_with_refs;
+        let mut md_cache = Default::default();
+        let mut java_tree_gen = legion_with_refs::JavaTreeGen::new(&mut stores, &mut md_cache);
+        let tree = match legion_with_refs::tree_sitter_parse(text2.as_bytes()) {
+            Ok(t) => t,
+            Err(t) => t,
+        };
+        let full_node = java_tree_gen.generate_file(b"", text2.as_bytes(), tree.walk());
+        full_node.local.compressed_node
+    };
+    let count = |code| {
+        let pos = hyperast::position::StructuralPosition::new(code);
+        let cursor = hyperast_tsquery::hyperast_cursor::TreeCursor::new(&stores, pos);
+        query.matches(cursor).count() as u64
+    };
+    let mut cache = MatchCache::default();
+    let memoized = MemoizedQuery::new(&query, 0);
+    let matches = memoized.eval(&stores, &mut cache, code);
+    assert_eq!(&[count(code)][..], &matches.counts[..]);
+    assert_eq!(&[2][..], &matches.counts[..]);
+    let len = cache.len();
+    let matches = memoized.eval(&stores, &mut cache, code2);
+    assert_eq!(&[count(code2)][..], &matches.counts[..]);
+    // the subtrees shared with the first version are not visited again
+    assert!(cache.len() < 2 * len);
+    let again = memoized.eval(&stores, &mut cache, code2);
+    assert_eq!(matches, again);
+
+    // subtrees computed before stopping stay in cache
+    let mut cache = MatchCache::default();
+    let other = MemoizedQuery::new(&query, 1);
+    let stopped = other.eval_until(&stores, &mut cache, code, |_| true);
+    assert!(stopped.is_none());
+    assert_eq!(cache.len(), 1);
+    memoized.eval(&stores, &mut cache, code);
+    // the least recently used subtrees are evicted first
+    cache.evict(cache.len() - 1, Some(0));
+    assert_eq!(cache.len(), len);
+    assert!(cache.get(0, code).is_some());
+    // but never the ones of the current query
+    cache.evict(0, Some(0));
+    assert_eq!(cache.len(), len);
+    cache.evict(0, None);
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn test_lazy_precomputed() {
+    use hyperast_tsquery::lazy_precomputed::{LazyPrecomputed, TreeCursor};
+    let text = r#"
+class A {
+    void f() {
+        try {
+            g();
+        } catch (Exception e) {}
+    }
+    void g() {
+        h();
+    }
+}
+    "#;
+    let query = r#"(try_statement (block (expression_statement (method_invocation) @call)))"#;
+    let (plain, stores, code) = prep_stepped(query, text.as_bytes());
+    // nothing was precomputed while building
+    let lazy = LazyPrecomputed::new(crate::language(), &[], &["(try_statement)"]).unwrap();
+    let precomputeds = lazy.precomputeds();
+    let (_, query) =
+        hyperast_tsquery::Query::with_precomputed(query, crate::language(), &precomputeds[..])
+            .unwrap();
+    assert_eq!(query.precomputed_uses(), vec![0b1]);
+    let pos = hyperast::position::StructuralPosition::new(code);
+    let cursor = hyperast_tsquery::hyperast_cursor::TreeCursor::new(&stores, pos);
+    let expected = plain.matches(cursor).count();
+    assert_eq!(expected, 1);
+    assert_eq!(lazy.computed(), 0);
+    let pos = hyperast::position::StructuralPosition::new(code);
+    let cursor = TreeCursor::new(&stores, pos, &lazy);
+    assert_eq!(query.matches(cursor).count(), expected);
+    assert!(lazy.computed() > 0);
+    lazy.clear();
+    assert_eq!(lazy.computed(), 0);
+    // each registered entry must hold exactly one pattern
+    assert!(LazyPrecomputed::<NodeIdentifier, u16>::new(crate::language(), &[], &[""]).is_err());
+    let two = "(try_statement) (block)";
+    assert!(LazyPrecomputed::<NodeIdentifier, u16>::new(crate::language(), &[], &[two]).is_err());
+}
+
+#[test]
+fn test_explain() {
+    use hyperast_tsquery::lazy_precomputed::{LazyPrecomputed, TreeCursor};
+    let text = r#"
+class A {
+    void f() {
+        try {
+            g();
+        } catch (Exception e) {}
+    }
+    void g() {
+        h();
+    }
+}
+    "#;
+    let query = r#"(try_statement (block (expression_statement (method_invocation) @call)))"#;
+    let (plain, stores, code) = prep_stepped(query, text.as_bytes());
+    assert_eq!(
+        plain.precomputed_hints(),
+        vec![Some("(try_statement)".to_string())]
+    );
+    let lazy = LazyPrecomputed::new(crate::language(), &[], &["(try_statement)"]).unwrap();
+    let precomputeds = lazy.precomputeds();
+    let (_, query) =
+        hyperast_tsquery::Query::with_precomputed(query, crate::language(), &precomputeds[..])
+            .unwrap();
+    assert_eq!(query.precomputed_hints(), vec![None]);
+    let pos = hyperast::position::StructuralPosition::new(code);
+    let cursor = TreeCursor::new(&stores, pos, &lazy);
+    let mut qcursor = query.matches(cursor).explain();
+    assert_eq!(qcursor.by_ref().count(), 1);
+    let profile = qcursor.profile().unwrap();
+    // the body of g does not contain a try_statement
+    assert!(profile.skipped_subtrees > 0);
+    let patterns = profile.enabled_patterns(&query);
+    assert_eq!(patterns.len(), 1);
+    assert_eq!(patterns[0].started, 1);
+    assert_eq!(patterns[0].steps[0], 1);
+    assert_eq!(patterns[0].matches, 1);
+    assert_eq!(patterns[0].rejected, 0);
+}
+
 #[allow(unused)]
 fn run_stepped2(query: &str, text: &[u8]) -> usize {
     let (query, tree) = prep_stepped2(query, text);
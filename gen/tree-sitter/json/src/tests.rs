@@ -0,0 +1,46 @@
+use hyperast::store::SimpleStores;
+
+use crate::{
+    legion::{JsonTreeGen, tree_sitter_parse_json},
+    types::TStore,
+};
+
+static PACKAGE_JSON: &str = r#"{
+  "name": "root",
+  "private": true,
+  "version": "1.0.0",
+  "workspaces": ["packages/*", "tools/cli"],
+  "scripts": { "build": "tsc -b \"tsconfig.json\"" },
+  "dependencies": null
+}
+"#;
+
+#[test]
+fn json_tree_sitter_simple() {
+    let tree = match tree_sitter_parse_json(PACKAGE_JSON.as_bytes()) {
+        Ok(t) => t,
+        Err(t) => t,
+    };
+    println!("{}", tree.root_node().to_sexp());
+    assert!(!tree.root_node().has_error());
+}
+
+#[test]
+fn hyperast_on_package_json() {
+    let text = PACKAGE_JSON.as_bytes();
+    let tree = match tree_sitter_parse_json(text) {
+        Ok(t) => t,
+        Err(t) => t,
+    };
+    let mut stores = SimpleStores::<TStore>::default();
+    let mut md_cache = Default::default();
+    let mut tree_gen = JsonTreeGen::new(&mut stores, &mut md_cache);
+    let x = tree_gen
+        .generate_file(b"package.json", text, tree.walk())
+        .local;
+    let id = x.compressed_node;
+    use hyperast::nodes;
+    println!("{}", nodes::SexpSerializer::new(&stores, id));
+    let printed = nodes::TextSerializer::new(&stores, id).to_string();
+    assert_eq!(printed, PACKAGE_JSON);
+}
@@ -0,0 +1,647 @@
+//! Types of the nodes produced by the JSON grammar.
+//!
+//! The discriminants of [`Type`] are not the tree-sitter symbols,
+//! the few symbols that are not listed here are resolved to [`Type::Token`] and keep their text as label.
+use std::fmt::Display;
+
+use hyperast::tree_gen::{TsEnableTS, TsType};
+use hyperast::types::TypeStore;
+use hyperast::types::{AAAA, AnyType, HyperType, LangRef, NodeId, TypeTrait, TypeU16, TypedNodeId};
+
+impl hyperast::types::ETypeStore for TStore {
+    type Ty2 = Type;
+
+    fn intern(ty: Self::Ty2) -> Self::Ty {
+        TType::new(ty)
+    }
+}
+
+impl TsEnableTS for TStore {
+    fn obtain_type<'a, N: hyperast::tree_gen::parser::NodeWithU16TypeId>(
+        n: &N,
+    ) -> <Self as hyperast::types::ETypeStore>::Ty2 {
+        let k = n.kind_id();
+        type_for_symbol(k).unwrap_or(Type::Token)
+    }
+
+    fn try_obtain_type<N: hyperast::tree_gen::parser::NodeWithU16TypeId>(
+        n: &N,
+    ) -> Option<Self::Ty2> {
+        let k = n.kind_id();
+        type_for_symbol(k)
+    }
+}
+
+impl TsType for Type {
+    fn spaces() -> Self {
+        Self::Spaces
+    }
+
+    fn is_repeat(&self) -> bool {
+        false
+    }
+}
+
+impl TypeStore for TStore {
+    type Ty = TypeU16<Json>;
+}
+impl TypeStore for &TStore {
+    type Ty = TypeU16<Json>;
+}
+
+#[cfg(feature = "impl")]
+mod impls {
+    use super::*;
+
+    use hyperast::types::{LangWrapper, RoleStore};
+
+    impl JsonEnabledTypeStore for TStore {
+        fn resolve(t: Self::Ty) -> Type {
+            t.e()
+        }
+    }
+
+    // static dynamically initialized once association table between Role and tree_sitter_json Fields
+    static ROLE2FIELD: std::sync::LazyLock<Box<[u16]>> = std::sync::LazyLock::new(|| {
+        (0..hyperast::types::Role::len())
+            .map(|i| {
+                let i = i as u8;
+                let role: hyperast::types::Role = unsafe { std::mem::transmute(i) };
+                let field_name = role.to_string();
+                crate::language()
+                    .field_id_for_name(field_name)
+                    .map_or(u16::MAX, |x| x.into())
+            })
+            .collect()
+    });
+
+    impl RoleStore for TStore {
+        type IdF = u16;
+
+        type Role = hyperast::types::Role;
+
+        fn resolve_field(_lang: LangWrapper<Self::Ty>, field_id: Self::IdF) -> Self::Role {
+            let s = crate::language()
+                .field_name_for_id(field_id)
+                .ok_or_else(|| format!("{}", field_id))
+                .unwrap();
+            hyperast::types::Role::try_from(s).expect(s)
+        }
+
+        fn intern_role(_lang: LangWrapper<Self::Ty>, role: Self::Role) -> Self::IdF {
+            let r = ROLE2FIELD[role as usize];
+            assert!(r < u16::MAX, "Role not found");
+            r
+        }
+    }
+}
+
+// static dynamically initialized once association table between tree_sitter_json symbols and types
+#[cfg(feature = "impl")]
+static SYMBOL2TYPE: std::sync::LazyLock<Box<[Option<Type>]>> = std::sync::LazyLock::new(|| {
+    let l = crate::language();
+    (0..l.node_kind_count() as u16)
+        .map(|id| {
+            let kind = l.node_kind_for_id(id)?;
+            let named = l.node_kind_is_named(id);
+            let t = Type::from_str(kind).filter(|t| t.is_named() == named);
+            Some(t.unwrap_or(Type::Token))
+        })
+        .collect()
+});
+
+#[cfg(feature = "impl")]
+fn type_for_symbol(k: u16) -> Option<Type> {
+    if k == u16::MAX {
+        // the symbol of error nodes
+        return Some(Type::ERROR);
+    }
+    SYMBOL2TYPE.get(k as usize).copied().flatten()
+}
+
+#[cfg(not(feature = "impl"))]
+fn type_for_symbol(_k: u16) -> Option<Type> {
+    unimplemented!("need treesitter grammar")
+}
+
+#[cfg(feature = "impl")]
+fn id_for_node_kind(kind: &str, named: bool) -> u16 {
+    crate::language().id_for_node_kind(kind, named)
+}
+
+#[cfg(not(feature = "impl"))]
+fn id_for_node_kind(_kind: &str, _named: bool) -> u16 {
+    unimplemented!("need treesitter grammar")
+}
+
+pub fn as_any(t: &Type) -> AnyType {
+    let t = <Json as hyperast::types::Lang<Type>>::to_u16(*t);
+    let t = <Json as hyperast::types::Lang<Type>>::make(t);
+    let t: &'static dyn HyperType = t;
+    t.into()
+}
+
+#[cfg(not(feature = "impl"))]
+pub trait JsonEnabledTypeStore: hyperast::types::ETypeStore<Ty2 = Type> {
+    fn resolve(t: Self::Ty) -> Type;
+}
+
+#[cfg(feature = "impl")]
+pub trait JsonEnabledTypeStore:
+    hyperast::types::ETypeStore<Ty2 = Type> + hyperast::tree_gen::utils_ts::TsEnableTS
+{
+    fn resolve(t: Self::Ty) -> Type;
+}
+
+#[derive(Clone, Copy)]
+pub struct TStore;
+
+impl Default for TStore {
+    fn default() -> Self {
+        Self
+    }
+}
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TIdN<IdN>(IdN);
+
+impl<IdN: Clone + Eq + AAAA> NodeId for TIdN<IdN> {
+    type IdN = IdN;
+
+    fn as_id(&self) -> &Self::IdN {
+        &self.0
+    }
+
+    unsafe fn from_id(id: Self::IdN) -> Self {
+        Self(id)
+    }
+
+    unsafe fn from_ref_id(_id: &Self::IdN) -> &Self {
+        todo!()
+    }
+}
+
+impl<IdN: Clone + Eq + AAAA> TypedNodeId for TIdN<IdN> {
+    type Ty = Type;
+    type TyErazed = TType;
+    fn unerase(ty: Self::TyErazed) -> Self::Ty {
+        ty.e()
+    }
+}
+
+#[derive(Debug)]
+pub struct Lang;
+
+pub type Json = Lang;
+
+impl hyperast::types::Lang<Type> for Json {
+    const INST: Self = Lang;
+    fn make(t: u16) -> &'static Type {
+        Lang.make(t)
+    }
+    fn to_u16(t: Type) -> u16 {
+        Lang.to_u16(t)
+    }
+}
+
+impl LangRef<Type> for Json {
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Json>()
+    }
+
+    fn make(&self, t: u16) -> &'static Type {
+        &S_T_L[t as usize]
+    }
+
+    fn to_u16(&self, t: Type) -> u16 {
+        t as u16
+    }
+
+    fn ts_symbol(&self, t: Type) -> u16 {
+        assert!(t != Type::Spaces && t != Type::Directory && t != Type::Token);
+        id_for_node_kind(t.as_static_str(), t.is_named())
+    }
+}
+
+impl LangRef<AnyType> for Json {
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Json>()
+    }
+
+    fn make(&self, _t: u16) -> &'static AnyType {
+        todo!()
+    }
+
+    fn to_u16(&self, t: AnyType) -> u16 {
+        let t: &Type = t.as_any().downcast_ref().unwrap();
+        Lang.to_u16(*t)
+    }
+
+    fn ts_symbol(&self, t: AnyType) -> u16 {
+        let t: Type = *t.as_any().downcast_ref().unwrap();
+        Lang.ts_symbol(t)
+    }
+}
+
+impl LangRef<hyperast::types::TypeU16<Self>> for Lang {
+    fn make(&self, t: u16) -> &'static TType {
+        // TODO could make one safe, but not priority
+        unsafe { std::mem::transmute(&S_T_L[t as usize]) }
+    }
+    fn to_u16(&self, t: TType) -> u16 {
+        t.e() as u16
+    }
+
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Lang>()
+    }
+
+    fn ts_symbol(&self, t: TType) -> u16 {
+        id_for_node_kind(t.as_static_str(), t.is_named())
+    }
+}
+
+macro_rules! is {
+    ($e:expr, $($p:ident $(if $guard:expr)?, )*) => {
+        match $e {$(
+            Type::$p $(if $guard)? => true,)*
+            _ => false
+        }
+    };
+}
+
+impl HyperType for Type {
+    fn generic_eq(&self, other: &dyn HyperType) -> bool
+    where
+        Self: 'static + PartialEq + Sized,
+    {
+        // Do a type-safe casting. If the types are different,
+        // return false, otherwise test the values for equality.
+        other
+            .as_any()
+            .downcast_ref::<Self>()
+            .map_or(false, |a| self == a)
+    }
+
+    fn as_shared(&self) -> hyperast::types::Shared {
+        use hyperast::types::Shared;
+        if self.is_error() {
+            return Shared::Error;
+        }
+        if self == &Type::Comment {
+            return Shared::Comment;
+        }
+        Shared::Other
+    }
+
+    fn is_error(&self) -> bool {
+        self == &Self::ERROR
+    }
+
+    fn as_abstract(&self) -> hyperast::types::Abstracts {
+        hyperast::types::Abstracts::empty()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_static(&self) -> &'static dyn HyperType {
+        let t = <Json as hyperast::types::Lang<Type>>::to_u16(*self);
+        (<Json as hyperast::types::Lang<Type>>::make(t)) as _
+    }
+
+    fn as_static_str(&self) -> &'static str {
+        self.to_str()
+    }
+
+    fn is_file(&self) -> bool {
+        self == &Type::Document
+    }
+
+    fn is_directory(&self) -> bool {
+        self == &Type::Directory
+    }
+
+    fn is_spaces(&self) -> bool {
+        self == &Type::Spaces
+    }
+
+    fn is_syntax(&self) -> bool {
+        !self.is_named() && !is!(self, Token, Spaces, Directory, ERROR,)
+    }
+
+    fn is_hidden(&self) -> bool {
+        self.is_hidden()
+    }
+
+    fn is_supertype(&self) -> bool {
+        self.is_supertype()
+    }
+
+    fn is_named(&self) -> bool {
+        self.is_named()
+    }
+
+    fn get_lang(&self) -> hyperast::types::LangWrapper<Self>
+    where
+        Self: Sized,
+    {
+        hyperast::types::LangWrapper::from(&Lang as &(dyn LangRef<Self> + 'static))
+    }
+
+    fn lang_ref(&self) -> hyperast::types::LangWrapper<AnyType> {
+        hyperast::types::LangWrapper::from(&Lang as &(dyn LangRef<AnyType> + 'static))
+    }
+}
+
+impl TypeTrait for Type {
+    type Lang = Json;
+
+    fn is_fork(&self) -> bool {
+        todo!()
+    }
+
+    fn is_literal(&self) -> bool {
+        todo!()
+    }
+
+    fn is_primitive(&self) -> bool {
+        todo!()
+    }
+
+    fn is_type_declaration(&self) -> bool {
+        todo!()
+    }
+
+    fn is_identifier(&self) -> bool {
+        todo!()
+    }
+
+    fn is_instance_ref(&self) -> bool {
+        todo!()
+    }
+
+    fn is_type_body(&self) -> bool {
+        todo!()
+    }
+
+    fn is_value_member(&self) -> bool {
+        todo!()
+    }
+
+    fn is_executable_member(&self) -> bool {
+        todo!()
+    }
+
+    fn is_statement(&self) -> bool {
+        todo!()
+    }
+
+    fn is_declarative_statement(&self) -> bool {
+        todo!()
+    }
+
+    fn is_structural_statement(&self) -> bool {
+        todo!()
+    }
+
+    fn is_block_related(&self) -> bool {
+        todo!()
+    }
+
+    fn is_simple_statement(&self) -> bool {
+        todo!()
+    }
+
+    fn is_local_declare(&self) -> bool {
+        todo!()
+    }
+
+    fn is_parameter(&self) -> bool {
+        todo!()
+    }
+
+    fn is_parameter_list(&self) -> bool {
+        todo!()
+    }
+
+    fn is_argument_list(&self) -> bool {
+        todo!()
+    }
+
+    fn is_expression(&self) -> bool {
+        todo!()
+    }
+
+    fn is_comment(&self) -> bool {
+        self == &Type::Comment
+    }
+}
+
+impl Type {
+    pub fn resolve(t: u16) -> Self {
+        assert!(t < COUNT);
+        unsafe { std::mem::transmute(t) }
+    }
+}
+const COUNT: u16 = S_T_L.len() as u16;
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+impl TryFrom<&str> for Type {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, <Self as TryFrom<&str>>::Error> {
+        Type::from_str(value).ok_or_else(|| value.to_owned())
+    }
+}
+
+impl hyperast::types::LLang<hyperast::types::TypeU16<Self>> for Json {
+    type I = u16;
+
+    type E = Type;
+
+    const TE: &[Self::E] = S_T_L;
+
+    fn as_lang_wrapper() -> hyperast::types::LangWrapper<hyperast::types::TypeU16<Self>> {
+        From::<&'static dyn LangRef<_>>::from(&Lang)
+    }
+}
+
+pub type TType = TypeU16<Lang>;
+
+impl From<u16> for Type {
+    fn from(value: u16) -> Self {
+        S_T_L[value as usize]
+    }
+}
+
+impl From<Type> for TypeU16<Json> {
+    fn from(val: Type) -> Self {
+        TypeU16::new(val)
+    }
+}
+
+impl From<Type> for u16 {
+    fn from(val: Type) -> Self {
+        val as u16
+    }
+}
+
+#[cfg(feature = "impl")]
+#[test]
+fn test_tslanguage_and_type_identity() {
+    let l = crate::language();
+    for id in 0..l.node_kind_count() {
+        let kind = l.node_kind_for_id(id as u16).unwrap();
+        let Some(ty) = type_for_symbol(id as u16) else {
+            continue;
+        };
+        if ty != Type::Token {
+            assert_eq!(ty.to_str(), kind);
+            assert_eq!(ty.is_named(), l.node_kind_is_named(id as u16));
+        }
+    }
+    for ty in S_T_L {
+        assert_eq!(Type::resolve(*ty as u16), *ty);
+    }
+}
+
+#[repr(u16)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Type {
+    Document,
+    Object,
+    Pair,
+    Array,
+    String,
+    StringContent,
+    EscapeSequence,
+    Number,
+    True,
+    False,
+    Null,
+    Comment,
+    LBrace,
+    RBrace,
+    Comma,
+    Colon,
+    LBracket,
+    RBracket,
+    DQuote,
+    Token,
+    Spaces,
+    Directory,
+    ERROR,
+}
+
+impl Type {
+    pub fn from_u16(t: u16) -> Type {
+        S_T_L[t as usize]
+    }
+    pub fn from_str(t: &str) -> Option<Type> {
+        Some(match t {
+            "document" => Type::Document,
+            "object" => Type::Object,
+            "pair" => Type::Pair,
+            "array" => Type::Array,
+            "string" => Type::String,
+            "string_content" => Type::StringContent,
+            "escape_sequence" => Type::EscapeSequence,
+            "number" => Type::Number,
+            "true" => Type::True,
+            "false" => Type::False,
+            "null" => Type::Null,
+            "comment" => Type::Comment,
+            "{" => Type::LBrace,
+            "}" => Type::RBrace,
+            "," => Type::Comma,
+            ":" => Type::Colon,
+            "[" => Type::LBracket,
+            "]" => Type::RBracket,
+            "\"" => Type::DQuote,
+            "ERROR" => Type::ERROR,
+            _ => return None,
+        })
+    }
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Type::Document => "document",
+            Type::Object => "object",
+            Type::Pair => "pair",
+            Type::Array => "array",
+            Type::String => "string",
+            Type::StringContent => "string_content",
+            Type::EscapeSequence => "escape_sequence",
+            Type::Number => "number",
+            Type::True => "true",
+            Type::False => "false",
+            Type::Null => "null",
+            Type::Comment => "comment",
+            Type::LBrace => "{",
+            Type::RBrace => "}",
+            Type::Comma => ",",
+            Type::Colon => ":",
+            Type::LBracket => "[",
+            Type::RBracket => "]",
+            Type::DQuote => "\"",
+            Type::Token => "Token",
+            Type::Spaces => "Spaces",
+            Type::Directory => "Directory",
+            Type::ERROR => "ERROR",
+        }
+    }
+    pub fn is_hidden(&self) -> bool {
+        false
+    }
+    pub fn is_supertype(&self) -> bool {
+        false
+    }
+    pub fn is_named(&self) -> bool {
+        is!(
+            self,
+            Document,
+            Object,
+            Pair,
+            Array,
+            String,
+            StringContent,
+            EscapeSequence,
+            Number,
+            True,
+            False,
+            Null,
+            Comment,
+        )
+    }
+}
+
+const S_T_L: &[Type] = &[
+    Type::Document,
+    Type::Object,
+    Type::Pair,
+    Type::Array,
+    Type::String,
+    Type::StringContent,
+    Type::EscapeSequence,
+    Type::Number,
+    Type::True,
+    Type::False,
+    Type::Null,
+    Type::Comment,
+    Type::LBrace,
+    Type::RBrace,
+    Type::Comma,
+    Type::Colon,
+    Type::LBracket,
+    Type::RBracket,
+    Type::DQuote,
+    Type::Token,
+    Type::Spaces,
+    Type::Directory,
+    Type::ERROR,
+];
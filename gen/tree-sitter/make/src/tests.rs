@@ -0,0 +1,45 @@
+use hyperast::store::SimpleStores;
+
+use crate::{
+    legion::{MakeTreeGen, tree_sitter_parse_make},
+    types::TStore,
+};
+
+static MAKEFILE: &str = r#"CC = gcc
+CFLAGS = -Wall -Iinclude -I lib/include
+SRCS = $(wildcard src/*.c) lib/util.c
+
+all: main
+	$(MAKE) -C tests
+
+main: $(SRCS:.c=.o)
+	$(CC) $(CFLAGS) -o $@ $^
+
+.PHONY: all
+"#;
+
+#[test]
+fn make_tree_sitter_simple() {
+    let tree = match tree_sitter_parse_make(MAKEFILE.as_bytes()) {
+        Ok(t) => t,
+        Err(t) => t,
+    };
+    println!("{}", tree.root_node().to_sexp());
+}
+
+#[test]
+fn hyperast_on_makefile() {
+    let text = MAKEFILE.as_bytes();
+    let tree = match tree_sitter_parse_make(text) {
+        Ok(t) => t,
+        Err(t) => t,
+    };
+    let mut stores = SimpleStores::<TStore>::default();
+    let mut md_cache = Default::default();
+    let mut tree_gen = MakeTreeGen::new(&mut stores, &mut md_cache);
+    let x = tree_gen.generate_file(b"Makefile", text, tree.walk()).local;
+    let id = x.compressed_node;
+    use hyperast::nodes;
+    println!("{}", nodes::SexpSerializer::new(&stores, id));
+    println!("{}", nodes::TextSerializer::new(&stores, id));
+}
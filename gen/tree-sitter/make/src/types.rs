@@ -0,0 +1,962 @@
+//! Types of the nodes produced by the Makefile grammar.
+//!
+//! Contrary to other generated languages, the discriminants of [`Type`] are not the tree-sitter symbols.
+//! The grammar exposes many keywords (every builtin function is one),
+//! those that are not listed here are resolved to [`Type::Token`] and keep their text as label.
+use std::fmt::Display;
+
+use hyperast::tree_gen::{TsEnableTS, TsType};
+use hyperast::types::TypeStore;
+use hyperast::types::{AAAA, AnyType, HyperType, LangRef, NodeId, TypeTrait, TypeU16, TypedNodeId};
+
+impl hyperast::types::ETypeStore for TStore {
+    type Ty2 = Type;
+
+    fn intern(ty: Self::Ty2) -> Self::Ty {
+        TType::new(ty)
+    }
+}
+
+impl TsEnableTS for TStore {
+    fn obtain_type<'a, N: hyperast::tree_gen::parser::NodeWithU16TypeId>(
+        n: &N,
+    ) -> <Self as hyperast::types::ETypeStore>::Ty2 {
+        let k = n.kind_id();
+        type_for_symbol(k).unwrap_or(Type::Token)
+    }
+
+    fn try_obtain_type<N: hyperast::tree_gen::parser::NodeWithU16TypeId>(
+        n: &N,
+    ) -> Option<Self::Ty2> {
+        let k = n.kind_id();
+        type_for_symbol(k)
+    }
+}
+
+impl TsType for Type {
+    fn spaces() -> Self {
+        Self::Spaces
+    }
+
+    fn is_repeat(&self) -> bool {
+        false
+    }
+}
+
+impl TypeStore for TStore {
+    type Ty = TypeU16<Make>;
+}
+impl TypeStore for &TStore {
+    type Ty = TypeU16<Make>;
+}
+
+#[cfg(feature = "impl")]
+mod impls {
+    use super::*;
+
+    use hyperast::types::{LangWrapper, RoleStore};
+
+    impl MakeEnabledTypeStore for TStore {
+        fn resolve(t: Self::Ty) -> Type {
+            t.e()
+        }
+    }
+
+    // static dynamically initialized once association table between Role and tree_sitter_make Fields
+    static ROLE2FIELD: std::sync::LazyLock<Box<[u16]>> = std::sync::LazyLock::new(|| {
+        (0..hyperast::types::Role::len())
+            .map(|i| {
+                let i = i as u8;
+                let role: hyperast::types::Role = unsafe { std::mem::transmute(i) };
+                let field_name = role.to_string();
+                crate::language()
+                    .field_id_for_name(field_name)
+                    .map_or(u16::MAX, |x| x.into())
+            })
+            .collect()
+    });
+
+    impl RoleStore for TStore {
+        type IdF = u16;
+
+        type Role = hyperast::types::Role;
+
+        fn resolve_field(_lang: LangWrapper<Self::Ty>, field_id: Self::IdF) -> Self::Role {
+            let s = crate::language()
+                .field_name_for_id(field_id)
+                .ok_or_else(|| format!("{}", field_id))
+                .unwrap();
+            hyperast::types::Role::try_from(s).expect(s)
+        }
+
+        fn intern_role(_lang: LangWrapper<Self::Ty>, role: Self::Role) -> Self::IdF {
+            let r = ROLE2FIELD[role as usize];
+            assert!(r < u16::MAX, "Role not found");
+            r
+        }
+    }
+}
+
+// static dynamically initialized once association table between tree_sitter_make symbols and types
+#[cfg(feature = "impl")]
+static SYMBOL2TYPE: std::sync::LazyLock<Box<[Option<Type>]>> = std::sync::LazyLock::new(|| {
+    let l = crate::language();
+    (0..l.node_kind_count() as u16)
+        .map(|id| {
+            let kind = l.node_kind_for_id(id)?;
+            let named = l.node_kind_is_named(id);
+            let t = Type::from_str(kind).filter(|t| t.is_named() == named);
+            Some(t.unwrap_or(Type::Token))
+        })
+        .collect()
+});
+
+#[cfg(feature = "impl")]
+fn type_for_symbol(k: u16) -> Option<Type> {
+    if k == u16::MAX {
+        // the symbol of error nodes
+        return Some(Type::ERROR);
+    }
+    SYMBOL2TYPE.get(k as usize).copied().flatten()
+}
+
+#[cfg(not(feature = "impl"))]
+fn type_for_symbol(_k: u16) -> Option<Type> {
+    unimplemented!("need treesitter grammar")
+}
+
+#[cfg(feature = "impl")]
+fn id_for_node_kind(kind: &str, named: bool) -> u16 {
+    crate::language().id_for_node_kind(kind, named)
+}
+
+#[cfg(not(feature = "impl"))]
+fn id_for_node_kind(_kind: &str, _named: bool) -> u16 {
+    unimplemented!("need treesitter grammar")
+}
+
+pub fn as_any(t: &Type) -> AnyType {
+    let t = <Make as hyperast::types::Lang<Type>>::to_u16(*t);
+    let t = <Make as hyperast::types::Lang<Type>>::make(t);
+    let t: &'static dyn HyperType = t;
+    t.into()
+}
+
+#[cfg(not(feature = "impl"))]
+pub trait MakeEnabledTypeStore: hyperast::types::ETypeStore<Ty2 = Type> {
+    fn resolve(t: Self::Ty) -> Type;
+}
+
+#[cfg(feature = "impl")]
+pub trait MakeEnabledTypeStore:
+    hyperast::types::ETypeStore<Ty2 = Type> + hyperast::tree_gen::utils_ts::TsEnableTS
+{
+    fn resolve(t: Self::Ty) -> Type;
+}
+
+#[derive(Clone, Copy)]
+pub struct TStore;
+
+impl Default for TStore {
+    fn default() -> Self {
+        Self
+    }
+}
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TIdN<IdN>(IdN);
+
+impl<IdN: Clone + Eq + AAAA> NodeId for TIdN<IdN> {
+    type IdN = IdN;
+
+    fn as_id(&self) -> &Self::IdN {
+        &self.0
+    }
+
+    unsafe fn from_id(id: Self::IdN) -> Self {
+        Self(id)
+    }
+
+    unsafe fn from_ref_id(_id: &Self::IdN) -> &Self {
+        todo!()
+    }
+}
+
+impl<IdN: Clone + Eq + AAAA> TypedNodeId for TIdN<IdN> {
+    type Ty = Type;
+    type TyErazed = TType;
+    fn unerase(ty: Self::TyErazed) -> Self::Ty {
+        ty.e()
+    }
+}
+
+#[derive(Debug)]
+pub struct Lang;
+
+pub type Make = Lang;
+
+impl hyperast::types::Lang<Type> for Make {
+    const INST: Self = Lang;
+    fn make(t: u16) -> &'static Type {
+        Lang.make(t)
+    }
+    fn to_u16(t: Type) -> u16 {
+        Lang.to_u16(t)
+    }
+}
+
+impl LangRef<Type> for Make {
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Make>()
+    }
+
+    fn make(&self, t: u16) -> &'static Type {
+        &S_T_L[t as usize]
+    }
+
+    fn to_u16(&self, t: Type) -> u16 {
+        t as u16
+    }
+
+    fn ts_symbol(&self, t: Type) -> u16 {
+        assert!(t != Type::Spaces && t != Type::Directory && t != Type::Token);
+        id_for_node_kind(t.as_static_str(), t.is_named())
+    }
+}
+
+impl LangRef<AnyType> for Make {
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Make>()
+    }
+
+    fn make(&self, _t: u16) -> &'static AnyType {
+        todo!()
+    }
+
+    fn to_u16(&self, t: AnyType) -> u16 {
+        let t: &Type = t.as_any().downcast_ref().unwrap();
+        Lang.to_u16(*t)
+    }
+
+    fn ts_symbol(&self, t: AnyType) -> u16 {
+        let t: Type = *t.as_any().downcast_ref().unwrap();
+        Lang.ts_symbol(t)
+    }
+}
+
+impl LangRef<hyperast::types::TypeU16<Self>> for Lang {
+    fn make(&self, t: u16) -> &'static TType {
+        // TODO could make one safe, but not priority
+        unsafe { std::mem::transmute(&S_T_L[t as usize]) }
+    }
+    fn to_u16(&self, t: TType) -> u16 {
+        t.e() as u16
+    }
+
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Lang>()
+    }
+
+    fn ts_symbol(&self, t: TType) -> u16 {
+        id_for_node_kind(t.as_static_str(), t.is_named())
+    }
+}
+
+macro_rules! is {
+    ($e:expr, $($p:ident $(if $guard:expr)?, )*) => {
+        match $e {$(
+            Type::$p $(if $guard)? => true,)*
+            _ => false
+        }
+    };
+}
+
+impl HyperType for Type {
+    fn generic_eq(&self, other: &dyn HyperType) -> bool
+    where
+        Self: 'static + PartialEq + Sized,
+    {
+        // Do a type-safe casting. If the types are different,
+        // return false, otherwise test the values for equality.
+        other
+            .as_any()
+            .downcast_ref::<Self>()
+            .map_or(false, |a| self == a)
+    }
+
+    fn as_shared(&self) -> hyperast::types::Shared {
+        use hyperast::types::Shared;
+        if self.is_error() {
+            return Shared::Error;
+        }
+        if self == &Type::Comment {
+            return Shared::Comment;
+        }
+        Shared::Other
+    }
+
+    fn is_error(&self) -> bool {
+        self == &Self::ERROR
+    }
+
+    fn as_abstract(&self) -> hyperast::types::Abstracts {
+        hyperast::types::Abstracts::empty()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_static(&self) -> &'static dyn HyperType {
+        let t = <Make as hyperast::types::Lang<Type>>::to_u16(*self);
+        (<Make as hyperast::types::Lang<Type>>::make(t)) as _
+    }
+
+    fn as_static_str(&self) -> &'static str {
+        self.to_str()
+    }
+
+    fn is_file(&self) -> bool {
+        self == &Type::Makefile
+    }
+
+    fn is_directory(&self) -> bool {
+        self == &Type::Directory
+    }
+
+    fn is_spaces(&self) -> bool {
+        self == &Type::Spaces
+    }
+
+    fn is_syntax(&self) -> bool {
+        !self.is_named() && !is!(self, Token, Spaces, Directory, ERROR,)
+    }
+
+    fn is_hidden(&self) -> bool {
+        self.is_hidden()
+    }
+
+    fn is_supertype(&self) -> bool {
+        self.is_supertype()
+    }
+
+    fn is_named(&self) -> bool {
+        self.is_named()
+    }
+
+    fn get_lang(&self) -> hyperast::types::LangWrapper<Self>
+    where
+        Self: Sized,
+    {
+        hyperast::types::LangWrapper::from(&Lang as &(dyn LangRef<Self> + 'static))
+    }
+
+    fn lang_ref(&self) -> hyperast::types::LangWrapper<AnyType> {
+        hyperast::types::LangWrapper::from(&Lang as &(dyn LangRef<AnyType> + 'static))
+    }
+}
+
+impl TypeTrait for Type {
+    type Lang = Make;
+
+    fn is_fork(&self) -> bool {
+        todo!()
+    }
+
+    fn is_literal(&self) -> bool {
+        todo!()
+    }
+
+    fn is_primitive(&self) -> bool {
+        todo!()
+    }
+
+    fn is_type_declaration(&self) -> bool {
+        todo!()
+    }
+
+    fn is_identifier(&self) -> bool {
+        todo!()
+    }
+
+    fn is_instance_ref(&self) -> bool {
+        todo!()
+    }
+
+    fn is_type_body(&self) -> bool {
+        todo!()
+    }
+
+    fn is_value_member(&self) -> bool {
+        todo!()
+    }
+
+    fn is_executable_member(&self) -> bool {
+        todo!()
+    }
+
+    fn is_statement(&self) -> bool {
+        todo!()
+    }
+
+    fn is_declarative_statement(&self) -> bool {
+        todo!()
+    }
+
+    fn is_structural_statement(&self) -> bool {
+        todo!()
+    }
+
+    fn is_block_related(&self) -> bool {
+        todo!()
+    }
+
+    fn is_simple_statement(&self) -> bool {
+        todo!()
+    }
+
+    fn is_local_declare(&self) -> bool {
+        todo!()
+    }
+
+    fn is_parameter(&self) -> bool {
+        todo!()
+    }
+
+    fn is_parameter_list(&self) -> bool {
+        todo!()
+    }
+
+    fn is_argument_list(&self) -> bool {
+        todo!()
+    }
+
+    fn is_expression(&self) -> bool {
+        todo!()
+    }
+
+    fn is_comment(&self) -> bool {
+        self == &Type::Comment
+    }
+}
+
+impl Type {
+    pub fn resolve(t: u16) -> Self {
+        assert!(t < COUNT);
+        unsafe { std::mem::transmute(t) }
+    }
+}
+const COUNT: u16 = S_T_L.len() as u16;
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+impl TryFrom<&str> for Type {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, <Self as TryFrom<&str>>::Error> {
+        Type::from_str(value).ok_or_else(|| value.to_owned())
+    }
+}
+
+impl hyperast::types::LLang<hyperast::types::TypeU16<Self>> for Make {
+    type I = u16;
+
+    type E = Type;
+
+    const TE: &[Self::E] = S_T_L;
+
+    fn as_lang_wrapper() -> hyperast::types::LangWrapper<hyperast::types::TypeU16<Self>> {
+        From::<&'static dyn LangRef<_>>::from(&Lang)
+    }
+}
+
+pub type TType = TypeU16<Lang>;
+
+impl From<u16> for Type {
+    fn from(value: u16) -> Self {
+        S_T_L[value as usize]
+    }
+}
+
+impl From<Type> for TypeU16<Make> {
+    fn from(val: Type) -> Self {
+        TypeU16::new(val)
+    }
+}
+
+impl From<Type> for u16 {
+    fn from(val: Type) -> Self {
+        val as u16
+    }
+}
+
+#[cfg(feature = "impl")]
+#[test]
+fn test_tslanguage_and_type_identity() {
+    let l = crate::language();
+    for id in 0..l.node_kind_count() {
+        let kind = l.node_kind_for_id(id as u16).unwrap();
+        let Some(ty) = type_for_symbol(id as u16) else {
+            continue;
+        };
+        if ty != Type::Token {
+            assert_eq!(ty.to_str(), kind);
+            assert_eq!(ty.is_named(), l.node_kind_is_named(id as u16));
+        }
+    }
+    for ty in S_T_L {
+        assert_eq!(Type::resolve(*ty as u16), *ty);
+    }
+}
+
+#[repr(u16)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Type {
+    Makefile,
+    Comment,
+    Rule,
+    Targets,
+    Prerequisites,
+    TargetPattern,
+    PatternList,
+    Recipe,
+    RecipeLine,
+    ShellText,
+    ShellCommand,
+    VariableAssignment,
+    ShellAssignment,
+    DefineDirective,
+    IncludeDirective,
+    VpathDirective,
+    ExportDirective,
+    UnexportDirective,
+    OverrideDirective,
+    UndefineDirective,
+    PrivateDirective,
+    Conditional,
+    IfeqDirective,
+    IfneqDirective,
+    IfdefDirective,
+    IfndefDirective,
+    ElseDirective,
+    ElsifDirective,
+    VariableReference,
+    SubstitutionReference,
+    AutomaticVariable,
+    FunctionCall,
+    ShellFunction,
+    Arguments,
+    Concatenation,
+    Archive,
+    List,
+    Paths,
+    Word,
+    String,
+    RawText,
+    Text,
+    Colon,
+    ColonColon,
+    AmpColon,
+    Pipe,
+    SemiColon,
+    At,
+    Dash,
+    Plus,
+    Eq,
+    ColonEq,
+    ColonColonEq,
+    QMarkEq,
+    PlusEq,
+    BangEq,
+    Dollar,
+    DollarDollar,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Comma,
+    Include,
+    Sinclude,
+    DashInclude,
+    Vpath,
+    Export,
+    Unexport,
+    Override,
+    Undefine,
+    Private,
+    Define,
+    Endef,
+    Ifeq,
+    Ifneq,
+    Ifdef,
+    Ifndef,
+    Else,
+    Endif,
+    Shell,
+    Percent,
+    LT,
+    QMark,
+    Caret,
+    Star,
+    Slash,
+    BackSlash,
+    SQuote,
+    DQuote,
+    Token,
+    Spaces,
+    Directory,
+    ERROR,
+}
+
+impl Type {
+    pub fn from_u16(t: u16) -> Type {
+        S_T_L[t as usize]
+    }
+    pub fn from_str(t: &str) -> Option<Type> {
+        Some(match t {
+            "makefile" => Type::Makefile,
+            "comment" => Type::Comment,
+            "rule" => Type::Rule,
+            "targets" => Type::Targets,
+            "prerequisites" => Type::Prerequisites,
+            "target_pattern" => Type::TargetPattern,
+            "pattern_list" => Type::PatternList,
+            "recipe" => Type::Recipe,
+            "recipe_line" => Type::RecipeLine,
+            "shell_text" => Type::ShellText,
+            "shell_command" => Type::ShellCommand,
+            "variable_assignment" => Type::VariableAssignment,
+            "shell_assignment" => Type::ShellAssignment,
+            "define_directive" => Type::DefineDirective,
+            "include_directive" => Type::IncludeDirective,
+            "vpath_directive" => Type::VpathDirective,
+            "export_directive" => Type::ExportDirective,
+            "unexport_directive" => Type::UnexportDirective,
+            "override_directive" => Type::OverrideDirective,
+            "undefine_directive" => Type::UndefineDirective,
+            "private_directive" => Type::PrivateDirective,
+            "conditional" => Type::Conditional,
+            "ifeq_directive" => Type::IfeqDirective,
+            "ifneq_directive" => Type::IfneqDirective,
+            "ifdef_directive" => Type::IfdefDirective,
+            "ifndef_directive" => Type::IfndefDirective,
+            "else_directive" => Type::ElseDirective,
+            "elsif_directive" => Type::ElsifDirective,
+            "variable_reference" => Type::VariableReference,
+            "substitution_reference" => Type::SubstitutionReference,
+            "automatic_variable" => Type::AutomaticVariable,
+            "function_call" => Type::FunctionCall,
+            "shell_function" => Type::ShellFunction,
+            "arguments" => Type::Arguments,
+            "concatenation" => Type::Concatenation,
+            "archive" => Type::Archive,
+            "list" => Type::List,
+            "paths" => Type::Paths,
+            "word" => Type::Word,
+            "string" => Type::String,
+            "raw_text" => Type::RawText,
+            "text" => Type::Text,
+            ":" => Type::Colon,
+            "::" => Type::ColonColon,
+            "&:" => Type::AmpColon,
+            "|" => Type::Pipe,
+            ";" => Type::SemiColon,
+            "@" => Type::At,
+            "-" => Type::Dash,
+            "+" => Type::Plus,
+            "=" => Type::Eq,
+            ":=" => Type::ColonEq,
+            "::=" => Type::ColonColonEq,
+            "?=" => Type::QMarkEq,
+            "+=" => Type::PlusEq,
+            "!=" => Type::BangEq,
+            "$" => Type::Dollar,
+            "$$" => Type::DollarDollar,
+            "(" => Type::LParen,
+            ")" => Type::RParen,
+            "{" => Type::LBrace,
+            "}" => Type::RBrace,
+            "," => Type::Comma,
+            "include" => Type::Include,
+            "sinclude" => Type::Sinclude,
+            "-include" => Type::DashInclude,
+            "vpath" => Type::Vpath,
+            "export" => Type::Export,
+            "unexport" => Type::Unexport,
+            "override" => Type::Override,
+            "undefine" => Type::Undefine,
+            "private" => Type::Private,
+            "define" => Type::Define,
+            "endef" => Type::Endef,
+            "ifeq" => Type::Ifeq,
+            "ifneq" => Type::Ifneq,
+            "ifdef" => Type::Ifdef,
+            "ifndef" => Type::Ifndef,
+            "else" => Type::Else,
+            "endif" => Type::Endif,
+            "shell" => Type::Shell,
+            "%" => Type::Percent,
+            "<" => Type::LT,
+            "?" => Type::QMark,
+            "^" => Type::Caret,
+            "*" => Type::Star,
+            "/" => Type::Slash,
+            "\\" => Type::BackSlash,
+            "'" => Type::SQuote,
+            "\"" => Type::DQuote,
+            "ERROR" => Type::ERROR,
+            _ => return None,
+        })
+    }
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Type::Makefile => "makefile",
+            Type::Comment => "comment",
+            Type::Rule => "rule",
+            Type::Targets => "targets",
+            Type::Prerequisites => "prerequisites",
+            Type::TargetPattern => "target_pattern",
+            Type::PatternList => "pattern_list",
+            Type::Recipe => "recipe",
+            Type::RecipeLine => "recipe_line",
+            Type::ShellText => "shell_text",
+            Type::ShellCommand => "shell_command",
+            Type::VariableAssignment => "variable_assignment",
+            Type::ShellAssignment => "shell_assignment",
+            Type::DefineDirective => "define_directive",
+            Type::IncludeDirective => "include_directive",
+            Type::VpathDirective => "vpath_directive",
+            Type::ExportDirective => "export_directive",
+            Type::UnexportDirective => "unexport_directive",
+            Type::OverrideDirective => "override_directive",
+            Type::UndefineDirective => "undefine_directive",
+            Type::PrivateDirective => "private_directive",
+            Type::Conditional => "conditional",
+            Type::IfeqDirective => "ifeq_directive",
+            Type::IfneqDirective => "ifneq_directive",
+            Type::IfdefDirective => "ifdef_directive",
+            Type::IfndefDirective => "ifndef_directive",
+            Type::ElseDirective => "else_directive",
+            Type::ElsifDirective => "elsif_directive",
+            Type::VariableReference => "variable_reference",
+            Type::SubstitutionReference => "substitution_reference",
+            Type::AutomaticVariable => "automatic_variable",
+            Type::FunctionCall => "function_call",
+            Type::ShellFunction => "shell_function",
+            Type::Arguments => "arguments",
+            Type::Concatenation => "concatenation",
+            Type::Archive => "archive",
+            Type::List => "list",
+            Type::Paths => "paths",
+            Type::Word => "word",
+            Type::String => "string",
+            Type::RawText => "raw_text",
+            Type::Text => "text",
+            Type::Colon => ":",
+            Type::ColonColon => "::",
+            Type::AmpColon => "&:",
+            Type::Pipe => "|",
+            Type::SemiColon => ";",
+            Type::At => "@",
+            Type::Dash => "-",
+            Type::Plus => "+",
+            Type::Eq => "=",
+            Type::ColonEq => ":=",
+            Type::ColonColonEq => "::=",
+            Type::QMarkEq => "?=",
+            Type::PlusEq => "+=",
+            Type::BangEq => "!=",
+            Type::Dollar => "$",
+            Type::DollarDollar => "$$",
+            Type::LParen => "(",
+            Type::RParen => ")",
+            Type::LBrace => "{",
+            Type::RBrace => "}",
+            Type::Comma => ",",
+            Type::Include => "include",
+            Type::Sinclude => "sinclude",
+            Type::DashInclude => "-include",
+            Type::Vpath => "vpath",
+            Type::Export => "export",
+            Type::Unexport => "unexport",
+            Type::Override => "override",
+            Type::Undefine => "undefine",
+            Type::Private => "private",
+            Type::Define => "define",
+            Type::Endef => "endef",
+            Type::Ifeq => "ifeq",
+            Type::Ifneq => "ifneq",
+            Type::Ifdef => "ifdef",
+            Type::Ifndef => "ifndef",
+            Type::Else => "else",
+            Type::Endif => "endif",
+            Type::Shell => "shell",
+            Type::Percent => "%",
+            Type::LT => "<",
+            Type::QMark => "?",
+            Type::Caret => "^",
+            Type::Star => "*",
+            Type::Slash => "/",
+            Type::BackSlash => "\\",
+            Type::SQuote => "'",
+            Type::DQuote => "\"",
+            Type::Token => "Token",
+            Type::Spaces => "Spaces",
+            Type::Directory => "Directory",
+            Type::ERROR => "ERROR",
+        }
+    }
+    pub fn is_hidden(&self) -> bool {
+        false
+    }
+    pub fn is_supertype(&self) -> bool {
+        false
+    }
+    pub fn is_named(&self) -> bool {
+        is!(
+            self,
+            Makefile,
+            Comment,
+            Rule,
+            Targets,
+            Prerequisites,
+            TargetPattern,
+            PatternList,
+            Recipe,
+            RecipeLine,
+            ShellText,
+            ShellCommand,
+            VariableAssignment,
+            ShellAssignment,
+            DefineDirective,
+            IncludeDirective,
+            VpathDirective,
+            ExportDirective,
+            UnexportDirective,
+            OverrideDirective,
+            UndefineDirective,
+            PrivateDirective,
+            Conditional,
+            IfeqDirective,
+            IfneqDirective,
+            IfdefDirective,
+            IfndefDirective,
+            ElseDirective,
+            ElsifDirective,
+            VariableReference,
+            SubstitutionReference,
+            AutomaticVariable,
+            FunctionCall,
+            ShellFunction,
+            Arguments,
+            Concatenation,
+            Archive,
+            List,
+            Paths,
+            Word,
+            String,
+            RawText,
+            Text,
+        )
+    }
+}
+
+const S_T_L: &[Type] = &[
+    Type::Makefile,
+    Type::Comment,
+    Type::Rule,
+    Type::Targets,
+    Type::Prerequisites,
+    Type::TargetPattern,
+    Type::PatternList,
+    Type::Recipe,
+    Type::RecipeLine,
+    Type::ShellText,
+    Type::ShellCommand,
+    Type::VariableAssignment,
+    Type::ShellAssignment,
+    Type::DefineDirective,
+    Type::IncludeDirective,
+    Type::VpathDirective,
+    Type::ExportDirective,
+    Type::UnexportDirective,
+    Type::OverrideDirective,
+    Type::UndefineDirective,
+    Type::PrivateDirective,
+    Type::Conditional,
+    Type::IfeqDirective,
+    Type::IfneqDirective,
+    Type::IfdefDirective,
+    Type::IfndefDirective,
+    Type::ElseDirective,
+    Type::ElsifDirective,
+    Type::VariableReference,
+    Type::SubstitutionReference,
+    Type::AutomaticVariable,
+    Type::FunctionCall,
+    Type::ShellFunction,
+    Type::Arguments,
+    Type::Concatenation,
+    Type::Archive,
+    Type::List,
+    Type::Paths,
+    Type::Word,
+    Type::String,
+    Type::RawText,
+    Type::Text,
+    Type::Colon,
+    Type::ColonColon,
+    Type::AmpColon,
+    Type::Pipe,
+    Type::SemiColon,
+    Type::At,
+    Type::Dash,
+    Type::Plus,
+    Type::Eq,
+    Type::ColonEq,
+    Type::ColonColonEq,
+    Type::QMarkEq,
+    Type::PlusEq,
+    Type::BangEq,
+    Type::Dollar,
+    Type::DollarDollar,
+    Type::LParen,
+    Type::RParen,
+    Type::LBrace,
+    Type::RBrace,
+    Type::Comma,
+    Type::Include,
+    Type::Sinclude,
+    Type::DashInclude,
+    Type::Vpath,
+    Type::Export,
+    Type::Unexport,
+    Type::Override,
+    Type::Undefine,
+    Type::Private,
+    Type::Define,
+    Type::Endef,
+    Type::Ifeq,
+    Type::Ifneq,
+    Type::Ifdef,
+    Type::Ifndef,
+    Type::Else,
+    Type::Endif,
+    Type::Shell,
+    Type::Percent,
+    Type::LT,
+    Type::QMark,
+    Type::Caret,
+    Type::Star,
+    Type::Slash,
+    Type::BackSlash,
+    Type::SQuote,
+    Type::DQuote,
+    Type::Token,
+    Type::Spaces,
+    Type::Directory,
+    Type::ERROR,
+];
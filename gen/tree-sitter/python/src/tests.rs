@@ -0,0 +1,62 @@
+use hyperast::store::SimpleStores;
+
+use crate::{
+    legion::{PythonTreeGen, tree_sitter_parse},
+    types::TStore,
+};
+
+static PACKAGE_INIT: &str = r#"from .core import Engine, run
+
+__all__ = ["Engine", "run"]
+"#;
+
+static MODULE: &str = r#"import os
+
+
+class Engine:
+    """Runs the jobs."""
+
+    def __init__(self, workers: int = 4):
+        self.workers = workers
+
+    async def run(self, *jobs, **opts):
+        for job in jobs:
+            if job is not None:
+                await job(**opts)
+        return [j for j in jobs if j]
+
+
+def run(path=os.getcwd()):
+    with open(path) as f:
+        return f"{path}: {len(f.read())}"
+"#;
+
+#[test]
+fn python_tree_sitter_simple() {
+    let tree = match tree_sitter_parse(MODULE.as_bytes()) {
+        Ok(t) => t,
+        Err(t) => panic!("{}", t.root_node().to_sexp()),
+    };
+    println!("{}", tree.root_node().to_sexp());
+}
+
+#[test]
+fn hyperast_on_python_files() {
+    let mut stores = SimpleStores::<TStore>::default();
+    let mut md_cache = Default::default();
+    for (name, text) in [("__init__.py", PACKAGE_INIT), ("core.py", MODULE)] {
+        let text = text.as_bytes();
+        let tree = match tree_sitter_parse(text) {
+            Ok(t) => t,
+            Err(t) => t,
+        };
+        let mut tree_gen = PythonTreeGen::new(&mut stores, &mut md_cache);
+        let x = tree_gen
+            .generate_file(name.as_bytes(), text, tree.walk())
+            .local;
+        let id = x.compressed_node;
+        use hyperast::nodes;
+        println!("{}", nodes::SexpSerializer::new(&stores, id));
+        println!("{}", nodes::TextSerializer::new(&stores, id));
+    }
+}
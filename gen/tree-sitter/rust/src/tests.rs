@@ -0,0 +1,78 @@
+use hyperast::store::SimpleStores;
+
+use crate::{
+    legion::{RustTreeGen, tree_sitter_parse},
+    types::TStore,
+};
+
+static LIB: &str = r#"//! Runs the jobs.
+pub mod core;
+
+pub use core::Engine;
+"#;
+
+static MODULE: &str = r#"use std::collections::HashMap;
+
+/// Runs the jobs.
+#[derive(Debug, Default)]
+pub struct Engine<'a, T: Clone> {
+    workers: usize,
+    jobs: HashMap<&'a str, Vec<T>>,
+}
+
+impl<'a, T: Clone> Engine<'a, T> {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            workers,
+            ..Default::default()
+        }
+    }
+
+    pub async fn run(&mut self, name: &'a str) -> Option<usize> {
+        let jobs = self.jobs.get(name)?;
+        for (i, _job) in jobs.iter().enumerate() {
+            if i >= self.workers {
+                break;
+            }
+        }
+        println!("{name}: {}", r"raw");
+        Some(jobs.len())
+    }
+}
+
+macro_rules! engine {
+    ($w:expr) => {
+        Engine::<()>::new($w)
+    };
+}
+"#;
+
+#[test]
+fn rust_tree_sitter_simple() {
+    let tree = match tree_sitter_parse(MODULE.as_bytes()) {
+        Ok(t) => t,
+        Err(t) => panic!("{}", t.root_node().to_sexp()),
+    };
+    println!("{}", tree.root_node().to_sexp());
+}
+
+#[test]
+fn hyperast_on_rust_files() {
+    let mut stores = SimpleStores::<TStore>::default();
+    let mut md_cache = Default::default();
+    for (name, text) in [("lib.rs", LIB), ("core.rs", MODULE)] {
+        let text = text.as_bytes();
+        let tree = match tree_sitter_parse(text) {
+            Ok(t) => t,
+            Err(t) => t,
+        };
+        let mut tree_gen = RustTreeGen::new(&mut stores, &mut md_cache);
+        let x = tree_gen
+            .generate_file(name.as_bytes(), text, tree.walk())
+            .local;
+        let id = x.compressed_node;
+        use hyperast::nodes;
+        println!("{}", nodes::SexpSerializer::new(&stores, id));
+        println!("{}", nodes::TextSerializer::new(&stores, id));
+    }
+}
@@ -0,0 +1,2652 @@
+use std::fmt::Display;
+
+use hyperast::tree_gen::utils_ts::{TsEnableTS, TsType};
+use hyperast::types::{
+    AAAA, AnyType, HyperType, LangRef, NodeId, TypeStore, TypeTrait, TypeU16, TypedNodeId,
+};
+
+impl TsEnableTS for TStore {
+    fn obtain_type<'a, N: hyperast::tree_gen::parser::NodeWithU16TypeId>(
+        n: &N,
+    ) -> <Self as hyperast::types::ETypeStore>::Ty2 {
+        let k = n.kind_id();
+        Type::from_u16(k)
+    }
+
+    fn try_obtain_type<N: hyperast::tree_gen::parser::NodeWithU16TypeId>(
+        n: &N,
+    ) -> Option<Self::Ty2> {
+        let k = n.kind_id();
+        const LEN: u16 = S_T_L.len() as u16;
+        if (LEN..TStore::LOWEST_RESERVED).contains(&k) {
+            return None;
+        }
+        Some(Type::from_u16(k))
+    }
+}
+
+impl TsType for Type {
+    fn spaces() -> Self {
+        Self::Spaces
+    }
+
+    fn is_repeat(&self) -> bool {
+        self.is_repeat()
+    }
+}
+
+impl TypeStore for TStore {
+    type Ty = TypeU16<Rust>;
+}
+
+impl hyperast::types::ETypeStore for TStore {
+    type Ty2 = Type;
+
+    fn intern(ty: Self::Ty2) -> Self::Ty {
+        TType::new(ty)
+    }
+}
+
+#[cfg(feature = "impl")]
+mod impls {
+    use super::*;
+
+    use hyperast::types::{LangWrapper, RoleStore};
+
+    impl RustEnabledTypeStore for TStore {
+        fn resolve(t: Self::Ty) -> Type {
+            t.e()
+        }
+    }
+
+    impl RoleStore for TStore {
+        type IdF = u16;
+
+        type Role = hyperast::types::Role;
+
+        fn resolve_field(_lang: LangWrapper<Self::Ty>, field_id: Self::IdF) -> Self::Role {
+            let s = crate::language()
+                .field_name_for_id(field_id)
+                .ok_or_else(|| format!("{}", field_id))
+                .unwrap();
+            hyperast::types::Role::try_from(s).expect(s)
+        }
+
+        fn intern_role(_lang: LangWrapper<Self::Ty>, role: Self::Role) -> Self::IdF {
+            let field_name = role.to_string();
+            crate::language()
+                .field_id_for_name(field_name)
+                .unwrap()
+                .into()
+        }
+    }
+}
+
+#[cfg(feature = "impl")]
+fn id_for_node_kind(kind: &str, named: bool) -> u16 {
+    crate::language().id_for_node_kind(kind, named)
+}
+#[cfg(not(feature = "impl"))]
+fn id_for_node_kind(_kind: &str, _named: bool) -> u16 {
+    unimplemented!("need treesitter grammar")
+}
+
+#[cfg(feature = "impl")]
+pub trait RustEnabledTypeStore:
+    hyperast::types::ETypeStore<Ty2 = Type> + Clone + TsEnableTS
+{
+    fn resolve(t: Self::Ty) -> Type;
+}
+
+#[cfg(not(feature = "impl"))]
+pub trait RustEnabledTypeStore: hyperast::types::ETypeStore<Ty2 = Type> {
+    fn resolve(t: Self::Ty) -> Type;
+}
+
+impl Type {
+    pub fn resolve(t: u16) -> Self {
+        assert!(t < COUNT);
+        unsafe { std::mem::transmute(t) }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TIdN<IdN>(IdN);
+
+impl<IdN: Clone + Eq + AAAA> NodeId for TIdN<IdN> {
+    type IdN = IdN;
+
+    fn as_id(&self) -> &Self::IdN {
+        &self.0
+    }
+
+    unsafe fn from_id(id: Self::IdN) -> Self {
+        Self(id)
+    }
+
+    unsafe fn from_ref_id(_id: &Self::IdN) -> &Self {
+        todo!()
+    }
+}
+
+impl<IdN: Clone + Eq + AAAA> TypedNodeId for TIdN<IdN> {
+    type Ty = Type;
+    type TyErazed = TType;
+    fn unerase(ty: Self::TyErazed) -> Self::Ty {
+        ty.e()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct TStore;
+
+impl Default for TStore {
+    fn default() -> Self {
+        Self
+    }
+}
+
+#[derive(Debug)]
+pub struct Lang;
+pub type Rust = Lang;
+
+impl Rust {
+    pub const INST: Rust = Lang;
+}
+
+pub fn as_any(t: &Type) -> AnyType {
+    let t = <Rust as hyperast::types::Lang<Type>>::to_u16(*t);
+    let t = <Rust as hyperast::types::Lang<Type>>::make(t);
+    let t: &'static dyn HyperType = t;
+    t.into()
+}
+
+impl LangRef<AnyType> for Rust {
+    fn make(&self, _t: u16) -> &'static AnyType {
+        panic!()
+    }
+    fn to_u16(&self, t: AnyType) -> u16 {
+        let t = t.as_any().downcast_ref::<Type>().unwrap();
+        *t as u16
+    }
+
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Rust>()
+    }
+
+    fn ts_symbol(&self, t: AnyType) -> u16 {
+        id_for_node_kind(t.as_static_str(), t.is_named())
+    }
+}
+
+impl LangRef<Type> for Rust {
+    fn make(&self, t: u16) -> &'static Type {
+        if t == TStore::ERROR {
+            &Type::ERROR
+        } else if t == TStore::_ERROR {
+            &Type::_ERROR
+        } else if t == TStore::SPACES {
+            &Type::Spaces
+        } else if t == TStore::DIRECTORY {
+            &Type::Directory
+        } else {
+            &S_T_L[t as usize]
+        }
+    }
+    fn to_u16(&self, t: Type) -> u16 {
+        t as u16
+    }
+
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Rust>()
+    }
+
+    fn ts_symbol(&self, t: Type) -> u16 {
+        id_for_node_kind(t.as_static_str(), t.is_named())
+    }
+}
+
+impl LangRef<TType> for Lang {
+    fn make(&self, t: u16) -> &'static TType {
+        // TODO could make one safe, but not priority
+        unsafe { std::mem::transmute(&S_T_L[t as usize]) }
+    }
+    fn to_u16(&self, t: TType) -> u16 {
+        t.e() as u16
+    }
+
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Lang>()
+    }
+
+    fn ts_symbol(&self, t: TType) -> u16 {
+        id_for_node_kind(t.as_static_str(), t.is_named())
+    }
+}
+
+impl hyperast::types::Lang<Type> for Rust {
+    const INST: Self = Lang;
+    fn make(t: u16) -> &'static Type {
+        Lang.make(t)
+    }
+    fn to_u16(t: Type) -> u16 {
+        Lang.to_u16(t)
+    }
+}
+
+macro_rules! is {
+    ($e:expr, $($p:ident $(if $guard:expr)?, )*) => {
+        match $e {$(
+            Type::$p $(if $guard)? => true,)*
+            _ => false
+        }
+    };
+}
+
+impl HyperType for Type {
+    fn generic_eq(&self, other: &dyn HyperType) -> bool
+    where
+        Self: 'static + PartialEq + Sized,
+    {
+        // Do a type-safe casting. If the types are different,
+        // return false, otherwise test the values for equality.
+        other
+            .as_any()
+            .downcast_ref::<Self>()
+            .map_or(false, |a| self == a)
+    }
+
+    fn is_directory(&self) -> bool {
+        self == &Type::Directory
+    }
+
+    fn is_file(&self) -> bool {
+        self == &Type::SourceFile
+    }
+
+    fn is_spaces(&self) -> bool {
+        self == &Type::Spaces
+    }
+
+    fn is_syntax(&self) -> bool {
+        !self.is_named() && !self.is_hidden() && !is!(self, Spaces, Directory, _ERROR, ERROR,)
+    }
+
+    fn as_shared(&self) -> hyperast::types::Shared {
+        use hyperast::types::Shared;
+        if self.is_error() {
+            return Shared::Error;
+        }
+        match self {
+            Type::StructItem | Type::EnumItem | Type::UnionItem | Type::TraitItem => {
+                Shared::TypeDeclaration
+            }
+            Type::LineComment | Type::BlockComment => Shared::Comment,
+            Type::Identifier => Shared::Identifier,
+            x if x.is_literal() => Shared::Literal,
+            _ => Shared::Other,
+        }
+    }
+
+    fn is_error(&self) -> bool {
+        self == &Self::ERROR || self == &Self::_ERROR
+    }
+
+    fn as_abstract(&self) -> hyperast::types::Abstracts {
+        use hyperast::types::Abstract;
+        Abstract::Statement.when(self.is_statement())
+            | Abstract::Executable.when(self.is_executable_member())
+            | Abstract::Declaration.when(self.is_type_declaration())
+            | Abstract::Literal.when(self.is_literal())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    /// ```
+    /// # fn main() {
+    /// # use hyperast_gen_ts_rust::types::Type;
+    /// # use hyperast::types::HyperType;
+    /// let k0 = Type::FunctionItem.as_static();
+    /// let k1 = Type::FunctionItem.as_static();
+    /// let k2 = Type::StructItem.as_static();
+    /// assert!(std::ptr::eq(k0,k1));
+    /// assert!(!std::ptr::eq(k0,k2));
+    /// # }
+    /// ```
+    fn as_static(&self) -> &'static dyn HyperType {
+        let t = <Rust as hyperast::types::Lang<Type>>::to_u16(*self);
+        (<Rust as hyperast::types::Lang<Type>>::make(t)) as _
+    }
+
+    fn as_static_str(&self) -> &'static str {
+        self.to_str()
+    }
+
+    fn is_hidden(&self) -> bool {
+        self.is_hidden()
+    }
+
+    fn is_supertype(&self) -> bool {
+        self.is_supertype()
+    }
+
+    fn is_named(&self) -> bool {
+        self.is_named()
+    }
+
+    fn get_lang(&self) -> hyperast::types::LangWrapper<Self>
+    where
+        Self: Sized,
+    {
+        hyperast::types::LangWrapper::from(&Lang as &(dyn LangRef<Self> + 'static))
+    }
+
+    fn lang_ref(&self) -> hyperast::types::LangWrapper<AnyType> {
+        hyperast::types::LangWrapper::from(&Lang as &(dyn LangRef<AnyType> + 'static))
+    }
+}
+
+impl TypeTrait for Type {
+    type Lang = Rust;
+
+    fn is_fork(&self) -> bool {
+        todo!()
+    }
+
+    fn is_literal(&self) -> bool {
+        is!(
+            self,
+            IntegerLiteral,
+            FloatLiteral,
+            StringLiteral,
+            RawStringLiteral,
+            CharLiteral,
+            BooleanLiteral,
+            NegativeLiteral,
+        )
+    }
+
+    fn is_primitive(&self) -> bool {
+        todo!()
+    }
+
+    fn is_type_declaration(&self) -> bool {
+        is!(self, StructItem, EnumItem, UnionItem, TraitItem, TypeItem,)
+    }
+
+    fn is_identifier(&self) -> bool {
+        self == &Self::Identifier
+    }
+
+    fn is_instance_ref(&self) -> bool {
+        todo!()
+    }
+
+    fn is_type_body(&self) -> bool {
+        todo!()
+    }
+
+    fn is_value_member(&self) -> bool {
+        todo!()
+    }
+
+    fn is_executable_member(&self) -> bool {
+        is!(self, FunctionItem, FunctionSignatureItem, ClosureExpression,)
+    }
+
+    fn is_statement(&self) -> bool {
+        is!(
+            self,
+            EmptyStatement,
+            ExpressionStatement,
+            LetDeclaration,
+            MacroDefinition,
+            MacroInvocation,
+            AttributeItem,
+            InnerAttributeItem,
+            ModItem,
+            ForeignModItem,
+            StructItem,
+            UnionItem,
+            EnumItem,
+            ExternCrateDeclaration,
+            ConstItem,
+            StaticItem,
+            TypeItem,
+            FunctionItem,
+            FunctionSignatureItem,
+            ImplItem,
+            TraitItem,
+            AssociatedType,
+            UseDeclaration,
+        )
+    }
+
+    fn is_declarative_statement(&self) -> bool {
+        todo!()
+    }
+
+    fn is_structural_statement(&self) -> bool {
+        todo!()
+    }
+
+    fn is_block_related(&self) -> bool {
+        todo!()
+    }
+
+    fn is_simple_statement(&self) -> bool {
+        todo!()
+    }
+
+    fn is_local_declare(&self) -> bool {
+        todo!()
+    }
+
+    fn is_parameter(&self) -> bool {
+        todo!()
+    }
+
+    fn is_parameter_list(&self) -> bool {
+        todo!()
+    }
+
+    fn is_argument_list(&self) -> bool {
+        todo!()
+    }
+
+    fn is_expression(&self) -> bool {
+        todo!()
+    }
+
+    fn is_comment(&self) -> bool {
+        is!(self, LineComment, BlockComment,)
+    }
+}
+
+const COUNT: u16 = S_T_L.len() as u16;
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+impl hyperast::types::LLang<TType> for Rust {
+    type I = u16;
+
+    type E = Type;
+
+    const TE: &[Self::E] = S_T_L;
+
+    fn as_lang_wrapper() -> hyperast::types::LangWrapper<TType> {
+        From::<&'static dyn LangRef<_>>::from(&Lang)
+    }
+}
+
+pub type TType = TypeU16<Lang>;
+
+impl From<u16> for Type {
+    fn from(value: u16) -> Self {
+        debug_assert_eq!(Self::from_u16(value), S_T_L[value as usize]);
+        S_T_L[value as usize]
+    }
+}
+
+impl From<Type> for TypeU16<Rust> {
+    fn from(val: Type) -> Self {
+        TypeU16::new(val)
+    }
+}
+
+impl From<Type> for u16 {
+    fn from(val: Type) -> Self {
+        val as u16
+    }
+}
+
+#[cfg(feature = "impl")]
+#[test]
+fn test_tslanguage_and_type_identity() {
+    let l = crate::language();
+    assert_eq!(l.node_kind_count(), S_T_L.len());
+    for id in 0..l.node_kind_count() {
+        let kind = l.node_kind_for_id(id as u16).unwrap();
+        let ty = Type::from_u16(id as u16);
+        assert_eq!(ty.to_str(), kind);
+        assert_eq!(ty.is_named(), l.node_kind_is_named(id as u16));
+    }
+}
+
+#[repr(u16)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Type {
+    End,
+    Identifier,
+    SemiColon,
+    TS0,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    BigArrow,
+    Colon,
+    TS1,
+    TokenRepetitionPatternToken1,
+    Plus,
+    Star,
+    QMark,
+    Block,
+    Expr,
+    TS2,
+    Ident,
+    Item,
+    Lifetime,
+    Literal,
+    Meta,
+    Pat,
+    PatParam,
+    Path,
+    Stmt,
+    Tt,
+    Ty,
+    Vis,
+    PrimitiveType,
+    PrimitiveType_,
+    PrimitiveType__,
+    PrimitiveType___,
+    PrimitiveType____,
+    PrimitiveType_____,
+    PrimitiveType______,
+    PrimitiveType_______,
+    PrimitiveType________,
+    PrimitiveType_________,
+    PrimitiveType__________,
+    PrimitiveType___________,
+    PrimitiveType____________,
+    PrimitiveType_____________,
+    PrimitiveType______________,
+    PrimitiveType_______________,
+    PrimitiveType________________,
+    Dash,
+    Slash,
+    Percent,
+    Caret,
+    Bang,
+    Amp,
+    Pipe,
+    AmpAmp,
+    PipePipe,
+    LtLt,
+    GtGt,
+    PlusEq,
+    DashEq,
+    StarEq,
+    SlashEq,
+    PercentEq,
+    CaretEq,
+    AmpEq,
+    PipeEq,
+    LtLtEq,
+    GtGtEq,
+    Eq,
+    EqEq,
+    BangEq,
+    GT,
+    LT,
+    GTEq,
+    LTEq,
+    At,
+    Inderscore,
+    Dot,
+    TS3,
+    DotDotDot,
+    TS4,
+    Comma,
+    ColonColon,
+    DashGt,
+    Sharp,
+    SQuote,
+    As,
+    Async,
+    Await,
+    Break,
+    Const,
+    Continue,
+    Default,
+    Enum,
+    Fn,
+    For,
+    Gen,
+    If,
+    Impl,
+    Let,
+    Loop,
+    Match,
+    Mod,
+    Pub,
+    Return,
+    Static,
+    Struct,
+    Trait,
+    Type,
+    Union,
+    Unsafe,
+    Use,
+    Where,
+    While,
+    Extern,
+    Ref,
+    Else,
+    In,
+    LT_,
+    Dyn,
+    MutableSpecifier,
+    Raw,
+    Yield,
+    Move,
+    Try,
+    IntegerLiteral,
+    DQuote,
+    CharLiteral,
+    EscapeSequence,
+    True,
+    False,
+    TS5,
+    LineCommentToken1,
+    LineCommentToken2,
+    LineCommentToken3,
+    Bang_,
+    Slash_,
+    TS6,
+    TS7,
+    Shebang,
+    Self_,
+    Super,
+    Crate,
+    Metavariable,
+    StringContent,
+    DQuote_,
+    _RawStringLiteralStart,
+    StringContent_,
+    _RawStringLiteralEnd,
+    FloatLiteral,
+    OuterDocCommentMarker,
+    InnerDocCommentMarker,
+    _BlockCommentContent,
+    DocComment,
+    _ErrorSentinel,
+    SourceFile,
+    _Statement,
+    EmptyStatement,
+    ExpressionStatement,
+    MacroDefinition,
+    MacroRule,
+    _TokenPattern,
+    TokenTreePattern,
+    TokenBindingPattern,
+    TokenRepetitionPattern,
+    FragmentSpecifier,
+    TokenTree,
+    TokenRepetition,
+    AttributeItem,
+    InnerAttributeItem,
+    Attribute,
+    ModItem,
+    ForeignModItem,
+    DeclarationList,
+    StructItem,
+    UnionItem,
+    EnumItem,
+    EnumVariantList,
+    EnumVariant,
+    FieldDeclarationList,
+    FieldDeclaration,
+    OrderedFieldDeclarationList,
+    ExternCrateDeclaration,
+    ConstItem,
+    StaticItem,
+    TypeItem,
+    FunctionItem,
+    FunctionSignatureItem,
+    FunctionModifiers,
+    WhereClause,
+    WherePredicate,
+    ImplItem,
+    TraitItem,
+    AssociatedType,
+    TraitBounds,
+    HigherRankedTraitBound,
+    RemovedTraitBound,
+    TypeParameters,
+    ConstParameter,
+    TypeParameter,
+    LifetimeParameter,
+    LetDeclaration,
+    UseDeclaration,
+    _UseClause,
+    ScopedUseList,
+    UseList,
+    UseAsClause,
+    UseWildcard,
+    Parameters,
+    SelfParameter,
+    VariadicParameter,
+    Parameter,
+    ExternModifier,
+    VisibilityModifier,
+    _Type,
+    BracketedType,
+    QualifiedType,
+    Lifetime_,
+    ArrayType,
+    ForLifetimes,
+    FunctionType,
+    TupleType,
+    UnitType,
+    GenericFunction,
+    GenericType,
+    GenericTypeWithTurbofish,
+    BoundedType,
+    UseBounds,
+    TypeArguments,
+    TypeBinding,
+    ReferenceType,
+    PointerType,
+    NeverType,
+    AbstractType,
+    DynamicType,
+    _ExpressionExceptRange,
+    _Expression,
+    MacroInvocation,
+    TokenTree_,
+    _DelimTokens,
+    _NonDelimToken,
+    ScopedIdentifier,
+    ScopedTypeIdentifier,
+    ScopedTypeIdentifier_,
+    RangeExpression,
+    UnaryExpression,
+    TryExpression,
+    ReferenceExpression,
+    BinaryExpression,
+    AssignmentExpression,
+    CompoundAssignmentExpr,
+    TypeCastExpression,
+    ReturnExpression,
+    YieldExpression,
+    CallExpression,
+    Arguments,
+    ArrayExpression,
+    ParenthesizedExpression,
+    TupleExpression,
+    UnitExpression,
+    StructExpression,
+    FieldInitializerList,
+    ShorthandFieldInitializer,
+    FieldInitializer,
+    BaseFieldInitializer,
+    IfExpression,
+    LetCondition,
+    _LetChain,
+    _Condition,
+    ElseClause,
+    MatchExpression,
+    MatchBlock,
+    MatchArm,
+    MatchArm_,
+    MatchPattern,
+    WhileExpression,
+    LoopExpression,
+    ForExpression,
+    ConstBlock,
+    ClosureExpression,
+    ClosureParameters,
+    Label,
+    BreakExpression,
+    ContinueExpression,
+    IndexExpression,
+    AwaitExpression,
+    FieldExpression,
+    UnsafeBlock,
+    AsyncBlock,
+    GenBlock,
+    TryBlock,
+    Block_,
+    _Pattern,
+    GenericPattern,
+    TuplePattern,
+    SlicePattern,
+    TupleStructPattern,
+    StructPattern,
+    FieldPattern,
+    RemainingFieldPattern,
+    MutPattern,
+    RangePattern,
+    RefPattern,
+    CapturedPattern,
+    ReferencePattern,
+    OrPattern,
+    _Literal,
+    _LiteralPattern,
+    NegativeLiteral,
+    StringLiteral,
+    RawStringLiteral,
+    BooleanLiteral,
+    LineComment,
+    _LineDocCommentMarker,
+    InnerDocCommentMarker_,
+    OuterDocCommentMarker_,
+    BlockComment,
+    _BlockDocCommentMarker,
+    SourceFileRepeat1,
+    MacroDefinitionRepeat1,
+    TokenTreePatternRepeat1,
+    TokenTreeRepeat1,
+    _NonSpecialTokenRepeat1,
+    DeclarationListRepeat1,
+    EnumVariantListRepeat1,
+    EnumVariantListRepeat2,
+    FieldDeclarationListRepeat1,
+    OrderedFieldDeclarationListRepeat1,
+    FunctionModifiersRepeat1,
+    WhereClauseRepeat1,
+    TraitBoundsRepeat1,
+    TypeParametersRepeat1,
+    UseListRepeat1,
+    ParametersRepeat1,
+    ForLifetimesRepeat1,
+    TupleTypeRepeat1,
+    UseBoundsRepeat1,
+    TypeArgumentsRepeat1,
+    DelimTokenTreeRepeat1,
+    ArgumentsRepeat1,
+    TupleExpressionRepeat1,
+    FieldInitializerListRepeat1,
+    MatchBlockRepeat1,
+    MatchArmRepeat1,
+    ClosureParametersRepeat1,
+    TuplePatternRepeat1,
+    SlicePatternRepeat1,
+    StructPatternRepeat1,
+    StringLiteralRepeat1,
+    FieldIdentifier,
+    LetChain,
+    ShorthandFieldIdentifier,
+    TypeIdentifier,
+    Directory = TStore::DIRECTORY,
+    Spaces = TStore::SPACES,
+    _ERROR = TStore::_ERROR,
+    ERROR = TStore::ERROR,
+}
+impl Type {
+    pub fn from_u16(t: u16) -> Type {
+        match t {
+            0u16 => Type::End,
+            1u16 => Type::Identifier,
+            2u16 => Type::SemiColon,
+            3u16 => Type::TS0,
+            4u16 => Type::LParen,
+            5u16 => Type::RParen,
+            6u16 => Type::LBracket,
+            7u16 => Type::RBracket,
+            8u16 => Type::LBrace,
+            9u16 => Type::RBrace,
+            10u16 => Type::BigArrow,
+            11u16 => Type::Colon,
+            12u16 => Type::TS1,
+            13u16 => Type::TokenRepetitionPatternToken1,
+            14u16 => Type::Plus,
+            15u16 => Type::Star,
+            16u16 => Type::QMark,
+            17u16 => Type::Block,
+            18u16 => Type::Expr,
+            19u16 => Type::TS2,
+            20u16 => Type::Ident,
+            21u16 => Type::Item,
+            22u16 => Type::Lifetime,
+            23u16 => Type::Literal,
+            24u16 => Type::Meta,
+            25u16 => Type::Pat,
+            26u16 => Type::PatParam,
+            27u16 => Type::Path,
+            28u16 => Type::Stmt,
+            29u16 => Type::Tt,
+            30u16 => Type::Ty,
+            31u16 => Type::Vis,
+            32u16 => Type::PrimitiveType,
+            33u16 => Type::PrimitiveType_,
+            34u16 => Type::PrimitiveType__,
+            35u16 => Type::PrimitiveType___,
+            36u16 => Type::PrimitiveType____,
+            37u16 => Type::PrimitiveType_____,
+            38u16 => Type::PrimitiveType______,
+            39u16 => Type::PrimitiveType_______,
+            40u16 => Type::PrimitiveType________,
+            41u16 => Type::PrimitiveType_________,
+            42u16 => Type::PrimitiveType__________,
+            43u16 => Type::PrimitiveType___________,
+            44u16 => Type::PrimitiveType____________,
+            45u16 => Type::PrimitiveType_____________,
+            46u16 => Type::PrimitiveType______________,
+            47u16 => Type::PrimitiveType_______________,
+            48u16 => Type::PrimitiveType________________,
+            49u16 => Type::Dash,
+            50u16 => Type::Slash,
+            51u16 => Type::Percent,
+            52u16 => Type::Caret,
+            53u16 => Type::Bang,
+            54u16 => Type::Amp,
+            55u16 => Type::Pipe,
+            56u16 => Type::AmpAmp,
+            57u16 => Type::PipePipe,
+            58u16 => Type::LtLt,
+            59u16 => Type::GtGt,
+            60u16 => Type::PlusEq,
+            61u16 => Type::DashEq,
+            62u16 => Type::StarEq,
+            63u16 => Type::SlashEq,
+            64u16 => Type::PercentEq,
+            65u16 => Type::CaretEq,
+            66u16 => Type::AmpEq,
+            67u16 => Type::PipeEq,
+            68u16 => Type::LtLtEq,
+            69u16 => Type::GtGtEq,
+            70u16 => Type::Eq,
+            71u16 => Type::EqEq,
+            72u16 => Type::BangEq,
+            73u16 => Type::GT,
+            74u16 => Type::LT,
+            75u16 => Type::GTEq,
+            76u16 => Type::LTEq,
+            77u16 => Type::At,
+            78u16 => Type::Inderscore,
+            79u16 => Type::Dot,
+            80u16 => Type::TS3,
+            81u16 => Type::DotDotDot,
+            82u16 => Type::TS4,
+            83u16 => Type::Comma,
+            84u16 => Type::ColonColon,
+            85u16 => Type::DashGt,
+            86u16 => Type::Sharp,
+            87u16 => Type::SQuote,
+            88u16 => Type::As,
+            89u16 => Type::Async,
+            90u16 => Type::Await,
+            91u16 => Type::Break,
+            92u16 => Type::Const,
+            93u16 => Type::Continue,
+            94u16 => Type::Default,
+            95u16 => Type::Enum,
+            96u16 => Type::Fn,
+            97u16 => Type::For,
+            98u16 => Type::Gen,
+            99u16 => Type::If,
+            100u16 => Type::Impl,
+            101u16 => Type::Let,
+            102u16 => Type::Loop,
+            103u16 => Type::Match,
+            104u16 => Type::Mod,
+            105u16 => Type::Pub,
+            106u16 => Type::Return,
+            107u16 => Type::Static,
+            108u16 => Type::Struct,
+            109u16 => Type::Trait,
+            110u16 => Type::Type,
+            111u16 => Type::Union,
+            112u16 => Type::Unsafe,
+            113u16 => Type::Use,
+            114u16 => Type::Where,
+            115u16 => Type::While,
+            116u16 => Type::Extern,
+            117u16 => Type::Ref,
+            118u16 => Type::Else,
+            119u16 => Type::In,
+            120u16 => Type::LT_,
+            121u16 => Type::Dyn,
+            122u16 => Type::MutableSpecifier,
+            123u16 => Type::Raw,
+            124u16 => Type::Yield,
+            125u16 => Type::Move,
+            126u16 => Type::Try,
+            127u16 => Type::IntegerLiteral,
+            128u16 => Type::DQuote,
+            129u16 => Type::CharLiteral,
+            130u16 => Type::EscapeSequence,
+            131u16 => Type::True,
+            132u16 => Type::False,
+            133u16 => Type::TS5,
+            134u16 => Type::LineCommentToken1,
+            135u16 => Type::LineCommentToken2,
+            136u16 => Type::LineCommentToken3,
+            137u16 => Type::Bang_,
+            138u16 => Type::Slash_,
+            139u16 => Type::TS6,
+            140u16 => Type::TS7,
+            141u16 => Type::Shebang,
+            142u16 => Type::Self_,
+            143u16 => Type::Super,
+            144u16 => Type::Crate,
+            145u16 => Type::Metavariable,
+            146u16 => Type::StringContent,
+            147u16 => Type::DQuote_,
+            148u16 => Type::_RawStringLiteralStart,
+            149u16 => Type::StringContent_,
+            150u16 => Type::_RawStringLiteralEnd,
+            151u16 => Type::FloatLiteral,
+            152u16 => Type::OuterDocCommentMarker,
+            153u16 => Type::InnerDocCommentMarker,
+            154u16 => Type::_BlockCommentContent,
+            155u16 => Type::DocComment,
+            156u16 => Type::_ErrorSentinel,
+            157u16 => Type::SourceFile,
+            158u16 => Type::_Statement,
+            159u16 => Type::EmptyStatement,
+            160u16 => Type::ExpressionStatement,
+            161u16 => Type::MacroDefinition,
+            162u16 => Type::MacroRule,
+            163u16 => Type::_TokenPattern,
+            164u16 => Type::TokenTreePattern,
+            165u16 => Type::TokenBindingPattern,
+            166u16 => Type::TokenRepetitionPattern,
+            167u16 => Type::FragmentSpecifier,
+            168u16 => Type::TokenTree,
+            169u16 => Type::TokenRepetition,
+            170u16 => Type::AttributeItem,
+            171u16 => Type::InnerAttributeItem,
+            172u16 => Type::Attribute,
+            173u16 => Type::ModItem,
+            174u16 => Type::ForeignModItem,
+            175u16 => Type::DeclarationList,
+            176u16 => Type::StructItem,
+            177u16 => Type::UnionItem,
+            178u16 => Type::EnumItem,
+            179u16 => Type::EnumVariantList,
+            180u16 => Type::EnumVariant,
+            181u16 => Type::FieldDeclarationList,
+            182u16 => Type::FieldDeclaration,
+            183u16 => Type::OrderedFieldDeclarationList,
+            184u16 => Type::ExternCrateDeclaration,
+            185u16 => Type::ConstItem,
+            186u16 => Type::StaticItem,
+            187u16 => Type::TypeItem,
+            188u16 => Type::FunctionItem,
+            189u16 => Type::FunctionSignatureItem,
+            190u16 => Type::FunctionModifiers,
+            191u16 => Type::WhereClause,
+            192u16 => Type::WherePredicate,
+            193u16 => Type::ImplItem,
+            194u16 => Type::TraitItem,
+            195u16 => Type::AssociatedType,
+            196u16 => Type::TraitBounds,
+            197u16 => Type::HigherRankedTraitBound,
+            198u16 => Type::RemovedTraitBound,
+            199u16 => Type::TypeParameters,
+            200u16 => Type::ConstParameter,
+            201u16 => Type::TypeParameter,
+            202u16 => Type::LifetimeParameter,
+            203u16 => Type::LetDeclaration,
+            204u16 => Type::UseDeclaration,
+            205u16 => Type::_UseClause,
+            206u16 => Type::ScopedUseList,
+            207u16 => Type::UseList,
+            208u16 => Type::UseAsClause,
+            209u16 => Type::UseWildcard,
+            210u16 => Type::Parameters,
+            211u16 => Type::SelfParameter,
+            212u16 => Type::VariadicParameter,
+            213u16 => Type::Parameter,
+            214u16 => Type::ExternModifier,
+            215u16 => Type::VisibilityModifier,
+            216u16 => Type::_Type,
+            217u16 => Type::BracketedType,
+            218u16 => Type::QualifiedType,
+            219u16 => Type::Lifetime_,
+            220u16 => Type::ArrayType,
+            221u16 => Type::ForLifetimes,
+            222u16 => Type::FunctionType,
+            223u16 => Type::TupleType,
+            224u16 => Type::UnitType,
+            225u16 => Type::GenericFunction,
+            226u16 => Type::GenericType,
+            227u16 => Type::GenericTypeWithTurbofish,
+            228u16 => Type::BoundedType,
+            229u16 => Type::UseBounds,
+            230u16 => Type::TypeArguments,
+            231u16 => Type::TypeBinding,
+            232u16 => Type::ReferenceType,
+            233u16 => Type::PointerType,
+            234u16 => Type::NeverType,
+            235u16 => Type::AbstractType,
+            236u16 => Type::DynamicType,
+            237u16 => Type::_ExpressionExceptRange,
+            238u16 => Type::_Expression,
+            239u16 => Type::MacroInvocation,
+            240u16 => Type::TokenTree_,
+            241u16 => Type::_DelimTokens,
+            242u16 => Type::_NonDelimToken,
+            243u16 => Type::ScopedIdentifier,
+            244u16 => Type::ScopedTypeIdentifier,
+            245u16 => Type::ScopedTypeIdentifier_,
+            246u16 => Type::RangeExpression,
+            247u16 => Type::UnaryExpression,
+            248u16 => Type::TryExpression,
+            249u16 => Type::ReferenceExpression,
+            250u16 => Type::BinaryExpression,
+            251u16 => Type::AssignmentExpression,
+            252u16 => Type::CompoundAssignmentExpr,
+            253u16 => Type::TypeCastExpression,
+            254u16 => Type::ReturnExpression,
+            255u16 => Type::YieldExpression,
+            256u16 => Type::CallExpression,
+            257u16 => Type::Arguments,
+            258u16 => Type::ArrayExpression,
+            259u16 => Type::ParenthesizedExpression,
+            260u16 => Type::TupleExpression,
+            261u16 => Type::UnitExpression,
+            262u16 => Type::StructExpression,
+            263u16 => Type::FieldInitializerList,
+            264u16 => Type::ShorthandFieldInitializer,
+            265u16 => Type::FieldInitializer,
+            266u16 => Type::BaseFieldInitializer,
+            267u16 => Type::IfExpression,
+            268u16 => Type::LetCondition,
+            269u16 => Type::_LetChain,
+            270u16 => Type::_Condition,
+            271u16 => Type::ElseClause,
+            272u16 => Type::MatchExpression,
+            273u16 => Type::MatchBlock,
+            274u16 => Type::MatchArm,
+            275u16 => Type::MatchArm_,
+            276u16 => Type::MatchPattern,
+            277u16 => Type::WhileExpression,
+            278u16 => Type::LoopExpression,
+            279u16 => Type::ForExpression,
+            280u16 => Type::ConstBlock,
+            281u16 => Type::ClosureExpression,
+            282u16 => Type::ClosureParameters,
+            283u16 => Type::Label,
+            284u16 => Type::BreakExpression,
+            285u16 => Type::ContinueExpression,
+            286u16 => Type::IndexExpression,
+            287u16 => Type::AwaitExpression,
+            288u16 => Type::FieldExpression,
+            289u16 => Type::UnsafeBlock,
+            290u16 => Type::AsyncBlock,
+            291u16 => Type::GenBlock,
+            292u16 => Type::TryBlock,
+            293u16 => Type::Block_,
+            294u16 => Type::_Pattern,
+            295u16 => Type::GenericPattern,
+            296u16 => Type::TuplePattern,
+            297u16 => Type::SlicePattern,
+            298u16 => Type::TupleStructPattern,
+            299u16 => Type::StructPattern,
+            300u16 => Type::FieldPattern,
+            301u16 => Type::RemainingFieldPattern,
+            302u16 => Type::MutPattern,
+            303u16 => Type::RangePattern,
+            304u16 => Type::RefPattern,
+            305u16 => Type::CapturedPattern,
+            306u16 => Type::ReferencePattern,
+            307u16 => Type::OrPattern,
+            308u16 => Type::_Literal,
+            309u16 => Type::_LiteralPattern,
+            310u16 => Type::NegativeLiteral,
+            311u16 => Type::StringLiteral,
+            312u16 => Type::RawStringLiteral,
+            313u16 => Type::BooleanLiteral,
+            314u16 => Type::LineComment,
+            315u16 => Type::_LineDocCommentMarker,
+            316u16 => Type::InnerDocCommentMarker_,
+            317u16 => Type::OuterDocCommentMarker_,
+            318u16 => Type::BlockComment,
+            319u16 => Type::_BlockDocCommentMarker,
+            320u16 => Type::SourceFileRepeat1,
+            321u16 => Type::MacroDefinitionRepeat1,
+            322u16 => Type::TokenTreePatternRepeat1,
+            323u16 => Type::TokenTreeRepeat1,
+            324u16 => Type::_NonSpecialTokenRepeat1,
+            325u16 => Type::DeclarationListRepeat1,
+            326u16 => Type::EnumVariantListRepeat1,
+            327u16 => Type::EnumVariantListRepeat2,
+            328u16 => Type::FieldDeclarationListRepeat1,
+            329u16 => Type::OrderedFieldDeclarationListRepeat1,
+            330u16 => Type::FunctionModifiersRepeat1,
+            331u16 => Type::WhereClauseRepeat1,
+            332u16 => Type::TraitBoundsRepeat1,
+            333u16 => Type::TypeParametersRepeat1,
+            334u16 => Type::UseListRepeat1,
+            335u16 => Type::ParametersRepeat1,
+            336u16 => Type::ForLifetimesRepeat1,
+            337u16 => Type::TupleTypeRepeat1,
+            338u16 => Type::UseBoundsRepeat1,
+            339u16 => Type::TypeArgumentsRepeat1,
+            340u16 => Type::DelimTokenTreeRepeat1,
+            341u16 => Type::ArgumentsRepeat1,
+            342u16 => Type::TupleExpressionRepeat1,
+            343u16 => Type::FieldInitializerListRepeat1,
+            344u16 => Type::MatchBlockRepeat1,
+            345u16 => Type::MatchArmRepeat1,
+            346u16 => Type::ClosureParametersRepeat1,
+            347u16 => Type::TuplePatternRepeat1,
+            348u16 => Type::SlicePatternRepeat1,
+            349u16 => Type::StructPatternRepeat1,
+            350u16 => Type::StringLiteralRepeat1,
+            351u16 => Type::FieldIdentifier,
+            352u16 => Type::LetChain,
+            353u16 => Type::ShorthandFieldIdentifier,
+            354u16 => Type::TypeIdentifier,
+            TStore::DIRECTORY => Type::Directory,
+            TStore::SPACES => Type::Spaces,
+            TStore::_ERROR => Type::_ERROR,
+            TStore::ERROR => Type::ERROR,
+            x => panic!("{}", x),
+        }
+    }
+    #[allow(unreachable_patterns)]
+    pub fn from_str(t: &str) -> Option<Type> {
+        Some(match t {
+            "end" => Type::End,
+            "identifier" => Type::Identifier,
+            ";" => Type::SemiColon,
+            "macro_rules!" => Type::TS0,
+            "(" => Type::LParen,
+            ")" => Type::RParen,
+            "[" => Type::LBracket,
+            "]" => Type::RBracket,
+            "{" => Type::LBrace,
+            "}" => Type::RBrace,
+            "=>" => Type::BigArrow,
+            ":" => Type::Colon,
+            "$" => Type::TS1,
+            "token_repetition_pattern_token1" => Type::TokenRepetitionPatternToken1,
+            "+" => Type::Plus,
+            "*" => Type::Star,
+            "?" => Type::QMark,
+            "block" => Type::Block,
+            "expr" => Type::Expr,
+            "expr_2021" => Type::TS2,
+            "ident" => Type::Ident,
+            "item" => Type::Item,
+            "lifetime" => Type::Lifetime,
+            "literal" => Type::Literal,
+            "meta" => Type::Meta,
+            "pat" => Type::Pat,
+            "pat_param" => Type::PatParam,
+            "path" => Type::Path,
+            "stmt" => Type::Stmt,
+            "tt" => Type::Tt,
+            "ty" => Type::Ty,
+            "vis" => Type::Vis,
+            "primitive_type" => Type::PrimitiveType,
+            "primitive_type" => Type::PrimitiveType_,
+            "primitive_type" => Type::PrimitiveType__,
+            "primitive_type" => Type::PrimitiveType___,
+            "primitive_type" => Type::PrimitiveType____,
+            "primitive_type" => Type::PrimitiveType_____,
+            "primitive_type" => Type::PrimitiveType______,
+            "primitive_type" => Type::PrimitiveType_______,
+            "primitive_type" => Type::PrimitiveType________,
+            "primitive_type" => Type::PrimitiveType_________,
+            "primitive_type" => Type::PrimitiveType__________,
+            "primitive_type" => Type::PrimitiveType___________,
+            "primitive_type" => Type::PrimitiveType____________,
+            "primitive_type" => Type::PrimitiveType_____________,
+            "primitive_type" => Type::PrimitiveType______________,
+            "primitive_type" => Type::PrimitiveType_______________,
+            "primitive_type" => Type::PrimitiveType________________,
+            "-" => Type::Dash,
+            "/" => Type::Slash,
+            "%" => Type::Percent,
+            "^" => Type::Caret,
+            "!" => Type::Bang,
+            "&" => Type::Amp,
+            "|" => Type::Pipe,
+            "&&" => Type::AmpAmp,
+            "||" => Type::PipePipe,
+            "<<" => Type::LtLt,
+            ">>" => Type::GtGt,
+            "+=" => Type::PlusEq,
+            "-=" => Type::DashEq,
+            "*=" => Type::StarEq,
+            "/=" => Type::SlashEq,
+            "%=" => Type::PercentEq,
+            "^=" => Type::CaretEq,
+            "&=" => Type::AmpEq,
+            "|=" => Type::PipeEq,
+            "<<=" => Type::LtLtEq,
+            ">>=" => Type::GtGtEq,
+            "=" => Type::Eq,
+            "==" => Type::EqEq,
+            "!=" => Type::BangEq,
+            ">" => Type::GT,
+            "<" => Type::LT,
+            ">=" => Type::GTEq,
+            "<=" => Type::LTEq,
+            "@" => Type::At,
+            "_" => Type::Inderscore,
+            "." => Type::Dot,
+            ".." => Type::TS3,
+            "..." => Type::DotDotDot,
+            "..=" => Type::TS4,
+            "," => Type::Comma,
+            "::" => Type::ColonColon,
+            "->" => Type::DashGt,
+            "#" => Type::Sharp,
+            "'" => Type::SQuote,
+            "as" => Type::As,
+            "async" => Type::Async,
+            "await" => Type::Await,
+            "break" => Type::Break,
+            "const" => Type::Const,
+            "continue" => Type::Continue,
+            "default" => Type::Default,
+            "enum" => Type::Enum,
+            "fn" => Type::Fn,
+            "for" => Type::For,
+            "gen" => Type::Gen,
+            "if" => Type::If,
+            "impl" => Type::Impl,
+            "let" => Type::Let,
+            "loop" => Type::Loop,
+            "match" => Type::Match,
+            "mod" => Type::Mod,
+            "pub" => Type::Pub,
+            "return" => Type::Return,
+            "static" => Type::Static,
+            "struct" => Type::Struct,
+            "trait" => Type::Trait,
+            "type" => Type::Type,
+            "union" => Type::Union,
+            "unsafe" => Type::Unsafe,
+            "use" => Type::Use,
+            "where" => Type::Where,
+            "while" => Type::While,
+            "extern" => Type::Extern,
+            "ref" => Type::Ref,
+            "else" => Type::Else,
+            "in" => Type::In,
+            "<" => Type::LT_,
+            "dyn" => Type::Dyn,
+            "mutable_specifier" => Type::MutableSpecifier,
+            "raw" => Type::Raw,
+            "yield" => Type::Yield,
+            "move" => Type::Move,
+            "try" => Type::Try,
+            "integer_literal" => Type::IntegerLiteral,
+            "\"" => Type::DQuote,
+            "char_literal" => Type::CharLiteral,
+            "escape_sequence" => Type::EscapeSequence,
+            "true" => Type::True,
+            "false" => Type::False,
+            "//" => Type::TS5,
+            "line_comment_token1" => Type::LineCommentToken1,
+            "line_comment_token2" => Type::LineCommentToken2,
+            "line_comment_token3" => Type::LineCommentToken3,
+            "!" => Type::Bang_,
+            "/" => Type::Slash_,
+            "/*" => Type::TS6,
+            "*/" => Type::TS7,
+            "shebang" => Type::Shebang,
+            "self" => Type::Self_,
+            "super" => Type::Super,
+            "crate" => Type::Crate,
+            "metavariable" => Type::Metavariable,
+            "string_content" => Type::StringContent,
+            "\"" => Type::DQuote_,
+            "_raw_string_literal_start" => Type::_RawStringLiteralStart,
+            "string_content" => Type::StringContent_,
+            "_raw_string_literal_end" => Type::_RawStringLiteralEnd,
+            "float_literal" => Type::FloatLiteral,
+            "outer_doc_comment_marker" => Type::OuterDocCommentMarker,
+            "inner_doc_comment_marker" => Type::InnerDocCommentMarker,
+            "_block_comment_content" => Type::_BlockCommentContent,
+            "doc_comment" => Type::DocComment,
+            "_error_sentinel" => Type::_ErrorSentinel,
+            "source_file" => Type::SourceFile,
+            "_statement" => Type::_Statement,
+            "empty_statement" => Type::EmptyStatement,
+            "expression_statement" => Type::ExpressionStatement,
+            "macro_definition" => Type::MacroDefinition,
+            "macro_rule" => Type::MacroRule,
+            "_token_pattern" => Type::_TokenPattern,
+            "token_tree_pattern" => Type::TokenTreePattern,
+            "token_binding_pattern" => Type::TokenBindingPattern,
+            "token_repetition_pattern" => Type::TokenRepetitionPattern,
+            "fragment_specifier" => Type::FragmentSpecifier,
+            "token_tree" => Type::TokenTree,
+            "token_repetition" => Type::TokenRepetition,
+            "attribute_item" => Type::AttributeItem,
+            "inner_attribute_item" => Type::InnerAttributeItem,
+            "attribute" => Type::Attribute,
+            "mod_item" => Type::ModItem,
+            "foreign_mod_item" => Type::ForeignModItem,
+            "declaration_list" => Type::DeclarationList,
+            "struct_item" => Type::StructItem,
+            "union_item" => Type::UnionItem,
+            "enum_item" => Type::EnumItem,
+            "enum_variant_list" => Type::EnumVariantList,
+            "enum_variant" => Type::EnumVariant,
+            "field_declaration_list" => Type::FieldDeclarationList,
+            "field_declaration" => Type::FieldDeclaration,
+            "ordered_field_declaration_list" => Type::OrderedFieldDeclarationList,
+            "extern_crate_declaration" => Type::ExternCrateDeclaration,
+            "const_item" => Type::ConstItem,
+            "static_item" => Type::StaticItem,
+            "type_item" => Type::TypeItem,
+            "function_item" => Type::FunctionItem,
+            "function_signature_item" => Type::FunctionSignatureItem,
+            "function_modifiers" => Type::FunctionModifiers,
+            "where_clause" => Type::WhereClause,
+            "where_predicate" => Type::WherePredicate,
+            "impl_item" => Type::ImplItem,
+            "trait_item" => Type::TraitItem,
+            "associated_type" => Type::AssociatedType,
+            "trait_bounds" => Type::TraitBounds,
+            "higher_ranked_trait_bound" => Type::HigherRankedTraitBound,
+            "removed_trait_bound" => Type::RemovedTraitBound,
+            "type_parameters" => Type::TypeParameters,
+            "const_parameter" => Type::ConstParameter,
+            "type_parameter" => Type::TypeParameter,
+            "lifetime_parameter" => Type::LifetimeParameter,
+            "let_declaration" => Type::LetDeclaration,
+            "use_declaration" => Type::UseDeclaration,
+            "_use_clause" => Type::_UseClause,
+            "scoped_use_list" => Type::ScopedUseList,
+            "use_list" => Type::UseList,
+            "use_as_clause" => Type::UseAsClause,
+            "use_wildcard" => Type::UseWildcard,
+            "parameters" => Type::Parameters,
+            "self_parameter" => Type::SelfParameter,
+            "variadic_parameter" => Type::VariadicParameter,
+            "parameter" => Type::Parameter,
+            "extern_modifier" => Type::ExternModifier,
+            "visibility_modifier" => Type::VisibilityModifier,
+            "_type" => Type::_Type,
+            "bracketed_type" => Type::BracketedType,
+            "qualified_type" => Type::QualifiedType,
+            "lifetime" => Type::Lifetime_,
+            "array_type" => Type::ArrayType,
+            "for_lifetimes" => Type::ForLifetimes,
+            "function_type" => Type::FunctionType,
+            "tuple_type" => Type::TupleType,
+            "unit_type" => Type::UnitType,
+            "generic_function" => Type::GenericFunction,
+            "generic_type" => Type::GenericType,
+            "generic_type_with_turbofish" => Type::GenericTypeWithTurbofish,
+            "bounded_type" => Type::BoundedType,
+            "use_bounds" => Type::UseBounds,
+            "type_arguments" => Type::TypeArguments,
+            "type_binding" => Type::TypeBinding,
+            "reference_type" => Type::ReferenceType,
+            "pointer_type" => Type::PointerType,
+            "never_type" => Type::NeverType,
+            "abstract_type" => Type::AbstractType,
+            "dynamic_type" => Type::DynamicType,
+            "_expression_except_range" => Type::_ExpressionExceptRange,
+            "_expression" => Type::_Expression,
+            "macro_invocation" => Type::MacroInvocation,
+            "token_tree" => Type::TokenTree_,
+            "_delim_tokens" => Type::_DelimTokens,
+            "_non_delim_token" => Type::_NonDelimToken,
+            "scoped_identifier" => Type::ScopedIdentifier,
+            "scoped_type_identifier" => Type::ScopedTypeIdentifier,
+            "scoped_type_identifier" => Type::ScopedTypeIdentifier_,
+            "range_expression" => Type::RangeExpression,
+            "unary_expression" => Type::UnaryExpression,
+            "try_expression" => Type::TryExpression,
+            "reference_expression" => Type::ReferenceExpression,
+            "binary_expression" => Type::BinaryExpression,
+            "assignment_expression" => Type::AssignmentExpression,
+            "compound_assignment_expr" => Type::CompoundAssignmentExpr,
+            "type_cast_expression" => Type::TypeCastExpression,
+            "return_expression" => Type::ReturnExpression,
+            "yield_expression" => Type::YieldExpression,
+            "call_expression" => Type::CallExpression,
+            "arguments" => Type::Arguments,
+            "array_expression" => Type::ArrayExpression,
+            "parenthesized_expression" => Type::ParenthesizedExpression,
+            "tuple_expression" => Type::TupleExpression,
+            "unit_expression" => Type::UnitExpression,
+            "struct_expression" => Type::StructExpression,
+            "field_initializer_list" => Type::FieldInitializerList,
+            "shorthand_field_initializer" => Type::ShorthandFieldInitializer,
+            "field_initializer" => Type::FieldInitializer,
+            "base_field_initializer" => Type::BaseFieldInitializer,
+            "if_expression" => Type::IfExpression,
+            "let_condition" => Type::LetCondition,
+            "_let_chain" => Type::_LetChain,
+            "_condition" => Type::_Condition,
+            "else_clause" => Type::ElseClause,
+            "match_expression" => Type::MatchExpression,
+            "match_block" => Type::MatchBlock,
+            "match_arm" => Type::MatchArm,
+            "match_arm" => Type::MatchArm_,
+            "match_pattern" => Type::MatchPattern,
+            "while_expression" => Type::WhileExpression,
+            "loop_expression" => Type::LoopExpression,
+            "for_expression" => Type::ForExpression,
+            "const_block" => Type::ConstBlock,
+            "closure_expression" => Type::ClosureExpression,
+            "closure_parameters" => Type::ClosureParameters,
+            "label" => Type::Label,
+            "break_expression" => Type::BreakExpression,
+            "continue_expression" => Type::ContinueExpression,
+            "index_expression" => Type::IndexExpression,
+            "await_expression" => Type::AwaitExpression,
+            "field_expression" => Type::FieldExpression,
+            "unsafe_block" => Type::UnsafeBlock,
+            "async_block" => Type::AsyncBlock,
+            "gen_block" => Type::GenBlock,
+            "try_block" => Type::TryBlock,
+            "block" => Type::Block_,
+            "_pattern" => Type::_Pattern,
+            "generic_pattern" => Type::GenericPattern,
+            "tuple_pattern" => Type::TuplePattern,
+            "slice_pattern" => Type::SlicePattern,
+            "tuple_struct_pattern" => Type::TupleStructPattern,
+            "struct_pattern" => Type::StructPattern,
+            "field_pattern" => Type::FieldPattern,
+            "remaining_field_pattern" => Type::RemainingFieldPattern,
+            "mut_pattern" => Type::MutPattern,
+            "range_pattern" => Type::RangePattern,
+            "ref_pattern" => Type::RefPattern,
+            "captured_pattern" => Type::CapturedPattern,
+            "reference_pattern" => Type::ReferencePattern,
+            "or_pattern" => Type::OrPattern,
+            "_literal" => Type::_Literal,
+            "_literal_pattern" => Type::_LiteralPattern,
+            "negative_literal" => Type::NegativeLiteral,
+            "string_literal" => Type::StringLiteral,
+            "raw_string_literal" => Type::RawStringLiteral,
+            "boolean_literal" => Type::BooleanLiteral,
+            "line_comment" => Type::LineComment,
+            "_line_doc_comment_marker" => Type::_LineDocCommentMarker,
+            "inner_doc_comment_marker" => Type::InnerDocCommentMarker_,
+            "outer_doc_comment_marker" => Type::OuterDocCommentMarker_,
+            "block_comment" => Type::BlockComment,
+            "_block_doc_comment_marker" => Type::_BlockDocCommentMarker,
+            "source_file_repeat1" => Type::SourceFileRepeat1,
+            "macro_definition_repeat1" => Type::MacroDefinitionRepeat1,
+            "token_tree_pattern_repeat1" => Type::TokenTreePatternRepeat1,
+            "token_tree_repeat1" => Type::TokenTreeRepeat1,
+            "_non_special_token_repeat1" => Type::_NonSpecialTokenRepeat1,
+            "declaration_list_repeat1" => Type::DeclarationListRepeat1,
+            "enum_variant_list_repeat1" => Type::EnumVariantListRepeat1,
+            "enum_variant_list_repeat2" => Type::EnumVariantListRepeat2,
+            "field_declaration_list_repeat1" => Type::FieldDeclarationListRepeat1,
+            "ordered_field_declaration_list_repeat1" => Type::OrderedFieldDeclarationListRepeat1,
+            "function_modifiers_repeat1" => Type::FunctionModifiersRepeat1,
+            "where_clause_repeat1" => Type::WhereClauseRepeat1,
+            "trait_bounds_repeat1" => Type::TraitBoundsRepeat1,
+            "type_parameters_repeat1" => Type::TypeParametersRepeat1,
+            "use_list_repeat1" => Type::UseListRepeat1,
+            "parameters_repeat1" => Type::ParametersRepeat1,
+            "for_lifetimes_repeat1" => Type::ForLifetimesRepeat1,
+            "tuple_type_repeat1" => Type::TupleTypeRepeat1,
+            "use_bounds_repeat1" => Type::UseBoundsRepeat1,
+            "type_arguments_repeat1" => Type::TypeArgumentsRepeat1,
+            "delim_token_tree_repeat1" => Type::DelimTokenTreeRepeat1,
+            "arguments_repeat1" => Type::ArgumentsRepeat1,
+            "tuple_expression_repeat1" => Type::TupleExpressionRepeat1,
+            "field_initializer_list_repeat1" => Type::FieldInitializerListRepeat1,
+            "match_block_repeat1" => Type::MatchBlockRepeat1,
+            "match_arm_repeat1" => Type::MatchArmRepeat1,
+            "closure_parameters_repeat1" => Type::ClosureParametersRepeat1,
+            "tuple_pattern_repeat1" => Type::TuplePatternRepeat1,
+            "slice_pattern_repeat1" => Type::SlicePatternRepeat1,
+            "struct_pattern_repeat1" => Type::StructPatternRepeat1,
+            "string_literal_repeat1" => Type::StringLiteralRepeat1,
+            "field_identifier" => Type::FieldIdentifier,
+            "let_chain" => Type::LetChain,
+            "shorthand_field_identifier" => Type::ShorthandFieldIdentifier,
+            "type_identifier" => Type::TypeIdentifier,
+            "Directory" => Type::Directory,
+            "Spaces" => Type::Spaces,
+            "_ERROR" => Type::_ERROR,
+            "ERROR" => Type::ERROR,
+            _ => return None,
+        })
+    }
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Type::End => "end",
+            Type::Identifier => "identifier",
+            Type::SemiColon => ";",
+            Type::TS0 => "macro_rules!",
+            Type::LParen => "(",
+            Type::RParen => ")",
+            Type::LBracket => "[",
+            Type::RBracket => "]",
+            Type::LBrace => "{",
+            Type::RBrace => "}",
+            Type::BigArrow => "=>",
+            Type::Colon => ":",
+            Type::TS1 => "$",
+            Type::TokenRepetitionPatternToken1 => "token_repetition_pattern_token1",
+            Type::Plus => "+",
+            Type::Star => "*",
+            Type::QMark => "?",
+            Type::Block => "block",
+            Type::Expr => "expr",
+            Type::TS2 => "expr_2021",
+            Type::Ident => "ident",
+            Type::Item => "item",
+            Type::Lifetime => "lifetime",
+            Type::Literal => "literal",
+            Type::Meta => "meta",
+            Type::Pat => "pat",
+            Type::PatParam => "pat_param",
+            Type::Path => "path",
+            Type::Stmt => "stmt",
+            Type::Tt => "tt",
+            Type::Ty => "ty",
+            Type::Vis => "vis",
+            Type::PrimitiveType => "primitive_type",
+            Type::PrimitiveType_ => "primitive_type",
+            Type::PrimitiveType__ => "primitive_type",
+            Type::PrimitiveType___ => "primitive_type",
+            Type::PrimitiveType____ => "primitive_type",
+            Type::PrimitiveType_____ => "primitive_type",
+            Type::PrimitiveType______ => "primitive_type",
+            Type::PrimitiveType_______ => "primitive_type",
+            Type::PrimitiveType________ => "primitive_type",
+            Type::PrimitiveType_________ => "primitive_type",
+            Type::PrimitiveType__________ => "primitive_type",
+            Type::PrimitiveType___________ => "primitive_type",
+            Type::PrimitiveType____________ => "primitive_type",
+            Type::PrimitiveType_____________ => "primitive_type",
+            Type::PrimitiveType______________ => "primitive_type",
+            Type::PrimitiveType_______________ => "primitive_type",
+            Type::PrimitiveType________________ => "primitive_type",
+            Type::Dash => "-",
+            Type::Slash => "/",
+            Type::Percent => "%",
+            Type::Caret => "^",
+            Type::Bang => "!",
+            Type::Amp => "&",
+            Type::Pipe => "|",
+            Type::AmpAmp => "&&",
+            Type::PipePipe => "||",
+            Type::LtLt => "<<",
+            Type::GtGt => ">>",
+            Type::PlusEq => "+=",
+            Type::DashEq => "-=",
+            Type::StarEq => "*=",
+            Type::SlashEq => "/=",
+            Type::PercentEq => "%=",
+            Type::CaretEq => "^=",
+            Type::AmpEq => "&=",
+            Type::PipeEq => "|=",
+            Type::LtLtEq => "<<=",
+            Type::GtGtEq => ">>=",
+            Type::Eq => "=",
+            Type::EqEq => "==",
+            Type::BangEq => "!=",
+            Type::GT => ">",
+            Type::LT => "<",
+            Type::GTEq => ">=",
+            Type::LTEq => "<=",
+            Type::At => "@",
+            Type::Inderscore => "_",
+            Type::Dot => ".",
+            Type::TS3 => "..",
+            Type::DotDotDot => "...",
+            Type::TS4 => "..=",
+            Type::Comma => ",",
+            Type::ColonColon => "::",
+            Type::DashGt => "->",
+            Type::Sharp => "#",
+            Type::SQuote => "'",
+            Type::As => "as",
+            Type::Async => "async",
+            Type::Await => "await",
+            Type::Break => "break",
+            Type::Const => "const",
+            Type::Continue => "continue",
+            Type::Default => "default",
+            Type::Enum => "enum",
+            Type::Fn => "fn",
+            Type::For => "for",
+            Type::Gen => "gen",
+            Type::If => "if",
+            Type::Impl => "impl",
+            Type::Let => "let",
+            Type::Loop => "loop",
+            Type::Match => "match",
+            Type::Mod => "mod",
+            Type::Pub => "pub",
+            Type::Return => "return",
+            Type::Static => "static",
+            Type::Struct => "struct",
+            Type::Trait => "trait",
+            Type::Type => "type",
+            Type::Union => "union",
+            Type::Unsafe => "unsafe",
+            Type::Use => "use",
+            Type::Where => "where",
+            Type::While => "while",
+            Type::Extern => "extern",
+            Type::Ref => "ref",
+            Type::Else => "else",
+            Type::In => "in",
+            Type::LT_ => "<",
+            Type::Dyn => "dyn",
+            Type::MutableSpecifier => "mutable_specifier",
+            Type::Raw => "raw",
+            Type::Yield => "yield",
+            Type::Move => "move",
+            Type::Try => "try",
+            Type::IntegerLiteral => "integer_literal",
+            Type::DQuote => "\"",
+            Type::CharLiteral => "char_literal",
+            Type::EscapeSequence => "escape_sequence",
+            Type::True => "true",
+            Type::False => "false",
+            Type::TS5 => "//",
+            Type::LineCommentToken1 => "line_comment_token1",
+            Type::LineCommentToken2 => "line_comment_token2",
+            Type::LineCommentToken3 => "line_comment_token3",
+            Type::Bang_ => "!",
+            Type::Slash_ => "/",
+            Type::TS6 => "/*",
+            Type::TS7 => "*/",
+            Type::Shebang => "shebang",
+            Type::Self_ => "self",
+            Type::Super => "super",
+            Type::Crate => "crate",
+            Type::Metavariable => "metavariable",
+            Type::StringContent => "string_content",
+            Type::DQuote_ => "\"",
+            Type::_RawStringLiteralStart => "_raw_string_literal_start",
+            Type::StringContent_ => "string_content",
+            Type::_RawStringLiteralEnd => "_raw_string_literal_end",
+            Type::FloatLiteral => "float_literal",
+            Type::OuterDocCommentMarker => "outer_doc_comment_marker",
+            Type::InnerDocCommentMarker => "inner_doc_comment_marker",
+            Type::_BlockCommentContent => "_block_comment_content",
+            Type::DocComment => "doc_comment",
+            Type::_ErrorSentinel => "_error_sentinel",
+            Type::SourceFile => "source_file",
+            Type::_Statement => "_statement",
+            Type::EmptyStatement => "empty_statement",
+            Type::ExpressionStatement => "expression_statement",
+            Type::MacroDefinition => "macro_definition",
+            Type::MacroRule => "macro_rule",
+            Type::_TokenPattern => "_token_pattern",
+            Type::TokenTreePattern => "token_tree_pattern",
+            Type::TokenBindingPattern => "token_binding_pattern",
+            Type::TokenRepetitionPattern => "token_repetition_pattern",
+            Type::FragmentSpecifier => "fragment_specifier",
+            Type::TokenTree => "token_tree",
+            Type::TokenRepetition => "token_repetition",
+            Type::AttributeItem => "attribute_item",
+            Type::InnerAttributeItem => "inner_attribute_item",
+            Type::Attribute => "attribute",
+            Type::ModItem => "mod_item",
+            Type::ForeignModItem => "foreign_mod_item",
+            Type::DeclarationList => "declaration_list",
+            Type::StructItem => "struct_item",
+            Type::UnionItem => "union_item",
+            Type::EnumItem => "enum_item",
+            Type::EnumVariantList => "enum_variant_list",
+            Type::EnumVariant => "enum_variant",
+            Type::FieldDeclarationList => "field_declaration_list",
+            Type::FieldDeclaration => "field_declaration",
+            Type::OrderedFieldDeclarationList => "ordered_field_declaration_list",
+            Type::ExternCrateDeclaration => "extern_crate_declaration",
+            Type::ConstItem => "const_item",
+            Type::StaticItem => "static_item",
+            Type::TypeItem => "type_item",
+            Type::FunctionItem => "function_item",
+            Type::FunctionSignatureItem => "function_signature_item",
+            Type::FunctionModifiers => "function_modifiers",
+            Type::WhereClause => "where_clause",
+            Type::WherePredicate => "where_predicate",
+            Type::ImplItem => "impl_item",
+            Type::TraitItem => "trait_item",
+            Type::AssociatedType => "associated_type",
+            Type::TraitBounds => "trait_bounds",
+            Type::HigherRankedTraitBound => "higher_ranked_trait_bound",
+            Type::RemovedTraitBound => "removed_trait_bound",
+            Type::TypeParameters => "type_parameters",
+            Type::ConstParameter => "const_parameter",
+            Type::TypeParameter => "type_parameter",
+            Type::LifetimeParameter => "lifetime_parameter",
+            Type::LetDeclaration => "let_declaration",
+            Type::UseDeclaration => "use_declaration",
+            Type::_UseClause => "_use_clause",
+            Type::ScopedUseList => "scoped_use_list",
+            Type::UseList => "use_list",
+            Type::UseAsClause => "use_as_clause",
+            Type::UseWildcard => "use_wildcard",
+            Type::Parameters => "parameters",
+            Type::SelfParameter => "self_parameter",
+            Type::VariadicParameter => "variadic_parameter",
+            Type::Parameter => "parameter",
+            Type::ExternModifier => "extern_modifier",
+            Type::VisibilityModifier => "visibility_modifier",
+            Type::_Type => "_type",
+            Type::BracketedType => "bracketed_type",
+            Type::QualifiedType => "qualified_type",
+            Type::Lifetime_ => "lifetime",
+            Type::ArrayType => "array_type",
+            Type::ForLifetimes => "for_lifetimes",
+            Type::FunctionType => "function_type",
+            Type::TupleType => "tuple_type",
+            Type::UnitType => "unit_type",
+            Type::GenericFunction => "generic_function",
+            Type::GenericType => "generic_type",
+            Type::GenericTypeWithTurbofish => "generic_type_with_turbofish",
+            Type::BoundedType => "bounded_type",
+            Type::UseBounds => "use_bounds",
+            Type::TypeArguments => "type_arguments",
+            Type::TypeBinding => "type_binding",
+            Type::ReferenceType => "reference_type",
+            Type::PointerType => "pointer_type",
+            Type::NeverType => "never_type",
+            Type::AbstractType => "abstract_type",
+            Type::DynamicType => "dynamic_type",
+            Type::_ExpressionExceptRange => "_expression_except_range",
+            Type::_Expression => "_expression",
+            Type::MacroInvocation => "macro_invocation",
+            Type::TokenTree_ => "token_tree",
+            Type::_DelimTokens => "_delim_tokens",
+            Type::_NonDelimToken => "_non_delim_token",
+            Type::ScopedIdentifier => "scoped_identifier",
+            Type::ScopedTypeIdentifier => "scoped_type_identifier",
+            Type::ScopedTypeIdentifier_ => "scoped_type_identifier",
+            Type::RangeExpression => "range_expression",
+            Type::UnaryExpression => "unary_expression",
+            Type::TryExpression => "try_expression",
+            Type::ReferenceExpression => "reference_expression",
+            Type::BinaryExpression => "binary_expression",
+            Type::AssignmentExpression => "assignment_expression",
+            Type::CompoundAssignmentExpr => "compound_assignment_expr",
+            Type::TypeCastExpression => "type_cast_expression",
+            Type::ReturnExpression => "return_expression",
+            Type::YieldExpression => "yield_expression",
+            Type::CallExpression => "call_expression",
+            Type::Arguments => "arguments",
+            Type::ArrayExpression => "array_expression",
+            Type::ParenthesizedExpression => "parenthesized_expression",
+            Type::TupleExpression => "tuple_expression",
+            Type::UnitExpression => "unit_expression",
+            Type::StructExpression => "struct_expression",
+            Type::FieldInitializerList => "field_initializer_list",
+            Type::ShorthandFieldInitializer => "shorthand_field_initializer",
+            Type::FieldInitializer => "field_initializer",
+            Type::BaseFieldInitializer => "base_field_initializer",
+            Type::IfExpression => "if_expression",
+            Type::LetCondition => "let_condition",
+            Type::_LetChain => "_let_chain",
+            Type::_Condition => "_condition",
+            Type::ElseClause => "else_clause",
+            Type::MatchExpression => "match_expression",
+            Type::MatchBlock => "match_block",
+            Type::MatchArm => "match_arm",
+            Type::MatchArm_ => "match_arm",
+            Type::MatchPattern => "match_pattern",
+            Type::WhileExpression => "while_expression",
+            Type::LoopExpression => "loop_expression",
+            Type::ForExpression => "for_expression",
+            Type::ConstBlock => "const_block",
+            Type::ClosureExpression => "closure_expression",
+            Type::ClosureParameters => "closure_parameters",
+            Type::Label => "label",
+            Type::BreakExpression => "break_expression",
+            Type::ContinueExpression => "continue_expression",
+            Type::IndexExpression => "index_expression",
+            Type::AwaitExpression => "await_expression",
+            Type::FieldExpression => "field_expression",
+            Type::UnsafeBlock => "unsafe_block",
+            Type::AsyncBlock => "async_block",
+            Type::GenBlock => "gen_block",
+            Type::TryBlock => "try_block",
+            Type::Block_ => "block",
+            Type::_Pattern => "_pattern",
+            Type::GenericPattern => "generic_pattern",
+            Type::TuplePattern => "tuple_pattern",
+            Type::SlicePattern => "slice_pattern",
+            Type::TupleStructPattern => "tuple_struct_pattern",
+            Type::StructPattern => "struct_pattern",
+            Type::FieldPattern => "field_pattern",
+            Type::RemainingFieldPattern => "remaining_field_pattern",
+            Type::MutPattern => "mut_pattern",
+            Type::RangePattern => "range_pattern",
+            Type::RefPattern => "ref_pattern",
+            Type::CapturedPattern => "captured_pattern",
+            Type::ReferencePattern => "reference_pattern",
+            Type::OrPattern => "or_pattern",
+            Type::_Literal => "_literal",
+            Type::_LiteralPattern => "_literal_pattern",
+            Type::NegativeLiteral => "negative_literal",
+            Type::StringLiteral => "string_literal",
+            Type::RawStringLiteral => "raw_string_literal",
+            Type::BooleanLiteral => "boolean_literal",
+            Type::LineComment => "line_comment",
+            Type::_LineDocCommentMarker => "_line_doc_comment_marker",
+            Type::InnerDocCommentMarker_ => "inner_doc_comment_marker",
+            Type::OuterDocCommentMarker_ => "outer_doc_comment_marker",
+            Type::BlockComment => "block_comment",
+            Type::_BlockDocCommentMarker => "_block_doc_comment_marker",
+            Type::SourceFileRepeat1 => "source_file_repeat1",
+            Type::MacroDefinitionRepeat1 => "macro_definition_repeat1",
+            Type::TokenTreePatternRepeat1 => "token_tree_pattern_repeat1",
+            Type::TokenTreeRepeat1 => "token_tree_repeat1",
+            Type::_NonSpecialTokenRepeat1 => "_non_special_token_repeat1",
+            Type::DeclarationListRepeat1 => "declaration_list_repeat1",
+            Type::EnumVariantListRepeat1 => "enum_variant_list_repeat1",
+            Type::EnumVariantListRepeat2 => "enum_variant_list_repeat2",
+            Type::FieldDeclarationListRepeat1 => "field_declaration_list_repeat1",
+            Type::OrderedFieldDeclarationListRepeat1 => "ordered_field_declaration_list_repeat1",
+            Type::FunctionModifiersRepeat1 => "function_modifiers_repeat1",
+            Type::WhereClauseRepeat1 => "where_clause_repeat1",
+            Type::TraitBoundsRepeat1 => "trait_bounds_repeat1",
+            Type::TypeParametersRepeat1 => "type_parameters_repeat1",
+            Type::UseListRepeat1 => "use_list_repeat1",
+            Type::ParametersRepeat1 => "parameters_repeat1",
+            Type::ForLifetimesRepeat1 => "for_lifetimes_repeat1",
+            Type::TupleTypeRepeat1 => "tuple_type_repeat1",
+            Type::UseBoundsRepeat1 => "use_bounds_repeat1",
+            Type::TypeArgumentsRepeat1 => "type_arguments_repeat1",
+            Type::DelimTokenTreeRepeat1 => "delim_token_tree_repeat1",
+            Type::ArgumentsRepeat1 => "arguments_repeat1",
+            Type::TupleExpressionRepeat1 => "tuple_expression_repeat1",
+            Type::FieldInitializerListRepeat1 => "field_initializer_list_repeat1",
+            Type::MatchBlockRepeat1 => "match_block_repeat1",
+            Type::MatchArmRepeat1 => "match_arm_repeat1",
+            Type::ClosureParametersRepeat1 => "closure_parameters_repeat1",
+            Type::TuplePatternRepeat1 => "tuple_pattern_repeat1",
+            Type::SlicePatternRepeat1 => "slice_pattern_repeat1",
+            Type::StructPatternRepeat1 => "struct_pattern_repeat1",
+            Type::StringLiteralRepeat1 => "string_literal_repeat1",
+            Type::FieldIdentifier => "field_identifier",
+            Type::LetChain => "let_chain",
+            Type::ShorthandFieldIdentifier => "shorthand_field_identifier",
+            Type::TypeIdentifier => "type_identifier",
+            Type::Directory => "Directory",
+            Type::Spaces => "Spaces",
+            Type::_ERROR => "_ERROR",
+            Type::ERROR => "ERROR",
+        }
+    }
+    pub fn is_hidden(&self) -> bool {
+        match self {
+            Type::End => true,
+            Type::TokenRepetitionPatternToken1 => true,
+            Type::LineCommentToken1 => true,
+            Type::LineCommentToken2 => true,
+            Type::LineCommentToken3 => true,
+            Type::_RawStringLiteralStart => true,
+            Type::_RawStringLiteralEnd => true,
+            Type::_BlockCommentContent => true,
+            Type::_ErrorSentinel => true,
+            Type::_Statement => true,
+            Type::_TokenPattern => true,
+            Type::_UseClause => true,
+            Type::_Type => true,
+            Type::_ExpressionExceptRange => true,
+            Type::_Expression => true,
+            Type::_DelimTokens => true,
+            Type::_NonDelimToken => true,
+            Type::_LetChain => true,
+            Type::_Condition => true,
+            Type::_Pattern => true,
+            Type::_Literal => true,
+            Type::_LiteralPattern => true,
+            Type::_LineDocCommentMarker => true,
+            Type::_BlockDocCommentMarker => true,
+            Type::SourceFileRepeat1 => true,
+            Type::MacroDefinitionRepeat1 => true,
+            Type::TokenTreePatternRepeat1 => true,
+            Type::TokenTreeRepeat1 => true,
+            Type::_NonSpecialTokenRepeat1 => true,
+            Type::DeclarationListRepeat1 => true,
+            Type::EnumVariantListRepeat1 => true,
+            Type::EnumVariantListRepeat2 => true,
+            Type::FieldDeclarationListRepeat1 => true,
+            Type::OrderedFieldDeclarationListRepeat1 => true,
+            Type::FunctionModifiersRepeat1 => true,
+            Type::WhereClauseRepeat1 => true,
+            Type::TraitBoundsRepeat1 => true,
+            Type::TypeParametersRepeat1 => true,
+            Type::UseListRepeat1 => true,
+            Type::ParametersRepeat1 => true,
+            Type::ForLifetimesRepeat1 => true,
+            Type::TupleTypeRepeat1 => true,
+            Type::UseBoundsRepeat1 => true,
+            Type::TypeArgumentsRepeat1 => true,
+            Type::DelimTokenTreeRepeat1 => true,
+            Type::ArgumentsRepeat1 => true,
+            Type::TupleExpressionRepeat1 => true,
+            Type::FieldInitializerListRepeat1 => true,
+            Type::MatchBlockRepeat1 => true,
+            Type::MatchArmRepeat1 => true,
+            Type::ClosureParametersRepeat1 => true,
+            Type::TuplePatternRepeat1 => true,
+            Type::SlicePatternRepeat1 => true,
+            Type::StructPatternRepeat1 => true,
+            Type::StringLiteralRepeat1 => true,
+            _ => false,
+        }
+    }
+    pub fn is_supertype(&self) -> bool {
+        match self {
+            Type::_Type => true,
+            Type::_Expression => true,
+            Type::_Pattern => true,
+            Type::_Literal => true,
+            Type::_LiteralPattern => true,
+            _ => false,
+        }
+    }
+    pub fn is_named(&self) -> bool {
+        match self {
+            Type::End => true,
+            Type::Identifier => true,
+            Type::PrimitiveType => true,
+            Type::PrimitiveType_ => true,
+            Type::PrimitiveType__ => true,
+            Type::PrimitiveType___ => true,
+            Type::PrimitiveType____ => true,
+            Type::PrimitiveType_____ => true,
+            Type::PrimitiveType______ => true,
+            Type::PrimitiveType_______ => true,
+            Type::PrimitiveType________ => true,
+            Type::PrimitiveType_________ => true,
+            Type::PrimitiveType__________ => true,
+            Type::PrimitiveType___________ => true,
+            Type::PrimitiveType____________ => true,
+            Type::PrimitiveType_____________ => true,
+            Type::PrimitiveType______________ => true,
+            Type::PrimitiveType_______________ => true,
+            Type::PrimitiveType________________ => true,
+            Type::MutableSpecifier => true,
+            Type::IntegerLiteral => true,
+            Type::CharLiteral => true,
+            Type::EscapeSequence => true,
+            Type::Shebang => true,
+            Type::Self_ => true,
+            Type::Super => true,
+            Type::Crate => true,
+            Type::Metavariable => true,
+            Type::StringContent => true,
+            Type::_RawStringLiteralStart => true,
+            Type::StringContent_ => true,
+            Type::_RawStringLiteralEnd => true,
+            Type::FloatLiteral => true,
+            Type::OuterDocCommentMarker => true,
+            Type::InnerDocCommentMarker => true,
+            Type::_BlockCommentContent => true,
+            Type::DocComment => true,
+            Type::_ErrorSentinel => true,
+            Type::SourceFile => true,
+            Type::_Statement => true,
+            Type::EmptyStatement => true,
+            Type::ExpressionStatement => true,
+            Type::MacroDefinition => true,
+            Type::MacroRule => true,
+            Type::_TokenPattern => true,
+            Type::TokenTreePattern => true,
+            Type::TokenBindingPattern => true,
+            Type::TokenRepetitionPattern => true,
+            Type::FragmentSpecifier => true,
+            Type::TokenTree => true,
+            Type::TokenRepetition => true,
+            Type::AttributeItem => true,
+            Type::InnerAttributeItem => true,
+            Type::Attribute => true,
+            Type::ModItem => true,
+            Type::ForeignModItem => true,
+            Type::DeclarationList => true,
+            Type::StructItem => true,
+            Type::UnionItem => true,
+            Type::EnumItem => true,
+            Type::EnumVariantList => true,
+            Type::EnumVariant => true,
+            Type::FieldDeclarationList => true,
+            Type::FieldDeclaration => true,
+            Type::OrderedFieldDeclarationList => true,
+            Type::ExternCrateDeclaration => true,
+            Type::ConstItem => true,
+            Type::StaticItem => true,
+            Type::TypeItem => true,
+            Type::FunctionItem => true,
+            Type::FunctionSignatureItem => true,
+            Type::FunctionModifiers => true,
+            Type::WhereClause => true,
+            Type::WherePredicate => true,
+            Type::ImplItem => true,
+            Type::TraitItem => true,
+            Type::AssociatedType => true,
+            Type::TraitBounds => true,
+            Type::HigherRankedTraitBound => true,
+            Type::RemovedTraitBound => true,
+            Type::TypeParameters => true,
+            Type::ConstParameter => true,
+            Type::TypeParameter => true,
+            Type::LifetimeParameter => true,
+            Type::LetDeclaration => true,
+            Type::UseDeclaration => true,
+            Type::_UseClause => true,
+            Type::ScopedUseList => true,
+            Type::UseList => true,
+            Type::UseAsClause => true,
+            Type::UseWildcard => true,
+            Type::Parameters => true,
+            Type::SelfParameter => true,
+            Type::VariadicParameter => true,
+            Type::Parameter => true,
+            Type::ExternModifier => true,
+            Type::VisibilityModifier => true,
+            Type::_Type => true,
+            Type::BracketedType => true,
+            Type::QualifiedType => true,
+            Type::Lifetime_ => true,
+            Type::ArrayType => true,
+            Type::ForLifetimes => true,
+            Type::FunctionType => true,
+            Type::TupleType => true,
+            Type::UnitType => true,
+            Type::GenericFunction => true,
+            Type::GenericType => true,
+            Type::GenericTypeWithTurbofish => true,
+            Type::BoundedType => true,
+            Type::UseBounds => true,
+            Type::TypeArguments => true,
+            Type::TypeBinding => true,
+            Type::ReferenceType => true,
+            Type::PointerType => true,
+            Type::NeverType => true,
+            Type::AbstractType => true,
+            Type::DynamicType => true,
+            Type::_ExpressionExceptRange => true,
+            Type::_Expression => true,
+            Type::MacroInvocation => true,
+            Type::TokenTree_ => true,
+            Type::_DelimTokens => true,
+            Type::_NonDelimToken => true,
+            Type::ScopedIdentifier => true,
+            Type::ScopedTypeIdentifier => true,
+            Type::ScopedTypeIdentifier_ => true,
+            Type::RangeExpression => true,
+            Type::UnaryExpression => true,
+            Type::TryExpression => true,
+            Type::ReferenceExpression => true,
+            Type::BinaryExpression => true,
+            Type::AssignmentExpression => true,
+            Type::CompoundAssignmentExpr => true,
+            Type::TypeCastExpression => true,
+            Type::ReturnExpression => true,
+            Type::YieldExpression => true,
+            Type::CallExpression => true,
+            Type::Arguments => true,
+            Type::ArrayExpression => true,
+            Type::ParenthesizedExpression => true,
+            Type::TupleExpression => true,
+            Type::UnitExpression => true,
+            Type::StructExpression => true,
+            Type::FieldInitializerList => true,
+            Type::ShorthandFieldInitializer => true,
+            Type::FieldInitializer => true,
+            Type::BaseFieldInitializer => true,
+            Type::IfExpression => true,
+            Type::LetCondition => true,
+            Type::_LetChain => true,
+            Type::_Condition => true,
+            Type::ElseClause => true,
+            Type::MatchExpression => true,
+            Type::MatchBlock => true,
+            Type::MatchArm => true,
+            Type::MatchArm_ => true,
+            Type::MatchPattern => true,
+            Type::WhileExpression => true,
+            Type::LoopExpression => true,
+            Type::ForExpression => true,
+            Type::ConstBlock => true,
+            Type::ClosureExpression => true,
+            Type::ClosureParameters => true,
+            Type::Label => true,
+            Type::BreakExpression => true,
+            Type::ContinueExpression => true,
+            Type::IndexExpression => true,
+            Type::AwaitExpression => true,
+            Type::FieldExpression => true,
+            Type::UnsafeBlock => true,
+            Type::AsyncBlock => true,
+            Type::GenBlock => true,
+            Type::TryBlock => true,
+            Type::Block_ => true,
+            Type::_Pattern => true,
+            Type::GenericPattern => true,
+            Type::TuplePattern => true,
+            Type::SlicePattern => true,
+            Type::TupleStructPattern => true,
+            Type::StructPattern => true,
+            Type::FieldPattern => true,
+            Type::RemainingFieldPattern => true,
+            Type::MutPattern => true,
+            Type::RangePattern => true,
+            Type::RefPattern => true,
+            Type::CapturedPattern => true,
+            Type::ReferencePattern => true,
+            Type::OrPattern => true,
+            Type::_Literal => true,
+            Type::_LiteralPattern => true,
+            Type::NegativeLiteral => true,
+            Type::StringLiteral => true,
+            Type::RawStringLiteral => true,
+            Type::BooleanLiteral => true,
+            Type::LineComment => true,
+            Type::_LineDocCommentMarker => true,
+            Type::InnerDocCommentMarker_ => true,
+            Type::OuterDocCommentMarker_ => true,
+            Type::BlockComment => true,
+            Type::_BlockDocCommentMarker => true,
+            Type::FieldIdentifier => true,
+            Type::LetChain => true,
+            Type::ShorthandFieldIdentifier => true,
+            Type::TypeIdentifier => true,
+            _ => false,
+        }
+    }
+    pub(crate) fn is_repeat(&self) -> bool {
+        is!(
+            self,
+            SourceFileRepeat1,
+            MacroDefinitionRepeat1,
+            TokenTreePatternRepeat1,
+            TokenTreeRepeat1,
+            _NonSpecialTokenRepeat1,
+            DeclarationListRepeat1,
+            EnumVariantListRepeat1,
+            EnumVariantListRepeat2,
+            FieldDeclarationListRepeat1,
+            OrderedFieldDeclarationListRepeat1,
+            FunctionModifiersRepeat1,
+            WhereClauseRepeat1,
+            TraitBoundsRepeat1,
+            TypeParametersRepeat1,
+            UseListRepeat1,
+            ParametersRepeat1,
+            ForLifetimesRepeat1,
+            TupleTypeRepeat1,
+            UseBoundsRepeat1,
+            TypeArgumentsRepeat1,
+            DelimTokenTreeRepeat1,
+            ArgumentsRepeat1,
+            TupleExpressionRepeat1,
+            FieldInitializerListRepeat1,
+            MatchBlockRepeat1,
+            MatchArmRepeat1,
+            ClosureParametersRepeat1,
+            TuplePatternRepeat1,
+            SlicePatternRepeat1,
+            StructPatternRepeat1,
+            StringLiteralRepeat1,
+        )
+    }
+}
+
+const S_T_L: &[Type] = &[
+    Type::End,
+    Type::Identifier,
+    Type::SemiColon,
+    Type::TS0,
+    Type::LParen,
+    Type::RParen,
+    Type::LBracket,
+    Type::RBracket,
+    Type::LBrace,
+    Type::RBrace,
+    Type::BigArrow,
+    Type::Colon,
+    Type::TS1,
+    Type::TokenRepetitionPatternToken1,
+    Type::Plus,
+    Type::Star,
+    Type::QMark,
+    Type::Block,
+    Type::Expr,
+    Type::TS2,
+    Type::Ident,
+    Type::Item,
+    Type::Lifetime,
+    Type::Literal,
+    Type::Meta,
+    Type::Pat,
+    Type::PatParam,
+    Type::Path,
+    Type::Stmt,
+    Type::Tt,
+    Type::Ty,
+    Type::Vis,
+    Type::PrimitiveType,
+    Type::PrimitiveType_,
+    Type::PrimitiveType__,
+    Type::PrimitiveType___,
+    Type::PrimitiveType____,
+    Type::PrimitiveType_____,
+    Type::PrimitiveType______,
+    Type::PrimitiveType_______,
+    Type::PrimitiveType________,
+    Type::PrimitiveType_________,
+    Type::PrimitiveType__________,
+    Type::PrimitiveType___________,
+    Type::PrimitiveType____________,
+    Type::PrimitiveType_____________,
+    Type::PrimitiveType______________,
+    Type::PrimitiveType_______________,
+    Type::PrimitiveType________________,
+    Type::Dash,
+    Type::Slash,
+    Type::Percent,
+    Type::Caret,
+    Type::Bang,
+    Type::Amp,
+    Type::Pipe,
+    Type::AmpAmp,
+    Type::PipePipe,
+    Type::LtLt,
+    Type::GtGt,
+    Type::PlusEq,
+    Type::DashEq,
+    Type::StarEq,
+    Type::SlashEq,
+    Type::PercentEq,
+    Type::CaretEq,
+    Type::AmpEq,
+    Type::PipeEq,
+    Type::LtLtEq,
+    Type::GtGtEq,
+    Type::Eq,
+    Type::EqEq,
+    Type::BangEq,
+    Type::GT,
+    Type::LT,
+    Type::GTEq,
+    Type::LTEq,
+    Type::At,
+    Type::Inderscore,
+    Type::Dot,
+    Type::TS3,
+    Type::DotDotDot,
+    Type::TS4,
+    Type::Comma,
+    Type::ColonColon,
+    Type::DashGt,
+    Type::Sharp,
+    Type::SQuote,
+    Type::As,
+    Type::Async,
+    Type::Await,
+    Type::Break,
+    Type::Const,
+    Type::Continue,
+    Type::Default,
+    Type::Enum,
+    Type::Fn,
+    Type::For,
+    Type::Gen,
+    Type::If,
+    Type::Impl,
+    Type::Let,
+    Type::Loop,
+    Type::Match,
+    Type::Mod,
+    Type::Pub,
+    Type::Return,
+    Type::Static,
+    Type::Struct,
+    Type::Trait,
+    Type::Type,
+    Type::Union,
+    Type::Unsafe,
+    Type::Use,
+    Type::Where,
+    Type::While,
+    Type::Extern,
+    Type::Ref,
+    Type::Else,
+    Type::In,
+    Type::LT_,
+    Type::Dyn,
+    Type::MutableSpecifier,
+    Type::Raw,
+    Type::Yield,
+    Type::Move,
+    Type::Try,
+    Type::IntegerLiteral,
+    Type::DQuote,
+    Type::CharLiteral,
+    Type::EscapeSequence,
+    Type::True,
+    Type::False,
+    Type::TS5,
+    Type::LineCommentToken1,
+    Type::LineCommentToken2,
+    Type::LineCommentToken3,
+    Type::Bang_,
+    Type::Slash_,
+    Type::TS6,
+    Type::TS7,
+    Type::Shebang,
+    Type::Self_,
+    Type::Super,
+    Type::Crate,
+    Type::Metavariable,
+    Type::StringContent,
+    Type::DQuote_,
+    Type::_RawStringLiteralStart,
+    Type::StringContent_,
+    Type::_RawStringLiteralEnd,
+    Type::FloatLiteral,
+    Type::OuterDocCommentMarker,
+    Type::InnerDocCommentMarker,
+    Type::_BlockCommentContent,
+    Type::DocComment,
+    Type::_ErrorSentinel,
+    Type::SourceFile,
+    Type::_Statement,
+    Type::EmptyStatement,
+    Type::ExpressionStatement,
+    Type::MacroDefinition,
+    Type::MacroRule,
+    Type::_TokenPattern,
+    Type::TokenTreePattern,
+    Type::TokenBindingPattern,
+    Type::TokenRepetitionPattern,
+    Type::FragmentSpecifier,
+    Type::TokenTree,
+    Type::TokenRepetition,
+    Type::AttributeItem,
+    Type::InnerAttributeItem,
+    Type::Attribute,
+    Type::ModItem,
+    Type::ForeignModItem,
+    Type::DeclarationList,
+    Type::StructItem,
+    Type::UnionItem,
+    Type::EnumItem,
+    Type::EnumVariantList,
+    Type::EnumVariant,
+    Type::FieldDeclarationList,
+    Type::FieldDeclaration,
+    Type::OrderedFieldDeclarationList,
+    Type::ExternCrateDeclaration,
+    Type::ConstItem,
+    Type::StaticItem,
+    Type::TypeItem,
+    Type::FunctionItem,
+    Type::FunctionSignatureItem,
+    Type::FunctionModifiers,
+    Type::WhereClause,
+    Type::WherePredicate,
+    Type::ImplItem,
+    Type::TraitItem,
+    Type::AssociatedType,
+    Type::TraitBounds,
+    Type::HigherRankedTraitBound,
+    Type::RemovedTraitBound,
+    Type::TypeParameters,
+    Type::ConstParameter,
+    Type::TypeParameter,
+    Type::LifetimeParameter,
+    Type::LetDeclaration,
+    Type::UseDeclaration,
+    Type::_UseClause,
+    Type::ScopedUseList,
+    Type::UseList,
+    Type::UseAsClause,
+    Type::UseWildcard,
+    Type::Parameters,
+    Type::SelfParameter,
+    Type::VariadicParameter,
+    Type::Parameter,
+    Type::ExternModifier,
+    Type::VisibilityModifier,
+    Type::_Type,
+    Type::BracketedType,
+    Type::QualifiedType,
+    Type::Lifetime_,
+    Type::ArrayType,
+    Type::ForLifetimes,
+    Type::FunctionType,
+    Type::TupleType,
+    Type::UnitType,
+    Type::GenericFunction,
+    Type::GenericType,
+    Type::GenericTypeWithTurbofish,
+    Type::BoundedType,
+    Type::UseBounds,
+    Type::TypeArguments,
+    Type::TypeBinding,
+    Type::ReferenceType,
+    Type::PointerType,
+    Type::NeverType,
+    Type::AbstractType,
+    Type::DynamicType,
+    Type::_ExpressionExceptRange,
+    Type::_Expression,
+    Type::MacroInvocation,
+    Type::TokenTree_,
+    Type::_DelimTokens,
+    Type::_NonDelimToken,
+    Type::ScopedIdentifier,
+    Type::ScopedTypeIdentifier,
+    Type::ScopedTypeIdentifier_,
+    Type::RangeExpression,
+    Type::UnaryExpression,
+    Type::TryExpression,
+    Type::ReferenceExpression,
+    Type::BinaryExpression,
+    Type::AssignmentExpression,
+    Type::CompoundAssignmentExpr,
+    Type::TypeCastExpression,
+    Type::ReturnExpression,
+    Type::YieldExpression,
+    Type::CallExpression,
+    Type::Arguments,
+    Type::ArrayExpression,
+    Type::ParenthesizedExpression,
+    Type::TupleExpression,
+    Type::UnitExpression,
+    Type::StructExpression,
+    Type::FieldInitializerList,
+    Type::ShorthandFieldInitializer,
+    Type::FieldInitializer,
+    Type::BaseFieldInitializer,
+    Type::IfExpression,
+    Type::LetCondition,
+    Type::_LetChain,
+    Type::_Condition,
+    Type::ElseClause,
+    Type::MatchExpression,
+    Type::MatchBlock,
+    Type::MatchArm,
+    Type::MatchArm_,
+    Type::MatchPattern,
+    Type::WhileExpression,
+    Type::LoopExpression,
+    Type::ForExpression,
+    Type::ConstBlock,
+    Type::ClosureExpression,
+    Type::ClosureParameters,
+    Type::Label,
+    Type::BreakExpression,
+    Type::ContinueExpression,
+    Type::IndexExpression,
+    Type::AwaitExpression,
+    Type::FieldExpression,
+    Type::UnsafeBlock,
+    Type::AsyncBlock,
+    Type::GenBlock,
+    Type::TryBlock,
+    Type::Block_,
+    Type::_Pattern,
+    Type::GenericPattern,
+    Type::TuplePattern,
+    Type::SlicePattern,
+    Type::TupleStructPattern,
+    Type::StructPattern,
+    Type::FieldPattern,
+    Type::RemainingFieldPattern,
+    Type::MutPattern,
+    Type::RangePattern,
+    Type::RefPattern,
+    Type::CapturedPattern,
+    Type::ReferencePattern,
+    Type::OrPattern,
+    Type::_Literal,
+    Type::_LiteralPattern,
+    Type::NegativeLiteral,
+    Type::StringLiteral,
+    Type::RawStringLiteral,
+    Type::BooleanLiteral,
+    Type::LineComment,
+    Type::_LineDocCommentMarker,
+    Type::InnerDocCommentMarker_,
+    Type::OuterDocCommentMarker_,
+    Type::BlockComment,
+    Type::_BlockDocCommentMarker,
+    Type::SourceFileRepeat1,
+    Type::MacroDefinitionRepeat1,
+    Type::TokenTreePatternRepeat1,
+    Type::TokenTreeRepeat1,
+    Type::_NonSpecialTokenRepeat1,
+    Type::DeclarationListRepeat1,
+    Type::EnumVariantListRepeat1,
+    Type::EnumVariantListRepeat2,
+    Type::FieldDeclarationListRepeat1,
+    Type::OrderedFieldDeclarationListRepeat1,
+    Type::FunctionModifiersRepeat1,
+    Type::WhereClauseRepeat1,
+    Type::TraitBoundsRepeat1,
+    Type::TypeParametersRepeat1,
+    Type::UseListRepeat1,
+    Type::ParametersRepeat1,
+    Type::ForLifetimesRepeat1,
+    Type::TupleTypeRepeat1,
+    Type::UseBoundsRepeat1,
+    Type::TypeArgumentsRepeat1,
+    Type::DelimTokenTreeRepeat1,
+    Type::ArgumentsRepeat1,
+    Type::TupleExpressionRepeat1,
+    Type::FieldInitializerListRepeat1,
+    Type::MatchBlockRepeat1,
+    Type::MatchArmRepeat1,
+    Type::ClosureParametersRepeat1,
+    Type::TuplePatternRepeat1,
+    Type::SlicePatternRepeat1,
+    Type::StructPatternRepeat1,
+    Type::StringLiteralRepeat1,
+    Type::FieldIdentifier,
+    Type::LetChain,
+    Type::ShorthandFieldIdentifier,
+    Type::TypeIdentifier,
+];
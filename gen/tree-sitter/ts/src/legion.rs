@@ -6,7 +6,7 @@ use hyperast::store::nodes::legion::subtree_builder;
 use hyperast::tree_gen::{PreResult, ZippedTreeGen};
 use legion::world::EntryRef;
 
-use hyperast::store::nodes::compo::{self, CS, NoSpacesCS};
+use hyperast::store::nodes::compo;
 use hyperast::{
     filter::BloomSize,
     full::FullNode,
@@ -17,7 +17,7 @@ use hyperast::{
         nodes::{DefaultNodeStore as NodeStore, legion::NodeIdentifier},
     },
     tree_gen::{
-        AccIndentation, Accumulator, BasicAccumulator, BasicGlobalData, GlobalData, Parents,
+        self, AccIndentation, Accumulator, BasicAccumulator, BasicGlobalData, GlobalData, Parents,
         SpacedGlobalData, Spaces, SubTreeMetrics, TextedGlobalData, TreeGen, WithByteRange,
         compute_indentation, get_spacing, has_final_space,
         parser::{Node as _, TreeCursor},
@@ -42,7 +42,7 @@ pub type MDCache = HashMap<NodeIdentifier, MD>;
 // * metadata: computation results from concrete code of node and its children
 // they can be qualitative metadata .eg a hash or they can be quantitative .eg lines of code
 pub struct MD {
-    metrics: SubTreeMetrics<SyntaxNodeHashs<u32>>,
+    pub metrics: SubTreeMetrics<SyntaxNodeHashs<u32>>,
 }
 
 impl From<Local> for MD {
@@ -422,17 +422,8 @@ where
         let l = x.get_component::<LabelIdentifier>().ok();
         if l != label_id {
             return false;
-        } else {
-            let cs = x.get_component::<CS<legion::Entity>>();
-            let r = match cs {
-                Ok(CS(cs)) => cs.as_ref() == children,
-                Err(_) => children.is_empty(),
-            };
-            if !r {
-                return false;
-            }
         }
-        true
+        hyperast::store::nodes::legion::eq_node_cs(children)(x)
     }
 }
 
@@ -464,58 +455,45 @@ impl<'stores, 'cache, TS: TsEnabledTypeStore> TreeGen for TsTreeGen<'stores, 'ca
 
         let insertion = node_store.prepare_insertion(&hashable, eq);
 
+        let hashs = hbuilder.build();
+        let metrics = SubTreeMetrics {
+            size,
+            height,
+            hashs,
+            size_no_spaces,
+            line_count,
+        };
+
         let local = if let Some(compressed_node) = insertion.occupied_id() {
-            let md = self.md_cache.get(&compressed_node).unwrap();
-            let hashs = hbuilder.build();
-            let metrics = SubTreeMetrics {
-                size,
-                height,
-                hashs,
-                size_no_spaces,
-                line_count,
-            };
-            assert_eq!(md.metrics, metrics);
+            if let Some(md) = self.md_cache.get(&compressed_node) {
+                debug_assert_eq!(md.metrics, metrics);
+            }
             Local {
                 compressed_node,
                 metrics,
             }
         } else {
-            let hashs = hbuilder.build();
             use hyperast::store::nodes::EntityBuilder as _;
 
             let mut dyn_builder = subtree_builder::<TS>(interned_kind);
-            dyn_builder.add(interned_kind);
             dyn_builder.add(hashs);
             dyn_builder.add(compo::BytesLen(
                 (acc.end_byte - acc.start_byte).try_into().unwrap(),
             ));
-            if let Some(label_id) = label_id {
-                dyn_builder.add(label_id);
-            }
-            match acc.simple.children.len() {
-                0 => {}
-                x => {
-                    let a = acc.simple.children.into_boxed_slice();
-                    dyn_builder.add(compo::Size(size));
-                    dyn_builder.add(compo::SizeNoSpaces(size_no_spaces));
-                    dyn_builder.add(compo::Height(height));
-                    dyn_builder.add(CS(a));
-                    if x != acc.no_space.len() {
-                        dyn_builder.add(NoSpacesCS(acc.no_space.into_boxed_slice()));
-                    }
-                    panic!("use CS0<_,1>, CS0<_,2>, and corresponding eq_node");
+            if !acc.simple.children.is_empty() {
+                dyn_builder.add(compo::Size(size));
+                dyn_builder.add(compo::SizeNoSpaces(size_no_spaces));
+                dyn_builder.add(compo::Height(height));
+                if acc.simple.children.len() != acc.no_space.len() {
+                    tree_gen::add_cs_no_spaces(&mut dyn_builder, acc.no_space);
                 }
             }
+            acc.simple
+                .add_primary(&mut dyn_builder, interned_kind, label_id);
             let compressed_node =
                 NodeStore::insert_built_after_prepare(insertion.vacant(), dyn_builder.build());
 
-            let metrics = SubTreeMetrics {
-                size,
-                height,
-                hashs,
-                size_no_spaces,
-                line_count,
-            };
+            self.md_cache.insert(compressed_node, MD { metrics });
             Local {
                 compressed_node,
                 metrics,
@@ -529,3 +507,12 @@ impl<'stores, 'cache, TS: TsEnabledTypeStore> TreeGen for TsTreeGen<'stores, 'ca
         full_node
     }
 }
+
+/// Registers the components specific to this language, to snapshot stores containing its subtrees.
+pub fn register_persisted_components(
+    registry: &mut hyperast::store::nodes::legion::persist::ComponentRegistry,
+) {
+    registry
+        .register_lang::<crate::types::Lang>()
+        .register_type::<crate::types::Lang>();
+}
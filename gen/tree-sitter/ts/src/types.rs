@@ -55,6 +55,35 @@ mod legion_impls {
             t.e()
         }
     }
+
+    #[cfg(feature = "impl")]
+    impl hyperast::types::RoleStore for TStore {
+        type IdF = u16;
+
+        type Role = hyperast::types::Role;
+
+        fn resolve_field(
+            _lang: hyperast::types::LangWrapper<Self::Ty>,
+            field_id: Self::IdF,
+        ) -> Self::Role {
+            let s = crate::language()
+                .field_name_for_id(field_id)
+                .ok_or_else(|| format!("{}", field_id))
+                .unwrap();
+            hyperast::types::Role::try_from(s).expect(s)
+        }
+
+        fn intern_role(
+            _lang: hyperast::types::LangWrapper<Self::Ty>,
+            role: Self::Role,
+        ) -> Self::IdF {
+            let field_name = role.to_string();
+            crate::language()
+                .field_id_for_name(field_name)
+                .unwrap()
+                .into()
+        }
+    }
 }
 
 pub trait TsEnabledTypeStore: hyperast::types::ETypeStore<Ty2 = Type> + Clone + TsEnableTS {
@@ -105,7 +134,7 @@ impl<IdN: Clone + Eq + AAAA> TypedNodeId for TIdN<IdN> {
 }
 
 #[derive(Clone, Copy)]
-pub(crate) struct TStore;
+pub struct TStore;
 
 impl Default for TStore {
     fn default() -> Self {
@@ -392,7 +421,7 @@ impl Display for Type {
     }
 }
 
-type TType = hyperast::types::TypeU16<Lang>;
+pub type TType = hyperast::types::TypeU16<Lang>;
 
 impl hyperast::types::LLang<TType> for Ts {
     type I = u16;
@@ -646,3 +646,12 @@ fn compress<Ty: std::marker::Send + std::marker::Sync + 'static>(
 //         parent_indent,
 //     )
 // }
+
+/// Registers the components specific to this language, to snapshot stores containing its subtrees.
+pub fn register_persisted_components(
+    registry: &mut hyperast::store::nodes::legion::persist::ComponentRegistry,
+) {
+    registry
+        .register_lang::<crate::types::Lang>()
+        .register_type::<crate::types::Lang>();
+}
@@ -448,6 +448,7 @@ pub(super) fn show_querying(
     if is_portrait {
         egui::ScrollArea::vertical().show(ui, |ui| {
             show_scripts_edition(ui, api_endpoint, query_editors, query);
+            show_precomputed_hints(ui, querying_result);
             handle_interactions(ui, query_editors, querying_result, query, trigger_compute);
             show_long_result(&*querying_result, ui);
         });
@@ -457,6 +458,7 @@ pub(super) fn show_querying(
             .show(ui, |ui1, ui2| {
                 ui1.push_id(ui1.id().with("input"), |ui| {
                     show_scripts_edition(ui, api_endpoint, query_editors, query);
+                    show_precomputed_hints(ui, querying_result);
                 });
                 let ui = ui2;
                 handle_interactions(ui, query_editors, querying_result, query, trigger_compute);
@@ -465,6 +467,28 @@ pub(super) fn show_querying(
     }
 }
 
+/// Suggests patterns to precompute, to speed up the search of the query being edited
+fn show_precomputed_hints(
+    ui: &mut egui::Ui,
+    querying_result: &Option<ComputeResultsProm<QueryingError>>,
+) {
+    let Some(Ok(resource)) = querying_result.as_ref().and_then(|x| x.ready()) else {
+        return;
+    };
+    let Some(Ok(content)) = &resource.content else {
+        return;
+    };
+    for hint in &content.hints {
+        ui.label(
+            egui::RichText::new(format!(
+                "pattern {} would be searched faster by precomputing {}",
+                hint.pattern, hint.precomputed
+            ))
+            .color(ui.visuals().warn_fg_color),
+        );
+    }
+}
+
 pub(crate) fn end_point(api_addr: &str) -> String {
     format!("{}/sharing-queries", api_addr)
 }
@@ -125,11 +125,21 @@ pub fn prep_compute_res_prom_mut<'a, T: ComputeError + Send + Sync>(
 pub struct ComputeResults {
     pub prepare_time: f64,
     pub results: Vec<Result<ComputeResultIdentified, String>>,
+    /// patterns that would be searched faster with a precomputed pattern
+    #[serde(default)]
+    pub hints: Vec<PrecomputedHint>,
     #[cfg(feature = "force_layout")]
     #[serde(skip)]
     pub graph: Option<Box<dyn std::any::Any + Send + Sync>>,
 }
 
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct PrecomputedHint {
+    /// index among the enabled patterns
+    pub pattern: u16,
+    pub precomputed: String,
+}
+
 pub type ComputeResultsProm<Err> = Promise<Result<Resource<Result<ComputeResults, Err>>, String>>;
 
 impl std::hash::Hash for ComputeResults {
@@ -0,0 +1,50 @@
+use hyperast::store::defaults::{LabelIdentifier, NodeIdentifier};
+
+use crate::{Accumulator, BasicDirAcc, DefaultMetrics};
+
+#[derive(Debug, Clone)]
+pub struct MD {
+    pub(crate) metrics: DefaultMetrics,
+}
+
+/// Accumulates the content of a directory whose files can be in any supported language.
+pub struct AnyAcc {
+    pub(crate) primary: BasicDirAcc<NodeIdentifier, LabelIdentifier, DefaultMetrics>,
+}
+
+impl AnyAcc {
+    pub(crate) fn new(name: String) -> Self {
+        Self {
+            primary: BasicDirAcc::new(name),
+        }
+    }
+}
+
+impl From<String> for AnyAcc {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl AnyAcc {
+    /// Pushes a file, whatever its language.
+    pub(crate) fn push_file(
+        &mut self,
+        name: LabelIdentifier,
+        compressed_node: NodeIdentifier,
+        metrics: DefaultMetrics,
+    ) {
+        self.primary.push(name, compressed_node, metrics);
+    }
+}
+
+impl hyperast::tree_gen::Accumulator for AnyAcc {
+    type Node = (LabelIdentifier, (NodeIdentifier, MD));
+    fn push(&mut self, (name, full_node): Self::Node) {
+        self.primary.push(name, full_node.0, full_node.1.metrics);
+    }
+}
+
+impl Accumulator for AnyAcc {
+    type Unlabeled = (NodeIdentifier, MD);
+}
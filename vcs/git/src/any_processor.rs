@@ -0,0 +1,448 @@
+use crate::StackEle;
+use crate::processing::erased::{
+    CommitProcessorHandle, ParametrizedCommitProcessor2Handle as PCP2Handle,
+};
+use crate::{
+    Processor,
+    any::{AnyAcc, MD},
+    git::BasicGitObject,
+    preprocessed::RepositoryProcessor,
+    processing::{
+        CacheHolding, InFiles, ObjectName, ParametrizedCommitProcessorHandle,
+        erased::ParametrizedCommitProc2,
+    },
+};
+use git2::{Oid, Repository};
+use hyperast::types::ETypeStore as _;
+use hyperast::{
+    hashed::{IndexingHashBuilder, MetaDataHashsBuilder},
+    store::{defaults::NodeIdentifier, nodes::legion::eq_node},
+    types::LabelStore,
+};
+use hyperast_gen_ts_xml::types::Type;
+
+pub type SimpleStores = hyperast::store::SimpleStores<hyperast_gen_ts_xml::types::TStore>;
+
+/// Explores every directory, each blob being handled by the generator matching its file extension,
+/// blobs that do not match any generator are ignored.
+pub struct AnyProcessor<'a, 'b, Acc> {
+    prepro: &'b mut RepositoryProcessor,
+    repository: &'a Repository,
+    stack: Vec<StackEle<Acc>>,
+    handle: ParametrizedCommitProcessorHandle,
+}
+
+impl<'a, 'b, Acc: From<String>> AnyProcessor<'a, 'b, Acc> {
+    pub fn new(
+        repository: &'a Repository,
+        prepro: &'b mut RepositoryProcessor,
+        name: &[u8],
+        oid: git2::Oid,
+        handle: ParametrizedCommitProcessorHandle,
+    ) -> Self {
+        let tree = repository.find_tree(oid).unwrap();
+        let prepared = prepare_dir_exploration(tree);
+        let name = std::str::from_utf8(&name).unwrap().to_string();
+        let stack = vec![StackEle::new(oid, prepared, Acc::from(name))];
+        Self {
+            stack,
+            repository,
+            prepro,
+            handle,
+        }
+    }
+}
+
+impl<'a, 'b> Processor<AnyAcc> for AnyProcessor<'a, 'b, AnyAcc> {
+    fn pre(&mut self, current_dir: BasicGitObject) {
+        match current_dir {
+            BasicGitObject::Tree(oid, name) => {
+                if crate::ts_processor::is_ignored_dir(&name) {
+                    return;
+                }
+                self.handle_tree_cached(name, oid);
+            }
+            BasicGitObject::Blob(oid, name) => {
+                if let Err(err) = self.handle_blob(oid, &name) {
+                    log::warn!("failed to handle {:?}: {:?}", name.try_str(), err);
+                }
+            }
+        }
+    }
+    fn post(&mut self, oid: Oid, acc: AnyAcc) -> Option<(NodeIdentifier, MD)> {
+        let name = acc.primary.name.clone();
+        let key = (oid, name.as_bytes().into());
+        let full_node = make(acc, self.prepro.main_stores_mut().mut_with_ts());
+        self.prepro
+            .processing_systems
+            .mut_or_default::<AnyProcessorHolder>()
+            .with_parameters_mut(self.handle.1)
+            .cache
+            .object_map
+            .insert(key, full_node.clone());
+
+        let name = self.prepro.main_stores.label_store.get_or_insert(name);
+        if self.stack.is_empty() {
+            Some(full_node)
+        } else {
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            assert!(
+                !w.primary.children_names.contains(&name),
+                "{:?} {:?}",
+                w.primary.children_names,
+                name
+            );
+            hyperast::tree_gen::Accumulator::push(w, (name, full_node));
+            None
+        }
+    }
+
+    fn stack(&mut self) -> &mut Vec<StackEle<AnyAcc>> {
+        &mut self.stack
+    }
+}
+
+impl<'a, 'b> AnyProcessor<'a, 'b, AnyAcc> {
+    fn handle_tree_cached(&mut self, name: ObjectName, oid: Oid) {
+        let any_proc = self
+            .prepro
+            .processing_systems
+            .mut_or_default::<AnyProcessorHolder>()
+            .with_parameters_mut(self.handle.1);
+        if let Some(already) = any_proc.cache.object_map.get(&(oid, name.clone())) {
+            // reinit already computed node for post order
+            let full_node = already.clone();
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            let name = self.prepro.intern_object_name(name);
+            assert!(!w.primary.children_names.contains(&name));
+            hyperast::tree_gen::Accumulator::push(w, (name, full_node));
+            return;
+        }
+        log::debug!("any tree {:?}", name.try_str());
+        let tree = self.repository.find_tree(oid).unwrap();
+        let prepared = prepare_dir_exploration(tree);
+        self.stack.push(StackEle::new(
+            oid,
+            prepared,
+            AnyAcc::new(name.try_into().unwrap()),
+        ));
+    }
+
+    /// Dispatches the blob to the generator matching its extension.
+    fn handle_blob(&mut self, oid: Oid, name: &ObjectName) -> Result<(), crate::ParseErr> {
+        use crate::processing::file_sys;
+        let parameter = self
+            .prepro
+            .processing_systems
+            .mut_or_default::<AnyProcessorHolder>()
+            .with_parameters(self.handle.1)
+            .parameter
+            .clone();
+        let (id, metrics) = if file_sys::Java::matches(name) {
+            let (n,) =
+                self.prepro
+                    .handle_java_blob(oid, name, self.repository, parameter.java_handle)?;
+            (n.compressed_node, n.metrics)
+        } else if file_sys::Cpp::matches(name) {
+            let (n,) =
+                self.prepro
+                    .handle_cpp_blob(oid, name, self.repository, parameter.cpp_handle)?;
+            (n.compressed_node, n.metrics)
+        } else if file_sys::Ts::matches(name) {
+            let (n,) =
+                self.prepro
+                    .handle_ts_blob(oid, name, self.repository, parameter.ts_handle)?;
+            (n.compressed_node, n.metrics)
+        } else if file_sys::Python::matches(name) {
+            let (n,) = self.prepro.handle_python_blob(
+                oid,
+                name,
+                self.repository,
+                parameter.python_handle,
+            )?;
+            (n.compressed_node, n.metrics)
+        } else if file_sys::Rust::matches(name) {
+            let (n,) =
+                self.prepro
+                    .handle_rust_blob(oid, name, self.repository, parameter.rust_handle)?;
+            (n.compressed_node, n.metrics)
+        } else {
+            log::debug!("no generator for {:?}", name.try_str());
+            return Ok(());
+        };
+        let name = self.prepro.intern_object_name(name);
+        let w = &mut self.stack.last_mut().unwrap().acc;
+        assert!(!w.primary.children_names.contains(&name));
+        w.push_file(name, id, metrics);
+        Ok(())
+    }
+}
+
+pub(crate) fn make(acc: AnyAcc, stores: &mut SimpleStores) -> (NodeIdentifier, MD) {
+    let kind = Type::Directory;
+    let interned_kind = hyperast_gen_ts_xml::types::TStore::intern(kind);
+    let label_id = stores.label_store.get_or_insert(acc.primary.name.clone());
+
+    let primary = acc
+        .primary
+        .map_metrics(|m| m.finalize(&interned_kind, &label_id, 0));
+
+    let hashable = primary.metrics.hashs.most_discriminating();
+
+    let eq = eq_node(&interned_kind, Some(&label_id), &primary.children);
+
+    let insertion = stores.node_store.prepare_insertion(&hashable, eq);
+    if let Some(id) = insertion.occupied_id() {
+        let metrics = primary
+            .metrics
+            .map_hashs(|h| MetaDataHashsBuilder::build(h));
+        return (id, MD { metrics });
+    }
+
+    let mut dyn_builder = hyperast::store::nodes::legion::dyn_builder::EntityBuilder::with_lang(
+        hyperast_gen_ts_xml::types::Lang,
+    );
+
+    let children_is_empty = primary.children.is_empty();
+
+    let metrics = primary.persist(&mut dyn_builder, interned_kind, label_id);
+    let metrics = metrics.map_hashs(|h| h.build());
+    let hashs = metrics.add_md_metrics(&mut dyn_builder, children_is_empty);
+    hashs.persist(&mut dyn_builder);
+
+    let vacant = insertion.vacant();
+    let node_id = hyperast::store::nodes::legion::NodeStore::insert_built_after_prepare(
+        vacant,
+        dyn_builder.build(),
+    );
+
+    (node_id, MD { metrics })
+}
+
+pub(crate) fn prepare_dir_exploration(tree: git2::Tree) -> Vec<BasicGitObject> {
+    tree.iter()
+        .rev()
+        .map(TryInto::try_into)
+        .filter_map(|x| x.ok())
+        .collect()
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub(crate) java_handle: PCP2Handle<crate::java_processor::JavaProc>,
+    pub(crate) cpp_handle: PCP2Handle<crate::cpp_processor::CppProc>,
+    pub(crate) ts_handle: PCP2Handle<crate::ts_processor::TsProc>,
+    pub(crate) python_handle: PCP2Handle<crate::python_processor::PythonProc>,
+    pub(crate) rust_handle: PCP2Handle<crate::rust_processor::RustProc>,
+}
+
+#[derive(Default)]
+pub(crate) struct AnyProcessorHolder(Vec<AnyProc>);
+pub(crate) struct AnyProc {
+    parameter: Parameter,
+    cache: crate::processing::caches::Any,
+    commits: std::collections::HashMap<git2::Oid, crate::Commit>,
+}
+impl crate::processing::erased::Parametrized for AnyProcessorHolder {
+    type T = Parameter;
+    fn register_param(
+        &mut self,
+        t: Self::T,
+    ) -> crate::processing::erased::ParametrizedCommitProcessorHandle {
+        let l = self
+            .0
+            .iter()
+            .position(|x| &x.parameter == &t)
+            .unwrap_or_else(|| {
+                let l = self.0.len();
+                self.0.push(AnyProc {
+                    parameter: t,
+                    cache: Default::default(),
+                    commits: Default::default(),
+                });
+                l
+            });
+        use crate::processing::erased::ConfigParametersHandle;
+        use crate::processing::erased::ParametrizedCommitProc;
+        ParametrizedCommitProcessorHandle(self.erased_handle(), ConfigParametersHandle(l))
+    }
+}
+
+struct PreparedAnyCommitProc<'repo> {
+    repository: &'repo git2::Repository,
+    commit_builder: crate::preprocessed::CommitBuilder,
+    pub(crate) handle: ParametrizedCommitProcessorHandle,
+}
+impl<'repo> crate::processing::erased::PreparedCommitProc for PreparedAnyCommitProc<'repo> {
+    fn process(
+        self: Box<PreparedAnyCommitProc<'repo>>,
+        prepro: &mut RepositoryProcessor,
+    ) -> hyperast::store::defaults::NodeIdentifier {
+        let name = b"";
+        let root_full_node = AnyProcessor::<AnyAcc>::new(
+            self.repository,
+            prepro,
+            name,
+            self.commit_builder.tree_oid(),
+            self.handle,
+        )
+        .process();
+        let h = prepro
+            .processing_systems
+            .mut_or_default::<AnyProcessorHolder>();
+        let handle = self.handle;
+        let commit_oid = self.commit_builder.commit_oid();
+        let commit = self.commit_builder.finish(root_full_node.0);
+        h.with_parameters_mut(handle.1)
+            .commits
+            .insert(commit_oid, commit);
+        root_full_node.0
+    }
+}
+
+impl crate::processing::erased::CommitProc for AnyProc {
+    fn prepare_processing<'repo>(
+        &self,
+        repository: &'repo git2::Repository,
+        commit_builder: crate::preprocessed::CommitBuilder,
+        handle: crate::processing::ParametrizedCommitProcessorHandle,
+    ) -> Box<dyn crate::processing::erased::PreparedCommitProc + 'repo> {
+        Box::new(PreparedAnyCommitProc {
+            repository,
+            commit_builder,
+            handle,
+        })
+    }
+
+    fn get_commit(&self, commit_oid: git2::Oid) -> Option<&crate::Commit> {
+        self.commits.get(&commit_oid)
+    }
+
+    fn commit_count(&self) -> usize {
+        self.commits.len()
+    }
+
+    fn get_lang_handle(&self, lang: &str) -> Option<ParametrizedCommitProcessorHandle> {
+        if lang.eq_ignore_ascii_case("java") {
+            Some(ParametrizedCommitProcessorHandle(
+                CommitProcessorHandle(std::any::TypeId::of::<
+                    crate::java_processor::JavaProcessorHolder,
+                >()),
+                self.parameter.java_handle.0,
+            ))
+        } else if lang.eq_ignore_ascii_case("cpp") {
+            Some(ParametrizedCommitProcessorHandle(
+                CommitProcessorHandle(std::any::TypeId::of::<
+                    crate::cpp_processor::CppProcessorHolder,
+                >()),
+                self.parameter.cpp_handle.0,
+            ))
+        } else if ["ts", "typescript", "js", "javascript"]
+            .iter()
+            .any(|x| lang.eq_ignore_ascii_case(x))
+        {
+            Some(ParametrizedCommitProcessorHandle(
+                CommitProcessorHandle(
+                    std::any::TypeId::of::<crate::ts_processor::TsProcessorHolder>(),
+                ),
+                self.parameter.ts_handle.0,
+            ))
+        } else if ["python", "py"]
+            .iter()
+            .any(|x| lang.eq_ignore_ascii_case(x))
+        {
+            Some(ParametrizedCommitProcessorHandle(
+                CommitProcessorHandle(std::any::TypeId::of::<
+                    crate::python_processor::PythonProcessorHolder,
+                >()),
+                self.parameter.python_handle.0,
+            ))
+        } else if ["rust", "rs"].iter().any(|x| lang.eq_ignore_ascii_case(x)) {
+            Some(ParametrizedCommitProcessorHandle(
+                CommitProcessorHandle(std::any::TypeId::of::<
+                    crate::rust_processor::RustProcessorHolder,
+                >()),
+                self.parameter.rust_handle.0,
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl crate::processing::erased::CommitProcExt for AnyProc {
+    type Holder = AnyProcessorHolder;
+}
+
+impl crate::processing::erased::ParametrizedCommitProc2 for AnyProcessorHolder {
+    type Proc = AnyProc;
+
+    fn with_parameters_mut(
+        &mut self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &mut Self::Proc {
+        &mut self.0[parameters.0]
+    }
+
+    fn with_parameters(
+        &self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &Self::Proc {
+        &self.0[parameters.0]
+    }
+
+    fn persist(
+        &self,
+        _stores: &crate::SimpleStores,
+        mut w: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::write_u32;
+        write_u32(&mut w, self.0.len() as u32)?;
+        for proc in &self.0 {
+            crate::persist::write_commits(&mut w, &proc.commits)?;
+        }
+        Ok(())
+    }
+
+    fn restore(
+        &mut self,
+        _stores: &crate::SimpleStores,
+        mut r: &mut dyn std::io::Read,
+    ) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::read_u32_from;
+        let len = read_u32_from(&mut r)? as usize;
+        if len > self.0.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "more persisted processors than registered ones",
+            ));
+        }
+        for proc in &mut self.0[..len] {
+            proc.commits = crate::persist::read_commits(&mut r)?;
+        }
+        Ok(())
+    }
+
+    fn commit_roots(&self) -> Vec<(git2::Oid, hyperast::store::defaults::NodeIdentifier)> {
+        self.0
+            .iter()
+            .flat_map(|proc| proc.commits.iter().map(|(oid, c)| (*oid, c.ast_root)))
+            .collect()
+    }
+
+    fn retain_reachable(&mut self, reachable: &hyperast::store::nodes::legion::gc::Reachable) {
+        for proc in &mut self.0 {
+            proc.commits.retain(|_, c| reachable.contains(&c.ast_root));
+            proc.cache.retain_reachable(reachable);
+        }
+    }
+}
+
+impl CacheHolding<crate::processing::caches::Any> for AnyProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::Any {
+        &mut self.cache
+    }
+    fn get_caches(&self) -> &crate::processing::caches::Any {
+        &self.cache
+    }
+}
@@ -0,0 +1,194 @@
+use std::path::PathBuf;
+
+use enumset::EnumSet;
+use hyperast::store::defaults::{LabelIdentifier, NodeIdentifier};
+use hyperast_gen_ts_rust::legion as rust_tree_gen;
+
+use crate::{Accumulator, BasicDirAcc, DefaultMetrics, processing::ObjectName};
+
+/// Extracts the information needed to explore a crate or a workspace from its Cargo.toml.
+///
+/// There is no toml generator, so the file itself is not part of the HyperAST.
+pub(crate) fn handle_cargo_toml_file(name: &ObjectName, text: &[u8]) -> Result<CargoToml, ()> {
+    let text = std::str::from_utf8(text).map_err(|_| ())?;
+    let manifest: Manifest = match toml::from_str(text) {
+        Ok(x) => x,
+        Err(err) => {
+            log::warn!("bad Cargo.toml {:?}: {}", name.try_str(), err);
+            return Err(());
+        }
+    };
+    let (is_workspace, members, exclude) = match manifest.workspace {
+        Some(ws) => (true, ws.members, ws.exclude),
+        None => (false, vec![], vec![]),
+    };
+    Ok(CargoToml {
+        name: manifest.package.and_then(|p| p.name),
+        is_workspace,
+        members,
+        exclude,
+    })
+}
+
+/// The subset of a Cargo.toml that matters to explore a crate
+#[derive(serde::Deserialize, Default)]
+struct Manifest {
+    #[serde(default)]
+    package: Option<Package>,
+    #[serde(default)]
+    workspace: Option<Workspace>,
+}
+
+#[derive(serde::Deserialize, Default)]
+struct Package {
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(serde::Deserialize, Default)]
+struct Workspace {
+    #[serde(default)]
+    members: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CargoToml {
+    /// only set when the manifest declares a package
+    pub name: Option<String>,
+    pub is_workspace: bool,
+    /// globs locating the crates of the workspace, e.g. `crates/*`
+    pub members: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+/// Directories of a crate that are compressed, the others are skipped.
+pub(crate) fn is_source_dir(name: &ObjectName) -> bool {
+    matches!(name.as_bytes(), b"src" | b"tests")
+}
+
+#[derive(Debug, Clone)]
+pub struct MD {
+    pub(crate) metrics: DefaultMetrics,
+    pub(crate) status: EnumSet<SemFlag>,
+}
+
+#[derive(enumset::EnumSetType, Debug)]
+pub enum SemFlag {
+    /// its manifest declares a package
+    IsCargoCrate,
+    /// its manifest declares a workspace
+    IsCargoWorkspace,
+    HoldCargoCrate,
+}
+
+pub struct CargoModuleAcc {
+    pub(crate) primary: BasicDirAcc<NodeIdentifier, LabelIdentifier, DefaultMetrics>,
+    /// remaining path of member globs, relative to this directory
+    pub(crate) members: Option<Vec<PathBuf>>,
+    /// remaining path of excluded directories, relative to this directory
+    pub(crate) exclude: Vec<PathBuf>,
+    /// the member globs and exclusions given by the parent workspace, part of the key of the cached crate
+    pub(crate) inherited: (Vec<PathBuf>, Vec<PathBuf>),
+    pub(crate) status: EnumSet<SemFlag>,
+}
+
+impl From<String> for CargoModuleAcc {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl CargoModuleAcc {
+    pub(crate) fn new(name: String) -> Self {
+        Self {
+            primary: BasicDirAcc::new(name),
+            members: None,
+            exclude: vec![],
+            inherited: Default::default(),
+            status: EnumSet::new(),
+        }
+    }
+    pub(crate) fn with_members(name: String, members: Vec<PathBuf>, exclude: Vec<PathBuf>) -> Self {
+        Self {
+            primary: BasicDirAcc::new(name),
+            members: if members.is_empty() {
+                None
+            } else {
+                Some(members.clone())
+            },
+            exclude: exclude.clone(),
+            inherited: (members, exclude),
+            status: EnumSet::new(),
+        }
+    }
+
+    pub(crate) fn is_crate(&self) -> bool {
+        self.status.contains(SemFlag::IsCargoCrate)
+    }
+
+    /// Returns the remaining member globs and exclusions
+    /// if the directory `name` could contain a crate of the workspace.
+    pub(crate) fn members_in(&self, name: &[u8]) -> Option<(Vec<PathBuf>, Vec<PathBuf>)> {
+        let name = std::str::from_utf8(name).ok()?;
+        let members = self.members.as_ref()?;
+        let mut exclude = vec![];
+        for ex in &self.exclude {
+            if let Ok(rest) = ex.strip_prefix(name) {
+                if rest.components().next().is_none() {
+                    return None;
+                }
+                exclude.push(rest.to_owned());
+            }
+        }
+        let rest = crate::utils::globs_in(members, name)?;
+        Some((rest, exclude))
+    }
+}
+
+impl CargoModuleAcc {
+    /// Manifests are consumed but not kept as children, see [`handle_cargo_toml_file`].
+    pub(crate) fn push_manifest(&mut self, manifest: CargoToml) {
+        if manifest.name.is_some() {
+            self.status |= SemFlag::IsCargoCrate;
+        }
+        if manifest.is_workspace {
+            self.status |= SemFlag::IsCargoWorkspace;
+            self.members
+                .get_or_insert_with(Default::default)
+                .extend(manifest.members.iter().map(PathBuf::from));
+            self.exclude
+                .extend(manifest.exclude.iter().map(PathBuf::from));
+        }
+    }
+    pub fn push_submodule(&mut self, name: LabelIdentifier, full_node: (NodeIdentifier, MD)) {
+        if full_node.1.status.contains(SemFlag::IsCargoCrate)
+            || full_node.1.status.contains(SemFlag::HoldCargoCrate)
+        {
+            self.status |= SemFlag::HoldCargoCrate;
+        }
+        self.primary.children.push(full_node.0);
+        self.primary.children_names.push(name);
+        self.primary.metrics.acc(full_node.1.metrics);
+    }
+    pub(crate) fn push_source_directory(
+        &mut self,
+        name: LabelIdentifier,
+        full_node: rust_tree_gen::Local,
+    ) {
+        self.primary
+            .push(name, full_node.compressed_node, full_node.metrics);
+    }
+}
+
+impl hyperast::tree_gen::Accumulator for CargoModuleAcc {
+    type Node = (LabelIdentifier, (NodeIdentifier, MD));
+    fn push(&mut self, (name, full_node): Self::Node) {
+        self.push_submodule(name, full_node);
+    }
+}
+
+impl Accumulator for CargoModuleAcc {
+    type Unlabeled = (NodeIdentifier, MD);
+}
@@ -0,0 +1,677 @@
+use crate::{Accumulator, BasicDirAcc, DefaultMetrics, processing::ObjectName};
+use enumset::EnumSet;
+use hyperast::{
+    store::defaults::{LabelIdentifier, NodeIdentifier},
+    tree_gen::SubTreeMetrics,
+};
+use hyperast_gen_ts_cpp::legion as cpp_tree_gen;
+use std::{collections::HashMap, path::PathBuf};
+
+/// Extracts the subdirectories and the targets declared in a `CMakeLists.txt`.
+///
+/// There is no cmake generator, so lists files are not part of the resulting HyperAST,
+/// they are only scanned for the few commands that matter to group sources by target.
+pub(crate) fn handle_cmake_file(name: &ObjectName, text: &[u8]) -> Result<CMakeLists, ()> {
+    let text = std::str::from_utf8(text).map_err(|_| ())?;
+    log::debug!("cmake lists {:?}", name.try_str());
+    Ok(CMakeLists::extract(text))
+}
+
+/// What a `CMakeLists.txt` declares, paths being relative to the directory containing it.
+///
+/// Conditions are not evaluated, every branch is considered,
+/// and paths that still depend on unknown variables or go up the hierarchy are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CMakeLists {
+    /// directories given to `add_subdirectory`
+    pub subdirectories: Vec<String>,
+    /// in declaration order
+    pub targets: Vec<Target>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub name: String,
+    pub kind: TargetKind,
+    /// files or glob patterns, eg. `src/**/*.cpp` for a `file(GLOB_RECURSE ...)`
+    pub sources: Vec<String>,
+    pub include_dirs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetKind {
+    Library,
+    Executable,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Arg<'a> {
+    text: &'a str,
+    /// quoted and bracket arguments are not split into lists
+    quoted: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Command<'a> {
+    name: &'a str,
+    args: Vec<Arg<'a>>,
+}
+
+/// Splits a lists file into command invocations, comments are skipped.
+fn parse_commands(text: &str) -> Vec<Command<'_>> {
+    let b = text.as_bytes();
+    let mut commands = vec![];
+    let mut i = 0;
+    while i < b.len() {
+        let c = b[i];
+        if c == b'#' {
+            i = skip_comment(text, i);
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            let start = i;
+            while i < b.len() && (b[i].is_ascii_alphanumeric() || b[i] == b'_') {
+                i += 1;
+            }
+            let name = &text[start..i];
+            while i < b.len() && (b[i] == b' ' || b[i] == b'\t') {
+                i += 1;
+            }
+            if b.get(i) != Some(&b'(') {
+                continue;
+            }
+            let (args, next) = parse_args(text, i + 1);
+            commands.push(Command { name, args });
+            i = next;
+        } else {
+            i += 1;
+        }
+    }
+    commands
+}
+
+/// Arguments up to the parenthesis closing the invocation,
+/// nested parenthesis, as found in conditions, are dropped.
+fn parse_args(text: &str, mut i: usize) -> (Vec<Arg<'_>>, usize) {
+    let b = text.as_bytes();
+    let mut args = vec![];
+    let mut depth = 0;
+    while i < b.len() {
+        let c = b[i];
+        if c.is_ascii_whitespace() {
+            i += 1;
+        } else if c == b'#' {
+            i = skip_comment(text, i);
+        } else if c == b'(' {
+            depth += 1;
+            i += 1;
+        } else if c == b')' {
+            i += 1;
+            if depth == 0 {
+                break;
+            }
+            depth -= 1;
+        } else if c == b'"' {
+            let start = i + 1;
+            let mut j = start;
+            while j < b.len() && b[j] != b'"' {
+                j += if b[j] == b'\\' { 2 } else { 1 };
+            }
+            let end = j.min(b.len());
+            args.push(Arg {
+                text: &text[start..end],
+                quoted: true,
+            });
+            i = end + 1;
+        } else if let Some((start, end, next)) = bracket(text, i) {
+            args.push(Arg {
+                text: &text[start..end],
+                quoted: true,
+            });
+            i = next;
+        } else {
+            let start = i;
+            while i < b.len() {
+                match b[i] {
+                    b'(' | b')' | b'"' | b'#' => break,
+                    x if x.is_ascii_whitespace() => break,
+                    b'\\' => i += 2,
+                    _ => i += 1,
+                }
+            }
+            let end = i.min(b.len());
+            i = end;
+            args.push(Arg {
+                text: &text[start..end],
+                quoted: false,
+            });
+        }
+    }
+    (args, i)
+}
+
+/// Matches a bracket argument, eg. `[[...]]` or `[=[...]=]`,
+/// giving the bounds of its content and the index following it.
+fn bracket(text: &str, i: usize) -> Option<(usize, usize, usize)> {
+    let b = text.as_bytes();
+    if b[i] != b'[' {
+        return None;
+    }
+    let eq = b[i + 1..].iter().take_while(|x| **x == b'=').count();
+    if b.get(i + 1 + eq) != Some(&b'[') {
+        return None;
+    }
+    let start = i + eq + 2;
+    let close = format!("]{}]", "=".repeat(eq));
+    let end = text[start..].find(&close).map_or(b.len(), |j| start + j);
+    Some((start, end, (end + close.len()).min(b.len())))
+}
+
+fn skip_comment(text: &str, i: usize) -> usize {
+    // bracket comments, eg. `#[[...]]`, can span multiple lines
+    if let Some((_, _, next)) = (i + 1 < text.len()).then(|| bracket(text, i + 1)).flatten() {
+        return next;
+    }
+    text[i..].find('\n').map_or(text.len(), |j| i + j)
+}
+
+/// Variables as set while scanning a lists file, lists being joined with `;` as in cmake.
+#[derive(Default)]
+struct Vars(HashMap<String, String>);
+
+impl Vars {
+    fn get(&self, name: &str) -> Option<&str> {
+        match name {
+            // relative paths are already relative to the current directory
+            "CMAKE_CURRENT_SOURCE_DIR" | "CMAKE_CURRENT_LIST_DIR" => Some("."),
+            name => self.0.get(name).map(|x| x.as_str()),
+        }
+    }
+
+    /// Expands `${...}` references, possibly nested,
+    /// unknown variables are left as is.
+    fn expand(&self, s: &str) -> String {
+        let mut out = String::new();
+        let mut rest = s;
+        while let Some(i) = rest.find("${") {
+            out.push_str(&rest[..i]);
+            let after = &rest[i + 2..];
+            let mut depth = 1;
+            let mut end = None;
+            let mut j = 0;
+            let b = after.as_bytes();
+            while j < b.len() {
+                if b[j..].starts_with(b"${") {
+                    depth += 1;
+                    j += 2;
+                    continue;
+                }
+                if b[j] == b'}' {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = Some(j);
+                        break;
+                    }
+                }
+                j += 1;
+            }
+            let Some(end) = end else {
+                out.push_str(&rest[i..]);
+                return out;
+            };
+            let name = self.expand(&after[..end]);
+            match self.get(&name) {
+                Some(value) => out.push_str(value),
+                None => {
+                    out.push_str("${");
+                    out.push_str(&name);
+                    out.push('}');
+                }
+            }
+            rest = &after[end + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+
+    /// Expanded arguments, unquoted ones being split as lists.
+    fn values(&self, args: &[Arg]) -> Vec<String> {
+        let mut values = vec![];
+        for arg in args {
+            let x = self.expand(arg.text);
+            if arg.quoted {
+                values.push(x);
+            } else {
+                values.extend(x.split(';').filter(|x| !x.is_empty()).map(|x| x.to_owned()));
+            }
+        }
+        values
+    }
+}
+
+const LIBRARY_KEYWORDS: &[&str] = &[
+    "STATIC",
+    "SHARED",
+    "MODULE",
+    "OBJECT",
+    "INTERFACE",
+    "UNKNOWN",
+    "EXCLUDE_FROM_ALL",
+];
+const EXECUTABLE_KEYWORDS: &[&str] = &["WIN32", "MACOSX_BUNDLE", "EXCLUDE_FROM_ALL"];
+const SCOPE_KEYWORDS: &[&str] = &["INTERFACE", "PUBLIC", "PRIVATE"];
+const INCLUDE_KEYWORDS: &[&str] = &[
+    "INTERFACE",
+    "PUBLIC",
+    "PRIVATE",
+    "SYSTEM",
+    "AFTER",
+    "BEFORE",
+];
+
+impl CMakeLists {
+    pub(crate) fn extract(text: &str) -> Self {
+        let mut vars = Vars::default();
+        let mut subdirectories = vec![];
+        let mut targets: Vec<Target> = vec![];
+        // from `include_directories`, they apply to every target of the directory
+        let mut include_dirs = vec![];
+        for cmd in parse_commands(text) {
+            let command = cmd.name.to_ascii_lowercase();
+            let args = vars.values(&cmd.args);
+            match (command.as_str(), &args[..]) {
+                ("project", [name, ..]) => {
+                    vars.0.insert("PROJECT_NAME".into(), name.clone());
+                    vars.0
+                        .entry("CMAKE_PROJECT_NAME".into())
+                        .or_insert_with(|| name.clone());
+                }
+                ("set", [var, values @ ..]) => {
+                    let values: Vec<_> = values
+                        .iter()
+                        .take_while(|x| *x != "CACHE" && *x != "PARENT_SCOPE")
+                        .map(|x| x.as_str())
+                        .collect();
+                    if values.is_empty() {
+                        vars.0.remove(var);
+                    } else {
+                        vars.0.insert(var.clone(), values.join(";"));
+                    }
+                }
+                ("unset", [var, ..]) => {
+                    vars.0.remove(var);
+                }
+                ("list", [op, var, values @ ..]) if op == "APPEND" => {
+                    let v = vars.0.entry(var.clone()).or_default();
+                    for x in values {
+                        if !v.is_empty() {
+                            v.push(';');
+                        }
+                        v.push_str(x);
+                    }
+                }
+                ("file", [op, var, rest @ ..]) if op == "GLOB" || op == "GLOB_RECURSE" => {
+                    let recurse = op == "GLOB_RECURSE";
+                    let mut patterns = vec![];
+                    let mut rest = rest.iter();
+                    while let Some(x) = rest.next() {
+                        match x.as_str() {
+                            "LIST_DIRECTORIES" | "RELATIVE" => {
+                                rest.next();
+                            }
+                            "CONFIGURE_DEPENDS" | "FOLLOW_SYMLINKS" => (),
+                            x if recurse => patterns.push(recursive_pattern(x)),
+                            x => patterns.push(x.to_owned()),
+                        }
+                    }
+                    vars.0.insert(var.clone(), patterns.join(";"));
+                }
+                ("add_subdirectory", [dir, ..]) => {
+                    if let Some(dir) = normalize_path(dir) {
+                        if !subdirectories.contains(&dir) {
+                            subdirectories.push(dir);
+                        }
+                    }
+                }
+                ("add_library" | "add_executable", [name, rest @ ..]) => {
+                    if rest.iter().any(|x| x == "ALIAS" || x == "IMPORTED") {
+                        continue;
+                    }
+                    if name.contains('$') {
+                        log::warn!("ignoring cmake target with unknown name: {}", name);
+                        continue;
+                    }
+                    let (kind, keywords) = if command == "add_library" {
+                        (TargetKind::Library, LIBRARY_KEYWORDS)
+                    } else {
+                        (TargetKind::Executable, EXECUTABLE_KEYWORDS)
+                    };
+                    let mut target = Target {
+                        name: name.clone(),
+                        kind,
+                        sources: vec![],
+                        include_dirs: vec![],
+                    };
+                    let sources = rest.iter().filter(|x| !keywords.contains(&x.as_str()));
+                    extend_paths(&mut target.sources, sources);
+                    targets.retain(|t| t.name != target.name);
+                    targets.push(target);
+                }
+                ("target_sources", [name, rest @ ..]) => {
+                    let Some(target) = targets.iter_mut().find(|t| &t.name == name) else {
+                        continue;
+                    };
+                    let mut sources = vec![];
+                    let mut rest = rest.iter();
+                    while let Some(x) = rest.next() {
+                        match x.as_str() {
+                            "FILE_SET" | "TYPE" | "BASE_DIRS" => {
+                                rest.next();
+                            }
+                            "FILES" => (),
+                            x if SCOPE_KEYWORDS.contains(&x) => (),
+                            _ => sources.push(x),
+                        }
+                    }
+                    extend_paths(&mut target.sources, sources.into_iter());
+                }
+                ("target_include_directories", [name, rest @ ..]) => {
+                    let Some(target) = targets.iter_mut().find(|t| &t.name == name) else {
+                        continue;
+                    };
+                    let dirs = rest
+                        .iter()
+                        .filter(|x| !INCLUDE_KEYWORDS.contains(&x.as_str()));
+                    extend_paths(&mut target.include_dirs, dirs);
+                }
+                ("include_directories", rest) => {
+                    let dirs = rest
+                        .iter()
+                        .filter(|x| !INCLUDE_KEYWORDS.contains(&x.as_str()));
+                    extend_paths(&mut include_dirs, dirs);
+                }
+                _ => (),
+            }
+        }
+        for target in &mut targets {
+            for dir in &include_dirs {
+                if !target.include_dirs.contains(dir) {
+                    target.include_dirs.push(dir.clone());
+                }
+            }
+        }
+        Self {
+            subdirectories,
+            targets,
+        }
+    }
+}
+
+fn extend_paths(paths: &mut Vec<String>, new: impl Iterator<Item = impl AsRef<str>>) {
+    for x in new {
+        if let Some(x) = normalize_path(x.as_ref()) {
+            if !paths.contains(&x) {
+                paths.push(x);
+            }
+        }
+    }
+}
+
+/// `GLOB_RECURSE` applies the file part of a pattern to every subdirectory
+fn recursive_pattern(pattern: &str) -> String {
+    match pattern.rsplit_once('/') {
+        Some((dir, file)) => format!("{}/**/{}", dir, file),
+        None => format!("**/{}", pattern),
+    }
+}
+
+/// Makes a path declared in a lists file relative to its directory.
+///
+/// Paths outside of the directory or depending on unknown variables are ignored,
+/// `$<BUILD_INTERFACE:...>` generator expressions are unwrapped.
+fn normalize_path(path: &str) -> Option<String> {
+    let path = path.trim();
+    let path = path
+        .strip_prefix("$<BUILD_INTERFACE:")
+        .and_then(|x| x.strip_suffix('>'))
+        .unwrap_or(path);
+    let mut path = path;
+    while let Some(x) = path.strip_prefix("./") {
+        path = x;
+    }
+    let path = path.trim_end_matches('/');
+    if path.is_empty() || path == "." {
+        return None;
+    }
+    if path.starts_with('/') || path.contains('$') || path.split('/').any(|x| x == "..") {
+        log::warn!("ignoring path declared in cmake lists: {}", path);
+        return None;
+    }
+    Some(path.to_owned())
+}
+
+pub(crate) fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?'])
+}
+
+/// Matches a `/` separated path against a cmake glob pattern,
+/// where `**` matches any number of directories.
+pub(crate) fn glob_matches(pattern: &str, path: &str) -> bool {
+    fn segments(p: &[&str], s: &[&str]) -> bool {
+        match (p.first(), s.first()) {
+            (None, None) => true,
+            (Some(&"**"), _) => segments(&p[1..], s) || (!s.is_empty() && segments(p, &s[1..])),
+            (Some(p0), Some(s0)) => {
+                segment(p0.as_bytes(), s0.as_bytes()) && segments(&p[1..], &s[1..])
+            }
+            _ => false,
+        }
+    }
+    fn segment(p: &[u8], s: &[u8]) -> bool {
+        match (p.first(), s.first()) {
+            (None, None) => true,
+            (Some(b'*'), _) => segment(&p[1..], s) || (!s.is_empty() && segment(p, &s[1..])),
+            (Some(b'?'), Some(_)) => segment(&p[1..], &s[1..]),
+            (Some(p0), Some(s0)) => p0 == s0 && segment(&p[1..], &s[1..]),
+            _ => false,
+        }
+    }
+    let p: Vec<_> = pattern.split('/').collect();
+    let s: Vec<_> = path.split('/').collect();
+    segments(&p, &s)
+}
+
+#[derive(Debug, Clone)]
+pub struct MD {
+    pub(crate) metrics: DefaultMetrics,
+    pub(crate) status: EnumSet<SemFlag>,
+}
+
+#[derive(enumset::EnumSetType, Debug)]
+pub enum SemFlag {
+    IsCMakeProject,
+    HoldCMakeSubProject,
+    HoldCMakeTarget,
+    IsCMakeTarget,
+    IsLibrary,
+    IsExecutable,
+    HoldIncludeFolder,
+}
+
+/// Name of the child grouping the targets of a directory,
+/// the lists file declaring them is not a child so it cannot clash with a subdirectory.
+pub(crate) const TARGETS: &str = "CMakeLists.txt";
+
+pub struct CMakeModuleAcc {
+    pub(crate) primary: BasicDirAcc<NodeIdentifier, LabelIdentifier, DefaultMetrics>,
+    pub(crate) sub_modules: Option<Vec<PathBuf>>,
+    /// the subdirectories given by the parent lists, part of the key of the cached directory
+    pub(crate) inherited: Vec<PathBuf>,
+    /// targets are only built once every subdirectory has been processed
+    pub(crate) targets: Vec<Target>,
+    pub(crate) status: EnumSet<SemFlag>,
+}
+
+impl From<String> for CMakeModuleAcc {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl CMakeModuleAcc {
+    pub(crate) fn new(name: String) -> Self {
+        Self {
+            primary: BasicDirAcc::new(name),
+            sub_modules: None,
+            inherited: vec![],
+            targets: vec![],
+            status: Default::default(),
+        }
+    }
+    pub(crate) fn with_content(name: String, inherited: Vec<PathBuf>) -> Self {
+        let sub_modules: Vec<_> = inherited
+            .iter()
+            .filter(|x| x.components().next().is_some())
+            .cloned()
+            .collect();
+        Self {
+            primary: BasicDirAcc::new(name),
+            sub_modules: if sub_modules.is_empty() {
+                None
+            } else {
+                Some(sub_modules)
+            },
+            inherited,
+            targets: vec![],
+            status: Default::default(),
+        }
+    }
+}
+
+impl CMakeModuleAcc {
+    /// Lists files are consumed but not kept as children, see [`handle_cmake_file`].
+    pub(crate) fn push_lists(&mut self, lists: CMakeLists) {
+        self.status |= SemFlag::IsCMakeProject;
+        self.sub_modules
+            .get_or_insert_with(Default::default)
+            .extend(lists.subdirectories.into_iter().map(PathBuf::from));
+        self.targets.extend(lists.targets);
+    }
+    pub fn push_submodule(&mut self, name: LabelIdentifier, full_node: (NodeIdentifier, MD)) {
+        if full_node.1.status.contains(SemFlag::HoldCMakeSubProject)
+            || full_node.1.status.contains(SemFlag::IsCMakeProject)
+        {
+            self.status |= SemFlag::HoldCMakeSubProject;
+        }
+        self.primary.children.push(full_node.0);
+        self.primary.children_names.push(name);
+        self.primary.metrics.acc(full_node.1.metrics);
+    }
+    /// Targets are grouped in a child named [`TARGETS`],
+    /// so their names can be the same as the ones of subdirectories.
+    pub(crate) fn push_targets(&mut self, name: LabelIdentifier, full_node: (NodeIdentifier, MD)) {
+        self.status |= SemFlag::HoldCMakeTarget;
+        self.primary.children.push(full_node.0);
+        self.primary.children_names.push(name);
+        self.primary.metrics.acc(full_node.1.metrics);
+    }
+}
+
+impl hyperast::tree_gen::Accumulator for CMakeModuleAcc {
+    type Node = (LabelIdentifier, (NodeIdentifier, MD));
+    fn push(&mut self, (name, full_node): Self::Node) {
+        self.push_submodule(name, full_node);
+    }
+}
+
+impl Accumulator for CMakeModuleAcc {
+    type Unlabeled = (NodeIdentifier, MD);
+}
+
+/// Groups the C/C++ subtrees of a target,
+/// its sources and include directories are nested in directories following their path relative to the lists file,
+/// so that every label is a single path segment.
+pub struct CMakeTargetAcc {
+    pub(crate) primary: BasicDirAcc<NodeIdentifier, LabelIdentifier, DefaultMetrics>,
+    /// directories leading to sources or include directories, made along with the target
+    pub(crate) dirs: Vec<CMakeTargetAcc>,
+    pub(crate) status: EnumSet<SemFlag>,
+}
+
+impl CMakeTargetAcc {
+    pub(crate) fn new(target: &Target) -> Self {
+        let kind = match target.kind {
+            TargetKind::Library => SemFlag::IsLibrary,
+            TargetKind::Executable => SemFlag::IsExecutable,
+        };
+        Self {
+            primary: BasicDirAcc::new(target.name.clone()),
+            dirs: vec![],
+            status: SemFlag::IsCMakeTarget | kind,
+        }
+    }
+    fn dir(name: String) -> Self {
+        Self {
+            primary: BasicDirAcc::new(name),
+            dirs: vec![],
+            status: EnumSet::new(),
+        }
+    }
+    /// `dirs` are the names of the directories leading to the file
+    pub(crate) fn push_source_file(
+        &mut self,
+        dirs: &[&str],
+        name: LabelIdentifier,
+        full_node: cpp_tree_gen::Local,
+    ) {
+        self.nested(dirs).push_cpp(name, full_node);
+    }
+    /// `dirs` are the names of the directories leading to the include directory
+    pub(crate) fn push_include_directory(
+        &mut self,
+        dirs: &[&str],
+        name: LabelIdentifier,
+        full_node: cpp_tree_gen::Local,
+    ) {
+        self.status |= SemFlag::HoldIncludeFolder;
+        self.nested(dirs).push_cpp(name, full_node);
+    }
+    fn nested(&mut self, dirs: &[&str]) -> &mut Self {
+        let Some((first, rest)) = dirs.split_first() else {
+            return self;
+        };
+        let i = match self.dirs.iter().position(|x| x.primary.name == *first) {
+            Some(i) => i,
+            None => {
+                self.dirs.push(Self::dir(first.to_string()));
+                self.dirs.len() - 1
+            }
+        };
+        self.dirs[i].nested(rest)
+    }
+    fn push_cpp(&mut self, name: LabelIdentifier, full_node: cpp_tree_gen::Local) {
+        if self.primary.children_names.contains(&name) {
+            // eg. a source also in an include directory
+            log::debug!("already in the target {:?}", name);
+            return;
+        }
+        self.primary.children.push(full_node.compressed_node);
+        self.primary.children_names.push(name);
+        self.primary.metrics.acc(SubTreeMetrics {
+            hashs: full_node.metrics.hashs,
+            size: full_node.metrics.size,
+            height: full_node.metrics.height,
+            size_no_spaces: full_node.metrics.size_no_spaces,
+            line_count: 0,
+        });
+    }
+    /// Pushes a made directory of the target, see [`CMakeTargetAcc::dirs`].
+    pub(crate) fn push_dir(&mut self, name: LabelIdentifier, full_node: (NodeIdentifier, MD)) {
+        if self.primary.children_names.contains(&name) {
+            // eg. an include directory that also leads to a source
+            log::debug!("already in the target {:?}", name);
+            return;
+        }
+        self.primary.children.push(full_node.0);
+        self.primary.children_names.push(name);
+        self.primary.metrics.acc(full_node.1.metrics);
+    }
+}
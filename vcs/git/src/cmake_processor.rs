@@ -0,0 +1,603 @@
+use crate::StackEle;
+use crate::processing::ParametrizedCommitProcessorHandle;
+use crate::processing::erased::{
+    CommitProcessorHandle, ParametrizedCommitProcessor2Handle as PCP2Handle,
+};
+use crate::{
+    BasicDirAcc, DefaultMetrics, Processor,
+    cmake::{CMakeModuleAcc, CMakeTargetAcc, MD, Target},
+    git::BasicGitObject,
+    preprocessed::RepositoryProcessor,
+    processing::{CacheHolding, InFiles, ObjectName, erased::ParametrizedCommitProc2},
+};
+use enumset::EnumSet;
+use git2::{Oid, Repository};
+use hyperast::store::nodes::compo;
+use hyperast::types::ETypeStore as _;
+use hyperast::{
+    hashed::{IndexingHashBuilder, MetaDataHashsBuilder},
+    store::nodes::legion::eq_node,
+    store::{defaults::LabelIdentifier, defaults::NodeIdentifier, nodes::EntityBuilder},
+    types::LabelStore,
+};
+use hyperast_gen_ts_cpp::types::Type;
+use std::{
+    iter::Peekable,
+    path::{Components, Path, PathBuf},
+};
+pub type SimpleStores = hyperast::store::SimpleStores<hyperast_gen_ts_cpp::types::TStore>;
+
+/// Explores cmake projects, following `add_subdirectory` from the root `CMakeLists.txt`.
+/// Each directory holds a node per target declared by its lists file,
+/// wrapping the C/C++ subtrees of the target sources and include directories.
+/// Directories that do not lead to a lists file are skipped.
+pub struct CMakeProcessor<'a, 'b, 'c, Acc> {
+    prepro: &'b mut RepositoryProcessor,
+    repository: &'a Repository,
+    stack: Vec<StackEle<Acc>>,
+    dir_path: &'c mut Peekable<Components<'c>>,
+    handle: ParametrizedCommitProcessorHandle,
+}
+
+impl<'a, 'b, 'c> CMakeProcessor<'a, 'b, 'c, CMakeModuleAcc> {
+    pub fn new(
+        repository: &'a Repository,
+        prepro: &'b mut RepositoryProcessor,
+        mut dir_path: &'c mut Peekable<Components<'c>>,
+        name: &[u8],
+        oid: git2::Oid,
+        handle: ParametrizedCommitProcessorHandle,
+    ) -> Self {
+        let tree = repository.find_tree(oid).unwrap();
+        let prepared = prepare_dir_exploration(tree, &mut dir_path);
+        let name = std::str::from_utf8(&name).unwrap().to_string();
+        let acc = CMakeModuleAcc::new(name);
+        let stack = vec![StackEle::new(oid, prepared, acc)];
+        Self {
+            stack,
+            repository,
+            prepro,
+            dir_path,
+            handle,
+        }
+    }
+}
+
+impl<'a, 'b, 'c> Processor<CMakeModuleAcc> for CMakeProcessor<'a, 'b, 'c, CMakeModuleAcc> {
+    fn pre(&mut self, current_dir: BasicGitObject) {
+        match current_dir {
+            BasicGitObject::Tree(oid, name) => {
+                self.handle_tree_cached(name, oid);
+            }
+            BasicGitObject::Blob(oid, name)
+                if self.dir_path.peek().is_none()
+                    && crate::processing::file_sys::CMakeLists::matches(&name) =>
+            {
+                let parent_acc = &mut self.stack.last_mut().unwrap().acc;
+                let parameters = PCP2Handle(self.handle.1, std::marker::PhantomData);
+                if let Err(err) = self.prepro.help_handle_cmake_lists(
+                    oid,
+                    parent_acc,
+                    name,
+                    &self.repository,
+                    parameters,
+                ) {
+                    log::debug!("{:?}", err);
+                }
+            }
+            _ => {}
+        }
+    }
+    fn post(&mut self, oid: Oid, mut acc: CMakeModuleAcc) -> Option<(NodeIdentifier, MD)> {
+        // targets come last, once subdirectories are pushed
+        let targets = std::mem::take(&mut acc.targets);
+        if !targets.is_empty() {
+            let mut targets_acc = BasicDirAcc::new(crate::cmake::TARGETS.to_string());
+            for target in targets {
+                let (name, full_node) = self.handle_target(oid, target);
+                if targets_acc.children_names.contains(&name) {
+                    log::debug!("target declared twice {:?}", name);
+                    continue;
+                }
+                targets_acc.children.push(full_node.0);
+                targets_acc.children_names.push(name);
+                targets_acc.metrics.acc(full_node.1.metrics);
+            }
+            let status = crate::cmake::SemFlag::HoldCMakeTarget.into();
+            let full_node = make(
+                targets_acc,
+                status,
+                self.prepro.main_stores_mut().mut_with_ts(),
+            );
+            let name = self.prepro.intern_label(crate::cmake::TARGETS);
+            acc.push_targets(name, full_node);
+        }
+        let key = (oid, acc.inherited.clone());
+        let name = acc.primary.name.clone();
+        let full_node = make(
+            acc.primary,
+            acc.status,
+            self.prepro.main_stores_mut().mut_with_ts(),
+        );
+        self.prepro
+            .processing_systems
+            .mut_or_default::<CMakeProcessorHolder>()
+            .with_parameters_mut(self.handle.1)
+            .cache
+            .object_map
+            .insert(key, full_node.clone());
+        let name = self.prepro.intern_label(&name);
+        if self.stack.is_empty() {
+            Some(full_node)
+        } else {
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            assert!(
+                !w.primary.children_names.contains(&name),
+                "{:?} {:?}",
+                w.primary.children_names,
+                name
+            );
+            w.push_submodule(name, full_node);
+            None
+        }
+    }
+
+    fn stack(&mut self) -> &mut Vec<StackEle<CMakeModuleAcc>> {
+        &mut self.stack
+    }
+}
+
+impl<'a, 'b, 'c> CMakeProcessor<'a, 'b, 'c, CMakeModuleAcc> {
+    fn handle_tree_cached(&mut self, name: ObjectName, oid: Oid) {
+        if let Some(s) = self.dir_path.peek() {
+            if name
+                .as_bytes()
+                .eq(std::ffi::OsStr::as_encoded_bytes(s.as_os_str()))
+            {
+                self.dir_path.next();
+                self.stack.last_mut().expect("never empty").cs.clear();
+                let tree = self.repository.find_tree(oid).unwrap();
+                let prepared = prepare_dir_exploration(tree, &mut self.dir_path);
+                let acc = CMakeModuleAcc::new(name.try_into().unwrap());
+                self.stack.push(StackEle::new(oid, prepared, acc));
+            }
+            return;
+        }
+        let parent_acc = &mut self.stack.last_mut().unwrap().acc;
+        let sub_modules = drain_filter_strip(&mut parent_acc.sub_modules, name.as_bytes());
+        if sub_modules.is_empty() {
+            return;
+        }
+        // either a subdirectory with its own lists file, or on the way to one
+        let key = (oid, sub_modules);
+        let cmake_proc = self
+            .prepro
+            .processing_systems
+            .mut_or_default::<CMakeProcessorHolder>()
+            .with_parameters_mut(self.handle.1);
+        if let Some(already) = cmake_proc.cache.object_map.get(&key) {
+            // reinit already computed node for post order
+            let full_node = already.clone();
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            let name = self.prepro.intern_object_name(&name);
+            assert!(!w.primary.children_names.contains(&name));
+            w.push_submodule(name, full_node);
+            return;
+        }
+
+        log::debug!("cmake tree {:?}", name.try_str());
+        let (_, sub_modules) = key;
+        let tree = self.repository.find_tree(oid).unwrap();
+        let prepared = prepare_dir_exploration(tree, &mut self.dir_path);
+        let acc = CMakeModuleAcc::with_content(name.try_into().unwrap(), sub_modules);
+        self.stack.push(StackEle::new(oid, prepared, acc));
+    }
+
+    /// Sources and include directories are resolved in the tree of the directory declaring the target.
+    fn handle_target(
+        &mut self,
+        tree_oid: Oid,
+        target: Target,
+    ) -> (LabelIdentifier, (NodeIdentifier, MD)) {
+        let cpp_handle = self
+            .prepro
+            .processing_systems
+            .mut_or_default::<CMakeProcessorHolder>()
+            .with_parameters_mut(self.handle.1)
+            .parameter
+            .cpp_handle;
+        let tree = self.repository.find_tree(tree_oid).unwrap();
+        let mut target_acc = CMakeTargetAcc::new(&target);
+        for (path, oid) in resolve_sources(&tree, &target.sources) {
+            let mut dirs: Vec<_> = path.split('/').collect();
+            let file_name = dirs.pop().unwrap_or_default();
+            let file_name = ObjectName::from(file_name.as_bytes());
+            if !crate::processing::file_sys::Cpp::matches(&file_name) {
+                log::debug!("not cpp source file {:?}", path);
+                continue;
+            }
+            match self
+                .prepro
+                .handle_cpp_blob(oid, &file_name, self.repository, cpp_handle)
+            {
+                Ok((full_node,)) => {
+                    let name = self.prepro.intern_object_name(&file_name);
+                    target_acc.push_source_file(&dirs, name, full_node);
+                }
+                Err(err) => log::debug!("{:?} {:?}", path, err),
+            }
+        }
+        for dir in &target.include_dirs {
+            let entry = match tree.get_path(Path::new(dir)) {
+                Ok(entry) if entry.kind() == Some(git2::ObjectType::Tree) => entry,
+                _ => {
+                    log::debug!("missing include directory {:?}", dir);
+                    continue;
+                }
+            };
+            let mut dirs: Vec<_> = dir.split('/').collect();
+            let dir_name = dirs.pop().unwrap_or_default();
+            let dir_name = ObjectName::from(dir_name.as_bytes());
+            let dir_path = PathBuf::new();
+            let mut dir_path = dir_path.components().peekable();
+            let (_, (full_node,)) = self.prepro.help_handle_cpp_folder(
+                self.repository,
+                &mut dir_path,
+                entry.id(),
+                &dir_name,
+                cpp_handle,
+            );
+            let name = self.prepro.intern_object_name(&dir_name);
+            target_acc.push_include_directory(&dirs, name, full_node);
+        }
+        let name = self.prepro.intern_label(&target.name);
+        let full_node = make_target(target_acc, self.prepro.main_stores_mut().mut_with_ts());
+        (name, full_node)
+    }
+}
+
+/// Makes the directories of the target first, see [`CMakeTargetAcc::dirs`].
+fn make_target(mut acc: CMakeTargetAcc, stores: &mut SimpleStores) -> (NodeIdentifier, MD) {
+    for dir in std::mem::take(&mut acc.dirs) {
+        let name = stores.label_store.get_or_insert(dir.primary.name.clone());
+        let full_node = make_target(dir, stores);
+        acc.push_dir(name, full_node);
+    }
+    make(acc.primary, acc.status, stores)
+}
+
+/// Files of the given tree matching the sources of a target, in declaration order for plain paths,
+/// then in tree order for the ones matched by globs.
+fn resolve_sources(tree: &git2::Tree, sources: &[String]) -> Vec<(String, Oid)> {
+    let mut files = vec![];
+    let (globs, paths): (Vec<_>, Vec<_>) = sources.iter().partition(|x| crate::cmake::is_glob(x));
+    for path in paths {
+        match tree.get_path(Path::new(path)) {
+            Ok(entry) if entry.kind() == Some(git2::ObjectType::Blob) => {
+                files.push((path.clone(), entry.id()))
+            }
+            // eg. generated sources
+            _ => log::debug!("missing cmake source {:?}", path),
+        }
+    }
+    if globs.is_empty() {
+        return files;
+    }
+    let walked = tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+        if entry.kind() != Some(git2::ObjectType::Blob) {
+            return git2::TreeWalkResult::Ok;
+        }
+        let Some(name) = entry.name() else {
+            return git2::TreeWalkResult::Ok;
+        };
+        let path = format!("{}{}", root, name);
+        if globs.iter().any(|g| crate::cmake::glob_matches(g, &path))
+            && !files.iter().any(|(p, _)| p == &path)
+        {
+            files.push((path, entry.id()));
+        }
+        git2::TreeWalkResult::Ok
+    });
+    if let Err(err) = walked {
+        log::warn!("{}", err);
+    }
+    files
+}
+
+/// Used for both directories and targets, they are told apart with their flags.
+pub(crate) fn make(
+    primary: BasicDirAcc<NodeIdentifier, LabelIdentifier, DefaultMetrics>,
+    status: EnumSet<crate::cmake::SemFlag>,
+    stores: &mut SimpleStores,
+) -> (NodeIdentifier, MD) {
+    let kind = Type::Directory;
+    let interned_kind = hyperast_gen_ts_cpp::types::TStore::intern(kind);
+    let label_id = stores.label_store.get_or_insert(primary.name.clone());
+
+    let primary = primary.map_metrics(|m| m.finalize(&interned_kind, &label_id, 0));
+
+    let hashable = primary.metrics.hashs.most_discriminating();
+
+    let eq = eq_node(&interned_kind, Some(&label_id), &primary.children);
+
+    let insertion = stores.node_store.prepare_insertion(&hashable, eq);
+    if let Some(id) = insertion.occupied_id() {
+        let metrics = primary.metrics.map_hashs(|h| h.build());
+        return (id, MD { metrics, status });
+    }
+
+    log::info!("make cmake {} {}", &primary.name, primary.children.len());
+    assert_eq!(primary.children_names.len(), primary.children.len());
+    let mut dyn_builder = hyperast::store::nodes::legion::dyn_builder::EntityBuilder::with_lang(
+        hyperast_gen_ts_cpp::types::Lang,
+    );
+    let children_is_empty = primary.children.is_empty();
+    if !status.is_empty() {
+        dyn_builder.add(compo::Flags(status));
+    }
+    let metrics = primary.persist(&mut dyn_builder, interned_kind, label_id);
+    let metrics = metrics.map_hashs(|h| h.build());
+    let hashs = metrics.add_md_metrics(&mut dyn_builder, children_is_empty);
+    hashs.persist(&mut dyn_builder);
+
+    let vacant = insertion.vacant();
+    let node_id = hyperast::store::nodes::legion::NodeStore::insert_built_after_prepare(
+        vacant,
+        dyn_builder.build(),
+    );
+
+    (node_id, MD { metrics, status })
+}
+
+impl RepositoryProcessor {
+    fn help_handle_cmake_lists(
+        &mut self,
+        oid: Oid,
+        parent_acc: &mut CMakeModuleAcc,
+        name: ObjectName,
+        repository: &Repository,
+        parameters: PCP2Handle<CMakeProc>,
+    ) -> Result<(), crate::ParseErr> {
+        let x = self
+            .processing_systems
+            .caching_blob_handler::<crate::processing::file_sys::CMakeLists>()
+            .handle(oid, repository, &name, parameters, |_c, n, t| {
+                crate::cmake::handle_cmake_file(n, t).map_err(|_| crate::ParseErr::IllFormed)
+            })?;
+        parent_acc.push_lists(x);
+        Ok(())
+    }
+}
+
+fn drain_filter_strip(v: &mut Option<Vec<PathBuf>>, name: &[u8]) -> Vec<PathBuf> {
+    let mut new_sub_modules = vec![];
+    let name = std::str::from_utf8(&name).unwrap();
+    if let Some(sub_modules) = v {
+        vec_extract_if_polyfill::MakeExtractIf::extract_if(sub_modules, |x| x.starts_with(name))
+            .for_each(|x| {
+                let x = x.strip_prefix(name).unwrap().to_owned();
+                new_sub_modules.push(x);
+            });
+    }
+    new_sub_modules
+}
+
+/// sometimes order of files/dirs can be important, similarly to order of statement
+/// exploration order for example
+pub(crate) fn prepare_dir_exploration(
+    tree: git2::Tree,
+    dir_path: &mut Peekable<Components>,
+) -> Vec<BasicGitObject> {
+    let mut children_objects: Vec<BasicGitObject> = tree
+        .iter()
+        .map(TryInto::try_into)
+        .filter_map(|x| x.ok())
+        .collect();
+    if dir_path.peek().is_none() {
+        let p = children_objects.iter().position(|x| match x {
+            BasicGitObject::Blob(_, n) => crate::processing::file_sys::CMakeLists::matches(n),
+            _ => false,
+        });
+        if let Some(p) = p {
+            children_objects.swap(0, p); // priority to config file processing
+            children_objects.reverse(); // we use it like a stack
+        }
+    }
+    children_objects
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub cpp_handle: PCP2Handle<crate::cpp_processor::CppProc>,
+}
+
+#[derive(Default)]
+pub struct CMakeProcessorHolder(Vec<CMakeProc>);
+pub struct CMakeProc {
+    parameter: Parameter,
+    cache: crate::processing::caches::CMake,
+    lists: crate::processing::caches::CMakeLists,
+    commits: std::collections::HashMap<git2::Oid, crate::Commit>,
+}
+impl crate::processing::erased::Parametrized for CMakeProcessorHolder {
+    type T = Parameter;
+    fn register_param(
+        &mut self,
+        t: Self::T,
+    ) -> crate::processing::erased::ParametrizedCommitProcessorHandle {
+        let l = self
+            .0
+            .iter()
+            .position(|x| &x.parameter == &t)
+            .unwrap_or_else(|| {
+                let l = self.0.len();
+                self.0.push(CMakeProc {
+                    parameter: t,
+                    cache: Default::default(),
+                    lists: Default::default(),
+                    commits: Default::default(),
+                });
+                l
+            });
+        use crate::processing::erased::ConfigParametersHandle;
+        use crate::processing::erased::ParametrizedCommitProc;
+        ParametrizedCommitProcessorHandle(self.erased_handle(), ConfigParametersHandle(l))
+    }
+}
+
+struct PreparedCMakeCommitProc<'repo> {
+    repository: &'repo git2::Repository,
+    commit_builder: crate::preprocessed::CommitBuilder,
+    pub(crate) handle: ParametrizedCommitProcessorHandle,
+}
+
+impl<'repo> crate::processing::erased::PreparedCommitProc for PreparedCMakeCommitProc<'repo> {
+    fn process(
+        self: Box<PreparedCMakeCommitProc<'repo>>,
+        prepro: &mut RepositoryProcessor,
+    ) -> hyperast::store::defaults::NodeIdentifier {
+        let dir_path = PathBuf::from("");
+        let mut dir_path = dir_path.components().peekable();
+        let name = b"";
+        let root_full_node = CMakeProcessor::<CMakeModuleAcc>::new(
+            self.repository,
+            prepro,
+            &mut dir_path,
+            name,
+            self.commit_builder.tree_oid(),
+            self.handle,
+        )
+        .process();
+        let h = prepro
+            .processing_systems
+            .mut_or_default::<CMakeProcessorHolder>();
+        let handle = self.handle;
+        let commit_oid = self.commit_builder.commit_oid();
+        let commit = self.commit_builder.finish(root_full_node.0);
+        h.with_parameters_mut(handle.1)
+            .commits
+            .insert(commit_oid, commit);
+        root_full_node.0
+    }
+}
+
+impl crate::processing::erased::CommitProc for CMakeProc {
+    fn prepare_processing<'repo>(
+        &self,
+        repository: &'repo git2::Repository,
+        commit_builder: crate::preprocessed::CommitBuilder,
+        handle: crate::processing::ParametrizedCommitProcessorHandle,
+    ) -> Box<dyn crate::processing::erased::PreparedCommitProc + 'repo> {
+        Box::new(PreparedCMakeCommitProc {
+            repository,
+            commit_builder,
+            handle,
+        })
+    }
+
+    fn get_commit(&self, commit_oid: git2::Oid) -> Option<&crate::Commit> {
+        self.commits.get(&commit_oid)
+    }
+
+    fn commit_count(&self) -> usize {
+        self.commits.len()
+    }
+
+    fn get_lang_handle(&self, lang: &str) -> Option<ParametrizedCommitProcessorHandle> {
+        if lang.eq_ignore_ascii_case("cpp") {
+            Some(ParametrizedCommitProcessorHandle(
+                CommitProcessorHandle(std::any::TypeId::of::<
+                    crate::cpp_processor::CppProcessorHolder,
+                >()),
+                self.parameter.cpp_handle.0,
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl crate::processing::erased::CommitProcExt for CMakeProc {
+    type Holder = CMakeProcessorHolder;
+}
+impl crate::processing::erased::ParametrizedCommitProc2 for CMakeProcessorHolder {
+    type Proc = CMakeProc;
+
+    fn with_parameters_mut(
+        &mut self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &mut Self::Proc {
+        &mut self.0[parameters.0]
+    }
+
+    fn with_parameters(
+        &self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &Self::Proc {
+        &self.0[parameters.0]
+    }
+
+    fn persist(
+        &self,
+        _stores: &crate::SimpleStores,
+        mut w: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::write_u32;
+        write_u32(&mut w, self.0.len() as u32)?;
+        for proc in &self.0 {
+            crate::persist::write_commits(&mut w, &proc.commits)?;
+        }
+        Ok(())
+    }
+
+    fn restore(
+        &mut self,
+        _stores: &crate::SimpleStores,
+        mut r: &mut dyn std::io::Read,
+    ) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::read_u32_from;
+        let len = read_u32_from(&mut r)? as usize;
+        if len > self.0.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "more persisted processors than registered ones",
+            ));
+        }
+        for proc in &mut self.0[..len] {
+            proc.commits = crate::persist::read_commits(&mut r)?;
+        }
+        Ok(())
+    }
+
+    fn commit_roots(&self) -> Vec<(git2::Oid, hyperast::store::defaults::NodeIdentifier)> {
+        self.0
+            .iter()
+            .flat_map(|proc| proc.commits.iter().map(|(oid, c)| (*oid, c.ast_root)))
+            .collect()
+    }
+
+    fn retain_reachable(&mut self, reachable: &hyperast::store::nodes::legion::gc::Reachable) {
+        for proc in &mut self.0 {
+            proc.commits.retain(|_, c| reachable.contains(&c.ast_root));
+            proc.cache.retain_reachable(reachable);
+        }
+    }
+}
+
+impl CacheHolding<crate::processing::caches::CMake> for CMakeProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::CMake {
+        &mut self.cache
+    }
+    fn get_caches(&self) -> &crate::processing::caches::CMake {
+        &self.cache
+    }
+}
+
+impl CacheHolding<crate::processing::caches::CMakeLists> for CMakeProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::CMakeLists {
+        &mut self.lists
+    }
+    fn get_caches(&self) -> &crate::processing::caches::CMakeLists {
+        &self.lists
+    }
+}
@@ -285,6 +285,54 @@ impl crate::processing::erased::ParametrizedCommitProc2 for CppProcessorHolder {
     ) -> &Self::Proc {
         &self.0[parameters.0]
     }
+
+    fn persist(
+        &self,
+        _stores: &crate::SimpleStores,
+        mut w: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::write_u32;
+        write_u32(&mut w, self.0.len() as u32)?;
+        for proc in &self.0 {
+            crate::persist::write_commits(&mut w, &proc.commits)?;
+            proc.cache.save(&mut w)?;
+        }
+        Ok(())
+    }
+
+    fn restore(
+        &mut self,
+        stores: &crate::SimpleStores,
+        mut r: &mut dyn std::io::Read,
+    ) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::read_u32_from;
+        let len = read_u32_from(&mut r)? as usize;
+        if len > self.0.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "more persisted processors than registered ones",
+            ));
+        }
+        for proc in &mut self.0[..len] {
+            proc.commits = crate::persist::read_commits(&mut r)?;
+            proc.cache = crate::processing::caches::Cpp::load(stores, &mut r)?;
+        }
+        Ok(())
+    }
+
+    fn commit_roots(&self) -> Vec<(git2::Oid, hyperast::store::defaults::NodeIdentifier)> {
+        self.0
+            .iter()
+            .flat_map(|proc| proc.commits.iter().map(|(oid, c)| (*oid, c.ast_root)))
+            .collect()
+    }
+
+    fn retain_reachable(&mut self, reachable: &hyperast::store::nodes::legion::gc::Reachable) {
+        for proc in &mut self.0 {
+            proc.commits.retain(|_, c| reachable.contains(&c.ast_root));
+            proc.cache.retain_reachable(reachable);
+        }
+    }
 }
 impl CacheHolding<crate::processing::caches::Cpp> for CppProc {
     fn get_caches_mut(&mut self) -> &mut crate::processing::caches::Cpp {
@@ -306,7 +354,7 @@ impl CacheHolding<crate::processing::caches::Cpp> for CppProc {
 
 #[cfg(feature = "cpp")]
 impl RepositoryProcessor {
-    fn handle_cpp_blob(
+    pub(crate) fn handle_cpp_blob(
         &mut self,
         oid: Oid,
         name: &ObjectName,
@@ -1,4 +1,5 @@
 use std::fmt::{Debug, Display};
+use std::str::FromStr;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 use std::{fs, process};
@@ -228,11 +229,17 @@ where
     repository.unwrap()
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub enum Forge {
     Github,
     Gitlab,
     GitlabInria,
+    /// Any other git host given by its base url,
+    /// e.g. `https://git.example.org/` or `file:///srv/git/`
+    Url(String),
+    /// Repositories already on disk at `<dir>/<user>/<name>`,
+    /// they are opened in place and never fetched
+    Local(PathBuf),
 }
 
 impl std::str::FromStr for Forge {
@@ -240,20 +247,43 @@ impl std::str::FromStr for Forge {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(match s {
-            "github.com" => Self::Github,
-            "gitlab.com" => Self::Gitlab,
+            "github.com" | "github" => Self::Github,
+            "gitlab.com" | "gitlab" => Self::Gitlab,
             "gitlab.inria.fr" => Self::GitlabInria,
+            x if x.contains("://") => Self::Url(x.to_string()),
+            x if Path::new(x).is_absolute() => Self::Local(PathBuf::from(x)),
             x => return Err(format!("'{}' is not an authorize forge", x)),
         })
     }
 }
 
 impl Forge {
-    fn url(&self) -> &str {
+    fn url(&self) -> String {
+        match self {
+            Forge::Github => "https://github.com/".to_string(),
+            Forge::Gitlab => "https://gitlab.com/".to_string(),
+            Forge::GitlabInria => "https://gitlab.inria.fr/".to_string(),
+            Forge::Url(base) if base.ends_with('/') => base.clone(),
+            Forge::Url(base) => format!("{}/", base),
+            Forge::Local(dir) => format!("file://{}/", dir.display()),
+        }
+    }
+
+    /// Identifies the forge in routes, e.g. `github` in `/view/github/<user>/<name>/<commit>/`.
+    ///
+    /// Generic forges are identified by their domain (or `file` without domain),
+    /// repositories on disk by `local`.
+    pub fn segment(&self) -> String {
         match self {
-            Forge::Github => "https://github.com/",
-            Forge::Gitlab => "https://gitlab.com/",
-            Forge::GitlabInria => "https://gitlab.inria.fr/",
+            Forge::Github => "github".to_string(),
+            Forge::Gitlab => "gitlab".to_string(),
+            Forge::GitlabInria => "gitlab.inria.fr".to_string(),
+            Forge::Url(base) => match Url::from_str(&self.url()) {
+                Ok(url) if !url.domain.is_empty() => url.domain,
+                Ok(url) => url.protocol,
+                Err(()) => base.clone(),
+            },
+            Forge::Local(_) => "local".to_string(),
         }
     }
 
@@ -283,6 +313,20 @@ impl Forge {
     }
 }
 
+const DEFAULT_CLONE_ROOT: &str = "/tmp/hyperastgitresources/repo/";
+static CLONE_ROOT: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+
+/// Sets the directory where remote repositories are cloned,
+/// must be done before the first fetch, otherwise the default one is kept.
+pub fn set_clone_root(path: impl Into<PathBuf>) -> Result<(), PathBuf> {
+    CLONE_ROOT.set(path.into())
+}
+
+/// Directory where remote repositories are cloned, see [`set_clone_root`].
+pub fn clone_root() -> &'static Path {
+    CLONE_ROOT.get_or_init(|| PathBuf::from(DEFAULT_CLONE_ROOT))
+}
+
 // TODO use `&'static str`s to derive with Copy
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub struct Repo {
@@ -296,30 +340,41 @@ impl Repo {
         format!("{}{}/{}", self.forge.url(), self.user, self.name)
     }
     pub fn fetch(&self) -> Repository {
-        let url = self.url();
-        let path = "/tmp/hyperastgitresources/repo/".to_string();
-        fetch_repository(url, path)
+        self.fetch_to(clone_root())
     }
     pub fn nofetch(&self) -> Repository {
-        let url = self.url();
-        let path = "/tmp/hyperastgitresources/repo/".to_string();
-        nofetch_repository(url, path)
+        self.nofetch_to(clone_root())
     }
 
+    /// Repositories on disk are opened in place.
     pub fn fetch_to(&self, path: impl Into<PathBuf>) -> Repository {
+        if let Some(path) = self.local_path() {
+            return open_local(&path);
+        }
         let url = self.url();
         let path = path.into();
         fetch_repository(url, path)
     }
 
     pub fn nofetch_to(&self, path: impl Into<PathBuf>) -> Repository {
+        if let Some(path) = self.local_path() {
+            return open_local(&path);
+        }
         let url = self.url();
         let path = path.into();
         nofetch_repository(url, path)
     }
 
-    pub fn forge(&self) -> Forge {
-        self.forge
+    /// Path of repositories that are already on disk.
+    pub fn local_path(&self) -> Option<PathBuf> {
+        match &self.forge {
+            Forge::Local(dir) => Some(dir.join(&self.user).join(&self.name)),
+            _ => None,
+        }
+    }
+
+    pub fn forge(&self) -> &Forge {
+        &self.forge
     }
     pub fn user(&self) -> &str {
         &self.user
@@ -329,6 +384,11 @@ impl Repo {
     }
 }
 
+fn open_local(path: &Path) -> Repository {
+    log::info!("opening local repo at {}", path.display());
+    Repository::open(path).unwrap()
+}
+
 impl Display for Repo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "{}{}/{}", self.forge.url(), self.user, self.name)
@@ -338,7 +398,24 @@ impl Display for Repo {
 impl std::str::FromStr for Repo {
     type Err = String;
 
+    /// Either `<forge>/<user>/<name>` for known forges, e.g. `github.com/INRIA/spoon`,
+    /// or `<url or absolute path>/<user>/<name>`, e.g. `file:///srv/git/INRIA/spoon`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains("://") || Path::new(s).is_absolute() {
+            let (rest, name) = s
+                .trim_end_matches('/')
+                .rsplit_once("/")
+                .ok_or("give a valid repository address")?;
+            let (forge, user) = rest
+                .rsplit_once("/")
+                .ok_or("give a valid repository address")?;
+            let forge: Forge = if forge.is_empty() {
+                Forge::Local(PathBuf::from("/"))
+            } else {
+                forge.parse()?
+            };
+            return forge.try_repo(user, name);
+        }
         let (forge, repo) = s
             .split_once("/")
             .ok_or("give a valid repository address without 'https://' and '.git'")?;
@@ -358,8 +435,7 @@ impl std::str::FromStr for Repo {
 
 pub fn fetch_github_repository(repo_name: &str) -> Repository {
     let url = format!("{}{}", "https://github.com/", repo_name);
-    let path = "/tmp/hyperastgitresources/repo/".to_string();
-    fetch_repository(url, path)
+    fetch_repository(url, clone_root())
 }
 
 pub fn fetch_remote(mut x: git2::Remote, head: &str) -> Result<(), git2::Error> {
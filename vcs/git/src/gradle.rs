@@ -0,0 +1,450 @@
+use crate::{Accumulator, BasicDirAcc, DefaultMetrics, processing::ObjectName};
+use enumset::EnumSet;
+use hyperast::{
+    store::defaults::{LabelIdentifier, NodeIdentifier},
+    tree_gen::SubTreeMetrics,
+};
+use hyperast_gen_ts_java::legion_with_refs as java_tree_gen;
+use std::path::PathBuf;
+
+const DEFAULT_SOURCE_DIR: &str = "src/main/java";
+const DEFAULT_TEST_SOURCE_DIR: &str = "src/test/java";
+
+/// Extracts the project layout declared in a gradle script,
+/// either a settings script (`settings.gradle(.kts)`) or a build script (`build.gradle(.kts)`).
+///
+/// There is no groovy nor kotlin generator, so scripts are not part of the resulting HyperAST,
+/// they are only scanned for the few declarations that matter to explore the project.
+pub(crate) fn handle_gradle_file(name: &ObjectName, text: &[u8]) -> Result<GradleScript, ()> {
+    let text = std::str::from_utf8(text).map_err(|_| ())?;
+    log::debug!("gradle script {:?}", name.try_str());
+    Ok(GradleScript::extract(text))
+}
+
+/// What a gradle script declares about the layout of its project.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GradleScript {
+    /// directories of the included projects and builds, relative to the script
+    pub submodules: Vec<String>,
+    /// None if the script does not configure the `main` source set
+    pub source_dirs: Option<Vec<String>>,
+    /// None if the script does not configure the `test` source set
+    pub test_source_dirs: Option<Vec<String>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Tok<'a> {
+    Ident(&'a str),
+    Str(&'a str),
+    Punct(char),
+    NewLine,
+}
+
+/// Splits a groovy or kotlin script in the few tokens needed to recognize gradle declarations,
+/// comments are skipped and string templates are kept as is.
+fn tokenize(text: &str) -> Vec<Tok<'_>> {
+    let b = text.as_bytes();
+    let mut toks = vec![];
+    let mut i = 0;
+    while i < b.len() {
+        let c = b[i];
+        if c == b'\n' {
+            toks.push(Tok::NewLine);
+            i += 1;
+        } else if c.is_ascii_whitespace() {
+            i += 1;
+        } else if text[i..].starts_with("//") {
+            i = text[i..].find('\n').map_or(b.len(), |j| i + j);
+        } else if text[i..].starts_with("/*") {
+            i = text[i + 2..].find("*/").map_or(b.len(), |j| i + 2 + j + 2);
+        } else if c == b'"' || c == b'\'' {
+            let q = if text[i..].starts_with("\"\"\"") {
+                "\"\"\""
+            } else if text[i..].starts_with("'''") {
+                "'''"
+            } else {
+                &text[i..i + 1]
+            };
+            let start = i + q.len();
+            let mut j = start;
+            while j < b.len() && !b[j..].starts_with(q.as_bytes()) {
+                j += if b[j] == b'\\' { 2 } else { 1 };
+            }
+            let end = j.min(b.len());
+            toks.push(Tok::Str(&text[start..end]));
+            i = end + q.len();
+        } else if c.is_ascii_alphabetic() || c == b'_' || c == b'$' {
+            let start = i;
+            while i < b.len() && (b[i].is_ascii_alphanumeric() || b[i] == b'_' || b[i] == b'$') {
+                i += 1;
+            }
+            toks.push(Tok::Ident(&text[start..i]));
+        } else if c.is_ascii() {
+            toks.push(Tok::Punct(c as char));
+            i += 1;
+        } else {
+            // skip the whole utf8 char
+            i += text[i..].chars().next().map_or(1, |c| c.len_utf8());
+        }
+    }
+    toks
+}
+
+#[derive(Default)]
+struct SourceSetDirs {
+    /// the default directory is replaced, eg. with `srcDirs = [...]`
+    replaced: bool,
+    dirs: Vec<String>,
+}
+
+impl SourceSetDirs {
+    fn finish(self, default: &str) -> Option<Vec<String>> {
+        if !self.replaced && self.dirs.is_empty() {
+            return None;
+        }
+        let mut dirs = if self.replaced {
+            vec![]
+        } else {
+            vec![default.to_owned()]
+        };
+        for d in self.dirs {
+            if !dirs.contains(&d) {
+                dirs.push(d);
+            }
+        }
+        Some(dirs)
+    }
+}
+
+impl GradleScript {
+    pub(crate) fn extract(text: &str) -> Self {
+        let toks = tokenize(text);
+        let mut included = vec![];
+        let mut project_dirs = std::collections::HashMap::new();
+        let mut main = SourceSetDirs::default();
+        let mut test = SourceSetDirs::default();
+        // names of the enclosing blocks, eg. `sourceSets`, `main`, `java`
+        let mut blocks: Vec<Vec<&str>> = vec![];
+        // names in the current statement, eg. `sourceSets.main.java.srcDirs`
+        let mut stmt: Vec<&str> = vec![];
+        let mut i = 0;
+        while i < toks.len() {
+            match toks[i] {
+                Tok::NewLine | Tok::Punct(';') => stmt.clear(),
+                Tok::Punct('{') => blocks.push(std::mem::take(&mut stmt)),
+                Tok::Punct('}') => {
+                    blocks.pop();
+                    stmt.clear();
+                }
+                Tok::Ident(kind @ ("include" | "includeFlat" | "includeBuild"))
+                    if stmt.is_empty() =>
+                {
+                    let (args, next) = statement_strings(&toks, i + 1);
+                    for a in args {
+                        included.push(match kind {
+                            "include" => project_path_to_dir(a),
+                            "includeFlat" => format!("../{}", a.trim_start_matches(':')),
+                            _ => a.to_owned(),
+                        });
+                    }
+                    i = next;
+                    continue;
+                }
+                Tok::Ident("project") => {
+                    if let Some((path, dir, next)) = project_dir(&toks, i) {
+                        project_dirs.insert(project_path_to_dir(path), dir.to_owned());
+                        i = next;
+                        continue;
+                    }
+                    stmt.push("project");
+                }
+                Tok::Ident(kind @ ("srcDir" | "srcDirs" | "setSrcDirs")) => {
+                    let context: Vec<&str> =
+                        blocks.iter().flatten().chain(&stmt).copied().collect();
+                    let set = context
+                        .iter()
+                        .rev()
+                        .find(|x| **x == "main" || **x == "test")
+                        .copied();
+                    let is_code = context.iter().any(|x| *x == "java" || *x == "kotlin");
+                    let (args, next) = statement_strings(&toks, i + 1);
+                    if context.contains(&"sourceSets") && is_code {
+                        let replaced = kind == "setSrcDirs"
+                            || (kind == "srcDirs" && toks.get(i + 1) == Some(&Tok::Punct('=')));
+                        let set = match set {
+                            Some("main") => Some(&mut main),
+                            Some("test") => Some(&mut test),
+                            _ => None,
+                        };
+                        if let Some(set) = set {
+                            set.replaced |= replaced;
+                            set.dirs.extend(args.into_iter().map(|x| x.to_owned()));
+                        }
+                    }
+                    i = next;
+                    continue;
+                }
+                Tok::Ident(x) | Tok::Str(x) => stmt.push(x),
+                Tok::Punct(_) => (),
+            }
+            i += 1;
+        }
+        let submodules = included
+            .into_iter()
+            .map(|x| project_dirs.remove(&x).unwrap_or(x))
+            .filter_map(|x| normalize_dir(&x))
+            .fold(vec![], |mut acc, x| {
+                if !acc.contains(&x) {
+                    acc.push(x);
+                }
+                acc
+            });
+        let normalize_all = |x: Vec<String>| {
+            x.iter()
+                .filter_map(|x| normalize_dir(x))
+                .collect::<Vec<_>>()
+        };
+        Self {
+            submodules,
+            source_dirs: main.finish(DEFAULT_SOURCE_DIR).map(normalize_all),
+            test_source_dirs: test.finish(DEFAULT_TEST_SOURCE_DIR).map(normalize_all),
+        }
+    }
+}
+
+/// Strings given as arguments to the statement starting at `i`,
+/// with or without parenthesis, possibly on multiple lines.
+fn statement_strings<'a>(toks: &[Tok<'a>], mut i: usize) -> (Vec<&'a str>, usize) {
+    let mut args = vec![];
+    let mut depth = 0;
+    let mut continued = true;
+    while i < toks.len() {
+        match toks[i] {
+            Tok::NewLine | Tok::Punct(';') if depth == 0 && !continued => break,
+            Tok::Punct('}') if depth == 0 => break,
+            Tok::Punct(')' | ']') if depth == 0 => break,
+            Tok::NewLine => (),
+            Tok::Punct('(' | '[') => {
+                depth += 1;
+                continued = true;
+            }
+            Tok::Punct(')' | ']') => {
+                depth -= 1;
+                continued = false;
+            }
+            Tok::Punct(',' | '=') => continued = true,
+            Tok::Str(x) => {
+                args.push(x);
+                continued = false;
+            }
+            // eg. `listOf(...)`, `files(...)`, `file(...)`
+            Tok::Ident(_) => continued = true,
+            Tok::Punct(_) => continued = false,
+        }
+        i += 1;
+    }
+    (args, i)
+}
+
+/// Matches `project(':a').projectDir = file('dir')` and its variants,
+/// giving the project path, its directory and the index following the statement.
+fn project_dir<'a>(toks: &[Tok<'a>], i: usize) -> Option<(&'a str, &'a str, usize)> {
+    let [
+        Tok::Ident("project"),
+        Tok::Punct('('),
+        Tok::Str(path),
+        Tok::Punct(')'),
+        Tok::Punct('.'),
+        Tok::Ident("projectDir"),
+        Tok::Punct('='),
+        ..,
+    ] = &toks[i..]
+    else {
+        return None;
+    };
+    let (args, next) = statement_strings(toks, i + 7);
+    // with `new File(settingsDir, 'dir')` the directory is the last argument
+    let dir = args.last()?;
+    Some((*path, *dir, next))
+}
+
+/// `:a:b` is located in `a/b` by default
+fn project_path_to_dir(path: &str) -> String {
+    path.trim_start_matches(':').replace(':', "/")
+}
+
+/// Makes a directory declared in a script relative to the project.
+///
+/// Directories outside of the project or depending on variables are ignored.
+fn normalize_dir(dir: &str) -> Option<String> {
+    let dir = dir.trim();
+    let dir = ["$projectDir/", "${projectDir}/", "$rootDir/", "${rootDir}/"]
+        .iter()
+        .find_map(|p| dir.strip_prefix(p))
+        .unwrap_or(dir);
+    let dir = dir.trim_start_matches("./").trim_end_matches('/');
+    if dir.is_empty()
+        || dir.starts_with('/')
+        || dir.contains('$')
+        || dir.split('/').any(|x| x == "..")
+    {
+        log::warn!("ignoring directory declared in gradle script: {}", dir);
+        return None;
+    }
+    Some(dir.to_owned())
+}
+
+#[derive(Debug, Clone)]
+pub struct MD {
+    pub(crate) metrics: DefaultMetrics,
+    pub(crate) status: EnumSet<SemFlag>,
+}
+
+#[derive(enumset::EnumSetType, Debug)]
+pub enum SemFlag {
+    IsGradleProject,
+    HoldMainFolder,
+    HoldTestFolder,
+    HoldGradleSubProject,
+}
+
+/// What a directory is given by its parent project:
+/// whether it is an included project, then the remaining paths of subprojects, main and test source directories.
+pub type Inherited = (bool, Vec<PathBuf>, Vec<PathBuf>, Vec<PathBuf>);
+
+pub struct GradleModuleAcc {
+    pub(crate) primary: BasicDirAcc<NodeIdentifier, LabelIdentifier, DefaultMetrics>,
+    pub(crate) sub_modules: Option<Vec<PathBuf>>,
+    pub(crate) main_dirs: Option<Vec<PathBuf>>,
+    pub(crate) test_dirs: Option<Vec<PathBuf>>,
+    /// part of the key of the cached directory
+    pub(crate) inherited: Inherited,
+    pub(crate) status: EnumSet<SemFlag>,
+}
+
+impl From<String> for GradleModuleAcc {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl GradleModuleAcc {
+    pub(crate) fn new(name: String) -> Self {
+        Self {
+            primary: BasicDirAcc::new(name),
+            sub_modules: None,
+            main_dirs: None,
+            test_dirs: None,
+            inherited: Default::default(),
+            status: Default::default(),
+        }
+    }
+    pub(crate) fn with_content(
+        name: String,
+        sub_modules: Vec<PathBuf>,
+        main_dirs: Vec<PathBuf>,
+        test_dirs: Vec<PathBuf>,
+    ) -> Self {
+        let non_empty = |x: Vec<PathBuf>| if x.is_empty() { None } else { Some(x) };
+        Self {
+            primary: BasicDirAcc::new(name),
+            sub_modules: non_empty(sub_modules),
+            main_dirs: non_empty(main_dirs),
+            test_dirs: non_empty(test_dirs),
+            inherited: Default::default(),
+            status: Default::default(),
+        }
+    }
+
+    /// Included projects do not need a build script,
+    /// so the conventional source sets are expected until a script says otherwise.
+    pub(crate) fn into_project(mut self) -> Self {
+        self.mark_project();
+        self
+    }
+
+    fn mark_project(&mut self) {
+        if self.status.contains(SemFlag::IsGradleProject) {
+            return;
+        }
+        self.status |= SemFlag::IsGradleProject;
+        self.main_dirs
+            .get_or_insert_with(Default::default)
+            .push(DEFAULT_SOURCE_DIR.into());
+        self.test_dirs
+            .get_or_insert_with(Default::default)
+            .push(DEFAULT_TEST_SOURCE_DIR.into());
+    }
+}
+
+impl GradleModuleAcc {
+    /// Scripts are consumed but not kept as children, see [`handle_gradle_file`].
+    pub(crate) fn push_script(&mut self, script: GradleScript) {
+        self.mark_project();
+        self.sub_modules
+            .get_or_insert_with(Default::default)
+            .extend(script.submodules.iter().map(|x| x.into()));
+        let replace = |dirs: &mut Option<Vec<PathBuf>>, declared: Option<Vec<String>>, default| {
+            let Some(declared) = declared else {
+                return;
+            };
+            let dirs = dirs.get_or_insert_with(Default::default);
+            dirs.retain(|x| x != &PathBuf::from(default));
+            dirs.extend(declared.into_iter().map(PathBuf::from));
+        };
+        replace(&mut self.main_dirs, script.source_dirs, DEFAULT_SOURCE_DIR);
+        replace(
+            &mut self.test_dirs,
+            script.test_source_dirs,
+            DEFAULT_TEST_SOURCE_DIR,
+        );
+    }
+    pub fn push_submodule(&mut self, name: LabelIdentifier, full_node: (NodeIdentifier, MD)) {
+        if full_node.1.status.contains(SemFlag::HoldGradleSubProject)
+            || full_node.1.status.contains(SemFlag::IsGradleProject)
+        {
+            self.status |= SemFlag::HoldGradleSubProject;
+        }
+        self.primary.children.push(full_node.0);
+        self.primary.children_names.push(name);
+        self.primary.metrics.acc(full_node.1.metrics);
+    }
+    pub(crate) fn push_source_directory(
+        &mut self,
+        name: LabelIdentifier,
+        full_node: java_tree_gen::Local,
+    ) {
+        self.status |= SemFlag::HoldMainFolder;
+        self.push_java_directory(name, full_node);
+    }
+    pub(crate) fn push_test_source_directory(
+        &mut self,
+        name: LabelIdentifier,
+        full_node: java_tree_gen::Local,
+    ) {
+        self.status |= SemFlag::HoldTestFolder;
+        self.push_java_directory(name, full_node);
+    }
+    fn push_java_directory(&mut self, name: LabelIdentifier, full_node: java_tree_gen::Local) {
+        self.primary.children.push(full_node.compressed_node);
+        self.primary.children_names.push(name);
+        self.primary.metrics.acc(SubTreeMetrics {
+            hashs: full_node.metrics.hashs,
+            size: full_node.metrics.size,
+            height: full_node.metrics.height,
+            size_no_spaces: full_node.metrics.size_no_spaces,
+            line_count: 0,
+        });
+    }
+}
+
+impl hyperast::tree_gen::Accumulator for GradleModuleAcc {
+    type Node = (LabelIdentifier, (NodeIdentifier, MD));
+    fn push(&mut self, (name, full_node): Self::Node) {
+        self.push_submodule(name, full_node);
+    }
+}
+
+impl Accumulator for GradleModuleAcc {
+    type Unlabeled = (NodeIdentifier, MD);
+}
@@ -0,0 +1,535 @@
+use crate::StackEle;
+use crate::processing::ParametrizedCommitProcessorHandle;
+use crate::processing::erased::{
+    CommitProcessorHandle, ParametrizedCommitProcessor2Handle as PCP2Handle,
+};
+use crate::{
+    Processor,
+    git::BasicGitObject,
+    gradle::{GradleModuleAcc, MD},
+    preprocessed::RepositoryProcessor,
+    processing::{CacheHolding, InFiles, ObjectName, erased::ParametrizedCommitProc2},
+};
+use git2::{Oid, Repository};
+use hyperast::store::nodes::compo;
+use hyperast::types::ETypeStore as _;
+use hyperast::{
+    hashed::{IndexingHashBuilder, MetaDataHashsBuilder},
+    store::{defaults::NodeIdentifier, nodes::EntityBuilder, nodes::legion::eq_node},
+    tree_gen::Accumulator,
+    types::LabelStore,
+};
+use hyperast_gen_ts_java::types::Type;
+use std::{
+    iter::Peekable,
+    path::{Components, PathBuf},
+};
+pub type SimpleStores = hyperast::store::SimpleStores<hyperast_gen_ts_java::types::TStore>;
+
+/// Explores gradle projects, starting from the root project.
+/// Subprojects are the ones included by a settings script,
+/// their source sets are given by their build script or follow the maven layout.
+/// Directories that are neither projects nor source sets, nor lead to one, are skipped.
+pub struct GradleProcessor<'a, 'b, 'c, Acc> {
+    prepro: &'b mut RepositoryProcessor,
+    repository: &'a Repository,
+    stack: Vec<StackEle<Acc>>,
+    dir_path: &'c mut Peekable<Components<'c>>,
+    handle: ParametrizedCommitProcessorHandle,
+}
+
+impl<'a, 'b, 'c> GradleProcessor<'a, 'b, 'c, GradleModuleAcc> {
+    pub fn new(
+        repository: &'a Repository,
+        prepro: &'b mut RepositoryProcessor,
+        mut dir_path: &'c mut Peekable<Components<'c>>,
+        name: &[u8],
+        oid: git2::Oid,
+        handle: ParametrizedCommitProcessorHandle,
+    ) -> Self {
+        let tree = repository.find_tree(oid).unwrap();
+        let prepared = prepare_dir_exploration(tree, &mut dir_path);
+        let name = std::str::from_utf8(&name).unwrap().to_string();
+        let acc = GradleModuleAcc::new(name);
+        let stack = vec![StackEle::new(oid, prepared, acc)];
+        Self {
+            stack,
+            repository,
+            prepro,
+            dir_path,
+            handle,
+        }
+    }
+}
+
+impl<'a, 'b, 'c> Processor<GradleModuleAcc> for GradleProcessor<'a, 'b, 'c, GradleModuleAcc> {
+    fn pre(&mut self, current_dir: BasicGitObject) {
+        match current_dir {
+            BasicGitObject::Tree(oid, name) => {
+                self.handle_tree_cached(name, oid);
+            }
+            BasicGitObject::Blob(oid, name)
+                if self.dir_path.peek().is_none()
+                    && crate::processing::file_sys::GradleScript::matches(&name) =>
+            {
+                let parent_acc = &mut self.stack.last_mut().unwrap().acc;
+                let parameters = PCP2Handle(self.handle.1, std::marker::PhantomData);
+                if let Err(err) = self.prepro.help_handle_gradle_script(
+                    oid,
+                    parent_acc,
+                    name,
+                    &self.repository,
+                    parameters,
+                ) {
+                    log::debug!("{:?}", err);
+                }
+            }
+            _ => {}
+        }
+    }
+    fn post(&mut self, oid: Oid, acc: GradleModuleAcc) -> Option<(NodeIdentifier, MD)> {
+        let key = (oid, acc.inherited.clone());
+        let name = acc.primary.name.clone();
+        let full_node = make(acc, self.prepro.main_stores_mut().mut_with_ts());
+        self.prepro
+            .processing_systems
+            .mut_or_default::<GradleProcessorHolder>()
+            .with_parameters_mut(self.handle.1)
+            .cache
+            .object_map
+            .insert(key, full_node.clone());
+        let name = self.prepro.intern_label(&name);
+        if self.stack.is_empty() {
+            Some(full_node)
+        } else {
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            assert!(
+                !w.primary.children_names.contains(&name),
+                "{:?} {:?}",
+                w.primary.children_names,
+                name
+            );
+            w.push((name, full_node));
+            None
+        }
+    }
+
+    fn stack(&mut self) -> &mut Vec<StackEle<GradleModuleAcc>> {
+        &mut self.stack
+    }
+}
+
+impl<'a, 'b, 'c> GradleProcessor<'a, 'b, 'c, GradleModuleAcc> {
+    fn handle_tree_cached(&mut self, name: ObjectName, oid: Oid) {
+        if let Some(s) = self.dir_path.peek() {
+            if name
+                .as_bytes()
+                .eq(std::ffi::OsStr::as_encoded_bytes(s.as_os_str()))
+            {
+                self.dir_path.next();
+                self.stack.last_mut().expect("never empty").cs.clear();
+                let tree = self.repository.find_tree(oid).unwrap();
+                let prepared = prepare_dir_exploration(tree, &mut self.dir_path);
+                let acc = GradleModuleAcc::new(name.try_into().unwrap());
+                self.stack.push(StackEle::new(oid, prepared, acc));
+            }
+            return;
+        }
+        let gradle_proc = self
+            .prepro
+            .processing_systems
+            .mut_or_default::<GradleProcessorHolder>()
+            .with_parameters_mut(self.handle.1);
+        let java_handle = gradle_proc.parameter.java_handle;
+        let parent_acc = &mut self.stack.last_mut().unwrap().acc;
+        let helper = GradleModuleHelper::from((parent_acc, &name));
+        if helper.source_directories.0 || helper.test_source_directories.0 {
+            // handle as source dir
+            let (name, (full_node,)) = self.prepro.help_handle_java_folder(
+                &self.repository,
+                self.dir_path,
+                oid,
+                &name,
+                java_handle,
+            );
+            let parent_acc = &mut self.stack.last_mut().unwrap().acc;
+            assert!(!parent_acc.primary.children_names.contains(&name));
+            if helper.source_directories.0 {
+                parent_acc.push_source_directory(name, full_node);
+            } else {
+                parent_acc.push_test_source_directory(name, full_node);
+            }
+        } else if helper.submodules.0
+            || !helper.submodules.1.is_empty()
+            || !helper.source_directories.1.is_empty()
+            || !helper.test_source_directories.1.is_empty()
+        {
+            let key = (oid, helper.inherited());
+            let gradle_proc = self
+                .prepro
+                .processing_systems
+                .mut_or_default::<GradleProcessorHolder>()
+                .with_parameters_mut(self.handle.1);
+            if let Some(already) = gradle_proc.cache.object_map.get(&key) {
+                // reinit already computed node for post order
+                let full_node = already.clone();
+                let w = &mut self.stack.last_mut().unwrap().acc;
+                let name = self.prepro.intern_object_name(&name);
+                assert!(!w.primary.children_names.contains(&name));
+                w.push_submodule(name, full_node);
+                return;
+            }
+            log::debug!("gradle tree {:?}", name.try_str());
+            let tree = self.repository.find_tree(oid).unwrap();
+            let prepared = prepare_dir_exploration(tree, &mut self.dir_path);
+            let mut acc = if helper.submodules.0 {
+                // handle as gradle subproject
+                helper.into_acc().into_project()
+            } else {
+                // search further inside
+                helper.into_acc()
+            };
+            acc.inherited = key.1;
+            self.stack.push(StackEle::new(oid, prepared, acc));
+        }
+    }
+}
+
+pub(crate) fn make(acc: GradleModuleAcc, stores: &mut SimpleStores) -> (NodeIdentifier, MD) {
+    let kind = Type::Directory;
+    let interned_kind = hyperast_gen_ts_java::types::TStore::intern(kind);
+    let label_id = stores.label_store.get_or_insert(acc.primary.name.clone());
+
+    let primary = acc
+        .primary
+        .map_metrics(|m| m.finalize(&interned_kind, &label_id, 0));
+
+    let hashable = primary.metrics.hashs.most_discriminating();
+
+    let eq = eq_node(&interned_kind, Some(&label_id), &primary.children);
+
+    let insertion = stores.node_store.prepare_insertion(&hashable, eq);
+    let status = acc.status;
+    if let Some(id) = insertion.occupied_id() {
+        let metrics = primary.metrics.map_hashs(|h| h.build());
+        return (id, MD { metrics, status });
+    }
+
+    log::info!("make gradle {} {}", &primary.name, primary.children.len());
+    assert_eq!(primary.children_names.len(), primary.children.len());
+    let mut dyn_builder = hyperast::store::nodes::legion::dyn_builder::EntityBuilder::with_lang(
+        hyperast_gen_ts_java::types::Lang,
+    );
+    let children_is_empty = primary.children.is_empty();
+    if !status.is_empty() {
+        dyn_builder.add(compo::Flags(status));
+    }
+    let metrics = primary.persist(&mut dyn_builder, interned_kind, label_id);
+    let metrics = metrics.map_hashs(|h| h.build());
+    let hashs = metrics.add_md_metrics(&mut dyn_builder, children_is_empty);
+    hashs.persist(&mut dyn_builder);
+
+    let vacant = insertion.vacant();
+    let node_id = hyperast::store::nodes::legion::NodeStore::insert_built_after_prepare(
+        vacant,
+        dyn_builder.build(),
+    );
+
+    (node_id, MD { metrics, status })
+}
+
+impl RepositoryProcessor {
+    fn help_handle_gradle_script(
+        &mut self,
+        oid: Oid,
+        parent_acc: &mut GradleModuleAcc,
+        name: ObjectName,
+        repository: &Repository,
+        parameters: PCP2Handle<GradleProc>,
+    ) -> Result<(), crate::ParseErr> {
+        let x = self
+            .processing_systems
+            .caching_blob_handler::<crate::processing::file_sys::GradleScript>()
+            .handle(oid, repository, &name, parameters, |_c, n, t| {
+                crate::gradle::handle_gradle_file(n, t).map_err(|_| crate::ParseErr::IllFormed)
+            })?;
+        parent_acc.push_script(x);
+        Ok(())
+    }
+}
+
+struct GradleModuleHelper {
+    name: String,
+    submodules: (bool, Vec<PathBuf>),
+    source_directories: (bool, Vec<PathBuf>),
+    test_source_directories: (bool, Vec<PathBuf>),
+}
+
+impl From<(&mut GradleModuleAcc, &ObjectName)> for GradleModuleHelper {
+    fn from((parent_acc, name): (&mut GradleModuleAcc, &ObjectName)) -> Self {
+        let process = |v: &mut Option<Vec<PathBuf>>| {
+            let mut v = drain_filter_strip(v, name.as_bytes());
+            let c = vec_extract_if_polyfill::MakeExtractIf::extract_if(&mut v, |x| {
+                x.components().next().is_none()
+            })
+            .count();
+            (c > 0, v)
+        };
+        Self {
+            name: name.try_into().unwrap(),
+            submodules: process(&mut parent_acc.sub_modules),
+            source_directories: process(&mut parent_acc.main_dirs),
+            test_source_directories: process(&mut parent_acc.test_dirs),
+        }
+    }
+}
+
+impl GradleModuleHelper {
+    fn inherited(&self) -> crate::gradle::Inherited {
+        (
+            self.submodules.0,
+            self.submodules.1.clone(),
+            self.source_directories.1.clone(),
+            self.test_source_directories.1.clone(),
+        )
+    }
+    fn into_acc(self) -> GradleModuleAcc {
+        GradleModuleAcc::with_content(
+            self.name,
+            self.submodules.1,
+            self.source_directories.1,
+            self.test_source_directories.1,
+        )
+    }
+}
+
+fn drain_filter_strip(v: &mut Option<Vec<PathBuf>>, name: &[u8]) -> Vec<PathBuf> {
+    let mut new_sub_modules = vec![];
+    let name = std::str::from_utf8(&name).unwrap();
+    if let Some(sub_modules) = v {
+        vec_extract_if_polyfill::MakeExtractIf::extract_if(sub_modules, |x| x.starts_with(name))
+            .for_each(|x| {
+                let x = x.strip_prefix(name).unwrap().to_owned();
+                new_sub_modules.push(x);
+            });
+    }
+    new_sub_modules
+}
+
+/// sometimes order of files/dirs can be important, similarly to order of statement
+/// exploration order for example
+pub(crate) fn prepare_dir_exploration(
+    tree: git2::Tree,
+    dir_path: &mut Peekable<Components>,
+) -> Vec<BasicGitObject> {
+    let mut children_objects: Vec<BasicGitObject> = tree
+        .iter()
+        .map(TryInto::try_into)
+        .filter_map(|x| x.ok())
+        .collect();
+    if dir_path.peek().is_none() {
+        // priority to gradle scripts processing
+        children_objects.sort_by_key(|x| match x {
+            BasicGitObject::Blob(_, n) => !crate::processing::file_sys::GradleScript::matches(n),
+            _ => true,
+        });
+        children_objects.reverse(); // we use it like a stack
+    }
+    children_objects
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub java_handle: PCP2Handle<crate::java_processor::JavaProc>,
+}
+
+#[derive(Default)]
+pub struct GradleProcessorHolder(Vec<GradleProc>);
+pub struct GradleProc {
+    parameter: Parameter,
+    cache: crate::processing::caches::Gradle,
+    scripts: crate::processing::caches::GradleScript,
+    commits: std::collections::HashMap<git2::Oid, crate::Commit>,
+}
+impl crate::processing::erased::Parametrized for GradleProcessorHolder {
+    type T = Parameter;
+    fn register_param(
+        &mut self,
+        t: Self::T,
+    ) -> crate::processing::erased::ParametrizedCommitProcessorHandle {
+        let l = self
+            .0
+            .iter()
+            .position(|x| &x.parameter == &t)
+            .unwrap_or_else(|| {
+                let l = self.0.len();
+                self.0.push(GradleProc {
+                    parameter: t,
+                    cache: Default::default(),
+                    scripts: Default::default(),
+                    commits: Default::default(),
+                });
+                l
+            });
+        use crate::processing::erased::ConfigParametersHandle;
+        use crate::processing::erased::ParametrizedCommitProc;
+        ParametrizedCommitProcessorHandle(self.erased_handle(), ConfigParametersHandle(l))
+    }
+}
+
+struct PreparedGradleCommitProc<'repo> {
+    repository: &'repo git2::Repository,
+    commit_builder: crate::preprocessed::CommitBuilder,
+    pub(crate) handle: ParametrizedCommitProcessorHandle,
+}
+
+impl<'repo> crate::processing::erased::PreparedCommitProc for PreparedGradleCommitProc<'repo> {
+    fn process(
+        self: Box<PreparedGradleCommitProc<'repo>>,
+        prepro: &mut RepositoryProcessor,
+    ) -> hyperast::store::defaults::NodeIdentifier {
+        let dir_path = PathBuf::from("");
+        let mut dir_path = dir_path.components().peekable();
+        let name = b"";
+        let root_full_node = GradleProcessor::<GradleModuleAcc>::new(
+            self.repository,
+            prepro,
+            &mut dir_path,
+            name,
+            self.commit_builder.tree_oid(),
+            self.handle,
+        )
+        .process();
+        let h = prepro
+            .processing_systems
+            .mut_or_default::<GradleProcessorHolder>();
+        let handle = self.handle;
+        let commit_oid = self.commit_builder.commit_oid();
+        let commit = self.commit_builder.finish(root_full_node.0);
+        h.with_parameters_mut(handle.1)
+            .commits
+            .insert(commit_oid, commit);
+        root_full_node.0
+    }
+}
+
+impl crate::processing::erased::CommitProc for GradleProc {
+    fn prepare_processing<'repo>(
+        &self,
+        repository: &'repo git2::Repository,
+        commit_builder: crate::preprocessed::CommitBuilder,
+        handle: crate::processing::ParametrizedCommitProcessorHandle,
+    ) -> Box<dyn crate::processing::erased::PreparedCommitProc + 'repo> {
+        Box::new(PreparedGradleCommitProc {
+            repository,
+            commit_builder,
+            handle,
+        })
+    }
+
+    fn get_commit(&self, commit_oid: git2::Oid) -> Option<&crate::Commit> {
+        self.commits.get(&commit_oid)
+    }
+
+    fn commit_count(&self) -> usize {
+        self.commits.len()
+    }
+
+    fn get_lang_handle(&self, lang: &str) -> Option<ParametrizedCommitProcessorHandle> {
+        if lang.eq_ignore_ascii_case("java") {
+            Some(ParametrizedCommitProcessorHandle(
+                CommitProcessorHandle(std::any::TypeId::of::<
+                    crate::java_processor::JavaProcessorHolder,
+                >()),
+                self.parameter.java_handle.0,
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl crate::processing::erased::CommitProcExt for GradleProc {
+    type Holder = GradleProcessorHolder;
+}
+impl crate::processing::erased::ParametrizedCommitProc2 for GradleProcessorHolder {
+    type Proc = GradleProc;
+
+    fn with_parameters_mut(
+        &mut self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &mut Self::Proc {
+        &mut self.0[parameters.0]
+    }
+
+    fn with_parameters(
+        &self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &Self::Proc {
+        &self.0[parameters.0]
+    }
+
+    fn persist(
+        &self,
+        _stores: &crate::SimpleStores,
+        mut w: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::write_u32;
+        write_u32(&mut w, self.0.len() as u32)?;
+        for proc in &self.0 {
+            crate::persist::write_commits(&mut w, &proc.commits)?;
+        }
+        Ok(())
+    }
+
+    fn restore(
+        &mut self,
+        _stores: &crate::SimpleStores,
+        mut r: &mut dyn std::io::Read,
+    ) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::read_u32_from;
+        let len = read_u32_from(&mut r)? as usize;
+        if len > self.0.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "more persisted processors than registered ones",
+            ));
+        }
+        for proc in &mut self.0[..len] {
+            proc.commits = crate::persist::read_commits(&mut r)?;
+        }
+        Ok(())
+    }
+
+    fn commit_roots(&self) -> Vec<(git2::Oid, hyperast::store::defaults::NodeIdentifier)> {
+        self.0
+            .iter()
+            .flat_map(|proc| proc.commits.iter().map(|(oid, c)| (*oid, c.ast_root)))
+            .collect()
+    }
+
+    fn retain_reachable(&mut self, reachable: &hyperast::store::nodes::legion::gc::Reachable) {
+        for proc in &mut self.0 {
+            proc.commits.retain(|_, c| reachable.contains(&c.ast_root));
+            proc.cache.retain_reachable(reachable);
+        }
+    }
+}
+
+impl CacheHolding<crate::processing::caches::Gradle> for GradleProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::Gradle {
+        &mut self.cache
+    }
+    fn get_caches(&self) -> &crate::processing::caches::Gradle {
+        &self.cache
+    }
+}
+
+impl CacheHolding<crate::processing::caches::GradleScript> for GradleProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::GradleScript {
+        &mut self.scripts
+    }
+    fn get_caches(&self) -> &crate::processing::caches::GradleScript {
+        &self.scripts
+    }
+}
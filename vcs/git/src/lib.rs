@@ -1,13 +1,39 @@
 #[cfg(feature = "impact")]
 pub mod allrefs;
+#[cfg(feature = "any")]
+pub mod any;
+#[cfg(feature = "cargo")]
+pub mod cargo;
+#[cfg(feature = "cmake")]
+pub mod cmake;
 pub mod cpp;
 pub mod git;
+#[cfg(feature = "gradle")]
+pub mod gradle;
 pub mod java;
 pub mod make;
 pub mod maven;
+#[cfg(feature = "npm")]
+pub mod npm;
+#[cfg(feature = "pip")]
+pub mod pip;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "rust")]
+pub mod rust;
+#[cfg(feature = "ts")]
+pub mod ts;
 
+#[cfg(feature = "any")]
+pub mod any_processor;
+#[cfg(feature = "cargo")]
+pub mod cargo_processor;
+#[cfg(feature = "cmake")]
+pub mod cmake_processor;
 #[cfg(feature = "cpp")]
 pub mod cpp_processor;
+#[cfg(feature = "gradle")]
+pub mod gradle_processor;
 #[cfg(feature = "java")]
 pub mod java_processor;
 #[cfg(feature = "make")]
@@ -17,9 +43,20 @@ pub mod maven_processor;
 pub mod multi_preprocessed;
 pub mod no_space;
 pub mod no_space2;
+#[cfg(feature = "npm")]
+pub mod npm_processor;
+pub mod persist;
+#[cfg(feature = "pip")]
+pub mod pip_processor;
 /// for now only tested on maven repositories with a pom in root.
 pub mod preprocessed;
 pub mod processing;
+#[cfg(feature = "python")]
+pub mod python_processor;
+#[cfg(feature = "rust")]
+pub mod rust_processor;
+#[cfg(feature = "ts")]
+pub mod ts_processor;
 mod utils;
 
 #[cfg(test)]
@@ -143,10 +180,40 @@ fn ts_lang_java() -> Option<tree_sitter::Language> {
     None
 }
 
+#[cfg(feature = "ts")]
+fn ts_lang_ts() -> Option<tree_sitter::Language> {
+    Some(hyperast_gen_ts_ts::language())
+}
+#[cfg(not(feature = "ts"))]
+fn ts_lang_ts() -> Option<tree_sitter::Language> {
+    None
+}
+
+#[cfg(feature = "python")]
+fn ts_lang_python() -> Option<tree_sitter::Language> {
+    Some(hyperast_gen_ts_python::language())
+}
+#[cfg(not(feature = "python"))]
+fn ts_lang_python() -> Option<tree_sitter::Language> {
+    None
+}
+
+#[cfg(feature = "rust")]
+fn ts_lang_rust() -> Option<tree_sitter::Language> {
+    Some(hyperast_gen_ts_rust::language())
+}
+#[cfg(not(feature = "rust"))]
+fn ts_lang_rust() -> Option<tree_sitter::Language> {
+    None
+}
+
 pub fn resolve_language(language: &str) -> Option<tree_sitter::Language> {
     match language {
         "Java" | "java" => ts_lang_java(),
         "Cpp" | "cpp" => ts_lang_cpp(),
+        "Ts" | "ts" | "typescript" | "javascript" => ts_lang_ts(),
+        "Python" | "python" | "py" => ts_lang_python(),
+        "Rust" | "rust" | "rs" => ts_lang_rust(),
         _ => None,
     }
 }
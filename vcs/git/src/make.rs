@@ -1,11 +1,18 @@
-use std::{fmt::Debug, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    path::{Component, Path, PathBuf},
+};
 
 use hyperast::{
     store::defaults::{LabelIdentifier, NodeIdentifier},
     tree_gen::SubTreeMetrics,
 };
 use hyperast_gen_ts_cpp::legion as cpp_tree_gen;
-use hyperast_gen_ts_xml::{legion::XmlTreeGen, types::TStore};
+use hyperast_gen_ts_make::{
+    legion::{MakeTreeGen, tree_sitter_parse_make},
+    types::TStore,
+};
 
 use crate::{
     Accumulator, BasicDirAcc, DefaultMetrics, PROPAGATE_ERROR_ON_BAD_CST_NODE,
@@ -13,13 +20,11 @@ use crate::{
 };
 
 pub(crate) fn handle_makefile_file<'a>(
-    tree_gen: &mut XmlTreeGen<'a, TStore>,
+    tree_gen: &mut MakeTreeGen<'a, '_, TStore>,
     name: &ObjectName,
     text: &'a [u8],
 ) -> Result<MakeFile, ()> {
-    log::trace!("not parsing {} bytes long Makefile", text.len()); // TODO parse the makefile
-    let text = b"<proj></proj>";
-    let tree = match hyperast_gen_ts_xml::legion::tree_sitter_parse_xml(text) {
+    let tree = match tree_sitter_parse_make(text) {
         Ok(tree) => tree,
         Err(tree) => {
             log::warn!("bad CST");
@@ -32,16 +37,17 @@ pub(crate) fn handle_makefile_file<'a>(
             }
         }
     };
+    let layout = MakefileLayout::extract(text, &tree);
     let x = tree_gen
         .generate_file(name.as_bytes(), text, tree.walk())
         .local;
-    // TODO extract submodules, dependencies and directories. maybe even more ie. artefact id, ...
     let x = MakeFile {
         compressed_node: x.compressed_node,
         metrics: x.metrics,
-        submodules: vec![],
-        source_dirs: vec![".".to_owned()],
-        test_source_dirs: vec!["../tests".to_owned()],
+        submodules: layout.submodules,
+        source_dirs: layout.source_dirs,
+        test_source_dirs: layout.test_source_dirs,
+        include_dirs: layout.include_dirs,
     };
     Ok(x)
 }
@@ -53,6 +59,284 @@ pub struct MakeFile {
     submodules: Vec<String>,
     source_dirs: Vec<String>,
     test_source_dirs: Vec<String>,
+    include_dirs: Vec<String>,
+}
+
+/// Directories referenced by a Makefile, relative to the directory containing it.
+///
+/// Variables are expanded when they are simply defined in the same Makefile,
+/// paths still containing references after that, or going up the hierarchy, are ignored.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct MakefileLayout {
+    /// directories built by a sub-make, eg. `$(MAKE) -C lib`
+    pub(crate) submodules: Vec<String>,
+    /// directories of the sources mentioned in variables, prerequisites and `vpath`s
+    pub(crate) source_dirs: Vec<String>,
+    /// source directories that are only about tests
+    pub(crate) test_source_dirs: Vec<String>,
+    /// directories given to the preprocessor with `-I`
+    pub(crate) include_dirs: Vec<String>,
+}
+
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "c", "cc", "cpp", "cxx", "c++", "h", "hh", "hpp", "hxx", "h++", "inl", "ipp", "tpp",
+];
+
+/// Variables conventionally listing the directories of sub-makes
+const SUBDIRS_VARIABLES: &[&str] = &["SUBDIRS", "SUBDIR", "DIRS", "MODULES"];
+
+impl MakefileLayout {
+    pub(crate) fn extract(text: &[u8], tree: &tree_sitter::Tree) -> Self {
+        let mut collector = Collector::default();
+        collector.visit(text, tree.root_node());
+        let Collector {
+            variables,
+            prerequisites,
+            recipes,
+            vpaths,
+        } = collector;
+        let expand = |s: &str| expand_variables(s, &variables, 0);
+
+        let mut layout = MakefileLayout::default();
+        let mut source_dirs = vec![];
+        for value in variables.values() {
+            let value = expand(value);
+            let words = words(&value);
+            layout.include_dirs.extend(include_flags(&words));
+            source_dirs.extend(words.iter().filter_map(|w| source_dir(w)));
+        }
+        for prerequisite in &prerequisites {
+            let prerequisite = expand(prerequisite);
+            source_dirs.extend(words(&prerequisite).iter().filter_map(|w| source_dir(w)));
+        }
+        for vpath in &vpaths {
+            let vpath = expand(vpath);
+            let dirs = vpath.split(|c: char| c == ':' || c.is_whitespace());
+            source_dirs.extend(dirs.filter_map(normalize_dir));
+        }
+        for recipe in &recipes {
+            let is_sub_make = recipe.contains("$(MAKE)")
+                || recipe.contains("${MAKE}")
+                || words(recipe).contains(&"make");
+            let recipe = expand(recipe);
+            let words = words(&recipe);
+            layout.include_dirs.extend(include_flags(&words));
+            if is_sub_make {
+                layout.submodules.extend(directory_flags(&words));
+            }
+        }
+        for var in SUBDIRS_VARIABLES {
+            if let Some(value) = variables.get(*var) {
+                let value = expand(value);
+                let dirs = words(&value).into_iter().filter_map(normalize_dir);
+                layout.submodules.extend(dirs);
+            }
+        }
+        for dir in source_dirs {
+            if is_test_dir(&dir) {
+                layout.test_source_dirs.push(dir);
+            } else {
+                layout.source_dirs.push(dir);
+            }
+        }
+        dedup(&mut layout.submodules);
+        dedup(&mut layout.source_dirs);
+        dedup(&mut layout.test_source_dirs);
+        dedup(&mut layout.include_dirs);
+        layout
+    }
+}
+
+#[derive(Default)]
+struct Collector {
+    variables: BTreeMap<String, String>,
+    prerequisites: Vec<String>,
+    recipes: Vec<String>,
+    vpaths: Vec<String>,
+}
+
+impl Collector {
+    fn visit(&mut self, text: &[u8], node: tree_sitter::Node) {
+        let text_of = |n: tree_sitter::Node| {
+            std::str::from_utf8(&text[n.byte_range()])
+                .unwrap_or_default()
+                .to_string()
+        };
+        match node.kind() {
+            "variable_assignment" => {
+                let (Some(name), value) = (
+                    node.child_by_field_name("name"),
+                    node.child_by_field_name("value"),
+                ) else {
+                    return;
+                };
+                let name = text_of(name).trim().to_string();
+                let value = value.map(text_of).unwrap_or_default();
+                if name == "VPATH" {
+                    self.vpaths.push(value.clone());
+                }
+                let op = node.child_by_field_name("operator").map(text_of);
+                match (op.as_deref(), self.variables.get_mut(&name)) {
+                    (Some("+="), Some(v)) => {
+                        v.push(' ');
+                        v.push_str(&value);
+                    }
+                    (Some("?="), Some(_)) => (),
+                    _ => {
+                        self.variables.insert(name, value);
+                    }
+                }
+                return;
+            }
+            "prerequisites" => {
+                self.prerequisites.push(text_of(node));
+                return;
+            }
+            "recipe_line" => {
+                self.recipes.push(text_of(node));
+                return;
+            }
+            "vpath_directive" => {
+                // vpath pattern directories
+                let mut cursor = node.walk();
+                let dirs = node.named_children(&mut cursor).skip(1).map(text_of);
+                self.vpaths.extend(dirs);
+                return;
+            }
+            "define_directive" => return,
+            _ => (),
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.visit(text, child);
+        }
+    }
+}
+
+/// Expands the references to the variables defined in the Makefile, eg. `$(SRC_DIR)` or `${SRC_DIR}`.
+fn expand_variables(s: &str, variables: &BTreeMap<String, String>, depth: usize) -> String {
+    const MAX_DEPTH: usize = 8;
+    if depth > MAX_DEPTH || !s.contains('$') {
+        return s.to_string();
+    }
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('$') {
+        result.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let close = match rest[1..].chars().next() {
+            Some('(') => ')',
+            Some('{') => '}',
+            _ => {
+                let l = rest[1..].chars().next().map_or(0, |c| c.len_utf8());
+                result.push_str(&rest[..1 + l]);
+                rest = &rest[1 + l..];
+                continue;
+            }
+        };
+        let Some(end) = rest.find(close) else {
+            result.push_str(rest);
+            return result;
+        };
+        let name = &rest[2..end];
+        let is_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-'));
+        match variables.get(name) {
+            Some(value) if is_name => {
+                result.push_str(&expand_variables(value, variables, depth + 1));
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                // might be a function call, its arguments can still contain references
+                result.push_str(&rest[..2]);
+                rest = &rest[2..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn words(s: &str) -> Vec<&str> {
+    s.split(|c: char| c.is_whitespace() || c == '\\' || c == ';')
+        // what remains of function calls such as `$(wildcard src/*.c)`
+        .map(|w| w.trim_start_matches('(').trim_end_matches(')'))
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// Extracts the directories of `-I dir` and `-Idir` flags
+fn include_flags(words: &[&str]) -> Vec<String> {
+    flag_values(words, "-I", None)
+}
+
+/// Extracts the directories of `-C dir`, `-Cdir` and `--directory=dir` flags
+fn directory_flags(words: &[&str]) -> Vec<String> {
+    flag_values(words, "-C", Some("--directory="))
+}
+
+fn flag_values(words: &[&str], short: &str, long: Option<&str>) -> Vec<String> {
+    let mut result = vec![];
+    let mut it = words.iter();
+    while let Some(w) = it.next() {
+        let value = if *w == short {
+            it.next().copied()
+        } else if let Some(v) = w.strip_prefix(short) {
+            Some(v)
+        } else {
+            long.and_then(|long| w.strip_prefix(long))
+        };
+        result.extend(value.and_then(normalize_dir));
+    }
+    result
+}
+
+/// Returns the directory of a word if it looks like a C/C++ source file, eg. `src/%.c` gives `src`
+fn source_dir(word: &str) -> Option<String> {
+    let ext = Path::new(word).extension()?.to_str()?;
+    if !SOURCE_EXTENSIONS.contains(&ext) {
+        return None;
+    }
+    normalize_dir(Path::new(word).parent()?.to_str()?)
+}
+
+/// Keeps the part of a path that does not contain patterns, ignoring paths leaving the directory.
+fn normalize_dir(dir: &str) -> Option<String> {
+    if dir.contains('$') {
+        // still contains references, eg. to automatic variables
+        return None;
+    }
+    let mut result = PathBuf::new();
+    for c in Path::new(dir).components() {
+        match c {
+            Component::CurDir => (),
+            Component::Normal(c) => {
+                let c = c.to_str()?;
+                if c.contains(['*', '?', '[', '%']) {
+                    break;
+                }
+                result.push(c);
+            }
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    let result = result.to_str()?;
+    (!result.is_empty()).then(|| result.to_string())
+}
+
+fn is_test_dir(dir: &str) -> bool {
+    Path::new(dir).components().any(|c| {
+        c.as_os_str()
+            .to_str()
+            .is_some_and(|c| c.starts_with("test"))
+    })
+}
+
+fn dedup(v: &mut Vec<String>) {
+    let mut seen = std::collections::HashSet::new();
+    v.retain(|x| seen.insert(x.clone()));
 }
 
 #[derive(Debug, Clone)]
@@ -65,6 +349,7 @@ pub struct MakeModuleAcc {
     pub(crate) sub_modules: Option<Vec<PathBuf>>,
     pub(crate) main_dirs: Option<Vec<PathBuf>>,
     pub(crate) test_dirs: Option<Vec<PathBuf>>,
+    pub(crate) include_dirs: Option<Vec<PathBuf>>,
 }
 
 impl From<String> for MakeModuleAcc {
@@ -74,6 +359,7 @@ impl From<String> for MakeModuleAcc {
             sub_modules: None,
             main_dirs: None,
             test_dirs: None,
+            include_dirs: None,
         }
     }
 }
@@ -85,6 +371,7 @@ impl MakeModuleAcc {
             sub_modules: None,
             main_dirs: None,
             test_dirs: None,
+            include_dirs: None,
         }
     }
     pub(crate) fn with_content(
@@ -92,6 +379,7 @@ impl MakeModuleAcc {
         sub_modules: Vec<PathBuf>,
         main_dirs: Vec<PathBuf>,
         test_dirs: Vec<PathBuf>,
+        include_dirs: Vec<PathBuf>,
     ) -> Self {
         Self {
             primary: BasicDirAcc::new(name),
@@ -110,6 +398,11 @@ impl MakeModuleAcc {
             } else {
                 Some(test_dirs)
             },
+            include_dirs: if include_dirs.is_empty() {
+                None
+            } else {
+                Some(include_dirs)
+            },
         }
     }
 }
@@ -118,15 +411,15 @@ impl MakeModuleAcc {
     pub(crate) fn push_makefile(&mut self, name: LabelIdentifier, full_node: MakeFile) {
         self.primary.children.push(full_node.compressed_node);
         self.primary.children_names.push(name);
-        self.main_dirs = Some(full_node.source_dirs.iter().map(|x| x.into()).collect());
-        self.test_dirs = Some(
-            full_node
-                .test_source_dirs
-                .iter()
-                .map(|x| x.into())
-                .collect(),
-        );
-        self.sub_modules = Some(full_node.submodules.iter().map(|x| x.into()).collect());
+        // extends what the Makefiles of parent directories already referenced in this one
+        let extend = |dirs: &mut Option<Vec<PathBuf>>, v: &[String]| {
+            dirs.get_or_insert_default()
+                .extend(v.iter().map(PathBuf::from))
+        };
+        extend(&mut self.main_dirs, &full_node.source_dirs);
+        extend(&mut self.test_dirs, &full_node.test_source_dirs);
+        extend(&mut self.sub_modules, &full_node.submodules);
+        extend(&mut self.include_dirs, &full_node.include_dirs);
         self.primary.metrics.acc(full_node.metrics);
     }
     pub fn push_submodule(&mut self, name: LabelIdentifier, full_node: (NodeIdentifier, MD)) {
@@ -19,6 +19,7 @@ use hyperast::{
     store::{defaults::NodeIdentifier, nodes::legion::eq_node},
     types::LabelStore,
 };
+use hyperast_gen_ts_make::legion::MakeTreeGen;
 use hyperast_gen_ts_xml::types::Type;
 use std::{
     iter::Peekable,
@@ -183,21 +184,16 @@ impl<'a, 'b, 'c, const RMS: bool, const FFWD: bool>
         }
         log::debug!("make tree {:?}", name.try_str());
         let parent_acc = &mut self.stack.last_mut().unwrap().acc;
-        if true {
-            // TODO also try to handle nested Makefiles
-            let (name, (full_node,)) = self.prepro.help_handle_cpp_folder(
-                &self.repository,
-                &mut self.dir_path,
-                oid,
-                &name,
-                cpp_handle,
-            );
-            assert!(!parent_acc.primary.children_names.contains(&name));
-            parent_acc.push_source_directory(name, full_node);
-            return;
-        }
         let helper = MakeModuleHelper::from((parent_acc, &name));
-        if helper.source_directories.0 || helper.test_source_directories.0 {
+        if helper.submodules.0 {
+            // handle as Make module, its own Makefile is processed first
+            let tree = self.repository.find_tree(oid).unwrap();
+            let prepared = prepare_dir_exploration(tree, &mut self.dir_path);
+            self.stack.push(StackEle::new(oid, prepared, helper.into()));
+        } else if helper.source_directories.0
+            || helper.test_source_directories.0
+            || helper.include_directories.0
+        {
             // handle as source dir
             let (name, (full_node,)) = self.prepro.help_handle_cpp_folder(
                 &self.repository,
@@ -208,37 +204,35 @@ impl<'a, 'b, 'c, const RMS: bool, const FFWD: bool>
             );
             let parent_acc = &mut self.stack.last_mut().unwrap().acc;
             assert!(!parent_acc.primary.children_names.contains(&name));
-            if helper.source_directories.0 {
-                parent_acc.push_source_directory(name, full_node);
-            } else {
-                // test_source_folders.0
+            if helper.test_source_directories.0 && !helper.source_directories.0 {
                 parent_acc.push_test_source_directory(name, full_node);
+            } else {
+                parent_acc.push_source_directory(name, full_node);
             }
-        }
-        // check if module or src/main/java or src/test/java
-        // TODO use Make pom.xml to find source_dir  and tests_dir ie. ignore resources, maybe also tests
-        // TODO maybe at some point try to handle Make modules and source dirs that reference parent directory in their path
-
-        // TODO check it we can use more info from context and prepare analysis more specifically
-        if helper.submodules.0
-            || !helper.submodules.1.is_empty()
+        } else if !helper.submodules.1.is_empty()
             || !helper.source_directories.1.is_empty()
             || !helper.test_source_directories.1.is_empty()
+            || !helper.include_directories.1.is_empty()
+            || RMS
         {
+            // search further inside,
+            // with RMS also try to find Make modules not referenced by the parent Makefile
             let tree = self.repository.find_tree(oid).unwrap();
             let prepared = prepare_dir_exploration(tree, &mut self.dir_path);
-            if helper.submodules.0 {
-                // handle as Make module
-                self.stack.push(StackEle::new(oid, prepared, helper.into()));
-            } else {
-                // search further inside
-                self.stack.push(StackEle::new(oid, prepared, helper.into()));
-            };
-        } else if RMS && !(helper.source_directories.0 || helper.test_source_directories.0) {
-            let tree = self.repository.find_tree(oid).unwrap();
-            // anyway try to find Make modules, but maybe can do better
-            let prepared = prepare_dir_exploration(tree, &mut self.dir_path);
             self.stack.push(StackEle::new(oid, prepared, helper.into()));
+        } else {
+            // not referenced by the Makefile, but keep its content as C/C++ code
+            // TODO maybe at some point try to handle Make modules and source dirs that reference parent directory in their path
+            let (name, (full_node,)) = self.prepro.help_handle_cpp_folder(
+                &self.repository,
+                self.dir_path,
+                oid,
+                &name,
+                cpp_handle,
+            );
+            let parent_acc = &mut self.stack.last_mut().unwrap().acc;
+            assert!(!parent_acc.primary.children_names.contains(&name));
+            parent_acc.push_source_directory(name, full_node);
         }
     }
 }
@@ -289,7 +283,6 @@ pub(crate) fn make(acc: MakeModuleAcc, stores: &mut SimpleStores) -> (NodeIdenti
     full_node
 }
 
-use hyperast_gen_ts_xml::legion::XmlTreeGen;
 impl RepositoryProcessor {
     fn help_handle_makefile(
         &mut self,
@@ -302,16 +295,17 @@ impl RepositoryProcessor {
         let x = self
             .processing_systems
             .caching_blob_handler::<crate::processing::file_sys::MakeFile>()
-            .handle(oid, repository, &name, parameters, |_c, n, t| {
-                crate::make::handle_makefile_file(
-                    &mut XmlTreeGen {
-                        line_break: "\n".as_bytes().to_vec(),
-                        stores: self.main_stores.mut_with_ts(),
-                    },
-                    n,
-                    t,
-                )
-                .map_err(|_| crate::ParseErr::IllFormed)
+            .handle(oid, repository, &name, parameters, |c, n, t| {
+                let md_cache = &mut c
+                    .mut_or_default::<MakefileProcessorHolder>()
+                    .with_parameters_mut(parameters.0)
+                    .get_caches_mut()
+                    .md_cache;
+                let stores = self
+                    .main_stores
+                    .mut_with_ts::<hyperast_gen_ts_make::types::TStore>();
+                crate::make::handle_makefile_file(&mut MakeTreeGen::new(stores, md_cache), n, t)
+                    .map_err(|_| crate::ParseErr::IllFormed)
             })?;
         let name = self.intern_object_name(&name);
         assert!(!parent_acc.primary.children_names.contains(&name));
@@ -325,6 +319,7 @@ struct MakeModuleHelper {
     submodules: (bool, Vec<PathBuf>),
     source_directories: (bool, Vec<PathBuf>),
     test_source_directories: (bool, Vec<PathBuf>),
+    include_directories: (bool, Vec<PathBuf>),
 }
 
 impl From<(&mut MakeModuleAcc, &ObjectName)> for MakeModuleHelper {
@@ -342,6 +337,7 @@ impl From<(&mut MakeModuleAcc, &ObjectName)> for MakeModuleHelper {
             submodules: process(&mut parent_acc.sub_modules),
             source_directories: process(&mut parent_acc.main_dirs),
             test_source_directories: process(&mut parent_acc.test_dirs),
+            include_directories: process(&mut parent_acc.include_dirs),
         }
     }
 }
@@ -353,6 +349,7 @@ impl From<MakeModuleHelper> for MakeModuleAcc {
             helper.submodules.1,
             helper.source_directories.1,
             helper.test_source_directories.1,
+            helper.include_directories.1,
         )
     }
 }
@@ -523,6 +520,12 @@ impl crate::processing::erased::ParametrizedCommitProc2 for MakefileProcessorHol
         assert_eq!(0, parameters.0);
         self.0.as_ref().unwrap()
     }
+
+    fn retain_reachable(&mut self, reachable: &hyperast::store::nodes::legion::gc::Reachable) {
+        for proc in &mut self.0 {
+            proc.1.retain_reachable(reachable);
+        }
+    }
 }
 impl CacheHolding<crate::processing::caches::Makefile> for MakefileProc {
     fn get_caches_mut(&mut self) -> &mut crate::processing::caches::Makefile {
@@ -676,6 +679,42 @@ impl crate::processing::erased::ParametrizedCommitProc2 for MakeProcessorHolder
         assert_eq!(0, parameters.0);
         self.0.as_ref().unwrap()
     }
+
+    fn persist(
+        &self,
+        _stores: &crate::SimpleStores,
+        mut w: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        if let Some(proc) = &self.0 {
+            crate::persist::write_commits(&mut w, &proc.commits)?;
+        }
+        Ok(())
+    }
+
+    fn restore(
+        &mut self,
+        _stores: &crate::SimpleStores,
+        mut r: &mut dyn std::io::Read,
+    ) -> std::io::Result<()> {
+        if let Some(proc) = &mut self.0 {
+            proc.commits = crate::persist::read_commits(&mut r)?;
+        }
+        Ok(())
+    }
+
+    fn commit_roots(&self) -> Vec<(git2::Oid, hyperast::store::defaults::NodeIdentifier)> {
+        self.0
+            .iter()
+            .flat_map(|proc| proc.commits.iter().map(|(oid, c)| (*oid, c.ast_root)))
+            .collect()
+    }
+
+    fn retain_reachable(&mut self, reachable: &hyperast::store::nodes::legion::gc::Reachable) {
+        for proc in &mut self.0 {
+            proc.commits.retain(|_, c| reachable.contains(&c.ast_root));
+            proc.cache.retain_reachable(reachable);
+        }
+    }
 }
 
 impl CacheHolding<crate::processing::caches::Make> for MakeProc {
@@ -41,13 +41,14 @@ pub(crate) fn handle_pom_file<'a>(
     let x = tree_gen
         .generate_file(name.as_bytes(), text, tree.walk())
         .local;
-    // TODO extract submodules, dependencies and directories. maybe even more ie. artefact id, ...
+    let content = PomReader::new(tree_gen.stores).read(x.compressed_node);
     let x = POM {
         compressed_node: x.compressed_node,
         metrics: x.metrics,
-        submodules: vec![],
-        source_dirs: vec!["src/main/java".to_owned()],
-        test_source_dirs: vec!["src/test/java".to_owned()],
+        submodules: content.submodules,
+        source_dirs: content.source_dirs,
+        test_source_dirs: content.test_source_dirs,
+        module: content.module,
     };
     Ok(x)
 }
@@ -56,9 +57,398 @@ pub(crate) fn handle_pom_file<'a>(
 pub struct POM {
     pub compressed_node: NodeIdentifier,
     pub metrics: DefaultMetrics,
+    pub(crate) submodules: Vec<String>,
+    pub(crate) source_dirs: Vec<String>,
+    pub(crate) test_source_dirs: Vec<String>,
+    pub module: MavenModule,
+}
+
+/// What a pom.xml declares about its module, see [`MavenModuleGraph`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct MavenModule {
+    pub group_id: Option<String>,
+    pub artifact_id: Option<String>,
+    pub version: Option<String>,
+    /// content of the `<module>` elements, as written in the pom
+    pub modules: Vec<String>,
+    pub dependencies: Vec<Dependency>,
+}
+
+impl MavenModule {
+    /// `groupId:artifactId`, the key used to resolve dependencies between modules of a same repository
+    pub fn coordinates(&self) -> Option<String> {
+        Some(format!(
+            "{}:{}",
+            self.group_id.as_ref()?,
+            self.artifact_id.as_ref()?
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct Dependency {
+    pub group_id: Option<String>,
+    pub artifact_id: Option<String>,
+    pub version: Option<String>,
+    pub scope: Option<String>,
+}
+
+impl Dependency {
+    pub fn coordinates(&self) -> Option<String> {
+        Some(format!(
+            "{}:{}",
+            self.group_id.as_ref()?,
+            self.artifact_id.as_ref()?
+        ))
+    }
+}
+
+/// Layout persisted in snapshots of the maven processor, see [`crate::persist`].
+impl MavenModule {
+    pub(crate) fn save(&self, w: &mut impl std::io::Write) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::write_u32;
+        write_opt_string(w, &self.group_id)?;
+        write_opt_string(w, &self.artifact_id)?;
+        write_opt_string(w, &self.version)?;
+        write_u32(w, self.modules.len() as u32)?;
+        for m in &self.modules {
+            write_string(w, m)?;
+        }
+        write_u32(w, self.dependencies.len() as u32)?;
+        for d in &self.dependencies {
+            write_opt_string(w, &d.group_id)?;
+            write_opt_string(w, &d.artifact_id)?;
+            write_opt_string(w, &d.version)?;
+            write_opt_string(w, &d.scope)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn load(r: &mut impl std::io::Read) -> std::io::Result<Self> {
+        use hyperast::store::nodes::legion::persist::read_u32_from;
+        let group_id = read_opt_string(r)?;
+        let artifact_id = read_opt_string(r)?;
+        let version = read_opt_string(r)?;
+        let modules = (0..read_u32_from(r)?)
+            .map(|_| read_string(r))
+            .collect::<Result<_, _>>()?;
+        let dependencies = (0..read_u32_from(r)?)
+            .map(|_| {
+                Ok(Dependency {
+                    group_id: read_opt_string(r)?,
+                    artifact_id: read_opt_string(r)?,
+                    version: read_opt_string(r)?,
+                    scope: read_opt_string(r)?,
+                })
+            })
+            .collect::<std::io::Result<_>>()?;
+        Ok(Self {
+            group_id,
+            artifact_id,
+            version,
+            modules,
+            dependencies,
+        })
+    }
+}
+
+fn write_string(w: &mut impl std::io::Write, s: &str) -> std::io::Result<()> {
+    hyperast::store::nodes::legion::persist::write_bytes(w, s.as_bytes())
+}
+
+fn read_string(r: &mut impl std::io::Read) -> std::io::Result<String> {
+    let b = hyperast::store::nodes::legion::persist::read_bytes(r)?;
+    String::from_utf8(b).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+fn write_opt_string(w: &mut impl std::io::Write, s: &Option<String>) -> std::io::Result<()> {
+    w.write_all(&[s.is_some() as u8])?;
+    s.as_ref().map_or(Ok(()), |s| write_string(w, s))
+}
+
+fn read_opt_string(r: &mut impl std::io::Read) -> std::io::Result<Option<String>> {
+    let mut b = [0; 1];
+    r.read_exact(&mut b)?;
+    (b[0] != 0).then(|| read_string(r)).transpose()
+}
+
+const DEFAULT_SOURCE_DIR: &str = "src/main/java";
+const DEFAULT_TEST_SOURCE_DIR: &str = "src/test/java";
+
+struct PomContent {
     submodules: Vec<String>,
     source_dirs: Vec<String>,
     test_source_dirs: Vec<String>,
+    module: MavenModule,
+}
+
+/// Reads the elements of a pom.xml directly from its HyperAST.
+///
+/// Only element names and their character data are considered,
+/// properties are not interpolated.
+struct PomReader<'s, TS> {
+    stores: &'s hyperast::store::SimpleStores<TS>,
+}
+
+impl<'s, TS> PomReader<'s, TS> {
+    fn new(stores: &'s hyperast::store::SimpleStores<TS>) -> Self {
+        Self { stores }
+    }
+
+    fn read(&self, document: NodeIdentifier) -> PomContent {
+        let Some(project) = self
+            .children(&document)
+            .into_iter()
+            .find(|x| self.tag_name(x) == Some("project"))
+        else {
+            log::warn!("no project element in pom");
+            return PomContent {
+                submodules: vec![],
+                source_dirs: vec![DEFAULT_SOURCE_DIR.to_owned()],
+                test_source_dirs: vec![DEFAULT_TEST_SOURCE_DIR.to_owned()],
+                module: Default::default(),
+            };
+        };
+        let text = |path: &[&str]| self.text_at(&project, path);
+        let mut modules = vec![];
+        for m in self.all_at(&project, &["modules", "module"]) {
+            modules.extend(self.text(&m));
+        }
+        for p in self.all_at(&project, &["profiles", "profile"]) {
+            for m in self.all_at(&p, &["modules", "module"]) {
+                modules.extend(self.text(&m));
+            }
+        }
+        let mut seen = std::collections::HashSet::new();
+        modules.retain(|x| seen.insert(x.clone()));
+        let dependencies = self
+            .all_at(&project, &["dependencies", "dependency"])
+            .into_iter()
+            .map(|d| Dependency {
+                group_id: self.text_at(&d, &["groupId"]),
+                artifact_id: self.text_at(&d, &["artifactId"]),
+                version: self.text_at(&d, &["version"]),
+                scope: self.text_at(&d, &["scope"]),
+            })
+            .collect();
+        let module = MavenModule {
+            group_id: text(&["groupId"]).or_else(|| text(&["parent", "groupId"])),
+            artifact_id: text(&["artifactId"]),
+            version: text(&["version"]).or_else(|| text(&["parent", "version"])),
+            modules,
+            dependencies,
+        };
+        let dir = |path: &[&str], default: &str| {
+            let d = text(path).map_or(Some(default.to_owned()), |x| normalize_dir(&x));
+            d.into_iter().collect::<Vec<_>>()
+        };
+        PomContent {
+            submodules: module
+                .modules
+                .iter()
+                .filter_map(|x| normalize_dir(x))
+                .collect(),
+            source_dirs: dir(&["build", "sourceDirectory"], DEFAULT_SOURCE_DIR),
+            test_source_dirs: dir(&["build", "testSourceDirectory"], DEFAULT_TEST_SOURCE_DIR),
+            module,
+        }
+    }
+
+    fn node(&self, id: &NodeIdentifier) -> Option<XmlNode<'s>> {
+        self.stores
+            .node_store
+            .try_resolve_typed::<XmlIdN>(id)
+            .map(|x| x.0)
+    }
+
+    fn children(&self, id: &NodeIdentifier) -> Vec<NodeIdentifier> {
+        self.node(id)
+            .and_then(|n| n.children().map(|cs| cs.iter_children().collect()))
+            .unwrap_or_default()
+    }
+
+    fn find_child(
+        &self,
+        id: &NodeIdentifier,
+        pred: impl Fn(Type) -> bool,
+    ) -> Option<NodeIdentifier> {
+        self.children(id)
+            .into_iter()
+            .find(|x| self.node(x).is_some_and(|n| pred(n.get_type())))
+    }
+
+    fn label(&self, id: &NodeIdentifier) -> Option<&'s str> {
+        let n = self.node(id)?;
+        if !n.has_label() {
+            return None;
+        }
+        let l = n.get_label_unchecked();
+        Some(self.stores.label_store.resolve(l))
+    }
+
+    /// name of an element, None if `id` is not an element
+    fn tag_name(&self, id: &NodeIdentifier) -> Option<&'s str> {
+        if self.node(id)?.get_type() != Type::Element {
+            return None;
+        }
+        let tag = self.find_child(id, |t| t == Type::STag || t == Type::EmptyElemTag)?;
+        let name = self.find_child(&tag, |t| t == Type::Name)?;
+        self.label(&name)
+    }
+
+    fn elements(&self, id: &NodeIdentifier) -> Vec<NodeIdentifier> {
+        let Some(content) = self.find_child(id, |t| t == Type::Content) else {
+            return vec![];
+        };
+        self.children(&content)
+            .into_iter()
+            .filter(|x| self.node(x).is_some_and(|n| n.get_type() == Type::Element))
+            .collect()
+    }
+
+    /// every element reached by following the given element names from `id`
+    fn all_at(&self, id: &NodeIdentifier, path: &[&str]) -> Vec<NodeIdentifier> {
+        let mut current = vec![*id];
+        for name in path {
+            current = current
+                .iter()
+                .flat_map(|x| self.elements(x))
+                .filter(|x| self.tag_name(x) == Some(name))
+                .collect();
+        }
+        current
+    }
+
+    fn text_at(&self, id: &NodeIdentifier, path: &[&str]) -> Option<String> {
+        self.all_at(id, path).first().and_then(|x| self.text(x))
+    }
+
+    /// trimmed character data of an element, None if empty
+    fn text(&self, id: &NodeIdentifier) -> Option<String> {
+        let content = self.find_child(id, |t| t == Type::Content)?;
+        let mut s = String::new();
+        for x in self.children(&content) {
+            if self
+                .node(&x)
+                .is_some_and(|n| n.get_type() == Type::CharData)
+            {
+                s.extend(self.label(&x));
+            }
+        }
+        let s = s.trim();
+        (!s.is_empty()).then(|| s.to_owned())
+    }
+}
+
+/// Makes a directory declared in a pom relative to the module.
+///
+/// Directories outside of the module or depending on properties are ignored.
+fn normalize_dir(dir: &str) -> Option<String> {
+    let dir = dir.trim();
+    let dir = ["${project.basedir}/", "${basedir}/"]
+        .iter()
+        .find_map(|p| dir.strip_prefix(p))
+        .unwrap_or(dir);
+    let dir = dir.trim_start_matches("./").trim_end_matches('/');
+    if dir.is_empty()
+        || dir.starts_with('/')
+        || dir.contains('$')
+        || dir.split('/').any(|x| x == "..")
+    {
+        log::warn!("ignoring directory declared in pom: {}", dir);
+        return None;
+    }
+    Some(dir.to_owned())
+}
+
+/// The modules of a maven project and how they relate to each other.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct MavenModuleGraph {
+    pub modules: Vec<ModuleNode>,
+    /// (parent, submodule) indexes in `modules`, following the directory structure
+    pub submodules: Vec<(usize, usize)>,
+    /// (dependent, dependency) indexes in `modules`, only for dependencies between modules of the project
+    pub dependencies: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ModuleNode {
+    /// path of the module directory from the root of the project
+    pub path: String,
+    #[serde(skip)]
+    pub id: NodeIdentifier,
+    #[serde(flatten)]
+    pub module: MavenModule,
+}
+
+impl MavenModuleGraph {
+    /// `modules` gives what was extracted from the pom.xml of a module directory
+    pub fn compute<'m>(
+        stores: &SimpleStores,
+        root: NodeIdentifier,
+        modules: impl Fn(&NodeIdentifier) -> Option<&'m MavenModule>,
+    ) -> Self {
+        let mut graph = Self::default();
+        let mut stack: Vec<(NodeIdentifier, String, Option<usize>)> = vec![(root, "".into(), None)];
+        while let Some((id, path, parent)) = stack.pop() {
+            let Some((b, _)) = stores.node_store.try_resolve_typed::<XmlIdN>(&id) else {
+                continue;
+            };
+            if b.get_type() != Type::MavenDirectory {
+                continue;
+            }
+            let parent = if let Some(module) = modules(&id) {
+                let i = graph.modules.len();
+                graph.modules.push(ModuleNode {
+                    path: path.clone(),
+                    id,
+                    module: module.clone(),
+                });
+                graph.submodules.extend(parent.map(|p| (p, i)));
+                Some(i)
+            } else {
+                parent
+            };
+            let Some(cs) = b.children() else {
+                continue;
+            };
+            for c in cs.iter_children().collect::<Vec<_>>().into_iter().rev() {
+                let Some((n, _)) = stores.node_store.try_resolve_typed::<XmlIdN>(&c) else {
+                    continue;
+                };
+                if n.get_type() != Type::MavenDirectory || !n.has_label() {
+                    continue;
+                }
+                let name = stores.label_store.resolve(n.get_label_unchecked());
+                if name == "src" {
+                    continue;
+                }
+                let path = if path.is_empty() {
+                    name.to_owned()
+                } else {
+                    format!("{}/{}", path, name)
+                };
+                stack.push((c, path, parent));
+            }
+        }
+        let mut by_coordinates = std::collections::HashMap::new();
+        for (i, m) in graph.modules.iter().enumerate() {
+            if let Some(c) = m.module.coordinates() {
+                by_coordinates.entry(c).or_insert(i);
+            }
+        }
+        for (i, m) in graph.modules.iter().enumerate() {
+            for d in &m.module.dependencies {
+                let Some(&j) = d.coordinates().and_then(|c| by_coordinates.get(&c)) else {
+                    continue;
+                };
+                if i != j {
+                    graph.dependencies.push((i, j));
+                }
+            }
+        }
+        graph
+    }
 }
 
 pub struct IterMavenModules2<'a> {
@@ -196,6 +586,7 @@ pub struct MavenModuleAcc {
     pub(crate) sub_modules: Option<Vec<PathBuf>>,
     pub(crate) main_dirs: Option<Vec<PathBuf>>,
     pub(crate) test_dirs: Option<Vec<PathBuf>>,
+    pub(crate) module: Option<MavenModule>,
     pub(crate) status: EnumSet<SemFlag>,
     pub(crate) scripting_acc: std::option::Option<hyperast::scripting::Acc>,
 }
@@ -214,6 +605,7 @@ impl MavenModuleAcc {
             sub_modules: None,
             main_dirs: None,
             test_dirs: None,
+            module: None,
             status: Default::default(),
             scripting_acc: None,
         }
@@ -242,6 +634,7 @@ impl MavenModuleAcc {
             } else {
                 Some(test_dirs)
             },
+            module: None,
             status: Default::default(),
             scripting_acc: None,
         }
@@ -271,6 +664,7 @@ impl MavenModuleAcc {
                 .collect(),
         );
         self.sub_modules = Some(full_node.submodules.iter().map(|x| x.into()).collect());
+        self.module = Some(full_node.module);
         self.primary.metrics.acc(full_node.metrics);
     }
     pub fn push_submodule(&mut self, name: LabelIdentifier, full_node: (NodeIdentifier, MD)) {
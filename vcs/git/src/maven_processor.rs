@@ -105,17 +105,21 @@ impl<'a, 'b, 'c, const RMS: bool, const FFWD: bool> Processor<MavenModuleAcc>
             _ => {}
         }
     }
-    fn post(&mut self, oid: Oid, acc: MavenModuleAcc) -> Option<(NodeIdentifier, MD)> {
+    fn post(&mut self, oid: Oid, mut acc: MavenModuleAcc) -> Option<(NodeIdentifier, MD)> {
         let name = acc.primary.name.clone();
+        let module = acc.module.take();
         let full_node = Self::make(acc, self.prepro.main_stores_mut().mut_with_ts());
         log::info!("tree size: {}", full_node.1.metrics.size);
-        self.prepro
+        let caches = self
+            .prepro
             .processing_systems
             .mut_or_default::<MavenProcessorHolder>()
             .with_parameters_mut(self.handle.1)
-            .get_caches_mut()
-            .object_map
-            .insert(oid, full_node.clone());
+            .get_caches_mut();
+        caches.object_map.insert(oid, full_node.clone());
+        if let Some(module) = module {
+            caches.modules.insert(full_node.0, module);
+        }
         let name = self.prepro.intern_label(&name);
         if self.stack.is_empty() {
             Some(full_node)
@@ -488,6 +492,19 @@ impl RepositoryProcessor {
     }
 }
 
+impl RepositoryProcessor {
+    /// What the pom.xml of the maven module directory `id` declares.
+    pub fn maven_module(&self, id: &NodeIdentifier) -> Option<&crate::maven::MavenModule> {
+        let holder = self.processing_systems.get::<MavenProcessorHolder>()?;
+        holder.0.iter().find_map(|p| p.cache.modules.get(id))
+    }
+
+    /// Module graph of an already processed maven project, `root` being its root directory.
+    pub fn maven_module_graph(&self, root: NodeIdentifier) -> crate::maven::MavenModuleGraph {
+        crate::maven::MavenModuleGraph::compute(&self.main_stores, root, |id| self.maven_module(id))
+    }
+}
+
 struct MavenModuleHelper {
     name: String,
     submodules: (bool, Vec<PathBuf>),
@@ -682,6 +699,12 @@ impl crate::processing::erased::ParametrizedCommitProc2 for PomProcessorHolder {
     ) -> &Self::Proc {
         &self.0[parameters.0]
     }
+
+    fn retain_reachable(&mut self, reachable: &hyperast::store::nodes::legion::gc::Reachable) {
+        for proc in &mut self.0 {
+            proc.cache.retain_reachable(reachable);
+        }
+    }
 }
 impl CacheHolding<crate::processing::caches::Pom> for PomProc {
     fn get_caches_mut(&mut self) -> &mut crate::processing::caches::Pom {
@@ -833,6 +856,64 @@ impl crate::processing::erased::ParametrizedCommitProc2 for MavenProcessorHolder
     ) -> &Self::Proc {
         &self.0[parameters.0]
     }
+
+    fn persist(
+        &self,
+        _stores: &crate::SimpleStores,
+        mut w: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::{write_id, write_u32};
+        write_u32(&mut w, self.0.len() as u32)?;
+        for proc in &self.0 {
+            crate::persist::write_commits(&mut w, &proc.commits)?;
+            // the pom nodes are in the stores, but not what was extracted from them
+            write_u32(&mut w, proc.cache.modules.len() as u32)?;
+            for (id, module) in &proc.cache.modules {
+                write_id(&mut w, *id)?;
+                module.save(&mut w)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn restore(
+        &mut self,
+        _stores: &crate::SimpleStores,
+        mut r: &mut dyn std::io::Read,
+    ) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::{read_id, read_u32_from};
+        let len = read_u32_from(&mut r)? as usize;
+        if len > self.0.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "more persisted processors than registered ones",
+            ));
+        }
+        for proc in &mut self.0[..len] {
+            proc.commits = crate::persist::read_commits(&mut r)?;
+            proc.cache.modules.clear();
+            for _ in 0..read_u32_from(&mut r)? {
+                let id = read_id(&mut r)?;
+                let module = crate::maven::MavenModule::load(&mut r)?;
+                proc.cache.modules.insert(id, module);
+            }
+        }
+        Ok(())
+    }
+
+    fn commit_roots(&self) -> Vec<(git2::Oid, hyperast::store::defaults::NodeIdentifier)> {
+        self.0
+            .iter()
+            .flat_map(|proc| proc.commits.iter().map(|(oid, c)| (*oid, c.ast_root)))
+            .collect()
+    }
+
+    fn retain_reachable(&mut self, reachable: &hyperast::store::nodes::legion::gc::Reachable) {
+        for proc in &mut self.0 {
+            proc.commits.retain(|_, c| reachable.contains(&c.ast_root));
+            proc.cache.retain_reachable(reachable);
+        }
+    }
 }
 impl CacheHolding<crate::processing::caches::Maven> for MavenProc {
     fn get_caches_mut(&mut self) -> &mut crate::processing::caches::Maven {
@@ -23,6 +23,23 @@ pub struct PreProcessedRepositories {
     pub processor: RepositoryProcessor,
     // pub processing_ordered_commits: HashMap<String,Vec<git2::Oid>>,
     configs: HashMap<Repo, ParametrizedCommitProcessorHandle>,
+    /// last uses of processed commits, to only keep the most recently used ones when collecting garbage
+    recency: std::sync::Mutex<Recency>,
+}
+
+#[derive(Default)]
+struct Recency {
+    clock: u64,
+    last_use: HashMap<git2::Oid, u64>,
+}
+
+impl Recency {
+    fn touch(&mut self, oids: impl IntoIterator<Item = git2::Oid>) {
+        for oid in oids {
+            self.clock += 1;
+            self.last_use.insert(oid, self.clock);
+        }
+    }
 }
 
 // #[derive(Default)]
@@ -105,6 +122,56 @@ impl PreProcessedRepositories {
         self.processor.purge_caches()
     }
 
+    /// Frees the nodes that are not reachable from the processed commits satisfying `keep`,
+    /// the other commits are forgotten, see [`RepositoryProcessor::collect_garbage`].
+    pub fn collect_garbage(
+        &mut self,
+        keep: impl Fn(&git2::Oid) -> bool,
+    ) -> (
+        hyperast::store::nodes::legion::gc::Reachable,
+        hyperast::store::nodes::legion::gc::GcStats,
+    ) {
+        let r = self.processor.collect_garbage(keep, []);
+        let procs = self.processor.processing_systems.values();
+        let remaining: std::collections::HashSet<_> = procs
+            .flat_map(|p| p.commit_roots())
+            .map(|(oid, _)| oid)
+            .collect();
+        let recency = self.recency.get_mut().unwrap();
+        recency.last_use.retain(|oid, _| remaining.contains(oid));
+        r
+    }
+
+    /// Like [`PreProcessedRepositories::collect_garbage`],
+    /// but only keeps the `count` processed commits that were the most recently processed or retrieved.
+    pub fn collect_garbage_keeping_recent(
+        &mut self,
+        count: usize,
+    ) -> (
+        hyperast::store::nodes::legion::gc::Reachable,
+        hyperast::store::nodes::legion::gc::GcStats,
+    ) {
+        let last_use = &self.recency.get_mut().unwrap().last_use;
+        let procs = self.processor.processing_systems.values();
+        // commits never retrieved come last
+        let mut recent: Vec<_> = procs
+            .flat_map(|p| p.commit_roots())
+            .map(|(oid, _)| (last_use.get(&oid).copied().unwrap_or(0), oid))
+            .collect();
+        recent.sort_unstable_by(|a, b| b.cmp(a));
+        recent.dedup();
+        let kept: std::collections::HashSet<_> =
+            recent.into_iter().take(count).map(|(_, oid)| oid).collect();
+        self.collect_garbage(|oid| kept.contains(oid))
+    }
+
+    /// The number of distinct processed commits, over all configurations.
+    pub fn total_commit_count(&self) -> usize {
+        let procs = self.processor.processing_systems.values();
+        let oids = procs.flat_map(|p| p.commit_roots()).map(|(oid, _)| oid);
+        oids.collect::<std::collections::HashSet<_>>().len()
+    }
+
     pub fn commit_count(&self, config: &ParametrizedCommitProcessorHandle) -> usize {
         let proc = self
             .processor
@@ -126,7 +193,9 @@ impl PreProcessedRepositories {
             .by_id(&config.0)
             .unwrap()
             .get(config.1);
-        proc.get_commit(*commit_oid)
+        let commit = proc.get_commit(*commit_oid)?;
+        self.recency.lock().unwrap().touch([*commit_oid]);
+        Some(commit)
     }
 
     pub fn register_config(&mut self, spec: Repo, config: RepoConfig) -> ConfiguredRepoHandle2 {
@@ -149,6 +218,10 @@ impl PreProcessedRepositories {
                 });
                 ConfiguredRepoHandle2 { spec, config }
             }
+            RepoConfig::JavaGradle => {
+                let config = self.register_gradle_param(crate::java_processor::Parameter::faster());
+                ConfiguredRepoHandle2 { spec, config }
+            }
             RepoConfig::Java => {
                 let processor_map = &mut self.processor.processing_systems;
                 use crate::java_processor::JavaProcessorHolder;
@@ -174,7 +247,39 @@ impl PreProcessedRepositories {
                 let config = h.register_param(crate::make_processor::Parameter { cpp_handle });
                 ConfiguredRepoHandle2 { spec, config }
             }
-            _ => todo!(),
+            RepoConfig::CppCMake => {
+                let q: &[&str] = &["(translation_unit)"];
+                let config = self.register_cmake_param(crate::cpp_processor::Parameter {
+                    query: Some(q.into()),
+                });
+                ConfiguredRepoHandle2 { spec, config }
+            }
+            RepoConfig::TsNpm => {
+                let config = self.register_npm_param(Default::default());
+                ConfiguredRepoHandle2 { spec, config }
+            }
+            RepoConfig::PythonPip => {
+                let q: &[&str] = &["(module)"];
+                let config = self.register_pip_param(crate::python_processor::Parameter {
+                    query: Some(q.into()),
+                });
+                ConfiguredRepoHandle2 { spec, config }
+            }
+            RepoConfig::RustCargo => {
+                let q: &[&str] = &["(source_file)"];
+                let config = self.register_cargo_param(crate::rust_processor::Parameter {
+                    query: Some(q.into()),
+                });
+                ConfiguredRepoHandle2 { spec, config }
+            }
+            RepoConfig::Any => {
+                let config = self.register_any_param(
+                    crate::java_processor::Parameter::faster(),
+                    crate::cpp_processor::Parameter { query: None },
+                    Default::default(),
+                );
+                ConfiguredRepoHandle2 { spec, config }
+            }
         };
 
         self.configs.insert(r.spec.clone(), r.config);
@@ -226,7 +331,58 @@ impl PreProcessedRepositories {
                 let config = h.register_param(crate::make_processor::Parameter { cpp_handle });
                 ConfiguredRepoHandle2 { spec, config }
             }
-            _ => todo!(),
+            RepoConfig::CppCMake => {
+                let config =
+                    self.register_cmake_param(crate::cpp_processor::Parameter { query: None });
+                ConfiguredRepoHandle2 { spec, config }
+            }
+            RepoConfig::JavaGradle => {
+                let config = self.register_gradle_param(crate::java_processor::Parameter {
+                    prepro: Some(prepro),
+                    ..Default::default()
+                });
+                ConfiguredRepoHandle2 { spec, config }
+            }
+            RepoConfig::TsNpm => {
+                log::warn!("no scripted preprocessing for typescript, ignoring it");
+                let config = self.register_npm_param(Default::default());
+                ConfiguredRepoHandle2 { spec, config }
+            }
+            RepoConfig::PythonPip => {
+                log::warn!("no scripted preprocessing for python, ignoring it");
+                let config =
+                    self.register_pip_param(crate::python_processor::Parameter { query: None });
+                ConfiguredRepoHandle2 { spec, config }
+            }
+            RepoConfig::RustCargo => {
+                log::warn!("no scripted preprocessing for rust, ignoring it");
+                let config =
+                    self.register_cargo_param(crate::rust_processor::Parameter { query: None });
+                ConfiguredRepoHandle2 { spec, config }
+            }
+            RepoConfig::Any => {
+                // NOTE the preprocessing script only applies to java
+                let config = self.register_any_param(
+                    crate::java_processor::Parameter {
+                        prepro: Some(prepro),
+                        ..Default::default()
+                    },
+                    crate::cpp_processor::Parameter { query: None },
+                    Default::default(),
+                );
+                ConfiguredRepoHandle2 { spec, config }
+            }
+            RepoConfig::Java => {
+                let processor_map = &mut self.processor.processing_systems;
+                use crate::java_processor::JavaProcessorHolder;
+                let h_java = processor_map.mut_or_default::<JavaProcessorHolder>();
+                let t = crate::java_processor::Parameter {
+                    prepro: Some(prepro),
+                    ..Default::default()
+                };
+                let config = h_java.register_param(t);
+                ConfiguredRepoHandle2 { spec, config }
+            }
         };
         self.configs.insert(r.spec.clone(), r.config);
         r
@@ -287,7 +443,51 @@ impl PreProcessedRepositories {
                 let config = h.register_param(crate::make_processor::Parameter { cpp_handle });
                 ConfiguredRepoHandle2 { spec, config }
             }
-            _ => todo!(),
+            RepoConfig::CppCMake => {
+                let config = self.register_cmake_param(crate::cpp_processor::Parameter {
+                    query: Some(query.into()),
+                });
+                ConfiguredRepoHandle2 { spec, config }
+            }
+            RepoConfig::JavaGradle => {
+                let config = self.register_gradle_param(crate::java_processor::Parameter {
+                    query: Some(query.into()),
+                    ..Default::default()
+                });
+                ConfiguredRepoHandle2 { spec, config }
+            }
+            RepoConfig::TsNpm => {
+                log::warn!("no precomputed queries for typescript, ignoring them");
+                let config = self.register_npm_param(Default::default());
+                ConfiguredRepoHandle2 { spec, config }
+            }
+            RepoConfig::PythonPip => {
+                let config = self.register_pip_param(crate::python_processor::Parameter {
+                    query: Some(query.into()),
+                });
+                ConfiguredRepoHandle2 { spec, config }
+            }
+            RepoConfig::RustCargo => {
+                let config = self.register_cargo_param(crate::rust_processor::Parameter {
+                    query: Some(query.into()),
+                });
+                ConfiguredRepoHandle2 { spec, config }
+            }
+            RepoConfig::Any => {
+                // NOTE precomputed queries are written for a single language,
+                // here they are only applied to java and cpp
+                let config = self.register_any_param(
+                    crate::java_processor::Parameter {
+                        query: Some(query.into()),
+                        ..Default::default()
+                    },
+                    crate::cpp_processor::Parameter {
+                        query: Some(query.into()),
+                    },
+                    Default::default(),
+                );
+                ConfiguredRepoHandle2 { spec, config }
+            }
         };
         self.configs.insert(r.spec.clone(), r.config);
         r
@@ -325,15 +525,130 @@ impl PreProcessedRepositories {
                 });
                 ConfiguredRepoHandle2 { spec: repo, config }
             }
-            RepoConfig::CppMake => {
+            RepoConfig::JavaGradle => {
+                let config = self.register_gradle_param(crate::java_processor::Parameter {
+                    prepro: None,
+                    query: None,
+                    tsg: Some(tsg),
+                });
+                ConfiguredRepoHandle2 { spec: repo, config }
+            }
+            RepoConfig::CppMake
+            | RepoConfig::CppCMake
+            | RepoConfig::PythonPip
+            | RepoConfig::RustCargo => {
+                unimplemented!()
+            }
+            RepoConfig::TsNpm | RepoConfig::Any | RepoConfig::Java => {
                 unimplemented!()
             }
-            _ => todo!(),
         };
         self.configs.insert(r.spec.clone(), r.config);
         r
     }
 
+    fn register_gradle_param(
+        &mut self,
+        t: crate::java_processor::Parameter,
+    ) -> ParametrizedCommitProcessorHandle {
+        use crate::processing::erased::Parametrized;
+        let processor_map = &mut self.processor.processing_systems;
+        let h_java = processor_map.mut_or_default::<crate::java_processor::JavaProcessorHolder>();
+        let java_handle = CommitProcExt::register_param(h_java, t);
+        let h = processor_map.mut_or_default::<crate::gradle_processor::GradleProcessorHolder>();
+        h.register_param(crate::gradle_processor::Parameter { java_handle })
+    }
+
+    fn register_cmake_param(
+        &mut self,
+        t: crate::cpp_processor::Parameter,
+    ) -> ParametrizedCommitProcessorHandle {
+        use crate::processing::erased::Parametrized;
+        let processor_map = &mut self.processor.processing_systems;
+        let h_cpp = processor_map.mut_or_default::<crate::cpp_processor::CppProcessorHolder>();
+        let cpp_handle = CommitProcExt::register_param(h_cpp, t);
+        let h = processor_map.mut_or_default::<crate::cmake_processor::CMakeProcessorHolder>();
+        h.register_param(crate::cmake_processor::Parameter { cpp_handle })
+    }
+
+    fn register_npm_param(
+        &mut self,
+        t: crate::ts_processor::Parameter,
+    ) -> ParametrizedCommitProcessorHandle {
+        use crate::processing::erased::Parametrized;
+        let processor_map = &mut self.processor.processing_systems;
+        let h_ts = processor_map.mut_or_default::<crate::ts_processor::TsProcessorHolder>();
+        let ts_handle = CommitProcExt::register_param(h_ts, t);
+        let h = processor_map.mut_or_default::<crate::npm_processor::NpmProcessorHolder>();
+        h.register_param(crate::npm_processor::Parameter { ts_handle })
+    }
+
+    fn register_pip_param(
+        &mut self,
+        t: crate::python_processor::Parameter,
+    ) -> ParametrizedCommitProcessorHandle {
+        use crate::processing::erased::Parametrized;
+        let processor_map = &mut self.processor.processing_systems;
+        let h_python =
+            processor_map.mut_or_default::<crate::python_processor::PythonProcessorHolder>();
+        let python_handle = CommitProcExt::register_param(h_python, t);
+        let h = processor_map.mut_or_default::<crate::pip_processor::PipProcessorHolder>();
+        h.register_param(crate::pip_processor::Parameter { python_handle })
+    }
+
+    fn register_cargo_param(
+        &mut self,
+        t: crate::rust_processor::Parameter,
+    ) -> ParametrizedCommitProcessorHandle {
+        use crate::processing::erased::Parametrized;
+        let processor_map = &mut self.processor.processing_systems;
+        let h_rust = processor_map.mut_or_default::<crate::rust_processor::RustProcessorHolder>();
+        let rust_handle = CommitProcExt::register_param(h_rust, t);
+        let h = processor_map.mut_or_default::<crate::cargo_processor::CargoProcessorHolder>();
+        h.register_param(crate::cargo_processor::Parameter { rust_handle })
+    }
+
+    fn register_any_param(
+        &mut self,
+        java: crate::java_processor::Parameter,
+        cpp: crate::cpp_processor::Parameter,
+        ts: crate::ts_processor::Parameter,
+    ) -> ParametrizedCommitProcessorHandle {
+        use crate::processing::erased::Parametrized;
+        let processor_map = &mut self.processor.processing_systems;
+        let h_java = processor_map.mut_or_default::<crate::java_processor::JavaProcessorHolder>();
+        let java_handle = CommitProcExt::register_param(h_java, java);
+        let h_cpp = processor_map.mut_or_default::<crate::cpp_processor::CppProcessorHolder>();
+        let cpp_handle = CommitProcExt::register_param(h_cpp, cpp);
+        let h_ts = processor_map.mut_or_default::<crate::ts_processor::TsProcessorHolder>();
+        let ts_handle = CommitProcExt::register_param(h_ts, ts);
+        let h_python =
+            processor_map.mut_or_default::<crate::python_processor::PythonProcessorHolder>();
+        let python_handle = CommitProcExt::register_param(
+            h_python,
+            crate::python_processor::Parameter { query: None },
+        );
+        let h_rust = processor_map.mut_or_default::<crate::rust_processor::RustProcessorHolder>();
+        let rust_handle =
+            CommitProcExt::register_param(h_rust, crate::rust_processor::Parameter { query: None });
+        let h = processor_map.mut_or_default::<crate::any_processor::AnyProcessorHolder>();
+        h.register_param(crate::any_processor::Parameter {
+            java_handle,
+            cpp_handle,
+            ts_handle,
+            python_handle,
+            rust_handle,
+        })
+    }
+
+    /// Finds a registered repository given the segment identifying its forge,
+    /// see [`crate::git::Forge::segment`].
+    pub fn find_repo(&self, forge: &str, user: &str, name: &str) -> Option<&Repo> {
+        self.configs.keys().find(|repo| {
+            repo.user() == user && repo.name() == name && repo.forge().segment() == forge
+        })
+    }
+
     pub fn get_config(&self, repo: Repo) -> Option<ConfiguredRepoHandle2> {
         self.configs
             .get(&repo)
@@ -367,7 +682,9 @@ impl PreProcessedRepositories {
         repository: &ConfiguredRepo2,
         size: usize,
     ) -> Vec<git2::Oid> {
-        self.processor.pre_pro(rw, repository, size)
+        let oids = self.processor.pre_pro(rw, repository, size);
+        self.recency.get_mut().unwrap().touch(oids.iter().copied());
+        oids
     }
 
     pub fn pre_process_with_limit(
@@ -378,8 +695,11 @@ impl PreProcessedRepositories {
         // dir_path: &str,
         limit: usize,
     ) -> Result<Vec<git2::Oid>, git2::Error> {
-        self.processor
-            .pre_process_with_limit(repository, before, after, limit)
+        let oids = self
+            .processor
+            .pre_process_with_limit(repository, before, after, limit)?;
+        self.recency.get_mut().unwrap().touch(oids.iter().copied());
+        Ok(oids)
     }
 
     pub fn ensure_pre_processed_with_limit(
@@ -0,0 +1,179 @@
+use std::path::PathBuf;
+
+use hyperast::store::defaults::{LabelIdentifier, NodeIdentifier};
+use hyperast_gen_ts_json::{
+    legion::{JsonTreeGen, tree_sitter_parse_json},
+    types::TStore,
+};
+use hyperast_gen_ts_ts::legion as ts_tree_gen;
+
+use crate::{
+    Accumulator, BasicDirAcc, DefaultMetrics, PROPAGATE_ERROR_ON_BAD_CST_NODE,
+    processing::ObjectName,
+};
+
+/// Compresses a package.json and extracts the information needed to explore the package.
+pub(crate) fn handle_package_json_file<'a>(
+    tree_gen: &mut JsonTreeGen<'a, '_, TStore>,
+    name: &ObjectName,
+    text: &'a [u8],
+) -> Result<PackageJson, ()> {
+    let manifest: Manifest = match serde_json::from_slice(text) {
+        Ok(x) => x,
+        Err(err) => {
+            log::warn!("bad package.json {:?}: {}", name.try_str(), err);
+            Manifest::default()
+        }
+    };
+    let tree = match tree_sitter_parse_json(text) {
+        Ok(tree) => tree,
+        Err(tree) => {
+            log::warn!("bad CST");
+            log::debug!("{:?}", name.try_str());
+            log::debug!("{}", tree.root_node().to_sexp());
+            if PROPAGATE_ERROR_ON_BAD_CST_NODE {
+                return Err(());
+            } else {
+                tree
+            }
+        }
+    };
+    let node = tree_gen
+        .generate_file(name.as_bytes(), text, tree.walk())
+        .local;
+    Ok(PackageJson {
+        compressed_node: node.compressed_node,
+        metrics: node.metrics,
+        name: manifest.name,
+        workspaces: manifest.workspaces.into_patterns(),
+    })
+}
+
+/// The subset of a package.json that matters to explore a package
+#[derive(serde::Deserialize, Default)]
+struct Manifest {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    workspaces: Workspaces,
+}
+
+/// Workspaces are either a list of globs or, with yarn, an object holding the list of globs in `packages`.
+#[derive(serde::Deserialize, Default)]
+#[serde(untagged)]
+enum Workspaces {
+    #[default]
+    None,
+    List(Vec<String>),
+    Yarn {
+        #[serde(default)]
+        packages: Vec<String>,
+    },
+}
+
+impl Workspaces {
+    fn into_patterns(self) -> Vec<String> {
+        match self {
+            Workspaces::None => vec![],
+            Workspaces::List(x) => x,
+            Workspaces::Yarn { packages } => packages,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageJson {
+    pub compressed_node: NodeIdentifier,
+    pub metrics: DefaultMetrics,
+    pub name: Option<String>,
+    /// globs locating nested packages, e.g. `packages/*`
+    pub workspaces: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MD {
+    pub(crate) metrics: DefaultMetrics,
+}
+
+pub struct NpmModuleAcc {
+    pub(crate) primary: BasicDirAcc<NodeIdentifier, LabelIdentifier, DefaultMetrics>,
+    /// remaining path of workspace globs, relative to this directory
+    pub(crate) workspaces: Option<Vec<PathBuf>>,
+    /// the workspace globs given by the parent package, part of the key of the cached package
+    pub(crate) inherited: Vec<PathBuf>,
+}
+
+impl From<String> for NpmModuleAcc {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl NpmModuleAcc {
+    pub(crate) fn new(name: String) -> Self {
+        Self {
+            primary: BasicDirAcc::new(name),
+            workspaces: None,
+            inherited: vec![],
+        }
+    }
+    pub(crate) fn with_workspaces(name: String, workspaces: Vec<PathBuf>) -> Self {
+        Self {
+            primary: BasicDirAcc::new(name),
+            workspaces: if workspaces.is_empty() {
+                None
+            } else {
+                Some(workspaces.clone())
+            },
+            inherited: workspaces,
+        }
+    }
+
+    /// Returns the remaining globs if the directory `name` could contain a workspace package.
+    pub(crate) fn workspaces_in(&self, name: &[u8]) -> Option<Vec<PathBuf>> {
+        let name = std::str::from_utf8(name).ok()?;
+        let workspaces = self.workspaces.as_ref()?;
+        crate::utils::globs_in(workspaces, name)
+    }
+}
+
+impl NpmModuleAcc {
+    pub(crate) fn push_package_json(&mut self, name: LabelIdentifier, full_node: PackageJson) {
+        self.primary.children.push(full_node.compressed_node);
+        self.primary.children_names.push(name);
+        self.workspaces = Some(full_node.workspaces.iter().map(|x| x.into()).collect());
+        self.primary.metrics.acc(full_node.metrics);
+    }
+    pub fn push_submodule(&mut self, name: LabelIdentifier, full_node: (NodeIdentifier, MD)) {
+        self.primary.children.push(full_node.0);
+        self.primary.children_names.push(name);
+        self.primary.metrics.acc(full_node.1.metrics);
+    }
+    pub(crate) fn push_source_file(
+        &mut self,
+        name: LabelIdentifier,
+        full_node: ts_tree_gen::Local,
+    ) {
+        self.primary
+            .push(name, full_node.compressed_node, full_node.metrics);
+    }
+    pub(crate) fn push_source_directory(
+        &mut self,
+        name: LabelIdentifier,
+        full_node: ts_tree_gen::Local,
+    ) {
+        self.primary
+            .push(name, full_node.compressed_node, full_node.metrics);
+    }
+}
+
+impl hyperast::tree_gen::Accumulator for NpmModuleAcc {
+    type Node = (LabelIdentifier, (NodeIdentifier, MD));
+    fn push(&mut self, (name, full_node): Self::Node) {
+        self.push_submodule(name, full_node);
+    }
+}
+
+impl Accumulator for NpmModuleAcc {
+    type Unlabeled = (NodeIdentifier, MD);
+}
@@ -0,0 +1,509 @@
+use crate::StackEle;
+use crate::processing::erased::{
+    CommitProcessorHandle, ParametrizedCommitProcessor2Handle as PCP2Handle,
+};
+use crate::{
+    Processor,
+    git::BasicGitObject,
+    npm::{MD, NpmModuleAcc},
+    preprocessed::RepositoryProcessor,
+    processing::{
+        CacheHolding, InFiles, ObjectName, ParametrizedCommitProcessorHandle,
+        erased::ParametrizedCommitProc2,
+    },
+};
+use git2::{Oid, Repository};
+use hyperast::types::ETypeStore as _;
+use hyperast::{
+    hashed::{IndexingHashBuilder, MetaDataHashsBuilder},
+    store::{defaults::NodeIdentifier, nodes::legion::eq_node},
+    types::LabelStore,
+};
+use hyperast_gen_ts_json::legion::JsonTreeGen;
+use hyperast_gen_ts_ts::types::Type;
+use std::{
+    iter::Peekable,
+    path::{Components, PathBuf},
+};
+
+pub type SimpleStores = hyperast::store::SimpleStores<hyperast_gen_ts_ts::types::TStore>;
+
+/// Explores npm packages, each directory containing a package.json is a package,
+/// as well as the directories matched by the workspaces declared by a parent package.
+/// Other directories are handled as plain typescript directories.
+pub struct NpmProcessor<'a, 'b, 'c, Acc> {
+    prepro: &'b mut RepositoryProcessor,
+    repository: &'a Repository,
+    stack: Vec<StackEle<Acc>>,
+    dir_path: &'c mut Peekable<Components<'c>>,
+    handle: ParametrizedCommitProcessorHandle,
+}
+
+impl<'a, 'b, 'c, Acc: From<String>> NpmProcessor<'a, 'b, 'c, Acc> {
+    pub fn new(
+        repository: &'a Repository,
+        prepro: &'b mut RepositoryProcessor,
+        mut dir_path: &'c mut Peekable<Components<'c>>,
+        name: &[u8],
+        oid: git2::Oid,
+        handle: ParametrizedCommitProcessorHandle,
+    ) -> Self {
+        let tree = repository.find_tree(oid).unwrap();
+        let prepared = prepare_dir_exploration(tree, &mut dir_path);
+        let name = std::str::from_utf8(&name).unwrap().to_string();
+        let stack = vec![StackEle::new(oid, prepared, Acc::from(name))];
+        Self {
+            stack,
+            repository,
+            prepro,
+            dir_path,
+            handle,
+        }
+    }
+}
+
+impl<'a, 'b, 'c> Processor<NpmModuleAcc> for NpmProcessor<'a, 'b, 'c, NpmModuleAcc> {
+    fn pre(&mut self, current_dir: BasicGitObject) {
+        match current_dir {
+            BasicGitObject::Tree(oid, name) => {
+                if crate::ts_processor::is_ignored_dir(&name) {
+                    return;
+                }
+                self.handle_tree_cached(name, oid);
+            }
+            BasicGitObject::Blob(oid, name) => {
+                if self.dir_path.peek().is_some() {
+                    return;
+                }
+                if crate::processing::file_sys::PackageJson::matches(&name) {
+                    self.prepro
+                        .help_handle_package_json(
+                            oid,
+                            &mut self.stack.last_mut().unwrap().acc,
+                            name,
+                            &self.repository,
+                            PCP2Handle(self.handle.1, std::marker::PhantomData),
+                        )
+                        .unwrap();
+                } else if crate::processing::file_sys::Ts::matches(&name) {
+                    let ts_handle = self
+                        .prepro
+                        .processing_systems
+                        .mut_or_default::<NpmProcessorHolder>()
+                        .with_parameters(self.handle.1)
+                        .parameter
+                        .ts_handle;
+                    let (full_node,) = self
+                        .prepro
+                        .handle_ts_blob(oid, &name, self.repository, ts_handle)
+                        .unwrap();
+                    let name = self.prepro.intern_object_name(&name);
+                    let w = &mut self.stack.last_mut().unwrap().acc;
+                    assert!(!w.primary.children_names.contains(&name));
+                    w.push_source_file(name, full_node);
+                } else {
+                    log::debug!("not ts source file {:?}", name.try_str());
+                }
+            }
+        }
+    }
+    fn post(&mut self, oid: Oid, acc: NpmModuleAcc) -> Option<(NodeIdentifier, MD)> {
+        let name = acc.primary.name.clone();
+        let key = (oid, acc.inherited.clone());
+        let full_node = make(acc, self.prepro.main_stores_mut().mut_with_ts());
+        self.prepro
+            .processing_systems
+            .mut_or_default::<NpmProcessorHolder>()
+            .with_parameters_mut(self.handle.1)
+            .cache
+            .object_map
+            .insert(key, full_node.clone());
+
+        let name = self.prepro.main_stores.label_store.get_or_insert(name);
+        if self.stack.is_empty() {
+            Some(full_node)
+        } else {
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            assert!(
+                !w.primary.children_names.contains(&name),
+                "{:?} {:?}",
+                w.primary.children_names,
+                name
+            );
+            w.push_submodule(name, full_node);
+            None
+        }
+    }
+
+    fn stack(&mut self) -> &mut Vec<StackEle<NpmModuleAcc>> {
+        &mut self.stack
+    }
+}
+
+impl<'a, 'b, 'c> NpmProcessor<'a, 'b, 'c, NpmModuleAcc> {
+    fn handle_tree_cached(&mut self, name: ObjectName, oid: Oid) {
+        if let Some(s) = self.dir_path.peek() {
+            if name
+                .as_bytes()
+                .eq(std::ffi::OsStr::as_encoded_bytes(s.as_os_str()))
+            {
+                self.dir_path.next();
+                self.stack.last_mut().expect("never empty").cs.clear();
+                let tree = self.repository.find_tree(oid).unwrap();
+                let prepared = prepare_dir_exploration(tree, &mut self.dir_path);
+                self.stack.push(StackEle::new(
+                    oid,
+                    prepared,
+                    NpmModuleAcc::new(name.try_into().unwrap()),
+                ));
+            }
+            return;
+        }
+        let npm_proc = self
+            .prepro
+            .processing_systems
+            .mut_or_default::<NpmProcessorHolder>()
+            .with_parameters_mut(self.handle.1);
+        let ts_handle = npm_proc.parameter.ts_handle;
+        let parent_acc = &self.stack.last().unwrap().acc;
+        let workspaces = parent_acc.workspaces_in(name.as_bytes());
+        let key = (oid, workspaces.clone().unwrap_or_default());
+        if let Some(already) = npm_proc.cache.object_map.get(&key) {
+            // reinit already computed node for post order
+            let full_node = already.clone();
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            let name = self.prepro.intern_object_name(name);
+            assert!(!w.primary.children_names.contains(&name));
+            w.push_submodule(name, full_node);
+            return;
+        }
+        log::debug!("npm tree {:?}", name.try_str());
+        let tree = self.repository.find_tree(oid).unwrap();
+        let is_package = tree
+            .iter()
+            .any(|x| crate::processing::file_sys::PackageJson::matches(&x.name_bytes().into()));
+        if is_package || workspaces.is_some() {
+            // handle as a nested package, or as a directory containing some
+            let prepared = prepare_dir_exploration(tree, &mut self.dir_path);
+            self.stack.push(StackEle::new(
+                oid,
+                prepared,
+                NpmModuleAcc::with_workspaces(
+                    name.try_into().unwrap(),
+                    workspaces.unwrap_or_default(),
+                ),
+            ));
+            return;
+        }
+        let (name, (full_node,)) = self.prepro.help_handle_ts_folder(
+            &self.repository,
+            &mut self.dir_path,
+            oid,
+            &name,
+            ts_handle,
+        );
+        let parent_acc = &mut self.stack.last_mut().unwrap().acc;
+        assert!(!parent_acc.primary.children_names.contains(&name));
+        parent_acc.push_source_directory(name, full_node);
+    }
+}
+
+pub(crate) fn make(acc: NpmModuleAcc, stores: &mut SimpleStores) -> (NodeIdentifier, MD) {
+    let kind = Type::Directory;
+    let interned_kind = hyperast_gen_ts_ts::types::TStore::intern(kind);
+    let label_id = stores.label_store.get_or_insert(acc.primary.name.clone());
+
+    let primary = acc
+        .primary
+        .map_metrics(|m| m.finalize(&interned_kind, &label_id, 0));
+
+    let hashable = primary.metrics.hashs.most_discriminating();
+
+    let eq = eq_node(&interned_kind, Some(&label_id), &primary.children);
+
+    assert_eq!(primary.children_names.len(), primary.children.len());
+
+    let insertion = stores.node_store.prepare_insertion(&hashable, eq);
+    if let Some(id) = insertion.occupied_id() {
+        let metrics = primary
+            .metrics
+            .map_hashs(|h| MetaDataHashsBuilder::build(h));
+        return (id, MD { metrics });
+    }
+
+    log::info!("npm package {} {}", &primary.name, primary.children.len());
+
+    let mut dyn_builder = hyperast::store::nodes::legion::dyn_builder::EntityBuilder::with_lang(
+        hyperast_gen_ts_ts::types::Lang,
+    );
+
+    let children_is_empty = primary.children.is_empty();
+
+    let metrics = primary.persist(&mut dyn_builder, interned_kind, label_id);
+    let metrics = metrics.map_hashs(|h| h.build());
+    let hashs = metrics.add_md_metrics(&mut dyn_builder, children_is_empty);
+    hashs.persist(&mut dyn_builder);
+
+    let vacant = insertion.vacant();
+    let node_id = hyperast::store::nodes::legion::NodeStore::insert_built_after_prepare(
+        vacant,
+        dyn_builder.build(),
+    );
+
+    (node_id, MD { metrics })
+}
+
+impl RepositoryProcessor {
+    fn help_handle_package_json(
+        &mut self,
+        oid: Oid,
+        parent_acc: &mut NpmModuleAcc,
+        name: ObjectName,
+        repository: &Repository,
+        parameters: PCP2Handle<NpmProc>,
+    ) -> Result<(), crate::ParseErr> {
+        let x = self
+            .processing_systems
+            .caching_blob_handler::<crate::processing::file_sys::PackageJson>()
+            .handle(oid, repository, &name, parameters, |c, n, t| {
+                let md_cache = &mut c
+                    .mut_or_default::<NpmProcessorHolder>()
+                    .with_parameters_mut(parameters.0)
+                    .package_json
+                    .md_cache;
+                let stores = self
+                    .main_stores
+                    .mut_with_ts::<hyperast_gen_ts_json::types::TStore>();
+                crate::npm::handle_package_json_file(&mut JsonTreeGen::new(stores, md_cache), n, t)
+                    .map_err(|_| crate::ParseErr::IllFormed)
+            })?;
+        let name = self.intern_object_name(&name);
+        assert!(!parent_acc.primary.children_names.contains(&name));
+        parent_acc.push_package_json(name, x);
+        Ok(())
+    }
+}
+
+/// sometimes order of files/dirs can be important, similarly to order of statement
+/// exploration order for example
+pub(crate) fn prepare_dir_exploration(
+    tree: git2::Tree,
+    dir_path: &mut Peekable<Components>,
+) -> Vec<BasicGitObject> {
+    let mut children_objects: Vec<BasicGitObject> = tree
+        .iter()
+        .map(TryInto::try_into)
+        .filter_map(|x| x.ok())
+        .collect();
+    if dir_path.peek().is_none() {
+        let p = children_objects.iter().position(|x| match x {
+            BasicGitObject::Blob(_, n) => crate::processing::file_sys::PackageJson::matches(n),
+            _ => false,
+        });
+        if let Some(p) = p {
+            children_objects.swap(0, p); // priority to config file processing
+            children_objects.reverse(); // we use it like a stack
+        }
+    }
+    children_objects
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub(crate) ts_handle: PCP2Handle<crate::ts_processor::TsProc>,
+}
+
+#[derive(Default)]
+pub(crate) struct NpmProcessorHolder(Vec<NpmProc>);
+pub(crate) struct NpmProc {
+    parameter: Parameter,
+    cache: crate::processing::caches::Npm,
+    package_json: crate::processing::caches::PackageJson,
+    commits: std::collections::HashMap<git2::Oid, crate::Commit>,
+}
+impl crate::processing::erased::Parametrized for NpmProcessorHolder {
+    type T = Parameter;
+    fn register_param(
+        &mut self,
+        t: Self::T,
+    ) -> crate::processing::erased::ParametrizedCommitProcessorHandle {
+        let l = self
+            .0
+            .iter()
+            .position(|x| &x.parameter == &t)
+            .unwrap_or_else(|| {
+                let l = self.0.len();
+                self.0.push(NpmProc {
+                    parameter: t,
+                    cache: Default::default(),
+                    package_json: Default::default(),
+                    commits: Default::default(),
+                });
+                l
+            });
+        use crate::processing::erased::ConfigParametersHandle;
+        use crate::processing::erased::ParametrizedCommitProc;
+        ParametrizedCommitProcessorHandle(self.erased_handle(), ConfigParametersHandle(l))
+    }
+}
+
+struct PreparedNpmCommitProc<'repo> {
+    repository: &'repo git2::Repository,
+    commit_builder: crate::preprocessed::CommitBuilder,
+    pub(crate) handle: ParametrizedCommitProcessorHandle,
+}
+impl<'repo> crate::processing::erased::PreparedCommitProc for PreparedNpmCommitProc<'repo> {
+    fn process(
+        self: Box<PreparedNpmCommitProc<'repo>>,
+        prepro: &mut RepositoryProcessor,
+    ) -> hyperast::store::defaults::NodeIdentifier {
+        let dir_path = PathBuf::from("");
+        let mut dir_path = dir_path.components().peekable();
+        let name = b"";
+        let root_full_node = NpmProcessor::<NpmModuleAcc>::new(
+            self.repository,
+            prepro,
+            &mut dir_path,
+            name,
+            self.commit_builder.tree_oid(),
+            self.handle,
+        )
+        .process();
+        let h = prepro
+            .processing_systems
+            .mut_or_default::<NpmProcessorHolder>();
+        let handle = self.handle;
+        let commit_oid = self.commit_builder.commit_oid();
+        let commit = self.commit_builder.finish(root_full_node.0);
+        h.with_parameters_mut(handle.1)
+            .commits
+            .insert(commit_oid, commit);
+        root_full_node.0
+    }
+}
+
+impl crate::processing::erased::CommitProc for NpmProc {
+    fn prepare_processing<'repo>(
+        &self,
+        repository: &'repo git2::Repository,
+        commit_builder: crate::preprocessed::CommitBuilder,
+        handle: crate::processing::ParametrizedCommitProcessorHandle,
+    ) -> Box<dyn crate::processing::erased::PreparedCommitProc + 'repo> {
+        Box::new(PreparedNpmCommitProc {
+            repository,
+            commit_builder,
+            handle,
+        })
+    }
+
+    fn get_commit(&self, commit_oid: git2::Oid) -> Option<&crate::Commit> {
+        self.commits.get(&commit_oid)
+    }
+
+    fn commit_count(&self) -> usize {
+        self.commits.len()
+    }
+
+    fn get_lang_handle(&self, lang: &str) -> Option<ParametrizedCommitProcessorHandle> {
+        if ["ts", "typescript", "js", "javascript"]
+            .iter()
+            .any(|x| lang.eq_ignore_ascii_case(x))
+        {
+            Some(ParametrizedCommitProcessorHandle(
+                CommitProcessorHandle(
+                    std::any::TypeId::of::<crate::ts_processor::TsProcessorHolder>(),
+                ),
+                self.parameter.ts_handle.0,
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl crate::processing::erased::CommitProcExt for NpmProc {
+    type Holder = NpmProcessorHolder;
+}
+
+impl crate::processing::erased::ParametrizedCommitProc2 for NpmProcessorHolder {
+    type Proc = NpmProc;
+
+    fn with_parameters_mut(
+        &mut self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &mut Self::Proc {
+        &mut self.0[parameters.0]
+    }
+
+    fn with_parameters(
+        &self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &Self::Proc {
+        &self.0[parameters.0]
+    }
+
+    fn persist(
+        &self,
+        _stores: &crate::SimpleStores,
+        mut w: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::write_u32;
+        write_u32(&mut w, self.0.len() as u32)?;
+        for proc in &self.0 {
+            crate::persist::write_commits(&mut w, &proc.commits)?;
+        }
+        Ok(())
+    }
+
+    fn restore(
+        &mut self,
+        _stores: &crate::SimpleStores,
+        mut r: &mut dyn std::io::Read,
+    ) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::read_u32_from;
+        let len = read_u32_from(&mut r)? as usize;
+        if len > self.0.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "more persisted processors than registered ones",
+            ));
+        }
+        for proc in &mut self.0[..len] {
+            proc.commits = crate::persist::read_commits(&mut r)?;
+        }
+        Ok(())
+    }
+
+    fn commit_roots(&self) -> Vec<(git2::Oid, hyperast::store::defaults::NodeIdentifier)> {
+        self.0
+            .iter()
+            .flat_map(|proc| proc.commits.iter().map(|(oid, c)| (*oid, c.ast_root)))
+            .collect()
+    }
+
+    fn retain_reachable(&mut self, reachable: &hyperast::store::nodes::legion::gc::Reachable) {
+        for proc in &mut self.0 {
+            proc.commits.retain(|_, c| reachable.contains(&c.ast_root));
+            proc.cache.retain_reachable(reachable);
+            proc.package_json.retain_reachable(reachable);
+        }
+    }
+}
+
+impl CacheHolding<crate::processing::caches::Npm> for NpmProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::Npm {
+        &mut self.cache
+    }
+    fn get_caches(&self) -> &crate::processing::caches::Npm {
+        &self.cache
+    }
+}
+
+impl CacheHolding<crate::processing::caches::PackageJson> for NpmProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::PackageJson {
+        &mut self.package_json
+    }
+    fn get_caches(&self) -> &crate::processing::caches::PackageJson {
+        &self.package_json
+    }
+}
@@ -0,0 +1,292 @@
+//! Snapshots of processed repositories,
+//! made of the stores (see [`hyperast::store::nodes::legion::persist`]),
+//! the processed commits and the blob caches of each processor.
+//!
+//! Processors are restored in place,
+//! so configs must be registered (in the same order) before loading a snapshot.
+
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::io::{Read, Write};
+
+use hyperast::store::nodes::legion::persist::{
+    self, ComponentRegistry, read_bytes, read_id, read_u32_from, read_u64_from, write_bytes,
+    write_id, write_u32, write_u64,
+};
+
+use crate::Commit;
+use crate::preprocessed::{PreProcessedRepository, RepositoryProcessor};
+use crate::processing::ObjectName;
+
+/// Components of the languages supported by the enabled features.
+pub fn component_registry() -> ComponentRegistry {
+    let mut registry = ComponentRegistry::new();
+    #[cfg(feature = "java")]
+    hyperast_gen_ts_java::legion_with_refs::register_persisted_components(&mut registry);
+    #[cfg(feature = "cpp")]
+    hyperast_gen_ts_cpp::legion::register_persisted_components(&mut registry);
+    hyperast_gen_ts_xml::legion::register_persisted_components(&mut registry);
+    #[cfg(feature = "ts")]
+    hyperast_gen_ts_ts::legion::register_persisted_components(&mut registry);
+    #[cfg(feature = "make")]
+    hyperast_gen_ts_make::legion::register_persisted_components(&mut registry);
+    #[cfg(feature = "json")]
+    hyperast_gen_ts_json::legion::register_persisted_components(&mut registry);
+    #[cfg(feature = "python")]
+    hyperast_gen_ts_python::legion::register_persisted_components(&mut registry);
+    #[cfg(feature = "rust")]
+    hyperast_gen_ts_rust::legion::register_persisted_components(&mut registry);
+    // SAFETY: an enumset is a bitset
+    unsafe {
+        registry.register_raw::<hyperast::store::nodes::compo::Flags<
+            enumset::EnumSet<crate::maven::SemFlag>,
+        >>();
+    }
+    #[cfg(feature = "gradle")]
+    // SAFETY: an enumset is a bitset
+    unsafe {
+        registry.register_raw::<hyperast::store::nodes::compo::Flags<
+            enumset::EnumSet<crate::gradle::SemFlag>,
+        >>();
+    }
+    #[cfg(feature = "cmake")]
+    // SAFETY: an enumset is a bitset
+    unsafe {
+        registry.register_raw::<hyperast::store::nodes::compo::Flags<
+            enumset::EnumSet<crate::cmake::SemFlag>,
+        >>();
+    }
+    #[cfg(feature = "pip")]
+    // SAFETY: an enumset is a bitset
+    unsafe {
+        registry.register_raw::<hyperast::store::nodes::compo::Flags<
+            enumset::EnumSet<crate::pip::SemFlag>,
+        >>();
+    }
+    #[cfg(feature = "cargo")]
+    // SAFETY: an enumset is a bitset
+    unsafe {
+        registry.register_raw::<hyperast::store::nodes::compo::Flags<
+            enumset::EnumSet<crate::cargo::SemFlag>,
+        >>();
+    }
+    registry
+}
+
+impl RepositoryProcessor {
+    pub fn save(&self, w: &mut impl Write) -> Result<(), persist::Error> {
+        let registry = component_registry();
+        self.main_stores.save(&registry, w)?;
+        let procs: Vec<_> = self.processing_systems.values().collect();
+        write_u32(w, procs.len() as u32)?;
+        for p in procs {
+            let mut buf = vec![];
+            p.persist(&self.main_stores, &mut buf)?;
+            write_bytes(w, p.type_name().as_bytes())?;
+            write_bytes(w, &buf)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the stores and restores the state of already registered processors,
+    /// the state of processors that are not registered is dropped.
+    pub fn load(&mut self, r: &mut impl Read) -> Result<(), persist::Error> {
+        let registry = component_registry();
+        self.main_stores = crate::SimpleStores::load(&registry, r)?;
+        let len = read_u32_from(r)?;
+        for _ in 0..len {
+            let name = read_bytes(r)?;
+            let buf = read_bytes(r)?;
+            let proc = self
+                .processing_systems
+                .values_mut()
+                .find(|p| p.type_name().as_bytes() == name);
+            let Some(proc) = proc else {
+                log::warn!(
+                    "dropping the snapshot of {}, it is not registered",
+                    String::from_utf8_lossy(&name)
+                );
+                continue;
+            };
+            proc.restore(&self.main_stores, &mut buf.as_slice())?;
+        }
+        Ok(())
+    }
+}
+
+impl crate::multi_preprocessed::PreProcessedRepositories {
+    pub fn save(&self, w: &mut impl Write) -> Result<(), persist::Error> {
+        self.processor.save(w)
+    }
+
+    /// Configs should be registered beforehand, see [`RepositoryProcessor::load`].
+    pub fn load(&mut self, r: &mut impl Read) -> Result<(), persist::Error> {
+        self.processor.load(r)
+    }
+}
+
+impl PreProcessedRepository {
+    pub fn save(&self, w: &mut impl Write) -> Result<(), persist::Error> {
+        self.processor.save(w)?;
+        write_commits(w, &self.commits)?;
+        Ok(())
+    }
+
+    pub fn load(&mut self, r: &mut impl Read) -> Result<(), persist::Error> {
+        self.processor.load(r)?;
+        self.commits = read_commits(r)?;
+        Ok(())
+    }
+}
+
+pub(crate) fn write_commits<S>(
+    w: &mut impl Write,
+    commits: &HashMap<git2::Oid, Commit, S>,
+) -> std::io::Result<()> {
+    write_u64(w, commits.len() as u64)?;
+    for (oid, commit) in commits {
+        write_oid(w, oid)?;
+        write_u32(w, commit.parents.len() as u32)?;
+        for p in &commit.parents {
+            write_oid(w, p)?;
+        }
+        w.write_all(&commit.processing_time.to_le_bytes())?;
+        write_u64(w, commit.memory_used.bytes() as u64)?;
+        write_id(w, commit.ast_root)?;
+        write_oid(w, &commit.tree_oid)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn read_commits<S: BuildHasher + Default>(
+    r: &mut impl Read,
+) -> std::io::Result<HashMap<git2::Oid, Commit, S>> {
+    let len = read_u64_from(r)? as usize;
+    let mut commits = HashMap::with_capacity_and_hasher(len, S::default());
+    for _ in 0..len {
+        let oid = read_oid(r)?;
+        let parents = (0..read_u32_from(r)?)
+            .map(|_| read_oid(r))
+            .collect::<Result<_, _>>()?;
+        let mut b = [0; 16];
+        r.read_exact(&mut b)?;
+        let processing_time = u128::from_le_bytes(b);
+        let memory_used = (read_u64_from(r)? as isize).into();
+        let ast_root = read_id(r)?;
+        let tree_oid = read_oid(r)?;
+        commits.insert(
+            oid,
+            Commit {
+                parents,
+                processing_time,
+                memory_used,
+                ast_root,
+                tree_oid,
+            },
+        );
+    }
+    Ok(commits)
+}
+
+pub(crate) fn write_oid(w: &mut impl Write, oid: &git2::Oid) -> std::io::Result<()> {
+    w.write_all(oid.as_bytes())
+}
+
+pub(crate) fn read_oid(r: &mut impl Read) -> std::io::Result<git2::Oid> {
+    let mut b = [0; 20];
+    r.read_exact(&mut b)?;
+    git2::Oid::from_bytes(&b).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Writes a blob cache, values that cannot be persisted are skipped,
+/// they will just be processed again.
+pub(crate) fn write_named_map<V>(
+    w: &mut impl Write,
+    map: &crate::processing::caches::NamedMap<(V,)>,
+    save: impl Fn(&V, &mut Vec<u8>) -> std::io::Result<bool>,
+) -> std::io::Result<()> {
+    let mut count = 0u64;
+    let mut buf = vec![];
+    for ((oid, name), (v,)) in map {
+        let mut entry = vec![];
+        if save(v, &mut entry)? {
+            write_oid(&mut buf, oid)?;
+            write_bytes(&mut buf, name.as_bytes())?;
+            buf.extend(entry);
+            count += 1;
+        }
+    }
+    write_u64(w, count)?;
+    w.write_all(&buf)
+}
+
+pub(crate) fn read_named_map<V, R: Read>(
+    r: &mut R,
+    load: impl Fn(&mut R) -> std::io::Result<V>,
+) -> std::io::Result<crate::processing::caches::NamedMap<(V,)>> {
+    let len = read_u64_from(r)?;
+    let mut map = crate::processing::caches::NamedMap::default();
+    for _ in 0..len {
+        let oid = read_oid(r)?;
+        let name = ObjectName::from(read_bytes(r)?.as_slice());
+        let v = load(r)?;
+        map.insert((oid, name), (v,));
+    }
+    Ok(map)
+}
+
+/// Writes the metadata cache of a generator along with its deduplication map.
+/// Nodes whose metadata cannot be persisted are also removed from the deduplication map,
+/// as generators expect metadata for each deduplicated node.
+pub(crate) fn write_md_cache<'a, MD: 'a>(
+    w: &mut impl Write,
+    md_cache: impl IntoIterator<Item = (&'a hyperast::store::defaults::NodeIdentifier, &'a MD)>,
+    dedup: &hyperast::store::nodes::legion::DedupMap,
+    save: impl Fn(&MD, &mut Vec<u8>) -> std::io::Result<bool>,
+) -> std::io::Result<()> {
+    let mut count = 0u64;
+    let mut buf = vec![];
+    let mut skipped = std::collections::HashSet::new();
+    for (id, md) in md_cache {
+        let mut entry = vec![];
+        if save(md, &mut entry)? {
+            write_id(&mut buf, *id)?;
+            buf.extend(entry);
+            count += 1;
+        } else {
+            skipped.insert(*id);
+        }
+    }
+    write_u64(w, count)?;
+    w.write_all(&buf)?;
+    // same layout as NodeStoreInner::save_dedup
+    let ids: Vec<_> = dedup.0.keys().filter(|x| !skipped.contains(*x)).collect();
+    write_u64(w, ids.len() as u64)?;
+    for id in ids {
+        write_id(w, *id)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn read_md_cache<MD, M, R: Read>(
+    r: &mut R,
+    stores: &crate::SimpleStores,
+    load: impl Fn(&mut R) -> std::io::Result<MD>,
+) -> Result<(M, hyperast::store::nodes::legion::DedupMap), persist::Error>
+where
+    M: FromIterator<(hyperast::store::defaults::NodeIdentifier, MD)>,
+{
+    let len = read_u64_from(r)?;
+    let md_cache = (0..len)
+        .map(|_| Ok((read_id(r)?, load(r)?)))
+        .collect::<std::io::Result<M>>()?;
+    let dedup = hyperast::store::nodes::legion::DedupMap::load(&stores.node_store.inner, r)?;
+    Ok((md_cache, dedup))
+}
+
+pub(crate) fn to_io_error(e: persist::Error) -> std::io::Error {
+    match e {
+        persist::Error::Io(e) => e,
+        e => std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+    }
+}
@@ -0,0 +1,119 @@
+use enumset::EnumSet;
+use hyperast::store::defaults::{LabelIdentifier, NodeIdentifier};
+use hyperast_gen_ts_python::legion as python_tree_gen;
+
+use crate::processing::{InFiles, ObjectName};
+use crate::{Accumulator, BasicDirAcc, DefaultMetrics};
+
+#[derive(Debug, Clone)]
+pub struct MD {
+    pub(crate) metrics: DefaultMetrics,
+    pub(crate) status: EnumSet<SemFlag>,
+}
+
+#[derive(enumset::EnumSetType, Debug)]
+pub enum SemFlag {
+    /// contains a `pyproject.toml` or a `setup.py`
+    IsPythonProject,
+    /// contains an `__init__.py`
+    IsPythonPackage,
+    HoldPythonPackage,
+}
+
+/// Flags a directory from its entries,
+/// looking one level down to also catch the src/ layout and collections of packages.
+pub(crate) fn package_status(repository: &git2::Repository, tree: &git2::Tree) -> EnumSet<SemFlag> {
+    let mut status = EnumSet::new();
+    for entry in tree.iter() {
+        let name: ObjectName = entry.name_bytes().into();
+        match entry.kind() {
+            Some(git2::ObjectType::Blob) => {
+                if crate::processing::file_sys::PyProject::matches(&name) {
+                    status |= SemFlag::IsPythonProject;
+                } else if crate::processing::file_sys::PyInit::matches(&name) {
+                    status |= SemFlag::IsPythonPackage;
+                }
+            }
+            Some(git2::ObjectType::Tree) if !crate::python_processor::is_ignored_dir(&name) => {
+                let Ok(sub) = repository.find_tree(entry.id()) else {
+                    continue;
+                };
+                let holds_package = sub.iter().any(|x| {
+                    x.kind() == Some(git2::ObjectType::Blob)
+                        && crate::processing::file_sys::PyInit::matches(&x.name_bytes().into())
+                });
+                if holds_package {
+                    status |= SemFlag::HoldPythonPackage;
+                }
+            }
+            _ => (),
+        }
+    }
+    status
+}
+
+pub struct PipModuleAcc {
+    pub(crate) primary: BasicDirAcc<NodeIdentifier, LabelIdentifier, DefaultMetrics>,
+    pub(crate) status: EnumSet<SemFlag>,
+}
+
+impl From<String> for PipModuleAcc {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl PipModuleAcc {
+    pub(crate) fn new(name: String) -> Self {
+        Self {
+            primary: BasicDirAcc::new(name),
+            status: EnumSet::new(),
+        }
+    }
+    pub(crate) fn with_status(name: String, status: EnumSet<SemFlag>) -> Self {
+        Self {
+            primary: BasicDirAcc::new(name),
+            status,
+        }
+    }
+}
+
+impl PipModuleAcc {
+    pub fn push_submodule(&mut self, name: LabelIdentifier, full_node: (NodeIdentifier, MD)) {
+        if full_node.1.status.contains(SemFlag::IsPythonPackage)
+            || full_node.1.status.contains(SemFlag::HoldPythonPackage)
+        {
+            self.status |= SemFlag::HoldPythonPackage;
+        }
+        self.primary.children.push(full_node.0);
+        self.primary.children_names.push(name);
+        self.primary.metrics.acc(full_node.1.metrics);
+    }
+    pub(crate) fn push_source_file(
+        &mut self,
+        name: LabelIdentifier,
+        full_node: python_tree_gen::Local,
+    ) {
+        self.primary
+            .push(name, full_node.compressed_node, full_node.metrics);
+    }
+    pub(crate) fn push_source_directory(
+        &mut self,
+        name: LabelIdentifier,
+        full_node: python_tree_gen::Local,
+    ) {
+        self.primary
+            .push(name, full_node.compressed_node, full_node.metrics);
+    }
+}
+
+impl hyperast::tree_gen::Accumulator for PipModuleAcc {
+    type Node = (LabelIdentifier, (NodeIdentifier, MD));
+    fn push(&mut self, (name, full_node): Self::Node) {
+        self.push_submodule(name, full_node);
+    }
+}
+
+impl Accumulator for PipModuleAcc {
+    type Unlabeled = (NodeIdentifier, MD);
+}
@@ -8,7 +8,11 @@ use std::{
 
 use git2::{Oid, Repository};
 use hyperast::{
-    store::{defaults::LabelIdentifier, nodes::DefaultNodeIdentifier as NodeIdentifier},
+    store::{
+        defaults::LabelIdentifier,
+        nodes::DefaultNodeIdentifier as NodeIdentifier,
+        nodes::legion::gc::{GcStats, Reachable},
+    },
     types::{AnyType, Childrn, LabelStore as _, WithChildren},
     utils::memusage,
 };
@@ -73,6 +77,36 @@ impl RepositoryProcessor {
     pub fn purge_caches(&mut self) {
         self.processing_systems.clear();
     }
+
+    /// Frees the nodes that are neither reachable from the roots of the processed commits satisfying `keep`
+    /// nor from `extra_roots`, the other processed commits are forgotten.
+    pub fn collect_garbage(
+        &mut self,
+        keep: impl Fn(&git2::Oid) -> bool,
+        extra_roots: impl IntoIterator<Item = NodeIdentifier>,
+    ) -> (Reachable, GcStats) {
+        let mut roots: Vec<_> = (self.processing_systems.values())
+            .flat_map(|p| p.commit_roots())
+            .filter(|(oid, _)| keep(oid))
+            .map(|(_, root)| root)
+            .collect();
+        roots.extend(extra_roots);
+        self.collect_garbage_from(roots)
+    }
+
+    /// Only keeps the nodes reachable from `roots`,
+    /// processed commits whose root is freed are forgotten,
+    /// processor caches are purged accordingly.
+    pub fn collect_garbage_from(
+        &mut self,
+        roots: impl IntoIterator<Item = NodeIdentifier>,
+    ) -> (Reachable, GcStats) {
+        let (reachable, stats) = self.main_stores.node_store.collect_garbage(roots);
+        for p in self.processing_systems.values_mut() {
+            p.retain_reachable(&reachable);
+        }
+        (reachable, stats)
+    }
 }
 
 impl PreProcessedRepository {
@@ -92,6 +126,13 @@ impl PreProcessedRepository {
         self.processor.purge_caches()
     }
 
+    /// Frees the nodes that are not reachable from processed commits.
+    pub fn collect_garbage(&mut self) -> GcStats {
+        let roots: Vec<_> = self.commits.values().map(|c| c.ast_root).collect();
+        let (_, stats) = self.processor.collect_garbage(|_| true, roots);
+        stats
+    }
+
     pub fn child_by_name(&self, d: NodeIdentifier, name: &str) -> Option<NodeIdentifier> {
         self.processor.child_by_name(d, name)
     }
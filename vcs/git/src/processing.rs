@@ -9,16 +9,24 @@ pub use erased::ParametrizedCommitProcessorHandle;
 
 pub enum BuildSystem {
     Maven,
+    Gradle,
     Make,
+    CMake,
     Npm,
+    Pip,
+    Cargo,
     None,
 }
 
 pub enum ProcessingConfig<P> {
     Java { limit: usize, dir_path: P },
     JavaMaven { limit: usize, dir_path: P },
+    JavaGradle { limit: usize, dir_path: P },
     CppMake { limit: usize, dir_path: P },
+    CppCMake { limit: usize, dir_path: P },
     TsNpm { limit: usize, dir_path: P },
+    PythonPip { limit: usize, dir_path: P },
+    RustCargo { limit: usize, dir_path: P },
     Any { limit: usize, dir_path: P },
 }
 
@@ -28,8 +36,12 @@ pub enum ProcessingConfig<P> {
 pub enum RepoConfig {
     Java,
     CppMake,
+    CppCMake,
     JavaMaven,
+    JavaGradle,
     TsNpm,
+    PythonPip,
+    RustCargo,
     Any,
 }
 
@@ -41,13 +53,26 @@ impl std::str::FromStr for RepoConfig {
             "CppMake" => Self::CppMake,
             "Cpp" => Self::CppMake,
             "cpp" => Self::CppMake,
+            "CppCMake" => Self::CppCMake,
+            "cmake" => Self::CppCMake,
             "JavaMaven" => Self::JavaMaven,
             "Java" => Self::JavaMaven,
             "java" => Self::JavaMaven,
+            "JavaGradle" => Self::JavaGradle,
+            "gradle" => Self::JavaGradle,
             "typescript" => Self::TsNpm,
             "javascript" => Self::TsNpm,
             "Ts" => Self::TsNpm,
             "ts" => Self::TsNpm,
+            "PythonPip" => Self::PythonPip,
+            "Python" => Self::PythonPip,
+            "python" => Self::PythonPip,
+            "py" => Self::PythonPip,
+            "pip" => Self::PythonPip,
+            "RustCargo" => Self::RustCargo,
+            "Rust" => Self::RustCargo,
+            "rust" => Self::RustCargo,
+            "cargo" => Self::RustCargo,
             "any" => Self::Any,
             x => return Err(format!("'{}' is not anvailable config", x)),
         })
@@ -61,16 +86,38 @@ impl From<&RepoConfig> for ProcessingConfig<&'static str> {
                 limit: 3,
                 dir_path: "",
             },
+            RepoConfig::CppCMake => Self::CppCMake {
+                limit: 3,
+                dir_path: "",
+            },
             RepoConfig::JavaMaven => Self::JavaMaven {
                 limit: 3,
                 dir_path: "",
             },
+            RepoConfig::JavaGradle => Self::JavaGradle {
+                limit: 3,
+                dir_path: "",
+            },
             RepoConfig::Java => Self::Java {
                 limit: 3,
                 dir_path: "",
             },
-            RepoConfig::TsNpm => todo!(),
-            RepoConfig::Any => todo!(),
+            RepoConfig::TsNpm => Self::TsNpm {
+                limit: 3,
+                dir_path: "",
+            },
+            RepoConfig::PythonPip => Self::PythonPip {
+                limit: 3,
+                dir_path: "",
+            },
+            RepoConfig::RustCargo => Self::RustCargo {
+                limit: 3,
+                dir_path: "",
+            },
+            RepoConfig::Any => Self::Any {
+                limit: 3,
+                dir_path: "",
+            },
         }
     }
 }
@@ -261,6 +308,7 @@ impl TryInto<String> for ObjectName {
 pub(crate) mod caches {
 
     use hyperast::store::defaults::NodeIdentifier;
+    use hyperast::store::nodes::legion::gc::Reachable;
 
     use super::ObjectName;
 
@@ -304,6 +352,38 @@ pub(crate) mod caches {
         }
     }
 
+    impl Java {
+        /// Metadata and locals holding a reference analysis are not persisted.
+        pub(crate) fn save(&self, w: &mut impl std::io::Write) -> std::io::Result<()> {
+            crate::persist::write_md_cache(w, &self.md_cache, &self.dedup, |md, buf| {
+                md.save(buf)
+            })?;
+            crate::persist::write_named_map(w, &self.object_map, |l, buf| l.save(buf))
+        }
+
+        pub(crate) fn load(
+            stores: &crate::SimpleStores,
+            r: &mut impl std::io::Read,
+        ) -> std::io::Result<Self> {
+            use hyperast_gen_ts_java::legion_with_refs::{Local, MD};
+            let (md_cache, dedup) = crate::persist::read_md_cache(r, stores, |r| MD::load(r))
+                .map_err(crate::persist::to_io_error)?;
+            let object_map = crate::persist::read_named_map(r, |r| Local::load(r))?;
+            Ok(Self {
+                md_cache,
+                dedup,
+                object_map,
+            })
+        }
+
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.md_cache.retain(|id, _| reachable.contains(id));
+            self.dedup.retain_reachable(reachable);
+            self.object_map
+                .retain(|_, (l,)| reachable.contains(&l.compressed_node));
+        }
+    }
+
     #[derive(Default)]
     pub struct Cpp {
         pub(crate) md_cache: hyperast_gen_ts_cpp::legion::MDCache,
@@ -325,9 +405,50 @@ pub(crate) mod caches {
         }
     }
 
+    impl Cpp {
+        /// Metadata and locals holding a partial analysis are not persisted.
+        pub(crate) fn save(&self, w: &mut impl std::io::Write) -> std::io::Result<()> {
+            crate::persist::write_md_cache(w, &self.md_cache, &self.dedup, |md, buf| {
+                md.save(buf)
+            })?;
+            crate::persist::write_named_map(w, &self.object_map, |l, buf| l.save(buf))
+        }
+
+        pub(crate) fn load(
+            stores: &crate::SimpleStores,
+            r: &mut impl std::io::Read,
+        ) -> std::io::Result<Self> {
+            use hyperast_gen_ts_cpp::legion::{Local, MD};
+            let (md_cache, dedup) = crate::persist::read_md_cache(r, stores, |r| MD::load(r))
+                .map_err(crate::persist::to_io_error)?;
+            let object_map = crate::persist::read_named_map(r, |r| Local::load(r))?;
+            Ok(Self {
+                md_cache,
+                dedup,
+                object_map,
+            })
+        }
+
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.md_cache.retain(|id, _| reachable.contains(id));
+            self.dedup.retain_reachable(reachable);
+            self.object_map
+                .retain(|_, (l,)| reachable.contains(&l.compressed_node));
+        }
+    }
+
     #[derive(Default)]
     pub struct Maven {
         pub object_map: OidMap<(NodeIdentifier, crate::maven::MD)>,
+        /// what the pom.xml of each module declares, keyed by module directory
+        pub modules: hyperast::compat::HashMap<NodeIdentifier, crate::maven::MavenModule>,
+    }
+
+    impl Maven {
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.object_map.retain(|_, (id, _)| reachable.contains(id));
+            self.modules.retain(|id, _| reachable.contains(id));
+        }
     }
 
     #[derive(Default)]
@@ -335,6 +456,13 @@ pub(crate) mod caches {
         pub object_map: OidMap<crate::maven::POM>,
     }
 
+    impl Pom {
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.object_map
+                .retain(|_, pom| reachable.contains(&pom.compressed_node));
+        }
+    }
+
     impl super::ObjectMapper for Pom {
         type K = git2::Oid;
 
@@ -349,16 +477,71 @@ pub(crate) mod caches {
         }
     }
 
+    #[cfg(feature = "gradle")]
+    #[derive(Default)]
+    pub struct Gradle {
+        /// keyed by the tree and what it is given by the parent project, see [`crate::gradle::Inherited`],
+        /// as it changes which directories are explored
+        pub object_map: hyperast::compat::HashMap<
+            (git2::Oid, crate::gradle::Inherited),
+            (NodeIdentifier, crate::gradle::MD),
+        >,
+    }
+
+    #[cfg(feature = "gradle")]
+    impl Gradle {
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.object_map.retain(|_, (id, _)| reachable.contains(id));
+        }
+    }
+
+    /// Scripts are not part of the HyperAST so there is nothing to collect.
+    #[cfg(feature = "gradle")]
+    #[derive(Default)]
+    pub struct GradleScript {
+        pub object_map: OidMap<crate::gradle::GradleScript>,
+    }
+
+    #[cfg(feature = "gradle")]
+    impl super::ObjectMapper for GradleScript {
+        type K = git2::Oid;
+
+        type V = crate::gradle::GradleScript;
+
+        fn get(&self, key: &Self::K) -> Option<&Self::V> {
+            self.object_map.get(key)
+        }
+
+        fn insert(&mut self, key: Self::K, value: Self::V) -> Option<Self::V> {
+            self.object_map.insert(key, value)
+        }
+    }
+
     #[derive(Default)]
     pub struct Make {
         pub object_map: OidMap<(NodeIdentifier, crate::make::MD)>,
     }
 
+    impl Make {
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.object_map.retain(|_, (id, _)| reachable.contains(id));
+        }
+    }
+
     #[derive(Default)]
     pub struct Makefile {
+        pub(crate) md_cache: hyperast_gen_ts_make::legion::MDCache,
         pub object_map: OidMap<crate::make::MakeFile>,
     }
 
+    impl Makefile {
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.md_cache.retain(|id, _| reachable.contains(id));
+            self.object_map
+                .retain(|_, mf| reachable.contains(&mf.compressed_node));
+        }
+    }
+
     impl super::ObjectMapper for Makefile {
         type K = git2::Oid;
 
@@ -373,6 +556,302 @@ pub(crate) mod caches {
         }
     }
 
+    #[cfg(feature = "cmake")]
+    #[derive(Default)]
+    pub struct CMake {
+        /// keyed by the tree and the subdirectories given by the parent lists,
+        /// as they change which directories are explored
+        pub object_map: hyperast::compat::HashMap<
+            (git2::Oid, Vec<std::path::PathBuf>),
+            (NodeIdentifier, crate::cmake::MD),
+        >,
+    }
+
+    #[cfg(feature = "cmake")]
+    impl CMake {
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.object_map.retain(|_, (id, _)| reachable.contains(id));
+        }
+    }
+
+    /// Lists files are not part of the HyperAST so there is nothing to collect.
+    #[cfg(feature = "cmake")]
+    #[derive(Default)]
+    pub struct CMakeLists {
+        pub object_map: OidMap<crate::cmake::CMakeLists>,
+    }
+
+    #[cfg(feature = "cmake")]
+    impl super::ObjectMapper for CMakeLists {
+        type K = git2::Oid;
+
+        type V = crate::cmake::CMakeLists;
+
+        fn get(&self, key: &Self::K) -> Option<&Self::V> {
+            self.object_map.get(key)
+        }
+
+        fn insert(&mut self, key: Self::K, value: Self::V) -> Option<Self::V> {
+            self.object_map.insert(key, value)
+        }
+    }
+
+    #[cfg(feature = "ts")]
+    #[derive(Default)]
+    pub struct Ts {
+        pub(crate) md_cache: hyperast_gen_ts_ts::legion::MDCache,
+        pub object_map: NamedMap<(hyperast_gen_ts_ts::legion::Local,)>,
+    }
+
+    #[cfg(feature = "ts")]
+    impl super::ObjectMapper for Ts {
+        type K = (git2::Oid, ObjectName);
+
+        type V = (hyperast_gen_ts_ts::legion::Local,);
+
+        fn get(&self, key: &Self::K) -> Option<&Self::V> {
+            self.object_map.get(key)
+        }
+
+        fn insert(&mut self, key: Self::K, value: Self::V) -> Option<Self::V> {
+            self.object_map.insert(key, value)
+        }
+    }
+
+    #[cfg(feature = "ts")]
+    impl Ts {
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.md_cache.retain(|id, _| reachable.contains(id));
+            self.object_map
+                .retain(|_, (l,)| reachable.contains(&l.compressed_node));
+        }
+    }
+
+    #[cfg(feature = "npm")]
+    #[derive(Default)]
+    pub struct Npm {
+        /// keyed by the tree and the workspace globs inherited from the parent package,
+        /// as they change which directories are explored as packages
+        pub object_map: hyperast::compat::HashMap<
+            (git2::Oid, Vec<std::path::PathBuf>),
+            (NodeIdentifier, crate::npm::MD),
+        >,
+    }
+
+    #[cfg(feature = "npm")]
+    impl Npm {
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.object_map.retain(|_, (id, _)| reachable.contains(id));
+        }
+    }
+
+    #[cfg(feature = "npm")]
+    #[derive(Default)]
+    pub struct PackageJson {
+        pub(crate) md_cache: hyperast_gen_ts_json::legion::MDCache,
+        pub object_map: OidMap<crate::npm::PackageJson>,
+    }
+
+    #[cfg(feature = "npm")]
+    impl PackageJson {
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.md_cache.retain(|id, _| reachable.contains(id));
+            self.object_map
+                .retain(|_, p| reachable.contains(&p.compressed_node));
+        }
+    }
+
+    #[cfg(feature = "npm")]
+    impl super::ObjectMapper for PackageJson {
+        type K = git2::Oid;
+
+        type V = crate::npm::PackageJson;
+
+        fn get(&self, key: &Self::K) -> Option<&Self::V> {
+            self.object_map.get(key)
+        }
+
+        fn insert(&mut self, key: Self::K, value: Self::V) -> Option<Self::V> {
+            self.object_map.insert(key, value)
+        }
+    }
+
+    #[cfg(feature = "python")]
+    #[derive(Default)]
+    pub struct Python {
+        pub(crate) md_cache: hyperast_gen_ts_python::legion::MDCache,
+        pub(crate) dedup: hyperast::store::nodes::legion::DedupMap,
+        pub object_map: NamedMap<(hyperast_gen_ts_python::legion::Local,)>,
+    }
+
+    #[cfg(feature = "python")]
+    impl super::ObjectMapper for Python {
+        type K = (git2::Oid, ObjectName);
+
+        type V = (hyperast_gen_ts_python::legion::Local,);
+
+        fn get(&self, key: &Self::K) -> Option<&Self::V> {
+            self.object_map.get(key)
+        }
+
+        fn insert(&mut self, key: Self::K, value: Self::V) -> Option<Self::V> {
+            self.object_map.insert(key, value)
+        }
+    }
+
+    #[cfg(feature = "python")]
+    impl Python {
+        pub(crate) fn save(&self, w: &mut impl std::io::Write) -> std::io::Result<()> {
+            crate::persist::write_md_cache(w, &self.md_cache, &self.dedup, |md, buf| md.save(buf))?;
+            crate::persist::write_named_map(w, &self.object_map, |l, buf| l.save(buf))
+        }
+
+        pub(crate) fn load(
+            stores: &crate::SimpleStores,
+            r: &mut impl std::io::Read,
+        ) -> std::io::Result<Self> {
+            use hyperast_gen_ts_python::legion::{Local, MD};
+            let (md_cache, dedup) = crate::persist::read_md_cache(r, stores, |r| MD::load(r))
+                .map_err(crate::persist::to_io_error)?;
+            let object_map = crate::persist::read_named_map(r, |r| Local::load(r))?;
+            Ok(Self {
+                md_cache,
+                dedup,
+                object_map,
+            })
+        }
+
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.md_cache.retain(|id, _| reachable.contains(id));
+            self.dedup.retain_reachable(reachable);
+            self.object_map
+                .retain(|_, (l,)| reachable.contains(&l.compressed_node));
+        }
+    }
+
+    #[cfg(feature = "pip")]
+    #[derive(Default)]
+    pub struct Pip {
+        pub object_map: NamedMap<(NodeIdentifier, crate::pip::MD)>,
+    }
+
+    #[cfg(feature = "pip")]
+    impl Pip {
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.object_map.retain(|_, (id, _)| reachable.contains(id));
+        }
+    }
+
+    #[cfg(feature = "rust")]
+    #[derive(Default)]
+    pub struct Rust {
+        pub(crate) md_cache: hyperast_gen_ts_rust::legion::MDCache,
+        pub(crate) dedup: hyperast::store::nodes::legion::DedupMap,
+        pub object_map: NamedMap<(hyperast_gen_ts_rust::legion::Local,)>,
+    }
+
+    #[cfg(feature = "rust")]
+    impl super::ObjectMapper for Rust {
+        type K = (git2::Oid, ObjectName);
+
+        type V = (hyperast_gen_ts_rust::legion::Local,);
+
+        fn get(&self, key: &Self::K) -> Option<&Self::V> {
+            self.object_map.get(key)
+        }
+
+        fn insert(&mut self, key: Self::K, value: Self::V) -> Option<Self::V> {
+            self.object_map.insert(key, value)
+        }
+    }
+
+    #[cfg(feature = "rust")]
+    impl Rust {
+        pub(crate) fn save(&self, w: &mut impl std::io::Write) -> std::io::Result<()> {
+            crate::persist::write_md_cache(w, &self.md_cache, &self.dedup, |md, buf| md.save(buf))?;
+            crate::persist::write_named_map(w, &self.object_map, |l, buf| l.save(buf))
+        }
+
+        pub(crate) fn load(
+            stores: &crate::SimpleStores,
+            r: &mut impl std::io::Read,
+        ) -> std::io::Result<Self> {
+            use hyperast_gen_ts_rust::legion::{Local, MD};
+            let (md_cache, dedup) = crate::persist::read_md_cache(r, stores, |r| MD::load(r))
+                .map_err(crate::persist::to_io_error)?;
+            let object_map = crate::persist::read_named_map(r, |r| Local::load(r))?;
+            Ok(Self {
+                md_cache,
+                dedup,
+                object_map,
+            })
+        }
+
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.md_cache.retain(|id, _| reachable.contains(id));
+            self.dedup.retain_reachable(reachable);
+            self.object_map
+                .retain(|_, (l,)| reachable.contains(&l.compressed_node));
+        }
+    }
+
+    #[cfg(feature = "cargo")]
+    #[derive(Default)]
+    pub struct Cargo {
+        /// keyed by the tree, its name and the member globs and exclusions inherited from the parent workspace,
+        /// as they change which directories are explored as crates
+        pub object_map: hyperast::compat::HashMap<
+            (
+                git2::Oid,
+                ObjectName,
+                (Vec<std::path::PathBuf>, Vec<std::path::PathBuf>),
+            ),
+            (NodeIdentifier, crate::cargo::MD),
+        >,
+    }
+
+    #[cfg(feature = "cargo")]
+    impl Cargo {
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.object_map.retain(|_, (id, _)| reachable.contains(id));
+        }
+    }
+
+    /// Manifests are not part of the HyperAST so there is nothing to collect.
+    #[cfg(feature = "cargo")]
+    #[derive(Default)]
+    pub struct CargoToml {
+        pub object_map: OidMap<crate::cargo::CargoToml>,
+    }
+
+    #[cfg(feature = "cargo")]
+    impl super::ObjectMapper for CargoToml {
+        type K = git2::Oid;
+
+        type V = crate::cargo::CargoToml;
+
+        fn get(&self, key: &Self::K) -> Option<&Self::V> {
+            self.object_map.get(key)
+        }
+
+        fn insert(&mut self, key: Self::K, value: Self::V) -> Option<Self::V> {
+            self.object_map.insert(key, value)
+        }
+    }
+
+    #[cfg(feature = "any")]
+    #[derive(Default)]
+    pub struct Any {
+        pub object_map: NamedMap<(NodeIdentifier, crate::any::MD)>,
+    }
+
+    #[cfg(feature = "any")]
+    impl Any {
+        pub(crate) fn retain_reachable(&mut self, reachable: &Reachable) {
+            self.object_map.retain(|_, (id, _)| reachable.contains(id));
+        }
+    }
+
     // // any
     // pub object_map_any: OidMap<(NodeIdentifier, DefaultMetrics)>,
     // // maven
@@ -411,9 +890,6 @@ pub mod file_sys {
     // TODO move these things to their respective modules
     use super::{CachesHolding, ObjectName};
 
-    // /// The default file system, directories and files
-    // pub struct Any;
-
     /// The maven scheme https://maven.apache.org/guides/introduction/introduction-to-the-standard-directory-layout.html ,
     /// made of nested maven modules.
     /// Each maven module has a config file (often a pom.xml),
@@ -457,6 +933,35 @@ pub mod file_sys {
         }
     }
 
+    /// The gradle scheme https://docs.gradle.org/current/userguide/multi_project_builds.html ,
+    /// a root project with a settings script including subprojects,
+    /// each project following the maven directory layout unless its build script configures its source sets.
+    #[cfg(feature = "gradle")]
+    pub struct Gradle;
+
+    #[cfg(feature = "gradle")]
+    impl CachesHolding for Gradle {
+        type Caches = super::caches::Gradle;
+    }
+
+    #[cfg(feature = "gradle")]
+    pub struct GradleScript;
+
+    #[cfg(feature = "gradle")]
+    impl CachesHolding for GradleScript {
+        type Caches = super::caches::GradleScript;
+    }
+
+    #[cfg(feature = "gradle")]
+    impl super::InFiles for GradleScript {
+        fn matches(name: &ObjectName) -> bool {
+            matches!(
+                &name.0[..],
+                b"settings.gradle" | b"settings.gradle.kts" | b"build.gradle" | b"build.gradle.kts"
+            )
+        }
+    }
+
     /// The make scheme,
     /// It contains a Makefile and different directories, often src/ or lib/, tests/ or tests/, and also third-party/ docs/ script/,
     /// but it is mostly community and programming language dependent.
@@ -476,7 +981,34 @@ pub mod file_sys {
 
     impl super::InFiles for MakeFile {
         fn matches(name: &ObjectName) -> bool {
-            name.0.eq(b"Makefile")
+            // in the order GNU make looks for them
+            matches!(&name.0[..], b"GNUmakefile" | b"makefile" | b"Makefile")
+        }
+    }
+
+    /// The cmake scheme https://cmake.org/cmake/help/latest/manual/cmake-buildsystem.7.html ,
+    /// a root `CMakeLists.txt` adding subdirectories with their own lists file,
+    /// each one declaring targets built from sources that can be anywhere below it.
+    #[cfg(feature = "cmake")]
+    pub struct CMake;
+
+    #[cfg(feature = "cmake")]
+    impl CachesHolding for CMake {
+        type Caches = super::caches::CMake;
+    }
+
+    #[cfg(feature = "cmake")]
+    pub struct CMakeLists;
+
+    #[cfg(feature = "cmake")]
+    impl CachesHolding for CMakeLists {
+        type Caches = super::caches::CMakeLists;
+    }
+
+    #[cfg(feature = "cmake")]
+    impl super::InFiles for CMakeLists {
+        fn matches(name: &ObjectName) -> bool {
+            &name.0[..] == b"CMakeLists.txt"
         }
     }
 
@@ -513,6 +1045,150 @@ pub mod file_sys {
     /// or is a collection of packages that contains a packages/ directory where each package is located
     #[cfg(feature = "npm")]
     pub struct Npm;
+
+    #[cfg(feature = "npm")]
+    impl CachesHolding for Npm {
+        type Caches = super::caches::Npm;
+    }
+
+    #[cfg(feature = "npm")]
+    pub struct PackageJson;
+
+    #[cfg(feature = "npm")]
+    impl CachesHolding for PackageJson {
+        type Caches = super::caches::PackageJson;
+    }
+
+    #[cfg(feature = "npm")]
+    impl super::InFiles for PackageJson {
+        fn matches(name: &ObjectName) -> bool {
+            name.0.eq(b"package.json")
+        }
+    }
+
+    /// Typescript and javascript files, both are parsed with the typescript grammar.
+    #[cfg(feature = "ts")]
+    pub struct Ts;
+
+    #[cfg(feature = "ts")]
+    impl CachesHolding for Ts {
+        type Caches = super::caches::Ts;
+    }
+
+    #[cfg(feature = "ts")]
+    impl super::InFiles for Ts {
+        fn matches(name: &ObjectName) -> bool {
+            let name = &name.0;
+            (name.ends_with(b".ts") && !name.ends_with(b".d.ts"))
+                || name.ends_with(b".mts")
+                || name.ends_with(b".cts")
+                || name.ends_with(b".js")
+                || name.ends_with(b".mjs")
+                || name.ends_with(b".cjs")
+        }
+    }
+
+    /// The python packaging scheme https://packaging.python.org/en/latest/tutorials/packaging-projects/ ,
+    /// a project is a directory containing a `pyproject.toml` (or a legacy `setup.py`),
+    /// its sources being either at its root or in a src/ directory,
+    /// and each directory containing an `__init__.py` is a package.
+    #[cfg(feature = "pip")]
+    pub struct Pip;
+
+    #[cfg(feature = "pip")]
+    impl CachesHolding for Pip {
+        type Caches = super::caches::Pip;
+    }
+
+    /// Project manifests, they are only looked up to flag directories.
+    #[cfg(feature = "pip")]
+    pub struct PyProject;
+
+    #[cfg(feature = "pip")]
+    impl super::InFiles for PyProject {
+        fn matches(name: &ObjectName) -> bool {
+            matches!(&name.0[..], b"pyproject.toml" | b"setup.py")
+        }
+    }
+
+    #[cfg(feature = "pip")]
+    pub struct PyInit;
+
+    #[cfg(feature = "pip")]
+    impl super::InFiles for PyInit {
+        fn matches(name: &ObjectName) -> bool {
+            &name.0[..] == b"__init__.py"
+        }
+    }
+
+    /// Python sources and stubs.
+    #[cfg(feature = "python")]
+    pub struct Python;
+
+    #[cfg(feature = "python")]
+    impl CachesHolding for Python {
+        type Caches = super::caches::Python;
+    }
+
+    #[cfg(feature = "python")]
+    impl super::InFiles for Python {
+        fn matches(name: &ObjectName) -> bool {
+            name.0.ends_with(b".py") || name.0.ends_with(b".pyi")
+        }
+    }
+
+    /// The cargo scheme https://doc.rust-lang.org/cargo/reference/workspaces.html ,
+    /// a crate is a directory containing a `Cargo.toml` declaring a package,
+    /// with its sources in src/ and its integration tests in tests/,
+    /// and a workspace lists the directories of its crates in the `members` of its `Cargo.toml`.
+    #[cfg(feature = "cargo")]
+    pub struct Cargo;
+
+    #[cfg(feature = "cargo")]
+    impl CachesHolding for Cargo {
+        type Caches = super::caches::Cargo;
+    }
+
+    #[cfg(feature = "cargo")]
+    pub struct CargoToml;
+
+    #[cfg(feature = "cargo")]
+    impl CachesHolding for CargoToml {
+        type Caches = super::caches::CargoToml;
+    }
+
+    #[cfg(feature = "cargo")]
+    impl super::InFiles for CargoToml {
+        fn matches(name: &ObjectName) -> bool {
+            &name.0[..] == b"Cargo.toml"
+        }
+    }
+
+    /// Rust sources, including build scripts.
+    #[cfg(feature = "rust")]
+    pub struct Rust;
+
+    #[cfg(feature = "rust")]
+    impl CachesHolding for Rust {
+        type Caches = super::caches::Rust;
+    }
+
+    #[cfg(feature = "rust")]
+    impl super::InFiles for Rust {
+        fn matches(name: &ObjectName) -> bool {
+            name.0.ends_with(b".rs")
+        }
+    }
+
+    /// The default file system, directories and files,
+    /// each file being handled by the generator matching its extension.
+    #[cfg(feature = "any")]
+    pub struct Any;
+
+    #[cfg(feature = "any")]
+    impl CachesHolding for Any {
+        type Caches = super::caches::Any;
+    }
 }
 
 impl crate::preprocessed::RepositoryProcessor {
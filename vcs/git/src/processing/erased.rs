@@ -100,12 +100,59 @@ pub trait ParametrizedCommitProc: std::any::Any {
 
     fn get_mut(&mut self, parameters: ConfigParametersHandle) -> &mut dyn CommitProc;
     fn get(&self, parameters: ConfigParametersHandle) -> &dyn CommitProc;
+
+    fn persist(
+        &self,
+        _stores: &crate::SimpleStores,
+        _w: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        Ok(())
+    }
+    fn restore(
+        &mut self,
+        _stores: &crate::SimpleStores,
+        _r: &mut dyn std::io::Read,
+    ) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn commit_roots(&self) -> Vec<(git2::Oid, NodeIdentifier)> {
+        vec![]
+    }
+    fn retain_reachable(&mut self, _reachable: &Reachable) {}
 }
 
 pub trait ParametrizedCommitProc2: ParametrizedCommitProc {
     type Proc: CommitProcExt;
     fn with_parameters(&self, parameters: ConfigParametersHandle) -> &Self::Proc;
     fn with_parameters_mut(&mut self, parameters: ConfigParametersHandle) -> &mut Self::Proc;
+
+    /// Writes the state worth keeping across restarts, i.e. processed commits and blob caches.
+    fn persist(
+        &self,
+        _stores: &crate::SimpleStores,
+        _w: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        Ok(())
+    }
+    /// Reads a state written by [`ParametrizedCommitProc2::persist`],
+    /// parameters must have been registered in the same order.
+    fn restore(
+        &mut self,
+        _stores: &crate::SimpleStores,
+        _r: &mut dyn std::io::Read,
+    ) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    /// Roots of the processed commits, by commit,
+    /// the subtrees of the commits kept by a garbage collection must survive it.
+    fn commit_roots(&self) -> Vec<(git2::Oid, NodeIdentifier)> {
+        vec![]
+    }
+    /// Called after a garbage collection,
+    /// forgets commits and cached subtrees that were freed.
+    fn retain_reachable(&mut self, _reachable: &Reachable) {}
 }
 
 impl<T: ParametrizedCommitProc2> ParametrizedCommitProc for T {
@@ -116,6 +163,30 @@ impl<T: ParametrizedCommitProc2> ParametrizedCommitProc for T {
     fn get(&self, parameters: ConfigParametersHandle) -> &dyn CommitProc {
         ParametrizedCommitProc2::with_parameters(self, parameters)
     }
+
+    fn persist(
+        &self,
+        stores: &crate::SimpleStores,
+        w: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        ParametrizedCommitProc2::persist(self, stores, w)
+    }
+
+    fn restore(
+        &mut self,
+        stores: &crate::SimpleStores,
+        r: &mut dyn std::io::Read,
+    ) -> std::io::Result<()> {
+        ParametrizedCommitProc2::restore(self, stores, r)
+    }
+
+    fn commit_roots(&self) -> Vec<(git2::Oid, NodeIdentifier)> {
+        ParametrizedCommitProc2::commit_roots(self)
+    }
+
+    fn retain_reachable(&mut self, reachable: &Reachable) {
+        ParametrizedCommitProc2::retain_reachable(self, reachable)
+    }
 }
 
 #[test]
@@ -228,6 +299,7 @@ fn t() {
 
 pub type ProcessorMap = spreaded::ProcessorMap<Box<dyn spreaded::ErasableProcessor>>;
 use hyperast::store::defaults::NodeIdentifier;
+use hyperast::store::nodes::legion::gc::Reachable;
 pub use spreaded::ErasableProcessor;
 
 mod spreaded {
@@ -243,6 +315,12 @@ mod spreaded {
         pub(crate) fn clear(&mut self) {
             self.0.clear()
         }
+        pub(crate) fn values(&self) -> impl Iterator<Item = &V> {
+            self.0.values()
+        }
+        pub(crate) fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+            self.0.values_mut()
+        }
     }
 
     unsafe impl<V> Send for ProcessorMap<V> {}
@@ -254,6 +332,8 @@ mod spreaded {
         fn to_erasable_processor(self: Box<Self>) -> Box<dyn ErasableProcessor>;
         fn as_mut_any(&mut self) -> &mut dyn Any;
         fn as_any(&self) -> &dyn Any;
+        /// Stable across builds contrary to [`std::any::TypeId`], used to identify persisted processors
+        fn type_name(&self) -> &'static str;
     }
 
     impl<T: ErasableProcessor> ToErasedProc for T {
@@ -266,6 +346,9 @@ mod spreaded {
         fn as_any(&self) -> &dyn Any {
             self
         }
+        fn type_name(&self) -> &'static str {
+            std::any::type_name::<T>()
+        }
     }
     impl<T> ErasableProcessor for T where T: Any + ParametrizedCommitProc {}
     // NOTE crazy good stuff
@@ -0,0 +1,92 @@
+use hyperast::hashed::SyntaxNodeHashs;
+use hyperast::store::defaults::{LabelIdentifier, NodeIdentifier};
+use hyperast::tree_gen::{self, SubTreeMetrics};
+
+use crate::PROPAGATE_ERROR_ON_BAD_CST_NODE;
+use crate::processing::ObjectName;
+use crate::{Accumulator, BasicDirAcc};
+use crate::{FailedParsing, FileProcessingResult, SuccessProcessing};
+
+use crate::rust_processor::SimpleStores;
+use hyperast_gen_ts_rust::legion as rust_tree_gen;
+use hyperast_gen_ts_rust::types::TStore;
+
+pub(crate) fn handle_rust_file<'a, More>(
+    tree_gen: &mut rust_tree_gen::RustTreeGen<'a, '_, TStore, SimpleStores, More>,
+    name: &ObjectName,
+    text: &'a [u8],
+) -> FileProcessingResult<rust_tree_gen::FNode>
+where
+    More: tree_gen::Prepro<SimpleStores>,
+    More: tree_gen::PreproTSG<SimpleStores, Acc = rust_tree_gen::Acc>,
+{
+    let mut parser = tree_sitter::Parser::new();
+    parser
+        .set_language(&hyperast_gen_ts_rust::language())
+        .unwrap();
+    let time = std::time::Instant::now();
+    let tree = parser.parse(text, None);
+    let parsing_time = time.elapsed();
+    let Some(tree) = tree else {
+        unimplemented!("You set a timeout or an cancel flag, so it now requires special handling.")
+    };
+    if tree.root_node().has_error() {
+        log::warn!("bad CST: {:?}", name.try_str());
+        if PROPAGATE_ERROR_ON_BAD_CST_NODE {
+            return Err(FailedParsing {
+                parsing_time,
+                tree,
+                error: "CST contains parsing errors",
+            });
+        }
+    };
+    let node = tree_gen.generate_file(name.as_bytes(), text, tree.walk());
+    let processing_time = time.elapsed() - parsing_time;
+    Ok(SuccessProcessing {
+        parsing_time,
+        processing_time,
+        node,
+    })
+}
+
+type PrecompQueries = u16;
+
+pub struct RustAcc {
+    pub(crate) primary:
+        BasicDirAcc<NodeIdentifier, LabelIdentifier, SubTreeMetrics<SyntaxNodeHashs<u32>>>,
+    pub(crate) precomp_queries: PrecompQueries,
+}
+
+impl RustAcc {
+    pub(crate) fn new(name: String) -> Self {
+        Self {
+            primary: BasicDirAcc::new(name),
+            precomp_queries: 0,
+        }
+    }
+}
+
+impl From<String> for RustAcc {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl RustAcc {
+    pub(crate) fn push(&mut self, name: LabelIdentifier, full_node: rust_tree_gen::Local) {
+        self.primary
+            .push(name, full_node.compressed_node, full_node.metrics);
+        self.precomp_queries |= full_node.precomp_queries;
+    }
+}
+
+impl hyperast::tree_gen::Accumulator for RustAcc {
+    type Node = (LabelIdentifier, (rust_tree_gen::Local,));
+    fn push(&mut self, (name, (full_node,)): Self::Node) {
+        self.push(name, full_node);
+    }
+}
+
+impl Accumulator for RustAcc {
+    type Unlabeled = (rust_tree_gen::Local,);
+}
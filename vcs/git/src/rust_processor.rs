@@ -0,0 +1,475 @@
+use std::iter::Peekable;
+use std::path::Components;
+use std::sync::Arc;
+
+use git2::{Oid, Repository};
+
+use hyperast::store::nodes::legion::subtree_builder;
+use hyperast::tree_gen::add_md_precomp_queries;
+
+use crate::git::BasicGitObject;
+use crate::preprocessed::RepositoryProcessor;
+use crate::processing::erased::ParametrizedCommitProc2;
+use crate::processing::{CacheHolding, InFiles, ObjectName};
+use crate::rust::RustAcc;
+use crate::{Processor, StackEle};
+
+use hyperast_gen_ts_rust::legion::{self as rust_gen};
+use hyperast_gen_ts_rust::types::Type;
+
+pub type SimpleStores = hyperast::store::SimpleStores<hyperast_gen_ts_rust::types::TStore>;
+
+type Handle = crate::processing::erased::ParametrizedCommitProcessor2Handle<RustProc>;
+
+pub(crate) fn prepare_dir_exploration(tree: git2::Tree) -> Vec<BasicGitObject> {
+    tree.iter()
+        .rev()
+        .map(TryInto::try_into)
+        .filter_map(|x| x.ok())
+        .collect()
+}
+
+/// Directories that are never explored, they contain build outputs.
+pub(crate) fn is_ignored_dir(name: &ObjectName) -> bool {
+    matches!(name.as_bytes(), b"target" | b".git")
+}
+
+pub struct RustProcessor<'repo, 'prepro, 'd, 'c, Acc> {
+    repository: &'repo Repository,
+    prepro: &'prepro mut RepositoryProcessor,
+    stack: Vec<StackEle<Acc>>,
+    pub dir_path: &'d mut Peekable<Components<'c>>,
+    handle: &'d Handle,
+}
+
+impl<'repo, 'prepro, 'd, 'c, Acc: From<String>> RustProcessor<'repo, 'prepro, 'd, 'c, Acc> {
+    pub(crate) fn new(
+        repository: &'repo Repository,
+        prepro: &'prepro mut RepositoryProcessor,
+        dir_path: &'d mut Peekable<Components<'c>>,
+        name: &ObjectName,
+        oid: git2::Oid,
+        parameters: &'d Handle,
+    ) -> Self {
+        let tree = repository.find_tree(oid).unwrap();
+        let prepared = prepare_dir_exploration(tree);
+        let name = name.try_into().unwrap();
+        let stack = vec![StackEle::new(oid, prepared, Acc::from(name))];
+        Self {
+            stack,
+            repository,
+            prepro,
+            dir_path,
+            handle: parameters,
+        }
+    }
+}
+
+impl<'repo, 'b, 'd, 'c> Processor<RustAcc> for RustProcessor<'repo, 'b, 'd, 'c, RustAcc> {
+    fn pre(&mut self, current_object: BasicGitObject) {
+        match current_object {
+            BasicGitObject::Tree(oid, name) => {
+                if is_ignored_dir(&name) {
+                    return;
+                }
+                self.handle_tree_cached(oid, name);
+            }
+            BasicGitObject::Blob(oid, name) => {
+                if crate::processing::file_sys::Rust::matches(&name) {
+                    self.prepro
+                        .help_handle_rust_file(
+                            oid,
+                            &mut self.stack.last_mut().unwrap().acc,
+                            &name,
+                            self.repository,
+                            *self.handle,
+                        )
+                        .unwrap();
+                } else {
+                    log::debug!("not rust source file {:?}", name.try_str());
+                }
+            }
+        }
+    }
+    fn post(&mut self, oid: Oid, acc: RustAcc) -> Option<(rust_gen::Local,)> {
+        let name = &acc.primary.name;
+        let key = (oid, name.as_bytes().into());
+        let name = self.prepro.get_or_insert_label(name);
+        let holder = self
+            .prepro
+            .processing_systems
+            .mut_or_default::<RustProcessorHolder>();
+        let rust_proc = holder.with_parameters_mut(self.handle.0);
+        let full_node = make(acc, self.prepro.main_stores.mut_with_ts(), rust_proc);
+        rust_proc.cache.object_map.insert(key, (full_node.clone(),));
+        if self.stack.is_empty() {
+            Some((full_node,))
+        } else {
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            assert!(
+                !w.primary.children_names.contains(&name),
+                "{:?} {:?}",
+                w.primary.children_names,
+                name
+            );
+            w.push(name, full_node.clone());
+            None
+        }
+    }
+
+    fn stack(&mut self) -> &mut Vec<StackEle<RustAcc>> {
+        &mut self.stack
+    }
+}
+
+impl<'repo, 'prepro, 'd, 'c> RustProcessor<'repo, 'prepro, 'd, 'c, RustAcc> {
+    fn handle_tree_cached(&mut self, oid: Oid, name: ObjectName) {
+        let holder = self
+            .prepro
+            .processing_systems
+            .mut_or_default::<RustProcessorHolder>();
+        let rust_proc = holder.with_parameters_mut(self.handle.0);
+        if let Some(already) = rust_proc.cache.object_map.get(&(oid, name.clone())) {
+            // reinit already computed node for post order
+            let full_node = already.clone();
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            let name = self.prepro.intern_object_name(&name);
+            assert!(!w.primary.children_names.contains(&name));
+            hyperast::tree_gen::Accumulator::push(w, (name, full_node));
+        } else {
+            log::debug!("tree {:?}", name.try_str());
+            let tree = self.repository.find_tree(oid).unwrap();
+            let prepared: Vec<BasicGitObject> = prepare_dir_exploration(tree);
+            self.stack.push(StackEle::new(
+                oid,
+                prepared,
+                RustAcc::new(name.try_into().unwrap()),
+            ));
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub(crate) query: Option<hyperast_tsquery::ZeroSepArrayStr>,
+}
+#[derive(Default)]
+pub(crate) struct RustProcessorHolder(Vec<RustProc>);
+pub(crate) struct RustProc {
+    parameter: Parameter,
+    query: Option<Query>,
+    cache: crate::processing::caches::Rust,
+    commits: std::collections::HashMap<git2::Oid, crate::Commit>,
+}
+impl crate::processing::erased::Parametrized for RustProcessorHolder {
+    type T = Parameter;
+    fn register_param(
+        &mut self,
+        t: Self::T,
+    ) -> crate::processing::erased::ParametrizedCommitProcessorHandle {
+        use crate::processing::erased::ConfigParametersHandle;
+        use crate::processing::erased::ParametrizedCommitProc;
+        use crate::processing::erased::ParametrizedCommitProcessorHandle;
+        if let Some(l) = self.0.iter().position(|x| &x.parameter == &t) {
+            return ParametrizedCommitProcessorHandle(
+                self.erased_handle(),
+                ConfigParametersHandle(l),
+            );
+        }
+        let l = self.0.len();
+        let query = t.query.as_ref().map(|q| {
+            use hyperast_tsquery::ArrayStr;
+            Query::new(q.iter())
+        });
+        let r = RustProc {
+            parameter: t,
+            query,
+            cache: Default::default(),
+            commits: Default::default(),
+        };
+        self.0.push(r);
+        ParametrizedCommitProcessorHandle(self.erased_handle(), ConfigParametersHandle(l))
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Query(pub(crate) hyperast_tsquery::Query, Arc<str>);
+
+impl PartialEq for Query {
+    fn eq(&self, other: &Self) -> bool {
+        self.1 == other.1
+    }
+}
+impl Eq for Query {}
+
+impl Query {
+    fn new<'a>(precomputeds: impl Iterator<Item = &'a str>) -> Self {
+        static DQ: &str = "(_)";
+        let precomputeds = precomputeds.collect::<Vec<_>>();
+        let (precomp, _) = hyperast_tsquery::Query::with_precomputed(
+            DQ,
+            hyperast_gen_ts_rust::language(),
+            precomputeds.as_slice(),
+        )
+        .unwrap();
+        Self(precomp, precomputeds.join("\n").into())
+    }
+}
+
+impl crate::processing::erased::CommitProc for RustProc {
+    fn prepare_processing(
+        &self,
+        _repository: &git2::Repository,
+        _builder: crate::preprocessed::CommitBuilder,
+        _handle: crate::processing::ParametrizedCommitProcessorHandle,
+    ) -> Box<dyn crate::processing::erased::PreparedCommitProc> {
+        unimplemented!("required for processing rust at the root of a project")
+    }
+
+    fn get_commit(&self, commit_oid: git2::Oid) -> Option<&crate::Commit> {
+        self.commits.get(&commit_oid)
+    }
+
+    fn commit_count(&self) -> usize {
+        self.commits.len()
+    }
+
+    fn get_precomp_query(&self) -> Option<hyperast_tsquery::ZeroSepArrayStr> {
+        self.parameter.query.clone()
+    }
+}
+
+impl crate::processing::erased::CommitProcExt for RustProc {
+    type Holder = RustProcessorHolder;
+}
+impl crate::processing::erased::ParametrizedCommitProc2 for RustProcessorHolder {
+    type Proc = RustProc;
+
+    fn with_parameters_mut(
+        &mut self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &mut Self::Proc {
+        &mut self.0[parameters.0]
+    }
+
+    fn with_parameters(
+        &self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &Self::Proc {
+        &self.0[parameters.0]
+    }
+
+    fn persist(
+        &self,
+        _stores: &crate::SimpleStores,
+        mut w: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::write_u32;
+        write_u32(&mut w, self.0.len() as u32)?;
+        for proc in &self.0 {
+            crate::persist::write_commits(&mut w, &proc.commits)?;
+            proc.cache.save(&mut w)?;
+        }
+        Ok(())
+    }
+
+    fn restore(
+        &mut self,
+        stores: &crate::SimpleStores,
+        mut r: &mut dyn std::io::Read,
+    ) -> std::io::Result<()> {
+        use hyperast::store::nodes::legion::persist::read_u32_from;
+        let len = read_u32_from(&mut r)? as usize;
+        if len > self.0.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "more persisted processors than registered ones",
+            ));
+        }
+        for proc in &mut self.0[..len] {
+            proc.commits = crate::persist::read_commits(&mut r)?;
+            proc.cache = crate::processing::caches::Rust::load(stores, &mut r)?;
+        }
+        Ok(())
+    }
+
+    fn commit_roots(&self) -> Vec<(git2::Oid, hyperast::store::defaults::NodeIdentifier)> {
+        self.0
+            .iter()
+            .flat_map(|proc| proc.commits.iter().map(|(oid, c)| (*oid, c.ast_root)))
+            .collect()
+    }
+
+    fn retain_reachable(&mut self, reachable: &hyperast::store::nodes::legion::gc::Reachable) {
+        for proc in &mut self.0 {
+            proc.commits.retain(|_, c| reachable.contains(&c.ast_root));
+            proc.cache.retain_reachable(reachable);
+        }
+    }
+}
+impl CacheHolding<crate::processing::caches::Rust> for RustProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::Rust {
+        &mut self.cache
+    }
+    fn get_caches(&self) -> &crate::processing::caches::Rust {
+        &self.cache
+    }
+}
+
+impl RepositoryProcessor {
+    pub(crate) fn handle_rust_blob(
+        &mut self,
+        oid: Oid,
+        name: &ObjectName,
+        repository: &Repository,
+        parameters: Handle,
+    ) -> Result<(rust_gen::Local,), crate::ParseErr> {
+        self.processing_systems
+            .caching_blob_handler::<crate::processing::file_sys::Rust>()
+            .handle2(oid, repository, &name, parameters, |c, n, t| {
+                let line_break = if t.contains(&b'\r') { "\r\n" } else { "\n" }
+                    .as_bytes()
+                    .to_vec();
+                let holder = c.mut_or_default::<RustProcessorHolder>();
+                let rust_proc = holder.with_parameters_mut(parameters.0);
+                let md_cache = &mut rust_proc.cache.md_cache;
+                let dedup = &mut rust_proc.cache.dedup;
+                let stores = self
+                    .main_stores
+                    .mut_with_ts::<hyperast_gen_ts_rust::types::TStore>();
+                let r = if let Some(more) = &rust_proc.query {
+                    let more = &more.0;
+                    let more: hyperast_tsquery::PreparedQuerying<_, _, _> = more.into();
+                    let mut rust_tree_gen = rust_gen::RustTreeGen::with_preprocessing_and_dedup(
+                        stores, dedup, md_cache, more,
+                    )
+                    .with_line_break(line_break);
+                    crate::rust::handle_rust_file::<_>(&mut rust_tree_gen, n, t)
+                } else {
+                    let mut rust_tree_gen =
+                        rust_gen::RustTreeGen::new(stores, md_cache).with_line_break(line_break);
+                    crate::rust::handle_rust_file(&mut rust_tree_gen, n, t)
+                }
+                .map_err(|_| crate::ParseErr::IllFormed)?;
+
+                self.parsing_time += r.parsing_time;
+                self.processing_time += r.processing_time;
+                log::info!(
+                    "parsing, processing, n, f: {} {} {} {}",
+                    self.parsing_time.as_secs(),
+                    self.processing_time.as_secs(),
+                    rust_proc.cache.md_cache.len(),
+                    rust_proc.cache.object_map.len()
+                );
+
+                let r = r.node;
+                Ok((r.local.clone(),))
+            })
+    }
+
+    pub(crate) fn help_handle_rust_file(
+        &mut self,
+        oid: Oid,
+        parent: &mut RustAcc,
+        name: &ObjectName,
+        repository: &Repository,
+        parameters: Handle,
+    ) -> Result<(), crate::ParseErr> {
+        let (full_node,) = self.handle_rust_blob(oid, name, repository, parameters)?;
+        let name = self.intern_object_name(name);
+        assert!(!parent.primary.children_names.contains(&name));
+
+        parent.push(name, full_node);
+        Ok(())
+    }
+
+    pub(crate) fn handle_rust_directory<'b, 'd: 'b>(
+        &mut self,
+        repository: &Repository,
+        dir_path: &'b mut Peekable<Components<'d>>,
+        name: &ObjectName,
+        oid: git2::Oid,
+        handle: Handle,
+    ) -> (rust_gen::Local,) {
+        RustProcessor::<RustAcc>::new(repository, self, dir_path, name, oid, &handle).process()
+    }
+
+    pub(crate) fn help_handle_rust_folder<'a, 'b, 'c, 'd: 'c>(
+        &'a mut self,
+        repository: &'b Repository,
+        dir_path: &'c mut Peekable<Components<'d>>,
+        oid: Oid,
+        name: &ObjectName,
+        handle: Handle,
+    ) -> <RustAcc as hyperast::tree_gen::Accumulator>::Node {
+        let full_node = self.handle_rust_directory(repository, dir_path, name, oid, handle);
+        let name = self.intern_object_name(name);
+        (name, full_node)
+    }
+}
+
+fn make(acc: RustAcc, stores: &mut SimpleStores, rust_proc: &mut RustProc) -> rust_gen::Local {
+    use hyperast::hashed::IndexingHashBuilder as _;
+    use hyperast::hashed::MetaDataHashsBuilder as _;
+    use hyperast::store::nodes::legion::eq_node;
+    use hyperast::types::ETypeStore as _;
+    use hyperast::types::LabelStore;
+
+    let node_store = &mut stores.node_store;
+    let label_store = &mut stores.label_store;
+    let kind = Type::Directory;
+    let interned_kind = hyperast_gen_ts_rust::types::TStore::intern(kind);
+    let label_id = label_store.get_or_insert(acc.primary.name.clone());
+
+    let primary = acc
+        .primary
+        .map_metrics(|m| m.finalize(&interned_kind, &label_id, 0));
+    let hashable = primary.metrics.hashs.most_discriminating();
+    let eq = eq_node(&Type::Directory, Some(&label_id), &primary.children);
+    let md_cache = &mut rust_proc.cache.md_cache;
+    let dedup_cache = &mut rust_proc.cache.dedup.0;
+    let insertion = node_store
+        .inner
+        .prepare_insertion(dedup_cache, &hashable, eq);
+
+    if let Some(id) = insertion.occupied_id() {
+        // same as for cpp, only reached when git objects differ but the subtrees are the same
+        let md = md_cache.get(&id).unwrap();
+        return md.local(id);
+    }
+
+    let mut dyn_builder = subtree_builder::<hyperast_gen_ts_rust::types::TStore>(interned_kind);
+
+    add_md_precomp_queries(&mut dyn_builder, acc.precomp_queries);
+
+    let children_is_empty = primary.children.is_empty();
+
+    let metrics = primary.persist(&mut dyn_builder, interned_kind, label_id);
+    let metrics = metrics.map_hashs(|h| h.build());
+    let hashs = metrics.add_md_metrics(&mut dyn_builder, children_is_empty);
+    hashs.persist(&mut dyn_builder);
+
+    let vacant = insertion.vacant();
+    let node_id = hyperast::store::nodes::legion::NodeStore::insert_built_after_prepare(
+        vacant,
+        dyn_builder.build(),
+    );
+
+    md_cache.insert(
+        node_id,
+        rust_gen::MD {
+            metrics,
+            ana: None,
+            precomp_queries: acc.precomp_queries,
+        },
+    );
+
+    rust_gen::Local {
+        compressed_node: node_id,
+        metrics,
+        ana: None,
+        role: None,
+        precomp_queries: acc.precomp_queries,
+        viz_cs_count: 0,
+    }
+}
@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+
+use crate::cargo::{CargoModuleAcc, CargoToml, handle_cargo_toml_file};
+
+static WORKSPACE: &str = r#"
+[workspace]
+resolver = "2"
+members = ["crates/*", "lib/polyglote", "vcs/git"]
+exclude = ["crates/ref-mining-evaluation"]
+
+[workspace.dependencies]
+log = "0.4"
+"#;
+
+static PACKAGE: &str = r#"
+[package]
+name = "hyperast_vcs_git"
+version = "0.3.0"
+edition = "2024"
+
+[dependencies]
+log = { workspace = true }
+"#;
+
+#[test]
+fn extract_cargo_manifests() {
+    let ws = handle_cargo_toml_file(&b"Cargo.toml".into(), WORKSPACE.as_bytes()).unwrap();
+    assert_eq!(
+        ws,
+        CargoToml {
+            name: None,
+            is_workspace: true,
+            members: vec![
+                "crates/*".to_string(),
+                "lib/polyglote".to_string(),
+                "vcs/git".to_string()
+            ],
+            exclude: vec!["crates/ref-mining-evaluation".to_string()],
+        }
+    );
+    let pkg = handle_cargo_toml_file(&b"Cargo.toml".into(), PACKAGE.as_bytes()).unwrap();
+    assert_eq!(pkg.name.as_deref(), Some("hyperast_vcs_git"));
+    assert!(!pkg.is_workspace);
+    assert!(handle_cargo_toml_file(&b"Cargo.toml".into(), b"[package").is_err());
+}
+
+#[test]
+fn follow_workspace_members() {
+    let ws = handle_cargo_toml_file(&b"Cargo.toml".into(), WORKSPACE.as_bytes()).unwrap();
+    let mut root = CargoModuleAcc::new("".to_string());
+    root.push_manifest(ws);
+    assert!(!root.is_crate());
+
+    assert_eq!(root.members_in(b"docs"), None);
+    assert_eq!(
+        root.members_in(b"lib"),
+        Some((vec![PathBuf::from("polyglote")], vec![]))
+    );
+    let (members, exclude) = root.members_in(b"crates").unwrap();
+    assert_eq!(members, vec![PathBuf::from("*")]);
+    assert_eq!(exclude, vec![PathBuf::from("ref-mining-evaluation")]);
+
+    let crates = CargoModuleAcc::with_members("crates".to_string(), members, exclude);
+    assert_eq!(crates.members_in(b"ref-mining-evaluation"), None);
+    let (members, exclude) = crates.members_in(b"hyper_ast").unwrap();
+    assert!(members.is_empty() && exclude.is_empty());
+}
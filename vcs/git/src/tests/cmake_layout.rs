@@ -0,0 +1,123 @@
+use crate::cmake::{CMakeLists, Target, TargetKind, glob_matches};
+use crate::preprocessed::child_at_path;
+use crate::processing::RepoConfig;
+
+#[test]
+fn extract_cmake_targets() {
+    let lists = CMakeLists::extract(
+        r#"cmake_minimum_required(VERSION 3.16)
+project(demo CXX)
+# add_subdirectory(commented)
+add_subdirectory(lib)
+add_subdirectory(third_party/fmt EXCLUDE_FROM_ALL)
+set(SOURCES src/main.cpp
+    ${CMAKE_CURRENT_SOURCE_DIR}/src/cli.cpp)
+list(APPEND SOURCES src/extra.cpp)
+include_directories(include)
+add_executable(${PROJECT_NAME} ${SOURCES})
+add_library(core STATIC "src/core.cpp" ../outside.cpp ${GENERATED_DIR}/gen.cpp)
+add_library(demo::core ALIAS core)
+target_sources(core PRIVATE src/core_impl.cpp)
+target_include_directories(core
+    PUBLIC $<BUILD_INTERFACE:${CMAKE_CURRENT_SOURCE_DIR}/core/include>
+    PRIVATE src)
+#[[ add_library(bracket_commented src/x.cpp)
+]]
+"#,
+    );
+    assert_eq!(lists.subdirectories, vec!["lib", "third_party/fmt"]);
+    assert_eq!(
+        lists.targets,
+        vec![
+            Target {
+                name: "demo".into(),
+                kind: TargetKind::Executable,
+                sources: vec![
+                    "src/main.cpp".into(),
+                    "src/cli.cpp".into(),
+                    "src/extra.cpp".into()
+                ],
+                include_dirs: vec!["include".into()],
+            },
+            Target {
+                name: "core".into(),
+                kind: TargetKind::Library,
+                sources: vec!["src/core.cpp".into(), "src/core_impl.cpp".into()],
+                include_dirs: vec!["core/include".into(), "src".into(), "include".into()],
+            },
+        ]
+    );
+}
+
+#[test]
+fn extract_cmake_globs() {
+    let lists = CMakeLists::extract(
+        r#"file(GLOB HEADERS CONFIGURE_DEPENDS include/*.h)
+file(GLOB_RECURSE SRCS src/*.cpp)
+add_library(lib ${HEADERS} ${SRCS})
+"#,
+    );
+    assert_eq!(
+        lists.targets[0].sources,
+        vec!["include/*.h".to_string(), "src/**/*.cpp".to_string()]
+    );
+    assert!(glob_matches("src/**/*.cpp", "src/a.cpp"));
+    assert!(glob_matches("src/**/*.cpp", "src/x/y/b.cpp"));
+    assert!(!glob_matches("src/**/*.cpp", "src/x/b.h"));
+    assert!(glob_matches("include/?.h", "include/a.h"));
+    assert!(!glob_matches("include/*.h", "include/sub/a.h"));
+}
+
+#[test]
+fn process_cmake_targets() {
+    let lists = "add_subdirectory(app)\ninclude_directories(include)\nadd_library(app src/a.cpp)\n";
+    let (repositories, root) = super::process_local_repo(
+        "cmake_targets",
+        RepoConfig::CppCMake,
+        &[
+            ("CMakeLists.txt", lists),
+            ("src/a.cpp", "int a() { return 1; }\n"),
+            ("include/a.h", "int a();\n"),
+            ("app/CMakeLists.txt", "add_executable(main main.cpp)\n"),
+            ("app/main.cpp", "int main() { return 0; }\n"),
+        ],
+    );
+    let at = |path: &str| child_at_path(&repositories.processor.main_stores, root, path.split('/'));
+    // the target and the subdirectory of the same name are both reachable
+    assert!(at("app").is_some());
+    assert!(at("CMakeLists.txt/app").is_some());
+    // sources and include directories are nested by path
+    assert!(at("CMakeLists.txt/app/src/a.cpp").is_some());
+    assert!(at("CMakeLists.txt/app/include").is_some());
+    assert!(at("app/CMakeLists.txt/main/main.cpp").is_some());
+}
+
+#[test]
+fn process_cmake_same_tree_different_subdirectories() {
+    let lists = "add_library(lib lib.cpp)\n";
+    let source = "int f() { return 0; }\n";
+    // x and y are the same tree, but a different subdirectory is added in each
+    let (repositories, root) = super::process_local_repo(
+        "cmake_same_tree",
+        RepoConfig::CppCMake,
+        &[
+            (
+                "CMakeLists.txt",
+                "add_subdirectory(x/a)\nadd_subdirectory(y/b)\n",
+            ),
+            ("x/a/CMakeLists.txt", lists),
+            ("x/a/lib.cpp", source),
+            ("x/b/CMakeLists.txt", lists),
+            ("x/b/lib.cpp", source),
+            ("y/a/CMakeLists.txt", lists),
+            ("y/a/lib.cpp", source),
+            ("y/b/CMakeLists.txt", lists),
+            ("y/b/lib.cpp", source),
+        ],
+    );
+    let at = |path: &str| child_at_path(&repositories.processor.main_stores, root, path.split('/'));
+    assert!(at("x/a").is_some());
+    assert!(at("x/b").is_none());
+    assert!(at("y/a").is_none());
+    assert!(at("y/b").is_some());
+}
@@ -0,0 +1,53 @@
+use std::path::{Path, PathBuf};
+
+use crate::git::{Forge, Repo};
+
+#[test]
+fn parse_repo_specs() {
+    let repo: Repo = "github.com/INRIA/spoon".parse().unwrap();
+    assert_eq!(repo, Forge::Github.repo("INRIA", "spoon"));
+    assert_eq!(repo.forge().segment(), "github");
+
+    let repo: Repo = "file:///srv/git/INRIA/spoon".parse().unwrap();
+    assert_eq!(repo.forge(), &Forge::Url("file:///srv/git".to_string()));
+    assert_eq!(repo.url(), "file:///srv/git/INRIA/spoon");
+    assert_eq!(repo.forge().segment(), "file");
+
+    let repo: Repo = "https://git.example.org/INRIA/spoon".parse().unwrap();
+    assert_eq!(repo.url(), "https://git.example.org/INRIA/spoon");
+    assert_eq!(repo.forge().segment(), "git.example.org");
+
+    let repo: Repo = "/srv/git/INRIA/spoon".parse().unwrap();
+    assert_eq!(repo.forge(), &Forge::Local(PathBuf::from("/srv/git")));
+    assert_eq!(repo.local_path(), Some(PathBuf::from("/srv/git/INRIA/spoon")));
+    assert_eq!(repo.forge().segment(), "local");
+
+    assert!("github.com/INRIA/spoon/more".parse::<Repo>().is_err());
+    assert!("unknown.org/INRIA/spoon".parse::<Repo>().is_err());
+}
+
+fn init_repo(path: &Path) -> git2::Oid {
+    let repo = git2::Repository::init(path).unwrap();
+    let sig = git2::Signature::now("test", "test@example.org").unwrap();
+    let tree = repo.treebuilder(None).unwrap().write().unwrap();
+    let tree = repo.find_tree(tree).unwrap();
+    repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[])
+        .unwrap()
+}
+
+#[test]
+fn offline_repositories() {
+    let dir = std::env::temp_dir().join(format!("hyperast_forges_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let oid = init_repo(&dir.join("origin/user/name"));
+
+    let local = Forge::Local(dir.join("origin")).repo("user", "name");
+    let repo = local.fetch();
+    assert!(repo.find_commit(oid).is_ok());
+
+    let url = Forge::Url(format!("file://{}", dir.join("origin").display())).repo("user", "name");
+    let repo = url.fetch_to(dir.join("clones"));
+    assert!(repo.find_commit(oid).is_ok());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
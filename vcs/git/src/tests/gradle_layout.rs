@@ -0,0 +1,90 @@
+use crate::gradle::GradleScript;
+use crate::preprocessed::child_at_path;
+use crate::processing::RepoConfig;
+
+#[test]
+fn extract_gradle_settings() {
+    let script = GradleScript::extract(
+        r#"rootProject.name = 'demo'
+// include ':commented'
+include ':app', ':lib:core'
+include(
+    ":tools",
+)
+includeFlat 'sibling'
+includeBuild 'build-logic'
+project(':tools').projectDir = new File(settingsDir, 'misc/tools')
+"#,
+    );
+    assert_eq!(
+        script.submodules,
+        vec!["app", "lib/core", "misc/tools", "build-logic"]
+    );
+    assert_eq!(script.source_dirs, None);
+    assert_eq!(script.test_source_dirs, None);
+}
+
+#[test]
+fn extract_gradle_source_sets() {
+    let script = GradleScript::extract(
+        r#"plugins { id 'java' }
+sourceSets {
+    main {
+        java {
+            srcDirs = ['src', "$buildDir/generated"]
+        }
+        resources { srcDir 'res' }
+    }
+    test.java.srcDir 'it/java'
+}
+"#,
+    );
+    assert!(script.submodules.is_empty());
+    assert_eq!(script.source_dirs, Some(vec!["src".to_string()]));
+    assert_eq!(
+        script.test_source_dirs,
+        Some(vec!["src/test/java".to_string(), "it/java".to_string()])
+    );
+}
+
+#[test]
+fn extract_gradle_kotlin_dsl() {
+    let script = GradleScript::extract(
+        r#"include(":app")
+sourceSets {
+    getByName("main") {
+        java.setSrcDirs(listOf("src/main/kotlin"))
+    }
+}
+"#,
+    );
+    assert_eq!(script.submodules, vec!["app"]);
+    assert_eq!(
+        script.source_dirs,
+        Some(vec!["src/main/kotlin".to_string()])
+    );
+    assert_eq!(script.test_source_dirs, None);
+}
+
+#[test]
+fn process_gradle_same_tree_different_projects() {
+    let class = |name: &str| format!("package a;\n\nclass {name} {{}}\n");
+    let (a, b) = (class("A"), class("B"));
+    // x and y are the same tree, but x is included as a project while y only leads to one
+    let (repositories, root) = super::process_local_repo(
+        "gradle_same_tree",
+        RepoConfig::JavaGradle,
+        &[
+            ("settings.gradle", "include ':x', ':y:lib'\n"),
+            ("x/src/main/java/a/B.java", b.as_str()),
+            ("x/lib/src/main/java/a/A.java", a.as_str()),
+            ("y/src/main/java/a/B.java", b.as_str()),
+            ("y/lib/src/main/java/a/A.java", a.as_str()),
+        ],
+    );
+    let at = |path: &str| child_at_path(&repositories.processor.main_stores, root, path.split('/'));
+    assert!(at("x/src/main/java/a/B.java").is_some());
+    assert!(at("x/lib").is_none());
+    assert!(at("y/src").is_none());
+    assert!(at("y/lib/src/main/java/a/A.java").is_some());
+}
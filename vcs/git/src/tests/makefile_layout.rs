@@ -0,0 +1,51 @@
+use crate::make::MakefileLayout;
+
+fn layout(text: &str) -> MakefileLayout {
+    let tree = match hyperast_gen_ts_make::legion::tree_sitter_parse_make(text.as_bytes()) {
+        Ok(t) => t,
+        Err(t) => t,
+    };
+    MakefileLayout::extract(text.as_bytes(), &tree)
+}
+
+#[test]
+fn extract_makefile_layout() {
+    let layout = layout(
+        r#"SRC_DIR = src
+INCLUDES = -Iinclude -I $(SRC_DIR)/internal -I../outside
+SRCS = $(wildcard $(SRC_DIR)/*.cpp) lib/util.c
+TEST_SRCS = tests/unit/main.cpp
+VPATH = gen:third_party/zlib
+
+all: main
+	$(MAKE) -C examples
+	@$(MAKE) --directory=docs html
+
+main: $(SRCS:.cpp=.o) bench/bench.cpp
+	$(CXX) $(INCLUDES) -Ivendor/include -o $@ $^
+"#,
+    );
+    assert_eq!(layout.submodules, vec!["examples", "docs"]);
+    assert_eq!(
+        layout.include_dirs,
+        vec!["include", "src/internal", "vendor/include"]
+    );
+    for dir in ["src", "lib", "bench", "gen", "third_party/zlib"] {
+        assert!(layout.source_dirs.contains(&dir.to_string()), "{dir}");
+    }
+    assert_eq!(layout.test_source_dirs, vec!["tests/unit"]);
+}
+
+#[test]
+fn extract_makefile_subdirs() {
+    let layout = layout(
+        r#"SUBDIRS = lib app
+SUBDIRS += tools
+
+all:
+	for d in $(SUBDIRS); do $(MAKE) -C $$d; done
+"#,
+    );
+    assert_eq!(layout.submodules, vec!["lib", "app", "tools"]);
+    assert!(layout.source_dirs.is_empty());
+}
@@ -3,9 +3,71 @@ pub mod direct_type_ref;
 #[cfg(feature = "impact")]
 #[cfg(test)]
 pub mod extends_package_local;
+#[cfg(feature = "cmake")]
+pub mod cmake_layout;
+pub mod forges;
+#[cfg(feature = "gradle")]
+pub mod gradle_layout;
+#[cfg(feature = "make")]
+pub mod makefile_layout;
 pub mod obj_creation;
+#[cfg(feature = "pip")]
+pub mod pip_layout;
+#[cfg(feature = "cargo")]
+pub mod cargo_manifest;
+#[cfg(feature = "maven")]
+pub mod pom_extraction;
 
 use crate::{git::fetch_github_repository, preprocessed::PreProcessedRepository};
+
+/// Commits the files, given as `(path, content)`, in a new repository,
+/// then processes the commit as a repository of the `local` forge, as `test/<name>`.
+///
+/// Returns the repositories along with the root of the commit.
+#[cfg(any(feature = "cmake", feature = "gradle"))]
+pub(crate) fn process_local_repo(
+    name: &str,
+    config: crate::processing::RepoConfig,
+    files: &[(&str, &str)],
+) -> (
+    crate::multi_preprocessed::PreProcessedRepositories,
+    hyperast::store::defaults::NodeIdentifier,
+) {
+    let dir = std::env::temp_dir().join(format!("hyperast_vcs_{}", std::process::id()));
+    let path = dir.join("test").join(name);
+    let _ = std::fs::remove_dir_all(&path);
+    let repo = git2::Repository::init(&path).unwrap();
+    let sig = git2::Signature::now("test", "test@example.org").unwrap();
+    let mut index = repo.index().unwrap();
+    for (file, content) in files {
+        let file = path.join(file);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(&file, content).unwrap();
+    }
+    index
+        .add_all(
+            files.iter().map(|(file, _)| file),
+            git2::IndexAddOption::DEFAULT,
+            None,
+        )
+        .unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let commit = repo
+        .commit(Some("HEAD"), &sig, &sig, "init", &tree, &[])
+        .unwrap();
+
+    let mut repositories = crate::multi_preprocessed::PreProcessedRepositories::default();
+    let spec = crate::git::Forge::Local(dir).repo("test", name);
+    let repo = repositories.register_config(spec, config).fetch();
+    let oids = repositories
+        .pre_process_with_limit(&repo, "", &commit.to_string(), 1)
+        .unwrap();
+    let root = repositories
+        .get_commit(&repo.config, &oids[0])
+        .unwrap()
+        .ast_root;
+    (repositories, root)
+}
 #[cfg(feature = "impact")]
 use std::env;
 
@@ -0,0 +1,38 @@
+use crate::pip::{SemFlag, package_status};
+
+fn tree_with(repo: &git2::Repository, files: &[&str], dirs: &[(&str, git2::Oid)]) -> git2::Oid {
+    let blob = repo.blob(b"").unwrap();
+    let mut builder = repo.treebuilder(None).unwrap();
+    for f in files {
+        builder.insert(f, blob, 0o100644).unwrap();
+    }
+    for (d, oid) in dirs {
+        builder.insert(d, *oid, 0o040000).unwrap();
+    }
+    builder.write().unwrap()
+}
+
+#[test]
+fn flag_python_packages() {
+    let dir = std::env::temp_dir().join(format!("hyperast_pip_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let repo = git2::Repository::init(&dir).unwrap();
+
+    let pkg = tree_with(&repo, &["__init__.py", "core.py"], &[]);
+    let cache = tree_with(&repo, &["__init__.py"], &[]);
+    let plain = tree_with(&repo, &["conf.py", "index.rst"], &[]);
+    let src = tree_with(&repo, &[], &[("demo", pkg), ("__pycache__", cache)]);
+    let root = tree_with(
+        &repo,
+        &["pyproject.toml", "README.md"],
+        &[("src", src), ("docs", plain)],
+    );
+
+    let status = |oid| package_status(&repo, &repo.find_tree(oid).unwrap());
+    assert_eq!(status(root), SemFlag::IsPythonProject.into());
+    assert_eq!(status(src), SemFlag::HoldPythonPackage.into());
+    assert_eq!(status(pkg), SemFlag::IsPythonPackage.into());
+    assert!(status(plain).is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,107 @@
+use crate::maven::{Dependency, POM, handle_pom_file};
+use crate::processing::ObjectName;
+use hyperast::store::SimpleStores;
+use hyperast_gen_ts_xml::{legion::XmlTreeGen, types::TStore};
+
+fn pom(text: &str) -> POM {
+    let mut stores = SimpleStores::<TStore>::default();
+    let mut tree_gen = XmlTreeGen::new(&mut stores);
+    let name = ObjectName::from(&b"pom.xml"[..]);
+    handle_pom_file(&mut tree_gen, &name, text.as_bytes()).unwrap()
+}
+
+#[test]
+fn extract_pom_modules_and_dependencies() {
+    let pom = pom(r#"<?xml version="1.0" encoding="UTF-8"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0">
+  <modelVersion>4.0.0</modelVersion>
+  <parent>
+    <groupId>org.example</groupId>
+    <artifactId>parent</artifactId>
+    <version>1.2</version>
+  </parent>
+  <artifactId>app</artifactId>
+  <modules>
+    <module>core</module>
+    <module>./cli/</module>
+    <module>../outside</module>
+  </modules>
+  <profiles>
+    <profile>
+      <modules>
+        <module>extra</module>
+        <module>core</module>
+      </modules>
+    </profile>
+  </profiles>
+  <dependencyManagement>
+    <dependencies>
+      <dependency>
+        <groupId>org.managed</groupId>
+        <artifactId>managed</artifactId>
+      </dependency>
+    </dependencies>
+  </dependencyManagement>
+  <dependencies>
+    <dependency>
+      <groupId>org.example</groupId>
+      <artifactId>core</artifactId>
+      <version>${project.version}</version>
+    </dependency>
+    <dependency>
+      <groupId>junit</groupId>
+      <artifactId>junit</artifactId>
+      <scope>test</scope>
+    </dependency>
+  </dependencies>
+  <build>
+    <sourceDirectory>${project.basedir}/src/java</sourceDirectory>
+  </build>
+</project>
+"#);
+    let module = &pom.module;
+    assert_eq!(module.coordinates().as_deref(), Some("org.example:app"));
+    assert_eq!(module.version.as_deref(), Some("1.2"));
+    assert_eq!(
+        module.modules,
+        vec!["core", "./cli/", "../outside", "extra"]
+    );
+    assert_eq!(pom.submodules, vec!["core", "cli", "extra"]);
+    assert_eq!(pom.source_dirs, vec!["src/java"]);
+    assert_eq!(pom.test_source_dirs, vec!["src/test/java"]);
+    assert_eq!(
+        module.dependencies,
+        vec![
+            Dependency {
+                group_id: Some("org.example".into()),
+                artifact_id: Some("core".into()),
+                version: Some("${project.version}".into()),
+                scope: None,
+            },
+            Dependency {
+                group_id: Some("junit".into()),
+                artifact_id: Some("junit".into()),
+                version: None,
+                scope: Some("test".into()),
+            },
+        ]
+    );
+
+    let mut buf = vec![];
+    module.save(&mut buf).unwrap();
+    let restored = crate::maven::MavenModule::load(&mut buf.as_slice()).unwrap();
+    assert_eq!(&restored, module);
+}
+
+#[test]
+fn extract_pom_defaults() {
+    let pom = pom(r#"<project>
+  <groupId>org.example</groupId>
+  <artifactId>lib</artifactId>
+</project>
+"#);
+    assert!(pom.submodules.is_empty());
+    assert_eq!(pom.source_dirs, vec!["src/main/java"]);
+    assert_eq!(pom.test_source_dirs, vec!["src/test/java"]);
+    assert!(pom.module.dependencies.is_empty());
+}
@@ -0,0 +1,378 @@
+use std::iter::Peekable;
+use std::path::Components;
+
+use git2::{Oid, Repository};
+
+use hyperast::store::nodes::legion::subtree_builder;
+
+use crate::git::BasicGitObject;
+use crate::preprocessed::RepositoryProcessor;
+use crate::processing::erased::ParametrizedCommitProc2;
+use crate::processing::{CacheHolding, InFiles, ObjectName};
+use crate::ts::TsAcc;
+use crate::{Processor, StackEle};
+
+use hyperast_gen_ts_ts::legion::{self as ts_gen};
+use hyperast_gen_ts_ts::types::Type;
+
+pub type SimpleStores = hyperast::store::SimpleStores<hyperast_gen_ts_ts::types::TStore>;
+
+type Handle = crate::processing::erased::ParametrizedCommitProcessor2Handle<TsProc>;
+
+pub(crate) fn prepare_dir_exploration(tree: git2::Tree) -> Vec<BasicGitObject> {
+    tree.iter()
+        .rev()
+        .map(TryInto::try_into)
+        .filter_map(|x| x.ok())
+        .collect()
+}
+
+/// Directories that are never explored, they contain installed dependencies or build outputs.
+pub(crate) fn is_ignored_dir(name: &ObjectName) -> bool {
+    matches!(name.as_bytes(), b"node_modules" | b".git")
+}
+
+pub struct TsProcessor<'repo, 'prepro, 'd, 'c, Acc> {
+    repository: &'repo Repository,
+    prepro: &'prepro mut RepositoryProcessor,
+    stack: Vec<StackEle<Acc>>,
+    pub dir_path: &'d mut Peekable<Components<'c>>,
+    handle: &'d Handle,
+}
+
+impl<'repo, 'prepro, 'd, 'c, Acc: From<String>> TsProcessor<'repo, 'prepro, 'd, 'c, Acc> {
+    pub(crate) fn new(
+        repository: &'repo Repository,
+        prepro: &'prepro mut RepositoryProcessor,
+        dir_path: &'d mut Peekable<Components<'c>>,
+        name: &ObjectName,
+        oid: git2::Oid,
+        parameters: &'d Handle,
+    ) -> Self {
+        let tree = repository.find_tree(oid).unwrap();
+        let prepared = prepare_dir_exploration(tree);
+        let name = name.try_into().unwrap();
+        let stack = vec![StackEle::new(oid, prepared, Acc::from(name))];
+        Self {
+            stack,
+            repository,
+            prepro,
+            dir_path,
+            handle: parameters,
+        }
+    }
+}
+
+impl<'repo, 'b, 'd, 'c> Processor<TsAcc> for TsProcessor<'repo, 'b, 'd, 'c, TsAcc> {
+    fn pre(&mut self, current_object: BasicGitObject) {
+        match current_object {
+            BasicGitObject::Tree(oid, name) => {
+                if is_ignored_dir(&name) {
+                    return;
+                }
+                self.handle_tree_cached(oid, name);
+            }
+            BasicGitObject::Blob(oid, name) => {
+                if crate::processing::file_sys::Ts::matches(&name) {
+                    self.prepro
+                        .help_handle_ts_file(
+                            oid,
+                            &mut self.stack.last_mut().unwrap().acc,
+                            &name,
+                            self.repository,
+                            *self.handle,
+                        )
+                        .unwrap();
+                } else {
+                    log::debug!("not ts source file {:?}", name.try_str());
+                }
+            }
+        }
+    }
+    fn post(&mut self, oid: Oid, acc: TsAcc) -> Option<(ts_gen::Local,)> {
+        let name = &acc.primary.name;
+        let key = (oid, name.as_bytes().into());
+        let name = self.prepro.get_or_insert_label(name);
+        let holder = self
+            .prepro
+            .processing_systems
+            .mut_or_default::<TsProcessorHolder>();
+        let ts_proc = holder.with_parameters_mut(self.handle.0);
+        let full_node = make(acc, self.prepro.main_stores.mut_with_ts(), ts_proc);
+        ts_proc.cache.object_map.insert(key, (full_node.clone(),));
+        if self.stack.is_empty() {
+            Some((full_node,))
+        } else {
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            assert!(
+                !w.primary.children_names.contains(&name),
+                "{:?} {:?}",
+                w.primary.children_names,
+                name
+            );
+            w.push(name, full_node.clone());
+            None
+        }
+    }
+
+    fn stack(&mut self) -> &mut Vec<StackEle<TsAcc>> {
+        &mut self.stack
+    }
+}
+
+impl<'repo, 'prepro, 'd, 'c> TsProcessor<'repo, 'prepro, 'd, 'c, TsAcc> {
+    fn handle_tree_cached(&mut self, oid: Oid, name: ObjectName) {
+        let holder = self
+            .prepro
+            .processing_systems
+            .mut_or_default::<TsProcessorHolder>();
+        let ts_proc = holder.with_parameters_mut(self.handle.0);
+        if let Some(already) = ts_proc.cache.object_map.get(&(oid, name.clone())) {
+            // reinit already computed node for post order
+            let full_node = already.clone();
+            let w = &mut self.stack.last_mut().unwrap().acc;
+            let name = self.prepro.intern_object_name(&name);
+            assert!(!w.primary.children_names.contains(&name));
+            hyperast::tree_gen::Accumulator::push(w, (name, full_node));
+        } else {
+            log::debug!("tree {:?}", name.try_str());
+            let tree = self.repository.find_tree(oid).unwrap();
+            let prepared: Vec<BasicGitObject> = prepare_dir_exploration(tree);
+            self.stack.push(StackEle::new(
+                oid,
+                prepared,
+                TsAcc::new(name.try_into().unwrap()),
+            ));
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct Parameter {}
+
+#[derive(Default)]
+pub(crate) struct TsProcessorHolder(Vec<TsProc>);
+pub(crate) struct TsProc {
+    parameter: Parameter,
+    cache: crate::processing::caches::Ts,
+    commits: std::collections::HashMap<git2::Oid, crate::Commit>,
+}
+impl crate::processing::erased::Parametrized for TsProcessorHolder {
+    type T = Parameter;
+    fn register_param(
+        &mut self,
+        t: Self::T,
+    ) -> crate::processing::erased::ParametrizedCommitProcessorHandle {
+        use crate::processing::erased::ConfigParametersHandle;
+        use crate::processing::erased::ParametrizedCommitProc;
+        use crate::processing::erased::ParametrizedCommitProcessorHandle;
+        if let Some(l) = self.0.iter().position(|x| &x.parameter == &t) {
+            return ParametrizedCommitProcessorHandle(
+                self.erased_handle(),
+                ConfigParametersHandle(l),
+            );
+        }
+        let l = self.0.len();
+        let r = TsProc {
+            parameter: t,
+            cache: Default::default(),
+            commits: Default::default(),
+        };
+        self.0.push(r);
+        ParametrizedCommitProcessorHandle(self.erased_handle(), ConfigParametersHandle(l))
+    }
+}
+
+impl crate::processing::erased::CommitProc for TsProc {
+    fn prepare_processing(
+        &self,
+        _repository: &git2::Repository,
+        _builder: crate::preprocessed::CommitBuilder,
+        _handle: crate::processing::ParametrizedCommitProcessorHandle,
+    ) -> Box<dyn crate::processing::erased::PreparedCommitProc> {
+        unimplemented!("required for processing ts at the root of a project")
+    }
+
+    fn get_commit(&self, commit_oid: git2::Oid) -> Option<&crate::Commit> {
+        self.commits.get(&commit_oid)
+    }
+
+    fn commit_count(&self) -> usize {
+        self.commits.len()
+    }
+}
+
+impl crate::processing::erased::CommitProcExt for TsProc {
+    type Holder = TsProcessorHolder;
+}
+impl crate::processing::erased::ParametrizedCommitProc2 for TsProcessorHolder {
+    type Proc = TsProc;
+
+    fn with_parameters_mut(
+        &mut self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &mut Self::Proc {
+        &mut self.0[parameters.0]
+    }
+
+    fn with_parameters(
+        &self,
+        parameters: crate::processing::erased::ConfigParametersHandle,
+    ) -> &Self::Proc {
+        &self.0[parameters.0]
+    }
+
+    fn commit_roots(&self) -> Vec<(git2::Oid, hyperast::store::defaults::NodeIdentifier)> {
+        self.0
+            .iter()
+            .flat_map(|proc| proc.commits.iter().map(|(oid, c)| (*oid, c.ast_root)))
+            .collect()
+    }
+
+    fn retain_reachable(&mut self, reachable: &hyperast::store::nodes::legion::gc::Reachable) {
+        for proc in &mut self.0 {
+            proc.commits.retain(|_, c| reachable.contains(&c.ast_root));
+            proc.cache.retain_reachable(reachable);
+        }
+    }
+}
+impl CacheHolding<crate::processing::caches::Ts> for TsProc {
+    fn get_caches_mut(&mut self) -> &mut crate::processing::caches::Ts {
+        &mut self.cache
+    }
+    fn get_caches(&self) -> &crate::processing::caches::Ts {
+        &self.cache
+    }
+}
+
+impl RepositoryProcessor {
+    pub(crate) fn handle_ts_blob(
+        &mut self,
+        oid: Oid,
+        name: &ObjectName,
+        repository: &Repository,
+        parameters: Handle,
+    ) -> Result<(ts_gen::Local,), crate::ParseErr> {
+        self.processing_systems
+            .caching_blob_handler::<crate::processing::file_sys::Ts>()
+            .handle2(oid, repository, &name, parameters, |c, n, t| {
+                let line_break = if t.contains(&b'\r') { "\r\n" } else { "\n" }
+                    .as_bytes()
+                    .to_vec();
+                let holder = c.mut_or_default::<TsProcessorHolder>();
+                let ts_proc = holder.with_parameters_mut(parameters.0);
+                let md_cache = &mut ts_proc.cache.md_cache;
+                let stores = self
+                    .main_stores
+                    .mut_with_ts::<hyperast_gen_ts_ts::types::TStore>();
+                let mut ts_tree_gen = ts_gen::TsTreeGen::new(stores, md_cache);
+                ts_tree_gen.line_break = line_break;
+                let r = crate::ts::handle_ts_file(&mut ts_tree_gen, n, t)
+                    .map_err(|_| crate::ParseErr::IllFormed)?;
+
+                self.parsing_time += r.parsing_time;
+                self.processing_time += r.processing_time;
+                log::info!(
+                    "parsing, processing, n, f: {} {} {} {}",
+                    self.parsing_time.as_secs(),
+                    self.processing_time.as_secs(),
+                    ts_proc.cache.md_cache.len(),
+                    ts_proc.cache.object_map.len()
+                );
+
+                let r = r.node;
+                Ok((r.local.clone(),))
+            })
+    }
+
+    pub(crate) fn help_handle_ts_file(
+        &mut self,
+        oid: Oid,
+        parent: &mut TsAcc,
+        name: &ObjectName,
+        repository: &Repository,
+        parameters: Handle,
+    ) -> Result<(), crate::ParseErr> {
+        let (full_node,) = self.handle_ts_blob(oid, name, repository, parameters)?;
+        let name = self.intern_object_name(name);
+        assert!(!parent.primary.children_names.contains(&name));
+
+        parent.push(name, full_node);
+        Ok(())
+    }
+
+    pub(crate) fn handle_ts_directory<'b, 'd: 'b>(
+        &mut self,
+        repository: &Repository,
+        dir_path: &'b mut Peekable<Components<'d>>,
+        name: &ObjectName,
+        oid: git2::Oid,
+        handle: Handle,
+    ) -> (ts_gen::Local,) {
+        TsProcessor::<TsAcc>::new(repository, self, dir_path, name, oid, &handle).process()
+    }
+
+    pub(crate) fn help_handle_ts_folder<'a, 'b, 'c, 'd: 'c>(
+        &'a mut self,
+        repository: &'b Repository,
+        dir_path: &'c mut Peekable<Components<'d>>,
+        oid: Oid,
+        name: &ObjectName,
+        handle: Handle,
+    ) -> <TsAcc as hyperast::tree_gen::Accumulator>::Node {
+        let full_node = self.handle_ts_directory(repository, dir_path, name, oid, handle);
+        let name = self.intern_object_name(name);
+        (name, full_node)
+    }
+}
+
+fn make(acc: TsAcc, stores: &mut SimpleStores, ts_proc: &mut TsProc) -> ts_gen::Local {
+    use hyperast::hashed::IndexingHashBuilder as _;
+    use hyperast::hashed::MetaDataHashsBuilder as _;
+    use hyperast::store::nodes::legion::eq_node;
+    use hyperast::types::ETypeStore as _;
+    use hyperast::types::LabelStore;
+
+    let kind = Type::Directory;
+    let interned_kind = hyperast_gen_ts_ts::types::TStore::intern(kind);
+    let label_id = stores.label_store.get_or_insert(acc.primary.name.clone());
+
+    let primary = acc
+        .primary
+        .map_metrics(|m| m.finalize(&interned_kind, &label_id, 0));
+    let hashable = primary.metrics.hashs.most_discriminating();
+    let eq = eq_node(&interned_kind, Some(&label_id), &primary.children);
+    let md_cache = &mut ts_proc.cache.md_cache;
+    let insertion = stores.node_store.prepare_insertion(&hashable, eq);
+
+    if let Some(id) = insertion.occupied_id() {
+        // NOTE should not happen often, due to the cache based on oids
+        let metrics = primary.metrics.map_hashs(|h| h.build());
+        return ts_gen::Local {
+            compressed_node: id,
+            metrics,
+        };
+    }
+
+    let mut dyn_builder = subtree_builder::<hyperast_gen_ts_ts::types::TStore>(interned_kind);
+
+    let children_is_empty = primary.children.is_empty();
+
+    let metrics = primary.persist(&mut dyn_builder, interned_kind, label_id);
+    let metrics = metrics.map_hashs(|h| h.build());
+    let hashs = metrics.add_md_metrics(&mut dyn_builder, children_is_empty);
+    hashs.persist(&mut dyn_builder);
+
+    let vacant = insertion.vacant();
+    let node_id = hyperast::store::nodes::legion::NodeStore::insert_built_after_prepare(
+        vacant,
+        dyn_builder.build(),
+    );
+
+    md_cache.insert(node_id, ts_gen::MD { metrics });
+
+    ts_gen::Local {
+        compressed_node: node_id,
+        metrics,
+    }
+}
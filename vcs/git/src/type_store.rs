@@ -11,6 +11,16 @@ impl hyperast::store::TyDown<hyperast_gen_ts_cpp::types::TStore> for TStore {}
 impl hyperast::store::TyDown<hyperast_gen_ts_java::types::TStore> for TStore {}
 #[cfg(feature = "maven")]
 impl hyperast::store::TyDown<hyperast_gen_ts_xml::types::TStore> for TStore {}
+#[cfg(feature = "ts")]
+impl hyperast::store::TyDown<hyperast_gen_ts_ts::types::TStore> for TStore {}
+#[cfg(feature = "make")]
+impl hyperast::store::TyDown<hyperast_gen_ts_make::types::TStore> for TStore {}
+#[cfg(feature = "json")]
+impl hyperast::store::TyDown<hyperast_gen_ts_json::types::TStore> for TStore {}
+#[cfg(feature = "python")]
+impl hyperast::store::TyDown<hyperast_gen_ts_python::types::TStore> for TStore {}
+#[cfg(feature = "rust")]
+impl hyperast::store::TyDown<hyperast_gen_ts_rust::types::TStore> for TStore {}
 
 impl Default for TStore {
     fn default() -> Self {
@@ -46,6 +56,40 @@ impl hyperast::types::RoleStore for TStore {
                 );
                 hyperast_gen_ts_xml::types::TStore::resolve_field(t.get_lang(), field_id)
             }
+            #[cfg(feature = "ts")]
+            "hyperast_gen_ts_ts::types::Lang" => {
+                let t =
+                    hyperast_gen_ts_ts::types::TType::new(hyperast_gen_ts_ts::types::Type::Spaces);
+                hyperast_gen_ts_ts::types::TStore::resolve_field(t.get_lang(), field_id)
+            }
+            #[cfg(feature = "make")]
+            "hyperast_gen_ts_make::types::Lang" => {
+                let t = hyperast_gen_ts_make::types::TType::new(
+                    hyperast_gen_ts_make::types::Type::Spaces,
+                );
+                hyperast_gen_ts_make::types::TStore::resolve_field(t.get_lang(), field_id)
+            }
+            #[cfg(feature = "json")]
+            "hyperast_gen_ts_json::types::Lang" => {
+                let t = hyperast_gen_ts_json::types::TType::new(
+                    hyperast_gen_ts_json::types::Type::Spaces,
+                );
+                hyperast_gen_ts_json::types::TStore::resolve_field(t.get_lang(), field_id)
+            }
+            #[cfg(feature = "python")]
+            "hyperast_gen_ts_python::types::Lang" => {
+                let t = hyperast_gen_ts_python::types::TType::new(
+                    hyperast_gen_ts_python::types::Type::Spaces,
+                );
+                hyperast_gen_ts_python::types::TStore::resolve_field(t.get_lang(), field_id)
+            }
+            #[cfg(feature = "rust")]
+            "hyperast_gen_ts_rust::types::Lang" => {
+                let t = hyperast_gen_ts_rust::types::TType::new(
+                    hyperast_gen_ts_rust::types::Type::Spaces,
+                );
+                hyperast_gen_ts_rust::types::TStore::resolve_field(t.get_lang(), field_id)
+            }
             x => panic!("{}", x),
         }
     }
@@ -74,6 +118,40 @@ impl hyperast::types::RoleStore for TStore {
                 );
                 hyperast_gen_ts_xml::types::TStore::intern_role(t.get_lang(), role)
             }
+            #[cfg(feature = "ts")]
+            "hyperast_gen_ts_ts::types::Lang" => {
+                let t =
+                    hyperast_gen_ts_ts::types::TType::new(hyperast_gen_ts_ts::types::Type::Spaces);
+                hyperast_gen_ts_ts::types::TStore::intern_role(t.get_lang(), role)
+            }
+            #[cfg(feature = "make")]
+            "hyperast_gen_ts_make::types::Lang" => {
+                let t = hyperast_gen_ts_make::types::TType::new(
+                    hyperast_gen_ts_make::types::Type::Spaces,
+                );
+                hyperast_gen_ts_make::types::TStore::intern_role(t.get_lang(), role)
+            }
+            #[cfg(feature = "json")]
+            "hyperast_gen_ts_json::types::Lang" => {
+                let t = hyperast_gen_ts_json::types::TType::new(
+                    hyperast_gen_ts_json::types::Type::Spaces,
+                );
+                hyperast_gen_ts_json::types::TStore::intern_role(t.get_lang(), role)
+            }
+            #[cfg(feature = "python")]
+            "hyperast_gen_ts_python::types::Lang" => {
+                let t = hyperast_gen_ts_python::types::TType::new(
+                    hyperast_gen_ts_python::types::Type::Spaces,
+                );
+                hyperast_gen_ts_python::types::TStore::intern_role(t.get_lang(), role)
+            }
+            #[cfg(feature = "rust")]
+            "hyperast_gen_ts_rust::types::Lang" => {
+                let t = hyperast_gen_ts_rust::types::TType::new(
+                    hyperast_gen_ts_rust::types::Type::Spaces,
+                );
+                hyperast_gen_ts_rust::types::TStore::intern_role(t.get_lang(), role)
+            }
             x => panic!("{}", x),
         }
     }
@@ -102,6 +180,16 @@ impl TypeStore for TStore {
         decomp_t!(hyperast_gen_ts_java::types);
         decomp_t!(hyperast_gen_ts_cpp::types);
         decomp_t!(hyperast_gen_ts_xml::types);
+        #[cfg(feature = "ts")]
+        decomp_t!(hyperast_gen_ts_ts::types);
+        #[cfg(feature = "make")]
+        decomp_t!(hyperast_gen_ts_make::types);
+        #[cfg(feature = "json")]
+        decomp_t!(hyperast_gen_ts_json::types);
+        #[cfg(feature = "python")]
+        decomp_t!(hyperast_gen_ts_python::types);
+        #[cfg(feature = "rust")]
+        decomp_t!(hyperast_gen_ts_rust::types);
         None
     }
     fn decompress_type(
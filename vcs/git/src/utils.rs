@@ -1 +1,30 @@
+#[cfg(any(feature = "npm", feature = "cargo"))]
+use std::path::PathBuf;
 
+/// Walks a list of path globs, e.g. npm workspaces or cargo workspace members, down to the directory `name`.
+///
+/// Returns the globs remaining relative to `name`, or `None` if no glob matches `name`.
+/// Only whole segments are matched, `*` matches any segment and `**` any number of segments.
+#[cfg(any(feature = "npm", feature = "cargo"))]
+pub(crate) fn globs_in(globs: &[PathBuf], name: &str) -> Option<Vec<PathBuf>> {
+    let mut matched = false;
+    let mut rest = vec![];
+    for glob in globs {
+        let mut components = glob.components();
+        let Some(first) = components.next() else {
+            continue;
+        };
+        let first = first.as_os_str().to_str().unwrap_or_default();
+        if first == name || first == "*" || first == "**" {
+            matched = true;
+            let remaining = components.as_path().to_owned();
+            if first == "**" {
+                rest.push(glob.clone());
+            }
+            if remaining.components().next().is_some() {
+                rest.push(remaining);
+            }
+        }
+    }
+    matched.then_some(rest)
+}